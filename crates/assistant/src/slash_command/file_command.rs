@@ -40,8 +40,11 @@ impl FileSlashCommand {
                     include_ignored: worktree
                         .root_entry()
                         .map_or(false, |entry| entry.is_ignored),
+                    include_hidden: true,
                     include_root_name,
                     directories_only: false,
+                    score_penalties: Vec::new(),
+                    excluded_paths: Vec::new(),
                 }
             })
             .collect::<Vec<_>>();
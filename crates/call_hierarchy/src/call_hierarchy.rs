@@ -0,0 +1,448 @@
+mod call_hierarchy_settings;
+mod call_tree;
+
+use anyhow::Result;
+use db::kvp::KEY_VALUE_STORE;
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{
+    actions, px, uniform_list, Action, AppContext, AsyncWindowContext, EventEmitter, FocusHandle,
+    FocusableView, InteractiveElement, IntoElement, Model, ParentElement, Render, Styled,
+    Subscription, Task, UniformListScrollHandle, View, ViewContext, VisualContext, WeakView,
+    WindowContext,
+};
+use language::{Anchor, Buffer, OffsetRangeExt};
+use menu::{Confirm, SelectNext, SelectPrev};
+use project::{Fs, Location, Project};
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use std::sync::Arc;
+use ui::{prelude::*, IconButton, Label, ListItem, Tooltip};
+use util::ResultExt;
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+pub use call_hierarchy_settings::*;
+pub use call_tree::*;
+
+const CALL_HIERARCHY_PANEL_KEY: &str = "CallHierarchyPanel";
+
+actions!(
+    call_hierarchy,
+    [
+        ToggleFocus,
+        ShowIncomingCalls,
+        ShowOutgoingCalls,
+        ToggleDirection,
+        ExpandSelectedEntry,
+        CollapseSelectedEntry,
+    ]
+);
+
+pub fn init(cx: &mut AppContext) {
+    CallHierarchyPanelSettings::register(cx);
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace
+            .register_action(|workspace, _: &ToggleFocus, cx| {
+                workspace.toggle_panel_focus::<CallHierarchyPanel>(cx);
+            })
+            .register_action(|workspace, _: &ShowIncomingCalls, cx| {
+                show_call_hierarchy(workspace, CallDirection::Incoming, cx);
+            })
+            .register_action(|workspace, _: &ShowOutgoingCalls, cx| {
+                show_call_hierarchy(workspace, CallDirection::Outgoing, cx);
+            });
+    })
+    .detach();
+}
+
+/// Shows the calls of the symbol under the newest cursor of the active editor in the panel.
+fn show_call_hierarchy(
+    workspace: &mut Workspace,
+    direction: CallDirection,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some(panel) = workspace.panel::<CallHierarchyPanel>(cx) else {
+        return;
+    };
+    let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+        let head = editor.selections.newest_anchor().head();
+        editor.buffer().read(cx).text_anchor_for_position(head, cx)
+    }) else {
+        return;
+    };
+    panel.update(cx, |panel, cx| panel.show(&buffer, position, direction, cx));
+    workspace.focus_panel::<CallHierarchyPanel>(cx);
+}
+
+pub struct CallHierarchyPanel {
+    workspace: WeakView<Workspace>,
+    fs: Arc<dyn Fs>,
+    tree: Model<CallTree>,
+    selected: Option<CallNodeId>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+    width: Option<Pixels>,
+    pending_serialization: Task<Option<()>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedCallHierarchyPanel {
+    width: Option<Pixels>,
+}
+
+impl CallHierarchyPanel {
+    pub fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let fs = workspace.app_state().fs.clone();
+        let workspace = workspace.weak_handle();
+        cx.new_view(|cx| Self::new_with_project(workspace, project, fs, cx))
+    }
+
+    fn new_with_project(
+        workspace: WeakView<Workspace>,
+        project: Model<Project>,
+        fs: Arc<dyn Fs>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let tree = cx.new_model(|_| CallTree::new(project, CallDirection::Incoming));
+        let subscriptions = vec![
+            cx.observe(&tree, |_, _, cx| cx.notify()),
+            cx.subscribe(&tree, |this, tree, event, cx| match event {
+                CallTreeEvent::RootsChanged => {
+                    this.selected = tree.read(cx).roots().first().copied();
+                    cx.notify();
+                }
+            }),
+        ];
+        Self {
+            workspace,
+            fs,
+            tree,
+            selected: None,
+            scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+            width: None,
+            pending_serialization: Task::ready(None),
+            _subscriptions: subscriptions,
+        }
+    }
+
+    pub fn load(
+        workspace: WeakView<Workspace>,
+        cx: AsyncWindowContext,
+    ) -> Task<Result<View<Self>>> {
+        cx.spawn(|mut cx| async move {
+            let serialized_panel = if let Some(panel) = cx
+                .background_executor()
+                .spawn(async move { KEY_VALUE_STORE.read_kvp(CALL_HIERARCHY_PANEL_KEY) })
+                .await
+                .log_err()
+                .flatten()
+            {
+                Some(serde_json::from_str::<SerializedCallHierarchyPanel>(
+                    &panel,
+                )?)
+            } else {
+                None
+            };
+
+            workspace.update(&mut cx, |workspace, cx| {
+                let panel = Self::new(workspace, cx);
+                if let Some(serialized_panel) = serialized_panel {
+                    panel.update(cx, |panel, cx| {
+                        panel.width = serialized_panel.width.map(|w| w.round());
+                        cx.notify();
+                    });
+                }
+                panel
+            })
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let width = self.width;
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        CALL_HIERARCHY_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedCallHierarchyPanel { width })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    pub fn tree(&self) -> &Model<CallTree> {
+        &self.tree
+    }
+
+    /// Shows the calls of the symbol at the given position.
+    pub fn show(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: Anchor,
+        direction: CallDirection,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.selected = None;
+        self.tree.update(cx, |tree, cx| {
+            tree.set_root(buffer, position, direction, cx)
+        });
+    }
+
+    fn toggle_direction(&mut self, _: &ToggleDirection, cx: &mut ViewContext<Self>) {
+        self.tree.update(cx, |tree, cx| {
+            let direction = tree.direction().toggle();
+            tree.set_direction(direction, cx);
+        });
+    }
+
+    fn select_next(&mut self, _: &SelectNext, cx: &mut ViewContext<Self>) {
+        self.select_offset(1, cx);
+    }
+
+    fn select_prev(&mut self, _: &SelectPrev, cx: &mut ViewContext<Self>) {
+        self.select_offset(-1, cx);
+    }
+
+    fn select_offset(&mut self, offset: isize, cx: &mut ViewContext<Self>) {
+        let visible_nodes = self.tree.read(cx).visible_nodes();
+        if visible_nodes.is_empty() {
+            return;
+        }
+        let ix = self
+            .selected
+            .and_then(|selected| visible_nodes.iter().position(|id| *id == selected))
+            .map_or(0, |ix| {
+                ix.saturating_add_signed(offset)
+                    .min(visible_nodes.len() - 1)
+            });
+        self.selected = Some(visible_nodes[ix]);
+        self.scroll_handle.scroll_to_item(ix);
+        cx.notify();
+    }
+
+    fn expand_selected_entry(&mut self, _: &ExpandSelectedEntry, cx: &mut ViewContext<Self>) {
+        if let Some(selected) = self.selected {
+            self.tree.update(cx, |tree, cx| tree.expand(selected, cx));
+        }
+    }
+
+    fn collapse_selected_entry(&mut self, _: &CollapseSelectedEntry, cx: &mut ViewContext<Self>) {
+        if let Some(selected) = self.selected {
+            self.tree.update(cx, |tree, cx| tree.collapse(selected, cx));
+        }
+    }
+
+    fn confirm(&mut self, _: &Confirm, cx: &mut ViewContext<Self>) {
+        if let Some(selected) = self.selected {
+            self.open_node(selected, cx);
+        }
+    }
+
+    /// Opens the first call of the node, or the node's symbol if it's a root.
+    fn open_node(&mut self, id: CallNodeId, cx: &mut ViewContext<Self>) {
+        let node = self.tree.read(cx).node(id);
+        let location = node
+            .call_sites
+            .first()
+            .unwrap_or(&node.item.location)
+            .clone();
+        self.open_location(location, cx);
+    }
+
+    fn open_location(&mut self, location: Location, cx: &mut ViewContext<Self>) {
+        self.workspace
+            .update(cx, |workspace, cx| {
+                let pane = workspace.active_pane().clone();
+                let editor =
+                    workspace.open_project_item::<Editor>(pane, location.buffer.clone(), cx);
+                editor.update(cx, |editor, cx| {
+                    // The anchors are resolved now, so that the location follows any edits
+                    // made since the calls were requested.
+                    let range = location.range.to_offset(location.buffer.read(cx));
+                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                        s.select_ranges([range])
+                    });
+                });
+            })
+            .log_err();
+    }
+
+    fn render_node(&self, ix: usize, id: CallNodeId, cx: &mut ViewContext<Self>) -> ListItem {
+        let tree = self.tree.read(cx);
+        let node = tree.node(id);
+        let call_count = node.call_sites.len();
+        ListItem::new(ix)
+            .indent_level(node.depth)
+            .indent_step_size(px(12.))
+            .selected(self.selected == Some(id))
+            .toggle(tree.may_have_children(id).then_some(node.expanded))
+            .on_toggle(cx.listener(move |this, _, cx| {
+                this.tree
+                    .update(cx, |tree, cx| tree.toggle_expanded(id, cx));
+            }))
+            .on_click(cx.listener(move |this, _, cx| {
+                this.selected = Some(id);
+                this.open_node(id, cx);
+                cx.notify();
+            }))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(node.item.name.clone()))
+                    .children(node.item.detail.clone().map(|detail| {
+                        Label::new(detail)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    }))
+                    .when(call_count > 1, |this| {
+                        this.child(
+                            Label::new(format!("×{call_count}"))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    })
+                    .when(tree.is_loading(id), |this| {
+                        this.child(Label::new("…").color(Color::Muted))
+                    }),
+            )
+    }
+}
+
+impl Render for CallHierarchyPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let tree = self.tree.read(cx);
+        let title = match tree.direction() {
+            CallDirection::Incoming => "Incoming Calls",
+            CallDirection::Outgoing => "Outgoing Calls",
+        };
+        let visible_nodes = tree.visible_nodes();
+        let is_loading = tree.is_loading_roots();
+
+        v_flex()
+            .key_context("CallHierarchyPanel")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::toggle_direction))
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_prev))
+            .on_action(cx.listener(Self::expand_selected_entry))
+            .on_action(cx.listener(Self::collapse_selected_entry))
+            .on_action(cx.listener(Self::confirm))
+            .size_full()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .px_2()
+                    .py_1()
+                    // Match the height of the tab bar so they line up.
+                    .h(rems(ui::Tab::CONTAINER_HEIGHT_IN_REMS))
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new(title))
+                    .child(
+                        IconButton::new("toggle_direction", IconName::ExpandVertical)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| {
+                                Tooltip::for_action("Toggle Direction", &ToggleDirection, cx)
+                            })
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.toggle_direction(&ToggleDirection, cx)
+                            })),
+                    ),
+            )
+            .map(|this| {
+                if visible_nodes.is_empty() {
+                    let message = if is_loading {
+                        "Loading…"
+                    } else {
+                        "Show the calls of a symbol with the call hierarchy actions."
+                    };
+                    this.child(
+                        v_flex()
+                            .p_4()
+                            .child(Label::new(message).color(Color::Muted)),
+                    )
+                } else {
+                    this.child(
+                        uniform_list(
+                            cx.view().clone(),
+                            "call_hierarchy_nodes",
+                            visible_nodes.len(),
+                            move |this, range, cx| {
+                                range
+                                    .map(|ix| this.render_node(ix, visible_nodes[ix], cx))
+                                    .collect()
+                            },
+                        )
+                        .size_full()
+                        .track_scroll(self.scroll_handle.clone()),
+                    )
+                }
+            })
+    }
+}
+
+impl FocusableView for CallHierarchyPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for CallHierarchyPanel {}
+
+impl Panel for CallHierarchyPanel {
+    fn persistent_name() -> &'static str {
+        "CallHierarchyPanel"
+    }
+
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        CallHierarchyPanelSettings::get_global(cx).dock
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<CallHierarchyPanelSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings| settings.dock = Some(position),
+        );
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.width
+            .unwrap_or_else(|| CallHierarchyPanelSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.width = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        CallHierarchyPanelSettings::get_global(cx)
+            .button
+            .then_some(IconName::Code)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Call Hierarchy")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
@@ -0,0 +1,41 @@
+use gpui::Pixels;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+use workspace::dock::DockPosition;
+
+#[derive(Deserialize, Debug)]
+pub struct CallHierarchyPanelSettings {
+    pub button: bool,
+    pub dock: DockPosition,
+    pub default_width: Pixels,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct CallHierarchyPanelSettingsContent {
+    /// Whether to show the call hierarchy panel button in the status bar.
+    ///
+    /// Default: true
+    pub button: Option<bool>,
+    /// Where to dock the call hierarchy panel.
+    ///
+    /// Default: right
+    pub dock: Option<DockPosition>,
+    /// Default width of the call hierarchy panel in pixels.
+    ///
+    /// Default: 320
+    pub default_width: Option<f32>,
+}
+
+impl Settings for CallHierarchyPanelSettings {
+    const KEY: Option<&'static str> = Some("call_hierarchy_panel");
+
+    type FileContent = CallHierarchyPanelSettingsContent;
+
+    fn load(
+        sources: SettingsSources<Self::FileContent>,
+        _: &mut gpui::AppContext,
+    ) -> anyhow::Result<Self> {
+        sources.json_merge()
+    }
+}
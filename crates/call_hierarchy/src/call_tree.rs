@@ -0,0 +1,435 @@
+use collections::HashMap;
+use gpui::{EntityId, EventEmitter, Model, ModelContext, Task};
+use language::{Anchor, Buffer};
+use project::{CallHierarchyCall, CallHierarchyItem, Location, Project};
+use util::ResultExt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CallDirection {
+    /// Each item's children are the items calling it.
+    Incoming,
+    /// Each item's children are the items it calls.
+    Outgoing,
+}
+
+impl CallDirection {
+    pub fn toggle(self) -> Self {
+        match self {
+            CallDirection::Incoming => CallDirection::Outgoing,
+            CallDirection::Outgoing => CallDirection::Incoming,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CallNodeId(usize);
+
+pub struct CallNode {
+    pub item: CallHierarchyItem,
+    /// The calls between this node's item and its parent's, which are in the parent for
+    /// outgoing calls and in this node for incoming calls. Roots don't have any.
+    pub call_sites: Vec<Location>,
+    pub depth: usize,
+    pub expanded: bool,
+    children: Children,
+}
+
+enum Children {
+    Unloaded,
+    Loading(Task<()>),
+    Loaded(Vec<CallNodeId>),
+}
+
+/// The calls to or from the symbols at a position, as a tree whose nodes are only loaded
+/// when they're first expanded. A node's calls are kept for as long as its buffer isn't
+/// edited, so collapsing and expanding it again, or switching back and forth between
+/// directions, doesn't query the language server again.
+pub struct CallTree {
+    project: Model<Project>,
+    direction: CallDirection,
+    nodes: Vec<CallNode>,
+    roots: Vec<CallNodeId>,
+    root_items: Vec<CallHierarchyItem>,
+    cache: HashMap<CacheKey, CachedCalls>,
+    pending_roots: Option<Task<()>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    direction: CallDirection,
+    buffer_id: EntityId,
+    start: Anchor,
+}
+
+struct CachedCalls {
+    version: clock::Global,
+    calls: Vec<CallHierarchyCall>,
+}
+
+pub enum CallTreeEvent {
+    RootsChanged,
+}
+
+impl EventEmitter<CallTreeEvent> for CallTree {}
+
+impl CallTree {
+    pub fn new(project: Model<Project>, direction: CallDirection) -> Self {
+        Self {
+            project,
+            direction,
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            root_items: Vec::new(),
+            cache: HashMap::default(),
+            pending_roots: None,
+        }
+    }
+
+    pub fn direction(&self) -> CallDirection {
+        self.direction
+    }
+
+    pub fn set_direction(&mut self, direction: CallDirection, cx: &mut ModelContext<Self>) {
+        if direction != self.direction {
+            self.direction = direction;
+            self.refresh(cx);
+        }
+    }
+
+    /// Rebuilds the tree from the same roots, collapsing all of their descendants.
+    pub fn refresh(&mut self, cx: &mut ModelContext<Self>) {
+        self.set_root_items(self.root_items.clone(), cx);
+    }
+
+    /// Replaces the tree with the calls in the given direction of the symbols at the given
+    /// position.
+    pub fn set_root(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: Anchor,
+        direction: CallDirection,
+        cx: &mut ModelContext<Self>,
+    ) {
+        self.direction = direction;
+        let items = self.project.update(cx, |project, cx| {
+            project.prepare_call_hierarchy(buffer, position, cx)
+        });
+        self.pending_roots = Some(cx.spawn(|this, mut cx| async move {
+            let items = items.await.log_err().unwrap_or_default();
+            this.update(&mut cx, |this, cx| {
+                this.pending_roots = None;
+                this.set_root_items(items, cx);
+            })
+            .ok();
+        }));
+        cx.notify();
+    }
+
+    fn set_root_items(&mut self, items: Vec<CallHierarchyItem>, cx: &mut ModelContext<Self>) {
+        self.nodes.clear();
+        self.roots.clear();
+        for item in &items {
+            let id = self.push_node(item.clone(), Vec::new(), 0);
+            self.roots.push(id);
+        }
+        self.root_items = items;
+        for root in self.roots.clone() {
+            self.expand(root, cx);
+        }
+        cx.emit(CallTreeEvent::RootsChanged);
+        cx.notify();
+    }
+
+    /// Whether the roots are still being requested from the language server.
+    pub fn is_loading_roots(&self) -> bool {
+        self.pending_roots.is_some()
+    }
+
+    pub fn roots(&self) -> &[CallNodeId] {
+        &self.roots
+    }
+
+    pub fn node(&self, id: CallNodeId) -> &CallNode {
+        &self.nodes[id.0]
+    }
+
+    /// Returns the nodes that are visible when the tree is rendered, in order.
+    pub fn visible_nodes(&self) -> Vec<CallNodeId> {
+        let mut result = Vec::new();
+        let mut stack = self.roots.iter().rev().copied().collect::<Vec<_>>();
+        while let Some(id) = stack.pop() {
+            result.push(id);
+            let node = self.node(id);
+            if node.expanded {
+                if let Children::Loaded(children) = &node.children {
+                    stack.extend(children.iter().rev());
+                }
+            }
+        }
+        result
+    }
+
+    /// Whether the node may have children, which isn't known until it's first expanded.
+    pub fn may_have_children(&self, id: CallNodeId) -> bool {
+        match &self.node(id).children {
+            Children::Unloaded | Children::Loading(_) => true,
+            Children::Loaded(children) => !children.is_empty(),
+        }
+    }
+
+    pub fn is_loading(&self, id: CallNodeId) -> bool {
+        matches!(self.node(id).children, Children::Loading(_))
+    }
+
+    pub fn toggle_expanded(&mut self, id: CallNodeId, cx: &mut ModelContext<Self>) {
+        if self.node(id).expanded {
+            self.collapse(id, cx);
+        } else {
+            self.expand(id, cx);
+        }
+    }
+
+    pub fn collapse(&mut self, id: CallNodeId, cx: &mut ModelContext<Self>) {
+        self.nodes[id.0].expanded = false;
+        cx.notify();
+    }
+
+    pub fn expand(&mut self, id: CallNodeId, cx: &mut ModelContext<Self>) {
+        self.nodes[id.0].expanded = true;
+        if let Children::Unloaded = self.node(id).children {
+            self.load_children(id, cx);
+        }
+        cx.notify();
+    }
+
+    fn load_children(&mut self, id: CallNodeId, cx: &mut ModelContext<Self>) {
+        let item = self.node(id).item.clone();
+        let direction = self.direction;
+        let key = CacheKey {
+            direction,
+            buffer_id: item.location.buffer.entity_id(),
+            start: item.location.range.start,
+        };
+        let version = item.location.buffer.read(cx).version();
+        if let Some(cached) = self.cache.get(&key) {
+            if cached.version == version {
+                let calls = cached.calls.clone();
+                self.insert_children(id, calls);
+                return;
+            }
+        }
+
+        let calls = self.project.update(cx, |project, cx| match direction {
+            CallDirection::Incoming => project.incoming_calls(&item, cx),
+            CallDirection::Outgoing => project.outgoing_calls(&item, cx),
+        });
+        let task = cx.spawn(|this, mut cx| async move {
+            let calls = calls.await.log_err();
+            this.update(&mut cx, |this, cx| {
+                if let Some(calls) = calls {
+                    this.cache.insert(
+                        key,
+                        CachedCalls {
+                            version,
+                            calls: calls.clone(),
+                        },
+                    );
+                    this.insert_children(id, calls);
+                } else {
+                    // Let the node be expanded again to retry.
+                    let node = &mut this.nodes[id.0];
+                    node.children = Children::Unloaded;
+                    node.expanded = false;
+                }
+                cx.notify();
+            })
+            .ok();
+        });
+        self.nodes[id.0].children = Children::Loading(task);
+    }
+
+    fn insert_children(&mut self, parent: CallNodeId, calls: Vec<CallHierarchyCall>) {
+        let depth = self.node(parent).depth + 1;
+        let children = calls
+            .into_iter()
+            .map(|call| self.push_node(call.item, call.call_sites, depth))
+            .collect();
+        self.nodes[parent.0].children = Children::Loaded(children);
+    }
+
+    fn push_node(
+        &mut self,
+        item: CallHierarchyItem,
+        call_sites: Vec<Location>,
+        depth: usize,
+    ) -> CallNodeId {
+        let id = CallNodeId(self.nodes.len());
+        self.nodes.push(CallNode {
+            item,
+            call_sites,
+            depth,
+            expanded: false,
+            children: Children::Unloaded,
+        });
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use gpui::{Context, TestAppContext};
+    use language::{FakeLspAdapter, Language, LanguageConfig, LanguageMatcher, OffsetRangeExt};
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc,
+    };
+
+    #[gpui::test]
+    async fn test_call_tree(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings = SettingsStore::test(cx);
+            cx.set_global(settings);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        // `a` is called by `b`, which is called by `c`.
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "main.rs": "fn a() {}\nfn b() { a() }\nfn c() { b() }\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+        language_registry.add(Arc::new(Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                matcher: LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+        )));
+        let mut fake_servers = language_registry.register_fake_lsp_adapter(
+            "Rust",
+            FakeLspAdapter {
+                capabilities: lsp::ServerCapabilities {
+                    call_hierarchy_provider: Some(lsp::CallHierarchyServerCapability::Simple(true)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/dir/main.rs", cx)
+            })
+            .await
+            .unwrap();
+        let fake_server = fake_servers.next().await.unwrap();
+
+        fn item(name: &str, row: u32) -> lsp::CallHierarchyItem {
+            let start = lsp::Position::new(row, 3);
+            lsp::CallHierarchyItem {
+                name: name.to_string(),
+                kind: lsp::SymbolKind::FUNCTION,
+                tags: None,
+                detail: None,
+                uri: lsp::Url::from_file_path("/dir/main.rs").unwrap(),
+                range: lsp::Range::new(lsp::Position::new(row, 0), lsp::Position::new(row, 9)),
+                selection_range: lsp::Range::new(start, lsp::Position::new(row, 4)),
+                data: None,
+            }
+        }
+        fake_server.handle_request::<lsp::request::CallHierarchyPrepare, _, _>(|_, _| async {
+            Ok(Some(vec![item("a", 0)]))
+        });
+        let incoming_requests = Arc::new(AtomicUsize::new(0));
+        fake_server.handle_request::<lsp::request::CallHierarchyIncomingCalls, _, _>({
+            let incoming_requests = incoming_requests.clone();
+            move |params, _| {
+                incoming_requests.fetch_add(1, SeqCst);
+                let caller = match params.item.name.as_str() {
+                    "a" => Some(("b", 1)),
+                    "b" => Some(("c", 2)),
+                    _ => None,
+                };
+                async move {
+                    Ok(Some(
+                        caller
+                            .into_iter()
+                            .map(|(name, row)| lsp::CallHierarchyIncomingCall {
+                                from: item(name, row),
+                                from_ranges: vec![lsp::Range::new(
+                                    lsp::Position::new(row, 9),
+                                    lsp::Position::new(row, 10),
+                                )],
+                            })
+                            .collect(),
+                    ))
+                }
+            }
+        });
+
+        let tree = cx.new_model(|_| CallTree::new(project.clone(), CallDirection::Incoming));
+        let anchor = buffer.read_with(cx, |buffer, _| buffer.anchor_before(3));
+        tree.update(cx, |tree, cx| {
+            tree.set_root(&buffer, anchor, CallDirection::Incoming, cx)
+        });
+        cx.run_until_parked();
+
+        let visible_names = |tree: &Model<CallTree>, cx: &mut TestAppContext| {
+            tree.read_with(cx, |tree, _| {
+                tree.visible_nodes()
+                    .into_iter()
+                    .map(|id| {
+                        let node = tree.node(id);
+                        format!("{}{}", "  ".repeat(node.depth), node.item.name)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        // Roots are expanded right away, but their children aren't.
+        assert_eq!(visible_names(&tree, cx), ["a", "  b"]);
+        assert_eq!(incoming_requests.load(SeqCst), 1);
+
+        let b = tree.read_with(cx, |tree, _| tree.visible_nodes()[1]);
+        tree.read_with(cx, |tree, cx| {
+            let call_site = &tree.node(b).call_sites[0];
+            assert_eq!(call_site.range.to_offset(call_site.buffer.read(cx)), 19..20);
+        });
+        tree.update(cx, |tree, cx| tree.expand(b, cx));
+        cx.run_until_parked();
+        assert_eq!(visible_names(&tree, cx), ["a", "  b", "    c"]);
+        assert_eq!(incoming_requests.load(SeqCst), 2);
+
+        // Collapsing and expanding again reuses the loaded calls.
+        tree.update(cx, |tree, cx| tree.collapse(b, cx));
+        assert_eq!(visible_names(&tree, cx), ["a", "  b"]);
+        tree.update(cx, |tree, cx| tree.expand(b, cx));
+        assert_eq!(visible_names(&tree, cx), ["a", "  b", "    c"]);
+
+        // Rebuilding the tree reuses the cached calls while the buffer is unchanged...
+        tree.update(cx, |tree, cx| tree.refresh(cx));
+        assert_eq!(visible_names(&tree, cx), ["a", "  b"]);
+        assert_eq!(incoming_requests.load(SeqCst), 2);
+
+        // ...but requests them again once it's edited.
+        buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "\n")], None, cx));
+        tree.update(cx, |tree, cx| tree.refresh(cx));
+        cx.run_until_parked();
+        assert_eq!(visible_names(&tree, cx), ["a", "  b"]);
+        assert_eq!(incoming_requests.load(SeqCst), 3);
+    }
+}
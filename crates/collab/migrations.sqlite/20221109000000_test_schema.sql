@@ -77,11 +77,16 @@ CREATE TABLE "worktree_entries" (
     "is_dir" BOOL NOT NULL,
     "path" VARCHAR NOT NULL,
     "inode" INTEGER NOT NULL,
+    "inode_volume" INTEGER NOT NULL DEFAULT 0,
     "mtime_seconds" INTEGER NOT NULL,
     "mtime_nanos" INTEGER NOT NULL,
+    "size" INTEGER NOT NULL DEFAULT 0,
+    "mode" INTEGER,
     "is_symlink" BOOL NOT NULL,
     "is_external" BOOL NOT NULL,
     "is_ignored" BOOL NOT NULL,
+    "is_symlink_cycle" BOOL NOT NULL DEFAULT FALSE,
+    "is_hidden" BOOL NOT NULL DEFAULT FALSE,
     "is_deleted" BOOL NOT NULL,
     "git_status" INTEGER,
     PRIMARY KEY(project_id, worktree_id, id),
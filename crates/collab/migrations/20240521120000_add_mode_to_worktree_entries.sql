@@ -0,0 +1 @@
+ALTER TABLE "worktree_entries" ADD COLUMN "mode" INT4;
@@ -0,0 +1 @@
+ALTER TABLE "worktree_entries" ADD COLUMN "is_symlink_cycle" BOOL NOT NULL DEFAULT FALSE;
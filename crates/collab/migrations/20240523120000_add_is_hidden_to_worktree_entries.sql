@@ -0,0 +1 @@
+ALTER TABLE "worktree_entries" ADD COLUMN "is_hidden" BOOL NOT NULL DEFAULT FALSE;
@@ -0,0 +1 @@
+ALTER TABLE "worktree_entries" ADD COLUMN "inode_volume" INT8 NOT NULL DEFAULT 0;
@@ -311,10 +311,15 @@ impl Database {
                         is_dir: ActiveValue::set(entry.is_dir),
                         path: ActiveValue::set(entry.path.clone()),
                         inode: ActiveValue::set(entry.inode as i64),
+                        inode_volume: ActiveValue::set(entry.inode_volume as i64),
                         mtime_seconds: ActiveValue::set(mtime.seconds as i64),
                         mtime_nanos: ActiveValue::set(mtime.nanos as i32),
+                        size: ActiveValue::set(entry.size as i64),
+                        mode: ActiveValue::set(entry.mode.map(|mode| mode as i32)),
                         is_symlink: ActiveValue::set(entry.is_symlink),
                         is_ignored: ActiveValue::set(entry.is_ignored),
+                        is_symlink_cycle: ActiveValue::set(entry.is_symlink_cycle),
+                        is_hidden: ActiveValue::set(entry.is_hidden),
                         is_external: ActiveValue::set(entry.is_external),
                         git_status: ActiveValue::set(entry.git_status.map(|status| status as i64)),
                         is_deleted: ActiveValue::set(false),
@@ -331,10 +336,15 @@ impl Database {
                         worktree_entry::Column::IsDir,
                         worktree_entry::Column::Path,
                         worktree_entry::Column::Inode,
+                        worktree_entry::Column::InodeVolume,
                         worktree_entry::Column::MtimeSeconds,
                         worktree_entry::Column::MtimeNanos,
+                        worktree_entry::Column::Size,
+                        worktree_entry::Column::Mode,
                         worktree_entry::Column::IsSymlink,
                         worktree_entry::Column::IsIgnored,
+                        worktree_entry::Column::IsSymlinkCycle,
+                        worktree_entry::Column::IsHidden,
                         worktree_entry::Column::GitStatus,
                         worktree_entry::Column::ScanId,
                     ])
@@ -719,12 +729,17 @@ impl Database {
                         is_dir: db_entry.is_dir,
                         path: db_entry.path,
                         inode: db_entry.inode as u64,
+                        inode_volume: db_entry.inode_volume as u64,
                         mtime: Some(proto::Timestamp {
                             seconds: db_entry.mtime_seconds as u64,
                             nanos: db_entry.mtime_nanos as u32,
                         }),
+                        size: db_entry.size as u64,
+                        mode: db_entry.mode.map(|mode| mode as u32),
                         is_symlink: db_entry.is_symlink,
                         is_ignored: db_entry.is_ignored,
+                        is_symlink_cycle: db_entry.is_symlink_cycle,
+                        is_hidden: db_entry.is_hidden,
                         is_external: db_entry.is_external,
                         git_status: db_entry.git_status.map(|status| status as i32),
                     });
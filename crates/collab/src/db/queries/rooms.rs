@@ -655,12 +655,17 @@ impl Database {
                             is_dir: db_entry.is_dir,
                             path: db_entry.path,
                             inode: db_entry.inode as u64,
+                            inode_volume: db_entry.inode_volume as u64,
                             mtime: Some(proto::Timestamp {
                                 seconds: db_entry.mtime_seconds as u64,
                                 nanos: db_entry.mtime_nanos as u32,
                             }),
+                            size: db_entry.size as u64,
+                            mode: db_entry.mode.map(|mode| mode as u32),
                             is_symlink: db_entry.is_symlink,
                             is_ignored: db_entry.is_ignored,
+                            is_symlink_cycle: db_entry.is_symlink_cycle,
+                            is_hidden: db_entry.is_hidden,
                             is_external: db_entry.is_external,
                             git_status: db_entry.git_status.map(|status| status as i32),
                         });
@@ -13,11 +13,16 @@ pub struct Model {
     pub is_dir: bool,
     pub path: String,
     pub inode: i64,
+    pub inode_volume: i64,
     pub mtime_seconds: i64,
     pub mtime_nanos: i32,
+    pub size: i64,
+    pub mode: Option<i32>,
     pub git_status: Option<i64>,
     pub is_symlink: bool,
     pub is_ignored: bool,
+    pub is_symlink_cycle: bool,
+    pub is_hidden: bool,
     pub is_external: bool,
     pub is_deleted: bool,
     pub scan_id: i64,
@@ -455,12 +455,33 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::GetHover>,
             ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSignatureHelp>,
+            ))
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::GetDefinition>,
             ))
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::GetTypeDefinition>,
             ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetImplementation>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::PrepareCallHierarchy>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetIncomingCalls>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetOutgoingCalls>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetCodeLens>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetDocumentColor>,
+            ))
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::GetReferences>,
             ))
@@ -0,0 +1,711 @@
+//! A client for the [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/),
+//! through which editors drive debuggers. Like language servers, debug adapters are processes
+//! that exchange messages framed by a `Content-Length` header over their standard streams.
+
+mod debug_config;
+pub mod protocol;
+
+pub use debug_config::*;
+
+use anyhow::{anyhow, Context, Result};
+use collections::HashMap;
+use futures::{channel::oneshot, io::BufWriter, select, AsyncRead, AsyncWrite, Future, FutureExt};
+use gpui::{AsyncAppContext, BackgroundExecutor, Task};
+use parking_lot::Mutex;
+use protocol::{Event, Request};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use smol::{
+    channel,
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    process::{self, Child},
+};
+use std::{
+    io::Write,
+    path::Path,
+    process::Stdio,
+    str,
+    sync::{
+        atomic::{AtomicU64, Ordering::SeqCst},
+        Arc,
+    },
+    time::Duration,
+};
+use util::{ResultExt, TryFutureExt};
+
+const HEADER_DELIMITER: &'static [u8; 4] = b"\r\n\r\n";
+const CONTENT_LEN_HEADER: &str = "Content-Length: ";
+const DAP_REQUEST_TIMEOUT: Duration = Duration::from_secs(60 * 2);
+
+type EventHandler = Box<dyn Send + FnMut(Value, AsyncAppContext)>;
+type ResponseHandler = Box<dyn Send + FnOnce(Result<Value>)>;
+
+/// Identifies a running debug adapter.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct DebugAdapterId(pub usize);
+
+/// A message of the protocol, as it's read before its arguments or body are deserialized.
+///
+/// [DAP Specification](https://microsoft.github.io/debug-adapter-protocol/specification#Base_Protocol_ProtocolMessage)
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum IncomingMessage {
+    Request {
+        seq: u64,
+        command: String,
+    },
+    Response {
+        request_seq: u64,
+        success: bool,
+        command: String,
+        #[serde(default)]
+        message: Option<String>,
+        #[serde(default)]
+        body: Value,
+    },
+    Event {
+        event: String,
+        #[serde(default)]
+        body: Value,
+    },
+}
+
+#[derive(Serialize)]
+struct OutgoingRequest<'a> {
+    seq: u64,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    command: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arguments: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct OutgoingResponse<'a> {
+    seq: u64,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    request_seq: u64,
+    success: bool,
+    command: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+/// A running debug adapter process.
+pub struct DebugAdapterClient {
+    id: DebugAdapterId,
+    name: Arc<str>,
+    next_seq: Arc<AtomicU64>,
+    outbound_tx: channel::Sender<String>,
+    event_handlers: Arc<Mutex<HashMap<&'static str, EventHandler>>>,
+    response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+    executor: BackgroundExecutor,
+    #[allow(clippy::type_complexity)]
+    io_tasks: Mutex<Option<(Task<Option<()>>, Task<Option<()>>)>>,
+    process: Arc<Mutex<Option<Child>>>,
+}
+
+impl DebugAdapterClient {
+    /// Starts a debug adapter process.
+    pub fn new(
+        id: DebugAdapterId,
+        binary: &DebugAdapterBinary,
+        working_dir: &Path,
+        cx: AsyncAppContext,
+    ) -> Result<Self> {
+        log::info!(
+            "starting debug adapter. binary path: {:?}, working directory: {:?}, args: {:?}",
+            binary.path,
+            working_dir,
+            &binary.arguments
+        );
+
+        let mut process = process::Command::new(&binary.path)
+            .current_dir(working_dir)
+            .args(&binary.arguments)
+            .envs(binary.env.clone())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "failed to spawn debug adapter. path: {:?}, working directory: {:?}, args: {:?}",
+                    binary.path, working_dir, &binary.arguments
+                )
+            })?;
+
+        let stdin = process.stdin.take().unwrap();
+        let stdout = process.stdout.take().unwrap();
+        let stderr = process.stderr.take().unwrap();
+        let name: Arc<str> = binary
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into())
+            .unwrap_or_else(|| "debug adapter".into());
+        cx.background_executor()
+            .spawn({
+                let name = name.clone();
+                async move {
+                    let mut stderr = BufReader::new(stderr).lines();
+                    while let Some(Ok(line)) = futures::StreamExt::next(&mut stderr).await {
+                        log::debug!("{name} stderr: {line}");
+                    }
+                }
+            })
+            .detach();
+
+        Ok(Self::new_internal(
+            id,
+            name,
+            stdin,
+            stdout,
+            Some(process),
+            cx,
+        ))
+    }
+
+    fn new_internal<Stdin, Stdout>(
+        id: DebugAdapterId,
+        name: Arc<str>,
+        stdin: Stdin,
+        stdout: Stdout,
+        process: Option<Child>,
+        cx: AsyncAppContext,
+    ) -> Self
+    where
+        Stdin: AsyncWrite + Unpin + Send + 'static,
+        Stdout: AsyncRead + Unpin + Send + 'static,
+    {
+        let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
+        let next_seq = Arc::new(AtomicU64::new(1));
+        let event_handlers = Arc::new(Mutex::new(HashMap::<_, EventHandler>::default()));
+        let response_handlers =
+            Arc::new(Mutex::new(Some(HashMap::<_, ResponseHandler>::default())));
+
+        let input_task = cx.spawn({
+            let name = name.clone();
+            let next_seq = next_seq.clone();
+            let outbound_tx = outbound_tx.clone();
+            let event_handlers = event_handlers.clone();
+            let response_handlers = response_handlers.clone();
+            move |cx| {
+                Self::handle_input(
+                    name,
+                    stdout,
+                    next_seq,
+                    outbound_tx,
+                    event_handlers,
+                    response_handlers,
+                    cx,
+                )
+                .log_err()
+            }
+        });
+        let output_task = cx
+            .background_executor()
+            .spawn(Self::handle_output(stdin, outbound_rx, response_handlers.clone()).log_err());
+
+        Self {
+            id,
+            name,
+            next_seq,
+            outbound_tx,
+            event_handlers,
+            response_handlers,
+            executor: cx.background_executor().clone(),
+            io_tasks: Mutex::new(Some((input_task, output_task))),
+            process: Arc::new(Mutex::new(process)),
+        }
+    }
+
+    async fn handle_input<Stdout>(
+        name: Arc<str>,
+        stdout: Stdout,
+        next_seq: Arc<AtomicU64>,
+        outbound_tx: channel::Sender<String>,
+        event_handlers: Arc<Mutex<HashMap<&'static str, EventHandler>>>,
+        response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+        cx: AsyncAppContext,
+    ) -> Result<()>
+    where
+        Stdout: AsyncRead + Unpin + Send + 'static,
+    {
+        let mut stdout = BufReader::new(stdout);
+        let _clear_response_handlers = util::defer({
+            let response_handlers = response_handlers.clone();
+            move || {
+                response_handlers.lock().take();
+            }
+        });
+        let mut buffer = Vec::new();
+        loop {
+            read_message(&mut stdout, &mut buffer).await?;
+            log::trace!("incoming message: {}", String::from_utf8_lossy(&buffer));
+
+            match serde_json::from_slice::<IncomingMessage>(&buffer) {
+                Ok(IncomingMessage::Response {
+                    request_seq,
+                    success,
+                    command,
+                    message,
+                    body,
+                }) => {
+                    let handler = response_handlers
+                        .lock()
+                        .as_mut()
+                        .and_then(|handlers| handlers.remove(&request_seq));
+                    if let Some(handler) = handler {
+                        if success {
+                            handler(Ok(body));
+                        } else {
+                            handler(Err(anyhow!(
+                                "{command} request failed: {}",
+                                message.unwrap_or_default()
+                            )));
+                        }
+                    }
+                }
+                Ok(IncomingMessage::Event { event, body }) => {
+                    let mut event_handlers = event_handlers.lock();
+                    if let Some(handler) = event_handlers.get_mut(event.as_str()) {
+                        handler(body, cx.clone());
+                    } else {
+                        log::debug!("{name} sent unhandled event {event}");
+                    }
+                }
+                Ok(IncomingMessage::Request { seq, command }) => {
+                    log::info!("{name} sent unsupported request {command}");
+                    let response = OutgoingResponse {
+                        seq: next_seq.fetch_add(1, SeqCst),
+                        kind: "response",
+                        request_seq: seq,
+                        success: false,
+                        command: &command,
+                        message: Some(format!("{command} is not supported")),
+                    };
+                    if let Some(response) = serde_json::to_string(&response).log_err() {
+                        outbound_tx.try_send(response).ok();
+                    }
+                }
+                Err(error) => {
+                    log::warn!(
+                        "failed to deserialize message from {name}: {error}\n{}",
+                        String::from_utf8_lossy(&buffer)
+                    );
+                }
+            }
+
+            // Don't starve the main thread when receiving lots of messages at once.
+            smol::future::yield_now().await;
+        }
+    }
+
+    async fn handle_output<Stdin>(
+        stdin: Stdin,
+        outbound_rx: channel::Receiver<String>,
+        response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+    ) -> Result<()>
+    where
+        Stdin: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut stdin = BufWriter::new(stdin);
+        let _clear_response_handlers = util::defer({
+            let response_handlers = response_handlers.clone();
+            move || {
+                response_handlers.lock().take();
+            }
+        });
+        while let Ok(message) = outbound_rx.recv().await {
+            log::trace!("outgoing message: {message}");
+            write_message(&mut stdin, &message).await?;
+        }
+        Ok(())
+    }
+
+    /// The identifier of the adapter.
+    pub fn id(&self) -> DebugAdapterId {
+        self.id
+    }
+
+    /// The name of the adapter's binary.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Registers a handler for an event of the adapter, which stays registered for as long as
+    /// the client lives.
+    pub fn on_event<E, F>(&self, mut f: F)
+    where
+        E: Event,
+        F: 'static + Send + FnMut(E::Body, AsyncAppContext),
+    {
+        let prev_handler = self.event_handlers.lock().insert(
+            E::EVENT,
+            Box::new(move |body, cx| {
+                if let Some(body) = serde_json::from_value(body).log_err() {
+                    f(body, cx);
+                }
+            }),
+        );
+        assert!(
+            prev_handler.is_none(),
+            "registered multiple handlers for the same DAP event"
+        );
+    }
+
+    /// Sends a request to the adapter. The request is sent right away, even if the returned
+    /// future isn't polled.
+    ///
+    /// [DAP Specification](https://microsoft.github.io/debug-adapter-protocol/specification#Base_Protocol_Request)
+    pub fn request<R: Request>(
+        &self,
+        arguments: R::Arguments,
+    ) -> impl 'static + Send + Future<Output = Result<R::Response>> {
+        let seq = self.next_seq.fetch_add(1, SeqCst);
+        let message = serde_json::to_value(arguments).and_then(|arguments| {
+            serde_json::to_string(&OutgoingRequest {
+                seq,
+                kind: "request",
+                command: R::COMMAND,
+                arguments: Some(arguments).filter(|arguments| !arguments.is_null()),
+            })
+        });
+
+        let (tx, rx) = oneshot::channel();
+        let handle_response = self
+            .response_handlers
+            .lock()
+            .as_mut()
+            .ok_or_else(|| anyhow!("debug adapter shut down"))
+            .map(|handlers| {
+                handlers.insert(
+                    seq,
+                    Box::new(move |response| {
+                        let response = response.and_then(|body| {
+                            serde_json::from_value(body)
+                                .with_context(|| format!("invalid {} response", R::COMMAND))
+                        });
+                        tx.send(response).ok();
+                    }),
+                );
+            });
+        let send = message
+            .context("failed to serialize request")
+            .and_then(|message| {
+                self.outbound_tx
+                    .try_send(message)
+                    .context("failed to write to debug adapter's stdin")
+            });
+
+        let mut timeout = self.executor.timer(DAP_REQUEST_TIMEOUT).fuse();
+        async move {
+            handle_response?;
+            send?;
+            select! {
+                response = rx.fuse() => response?,
+                _ = timeout => {
+                    anyhow::bail!("{} request timed out", R::COMMAND)
+                }
+            }
+        }
+    }
+
+    /// Stops reading and writing messages, and kills the adapter process.
+    pub fn kill(&self) {
+        self.outbound_tx.close();
+        self.response_handlers.lock().take();
+        self.io_tasks.lock().take();
+        if let Some(mut process) = self.process.lock().take() {
+            process.kill().log_err();
+        }
+    }
+}
+
+impl Drop for DebugAdapterClient {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+async fn read_message<Stdout>(reader: &mut BufReader<Stdout>, buffer: &mut Vec<u8>) -> Result<()>
+where
+    Stdout: AsyncRead + Unpin + Send + 'static,
+{
+    buffer.clear();
+    loop {
+        if buffer.len() >= HEADER_DELIMITER.len()
+            && buffer[(buffer.len() - HEADER_DELIMITER.len())..] == HEADER_DELIMITER[..]
+        {
+            break;
+        }
+        if reader.read_until(b'\n', buffer).await? == 0 {
+            return Err(anyhow!("cannot read DAP message headers"));
+        }
+    }
+
+    let headers = str::from_utf8(buffer)?;
+    let message_len = headers
+        .split('\n')
+        .find_map(|line| line.strip_prefix(CONTENT_LEN_HEADER))
+        .ok_or_else(|| anyhow!("invalid DAP message header {headers:?}"))?
+        .trim_end()
+        .parse()?;
+    buffer.resize(message_len, 0);
+    reader.read_exact(buffer).await?;
+    Ok(())
+}
+
+async fn write_message<Stdin>(writer: &mut BufWriter<Stdin>, message: &str) -> Result<()>
+where
+    Stdin: AsyncWrite + Unpin + Send + 'static,
+{
+    let mut header = Vec::new();
+    write!(header, "{CONTENT_LEN_HEADER}{}\r\n\r\n", message.len()).unwrap();
+    writer.write_all(&header).await?;
+    writer.write_all(message.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// A debug adapter that answers the client's requests with handlers registered by tests.
+#[cfg(any(test, feature = "test-support"))]
+pub struct FakeDebugAdapter {
+    next_seq: AtomicU64,
+    outbound_tx: channel::Sender<String>,
+    request_handlers: Arc<Mutex<HashMap<&'static str, FakeRequestHandler>>>,
+    received_commands: Arc<Mutex<Vec<String>>>,
+    _io_tasks: (Task<Option<()>>, Task<Option<()>>),
+}
+
+#[cfg(any(test, feature = "test-support"))]
+type FakeRequestHandler = Box<dyn Send + FnMut(Value) -> Result<Value>>;
+
+#[cfg(any(test, feature = "test-support"))]
+impl FakeDebugAdapter {
+    /// Creates a client connected to a fake adapter.
+    pub fn new(id: DebugAdapterId, cx: AsyncAppContext) -> (DebugAdapterClient, Self) {
+        let (client_stdin, adapter_stdin) = async_pipe::pipe();
+        let (adapter_stdout, client_stdout) = async_pipe::pipe();
+        let client = DebugAdapterClient::new_internal(
+            id,
+            "fake".into(),
+            client_stdin,
+            client_stdout,
+            None,
+            cx.clone(),
+        );
+
+        let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
+        let request_handlers = Arc::new(Mutex::new(HashMap::<_, FakeRequestHandler>::default()));
+        let received_commands = Arc::new(Mutex::new(Vec::new()));
+        let input_task = cx.background_executor().spawn(
+            Self::handle_requests(
+                adapter_stdin,
+                outbound_tx.clone(),
+                request_handlers.clone(),
+                received_commands.clone(),
+            )
+            .log_err(),
+        );
+        let output_task = cx.background_executor().spawn(
+            async move {
+                let mut writer = BufWriter::new(adapter_stdout);
+                while let Ok(message) = outbound_rx.recv().await {
+                    write_message(&mut writer, &message).await?;
+                }
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+
+        let adapter = Self {
+            next_seq: AtomicU64::new(1),
+            outbound_tx,
+            request_handlers,
+            received_commands,
+            _io_tasks: (input_task, output_task),
+        };
+        (client, adapter)
+    }
+
+    async fn handle_requests(
+        stdin: async_pipe::PipeReader,
+        outbound_tx: channel::Sender<String>,
+        request_handlers: Arc<Mutex<HashMap<&'static str, FakeRequestHandler>>>,
+        received_commands: Arc<Mutex<Vec<String>>>,
+    ) -> Result<()> {
+        #[derive(Deserialize)]
+        struct AnyRequest {
+            seq: u64,
+            command: String,
+            #[serde(default)]
+            arguments: Value,
+        }
+
+        let mut reader = BufReader::new(stdin);
+        let mut buffer = Vec::new();
+        let mut next_seq = 1_000_000;
+        loop {
+            read_message(&mut reader, &mut buffer).await?;
+            let request = serde_json::from_slice::<AnyRequest>(&buffer)?;
+            received_commands.lock().push(request.command.clone());
+            let result = match request_handlers.lock().get_mut(request.command.as_str()) {
+                Some(handler) => handler(request.arguments),
+                None => Err(anyhow!("unhandled request")),
+            };
+            next_seq += 1;
+            let mut response = serde_json::to_value(OutgoingResponse {
+                seq: next_seq,
+                kind: "response",
+                request_seq: request.seq,
+                success: result.is_ok(),
+                command: &request.command,
+                message: result.as_ref().err().map(|error| error.to_string()),
+            })?;
+            if let Ok(body) = result {
+                response["body"] = body;
+            }
+            outbound_tx.try_send(response.to_string())?;
+        }
+    }
+
+    /// Answers the requests of the given kind with the handler.
+    pub fn handle_request<R, F>(&self, mut f: F)
+    where
+        R: Request,
+        F: 'static + Send + FnMut(R::Arguments) -> Result<R::Response>,
+    {
+        self.request_handlers.lock().insert(
+            R::COMMAND,
+            Box::new(move |arguments| {
+                let arguments = serde_json::from_value(arguments)?;
+                Ok(serde_json::to_value(f(arguments)?)?)
+            }),
+        );
+    }
+
+    /// Sends an event to the client.
+    pub fn send_event<E: Event>(&self, body: E::Body) {
+        let mut message = serde_json::json!({
+            "seq": self.next_seq.fetch_add(1, SeqCst),
+            "type": "event",
+            "event": E::EVENT,
+        });
+        let body = serde_json::to_value(body).unwrap();
+        if !body.is_null() {
+            message["body"] = body;
+        }
+        self.outbound_tx.try_send(message.to_string()).unwrap();
+    }
+
+    /// The commands of the requests that the adapter received, in order.
+    pub fn received_commands(&self) -> Vec<String> {
+        self.received_commands.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use protocol::*;
+
+    #[gpui::test]
+    async fn test_requests_and_events(cx: &mut TestAppContext) {
+        let (client, adapter) = FakeDebugAdapter::new(DebugAdapterId(0), cx.to_async());
+        adapter.handle_request::<Initialize, _>(|arguments| {
+            assert_eq!(arguments.adapter_id, "lldb");
+            Ok(Capabilities {
+                supports_configuration_done_request: Some(true),
+                ..Default::default()
+            })
+        });
+        adapter.handle_request::<Threads, _>(|()| {
+            Ok(ThreadsResponse {
+                threads: vec![Thread {
+                    id: 1,
+                    name: "main".into(),
+                }],
+            })
+        });
+
+        let (stopped_tx, stopped_rx) = oneshot::channel();
+        let mut stopped_tx = Some(stopped_tx);
+        client.on_event::<Stopped, _>(move |body, _| {
+            if let Some(stopped_tx) = stopped_tx.take() {
+                stopped_tx.send(body).ok();
+            }
+        });
+
+        let capabilities = client
+            .request::<Initialize>(InitializeArguments {
+                adapter_id: "lldb".into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(capabilities.supports_configuration_done_request, Some(true));
+
+        let threads = client.request::<Threads>(()).await.unwrap();
+        assert_eq!(threads.threads[0].name, "main");
+
+        let error = client
+            .request::<ConfigurationDone>(())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unhandled request"), "{error}");
+
+        adapter.send_event::<Stopped>(StoppedEventBody {
+            reason: "breakpoint".into(),
+            description: None,
+            thread_id: Some(1),
+            all_threads_stopped: Some(true),
+        });
+        let stopped = stopped_rx.await.unwrap();
+        assert_eq!(stopped.reason, "breakpoint");
+        assert_eq!(stopped.thread_id, Some(1));
+
+        assert_eq!(
+            adapter.received_commands(),
+            ["initialize", "threads", "configurationDone"]
+        );
+    }
+
+    #[test]
+    fn test_parse_debug_configs() {
+        let configs = serde_json::from_str::<DebugConfigs>(
+            r#"[
+                {
+                    "label": "Debug server",
+                    "adapter": { "command": "/usr/bin/lldb-dap", "args": ["--verbose"] },
+                    "program": "target/debug/server",
+                    "stopOnEntry": true
+                },
+                {
+                    "label": "Attach",
+                    "adapter": { "id": "debugpy", "command": "python3" },
+                    "request": "attach",
+                    "processId": 42
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let server = &configs.0[0];
+        assert_eq!(server.request, DebugRequestKind::Launch);
+        assert_eq!(server.adapter.adapter_id(), "lldb-dap");
+        assert_eq!(server.arguments["program"], "target/debug/server");
+        assert_eq!(server.arguments["stopOnEntry"], true);
+        assert!(!server.arguments.contains_key("label"));
+
+        let attach = &configs.0[1];
+        assert_eq!(attach.request, DebugRequestKind::Attach);
+        assert_eq!(attach.adapter.adapter_id(), "debugpy");
+        assert_eq!(attach.arguments["processId"], 42);
+    }
+}
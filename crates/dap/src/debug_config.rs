@@ -0,0 +1,84 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The contents of a `.zed/debug.json` file: the configurations that debugging can be started
+/// with in a worktree.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DebugConfigs(pub Vec<DebugConfig>);
+
+/// How to start debugging: the adapter to run, and the arguments of the request that starts
+/// or attaches to the debuggee.
+///
+/// ```json
+/// {
+///   "label": "Debug server",
+///   "adapter": { "command": "lldb-dap" },
+///   "request": "launch",
+///   "program": "target/debug/server",
+///   "args": ["--port", "8080"]
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DebugConfig {
+    /// The name of the configuration, shown to the user.
+    pub label: String,
+    pub adapter: DebugAdapterConfig,
+    #[serde(default)]
+    pub request: DebugRequestKind,
+    /// Arguments specific to the adapter, passed as they are in the launch or attach request.
+    #[serde(flatten)]
+    pub arguments: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DebugAdapterConfig {
+    /// The identifier of the adapter's type sent in the `initialize` request, such as `"lldb"`.
+    /// Defaults to the name of the command.
+    #[serde(default)]
+    pub id: Option<String>,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// Whether the adapter starts the debuggee or attaches to one that's running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DebugRequestKind {
+    #[default]
+    Launch,
+    Attach,
+}
+
+/// Represents a launchable debug adapter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DebugAdapterBinary {
+    pub path: PathBuf,
+    pub arguments: Vec<OsString>,
+    pub env: HashMap<String, String>,
+}
+
+impl DebugAdapterConfig {
+    pub fn adapter_id(&self) -> String {
+        self.id.clone().unwrap_or_else(|| {
+            PathBuf::from(&self.command)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.command.clone())
+        })
+    }
+
+    pub fn binary(&self) -> DebugAdapterBinary {
+        DebugAdapterBinary {
+            path: PathBuf::from(&self.command),
+            arguments: self.args.iter().map(OsString::from).collect(),
+            env: self.env.clone(),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! The requests, events and types of the Debug Adapter Protocol that the client uses.
+//!
+//! [DAP Specification](https://microsoft.github.io/debug-adapter-protocol/specification)
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+/// A request that the client sends to the debug adapter.
+pub trait Request {
+    type Arguments: 'static + Serialize + DeserializeOwned + Send;
+    type Response: 'static + Serialize + DeserializeOwned + Send;
+    const COMMAND: &'static str;
+}
+
+/// An event that the debug adapter sends to the client.
+pub trait Event {
+    type Body: 'static + Serialize + DeserializeOwned + Send;
+    const EVENT: &'static str;
+}
+
+macro_rules! request {
+    ($name:ident, $command:literal, $arguments:ty, $response:ty) => {
+        #[derive(Debug)]
+        pub enum $name {}
+
+        impl Request for $name {
+            type Arguments = $arguments;
+            type Response = $response;
+            const COMMAND: &'static str = $command;
+        }
+    };
+}
+
+macro_rules! event {
+    ($name:ident, $event:literal, $body:ty) => {
+        #[derive(Debug)]
+        pub enum $name {}
+
+        impl Event for $name {
+            type Body = $body;
+            const EVENT: &'static str = $event;
+        }
+    };
+}
+
+request!(Initialize, "initialize", InitializeArguments, Capabilities);
+request!(Launch, "launch", Value, ());
+request!(Attach, "attach", Value, ());
+request!(ConfigurationDone, "configurationDone", (), ());
+request!(
+    SetBreakpoints,
+    "setBreakpoints",
+    SetBreakpointsArguments,
+    SetBreakpointsResponse
+);
+request!(Continue, "continue", ThreadArguments, ContinueResponse);
+request!(Next, "next", ThreadArguments, ());
+request!(StepIn, "stepIn", ThreadArguments, ());
+request!(StepOut, "stepOut", ThreadArguments, ());
+request!(Pause, "pause", ThreadArguments, ());
+request!(Threads, "threads", (), ThreadsResponse);
+request!(
+    StackTrace,
+    "stackTrace",
+    StackTraceArguments,
+    StackTraceResponse
+);
+request!(Scopes, "scopes", ScopesArguments, ScopesResponse);
+request!(
+    Variables,
+    "variables",
+    VariablesArguments,
+    VariablesResponse
+);
+request!(Disconnect, "disconnect", DisconnectArguments, ());
+
+event!(Initialized, "initialized", Option<Value>);
+event!(Stopped, "stopped", StoppedEventBody);
+event!(Continued, "continued", ContinuedEventBody);
+event!(Exited, "exited", ExitedEventBody);
+event!(Terminated, "terminated", Option<Value>);
+event!(Output, "output", OutputEventBody);
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeArguments {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    #[serde(rename = "adapterID")]
+    pub adapter_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines_start_at1: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns_start_at1: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_variable_type: Option<bool>,
+}
+
+/// The features that a debug adapter supports.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Capabilities {
+    pub supports_configuration_done_request: Option<bool>,
+    pub supports_conditional_breakpoints: Option<bool>,
+    pub supports_terminate_request: Option<bool>,
+    pub support_terminate_debuggee: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Source {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_reference: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceBreakpoint {
+    /// The one-based line of the breakpoint.
+    pub line: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetBreakpointsArguments {
+    pub source: Source,
+    pub breakpoints: Vec<SourceBreakpoint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Breakpoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub verified: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetBreakpointsResponse {
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+/// The arguments of the requests that act on a single thread, such as stepping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadArguments {
+    pub thread_id: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinueResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_threads_continued: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thread {
+    pub id: u64,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadsResponse {
+    pub threads: Vec<Thread>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackTraceArguments {
+    pub thread_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_frame: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub levels: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackFrame {
+    pub id: u64,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    /// The one-based line of the frame's position, or 0 if it has no source.
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackTraceResponse {
+    pub stack_frames: Vec<StackFrame>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_frames: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopesArguments {
+    pub frame_id: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scope {
+    pub name: String,
+    pub variables_reference: u64,
+    #[serde(default)]
+    pub expensive: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopesResponse {
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesArguments {
+    pub variables_reference: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    /// If greater than 0, the variable is structured and its children can be requested with
+    /// this reference.
+    #[serde(default)]
+    pub variables_reference: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesResponse {
+    pub variables: Vec<Variable>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisconnectArguments {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminate_debuggee: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoppedEventBody {
+    /// Why the debuggee stopped, such as `"breakpoint"` or `"step"`.
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_threads_stopped: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuedEventBody {
+    pub thread_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_threads_continued: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitedEventBody {
+    pub exit_code: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputEventBody {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    pub output: String,
+}
@@ -0,0 +1,691 @@
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashSet;
+use editor::{
+    display_map::{BlockDisposition, BlockId, BlockProperties, BlockStyle},
+    Editor, EditorEvent,
+};
+use git::diff::BufferDiff;
+use gpui::{
+    actions, div, AnyElement, AppContext, Context, EventEmitter, FocusHandle, FocusableView, Hsla,
+    IntoElement, Model, ParentElement, Render, SharedString, Styled, Subscription, Task, View,
+    ViewContext, VisualContext, WindowContext,
+};
+use language::{Anchor, Buffer, BufferSnapshot, OffsetRangeExt, Point};
+use project::Project;
+use std::ops::Range;
+use ui::prelude::*;
+use util::ResultExt as _;
+use workspace::{
+    item::{Item, ItemEvent, TabContentParams},
+    Workspace,
+};
+
+mod file_history;
+mod merge_view;
+
+pub use file_history::FileHistory;
+pub use merge_view::{ConflictResolution, ConflictSide, MergeView};
+
+actions!(
+    diff_view,
+    [
+        DiffWithSaved,
+        DiffWithHead,
+        ToggleUnifiedDiff,
+        CopyHunkToLeft,
+        CopyHunkToRight
+    ]
+);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(DiffView::register).detach();
+    merge_view::init(cx);
+    file_history::init(cx);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffViewMode {
+    /// The old text on the left and the new text on the right, with their lines aligned.
+    SideBySide,
+    /// Only the new text, with the removed lines shown above each hunk.
+    Unified,
+}
+
+/// A pair of corresponding ranges in the two sides of the diff.
+#[derive(Clone, Debug)]
+struct DiffViewHunk {
+    old_range: Range<Anchor>,
+    new_range: Range<Anchor>,
+}
+
+enum DiffViewRowHighlight {}
+
+/// A workspace item comparing two buffers, where the buffer on the right side can be edited.
+pub struct DiffView {
+    old_buffer: Model<Buffer>,
+    new_buffer: Model<Buffer>,
+    old_editor: View<Editor>,
+    new_editor: View<Editor>,
+    title: SharedString,
+    mode: DiffViewMode,
+    hunks: Vec<DiffViewHunk>,
+    old_blocks: HashSet<BlockId>,
+    new_blocks: HashSet<BlockId>,
+    _diff_task: Task<()>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl DiffView {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(|workspace, _: &DiffWithSaved, cx| {
+            Self::diff_active_buffer_with_saved(workspace, cx).detach_and_log_err(cx);
+        });
+        workspace.register_action(|workspace, _: &DiffWithHead, cx| {
+            Self::diff_active_buffer_with_head(workspace, cx).detach_and_log_err(cx);
+        });
+    }
+
+    fn active_buffer(workspace: &Workspace, cx: &AppContext) -> Result<Model<Buffer>> {
+        workspace
+            .active_item_as::<Editor>(cx)
+            .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+            .context("no active buffer to compare")
+    }
+
+    /// Compares the active buffer with the contents of its file on disk.
+    pub fn diff_active_buffer_with_saved(
+        workspace: &mut Workspace,
+        cx: &mut ViewContext<Workspace>,
+    ) -> Task<Result<()>> {
+        let buffer = match Self::active_buffer(workspace, cx) {
+            Ok(buffer) => buffer,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let Some(abs_path) = buffer
+            .read(cx)
+            .file()
+            .and_then(|file| file.as_local())
+            .map(|file| file.abs_path(cx))
+        else {
+            return Task::ready(Err(anyhow!("buffer has no file on disk")));
+        };
+        let fs = workspace.app_state().fs.clone();
+        let project = workspace.project().clone();
+
+        cx.spawn(|workspace, mut cx| async move {
+            let saved_text = fs.load(&abs_path).await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let title = format!("{} (saved ↔ current)", file_name(&buffer, cx)).into();
+                Self::open(saved_text, buffer, title, project, workspace, cx);
+            })
+        })
+    }
+
+    /// Compares the active buffer with its file's contents in the HEAD commit.
+    pub fn diff_active_buffer_with_head(
+        workspace: &mut Workspace,
+        cx: &mut ViewContext<Workspace>,
+    ) -> Task<Result<()>> {
+        let buffer = match Self::active_buffer(workspace, cx) {
+            Ok(buffer) => buffer,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let project = workspace.project().clone();
+        let committed_text = project.read(cx).load_committed_text(&buffer, cx);
+
+        cx.spawn(|workspace, mut cx| async move {
+            let committed_text = committed_text.await?.unwrap_or_default();
+            workspace.update(&mut cx, |workspace, cx| {
+                let title = format!("{} (HEAD ↔ current)", file_name(&buffer, cx)).into();
+                Self::open(committed_text, buffer, title, project, workspace, cx);
+            })
+        })
+    }
+
+    /// Opens a diff view comparing `old_text` with `new_buffer` in the active pane.
+    pub fn open(
+        old_text: String,
+        new_buffer: Model<Buffer>,
+        title: SharedString,
+        project: Model<Project>,
+        workspace: &mut Workspace,
+        cx: &mut ViewContext<Workspace>,
+    ) -> View<Self> {
+        let language = new_buffer.read(cx).language().cloned();
+        let old_buffer = cx.new_model(|cx| {
+            let mut buffer = Buffer::local(old_text, cx);
+            buffer.set_language(language, cx);
+            buffer
+        });
+        let diff_view = cx.new_view(|cx| Self::new(old_buffer, new_buffer, title, project, cx));
+        workspace.add_item_to_active_pane(Box::new(diff_view.clone()), None, cx);
+        diff_view
+    }
+
+    pub fn new(
+        old_buffer: Model<Buffer>,
+        new_buffer: Model<Buffer>,
+        title: SharedString,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let old_editor = cx.new_view(|cx| {
+            let mut editor = Editor::for_buffer(old_buffer.clone(), Some(project.clone()), cx);
+            editor.set_read_only(true);
+            editor
+        });
+        let new_editor =
+            cx.new_view(|cx| Editor::for_buffer(new_buffer.clone(), Some(project), cx));
+
+        let subscriptions = vec![
+            cx.subscribe(&old_buffer, |this, _, event, cx| {
+                if let language::Event::Edited = event {
+                    this.schedule_diff(cx);
+                }
+            }),
+            cx.subscribe(&new_buffer, |this, _, event, cx| match event {
+                language::Event::Edited => this.schedule_diff(cx),
+                language::Event::DirtyChanged | language::Event::Saved => {
+                    cx.emit(ItemEvent::UpdateTab)
+                }
+                _ => {}
+            }),
+            cx.subscribe(&old_editor, |this, _, event, cx| {
+                this.sync_scroll_position(event, true, cx)
+            }),
+            cx.subscribe(&new_editor, |this, _, event, cx| {
+                this.sync_scroll_position(event, false, cx)
+            }),
+        ];
+
+        let mut this = Self {
+            old_buffer,
+            new_buffer,
+            old_editor,
+            new_editor,
+            title,
+            mode: DiffViewMode::SideBySide,
+            hunks: Vec::new(),
+            old_blocks: HashSet::default(),
+            new_blocks: HashSet::default(),
+            _diff_task: Task::ready(()),
+            _subscriptions: subscriptions,
+        };
+        this.schedule_diff(cx);
+        this
+    }
+
+    pub fn mode(&self) -> DiffViewMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: DiffViewMode, cx: &mut ViewContext<Self>) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.update_editors(cx).log_err();
+            if mode == DiffViewMode::Unified && self.old_editor.focus_handle(cx).is_focused(cx) {
+                self.new_editor.focus_handle(cx).focus(cx);
+            }
+        }
+    }
+
+    fn toggle_unified_diff(&mut self, _: &ToggleUnifiedDiff, cx: &mut ViewContext<Self>) {
+        let mode = match self.mode {
+            DiffViewMode::SideBySide => DiffViewMode::Unified,
+            DiffViewMode::Unified => DiffViewMode::SideBySide,
+        };
+        self.set_mode(mode, cx);
+    }
+
+    /// Recomputes the hunks on the background executor, replacing any pending computation.
+    fn schedule_diff(&mut self, cx: &mut ViewContext<Self>) {
+        let old_snapshot = self.old_buffer.read(cx).snapshot();
+        let new_snapshot = self.new_buffer.read(cx).snapshot();
+        let hunks = cx
+            .background_executor()
+            .spawn(async move { compute_hunks(&old_snapshot, &new_snapshot).await });
+        self._diff_task = cx.spawn(|this, mut cx| async move {
+            let hunks = hunks.await;
+            this.update(&mut cx, |this, cx| {
+                this.hunks = hunks;
+                this.update_editors(cx).log_err();
+            })
+            .ok();
+        });
+    }
+
+    /// Highlights the changed rows, and inserts the blocks that keep both sides aligned or,
+    /// in the unified mode, that show the removed text.
+    fn update_editors(&mut self, cx: &mut ViewContext<Self>) -> Result<()> {
+        let old_snapshot = self.old_buffer.read(cx).snapshot();
+        let new_snapshot = self.new_buffer.read(cx).snapshot();
+        let deleted_color = hunk_color(cx.theme().status().git().deleted);
+        let created_color = hunk_color(cx.theme().status().git().created);
+
+        let mut old_highlights = Vec::new();
+        let mut new_highlights = Vec::new();
+        let mut old_blocks = Vec::new();
+        let mut new_blocks = Vec::new();
+        for hunk in &self.hunks {
+            let old_rows = row_range(&hunk.old_range, &old_snapshot);
+            let new_rows = row_range(&hunk.new_range, &new_snapshot);
+            if !old_rows.is_empty() {
+                old_highlights.push(old_rows.clone());
+            }
+            if !new_rows.is_empty() {
+                new_highlights.push(new_rows.clone());
+            }
+
+            match self.mode {
+                DiffViewMode::SideBySide => {
+                    let old_len = old_rows.len() as u32;
+                    let new_len = new_rows.len() as u32;
+                    if new_len > old_len {
+                        old_blocks.push((old_rows.end, new_len - old_len, None));
+                    } else if old_len > new_len {
+                        new_blocks.push((new_rows.end, old_len - new_len, None));
+                    }
+                }
+                DiffViewMode::Unified => {
+                    if !old_rows.is_empty() {
+                        let removed_text = old_snapshot
+                            .text_for_range(hunk.old_range.clone())
+                            .collect::<String>();
+                        new_blocks.push((
+                            new_rows.start,
+                            old_rows.len() as u32,
+                            Some(removed_text),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let old_block_ids = std::mem::take(&mut self.old_blocks);
+        self.old_editor.update(cx, |editor, cx| {
+            editor.remove_blocks(old_block_ids, None, cx);
+            editor.clear_row_highlights::<DiffViewRowHighlight>();
+            highlight_rows::<DiffViewRowHighlight>(editor, old_highlights, deleted_color, cx);
+            self.old_blocks = insert_blocks(editor, old_blocks, deleted_color, cx)?;
+            anyhow::Ok(())
+        })?;
+        let new_block_ids = std::mem::take(&mut self.new_blocks);
+        self.new_editor.update(cx, |editor, cx| {
+            editor.remove_blocks(new_block_ids, None, cx);
+            editor.clear_row_highlights::<DiffViewRowHighlight>();
+            highlight_rows::<DiffViewRowHighlight>(editor, new_highlights, created_color, cx);
+            self.new_blocks = insert_blocks(editor, new_blocks, deleted_color, cx)?;
+            anyhow::Ok(())
+        })?;
+        cx.notify();
+        Ok(())
+    }
+
+    /// Scrolls the other side along with the scrolled one. The alignment blocks make their
+    /// display rows correspond, so the scroll positions can be copied as they are.
+    fn sync_scroll_position(
+        &mut self,
+        event: &EditorEvent,
+        from_old_editor: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.mode != DiffViewMode::SideBySide {
+            return;
+        }
+        if let EditorEvent::ScrollPositionChanged { local: true, .. } = event {
+            let (source, target) = if from_old_editor {
+                (&self.old_editor, &self.new_editor)
+            } else {
+                (&self.new_editor, &self.old_editor)
+            };
+            let position = source.update(cx, |editor, cx| editor.scroll_position(cx));
+            target.update(cx, |editor, cx| {
+                if editor.scroll_position(cx) != position {
+                    editor.set_scroll_position(position, cx);
+                }
+            });
+        }
+    }
+
+    /// Returns the hunks that intersect the newest selection of the focused side.
+    fn selected_hunks(&self, cx: &mut ViewContext<Self>) -> Vec<DiffViewHunk> {
+        let from_old_editor = self.old_editor.focus_handle(cx).contains_focused(cx);
+        let (editor, buffer) = if from_old_editor {
+            (&self.old_editor, &self.old_buffer)
+        } else {
+            (&self.new_editor, &self.new_buffer)
+        };
+        let selection = editor.update(cx, |editor, cx| editor.selections.newest::<Point>(cx));
+        let snapshot = buffer.read(cx).snapshot();
+        self.hunks
+            .iter()
+            .filter(|hunk| {
+                let range = if from_old_editor {
+                    &hunk.old_range
+                } else {
+                    &hunk.new_range
+                };
+                let rows = row_range(range, &snapshot);
+                let selected_rows = selection.start.row..selection.end.row + 1;
+                if rows.is_empty() {
+                    selected_rows.contains(&rows.start)
+                } else {
+                    rows.start < selected_rows.end && selected_rows.start < rows.end
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Replaces the selected hunks on the left side with their text from the right side.
+    fn copy_hunk_to_left(&mut self, _: &CopyHunkToLeft, cx: &mut ViewContext<Self>) {
+        let hunks = self.selected_hunks(cx);
+        let new_snapshot = self.new_buffer.read(cx).snapshot();
+        let edits = hunks
+            .into_iter()
+            .map(|hunk| {
+                let text = new_snapshot
+                    .text_for_range(hunk.new_range)
+                    .collect::<String>();
+                (hunk.old_range, text)
+            })
+            .collect::<Vec<_>>();
+        self.old_buffer
+            .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+    }
+
+    /// Replaces the selected hunks on the right side with their text from the left side.
+    fn copy_hunk_to_right(&mut self, _: &CopyHunkToRight, cx: &mut ViewContext<Self>) {
+        let hunks = self.selected_hunks(cx);
+        let old_snapshot = self.old_buffer.read(cx).snapshot();
+        let edits = hunks
+            .into_iter()
+            .map(|hunk| {
+                let text = old_snapshot
+                    .text_for_range(hunk.old_range)
+                    .collect::<String>();
+                (hunk.new_range, text)
+            })
+            .collect::<Vec<_>>();
+        self.new_buffer
+            .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+    }
+}
+
+async fn compute_hunks(
+    old_snapshot: &BufferSnapshot,
+    new_snapshot: &BufferSnapshot,
+) -> Vec<DiffViewHunk> {
+    let mut diff = BufferDiff::new();
+    diff.update(old_snapshot.as_rope(), new_snapshot).await;
+    diff.hunks_intersecting_range(Anchor::MIN..Anchor::MAX, new_snapshot)
+        .map(|hunk| DiffViewHunk {
+            old_range: old_snapshot.anchor_before(hunk.diff_base_byte_range.start)
+                ..old_snapshot.anchor_before(hunk.diff_base_byte_range.end),
+            new_range: hunk.buffer_range,
+        })
+        .collect()
+}
+
+/// Returns the rows covered by a hunk's line-aligned range, which ends at the start of the
+/// row after its last line, or at the end of the buffer.
+fn row_range(range: &Range<Anchor>, snapshot: &BufferSnapshot) -> Range<u32> {
+    let range = range.to_point(snapshot);
+    let end_row = if range.end.column > 0 {
+        range.end.row + 1
+    } else {
+        range.end.row
+    };
+    range.start.row..end_row.max(range.start.row)
+}
+
+fn hunk_color(mut color: Hsla) -> Hsla {
+    color.fade_out(0.7);
+    color
+}
+
+fn highlight_rows<T: 'static>(
+    editor: &mut Editor,
+    rows: Vec<Range<u32>>,
+    color: Hsla,
+    cx: &mut ViewContext<Editor>,
+) {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    for rows in rows {
+        let start = snapshot.anchor_before(Point::new(rows.start, 0));
+        let end = snapshot.anchor_before(Point::new(rows.end - 1, 0));
+        editor.highlight_rows::<T>(start..=end, Some(color), false, cx);
+    }
+}
+
+/// Inserts blocks of the given heights before the given rows. Blocks with text show it on
+/// a background of the given color, while the others are empty padding.
+///
+/// Fails without inserting anything when a block is taller than a block can be, since the
+/// two sides would no longer line up.
+fn insert_blocks(
+    editor: &mut Editor,
+    blocks: Vec<(u32, u32, Option<String>)>,
+    color: Hsla,
+    cx: &mut ViewContext<Editor>,
+) -> Result<HashSet<BlockId>> {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let max_point = snapshot.max_point();
+    let blocks = blocks
+        .into_iter()
+        .map(|(row, height, text)| {
+            let height = u8::try_from(height)
+                .with_context(|| format!("hunk of {height} rows is too tall to align"))?;
+            anyhow::Ok((row, height, text))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let blocks = blocks.into_iter().map(|(row, height, text)| {
+        // Rows past the end of the buffer are only reachable by placing the block below its
+        // last line.
+        let (position, disposition) = if row > max_point.row {
+            (snapshot.anchor_after(max_point), BlockDisposition::Below)
+        } else {
+            (
+                snapshot.anchor_before(Point::new(row, 0)),
+                BlockDisposition::Above,
+            )
+        };
+        BlockProperties {
+            position,
+            height,
+            style: BlockStyle::Flex,
+            disposition,
+            render: Box::new(move |cx| match &text {
+                Some(text) => div()
+                    .size_full()
+                    .bg(color)
+                    .pl(cx.gutter_dimensions.width + cx.gutter_dimensions.margin)
+                    .font_family(cx.editor_style.text.font_family.clone())
+                    .children(text.lines().map(|line| {
+                        div()
+                            .h(cx.line_height)
+                            .child(SharedString::from(line.to_string()))
+                    }))
+                    .into_any_element(),
+                None => div().size_full().into_any_element(),
+            }),
+        }
+    });
+    Ok(editor.insert_blocks(blocks, None, cx).into_iter().collect())
+}
+
+fn file_name(buffer: &Model<Buffer>, cx: &AppContext) -> String {
+    buffer
+        .read(cx)
+        .file()
+        .map(|file| file.file_name(cx).to_string_lossy().to_string())
+        .unwrap_or_else(|| "untitled".to_string())
+}
+
+impl EventEmitter<ItemEvent> for DiffView {}
+
+impl FocusableView for DiffView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.new_editor.focus_handle(cx)
+    }
+}
+
+impl Item for DiffView {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn tab_content(&self, params: TabContentParams, _cx: &WindowContext) -> AnyElement {
+        Label::new(self.title.clone())
+            .single_line()
+            .color(if params.selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("diff view")
+    }
+
+    fn is_dirty(&self, cx: &AppContext) -> bool {
+        self.new_buffer.read(cx).is_dirty()
+    }
+
+    fn has_conflict(&self, cx: &AppContext) -> bool {
+        self.new_buffer.read(cx).has_conflict()
+    }
+
+    fn can_save(&self, cx: &AppContext) -> bool {
+        self.new_buffer.read(cx).file().is_some()
+    }
+
+    fn save(
+        &mut self,
+        _format: bool,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        project.update(cx, |project, cx| {
+            project.save_buffer(self.new_buffer.clone(), cx)
+        })
+    }
+
+    fn reload(&mut self, project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        let buffers = [self.new_buffer.clone()].into_iter().collect();
+        let reload = project.update(cx, |project, cx| project.reload_buffers(buffers, true, cx));
+        cx.background_executor().spawn(async move {
+            reload.await?;
+            Ok(())
+        })
+    }
+}
+
+impl Render for DiffView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let content = match self.mode {
+            DiffViewMode::SideBySide => h_flex()
+                .size_full()
+                .child(div().flex_1().h_full().child(self.old_editor.clone()))
+                .child(div().w_px().h_full().bg(cx.theme().colors().border_variant))
+                .child(div().flex_1().h_full().child(self.new_editor.clone())),
+            DiffViewMode::Unified => h_flex()
+                .size_full()
+                .child(div().flex_1().h_full().child(self.new_editor.clone())),
+        };
+
+        div()
+            .key_context("DiffView")
+            .on_action(cx.listener(Self::toggle_unified_diff))
+            .on_action(cx.listener(Self::copy_hunk_to_left))
+            .on_action(cx.listener(Self::copy_hunk_to_right))
+            .size_full()
+            .child(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use settings::SettingsStore;
+
+    pub(crate) fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings = SettingsStore::test(cx);
+            cx.set_global(settings);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            release_channel::init("0.0.0", cx);
+            client::init_settings(cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            workspace::init_settings(cx);
+            editor::init(cx);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_diff_view_hunks(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+
+        let old_buffer = cx.new_model(|cx| Buffer::local("one\ntwo\nthree\nfour\n", cx));
+        let new_buffer = cx.new_model(|cx| Buffer::local("one\nTWO\nthree\nfive\nsix\n", cx));
+        let (diff_view, cx) = cx.add_window_view(|cx| {
+            DiffView::new(
+                old_buffer.clone(),
+                new_buffer.clone(),
+                "test".into(),
+                project.clone(),
+                cx,
+            )
+        });
+        cx.run_until_parked();
+
+        let hunk_rows = |diff_view: &View<DiffView>, cx: &mut gpui::VisualTestContext| {
+            diff_view.update(cx, |diff_view, cx| {
+                let old_snapshot = diff_view.old_buffer.read(cx).snapshot();
+                let new_snapshot = diff_view.new_buffer.read(cx).snapshot();
+                diff_view
+                    .hunks
+                    .iter()
+                    .map(|hunk| {
+                        (
+                            row_range(&hunk.old_range, &old_snapshot),
+                            row_range(&hunk.new_range, &new_snapshot),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(hunk_rows(&diff_view, cx), vec![(1..2, 1..2), (3..4, 3..5)]);
+
+        // Editing the right side recomputes the hunks.
+        new_buffer.update(cx, |buffer, cx| buffer.edit([(4..7, "two")], None, cx));
+        cx.run_until_parked();
+        assert_eq!(hunk_rows(&diff_view, cx), vec![(3..4, 3..5)]);
+
+        // Copying a hunk to the right side makes both sides match there.
+        diff_view.update(cx, |diff_view, cx| {
+            diff_view.new_editor.update(cx, |editor, cx| {
+                editor.change_selections(None, cx, |s| {
+                    s.select_ranges([Point::new(3, 0)..Point::new(3, 0)])
+                })
+            });
+            diff_view.new_editor.focus_handle(cx).focus(cx);
+            diff_view.copy_hunk_to_right(&CopyHunkToRight, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            new_buffer.read_with(cx, |buffer, _| buffer.text()),
+            "one\ntwo\nthree\nfour\n"
+        );
+        assert_eq!(hunk_rows(&diff_view, cx), vec![]);
+    }
+}
@@ -0,0 +1,406 @@
+use anyhow::{Context as _, Result};
+use editor::Editor;
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use git::file_history::FileHistoryEntry;
+use gpui::{
+    actions, AppContext, Context, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use language::Buffer;
+use multi_buffer::MultiBuffer;
+use picker::{Picker, PickerDelegate};
+use project::Project;
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+use crate::{file_name, DiffView};
+
+actions!(file_history, [ToggleFileHistory]);
+
+pub(crate) fn init(cx: &mut AppContext) {
+    cx.observe_new_views(FileHistory::register).detach();
+}
+
+/// A picker listing the commits that changed the active buffer's file. Confirming a commit
+/// opens the file as of that commit, and secondary-confirming it compares that revision with
+/// the buffer.
+pub struct FileHistory {
+    picker: View<Picker<FileHistoryDelegate>>,
+}
+
+impl FileHistory {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(|workspace, _: &ToggleFileHistory, cx| {
+            Self::toggle(workspace, cx).detach_and_log_err(cx);
+        });
+    }
+
+    fn toggle(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> Task<Result<()>> {
+        if let Some(file_history) = workspace.active_modal::<Self>(cx) {
+            file_history.update(cx, |_, cx| cx.emit(DismissEvent));
+            return Task::ready(Ok(()));
+        }
+        let buffer = match DiffView::active_buffer(workspace, cx) {
+            Ok(buffer) => buffer,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let project = workspace.project().clone();
+        let history = project.read(cx).file_history(&buffer, cx);
+
+        cx.spawn(|workspace, mut cx| async move {
+            let entries = history.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let workspace_handle = cx.view().downgrade();
+                workspace.toggle_modal(cx, move |cx| {
+                    let delegate = FileHistoryDelegate::new(
+                        cx.view().downgrade(),
+                        workspace_handle,
+                        project,
+                        buffer,
+                        entries,
+                    );
+                    FileHistory {
+                        picker: cx.new_view(|cx| Picker::uniform_list(delegate, cx)),
+                    }
+                });
+            })
+        })
+    }
+}
+
+impl Render for FileHistory {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for FileHistory {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for FileHistory {}
+impl ModalView for FileHistory {}
+
+pub struct FileHistoryDelegate {
+    file_history: WeakView<FileHistory>,
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    buffer: Model<Buffer>,
+    entries: Vec<FileHistoryEntry>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl FileHistoryDelegate {
+    fn new(
+        file_history: WeakView<FileHistory>,
+        workspace: WeakView<Workspace>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        entries: Vec<FileHistoryEntry>,
+    ) -> Self {
+        let candidates = entries
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, entry)| {
+                StringMatchCandidate::new(
+                    candidate_id,
+                    format!(
+                        "{} {} ({})",
+                        entry.sha.display_short(),
+                        entry.summary,
+                        entry.author_name
+                    ),
+                )
+            })
+            .collect();
+
+        Self {
+            file_history,
+            workspace,
+            project,
+            buffer,
+            entries,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        }
+    }
+}
+
+impl PickerDelegate for FileHistoryDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Select a commit...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, secondary: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let entry = self.entries[mat.candidate_id].clone();
+            let buffer = self.buffer.clone();
+            let project = self.project.clone();
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    open_revision(workspace, project, buffer, entry, secondary, cx)
+                        .detach_and_log_err(cx);
+                })
+                .log_err();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.file_history
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let entry = &self.entries[mat.candidate_id];
+        let timestamp = time::OffsetDateTime::from_unix_timestamp(entry.author_time)
+            .map(|timestamp| {
+                time_format::format_localized_timestamp(
+                    timestamp,
+                    time::OffsetDateTime::now_utc(),
+                    cx.local_timezone(),
+                    time_format::TimestampFormat::Relative,
+                )
+            })
+            .unwrap_or_default();
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(
+                    h_flex()
+                        .w_full()
+                        .justify_between()
+                        .child(HighlightedLabel::new(
+                            mat.string.clone(),
+                            mat.positions.clone(),
+                        ))
+                        .child(
+                            Label::new(timestamp)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                ),
+        )
+    }
+}
+
+/// Opens the buffer's file as of the commit in a read-only editor, or in a diff view comparing
+/// it with the buffer if `diff_with_buffer` is true.
+fn open_revision(
+    workspace: &mut Workspace,
+    project: Model<Project>,
+    buffer: Model<Buffer>,
+    entry: FileHistoryEntry,
+    diff_with_buffer: bool,
+    cx: &mut ViewContext<Workspace>,
+) -> Task<Result<()>> {
+    let load_text = project.read(cx).load_text_at_revision(
+        &buffer,
+        entry.sha.to_string(),
+        entry.path.clone(),
+        cx,
+    );
+
+    cx.spawn(|workspace, mut cx| async move {
+        let text = load_text
+            .await?
+            .with_context(|| format!("{:?} not found in {}", entry.path, entry.sha))?;
+        workspace.update(&mut cx, |workspace, cx| {
+            let name = file_name(&buffer, cx);
+            let sha = entry.sha.display_short();
+            if diff_with_buffer {
+                let title = format!("{name} ({sha} ↔ current)").into();
+                DiffView::open(text, buffer, title, project, workspace, cx);
+            } else {
+                let language = buffer.read(cx).language().cloned();
+                let revision_buffer = cx.new_model(|cx| {
+                    let mut revision_buffer = Buffer::local(text, cx);
+                    revision_buffer.set_language(language, cx);
+                    revision_buffer
+                });
+                let multibuffer = cx.new_model(|cx| {
+                    MultiBuffer::singleton(revision_buffer, cx)
+                        .with_title(format!("{name} @ {sha}"))
+                });
+                let editor = cx.new_view(|cx| {
+                    let mut editor = Editor::for_multibuffer(multibuffer, Some(project), true, cx);
+                    editor.set_read_only(true);
+                    editor
+                });
+                workspace.add_item_to_active_pane(Box::new(editor), None, cx);
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init_test;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use std::path::Path;
+
+    #[gpui::test]
+    async fn test_open_revision(cx: &mut TestAppContext) {
+        init_test(cx);
+        let sha = "0c4d2b9a8f7e6d5c4b3a29180f7e6d5c4b3a2918";
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                ".git": {},
+                "src": { "main.rs": "fn main() {}\n" },
+            }),
+        )
+        .await;
+        fs.with_git_state(Path::new("/dir/.git"), true, |state| {
+            state.file_histories.insert(
+                "src/main.rs".into(),
+                vec![FileHistoryEntry {
+                    sha: sha.parse().unwrap(),
+                    author_name: "Jane Doe".into(),
+                    author_email: "jane@example.com".into(),
+                    author_time: 1714000000,
+                    summary: "Add main".into(),
+                    path: "main.rs".into(),
+                }],
+            );
+            state.revision_contents.insert(
+                (sha.into(), "main.rs".into()),
+                "fn main() {\n    todo!()\n}\n".into(),
+            );
+        });
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/dir/src/main.rs", cx)
+            })
+            .await
+            .unwrap();
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        let entries = project
+            .update(cx, |project, cx| project.file_history(&buffer, cx))
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, Path::new("main.rs"));
+
+        workspace
+            .update(cx, |workspace, cx| {
+                open_revision(
+                    workspace,
+                    project.clone(),
+                    buffer.clone(),
+                    entries[0].clone(),
+                    false,
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        let editor = workspace
+            .update(cx, |workspace, cx| workspace.active_item_as::<Editor>(cx))
+            .unwrap();
+        editor.update(cx, |editor, cx| {
+            assert!(editor.read_only(cx));
+            assert_eq!(editor.text(cx), "fn main() {\n    todo!()\n}\n");
+        });
+
+        workspace
+            .update(cx, |workspace, cx| {
+                open_revision(
+                    workspace,
+                    project.clone(),
+                    buffer.clone(),
+                    entries[0].clone(),
+                    true,
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        let diff_view = workspace
+            .update(cx, |workspace, cx| workspace.active_item_as::<DiffView>(cx))
+            .unwrap();
+        diff_view.update(cx, |diff_view, cx| {
+            assert_eq!(
+                diff_view.old_buffer.read(cx).text(),
+                "fn main() {\n    todo!()\n}\n"
+            );
+            assert_eq!(diff_view.new_buffer, buffer);
+        });
+    }
+}
@@ -0,0 +1,627 @@
+use anyhow::{Context as _, Result};
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{
+    actions, div, AnyElement, AppContext, Context, EventEmitter, FocusHandle, FocusableView,
+    IntoElement, Model, ParentElement, Render, SharedString, Styled, Subscription, Task, View,
+    ViewContext, VisualContext, WindowContext,
+};
+use language::{Anchor, Buffer, OffsetRangeExt, ToOffset};
+use project::Project;
+use std::ops::Range;
+use ui::prelude::*;
+use workspace::{
+    item::{Item, ItemEvent, TabContentParams},
+    Workspace,
+};
+
+use crate::{file_name, highlight_rows, hunk_color};
+
+actions!(
+    merge_view,
+    [
+        OpenMergeEditor,
+        AcceptOurs,
+        AcceptTheirs,
+        AcceptBase,
+        AcceptBoth,
+        NextConflict,
+        PreviousConflict
+    ]
+);
+
+pub(crate) fn init(cx: &mut AppContext) {
+    cx.observe_new_views(MergeView::register).detach();
+}
+
+/// The version of a conflicted region that was picked to resolve it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictSide {
+    Ours,
+    Theirs,
+    Base,
+    /// Our version followed by theirs.
+    Both,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Unresolved,
+    Accepted(ConflictSide),
+    /// The conflict markers were edited away by hand.
+    Manual,
+}
+
+/// A region of the output delimited by git's conflict markers, along with the text of each
+/// side that the markers contained.
+struct Conflict {
+    range: Range<Anchor>,
+    ours: String,
+    base: Option<String>,
+    theirs: String,
+    resolution: ConflictResolution,
+}
+
+/// The byte ranges of a conflict and its sections, as found by [`parse_conflicts`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ConflictMarkers {
+    range: Range<usize>,
+    ours: Range<usize>,
+    base: Option<Range<usize>>,
+    theirs: Range<usize>,
+}
+
+enum MergeViewRowHighlight {}
+
+/// A workspace item for resolving the conflicts of a three-way merge. The base, our and their
+/// versions are shown above the output, which is the conflicted file's buffer itself.
+pub struct MergeView {
+    output_buffer: Model<Buffer>,
+    base_editor: View<Editor>,
+    ours_editor: View<Editor>,
+    theirs_editor: View<Editor>,
+    output_editor: View<Editor>,
+    conflicts: Vec<Conflict>,
+    title: SharedString,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl MergeView {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(|workspace, _: &OpenMergeEditor, cx| {
+            Self::open_for_active_buffer(workspace, cx).detach_and_log_err(cx);
+        });
+    }
+
+    /// Opens a merge editor for the active buffer, loading the versions being merged from the
+    /// index of its repository.
+    pub fn open_for_active_buffer(
+        workspace: &mut Workspace,
+        cx: &mut ViewContext<Workspace>,
+    ) -> Task<Result<()>> {
+        let buffer = match crate::DiffView::active_buffer(workspace, cx) {
+            Ok(buffer) => buffer,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let project = workspace.project().clone();
+        let versions = project.read(cx).load_merge_versions(&buffer, cx);
+
+        cx.spawn(|workspace, mut cx| async move {
+            let versions = versions.await?.context("file has no merge conflicts")?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let merge_view = cx.new_view(|cx| {
+                    Self::new(
+                        versions.base.unwrap_or_default(),
+                        versions.ours.unwrap_or_default(),
+                        versions.theirs.unwrap_or_default(),
+                        buffer,
+                        project,
+                        cx,
+                    )
+                });
+                workspace.add_item_to_active_pane(Box::new(merge_view), None, cx);
+            })
+        })
+    }
+
+    pub fn new(
+        base_text: String,
+        ours_text: String,
+        theirs_text: String,
+        output_buffer: Model<Buffer>,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let language = output_buffer.read(cx).language().cloned();
+        let version_editor = |text: String, cx: &mut ViewContext<Self>| {
+            let buffer = cx.new_model(|cx| {
+                let mut buffer = Buffer::local(text, cx);
+                buffer.set_language(language.clone(), cx);
+                buffer
+            });
+            cx.new_view(|cx| {
+                let mut editor = Editor::for_buffer(buffer, Some(project.clone()), cx);
+                editor.set_read_only(true);
+                editor
+            })
+        };
+        let base_editor = version_editor(base_text, cx);
+        let ours_editor = version_editor(ours_text, cx);
+        let theirs_editor = version_editor(theirs_text, cx);
+        let output_editor =
+            cx.new_view(|cx| Editor::for_buffer(output_buffer.clone(), Some(project), cx));
+
+        let output = output_buffer.read(cx).snapshot();
+        let text = output.text();
+        let conflicts = parse_conflicts(&text)
+            .into_iter()
+            .map(|markers| Conflict {
+                range: output.anchor_before(markers.range.start)
+                    ..output.anchor_after(markers.range.end),
+                ours: text[markers.ours].to_string(),
+                base: markers.base.map(|base| text[base].to_string()),
+                theirs: text[markers.theirs].to_string(),
+                resolution: ConflictResolution::Unresolved,
+            })
+            .collect();
+
+        let subscriptions = vec![
+            cx.subscribe(&output_buffer, |this, _, event, cx| match event {
+                language::Event::Edited => this.detect_manual_resolutions(cx),
+                language::Event::DirtyChanged | language::Event::Saved => {
+                    cx.emit(ItemEvent::UpdateTab)
+                }
+                _ => {}
+            }),
+        ];
+
+        let mut this = Self {
+            title: format!("{} (merge)", file_name(&output_buffer, cx)).into(),
+            output_buffer,
+            base_editor,
+            ours_editor,
+            theirs_editor,
+            output_editor,
+            conflicts,
+            _subscriptions: subscriptions,
+        };
+        this.update_highlights(cx);
+        this
+    }
+
+    pub fn resolutions(&self) -> impl '_ + Iterator<Item = ConflictResolution> {
+        self.conflicts.iter().map(|conflict| conflict.resolution)
+    }
+
+    pub fn unresolved_count(&self) -> usize {
+        self.resolutions()
+            .filter(|resolution| *resolution == ConflictResolution::Unresolved)
+            .count()
+    }
+
+    /// Marks the conflicts whose markers were removed by editing the output as resolved.
+    fn detect_manual_resolutions(&mut self, cx: &mut ViewContext<Self>) {
+        let output = self.output_buffer.read(cx).snapshot();
+        let mut changed = false;
+        for conflict in &mut self.conflicts {
+            if conflict.resolution == ConflictResolution::Unresolved {
+                let text = output
+                    .text_for_range(conflict.range.clone())
+                    .collect::<String>();
+                if parse_conflicts(&text).is_empty() {
+                    conflict.resolution = ConflictResolution::Manual;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.update_highlights(cx);
+        }
+    }
+
+    fn update_highlights(&mut self, cx: &mut ViewContext<Self>) {
+        let unresolved_color = hunk_color(cx.theme().status().conflict);
+        let resolved_color = hunk_color(cx.theme().status().created);
+        let output = self.output_buffer.read(cx).snapshot();
+        let (unresolved, resolved): (Vec<_>, Vec<_>) = self
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                let range = conflict.range.to_point(&output);
+                let end_row = if range.end.column > 0 || range.end.row == range.start.row {
+                    range.end.row + 1
+                } else {
+                    range.end.row
+                };
+                (range.start.row..end_row, conflict.resolution)
+            })
+            .partition(|(_, resolution)| *resolution == ConflictResolution::Unresolved);
+
+        self.output_editor.update(cx, |editor, cx| {
+            editor.clear_row_highlights::<MergeViewRowHighlight>();
+            highlight_rows::<MergeViewRowHighlight>(
+                editor,
+                unresolved.into_iter().map(|(rows, _)| rows).collect(),
+                unresolved_color,
+                cx,
+            );
+            highlight_rows::<MergeViewRowHighlight>(
+                editor,
+                resolved.into_iter().map(|(rows, _)| rows).collect(),
+                resolved_color,
+                cx,
+            );
+        });
+        cx.notify();
+    }
+
+    /// Returns the index of the conflict containing the newest cursor in the output.
+    fn conflict_at_cursor(&self, cx: &mut ViewContext<Self>) -> Option<usize> {
+        let cursor = self.output_editor.update(cx, |editor, cx| {
+            editor.selections.newest::<usize>(cx).head()
+        });
+        let output = self.output_buffer.read(cx).snapshot();
+        self.conflicts.iter().position(|conflict| {
+            (conflict.range.start.to_offset(&output)..=conflict.range.end.to_offset(&output))
+                .contains(&cursor)
+        })
+    }
+
+    /// Replaces the conflict under the cursor with the text of the given side.
+    pub fn accept(&mut self, side: ConflictSide, cx: &mut ViewContext<Self>) {
+        let Some(ix) = self.conflict_at_cursor(cx) else {
+            return;
+        };
+        let conflict = &mut self.conflicts[ix];
+        let text = match side {
+            ConflictSide::Ours => conflict.ours.clone(),
+            ConflictSide::Theirs => conflict.theirs.clone(),
+            ConflictSide::Both => format!("{}{}", conflict.ours, conflict.theirs),
+            ConflictSide::Base => match &conflict.base {
+                Some(base) => base.clone(),
+                None => return,
+            },
+        };
+        conflict.resolution = ConflictResolution::Accepted(side);
+        let range = conflict.range.clone();
+        self.output_buffer
+            .update(cx, |buffer, cx| buffer.edit([(range, text)], None, cx));
+        self.update_highlights(cx);
+    }
+
+    fn accept_ours(&mut self, _: &AcceptOurs, cx: &mut ViewContext<Self>) {
+        self.accept(ConflictSide::Ours, cx);
+    }
+
+    fn accept_theirs(&mut self, _: &AcceptTheirs, cx: &mut ViewContext<Self>) {
+        self.accept(ConflictSide::Theirs, cx);
+    }
+
+    fn accept_base(&mut self, _: &AcceptBase, cx: &mut ViewContext<Self>) {
+        self.accept(ConflictSide::Base, cx);
+    }
+
+    fn accept_both(&mut self, _: &AcceptBoth, cx: &mut ViewContext<Self>) {
+        self.accept(ConflictSide::Both, cx);
+    }
+
+    fn next_conflict(&mut self, _: &NextConflict, cx: &mut ViewContext<Self>) {
+        self.select_unresolved_conflict(true, cx);
+    }
+
+    fn previous_conflict(&mut self, _: &PreviousConflict, cx: &mut ViewContext<Self>) {
+        self.select_unresolved_conflict(false, cx);
+    }
+
+    /// Moves the cursor to the start of the closest unresolved conflict in the given direction,
+    /// wrapping around the output.
+    fn select_unresolved_conflict(&mut self, forward: bool, cx: &mut ViewContext<Self>) {
+        let cursor = self.output_editor.update(cx, |editor, cx| {
+            editor.selections.newest::<usize>(cx).head()
+        });
+        let output = self.output_buffer.read(cx).snapshot();
+        let starts = self
+            .conflicts
+            .iter()
+            .filter(|conflict| conflict.resolution == ConflictResolution::Unresolved)
+            .map(|conflict| conflict.range.start.to_offset(&output))
+            .collect::<Vec<_>>();
+        let target = if forward {
+            starts
+                .iter()
+                .find(|start| **start > cursor)
+                .or(starts.first())
+        } else {
+            starts
+                .iter()
+                .rev()
+                .find(|start| **start < cursor)
+                .or(starts.last())
+        };
+        if let Some(&target) = target {
+            self.output_editor.update(cx, |editor, cx| {
+                editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                    s.select_ranges([target..target])
+                });
+            });
+        }
+    }
+}
+
+/// Finds the regions delimited by git's conflict markers, including the optional base section
+/// written by the `diff3` conflict style.
+fn parse_conflicts(text: &str) -> Vec<ConflictMarkers> {
+    let mut conflicts = Vec::new();
+    let mut start = None;
+    let mut ours_start = 0;
+    let mut ours_end = None;
+    let mut base_start = None;
+    let mut base_end = None;
+    let mut theirs_start = None;
+
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let next_line_start = line_start + line.len();
+        if line.starts_with("<<<<<<<") {
+            start = Some(line_start);
+            ours_start = next_line_start;
+            ours_end = None;
+            base_start = None;
+            base_end = None;
+            theirs_start = None;
+        } else if start.is_some() && ours_end.is_none() && line.starts_with("|||||||") {
+            ours_end = Some(line_start);
+            base_start = Some(next_line_start);
+        } else if start.is_some() && theirs_start.is_none() && line.starts_with("=======") {
+            if base_start.is_some() {
+                base_end = Some(line_start);
+            } else {
+                ours_end = Some(line_start);
+            }
+            theirs_start = Some(next_line_start);
+        } else if line.starts_with(">>>>>>>") {
+            if let (Some(start), Some(ours_end), Some(theirs_start)) =
+                (start.take(), ours_end, theirs_start)
+            {
+                conflicts.push(ConflictMarkers {
+                    range: start..next_line_start,
+                    ours: ours_start..ours_end,
+                    base: base_start.zip(base_end).map(|(start, end)| start..end),
+                    theirs: theirs_start..line_start,
+                });
+            }
+        }
+        line_start = next_line_start;
+    }
+    conflicts
+}
+
+impl EventEmitter<ItemEvent> for MergeView {}
+
+impl FocusableView for MergeView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.output_editor.focus_handle(cx)
+    }
+}
+
+impl Item for MergeView {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn tab_content(&self, params: TabContentParams, _cx: &WindowContext) -> AnyElement {
+        Label::new(self.title.clone())
+            .single_line()
+            .color(if params.selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("merge view")
+    }
+
+    fn is_dirty(&self, cx: &AppContext) -> bool {
+        self.output_buffer.read(cx).is_dirty()
+    }
+
+    fn has_conflict(&self, cx: &AppContext) -> bool {
+        self.output_buffer.read(cx).has_conflict()
+    }
+
+    fn can_save(&self, cx: &AppContext) -> bool {
+        self.output_buffer.read(cx).file().is_some()
+    }
+
+    fn save(
+        &mut self,
+        _format: bool,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        project.update(cx, |project, cx| {
+            project.save_buffer(self.output_buffer.clone(), cx)
+        })
+    }
+
+    fn reload(&mut self, project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        let buffers = [self.output_buffer.clone()].into_iter().collect();
+        let reload = project.update(cx, |project, cx| project.reload_buffers(buffers, true, cx));
+        cx.background_executor().spawn(async move {
+            reload.await?;
+            Ok(())
+        })
+    }
+}
+
+impl Render for MergeView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let pane = |label: &'static str, editor: &View<Editor>| {
+            v_flex()
+                .flex_1()
+                .h_full()
+                .child(
+                    div()
+                        .px_2()
+                        .border_b_1()
+                        .border_color(cx.theme().colors().border_variant)
+                        .child(Label::new(label).size(LabelSize::Small).color(Color::Muted)),
+                )
+                .child(div().flex_1().child(editor.clone()))
+        };
+        let resolved_count = self.conflicts.len() - self.unresolved_count();
+
+        v_flex()
+            .key_context("MergeView")
+            .on_action(cx.listener(Self::accept_ours))
+            .on_action(cx.listener(Self::accept_theirs))
+            .on_action(cx.listener(Self::accept_base))
+            .on_action(cx.listener(Self::accept_both))
+            .on_action(cx.listener(Self::next_conflict))
+            .on_action(cx.listener(Self::previous_conflict))
+            .size_full()
+            .child(
+                h_flex()
+                    .h_1_2()
+                    .w_full()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(pane("Ours", &self.ours_editor))
+                    .child(pane("Base", &self.base_editor))
+                    .child(pane("Theirs", &self.theirs_editor)),
+            )
+            .child(
+                v_flex()
+                    .flex_1()
+                    .w_full()
+                    .child(
+                        div().px_2().child(
+                            Label::new(format!(
+                                "Output: {resolved_count} of {} conflicts resolved",
+                                self.conflicts.len()
+                            ))
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                        ),
+                    )
+                    .child(div().flex_1().child(self.output_editor.clone())),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::init_test;
+    use gpui::TestAppContext;
+    use indoc::indoc;
+    use language::Point;
+    use project::FakeFs;
+
+    #[test]
+    fn test_parse_conflicts() {
+        let text = indoc! {"
+            a
+            <<<<<<< HEAD
+            ours
+            ||||||| base
+            base
+            =======
+            theirs
+            >>>>>>> branch
+            b
+            <<<<<<< HEAD
+            =======
+            theirs
+            >>>>>>> branch
+        "};
+        let conflicts = parse_conflicts(text);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(&text[conflicts[0].ours.clone()], "ours\n");
+        assert_eq!(
+            conflicts[0].base.clone().map(|base| &text[base]),
+            Some("base\n")
+        );
+        assert_eq!(&text[conflicts[0].theirs.clone()], "theirs\n");
+        assert_eq!(&text[conflicts[1].ours.clone()], "");
+        assert_eq!(conflicts[1].base, None);
+        assert!(text[conflicts[1].range.clone()].starts_with("<<<<<<< HEAD\n=======\n"));
+        assert!(text[conflicts[1].range.clone()].ends_with(">>>>>>> branch\n"));
+    }
+
+    #[gpui::test]
+    async fn test_resolving_conflicts(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+
+        let output = cx.new_model(|cx| {
+            Buffer::local(
+                indoc! {"
+                    <<<<<<< HEAD
+                    one
+                    =======
+                    1
+                    >>>>>>> branch
+                    middle
+                    <<<<<<< HEAD
+                    two
+                    =======
+                    2
+                    >>>>>>> branch
+                "},
+                cx,
+            )
+        });
+        let (merge_view, cx) = cx.add_window_view(|cx| {
+            MergeView::new(
+                String::new(),
+                "one\nmiddle\ntwo\n".into(),
+                "1\nmiddle\n2\n".into(),
+                output.clone(),
+                project.clone(),
+                cx,
+            )
+        });
+        merge_view.update(cx, |merge_view, _| {
+            assert_eq!(merge_view.unresolved_count(), 2)
+        });
+
+        merge_view.update(cx, |merge_view, cx| {
+            merge_view.accept(ConflictSide::Theirs, cx)
+        });
+        assert_eq!(
+            output.read_with(cx, |buffer, _| buffer.text()),
+            indoc! {"
+                1
+                middle
+                <<<<<<< HEAD
+                two
+                =======
+                2
+                >>>>>>> branch
+            "}
+        );
+
+        // Editing away the markers of the remaining conflict resolves it.
+        output.update(cx, |buffer, cx| {
+            let start = Point::new(2, 0).to_offset(buffer);
+            buffer.edit([(start..buffer.len(), "2\n")], None, cx)
+        });
+        cx.run_until_parked();
+        merge_view.update(cx, |merge_view, _| {
+            assert_eq!(
+                merge_view.resolutions().collect::<Vec<_>>(),
+                vec![
+                    ConflictResolution::Accepted(ConflictSide::Theirs),
+                    ConflictResolution::Manual
+                ]
+            );
+            assert_eq!(merge_view.unresolved_count(), 0);
+        });
+    }
+}
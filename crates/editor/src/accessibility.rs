@@ -0,0 +1,224 @@
+//! A structured description of the editor's content for assistive technologies, such as screen
+//! readers. The UI layer reads the description of the lines around the cursor whenever the
+//! editor changes, and speaks the announcements that the editor emits as
+//! [`EditorEvent::Announce`] when its diagnostics change or its buffer is saved.
+
+use std::ops::Range;
+
+use collections::HashMap;
+use gpui::{AppContext, SharedString, ViewContext};
+use language::{BufferSnapshot, Point};
+use lsp::DiagnosticSeverity;
+use multi_buffer::MultiBufferRow;
+use text::BufferId;
+
+use crate::{Editor, EditorEvent};
+
+/// The content of a range of the editor's lines.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessibleDocument {
+    pub title: SharedString,
+    /// The number of lines in the whole editor, not just in the described range.
+    pub line_count: u32,
+    pub lines: Vec<AccessibleLine>,
+    /// Every selection in the editor, newest last, including the ones outside the described
+    /// range.
+    pub selections: Vec<AccessibleSelection>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessibleLine {
+    pub row: u32,
+    pub text: String,
+    /// The primary diagnostics that start on the line, most severe first.
+    pub diagnostics: Vec<AccessibleDiagnostic>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessibleDiagnostic {
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessibleSelection {
+    pub start: Point,
+    pub end: Point,
+    /// Whether the cursor is at the start of the selection rather than its end.
+    pub reversed: bool,
+    /// The selected text, empty for a cursor.
+    pub text: String,
+}
+
+/// A message that should be spoken without the user moving to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Announcement {
+    pub message: SharedString,
+    pub priority: AnnouncementPriority,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnnouncementPriority {
+    /// Spoken once the screen reader finishes what it's saying.
+    Polite,
+    /// Interrupts what the screen reader is saying.
+    Assertive,
+}
+
+#[derive(Default)]
+pub(crate) struct AccessibilityState {
+    /// The number of errors and warnings that were last announced.
+    announced_diagnostic_counts: (usize, usize),
+    /// The number of errors and warnings in each buffer, along with the buffer's diagnostics
+    /// update count when they were counted.
+    buffer_diagnostic_counts: HashMap<BufferId, (usize, (usize, usize))>,
+}
+
+impl Editor {
+    /// Describes the editor's lines in the given range of rows, clipped to its lines.
+    /// Diagnostics are only described for editors of a single buffer.
+    pub fn accessible_document(&self, rows: Range<u32>, cx: &AppContext) -> AccessibleDocument {
+        let buffer = self.buffer.read(cx);
+        let snapshot = buffer.snapshot(cx);
+        let max_row = snapshot.max_point().row;
+        let rows = rows.start.min(max_row)..rows.end.min(max_row + 1);
+
+        let mut lines = rows
+            .clone()
+            .map(|row| {
+                let line_end = Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+                AccessibleLine {
+                    row,
+                    text: snapshot
+                        .text_for_range(Point::new(row, 0)..line_end)
+                        .collect(),
+                    diagnostics: Vec::new(),
+                }
+            })
+            .collect::<Vec<_>>();
+        let diagnostics = snapshot.diagnostics_in_range::<_, Point>(
+            Point::new(rows.start, 0)..Point::new(rows.end, 0),
+            false,
+        );
+        for entry in diagnostics {
+            if !entry.diagnostic.is_primary {
+                continue;
+            }
+            let Some(line_ix) = entry.range.start.row.checked_sub(rows.start) else {
+                continue;
+            };
+            if let Some(line) = lines.get_mut(line_ix as usize) {
+                line.diagnostics.push(AccessibleDiagnostic {
+                    severity: entry.diagnostic.severity,
+                    message: entry.diagnostic.message,
+                });
+            }
+        }
+        for line in &mut lines {
+            line.diagnostics
+                .sort_by_key(|diagnostic| diagnostic.severity);
+        }
+
+        let selections = self
+            .selections
+            .all::<Point>(cx)
+            .into_iter()
+            .map(|selection| AccessibleSelection {
+                text: snapshot
+                    .text_for_range(selection.start..selection.end)
+                    .collect(),
+                start: selection.start,
+                end: selection.end,
+                reversed: selection.reversed,
+            })
+            .collect();
+
+        AccessibleDocument {
+            title: buffer.title(cx).to_string().into(),
+            line_count: max_row + 1,
+            lines,
+            selections,
+        }
+    }
+
+    /// Describes the lines around the newest cursor, as many above and below it as are given.
+    pub fn accessible_document_around_cursor(
+        &self,
+        context_lines: u32,
+        cx: &AppContext,
+    ) -> AccessibleDocument {
+        let row = self.selections.newest::<Point>(cx).head().row;
+        self.accessible_document(
+            row.saturating_sub(context_lines)..row.saturating_add(context_lines + 1),
+            cx,
+        )
+    }
+}
+
+pub(crate) fn announce_save(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let title = editor.buffer.read(cx).title(cx).to_string();
+    cx.emit(EditorEvent::Announce(Announcement {
+        message: format!("Saved {title}").into(),
+        priority: AnnouncementPriority::Polite,
+    }));
+}
+
+/// Announces the number of errors and warnings in the editor's buffers, when it changed since
+/// it was last announced. New errors interrupt the screen reader.
+pub(crate) fn announce_diagnostics(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let state = &mut editor.accessibility_state;
+    let mut buffer_diagnostic_counts = HashMap::default();
+    let mut counts = (0, 0);
+    for buffer in editor.buffer.read(cx).all_buffers() {
+        let buffer = buffer.read(cx);
+        let update_count = buffer.diagnostics_update_count();
+        let buffer_counts = match state.buffer_diagnostic_counts.get(&buffer.remote_id()) {
+            Some((counted_update_count, buffer_counts))
+                if *counted_update_count == update_count =>
+            {
+                *buffer_counts
+            }
+            _ => count_diagnostics(&buffer.snapshot()),
+        };
+        buffer_diagnostic_counts.insert(buffer.remote_id(), (update_count, buffer_counts));
+        counts.0 += buffer_counts.0;
+        counts.1 += buffer_counts.1;
+    }
+    state.buffer_diagnostic_counts = buffer_diagnostic_counts;
+
+    if counts == state.announced_diagnostic_counts {
+        return;
+    }
+    let priority = if counts.0 > state.announced_diagnostic_counts.0 {
+        AnnouncementPriority::Assertive
+    } else {
+        AnnouncementPriority::Polite
+    };
+    state.announced_diagnostic_counts = counts;
+
+    let message = match counts {
+        (0, 0) => "No problems".to_string(),
+        (errors, warnings) => format!(
+            "{errors} {}, {warnings} {}",
+            if errors == 1 { "error" } else { "errors" },
+            if warnings == 1 { "warning" } else { "warnings" },
+        ),
+    };
+    cx.emit(EditorEvent::Announce(Announcement {
+        message: message.into(),
+        priority,
+    }));
+}
+
+/// Counts the errors and warnings among the primary diagnostics of the buffer.
+fn count_diagnostics(snapshot: &BufferSnapshot) -> (usize, usize) {
+    let mut counts = (0, 0);
+    for (_, group) in snapshot.diagnostic_groups(None) {
+        match group.entries[group.primary_ix].diagnostic.severity {
+            DiagnosticSeverity::ERROR => counts.0 += 1,
+            DiagnosticSeverity::WARNING => counts.1 += 1,
+            _ => {}
+        }
+    }
+    counts
+}
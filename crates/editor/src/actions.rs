@@ -126,15 +126,55 @@ pub struct ExpandExcerptsDown {
     pub(super) lines: u32,
 }
 
+#[derive(PartialEq, Clone, Deserialize)]
+pub struct AlignLines {
+    #[serde(default = "default_alignment_delimiter")]
+    pub delimiter: char,
+}
+
+impl Default for AlignLines {
+    fn default() -> Self {
+        Self {
+            delimiter: default_alignment_delimiter(),
+        }
+    }
+}
+
+fn default_alignment_delimiter() -> char {
+    '='
+}
+
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct SetMark {
+    /// The name of the mark. When omitted, the mark is named after the lowest unused number.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct GoToMark {
+    pub name: String,
+}
+
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct StartDebugging {
+    /// The label of the debug configuration to start. When omitted, the first configuration of
+    /// the project's `.zed/debug.json` files is started.
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
 impl_actions!(
     editor,
     [
+        AlignLines,
         ConfirmCodeAction,
         ConfirmCompletion,
         ExpandExcerpts,
         ExpandExcerptsUp,
         ExpandExcerptsDown,
         FoldAt,
+        GoToMark,
         MoveDownByLines,
         MovePageDown,
         MovePageUp,
@@ -147,6 +187,8 @@ impl_actions!(
         SelectToBeginningOfLine,
         SelectToEndOfLine,
         SelectUpByLines,
+        SetMark,
+        StartDebugging,
         ToggleCodeActions,
         ToggleComments,
         UnfoldAt,
@@ -164,10 +206,12 @@ gpui::actions!(
         Backspace,
         Cancel,
         ConfirmRename,
+        ContinueDebugging,
         ContextMenuFirst,
         ContextMenuLast,
         ContextMenuNext,
         ContextMenuPrev,
+        ConvertColorFormat,
         ConvertToKebabCase,
         ConvertToLowerCamelCase,
         ConvertToLowerCase,
@@ -196,6 +240,8 @@ gpui::actions!(
         DuplicateLineUp,
         ExpandAllHunkDiffs,
         ExpandMacroRecursively,
+        ExportToHtml,
+        ExportToPdf,
         FindAllReferences,
         Fold,
         FoldSelectedRanges,
@@ -206,6 +252,7 @@ gpui::actions!(
         GoToHunk,
         GoToImplementation,
         GoToImplementationSplit,
+        GoToLastEdit,
         GoToPrevDiagnostic,
         GoToPrevHunk,
         GoToTypeDefinition,
@@ -245,10 +292,14 @@ gpui::actions!(
         PageDown,
         PageUp,
         Paste,
+        PasteAndMatchIndentation,
+        PasteBelow,
+        PauseDebugging,
         PreviousInlineCompletion,
         Redo,
         RedoSelection,
         Rename,
+        RenameTextually,
         RestartLanguageServer,
         RevealInFinder,
         ReverseLines,
@@ -259,10 +310,13 @@ gpui::actions!(
         SelectAll,
         SelectAllMatches,
         SelectDown,
+        SelectHighlightedOccurrences,
+        SelectLargerBracketScope,
         SelectLargerSyntaxNode,
         SelectLeft,
         SelectLine,
         SelectRight,
+        SelectSmallerBracketScope,
         SelectSmallerSyntaxNode,
         SelectToBeginning,
         SelectToEnd,
@@ -279,9 +333,18 @@ gpui::actions!(
         ShuffleLines,
         SortLinesCaseInsensitive,
         SortLinesCaseSensitive,
+        SortLinesNaturally,
+        SortLinesNumerically,
         SplitSelectionIntoLines,
+        StageFile,
+        StageHunk,
+        StepInto,
+        StepOut,
+        StepOver,
+        StopDebugging,
         Tab,
         TabPrev,
+        ToggleBreakpoint,
         ToggleGitBlame,
         ToggleGitBlameInline,
         ToggleHunkDiff,
@@ -295,5 +358,7 @@ gpui::actions!(
         UnfoldLines,
         UniqueLinesCaseInsensitive,
         UniqueLinesCaseSensitive,
+        UnstageFile,
+        UnstageHunk,
     ]
 );
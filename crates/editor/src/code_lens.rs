@@ -0,0 +1,296 @@
+//! Code lenses are commands that language servers show above ranges of a buffer, such as
+//! running the test defined there or listing the references to a function. Each line's lenses
+//! are shown in a block above it, anchored so that they move with the text as it's edited, and
+//! they're requested again once the edits settle.
+
+use std::collections::BTreeMap;
+
+use collections::HashSet;
+use futures::future;
+use gpui::{div, AnyElement, AppContext, Model, Task, ViewContext, WeakView};
+use language::{Buffer, Point, ToPoint as _};
+use multi_buffer::{Anchor, MultiBuffer};
+use project::CodeLens;
+use settings::Settings;
+use text::BufferId;
+use ui::{h_flex, prelude::*, Label, LabelSize};
+use util::ResultExt;
+
+use crate::{
+    actions::{FindAllReferences, ToggleCodeActions},
+    scroll::Autoscroll,
+    BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle, Editor, EditorMode,
+    EditorSettings, ToDisplayPoint, CODE_LENS_DEBOUNCE_TIMEOUT,
+};
+
+#[derive(Default)]
+pub struct CodeLensState {
+    enabled: bool,
+    lenses: Vec<(Model<Buffer>, CodeLens)>,
+    block_ids: HashSet<BlockId>,
+    task: Option<Task<()>>,
+}
+
+impl CodeLensState {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The code lenses shown in the editor, along with the buffers they belong to.
+    pub fn lenses(&self) -> impl Iterator<Item = &(Model<Buffer>, CodeLens)> {
+        self.lenses.iter()
+    }
+}
+
+/// What clicking a code lens does, depending on its command. Some of the commands that
+/// servers put in code lenses are meant to be implemented by the editor rather than executed
+/// by the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CodeLensAction {
+    /// Runs the task that the runnables of the language define for the lens's range.
+    RunTask,
+    /// Finds the references to the symbol at the start of the lens's range.
+    ShowReferences,
+    /// Asks the language server to execute the command.
+    Execute,
+}
+
+impl CodeLensAction {
+    fn for_command(command: &str) -> Self {
+        match command {
+            "rust-analyzer.runSingle" | "rust-analyzer.debugSingle" => Self::RunTask,
+            "rust-analyzer.showReferences" | "editor.action.showReferences" => Self::ShowReferences,
+            _ => Self::Execute,
+        }
+    }
+}
+
+/// Requests the code lenses of every buffer in the editor after a delay, replacing the ones
+/// that are shown, or hides them if they're disabled.
+pub(crate) fn refresh_code_lenses(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    editor.code_lens_state.enabled = EditorSettings::get_global(cx).code_lens;
+    let Some(project) = editor
+        .project
+        .clone()
+        .filter(|_| editor.mode == EditorMode::Full && editor.code_lens_state.enabled)
+    else {
+        editor.code_lens_state.task = None;
+        if !editor.code_lens_state.block_ids.is_empty() {
+            show_code_lenses(editor, Vec::new(), cx);
+        }
+        return;
+    };
+
+    let buffers = editor.buffer.read(cx).all_buffers();
+    editor.code_lens_state.task = Some(cx.spawn(|editor, mut cx| async move {
+        cx.background_executor()
+            .timer(CODE_LENS_DEBOUNCE_TIMEOUT)
+            .await;
+
+        let Some(requests) = project
+            .update(&mut cx, |project, cx| {
+                buffers
+                    .into_iter()
+                    .map(|buffer| {
+                        let lenses = project.code_lens(&buffer, cx);
+                        async move { (buffer, lenses.await.log_err().unwrap_or_default()) }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .log_err()
+        else {
+            return;
+        };
+        let lenses = future::join_all(requests)
+            .await
+            .into_iter()
+            .flat_map(|(buffer, lenses)| {
+                lenses
+                    .into_iter()
+                    .filter(|lens| lens.command().is_some())
+                    .map(move |lens| (buffer.clone(), lens))
+            })
+            .collect::<Vec<_>>();
+
+        editor
+            .update(&mut cx, |editor, cx| show_code_lenses(editor, lenses, cx))
+            .ok();
+    }));
+}
+
+fn show_code_lenses(
+    editor: &mut Editor,
+    lenses: Vec<(Model<Buffer>, CodeLens)>,
+    cx: &mut ViewContext<Editor>,
+) {
+    let old_block_ids = std::mem::take(&mut editor.code_lens_state.block_ids);
+    if !old_block_ids.is_empty() {
+        editor.remove_blocks(old_block_ids, None, cx);
+    }
+
+    let mut lenses_by_line = BTreeMap::<(BufferId, u32), (Model<Buffer>, Vec<CodeLens>)>::new();
+    for (buffer, lens) in &lenses {
+        let row = lens.range.start.to_point(buffer.read(cx)).row;
+        lenses_by_line
+            .entry((buffer.read(cx).remote_id(), row))
+            .or_insert_with(|| (buffer.clone(), Vec::new()))
+            .1
+            .push(lens.clone());
+    }
+
+    let editor_handle = cx.view().downgrade();
+    let blocks = lenses_by_line
+        .into_values()
+        .filter_map(|(buffer, lenses)| {
+            // Align the lenses with the start of the line's text.
+            let buffer_snapshot = buffer.read(cx).snapshot();
+            let row = lenses[0].range.start.to_point(&buffer_snapshot).row;
+            let indent = buffer_snapshot.indent_size_for_line(row).len;
+            let text_anchor = buffer_snapshot.anchor_before(Point::new(row, indent));
+            let position = multi_buffer_anchor(editor.buffer.read(cx), &buffer, text_anchor, cx)?;
+            Some(BlockProperties {
+                position,
+                height: 1,
+                style: BlockStyle::Flex,
+                render: render_code_lenses(editor_handle.clone(), buffer, lenses),
+                disposition: BlockDisposition::Above,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    editor.code_lens_state.block_ids = editor.insert_blocks(blocks, None, cx).into_iter().collect();
+    editor.code_lens_state.lenses = lenses;
+    cx.notify();
+}
+
+fn render_code_lenses(
+    editor: WeakView<Editor>,
+    buffer: Model<Buffer>,
+    lenses: Vec<CodeLens>,
+) -> Box<dyn Send + Fn(&mut BlockContext) -> AnyElement> {
+    Box::new(move |cx: &mut BlockContext| {
+        h_flex()
+            .pl(cx.anchor_x)
+            .gap_2()
+            .children(lenses.iter().enumerate().map(|(ix, lens)| {
+                let title = lens
+                    .command()
+                    .map(|command| command.title.clone())
+                    .unwrap_or_default();
+                let editor = editor.clone();
+                let buffer = buffer.clone();
+                let lens = lens.clone();
+                div()
+                    .id(("code-lens", ix))
+                    .cursor_pointer()
+                    .child(Label::new(title).size(LabelSize::Small).color(Color::Muted))
+                    .on_click(move |_, cx| {
+                        editor
+                            .update(cx, |editor, cx| {
+                                run_code_lens(editor, buffer.clone(), lens.clone(), cx)
+                            })
+                            .ok();
+                    })
+            }))
+            .into_any_element()
+    })
+}
+
+/// Runs the command of the code lens, either in the editor or on the language server that
+/// produced it.
+pub fn run_code_lens(
+    editor: &mut Editor,
+    buffer: Model<Buffer>,
+    lens: CodeLens,
+    cx: &mut ViewContext<Editor>,
+) {
+    let Some(command) = lens.command() else {
+        return;
+    };
+    match CodeLensAction::for_command(&command.command) {
+        CodeLensAction::RunTask => {
+            if run_task_for_lens(editor, &buffer, &lens, cx).is_some() {
+                return;
+            }
+        }
+        CodeLensAction::ShowReferences => {
+            let Some(position) =
+                multi_buffer_anchor(editor.buffer.read(cx), &buffer, lens.range.start, cx)
+            else {
+                return;
+            };
+            editor.change_selections(Some(Autoscroll::fit()), cx, |selections| {
+                selections.select_anchor_ranges([position..position])
+            });
+            if let Some(task) = editor.find_all_references(&FindAllReferences, cx) {
+                task.detach_and_log_err(cx);
+            }
+            return;
+        }
+        CodeLensAction::Execute => {}
+    }
+
+    let (Some(project), Some(workspace)) = (editor.project.clone(), editor.workspace()) else {
+        return;
+    };
+    let title = command.title.clone();
+    let execute = project.update(cx, |project, cx| {
+        project.execute_code_lens(buffer, lens, cx)
+    });
+    let workspace = workspace.downgrade();
+    cx.spawn(|editor, cx| async move {
+        let project_transaction = execute.await?;
+        Editor::open_project_transaction(&editor, workspace, project_transaction, title, cx).await
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Runs the task defined on the first line of the lens's range that has one, the same way as
+/// clicking the line's run indicator does.
+fn run_task_for_lens(
+    editor: &mut Editor,
+    buffer: &Model<Buffer>,
+    lens: &CodeLens,
+    cx: &mut ViewContext<Editor>,
+) -> Option<()> {
+    let buffer_snapshot = buffer.read(cx).snapshot();
+    let buffer_id = buffer_snapshot.remote_id();
+    let range =
+        lens.range.start.to_point(&buffer_snapshot)..lens.range.end.to_point(&buffer_snapshot);
+    let row = (range.start.row..=range.end.row)
+        .find(|row| editor.tasks.contains_key(&(buffer_id, *row)))?;
+    let text_anchor = buffer_snapshot.anchor_before(Point::new(row, 0));
+    let position = multi_buffer_anchor(editor.buffer.read(cx), buffer, text_anchor, cx)?;
+    let display_row = position.to_display_point(&editor.snapshot(cx)).row();
+    editor.toggle_code_actions(
+        &ToggleCodeActions {
+            deployed_from_indicator: Some(display_row),
+        },
+        cx,
+    );
+    Some(())
+}
+
+/// Converts a position in one of the editor's buffers into a position in its multibuffer,
+/// if it's within one of the buffer's excerpts.
+fn multi_buffer_anchor(
+    multi_buffer: &MultiBuffer,
+    buffer: &Model<Buffer>,
+    text_anchor: text::Anchor,
+    cx: &AppContext,
+) -> Option<Anchor> {
+    let buffer_snapshot = buffer.read(cx);
+    let snapshot = multi_buffer.snapshot(cx);
+    multi_buffer
+        .excerpts_for_buffer(buffer, cx)
+        .into_iter()
+        .find(|(_, range)| {
+            range
+                .context
+                .start
+                .cmp(&text_anchor, buffer_snapshot)
+                .is_le()
+                && range.context.end.cmp(&text_anchor, buffer_snapshot).is_ge()
+        })
+        .and_then(|(excerpt_id, _)| snapshot.anchor_in_excerpt(excerpt_id, text_anchor))
+}
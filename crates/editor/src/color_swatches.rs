@@ -0,0 +1,180 @@
+//! Color swatches are painted under the colors written in buffers, such as `#ff8800`, in the
+//! color they describe. The colors are requested again once edits settle, and the color under
+//! each cursor can be rewritten in another format.
+
+use std::ops::Range;
+
+use futures::future;
+use gpui::{Hsla, Rgba, Task, ViewContext};
+use multi_buffer::Anchor;
+use project::document_colors::{format_color, parse_color, ColorFormat};
+use settings::Settings;
+use util::ResultExt;
+
+use crate::{
+    actions::ConvertColorFormat, display_map::DisplaySnapshot, DisplayPoint, Editor, EditorMode,
+    EditorSettings, ToDisplayPoint, COLOR_SWATCHES_DEBOUNCE_TIMEOUT,
+};
+
+#[derive(Default)]
+pub struct ColorSwatchState {
+    enabled: bool,
+    colors: Vec<(Range<Anchor>, Rgba)>,
+    task: Option<Task<()>>,
+}
+
+impl ColorSwatchState {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Requests the colors of every buffer in the editor after a delay, replacing the swatches
+/// that are shown, or hides them if they're disabled.
+pub(crate) fn refresh_color_swatches(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    editor.color_swatch_state.enabled = EditorSettings::get_global(cx).color_swatches;
+    let Some(project) = editor
+        .project
+        .clone()
+        .filter(|_| editor.mode == EditorMode::Full && editor.color_swatch_state.enabled)
+    else {
+        editor.color_swatch_state.task = None;
+        if !editor.color_swatch_state.colors.is_empty() {
+            editor.color_swatch_state.colors.clear();
+            cx.notify();
+        }
+        return;
+    };
+
+    let buffers = editor.buffer.read(cx).all_buffers();
+    editor.color_swatch_state.task = Some(cx.spawn(|editor, mut cx| async move {
+        cx.background_executor()
+            .timer(COLOR_SWATCHES_DEBOUNCE_TIMEOUT)
+            .await;
+
+        let Some(requests) = project
+            .update(&mut cx, |project, cx| {
+                buffers
+                    .into_iter()
+                    .map(|buffer| {
+                        let colors = project.document_colors(&buffer, cx);
+                        async move { (buffer, colors.await.log_err().unwrap_or_default()) }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .log_err()
+        else {
+            return;
+        };
+        let colors_by_buffer = future::join_all(requests).await;
+
+        editor
+            .update(&mut cx, |editor, cx| {
+                let multi_buffer = editor.buffer.read(cx);
+                let snapshot = multi_buffer.snapshot(cx);
+                let mut colors = Vec::new();
+                for (buffer, buffer_colors) in colors_by_buffer {
+                    let buffer_snapshot = buffer.read(cx);
+                    let excerpts = multi_buffer.excerpts_for_buffer(&buffer, cx);
+                    for color in buffer_colors {
+                        let excerpt_id = excerpts.iter().find_map(|(excerpt_id, range)| {
+                            let contains_color = range
+                                .context
+                                .start
+                                .cmp(&color.range.start, buffer_snapshot)
+                                .is_le()
+                                && range
+                                    .context
+                                    .end
+                                    .cmp(&color.range.end, buffer_snapshot)
+                                    .is_ge();
+                            contains_color.then_some(*excerpt_id)
+                        });
+                        let range = excerpt_id.and_then(|excerpt_id| {
+                            let start =
+                                snapshot.anchor_in_excerpt(excerpt_id, color.range.start)?;
+                            let end = snapshot.anchor_in_excerpt(excerpt_id, color.range.end)?;
+                            Some(start..end)
+                        });
+                        if let Some(range) = range {
+                            colors.push((range, color.color));
+                        }
+                    }
+                }
+                colors.sort_by(|(a, _), (b, _)| a.start.cmp(&b.start, &snapshot));
+                editor.color_swatch_state.colors = colors;
+                cx.notify();
+            })
+            .ok();
+    }));
+}
+
+impl Editor {
+    /// The colors written in the editor's buffers, with their ranges in the editor, in the
+    /// order they appear.
+    pub fn color_swatches(&self) -> &[(Range<Anchor>, Rgba)] {
+        &self.color_swatch_state.colors
+    }
+
+    /// The swatches of the colors that overlap the range, for painting them.
+    pub(crate) fn color_swatches_in_range(
+        &self,
+        search_range: Range<Anchor>,
+        display_snapshot: &DisplaySnapshot,
+    ) -> Vec<(Range<DisplayPoint>, Hsla)> {
+        let buffer = &display_snapshot.buffer_snapshot;
+        self.color_swatch_state
+            .colors
+            .iter()
+            .filter(|(range, _)| {
+                range.end.cmp(&search_range.start, buffer).is_ge()
+                    && range.start.cmp(&search_range.end, buffer).is_le()
+            })
+            .map(|(range, color)| {
+                let start = range.start.to_display_point(display_snapshot);
+                let end = range.end.to_display_point(display_snapshot);
+                (start..end, Hsla::from(*color))
+            })
+            .collect()
+    }
+
+    /// Rewrites the colors under the cursors in the next format, from hex to `rgb()` to
+    /// `hsl()` and back. Selected text that's a color is converted as well.
+    pub fn convert_color_format(&mut self, _: &ConvertColorFormat, cx: &mut ViewContext<Self>) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let selections = self.selections.all_adjusted(cx);
+        let mut edits = Vec::new();
+        for selection in &selections {
+            let selection_range =
+                snapshot.anchor_before(selection.start)..snapshot.anchor_after(selection.end);
+            let swatch = self.color_swatch_state.colors.iter().find(|(range, _)| {
+                range.start.cmp(&selection_range.start, &snapshot).is_le()
+                    && range.end.cmp(&selection_range.end, &snapshot).is_ge()
+            });
+            let (range, color) = match swatch {
+                Some((range, color)) => (range.clone(), Some(*color)),
+                None => (selection_range, None),
+            };
+            let text = snapshot.text_for_range(range.clone()).collect::<String>();
+            let parsed = parse_color(&text);
+            let Some(color) = color.or(parsed.map(|(color, _)| color)) else {
+                continue;
+            };
+            // Colors that aren't written in one of the formats, such as `red`, become hex.
+            let format = parsed.map_or(ColorFormat::Hex, |(_, format)| format.next());
+            let new_text = format_color(color, format);
+            if new_text != text && !edits.iter().any(|(edited, _)| edited == &range) {
+                edits.push((range, new_text));
+            }
+        }
+        if edits.is_empty() {
+            return;
+        }
+
+        self.transact(cx, |editor, cx| {
+            editor
+                .buffer
+                .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+        });
+    }
+}
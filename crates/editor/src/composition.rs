@@ -0,0 +1,74 @@
+//! Composition of text with an input method, such as the IME of a CJK language or a dead key.
+//! The composed text is marked until the input method commits it. Until then, its edits are
+//! held back from collaborators and undoing cancels the composition instead of undoing earlier
+//! edits. Once committed, the whole composition is undone as a single transaction.
+
+use std::ops::Range;
+
+use gpui::{AppContext, ViewContext, ViewInputHandler};
+
+use crate::{Editor, InputComposition};
+
+impl Editor {
+    /// Whether the editor has marked text that an input method is composing.
+    pub fn is_composing(&self, cx: &AppContext) -> bool {
+        self.text_highlights::<InputComposition>(cx).is_some()
+    }
+
+    /// Replaces the marked text, or the selections when there's none, with the given text and
+    /// marks it. The selected range is in UTF-16 code units from the start of the marked text.
+    pub fn set_marked_text(
+        &mut self,
+        text: &str,
+        selected_range_utf16: Option<Range<usize>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.replace_and_mark_text_in_range(None, text, selected_range_utf16, cx);
+    }
+
+    /// Replaces a range of the marked text, in UTF-16 code units from its start, and marks the
+    /// result. Does nothing when no text is marked.
+    pub fn replace_marked_range(
+        &mut self,
+        range_utf16: Range<usize>,
+        text: &str,
+        selected_range_utf16: Option<Range<usize>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.is_composing(cx) {
+            self.replace_and_mark_text_in_range(Some(range_utf16), text, selected_range_utf16, cx);
+        }
+    }
+
+    /// Replaces the marked text with the given text, and ends the composition.
+    pub fn commit_marked_text(&mut self, text: &str, cx: &mut ViewContext<Self>) {
+        self.replace_text_in_range(None, text, cx);
+    }
+
+    /// Removes the marked text and ends the composition, leaving nothing to undo.
+    pub fn cancel_marked_text(&mut self, cx: &mut ViewContext<Self>) {
+        self.clear_highlights::<InputComposition>(cx);
+        let transaction_id = self.ime_transaction.take();
+        self.buffer.update(cx, |buffer, cx| {
+            if let Some(transaction_id) = transaction_id {
+                buffer.undo_transaction(transaction_id, cx);
+                buffer.forget_transaction(transaction_id, cx);
+            }
+            buffer.release_held_operations(cx);
+        });
+    }
+
+    pub(crate) fn start_composition(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.is_composing(cx) {
+            self.buffer
+                .update(cx, |buffer, cx| buffer.hold_operations(cx));
+        }
+    }
+
+    pub(crate) fn end_composition(&mut self, cx: &mut ViewContext<Self>) {
+        self.clear_highlights::<InputComposition>(cx);
+        self.ime_transaction.take();
+        self.buffer
+            .update(cx, |buffer, cx| buffer.release_held_operations(cx));
+    }
+}
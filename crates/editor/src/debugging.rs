@@ -0,0 +1,219 @@
+//! Breakpoints and the actions that drive the project's debug sessions. Breakpoints belong to
+//! the project, so that every editor of a buffer shares them; editors highlight the rows that
+//! have breakpoints and the line where the debuggee is stopped.
+
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use gpui::{Model, ModelContext, Task, ViewContext};
+use language::{Point, ToPoint as _};
+use project::{DebugSession, DebugSessionEvent};
+use text::Bias;
+use theme::ActiveTheme as _;
+use workspace::{notifications::DetachAndPromptErr, Workspace};
+
+use crate::{
+    actions::{
+        ContinueDebugging, PauseDebugging, StartDebugging, StepInto, StepOut, StepOver,
+        StopDebugging, ToggleBreakpoint,
+    },
+    scroll::Autoscroll,
+    Editor,
+};
+
+enum BreakpointRowHighlight {}
+enum StoppedRowHighlight {}
+
+impl Editor {
+    pub fn toggle_breakpoint(&mut self, _: &ToggleBreakpoint, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let head = self.selections.newest_anchor().head();
+        let Some((buffer, position)) = self.buffer.read(cx).text_anchor_for_position(head, cx)
+        else {
+            return;
+        };
+        let row = position.to_point(buffer.read(cx)).row;
+        project.update(cx, |project, cx| {
+            project.toggle_breakpoint(&buffer, row, cx)
+        });
+    }
+}
+
+/// Highlights the rows of the editor's buffers that have breakpoints.
+pub(crate) fn refresh_breakpoint_highlights(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    editor.clear_row_highlights::<BreakpointRowHighlight>();
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+
+    let color = cx.theme().status().error_background;
+    let multi_buffer = editor.buffer.read(cx);
+    let snapshot = multi_buffer.snapshot(cx);
+    let mut breakpoints = Vec::new();
+    for buffer in multi_buffer.all_buffers() {
+        let rows = project.read(cx).breakpoint_rows(buffer.read(cx));
+        if rows.is_empty() {
+            continue;
+        }
+        let buffer_snapshot = buffer.read(cx).snapshot();
+        for (excerpt_id, range) in multi_buffer.excerpts_for_buffer(&buffer, cx) {
+            let excerpt_rows = range.context.start.to_point(&buffer_snapshot).row
+                ..=range.context.end.to_point(&buffer_snapshot).row;
+            for row in rows.iter().filter(|row| excerpt_rows.contains(row)) {
+                let position = buffer_snapshot.anchor_before(Point::new(*row, 0));
+                breakpoints.extend(snapshot.anchor_in_excerpt(excerpt_id, position));
+            }
+        }
+    }
+    for breakpoint in breakpoints {
+        editor.highlight_rows::<BreakpointRowHighlight>(
+            breakpoint..=breakpoint,
+            Some(color),
+            false,
+            cx,
+        );
+    }
+    cx.notify();
+}
+
+pub fn start_debugging(
+    workspace: &mut Workspace,
+    action: &StartDebugging,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let project = workspace.project().clone();
+    let configs = project.read(cx).debug_configs(cx);
+    let label = action.label.clone();
+    cx.spawn(|workspace, mut cx| async move {
+        let mut configs = configs.await.into_iter();
+        let (worktree_id, config) = match &label {
+            Some(label) => configs
+                .find(|(_, config)| &config.label == label)
+                .with_context(|| format!("no debug configuration is labeled {label:?}"))?,
+            None => configs
+                .next()
+                .context("no debug configurations were found in .zed/debug.json")?,
+        };
+        let session = project
+            .update(&mut cx, |project, cx| {
+                project.start_debug_session(worktree_id, config, cx)
+            })?
+            .await?;
+        workspace.update(&mut cx, |_, cx| {
+            cx.subscribe(&session, |workspace, session, event, cx| match event {
+                DebugSessionEvent::Stopped => reveal_stopped_location(workspace, &session, cx),
+                DebugSessionEvent::Continued | DebugSessionEvent::Exited => {
+                    clear_stopped_location(workspace, cx)
+                }
+                DebugSessionEvent::Output => {}
+            })
+            .detach();
+        })
+    })
+    .detach_and_prompt_err("Failed to start debugging", cx, |_, _| None);
+}
+
+pub fn continue_debugging(
+    workspace: &mut Workspace,
+    _: &ContinueDebugging,
+    cx: &mut ViewContext<Workspace>,
+) {
+    update_debug_session(workspace, DebugSession::continue_, cx);
+}
+
+pub fn pause_debugging(
+    workspace: &mut Workspace,
+    _: &PauseDebugging,
+    cx: &mut ViewContext<Workspace>,
+) {
+    update_debug_session(workspace, DebugSession::pause, cx);
+}
+
+pub fn step_over(workspace: &mut Workspace, _: &StepOver, cx: &mut ViewContext<Workspace>) {
+    update_debug_session(workspace, DebugSession::step_over, cx);
+}
+
+pub fn step_into(workspace: &mut Workspace, _: &StepInto, cx: &mut ViewContext<Workspace>) {
+    update_debug_session(workspace, DebugSession::step_into, cx);
+}
+
+pub fn step_out(workspace: &mut Workspace, _: &StepOut, cx: &mut ViewContext<Workspace>) {
+    update_debug_session(workspace, DebugSession::step_out, cx);
+}
+
+pub fn stop_debugging(
+    workspace: &mut Workspace,
+    _: &StopDebugging,
+    cx: &mut ViewContext<Workspace>,
+) {
+    update_debug_session(workspace, DebugSession::stop, cx);
+}
+
+/// Applies a command to the most recently started debug session.
+fn update_debug_session(
+    workspace: &Workspace,
+    command: impl FnOnce(&mut DebugSession, &mut ModelContext<DebugSession>) -> Task<Result<()>>,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(session) = workspace
+        .project()
+        .read(cx)
+        .debug_sessions()
+        .last()
+        .cloned()
+    else {
+        return;
+    };
+    session.update(cx, command).detach_and_log_err(cx);
+}
+
+/// Opens the file of the stopped thread's innermost frame and highlights the line it's on.
+fn reveal_stopped_location(
+    workspace: &mut Workspace,
+    session: &Model<DebugSession>,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(frame) = session.read(cx).stack_frames().first() else {
+        return;
+    };
+    let Some(abs_path) = frame.source.as_ref().and_then(|source| source.path.clone()) else {
+        return;
+    };
+    let point = Point::new(frame.line.saturating_sub(1), frame.column.saturating_sub(1));
+    let open_task = workspace.open_abs_path(PathBuf::from(abs_path), true, cx);
+    cx.spawn(|workspace, mut cx| async move {
+        let item = open_task.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            clear_stopped_location(workspace, cx)
+        })?;
+        if let Some(editor) = item.downcast::<Editor>() {
+            editor.update(&mut cx, |editor, cx| {
+                let snapshot = editor.buffer.read(cx).snapshot(cx);
+                let point = snapshot.clip_point(point, Bias::Left);
+                let position = snapshot.anchor_before(point);
+                editor.highlight_rows::<StoppedRowHighlight>(
+                    position..=position,
+                    Some(cx.theme().status().warning_background),
+                    false,
+                    cx,
+                );
+                editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                    s.select_ranges([point..point])
+                });
+            })?;
+        }
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+fn clear_stopped_location(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    for editor in workspace.items_of_type::<Editor>(cx).collect::<Vec<_>>() {
+        editor.update(cx, |editor, cx| {
+            editor.clear_row_highlights::<StoppedRowHighlight>();
+            cx.notify();
+        });
+    }
+}
@@ -0,0 +1,90 @@
+//! URLs and references to files, such as `src/main.rs:10:5`, are detected in the visible part
+//! of the editor once scrolling and edits settle, so that they can be opened by clicking them
+//! while holding the secondary modifier.
+
+use std::ops::Range;
+
+use gpui::{Task, ViewContext};
+use language::{Bias, Point};
+use multi_buffer::{Anchor, MultiBufferSnapshot};
+use project::LinkTarget;
+
+use crate::{hover_links::HoverLink, Editor, EditorMode, DETECTED_LINKS_DEBOUNCE_TIMEOUT};
+
+#[derive(Default)]
+pub struct DetectedLinkState {
+    links: Vec<(Range<Anchor>, LinkTarget)>,
+    task: Option<Task<()>>,
+}
+
+/// Detects the links in the visible part of the editor after a delay, replacing the ones
+/// detected before.
+pub(crate) fn refresh_detected_links(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    if editor.project.is_none() || editor.mode != EditorMode::Full {
+        return;
+    }
+
+    editor.detected_link_state.task = Some(cx.spawn(|editor, mut cx| async move {
+        cx.background_executor()
+            .timer(DETECTED_LINKS_DEBOUNCE_TIMEOUT)
+            .await;
+
+        editor
+            .update(&mut cx, |editor, cx| {
+                let Some(project) = editor.project.clone() else {
+                    return;
+                };
+                let multi_buffer = editor.buffer.read(cx);
+                let snapshot = multi_buffer.snapshot(cx);
+                let visible_start = editor.scroll_manager.anchor().anchor.to_point(&snapshot);
+                let visible_end = snapshot.clip_point(
+                    visible_start
+                        + Point::new(editor.visible_line_count().unwrap_or(0.).ceil() as u32, 0),
+                    Bias::Left,
+                );
+
+                let mut links = Vec::new();
+                for (buffer, range, excerpt_id) in
+                    multi_buffer.range_to_buffer_ranges(visible_start..visible_end, cx)
+                {
+                    for link in project.read(cx).detect_links(&buffer, range, cx) {
+                        let Some(start) = snapshot.anchor_in_excerpt(excerpt_id, link.range.start)
+                        else {
+                            continue;
+                        };
+                        let Some(end) = snapshot.anchor_in_excerpt(excerpt_id, link.range.end)
+                        else {
+                            continue;
+                        };
+                        links.push((start..end, link.target));
+                    }
+                }
+                editor.detected_link_state.links = links;
+            })
+            .ok();
+    }));
+}
+
+impl Editor {
+    /// The links detected in the visible part of the editor, in the order they appear.
+    pub fn detected_links(&self) -> &[(Range<Anchor>, LinkTarget)] {
+        &self.detected_link_state.links
+    }
+
+    /// The detected link at the position, for opening it when it's clicked.
+    pub(crate) fn detected_link_at(
+        &self,
+        position: Anchor,
+        snapshot: &MultiBufferSnapshot,
+    ) -> Option<(Range<Anchor>, HoverLink)> {
+        let (range, target) = self.detected_link_state.links.iter().find(|(range, _)| {
+            range.start.cmp(&position, snapshot).is_le()
+                && range.end.cmp(&position, snapshot).is_ge()
+        })?;
+        let link = match target.clone() {
+            LinkTarget::Url(url) => HoverLink::Url(url),
+            LinkTarget::File(project_path, position) => HoverLink::File(project_path, position),
+        };
+        Some((range.clone(), link))
+    }
+}
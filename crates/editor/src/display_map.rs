@@ -56,7 +56,7 @@ use std::ops::Add;
 use std::{any::TypeId, borrow::Cow, fmt::Debug, num::NonZeroU32, ops::Range, sync::Arc};
 use sum_tree::{Bias, TreeMap};
 use tab_map::{TabMap, TabSnapshot};
-use text::LineIndent;
+use text::{bidi_runs, BidiRun, LineIndent};
 use ui::WindowContext;
 use wrap_map::{WrapMap, WrapSnapshot};
 
@@ -857,6 +857,12 @@ impl DisplaySnapshot {
         result
     }
 
+    /// The runs of the display row's text that are displayed in a single direction, in the
+    /// order they're displayed from left to right. Byte ranges are display columns.
+    pub fn bidi_runs(&self, display_row: DisplayRow) -> Vec<BidiRun> {
+        bidi_runs(&self.line(display_row))
+    }
+
     pub fn line_indent_for_buffer_row(&self, buffer_row: MultiBufferRow) -> LineIndent {
         let (buffer, range) = self
             .buffer_snapshot
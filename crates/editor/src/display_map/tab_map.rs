@@ -6,6 +6,7 @@ use language::{Chunk, Point};
 use multi_buffer::MultiBufferSnapshot;
 use std::{cmp, mem, num::NonZeroU32, ops::Range};
 use sum_tree::Bias;
+use text::WidthCounter;
 
 const MAX_EXPANSION_COLUMN: u32 = 256;
 
@@ -255,6 +256,7 @@ impl TabSnapshot {
                 ..Default::default()
             },
             inside_leading_tab: to_next_stop > 0,
+            widths: WidthCounter::default(),
         }
     }
 
@@ -319,6 +321,9 @@ impl TabSnapshot {
     fn expand_tabs(&self, chars: impl Iterator<Item = char>, column: u32) -> u32 {
         let tab_size = self.tab_size.get();
 
+        // Tab stops are measured in the columns of a monospace grid, in which wide
+        // characters take two columns.
+        let mut widths = WidthCounter::default();
         let mut expanded_chars = 0;
         let mut expanded_bytes = 0;
         let mut collapsed_bytes = 0;
@@ -333,7 +338,7 @@ impl TabSnapshot {
                 expanded_chars += tab_len;
             } else {
                 expanded_bytes += c.len_utf8() as u32;
-                expanded_chars += 1;
+                expanded_chars += widths.width(c);
             }
             collapsed_bytes += c.len_utf8() as u32;
         }
@@ -348,6 +353,7 @@ impl TabSnapshot {
     ) -> (u32, u32, u32) {
         let tab_size = self.tab_size.get();
 
+        let mut widths = WidthCounter::default();
         let mut expanded_bytes = 0;
         let mut expanded_chars = 0;
         let mut collapsed_bytes = 0;
@@ -371,13 +377,13 @@ impl TabSnapshot {
                     };
                 }
             } else {
-                expanded_chars += 1;
+                let width = widths.width(c);
+                expanded_chars += width;
                 expanded_bytes += c.len_utf8() as u32;
-            }
-
-            if expanded_bytes > column && matches!(bias, Bias::Left) {
-                expanded_chars -= 1;
-                break;
+                if expanded_bytes > column && matches!(bias, Bias::Left) {
+                    expanded_chars -= width;
+                    break;
+                }
             }
 
             collapsed_bytes += c.len_utf8() as u32;
@@ -481,6 +487,7 @@ pub struct TabChunks<'a> {
     max_output_position: Point,
     tab_size: NonZeroU32,
     inside_leading_tab: bool,
+    widths: WidthCounter,
 }
 
 impl<'a> Iterator for TabChunks<'a> {
@@ -537,9 +544,10 @@ impl<'a> Iterator for TabChunks<'a> {
                     self.column = 0;
                     self.input_column = 0;
                     self.output_position += Point::new(1, 0);
+                    self.widths = WidthCounter::default();
                 }
                 _ => {
-                    self.column += 1;
+                    self.column += self.widths.width(c);
                     if !self.inside_leading_tab {
                         self.input_column += c.len_utf8() as u32;
                     }
@@ -574,6 +582,35 @@ mod tests {
         assert_eq!(tab_snapshot.expand_tabs("\ta".chars(), 2), 5);
     }
 
+    #[gpui::test]
+    fn test_tabs_after_wide_characters(cx: &mut gpui::AppContext) {
+        let buffer = MultiBuffer::build_simple("你\ta\ne\u{301}\tb\n👨\u{200d}👩\tc", cx);
+        let buffer_snapshot = buffer.read(cx).snapshot(cx);
+        let (_, inlay_snapshot) = InlayMap::new(buffer_snapshot.clone());
+        let (_, fold_snapshot) = FoldMap::new(inlay_snapshot);
+        let (_, tab_snapshot) = TabMap::new(fold_snapshot, 4.try_into().unwrap());
+
+        // Wide characters take two columns towards the next tab stop, combining characters
+        // and joined emoji take none.
+        assert_eq!(tab_snapshot.text(), "你  a\ne\u{301}   b\n👨\u{200d}👩  c");
+
+        let a = FoldPoint::new(0, "你\t".len() as u32);
+        let a_tab_point = TabPoint::new(0, "你  ".len() as u32);
+        assert_eq!(tab_snapshot.to_tab_point(a), a_tab_point);
+        assert_eq!(tab_snapshot.to_fold_point(a_tab_point, Bias::Left).0, a);
+        assert_eq!(
+            tab_snapshot
+                .chunks(
+                    TabPoint::new(0, "你 ".len() as u32)..tab_snapshot.max_point(),
+                    false,
+                    Highlights::default()
+                )
+                .map(|chunk| chunk.text)
+                .collect::<String>(),
+            " a\ne\u{301}   b\n👨\u{200d}👩  c"
+        );
+    }
+
     #[gpui::test]
     fn test_long_lines(cx: &mut gpui::AppContext) {
         let max_expansion_column = 12;
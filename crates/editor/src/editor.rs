@@ -12,30 +12,45 @@
 //! All other submodules and structs are mostly concerned with holding editor data about the way it displays current buffer region(s).
 //!
 //! If you're looking to improve Vim mode, you should check out Vim crate that wraps Editor and overrides its behaviour.
+mod accessibility;
 pub mod actions;
 mod blame_entry_tooltip;
 mod blink_manager;
 pub mod display_map;
 mod editor_settings;
 mod element;
+mod export;
 mod hunk_diff;
 mod inlay_hint_cache;
+mod inline_diagnostics;
 
+mod code_lens;
+mod color_swatches;
+mod composition;
 mod debounced_delay;
+mod debugging;
+mod detected_links;
 mod git;
 mod highlight_matching_bracket;
+mod highlight_occurrences;
 mod hover_links;
 mod hover_popover;
 mod indent_guides;
 mod inline_completion_provider;
 pub mod items;
+pub mod marks;
+mod minimap;
 mod mouse_context_menu;
 pub mod movement;
 mod persistence;
+pub mod recent_edits;
 mod rust_analyzer_ext;
 pub mod scroll;
 mod selections_collection;
+mod signature_help;
 pub mod tasks;
+mod text_tools;
+pub mod textual_rename;
 
 #[cfg(test)]
 mod editor_tests;
@@ -43,19 +58,29 @@ mod editor_tests;
 pub mod test;
 use ::git::diff::{DiffHunk, DiffHunkStatus};
 use ::git::{parse_git_remote_url, BuildPermalinkParams, GitHostingProviderRegistry};
+use accessibility::{announce_diagnostics, announce_save, AccessibilityState};
+pub use accessibility::{
+    AccessibleDiagnostic, AccessibleDocument, AccessibleLine, AccessibleSelection, Announcement,
+    AnnouncementPriority,
+};
 pub(crate) use actions::*;
 use aho_corasick::AhoCorasick;
 use anyhow::{anyhow, Context as _, Result};
 use blink_manager::BlinkManager;
 use client::{Collaborator, ParticipantIndex};
 use clock::ReplicaId;
+pub use code_lens::run_code_lens;
+use code_lens::{refresh_code_lenses, CodeLensState};
 use collections::{BTreeMap, Bound, HashMap, HashSet, VecDeque};
+use color_swatches::{refresh_color_swatches, ColorSwatchState};
 use convert_case::{Case, Casing};
 use debounced_delay::DebouncedDelay;
+use debugging::refresh_breakpoint_highlights;
+use detected_links::{refresh_detected_links, DetectedLinkState};
 use display_map::*;
 pub use display_map::{DisplayPoint, FoldPlaceholder};
-use editor_settings::CurrentLineHighlight;
 pub use editor_settings::EditorSettings;
+use editor_settings::{CurrentLineHighlight, CursorMovement};
 use element::LineWithInvisibles;
 pub use element::{
     CursorLayout, EditorElement, HighlightedRange, HighlightedRangeLine, PointForPosition,
@@ -64,6 +89,7 @@ use futures::FutureExt;
 use fuzzy::{StringMatch, StringMatchCandidate};
 use git::blame::GitBlame;
 use git::diff_hunk_to_display;
+use git::staging::StagedHunks;
 use gpui::{
     div, impl_actions, point, prelude::*, px, relative, size, uniform_list, Action, AnyElement,
     AppContext, AsyncWindowContext, AvailableSpace, BackgroundExecutor, Bounds, ClipboardItem,
@@ -74,12 +100,15 @@ use gpui::{
     ViewInputHandler, VisualContext, WeakView, WhiteSpace, WindowContext,
 };
 use highlight_matching_bracket::refresh_matching_bracket_highlights;
+use highlight_occurrences::refresh_occurrence_highlights;
 use hover_popover::{hide_hover, HoverState};
 use hunk_diff::ExpandedHunks;
 pub(crate) use hunk_diff::HunkToExpand;
 use indent_guides::ActiveIndentGuidesState;
 use inlay_hint_cache::{InlayHintCache, InlaySplice, InvalidationStrategy};
 pub use inline_completion_provider::*;
+pub use inline_diagnostics::InlineDiagnostic;
+use inline_diagnostics::{refresh_inline_diagnostics, InlineDiagnosticsState};
 pub use items::MAX_TAB_TITLE_LEN;
 use itertools::Itertools;
 use language::{
@@ -89,7 +118,13 @@ use language::{
     CursorShape, Diagnostic, Documentation, IndentKind, IndentSize, Language, OffsetRangeExt,
     Point, Selection, SelectionGoal, TransactionId,
 };
-use language::{BufferRow, Runnable, RunnableRange};
+use language::{BufferRow, ElasticTabstops, Runnable, RunnableRange};
+pub use minimap::MinimapLine;
+use minimap::MinimapState;
+pub use signature_help::SignatureHelpPopover;
+use signature_help::{
+    hide_signature_help, refresh_signature_help, show_signature_help_on_input, SignatureHelpState,
+};
 use task::{ResolvedTask, TaskTemplate, TaskVariables};
 
 use hover_links::{HoverLink, HoveredLinkState, InlayHighlight};
@@ -159,6 +194,12 @@ pub(crate) const CURSORS_VISIBLE_FOR: Duration = Duration::from_millis(2000);
 pub const CODE_ACTIONS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(250);
 #[doc(hidden)]
 pub const DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(75);
+#[doc(hidden)]
+pub const CODE_LENS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(500);
+#[doc(hidden)]
+pub const COLOR_SWATCHES_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(500);
+#[doc(hidden)]
+pub const DETECTED_LINKS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(200);
 
 pub(crate) const FORMAT_TIMEOUT: Duration = Duration::from_secs(2);
 
@@ -169,58 +210,17 @@ pub fn render_parsed_markdown(
     workspace: Option<WeakView<Workspace>>,
     cx: &mut WindowContext,
 ) -> InteractiveText {
-    let code_span_background_color = cx
-        .theme()
-        .colors()
-        .editor_document_highlight_read_background;
-
-    let highlights = gpui::combine_highlights(
-        parsed.highlights.iter().filter_map(|(range, highlight)| {
-            let highlight = highlight.to_highlight_style(&editor_style.syntax)?;
-            Some((range.clone(), highlight))
-        }),
-        parsed
-            .regions
-            .iter()
-            .zip(&parsed.region_ranges)
-            .filter_map(|(region, range)| {
-                if region.code {
-                    Some((
-                        range.clone(),
-                        HighlightStyle {
-                            background_color: Some(code_span_background_color),
-                            ..Default::default()
-                        },
-                    ))
-                } else {
-                    None
-                }
-            }),
-    );
-
-    let mut links = Vec::new();
-    let mut link_ranges = Vec::new();
-    for (range, region) in parsed.region_ranges.iter().zip(&parsed.regions) {
-        if let Some(link) = region.link.clone() {
-            links.push(link);
-            link_ranges.push(range.clone());
-        }
-    }
-
-    InteractiveText::new(
-        element_id,
-        StyledText::new(parsed.text.clone()).with_highlights(&editor_style.text, highlights),
-    )
-    .on_click(link_ranges, move |clicked_range_ix, cx| {
-        match &links[clicked_range_ix] {
-            markdown::Link::Web { url } => cx.open_url(url),
-            markdown::Link::Path { path } => {
-                if let Some(workspace) = &workspace {
-                    _ = workspace.update(cx, |workspace, cx| {
-                        workspace.open_abs_path(path.clone(), false, cx).detach();
-                    });
-                }
-            }
+    let style = markdown::MarkdownStyle {
+        syntax: editor_style.syntax.clone(),
+        ..markdown::MarkdownStyle::themed(editor_style.text.clone(), cx)
+    };
+    markdown::render_parsed_markdown(element_id, parsed, &style, move |path, cx| {
+        if let Some(workspace) = &workspace {
+            _ = workspace.update(cx, |workspace, cx| {
+                workspace
+                    .open_abs_path(path.to_path_buf(), false, cx)
+                    .detach();
+            });
         }
     })
 }
@@ -251,6 +251,11 @@ pub enum Direction {
     Next,
 }
 
+/// Deletes the unsaved changes that editors backed up for restoring them after a crash.
+pub async fn discard_buffer_backups() -> Result<()> {
+    persistence::DB.delete_buffer_backups().await
+}
+
 pub fn init_settings(cx: &mut AppContext) {
     EditorSettings::register(cx);
 }
@@ -265,9 +270,20 @@ pub fn init(cx: &mut AppContext) {
         |workspace: &mut Workspace, _cx: &mut ViewContext<Workspace>| {
             workspace.register_action(Editor::new_file);
             workspace.register_action(Editor::new_file_in_direction);
+            workspace.register_action(marks::go_to_mark);
+            workspace.register_action(recent_edits::go_to_last_edit);
+            workspace.register_action(debugging::start_debugging);
+            workspace.register_action(debugging::continue_debugging);
+            workspace.register_action(debugging::pause_debugging);
+            workspace.register_action(debugging::step_over);
+            workspace.register_action(debugging::step_into);
+            workspace.register_action(debugging::step_out);
+            workspace.register_action(debugging::stop_debugging);
         },
     )
     .detach();
+    marks::Marks::init(cx);
+    recent_edits::RecentEdits::init(cx);
 
     cx.on_action(move |_: &workspace::NewFile, cx| {
         let app_state = workspace::AppState::global(cx);
@@ -443,7 +459,8 @@ pub struct Editor {
     selection_history: SelectionHistory,
     autoclose_regions: Vec<AutocloseRegion>,
     snippet_stack: InvalidationStack<SnippetState>,
-    select_larger_syntax_node_stack: Vec<Box<[Selection<usize>]>>,
+    select_larger_syntax_node_stack: Vec<ExpandedSelections>,
+    select_larger_bracket_scope_stack: Vec<ExpandedSelections>,
     ime_transaction: Option<TransactionId>,
     active_diagnostics: Option<ActiveDiagnosticGroup>,
     soft_wrap_mode_override: Option<language_settings::SoftWrap>,
@@ -461,6 +478,7 @@ pub struct Editor {
     show_git_diff_gutter: Option<bool>,
     show_code_actions: Option<bool>,
     show_wrap_guides: Option<bool>,
+    wrap_guides_override: Option<Vec<usize>>,
     show_indent_guides: Option<bool>,
     placeholder_text: Option<Arc<str>>,
     highlight_order: usize,
@@ -468,6 +486,7 @@ pub struct Editor {
     background_highlights: TreeMap<TypeId, BackgroundHighlight>,
     scrollbar_marker_state: ScrollbarMarkerState,
     active_indent_guides_state: ActiveIndentGuidesState,
+    elastic_tabstops: Option<ElasticTabstops>,
     nav_history: Option<ItemNavHistory>,
     context_menu: RwLock<Option<ContextMenu>>,
     mouse_context_menu: Option<MouseContextMenu>,
@@ -478,6 +497,7 @@ pub struct Editor {
     available_code_actions: Option<(Location, Arc<[CodeAction]>)>,
     code_actions_task: Option<Task<()>>,
     document_highlights_task: Option<Task<()>>,
+    occurrence_highlights_task: Option<Task<()>>,
     pending_rename: Option<RenameState>,
     searchable: bool,
     cursor_shape: CursorShape,
@@ -492,6 +512,13 @@ pub struct Editor {
     leader_peer_id: Option<PeerId>,
     remote_id: Option<ViewId>,
     hover_state: HoverState,
+    signature_help_state: SignatureHelpState,
+    code_lens_state: CodeLensState,
+    color_swatch_state: ColorSwatchState,
+    detected_link_state: DetectedLinkState,
+    inline_diagnostics_state: InlineDiagnosticsState,
+    minimap_state: MinimapState,
+    accessibility_state: AccessibilityState,
     gutter_hovered: bool,
     hovered_link_state: Option<HoveredLinkState>,
     inline_completion_provider: Option<RegisteredInlineCompletionProvider>,
@@ -499,6 +526,7 @@ pub struct Editor {
     show_inline_completions: bool,
     inlay_hint_cache: InlayHintCache,
     expanded_hunks: ExpandedHunks,
+    staged_hunks: StagedHunks,
     next_inlay_id: usize,
     _subscriptions: Vec<Subscription>,
     pixel_position_of_newest_cursor: Option<gpui::Point<Pixels>>,
@@ -524,6 +552,7 @@ pub struct Editor {
     expect_bounds_change: Option<Bounds<Pixels>>,
     tasks: BTreeMap<(BufferId, BufferRow), RunnableTasks>,
     tasks_update_task: Option<Task<()>>,
+    backup_task: Option<Task<()>>,
 }
 
 #[derive(Clone)]
@@ -692,6 +721,15 @@ struct AddSelectionsState {
     stack: Vec<usize>,
 }
 
+/// Selections that were replaced by expanding them, e.g. to an enclosing syntax node.
+///
+/// Both sets of selections are anchored, so that the expansion can still be undone after
+/// edits, as long as the expanded selections themselves haven't been changed.
+struct ExpandedSelections {
+    previous: Arc<[Selection<Anchor>]>,
+    expanded: Arc<[Selection<Anchor>]>,
+}
+
 #[derive(Clone)]
 struct SelectNextState {
     query: AhoCorasick,
@@ -1488,6 +1526,13 @@ pub struct ClipboardSelection {
     pub first_line_indent: u32,
 }
 
+/// Where text copied as entire lines is pasted, relative to the line containing the cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EntireLinePastePlacement {
+    Above,
+    Below,
+}
+
 #[derive(Debug)]
 pub(crate) struct NavigationData {
     cursor_anchor: Anchor,
@@ -1664,6 +1709,13 @@ impl Editor {
                 project_subscriptions.push(cx.subscribe(project, |editor, _, event, cx| {
                     if let project::Event::RefreshInlayHints = event {
                         editor.refresh_inlay_hints(InlayHintRefreshReason::RefreshRequested, cx);
+                    } else if let project::Event::LanguageServerAdded(_) = event {
+                        refresh_code_lenses(editor, cx);
+                        refresh_color_swatches(editor, cx);
+                    } else if let project::Event::BreakpointsChanged(id) = event {
+                        if editor.buffer.read(cx).buffer(*id).is_some() {
+                            refresh_breakpoint_highlights(editor, cx);
+                        }
                     } else if let project::Event::SnippetEdit(id, snippet_edits) = event {
                         if let Some(buffer) = editor.buffer.read(cx).buffer(*id) {
                             let focus_handle = editor.focus_handle(cx);
@@ -1710,6 +1762,7 @@ impl Editor {
             autoclose_regions: Default::default(),
             snippet_stack: Default::default(),
             select_larger_syntax_node_stack: Vec::new(),
+            select_larger_bracket_scope_stack: Vec::new(),
             ime_transaction: Default::default(),
             active_diagnostics: None,
             soft_wrap_mode_override,
@@ -1725,6 +1778,7 @@ impl Editor {
             show_git_diff_gutter: None,
             show_code_actions: None,
             show_wrap_guides: None,
+            wrap_guides_override: None,
             show_indent_guides: None,
             placeholder_text: None,
             highlight_order: 0,
@@ -1732,6 +1786,7 @@ impl Editor {
             background_highlights: Default::default(),
             scrollbar_marker_state: ScrollbarMarkerState::default(),
             active_indent_guides_state: ActiveIndentGuidesState::default(),
+            elastic_tabstops: None,
             nav_history: None,
             context_menu: RwLock::new(None),
             mouse_context_menu: None,
@@ -1743,6 +1798,7 @@ impl Editor {
             available_code_actions: Default::default(),
             code_actions_task: Default::default(),
             document_highlights_task: Default::default(),
+            occurrence_highlights_task: Default::default(),
             pending_rename: Default::default(),
             searchable: true,
             cursor_shape: Default::default(),
@@ -1759,11 +1815,19 @@ impl Editor {
             leader_peer_id: None,
             remote_id: None,
             hover_state: Default::default(),
+            signature_help_state: Default::default(),
+            code_lens_state: Default::default(),
+            color_swatch_state: Default::default(),
+            detected_link_state: Default::default(),
+            inline_diagnostics_state: Default::default(),
+            minimap_state: Default::default(),
+            accessibility_state: Default::default(),
             hovered_link_state: Default::default(),
             inline_completion_provider: None,
             active_inline_completion: None,
             inlay_hint_cache: InlayHintCache::new(inlay_hint_settings),
             expanded_hunks: ExpandedHunks::default(),
+            staged_hunks: StagedHunks::default(),
             gutter_hovered: false,
             pixel_position_of_newest_cursor: None,
             last_bounds: None,
@@ -1801,10 +1865,23 @@ impl Editor {
                         }
                     });
                 }),
+                // Collaborators mustn't wait forever for edits that an unfinished
+                // composition held back.
+                cx.on_release(|editor, _, cx| {
+                    editor
+                        .buffer
+                        .update(cx, |buffer, cx| buffer.release_held_operations(cx));
+                }),
             ],
             tasks_update_task: None,
+            backup_task: None,
         };
         this.tasks_update_task = Some(this.refresh_runnables(cx));
+        refresh_code_lenses(&mut this, cx);
+        refresh_color_swatches(&mut this, cx);
+        refresh_detected_links(&mut this, cx);
+        refresh_inline_diagnostics(&mut this, cx);
+        refresh_breakpoint_highlights(&mut this, cx);
         this._subscriptions.extend(project_subscriptions);
 
         this.end_selection(cx);
@@ -2000,6 +2077,11 @@ impl Editor {
         self.mode
     }
 
+    /// Returns the signatures of the call being typed, if signature help is shown.
+    pub fn signature_help(&self) -> Option<&SignatureHelpPopover> {
+        self.signature_help_state.popover()
+    }
+
     pub fn collaboration_hub(&self) -> Option<&dyn CollaborationHub> {
         self.collaboration_hub.as_deref()
     }
@@ -2185,7 +2267,6 @@ impl Editor {
         self.add_selections_state = None;
         self.select_next_state = None;
         self.select_prev_state = None;
-        self.select_larger_syntax_node_stack.clear();
         self.invalidate_autoclose_regions(&self.selections.disjoint_anchors(), buffer);
         self.snippet_stack
             .invalidate(&self.selections.disjoint_anchors(), buffer);
@@ -2255,6 +2336,7 @@ impl Editor {
             }
 
             hide_hover(self, cx);
+            refresh_signature_help(self, cx);
 
             if old_cursor_position.to_display_point(&display_map).row()
                 != new_cursor_position.to_display_point(&display_map).row()
@@ -2263,6 +2345,7 @@ impl Editor {
             }
             self.refresh_code_actions(cx);
             self.refresh_document_highlights(cx);
+            refresh_occurrence_highlights(self, cx);
             refresh_matching_bracket_highlights(self, cx);
             self.discard_inline_completion(false, cx);
             if self.git_blame_inline_enabled {
@@ -2724,6 +2807,10 @@ impl Editor {
             return true;
         }
 
+        if hide_signature_help(self, cx) {
+            return true;
+        }
+
         if self.hide_context_menu(cx).is_some() {
             return true;
         }
@@ -3003,6 +3090,7 @@ impl Editor {
 
             let trigger_in_words = !had_active_inline_completion;
             this.trigger_completion_on_input(&text, trigger_in_words, cx);
+            show_signature_help_on_input(this, &text, cx);
             this.refresh_inline_completion(true, cx);
         });
     }
@@ -3189,6 +3277,13 @@ impl Editor {
                 .collect();
 
             this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(new_selections));
+            if EditorSettings::get_global(cx).use_on_type_format {
+                if let Some(on_type_format_task) =
+                    this.trigger_on_type_formatting("\n".to_string(), cx)
+                {
+                    on_type_format_task.detach_and_log_err(cx);
+                }
+            }
             this.refresh_inline_completion(true, cx);
         });
     }
@@ -3744,14 +3839,23 @@ impl Editor {
             )
         });
         Some(cx.spawn(|editor, mut cx| async move {
+            // By the time this task runs, the edit that inserted the trigger character has
+            // been committed to the buffer's history, and grouped with the edits before it.
+            let typed_transaction_id = buffer.update(&mut cx, |buffer, _| {
+                buffer.peek_undo_stack().map(|entry| entry.transaction_id())
+            })?;
             if let Some(transaction) = on_type_formatting.await? {
-                if push_to_client_history {
-                    buffer
-                        .update(&mut cx, |buffer, _| {
-                            buffer.push_transaction(transaction, Instant::now());
-                        })
-                        .ok();
-                }
+                buffer
+                    .update(&mut cx, |buffer, _| {
+                        if push_to_client_history {
+                            buffer.push_transaction(transaction.clone(), Instant::now());
+                        }
+                        // Undo the formatting together with the character that triggered it.
+                        if let Some(typed_transaction_id) = typed_transaction_id {
+                            buffer.merge_transactions(transaction.id, typed_transaction_id);
+                        }
+                    })
+                    .ok();
                 editor.update(&mut cx, |editor, cx| {
                     editor.refresh_document_highlights(cx);
                 })?;
@@ -4393,12 +4497,36 @@ impl Editor {
         if cursor_buffer != tail_buffer {
             return None;
         }
+        let lacks_document_highlights = project
+            .read(cx)
+            .supports_document_highlights(&cursor_buffer, cx)
+            == Some(false);
 
         self.document_highlights_task = Some(cx.spawn(|this, mut cx| async move {
             cx.background_executor()
                 .timer(DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT)
                 .await;
 
+            if lacks_document_highlights {
+                // When the language server can't find the symbol's occurrences, highlight the
+                // other occurrences of the word instead, unless they're highlighted already.
+                this.update(&mut cx, |this, cx| {
+                    let ranges = if EditorSettings::get_global(cx).highlight_occurrences {
+                        Vec::new()
+                    } else {
+                        highlight_occurrences::visible_occurrences(this, true, cx)
+                    };
+                    this.highlight_background::<DocumentHighlightRead>(
+                        &ranges,
+                        |theme| theme.editor_document_highlight_read_background,
+                        cx,
+                    );
+                    this.clear_background_highlights::<DocumentHighlightWrite>(cx);
+                })
+                .log_err();
+                return;
+            }
+
             let highlights = if let Some(highlights) = project
                 .update(&mut cx, |project, cx| {
                     project.document_highlights(&cursor_buffer, cursor_buffer_position, cx)
@@ -5466,10 +5594,26 @@ impl Editor {
     ) {
         self.manipulate_lines(cx, |lines| {
             let mut seen = HashSet::default();
-            lines.retain(|line| seen.insert(*line));
+            lines.retain(|line| seen.insert(line.clone()));
+        })
+    }
+
+    pub fn sort_lines_numerically(&mut self, _: &SortLinesNumerically, cx: &mut ViewContext<Self>) {
+        self.manipulate_lines(cx, |lines| {
+            lines.sort_by(|a, b| text_tools::numeric_cmp(a, b))
         })
     }
 
+    pub fn sort_lines_naturally(&mut self, _: &SortLinesNaturally, cx: &mut ViewContext<Self>) {
+        self.manipulate_lines(cx, |lines| {
+            lines.sort_by(|a, b| text_tools::natural_cmp(a, b))
+        })
+    }
+
+    pub fn align_lines(&mut self, action: &AlignLines, cx: &mut ViewContext<Self>) {
+        self.manipulate_lines(cx, |lines| text_tools::align_on(lines, action.delimiter))
+    }
+
     pub fn revert_selected_hunks(&mut self, _: &RevertSelectedHunks, cx: &mut ViewContext<Self>) {
         let revert_changes = self.gather_revert_changes(&self.selections.disjoint_anchors(), cx);
         if !revert_changes.is_empty() {
@@ -5563,7 +5707,7 @@ impl Editor {
 
     fn manipulate_lines<Fn>(&mut self, cx: &mut ViewContext<Self>, mut callback: Fn)
     where
-        Fn: FnMut(&mut Vec<&str>),
+        Fn: FnMut(&mut Vec<Cow<'_, str>>),
     {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
@@ -5594,7 +5738,7 @@ impl Editor {
                 .text_for_range(start_point..end_point)
                 .collect::<String>();
 
-            let mut lines = text.split('\n').collect_vec();
+            let mut lines = text.split('\n').map(Cow::Borrowed).collect_vec();
 
             let lines_before = lines.len();
             callback(&mut lines);
@@ -6166,10 +6310,51 @@ impl Editor {
     }
 
     pub fn paste(&mut self, _: &Paste, cx: &mut ViewContext<Self>) {
+        self.do_paste(EntireLinePastePlacement::Above, false, cx);
+    }
+
+    pub fn paste_below(&mut self, _: &PasteBelow, cx: &mut ViewContext<Self>) {
+        self.do_paste(EntireLinePastePlacement::Below, false, cx);
+    }
+
+    pub fn paste_and_match_indentation(
+        &mut self,
+        _: &PasteAndMatchIndentation,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.do_paste(EntireLinePastePlacement::Above, true, cx);
+    }
+
+    /// Pastes the clipboard contents at each selection.
+    ///
+    /// Text that was copied as entire lines is inserted as new lines at the given
+    /// `placement` relative to each empty selection, instead of at the cursor column.
+    /// By default, each pasted block keeps its relative indentation and is shifted as a
+    /// whole. When `match_indentation` is set, every pasted line is instead re-indented
+    /// according to the language's indentation rules at the destination. Languages with
+    /// significant indentation always keep the block's relative indentation, anchoring
+    /// only its first line to the destination, since re-indenting each line could change
+    /// the meaning of the pasted code.
+    fn do_paste(
+        &mut self,
+        placement: EntireLinePastePlacement,
+        match_indentation: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
         if self.read_only(cx) {
             return;
         }
 
+        let significant_indentation = {
+            let snapshot = self.buffer.read(cx).snapshot(cx);
+            self.selections.disjoint_anchors().iter().any(|selection| {
+                snapshot
+                    .language_at(selection.head())
+                    .map_or(false, |language| language.significant_indentation())
+            })
+        };
+        let match_indentation = match_indentation && !significant_indentation;
+
         self.transact(cx, |this, cx| {
             if let Some(item) = cx.read_from_clipboard() {
                 let clipboard_text = Cow::Borrowed(item.text());
@@ -6209,13 +6394,40 @@ impl Editor {
                             // If the corresponding selection was empty when this slice of the
                             // clipboard text was written, then the entire line containing the
                             // selection was copied. If this selection is also currently empty,
-                            // then paste the line before the current line of the buffer.
-                            let range = if selection.is_empty() && !line_mode && entire_line {
-                                let column = selection.start.to_point(&snapshot).column as usize;
-                                let line_start = selection.start - column;
-                                line_start..line_start
+                            // then paste the line before or after the current line of the buffer.
+                            let (range, to_insert) = if selection.is_empty()
+                                && !line_mode
+                                && entire_line
+                            {
+                                let point = selection.start.to_point(&snapshot);
+                                match placement {
+                                    EntireLinePastePlacement::Above => {
+                                        let line_start = selection.start - point.column as usize;
+                                        (line_start..line_start, Cow::Borrowed(to_insert))
+                                    }
+                                    EntireLinePastePlacement::Below => {
+                                        let next_row = point.row + 1;
+                                        if next_row <= snapshot.max_point().row {
+                                            let next_line_start =
+                                                Point::new(next_row, 0).to_offset(&snapshot);
+                                            (
+                                                next_line_start..next_line_start,
+                                                Cow::Borrowed(to_insert),
+                                            )
+                                        } else {
+                                            // The last line has no trailing newline to insert after,
+                                            // so move the copied line's newline to its front instead.
+                                            let line_end = snapshot.len();
+                                            let to_insert = format!(
+                                                "\n{}",
+                                                to_insert.strip_suffix('\n').unwrap_or(to_insert)
+                                            );
+                                            (line_end..line_end, Cow::Owned(to_insert))
+                                        }
+                                    }
+                                }
                             } else {
-                                selection.range()
+                                (selection.range(), Cow::Borrowed(to_insert))
                             };
 
                             edits.push((range, to_insert));
@@ -6223,17 +6435,32 @@ impl Editor {
                         }
                         drop(snapshot);
 
-                        buffer.edit(
-                            edits,
-                            Some(AutoindentMode::Block {
+                        let autoindent_mode = if match_indentation {
+                            AutoindentMode::EachLine
+                        } else {
+                            AutoindentMode::Block {
                                 original_indent_columns,
-                            }),
-                            cx,
-                        );
+                            }
+                        };
+                        buffer.edit(edits, Some(autoindent_mode), cx);
                     });
 
                     let selections = this.selections.all::<usize>(cx);
                     this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
+                } else if match_indentation {
+                    this.insert_with_autoindent_mode(
+                        &clipboard_text,
+                        Some(AutoindentMode::EachLine),
+                        cx,
+                    );
+                } else if significant_indentation {
+                    this.insert_with_autoindent_mode(
+                        &clipboard_text,
+                        Some(AutoindentMode::Block {
+                            original_indent_columns: Vec::new(),
+                        }),
+                        cx,
+                    );
                 } else {
                     this.insert(&clipboard_text, cx);
                 }
@@ -6245,6 +6472,10 @@ impl Editor {
         if self.read_only(cx) {
             return;
         }
+        if self.is_composing(cx) {
+            self.cancel_marked_text(cx);
+            return;
+        }
 
         if let Some(tx_id) = self.buffer.update(cx, |buffer, cx| buffer.undo(cx)) {
             if let Some((selections, _)) = self.selection_history.transaction(tx_id).cloned() {
@@ -6292,11 +6523,12 @@ impl Editor {
     }
 
     pub fn move_left(&mut self, _: &MoveLeft, cx: &mut ViewContext<Self>) {
+        let left = Self::left_movement(cx);
         self.change_selections(Some(Autoscroll::fit()), cx, |s| {
             let line_mode = s.line_mode;
             s.move_with(|map, selection| {
                 let cursor = if selection.is_empty() && !line_mode {
-                    movement::left(map, selection.start)
+                    left(map, selection.start)
                 } else {
                     selection.start
                 };
@@ -6306,17 +6538,19 @@ impl Editor {
     }
 
     pub fn select_left(&mut self, _: &SelectLeft, cx: &mut ViewContext<Self>) {
+        let left = Self::left_movement(cx);
         self.change_selections(Some(Autoscroll::fit()), cx, |s| {
-            s.move_heads_with(|map, head, _| (movement::left(map, head), SelectionGoal::None));
+            s.move_heads_with(|map, head, _| (left(map, head), SelectionGoal::None));
         })
     }
 
     pub fn move_right(&mut self, _: &MoveRight, cx: &mut ViewContext<Self>) {
+        let right = Self::right_movement(cx);
         self.change_selections(Some(Autoscroll::fit()), cx, |s| {
             let line_mode = s.line_mode;
             s.move_with(|map, selection| {
                 let cursor = if selection.is_empty() && !line_mode {
-                    movement::right(map, selection.end)
+                    right(map, selection.end)
                 } else {
                     selection.end
                 };
@@ -6326,11 +6560,26 @@ impl Editor {
     }
 
     pub fn select_right(&mut self, _: &SelectRight, cx: &mut ViewContext<Self>) {
+        let right = Self::right_movement(cx);
         self.change_selections(Some(Autoscroll::fit()), cx, |s| {
-            s.move_heads_with(|map, head, _| (movement::right(map, head), SelectionGoal::None));
+            s.move_heads_with(|map, head, _| (right(map, head), SelectionGoal::None));
         })
     }
 
+    fn left_movement(cx: &AppContext) -> fn(&DisplaySnapshot, DisplayPoint) -> DisplayPoint {
+        match EditorSettings::get_global(cx).cursor_movement {
+            CursorMovement::Logical => movement::left,
+            CursorMovement::Visual => movement::visual_left,
+        }
+    }
+
+    fn right_movement(cx: &AppContext) -> fn(&DisplaySnapshot, DisplayPoint) -> DisplayPoint {
+        match EditorSettings::get_global(cx).cursor_movement {
+            CursorMovement::Logical => movement::right,
+            CursorMovement::Visual => movement::visual_right,
+        }
+    }
+
     pub fn move_up(&mut self, _: &MoveUp, cx: &mut ViewContext<Self>) {
         if self.take_rename(true, cx).is_some() {
             return;
@@ -7870,9 +8119,10 @@ impl Editor {
     ) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
-        let old_selections = self.selections.all::<usize>(cx).into_boxed_slice();
+        let old_selections = self.selections.all::<usize>(cx);
 
         let mut stack = mem::take(&mut self.select_larger_syntax_node_stack);
+        self.discard_stale_selection_expansions(&mut stack, cx);
         let mut selected_larger_node = false;
         let new_selections = old_selections
             .iter()
@@ -7902,10 +8152,14 @@ impl Editor {
             .collect::<Vec<_>>();
 
         if selected_larger_node {
-            stack.push(old_selections);
+            let previous = self.selections.disjoint_anchors();
             self.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 s.select(new_selections);
             });
+            stack.push(ExpandedSelections {
+                previous,
+                expanded: self.selections.disjoint_anchors(),
+            });
         }
         self.select_larger_syntax_node_stack = stack;
     }
@@ -7916,14 +8170,111 @@ impl Editor {
         cx: &mut ViewContext<Self>,
     ) {
         let mut stack = mem::take(&mut self.select_larger_syntax_node_stack);
-        if let Some(selections) = stack.pop() {
+        self.discard_stale_selection_expansions(&mut stack, cx);
+        if let Some(expanded_selections) = stack.pop() {
             self.change_selections(Some(Autoscroll::fit()), cx, |s| {
-                s.select(selections.to_vec());
+                s.select_anchors(expanded_selections.previous.to_vec());
             });
         }
         self.select_larger_syntax_node_stack = stack;
     }
 
+    /// Expands each selection to the contents of its innermost enclosing bracket pair, or
+    /// to the pair itself when the contents are already selected.
+    pub fn select_larger_bracket_scope(
+        &mut self,
+        _: &SelectLargerBracketScope,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let old_selections = self.selections.all::<usize>(cx);
+
+        let mut stack = mem::take(&mut self.select_larger_bracket_scope_stack);
+        self.discard_stale_selection_expansions(&mut stack, cx);
+        let mut selected_larger_scope = false;
+        let new_selections = old_selections
+            .iter()
+            .map(|selection| {
+                let old_range = selection.start..selection.end;
+                let new_range = buffer
+                    .enclosing_bracket_ranges(old_range.clone())
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|(open, close)| [open.end..close.start, open.start..close.end])
+                    .filter(|range| {
+                        range.start <= old_range.start
+                            && range.end >= old_range.end
+                            && *range != old_range
+                    })
+                    .min_by_key(|range| range.len())
+                    .unwrap_or_else(|| old_range.clone());
+
+                selected_larger_scope |= new_range != old_range;
+                Selection {
+                    id: selection.id,
+                    start: new_range.start,
+                    end: new_range.end,
+                    goal: SelectionGoal::None,
+                    reversed: selection.reversed,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if selected_larger_scope {
+            let previous = self.selections.disjoint_anchors();
+            self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select(new_selections);
+            });
+            stack.push(ExpandedSelections {
+                previous,
+                expanded: self.selections.disjoint_anchors(),
+            });
+        }
+        self.select_larger_bracket_scope_stack = stack;
+    }
+
+    pub fn select_smaller_bracket_scope(
+        &mut self,
+        _: &SelectSmallerBracketScope,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let mut stack = mem::take(&mut self.select_larger_bracket_scope_stack);
+        self.discard_stale_selection_expansions(&mut stack, cx);
+        if let Some(expanded_selections) = stack.pop() {
+            self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_anchors(expanded_selections.previous.to_vec());
+            });
+        }
+        self.select_larger_bracket_scope_stack = stack;
+    }
+
+    /// Clears `stack` unless the current selections are the ones produced by its most
+    /// recent expansion, since the selections have been changed by other means otherwise.
+    fn discard_stale_selection_expansions(
+        &self,
+        stack: &mut Vec<ExpandedSelections>,
+        cx: &AppContext,
+    ) {
+        let Some(last) = stack.last() else {
+            return;
+        };
+
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let current_selections = self.selections.all::<usize>(cx);
+        let is_current = last.expanded.len() == current_selections.len()
+            && last
+                .expanded
+                .iter()
+                .zip(&current_selections)
+                .all(|(expanded, current)| {
+                    expanded.start.to_offset(&buffer) == current.start
+                        && expanded.end.to_offset(&buffer) == current.end
+                });
+        if !is_current {
+            stack.clear();
+        }
+    }
+
     fn refresh_runnables(&mut self, cx: &mut ViewContext<Self>) -> Task<()> {
         let project = self.project.clone();
         cx.spawn(|this, mut cx| async move {
@@ -8531,6 +8882,9 @@ impl Editor {
                     cx.open_url(&url);
                     Task::ready(Ok(None))
                 }
+                HoverLink::File(project_path, position) => {
+                    self.compute_file_location(project_path, position, cx)
+                }
             };
             cx.spawn(|editor, mut cx| async move {
                 let target = target_task.await.context("target resolution task")?;
@@ -8624,6 +8978,7 @@ impl Editor {
                                 }),
                                 HoverLink::InlayHint(_, _) => None,
                                 HoverLink::Url(_) => None,
+                                HoverLink::File(..) => None,
                             })
                             .unwrap_or(tab_kind.to_string());
                         let location_tasks = definitions
@@ -8634,6 +8989,9 @@ impl Editor {
                                     editor.compute_target_location(lsp_location, server_id, cx)
                                 }
                                 HoverLink::Url(_) => Task::ready(Ok(None)),
+                                HoverLink::File(project_path, position) => {
+                                    editor.compute_file_location(project_path, position, cx)
+                                }
                             })
                             .collect::<Vec<_>>();
                         (title, location_tasks, editor.workspace().clone())
@@ -8716,6 +9074,29 @@ impl Editor {
         })
     }
 
+    fn compute_file_location(
+        &self,
+        project_path: ProjectPath,
+        position: Point,
+        cx: &mut ViewContext<Editor>,
+    ) -> Task<anyhow::Result<Option<Location>>> {
+        let Some(project) = self.project.clone() else {
+            return Task::Ready(Some(Ok(None)));
+        };
+
+        let open_buffer = project.update(cx, |project, cx| project.open_buffer(project_path, cx));
+        cx.spawn(move |_, mut cx| async move {
+            let buffer = open_buffer.await.context("open buffer")?;
+            let anchor = buffer.update(&mut cx, |buffer, _| {
+                buffer.anchor_before(buffer.clip_point(position, Bias::Left))
+            })?;
+            Ok(Some(Location {
+                buffer,
+                range: anchor..anchor,
+            }))
+        })
+    }
+
     pub fn find_all_references(
         &mut self,
         _: &FindAllReferences,
@@ -9686,6 +10067,11 @@ impl Editor {
             return wrap_guides;
         }
 
+        if let Some(guides) = &self.wrap_guides_override {
+            wrap_guides.extend(guides.iter().map(|guide| (*guide, false)));
+            return wrap_guides;
+        }
+
         let settings = self.buffer.read(cx).settings_at(0, cx);
         if settings.show_wrap_guides {
             if let SoftWrap::Column(soft_wrap) = self.soft_wrap_mode(cx) {
@@ -9810,6 +10196,12 @@ impl Editor {
         cx.notify();
     }
 
+    /// Shows the given wrap guides instead of the ones configured in the settings.
+    pub fn set_wrap_guides(&mut self, wrap_guides: Vec<usize>, cx: &mut ViewContext<Self>) {
+        self.wrap_guides_override = Some(wrap_guides);
+        cx.notify();
+    }
+
     pub fn set_show_indent_guides(&mut self, show_indent_guides: bool, cx: &mut ViewContext<Self>) {
         self.show_indent_guides = Some(show_indent_guides);
         cx.notify();
@@ -10211,6 +10603,20 @@ impl Editor {
         self.background_highlights_in_range(start..end, &snapshot, theme)
     }
 
+    /// Returns the occurrences of the symbol under the newest cursor that are read from and
+    /// written to, as highlighted once the cursor stops moving.
+    pub fn document_highlights(&self) -> (Arc<[Range<Anchor>]>, Arc<[Range<Anchor>]>) {
+        let ranges = |type_id| {
+            self.background_highlights
+                .get(&type_id)
+                .map_or_else(|| Arc::from([]), |(_, ranges)| ranges.clone())
+        };
+        (
+            ranges(TypeId::of::<DocumentHighlightRead>()),
+            ranges(TypeId::of::<DocumentHighlightWrite>()),
+        )
+    }
+
     fn document_highlights_for_position<'a>(
         &'a self,
         position: Anchor,
@@ -10453,11 +10859,17 @@ impl Editor {
                 self.active_indent_guides_state.dirty = true;
                 self.refresh_active_diagnostics(cx);
                 self.refresh_code_actions(cx);
+                refresh_code_lenses(self, cx);
+                refresh_color_swatches(self, cx);
+                refresh_detected_links(self, cx);
                 if self.has_active_inline_completion(cx) {
                     self.update_visible_inline_completion(cx);
                 }
                 cx.emit(EditorEvent::BufferEdited);
                 cx.emit(SearchEvent::MatchesInvalidated);
+                if self.focus_handle.is_focused(cx) {
+                    recent_edits::record_edit(self, cx);
+                }
 
                 if *singleton_buffer_edited {
                     if let Some(project) = &self.project {
@@ -10498,6 +10910,10 @@ impl Editor {
                 excerpts,
             } => {
                 self.tasks_update_task = Some(self.refresh_runnables(cx));
+                refresh_code_lenses(self, cx);
+                refresh_color_swatches(self, cx);
+                refresh_detected_links(self, cx);
+                refresh_inline_diagnostics(self, cx);
                 cx.emit(EditorEvent::ExcerptsAdded {
                     buffer: buffer.clone(),
                     predecessor: *predecessor,
@@ -10507,34 +10923,44 @@ impl Editor {
             }
             multi_buffer::Event::ExcerptsRemoved { ids } => {
                 self.refresh_inlay_hints(InlayHintRefreshReason::ExcerptsRemoved(ids.clone()), cx);
+                refresh_inline_diagnostics(self, cx);
                 cx.emit(EditorEvent::ExcerptsRemoved { ids: ids.clone() })
             }
             multi_buffer::Event::Reparsed => {
                 self.tasks_update_task = Some(self.refresh_runnables(cx));
+                self.minimap_state.invalidate();
 
                 cx.emit(EditorEvent::Reparsed);
             }
             multi_buffer::Event::LanguageChanged => {
+                self.minimap_state.invalidate();
                 cx.emit(EditorEvent::Reparsed);
                 cx.notify();
             }
             multi_buffer::Event::DirtyChanged => cx.emit(EditorEvent::DirtyChanged),
-            multi_buffer::Event::Saved => cx.emit(EditorEvent::Saved),
+            multi_buffer::Event::Saved => {
+                cx.emit(EditorEvent::Saved);
+                announce_save(self, cx);
+            }
             multi_buffer::Event::FileHandleChanged | multi_buffer::Event::Reloaded => {
                 cx.emit(EditorEvent::TitleChanged)
             }
             multi_buffer::Event::DiffBaseChanged => {
+                self.refresh_staged_hunks(true, cx);
                 self.scrollbar_marker_state.dirty = true;
                 cx.emit(EditorEvent::DiffBaseChanged);
                 cx.notify();
             }
             multi_buffer::Event::DiffUpdated { buffer } => {
                 self.sync_expanded_diff_hunks(buffer.clone(), cx);
+                self.refresh_staged_hunks(false, cx);
                 cx.notify();
             }
             multi_buffer::Event::Closed => cx.emit(EditorEvent::Closed),
             multi_buffer::Event::DiagnosticsUpdated => {
                 self.refresh_active_diagnostics(cx);
+                refresh_inline_diagnostics(self, cx);
+                announce_diagnostics(self, cx);
                 self.scrollbar_marker_state.dirty = true;
                 cx.notify();
             }
@@ -10560,6 +10986,13 @@ impl Editor {
         self.scroll_manager.vertical_scroll_margin = editor_settings.vertical_scroll_margin;
         self.show_breadcrumbs = editor_settings.toolbar.breadcrumbs;
         self.current_line_highlight = editor_settings.current_line_highlight;
+        if editor_settings.code_lens != self.code_lens_state.is_enabled() {
+            refresh_code_lenses(self, cx);
+        }
+        if editor_settings.color_swatches != self.color_swatch_state.is_enabled() {
+            refresh_color_swatches(self, cx);
+        }
+        refresh_inline_diagnostics(self, cx);
 
         if self.mode == EditorMode::Full {
             let inline_blame_enabled = ProjectSettings::get_global(cx).git.inline_blame_enabled();
@@ -10947,6 +11380,9 @@ impl Editor {
     }
 
     pub fn handle_blur(&mut self, cx: &mut ViewContext<Self>) {
+        if self.is_composing(cx) {
+            self.end_composition(cx);
+        }
         self.blink_manager.update(cx, BlinkManager::disable);
         self.buffer
             .update(cx, |buffer, cx| buffer.remove_active_selections(cx));
@@ -11446,6 +11882,8 @@ pub enum EditorEvent {
     TransactionBegun {
         transaction_id: clock::Lamport,
     },
+    /// A message for screen readers to speak.
+    Announce(Announcement),
 }
 
 impl EventEmitter<EditorEvent> for Editor {}
@@ -11550,8 +11988,7 @@ impl ViewInputHandler for Editor {
     }
 
     fn unmark_text(&mut self, cx: &mut ViewContext<Self>) {
-        self.clear_highlights::<InputComposition>(cx);
-        self.ime_transaction.take();
+        self.end_composition(cx);
     }
 
     fn replace_text_in_range(
@@ -11627,6 +12064,7 @@ impl ViewInputHandler for Editor {
             return;
         }
 
+        self.start_composition(cx);
         let transaction = self.transact(cx, |this, cx| {
             let ranges_to_replace = if let Some(mut marked_ranges) = this.marked_text_ranges(cx) {
                 let snapshot = this.buffer.read(cx).read(cx);
@@ -8,10 +8,16 @@ pub struct EditorSettings {
     pub cursor_blink: bool,
     pub current_line_highlight: CurrentLineHighlight,
     pub hover_popover_enabled: bool,
+    pub highlight_occurrences: bool,
+    pub highlight_occurrences_case_sensitive: bool,
     pub show_completions_on_input: bool,
     pub show_completion_documentation: bool,
     pub completion_documentation_secondary_query_debounce: u64,
     pub use_on_type_format: bool,
+    pub show_signature_help_on_input: bool,
+    pub code_lens: bool,
+    pub color_swatches: bool,
+    pub inline_diagnostics: InlineDiagnostics,
     pub toolbar: Toolbar,
     pub scrollbar: Scrollbar,
     pub gutter: Gutter,
@@ -24,6 +30,8 @@ pub struct EditorSettings {
     pub expand_excerpt_lines: u32,
     #[serde(default)]
     pub double_click_in_multibuffer: DoubleClickInMultibuffer,
+    #[serde(default)]
+    pub cursor_movement: CursorMovement,
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
@@ -63,6 +71,18 @@ pub enum DoubleClickInMultibuffer {
     Open,
 }
 
+/// How the left and right arrow keys move the cursor through right-to-left text.
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorMovement {
+    /// Move to the preceding or following character in the text, which is displayed
+    /// to the right or left of the cursor in right-to-left text.
+    #[default]
+    Logical,
+    /// Move to the character that is displayed to the left or right of the cursor.
+    Visual,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct Toolbar {
     pub breadcrumbs: bool,
@@ -79,6 +99,12 @@ pub struct Scrollbar {
     pub cursors: bool,
 }
 
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct InlineDiagnostics {
+    pub enabled: bool,
+    pub max_length: usize,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct Gutter {
     pub line_numbers: bool,
@@ -129,6 +155,15 @@ pub struct EditorSettingsContent {
     ///
     /// Default: true
     pub hover_popover_enabled: Option<bool>,
+    /// Whether to highlight the other visible occurrences of the word under the
+    /// cursor, or of the selected text, after the cursor stops moving.
+    ///
+    /// Default: false
+    pub highlight_occurrences: Option<bool>,
+    /// Whether occurrences are only highlighted when their case matches.
+    ///
+    /// Default: true
+    pub highlight_occurrences_case_sensitive: Option<bool>,
     /// Whether to pop the completions menu while typing in an editor without
     /// explicitly requesting it.
     ///
@@ -149,6 +184,23 @@ pub struct EditorSettingsContent {
     ///
     /// Default: true
     pub use_on_type_format: Option<bool>,
+    /// Whether to show the signatures of the function being called after typing
+    /// one of the language server's signature help trigger characters, like `(`.
+    ///
+    /// Default: true
+    pub show_signature_help_on_input: Option<bool>,
+    /// Whether to show the code lenses of language servers, such as running a test or
+    /// listing references, above the lines they apply to.
+    ///
+    /// Default: false
+    pub code_lens: Option<bool>,
+    /// Whether to show a swatch of the colors written in buffers, such as `#ff8800`, under
+    /// them.
+    ///
+    /// Default: true
+    pub color_swatches: Option<bool>,
+    /// Inline diagnostics related settings
+    pub inline_diagnostics: Option<InlineDiagnosticsContent>,
     /// Toolbar related settings
     pub toolbar: Option<ToolbarContent>,
     /// Scrollbar related settings
@@ -193,6 +245,11 @@ pub struct EditorSettingsContent {
     ///
     /// Default: select
     pub double_click_in_multibuffer: Option<DoubleClickInMultibuffer>,
+
+    /// How the left and right arrow keys move the cursor through right-to-left text.
+    ///
+    /// Default: logical
+    pub cursor_movement: Option<CursorMovement>,
 }
 
 // Toolbar related settings
@@ -237,6 +294,19 @@ pub struct ScrollbarContent {
     pub cursors: Option<bool>,
 }
 
+/// Inline diagnostics related settings
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct InlineDiagnosticsContent {
+    /// Whether to show the most severe diagnostic message of each line after the line.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+    /// The number of characters after which the messages are truncated.
+    ///
+    /// Default: 120
+    pub max_length: Option<usize>,
+}
+
 /// Gutter related settings
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct GutterContent {
@@ -327,6 +327,75 @@ fn test_ime_composition(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_marked_text_api(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let buffer = cx.new_model(|cx| {
+        let mut buffer = language::Buffer::local("abc", cx);
+        buffer.set_group_interval(Duration::ZERO);
+        buffer
+    });
+    let operation_count = Rc::new(RefCell::new(0));
+    cx.update(|cx| {
+        let operation_count = operation_count.clone();
+        cx.subscribe(&buffer, move |_, event, _| {
+            if let language::Event::Operation(_) = event {
+                *operation_count.borrow_mut() += 1;
+            }
+        })
+        .detach();
+    });
+
+    let multibuffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+    cx.add_window(|cx| {
+        let mut editor = build_editor(multibuffer.clone(), cx);
+        editor.change_selections(None, cx, |s| s.select_ranges([3..3]));
+        editor.handle_input("d", cx);
+        let sent_operations = *operation_count.borrow();
+
+        // The composition's edits aren't sent until it's committed.
+        editor.set_marked_text("n", None, cx);
+        editor.set_marked_text("ni", None, cx);
+        editor.replace_marked_range(1..2, "ǐ", None, cx);
+        assert!(editor.is_composing(cx));
+        assert_eq!(editor.text(cx), "abcdnǐ");
+        assert!(buffer.read(cx).is_holding_operations());
+        assert_eq!(*operation_count.borrow(), sent_operations);
+
+        // Undoing cancels the composition, and doesn't leave it to redo.
+        editor.undo(&Default::default(), cx);
+        assert!(!editor.is_composing(cx));
+        assert_eq!(editor.text(cx), "abcd");
+        assert!(!buffer.read(cx).is_holding_operations());
+        assert!(*operation_count.borrow() > sent_operations);
+        editor.redo(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "abcd");
+
+        // Committing sends the composition, which is then undone at once.
+        editor.set_marked_text("n", None, cx);
+        editor.set_marked_text("ni", None, cx);
+        editor.commit_marked_text("你", cx);
+        assert!(!editor.is_composing(cx));
+        assert_eq!(editor.text(cx), "abcd你");
+        assert!(!buffer.read(cx).is_holding_operations());
+        editor.undo(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "abcd");
+        editor.undo(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "abc");
+
+        // Edits are sent when the composition is abandoned by blurring the editor.
+        editor.set_marked_text("n", None, cx);
+        assert!(buffer.read(cx).is_holding_operations());
+        editor.handle_blur(cx);
+        assert!(!editor.is_composing(cx));
+        assert!(!buffer.read(cx).is_holding_operations());
+        assert_eq!(editor.text(cx), "abcn");
+
+        editor
+    });
+}
+
 #[gpui::test]
 fn test_selection_with_mouse(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -543,6 +612,70 @@ fn test_clone(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+fn test_cloned_editors_share_buffer(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("one\ntwo\nthree\n", cx);
+        build_editor(buffer, cx)
+    });
+    let cloned_editor = editor
+        .update(cx, |editor, cx| {
+            cx.open_window(Default::default(), |cx| cx.new_view(|cx| editor.clone(cx)))
+        })
+        .unwrap();
+
+    // Edits made in either editor appear in both.
+    _ = editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| s.select_ranges([0..0]));
+        editor.handle_input("zero ", cx);
+    });
+    assert_eq!(
+        cloned_editor
+            .update(cx, |editor, cx| editor.text(cx))
+            .unwrap(),
+        "zero one\ntwo\nthree\n"
+    );
+
+    // Selections and folds belong to each editor.
+    _ = cloned_editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(2, 0)..Point::new(2, 0)])
+        });
+        editor.fold_ranges(
+            [(Point::new(1, 0)..Point::new(2, 0), FoldPlaceholder::test())],
+            true,
+            cx,
+        );
+    });
+    assert_eq!(
+        editor
+            .update(cx, |editor, cx| editor.selections.ranges::<Point>(cx))
+            .unwrap(),
+        [Point::new(0, 5)..Point::new(0, 5)]
+    );
+    assert_eq!(
+        editor
+            .update(cx, |editor, cx| editor.display_text(cx))
+            .unwrap(),
+        "zero one\ntwo\nthree\n"
+    );
+    assert_eq!(
+        cloned_editor
+            .update(cx, |editor, cx| editor.display_text(cx))
+            .unwrap(),
+        "zero one\n⋯three\n"
+    );
+
+    // The editors share the buffer's undo history.
+    _ = cloned_editor.update(cx, |editor, cx| editor.undo(&Undo, cx));
+    assert_eq!(
+        editor.update(cx, |editor, cx| editor.text(cx)).unwrap(),
+        "one\ntwo\nthree\n"
+    );
+}
+
 #[gpui::test]
 async fn test_navigation_history(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -2534,6 +2667,77 @@ fn test_indent_outdent_with_excerpts(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_move_and_delete_by_grapheme(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // The line is long enough for some of the emoji ZWJ sequences to span the rope's chunks.
+    let family = "👨\u{200d}👩\u{200d}👧";
+    let accented = "e\u{301}\u{302}";
+    cx.set_state(&format!("ˇ{}{accented}", family.repeat(16)));
+    for count in 1..=16 {
+        cx.update_editor(|editor, cx| editor.move_right(&MoveRight, cx));
+        cx.assert_editor_state(&format!(
+            "{}ˇ{}{accented}",
+            family.repeat(count),
+            family.repeat(16 - count)
+        ));
+    }
+    cx.update_editor(|editor, cx| editor.move_right(&MoveRight, cx));
+    cx.assert_editor_state(&format!("{}{accented}ˇ", family.repeat(16)));
+
+    cx.update_editor(|editor, cx| editor.move_left(&MoveLeft, cx));
+    cx.assert_editor_state(&format!("{}ˇ{accented}", family.repeat(16)));
+    cx.update_editor(|editor, cx| editor.move_left(&MoveLeft, cx));
+    cx.assert_editor_state(&format!("{}ˇ{family}{accented}", family.repeat(15)));
+
+    cx.update_editor(|editor, cx| editor.backspace(&Backspace, cx));
+    cx.assert_editor_state(&format!("{}ˇ{family}{accented}", family.repeat(14)));
+    cx.update_editor(|editor, cx| editor.delete(&Delete, cx));
+    cx.assert_editor_state(&format!("{}ˇ{accented}", family.repeat(14)));
+    cx.update_editor(|editor, cx| editor.delete(&Delete, cx));
+    cx.assert_editor_state(&format!("{}ˇ", family.repeat(14)));
+}
+
+#[gpui::test]
+async fn test_visual_cursor_movement(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    _ = cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.cursor_movement = Some(editor_settings::CursorMovement::Visual);
+            });
+        })
+    });
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // The Hebrew word is displayed right to left, so moving right visits its letters from
+    // last to first.
+    cx.set_state("abcˇ \u{5e9}\u{5dc}\u{5d5}\u{5dd}\nx");
+    cx.update_editor(|editor, cx| editor.move_right(&MoveRight, cx));
+    cx.assert_editor_state("abc \u{5e9}\u{5dc}\u{5d5}\u{5dd}ˇ\nx");
+    cx.update_editor(|editor, cx| editor.move_right(&MoveRight, cx));
+    cx.assert_editor_state("abc \u{5e9}\u{5dc}\u{5d5}ˇ\u{5dd}\nx");
+    for _ in 0..3 {
+        cx.update_editor(|editor, cx| editor.move_right(&MoveRight, cx));
+    }
+    cx.assert_editor_state("abc ˇ\u{5e9}\u{5dc}\u{5d5}\u{5dd}\nx");
+    cx.update_editor(|editor, cx| editor.move_right(&MoveRight, cx));
+    cx.assert_editor_state("abc \u{5e9}\u{5dc}\u{5d5}\u{5dd}\nˇx");
+
+    cx.update_editor(|editor, cx| editor.move_left(&MoveLeft, cx));
+    cx.assert_editor_state("abc \u{5e9}\u{5dc}\u{5d5}\u{5dd}ˇ\nx");
+    cx.update_editor(|editor, cx| editor.move_left(&MoveLeft, cx));
+    cx.assert_editor_state("abcˇ \u{5e9}\u{5dc}\u{5d5}\u{5dd}\nx");
+
+    cx.update_editor(|editor, cx| {
+        editor.select_right(&SelectRight, cx);
+        editor.select_right(&SelectRight, cx);
+    });
+    cx.assert_editor_state("abc« \u{5e9}\u{5dc}\u{5d5}ˇ»\u{5dd}\nx");
+}
+
 #[gpui::test]
 async fn test_backspace(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -2915,6 +3119,45 @@ async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
         5ˇ»
     "});
 
+    // Test sort_lines_numerically()
+    cx.set_state(indoc! {"
+        «10 ten
+        2 two
+        none
+        1 oneˇ»
+    "});
+    cx.update_editor(|e, cx| e.sort_lines_numerically(&SortLinesNumerically, cx));
+    cx.assert_editor_state(indoc! {"
+        «1 one
+        2 two
+        10 ten
+        noneˇ»
+    "});
+
+    // Test sort_lines_naturally()
+    cx.set_state(indoc! {"
+        «item10
+        item9
+        item1ˇ»
+    "});
+    cx.update_editor(|e, cx| e.sort_lines_naturally(&SortLinesNaturally, cx));
+    cx.assert_editor_state(indoc! {"
+        «item1
+        item9
+        item10ˇ»
+    "});
+
+    // Test align_lines()
+    cx.set_state(indoc! {"
+        «a = 1
+        bcd = 2ˇ»
+    "});
+    cx.update_editor(|e, cx| e.align_lines(&AlignLines { delimiter: '=' }, cx));
+    cx.assert_editor_state(indoc! {"
+        «a   = 1
+        bcd = 2ˇ»
+    "});
+
     // Skip testing shuffle_line()
 
     // From here on out, test more complex cases of manipulate_lines() with a single driver method: sort_lines_case_sensitive()
@@ -3704,6 +3947,89 @@ async fn test_clipboard(cx: &mut gpui::TestAppContext) {
         tˇhe lazy dog"});
 }
 
+#[gpui::test]
+async fn test_paste_below(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Copy with a single cursor only, which writes the whole line into the clipboard.
+    cx.set_state(indoc! {"
+        The quick brown
+        fox juˇmps over
+        the lazy dog"});
+    cx.update_editor(|e, cx| e.copy(&Copy, cx));
+
+    // Full-line clipboard text is inserted after the line containing each empty selection.
+    cx.set_state(indoc! {"
+        Tˇhe quick brown
+        fox jumps over
+        tˇhe lazy dog"});
+    cx.update_editor(|e, cx| e.paste_below(&PasteBelow, cx));
+    cx.assert_editor_state(indoc! {"
+        Tˇhe quick brown
+        fox jumps over
+        fox jumps over
+        tˇhe lazy dog
+        fox jumps over"});
+
+    // Non-empty selections are replaced, as with a regular paste.
+    cx.set_state(indoc! {"
+        The quick brown
+        «foˇ»x jumps over"});
+    cx.update_editor(|e, cx| e.paste_below(&PasteBelow, cx));
+    cx.assert_editor_state(indoc! {"
+        The quick brown
+        fox jumps over
+        ˇx jumps over"});
+}
+
+#[gpui::test]
+async fn test_paste_and_match_indentation(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_indents_query(r#"(_ "(" ")" @end) @indent"#)
+        .unwrap(),
+    );
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // Cut a block whose lines aren't indented consistently.
+    cx.set_state(indoc! {"
+        «d(
+                e,
+          f
+        )ˇ»
+    "});
+    cx.update_editor(|e, cx| e.cut(&Cut, cx));
+
+    // Every pasted line is indented as the language suggests at its destination, rather than
+    // keeping its indentation relative to the first line.
+    cx.set_state(indoc! {"
+
+        const a: B = (
+            c(),
+            ˇ
+        );
+    "});
+    cx.update_editor(|e, cx| e.paste_and_match_indentation(&PasteAndMatchIndentation, cx));
+    cx.assert_editor_state(indoc! {"
+
+        const a: B = (
+            c(),
+            d(
+                e,
+                f
+            )ˇ
+        );
+    "});
+}
+
 #[gpui::test]
 async fn test_paste_multiline(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -3818,6 +4144,58 @@ async fn test_paste_multiline(cx: &mut gpui::TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_paste_with_significant_indentation(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig {
+                significant_indentation: true,
+                ..Default::default()
+            },
+            Some(tree_sitter_rust::language()),
+        )
+        .with_indents_query(r#"(_ "{" "}" @end) @indent"#)
+        .unwrap(),
+    );
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // Text copied from outside of the editor is shifted as a whole, so that its
+    // first line is indented for the destination and the other lines keep their
+    // indentation relative to it.
+    cx.write_to_clipboard(ClipboardItem::new("a\n    b\nc".to_string()));
+    cx.set_state(indoc! {"
+        fn f() {
+            ˇ
+        }
+    "});
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
+    cx.assert_editor_state(indoc! {"
+        fn f() {
+            a
+                b
+            cˇ
+        }
+    "});
+
+    // Matching the destination's indentation doesn't re-indent each line either.
+    cx.set_state(indoc! {"
+        fn f() {
+            ˇ
+        }
+    "});
+    cx.update_editor(|e, cx| e.paste_and_match_indentation(&PasteAndMatchIndentation, cx));
+    cx.assert_editor_state(indoc! {"
+        fn f() {
+            a
+                b
+            cˇ
+        }
+    "});
+}
+
 #[gpui::test]
 fn test_select_all(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -4575,15 +4953,127 @@ async fn test_select_larger_smaller_syntax_node(cx: &mut gpui::TestAppContext) {
 }
 
 #[gpui::test]
-async fn test_autoindent_selections(cx: &mut gpui::TestAppContext) {
+async fn test_select_smaller_syntax_node_after_edit(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(Language::new(
+        LanguageConfig::default(),
+        Some(tree_sitter_rust::language()),
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    cx.set_state(indoc! {"
+        fn a() {
+            let b = cˇde;
+        }
+    "});
+    cx.update_editor(|e, cx| e.select_larger_syntax_node(&SelectLargerSyntaxNode, cx));
+    cx.update_editor(|e, cx| e.select_larger_syntax_node(&SelectLargerSyntaxNode, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            «let b = cde;ˇ»
+        }
+    "});
+
+    // Editing elsewhere doesn't prevent the expanded selections from being shrunk again.
+    cx.update_buffer(|buffer, cx| buffer.edit([(0..0, "// comment\n")], None, cx));
+    cx.update_editor(|e, cx| e.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state(indoc! {"
+        // comment
+        fn a() {
+            let b = «cdeˇ»;
+        }
+    "});
+    cx.update_editor(|e, cx| e.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state(indoc! {"
+        // comment
+        fn a() {
+            let b = cˇde;
+        }
+    "});
+
+    // Changing the selections discards the expansion history.
+    cx.update_editor(|e, cx| e.select_larger_syntax_node(&SelectLargerSyntaxNode, cx));
+    cx.update_editor(|e, cx| e.move_left(&MoveLeft, cx));
+    cx.update_editor(|e, cx| e.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state(indoc! {"
+        // comment
+        fn a() {
+            let b = ˇcde;
+        }
+    "});
+}
+
+#[gpui::test]
+async fn test_select_larger_smaller_bracket_scope(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
 
+    let mut cx = EditorTestContext::new(cx).await;
     let language = Arc::new(
         Language::new(
-            LanguageConfig {
-                brackets: BracketPairConfig {
-                    pairs: vec![
-                        BracketPair {
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_brackets_query(indoc! {r#"
+                ("{" @open "}" @close)
+                ("(" @open ")" @close)
+            "#})
+        .unwrap(),
+    );
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    cx.set_state(indoc! {"
+        fn a() {
+            b(c, ˇd);
+        }
+    "});
+
+    // The contents of the innermost pair are selected before the pair itself.
+    cx.update_editor(|e, cx| e.select_larger_bracket_scope(&SelectLargerBracketScope, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            b(«c, dˇ»);
+        }
+    "});
+    cx.update_editor(|e, cx| e.select_larger_bracket_scope(&SelectLargerBracketScope, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            b«(c, d)ˇ»;
+        }
+    "});
+    cx.update_editor(|e, cx| e.select_larger_bracket_scope(&SelectLargerBracketScope, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {«
+            b(c, d);
+        ˇ»}
+    "});
+
+    cx.update_editor(|e, cx| e.select_smaller_bracket_scope(&SelectSmallerBracketScope, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            b«(c, d)ˇ»;
+        }
+    "});
+    cx.update_editor(|e, cx| e.select_smaller_bracket_scope(&SelectSmallerBracketScope, cx));
+    cx.update_editor(|e, cx| e.select_smaller_bracket_scope(&SelectSmallerBracketScope, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            b(c, ˇd);
+        }
+    "});
+}
+
+#[gpui::test]
+async fn test_autoindent_selections(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig {
+                brackets: BracketPairConfig {
+                    pairs: vec![
+                        BracketPair {
                             start: "{".to_string(),
                             end: "}".to_string(),
                             close: false,
@@ -6393,141 +6883,599 @@ async fn test_concurrent_format_requests(cx: &mut gpui::TestAppContext) {
         .unwrap();
     cx.executor().run_until_parked();
 
-    // Submit a second format request.
-    let format_2 = cx
-        .update_editor(|editor, cx| editor.format(&Format, cx))
-        .unwrap();
-    cx.executor().run_until_parked();
+    // Submit a second format request.
+    let format_2 = cx
+        .update_editor(|editor, cx| editor.format(&Format, cx))
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    // Wait for both format requests to complete
+    cx.executor().advance_clock(Duration::from_millis(200));
+    cx.executor().start_waiting();
+    format_1.await.unwrap();
+    cx.executor().start_waiting();
+    format_2.await.unwrap();
+
+    // The formatting edits only happens once.
+    cx.assert_editor_state(indoc! {"
+        one
+            .twoˇ
+    "});
+}
+
+#[gpui::test]
+async fn test_strip_whitespace_and_format_via_lsp(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.formatter = Some(language_settings::Formatter::Auto)
+    });
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            document_formatting_provider: Some(lsp::OneOf::Left(true)),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+
+    // Set up a buffer white some trailing whitespace and no trailing newline.
+    cx.set_state(
+        &[
+            "one ",   //
+            "twoˇ",   //
+            "three ", //
+            "four",   //
+        ]
+        .join("\n"),
+    );
+
+    // Submit a format request.
+    let format = cx
+        .update_editor(|editor, cx| editor.format(&Format, cx))
+        .unwrap();
+
+    // Record which buffer changes have been sent to the language server
+    let buffer_changes = Arc::new(Mutex::new(Vec::new()));
+    cx.lsp
+        .handle_notification::<lsp::notification::DidChangeTextDocument, _>({
+            let buffer_changes = buffer_changes.clone();
+            move |params, _| {
+                buffer_changes.lock().extend(
+                    params
+                        .content_changes
+                        .into_iter()
+                        .map(|e| (e.range.unwrap(), e.text)),
+                );
+            }
+        });
+
+    // Handle formatting requests to the language server.
+    cx.lsp.handle_request::<lsp::request::Formatting, _, _>({
+        let buffer_changes = buffer_changes.clone();
+        move |_, _| {
+            // When formatting is requested, trailing whitespace has already been stripped,
+            // and the trailing newline has already been added.
+            assert_eq!(
+                &buffer_changes.lock()[1..],
+                &[
+                    (
+                        lsp::Range::new(lsp::Position::new(0, 3), lsp::Position::new(0, 4)),
+                        "".into()
+                    ),
+                    (
+                        lsp::Range::new(lsp::Position::new(2, 5), lsp::Position::new(2, 6)),
+                        "".into()
+                    ),
+                    (
+                        lsp::Range::new(lsp::Position::new(3, 4), lsp::Position::new(3, 4)),
+                        "\n".into()
+                    ),
+                ]
+            );
+
+            // Insert blank lines between each line of the buffer.
+            async move {
+                Ok(Some(vec![
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(1, 0)),
+                        new_text: "\n".into(),
+                    },
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(2, 0), lsp::Position::new(2, 0)),
+                        new_text: "\n".into(),
+                    },
+                ]))
+            }
+        }
+    });
+
+    // After formatting the buffer, the trailing whitespace is stripped,
+    // a newline is appended, and the edits provided by the language server
+    // have been applied.
+    format.await.unwrap();
+    cx.assert_editor_state(
+        &[
+            "one",   //
+            "",      //
+            "twoˇ",  //
+            "",      //
+            "three", //
+            "four",  //
+            "",      //
+        ]
+        .join("\n"),
+    );
+
+    // Undoing the formatting undoes the trailing whitespace removal, the
+    // trailing newline, and the LSP edits.
+    cx.update_buffer(|buffer, cx| buffer.undo(cx));
+    cx.assert_editor_state(
+        &[
+            "one ",   //
+            "twoˇ",   //
+            "three ", //
+            "four",   //
+        ]
+        .join("\n"),
+    );
+}
+
+#[gpui::test]
+async fn test_signature_help_on_input(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            signature_help_provider: Some(lsp::SignatureHelpOptions {
+                trigger_characters: Some(vec!["(".to_string()]),
+                retrigger_characters: Some(vec![",".to_string()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+
+    // The arguments of the call start after `foo(` on the first line.
+    let _requests =
+        cx.handle_request::<lsp::request::SignatureHelpRequest, _, _>(|_, params, _| async move {
+            let position = params.text_document_position_params.position;
+            if position.line != 0 || position.character < 4 {
+                return Ok(None);
+            }
+            Ok(Some(lsp::SignatureHelp {
+                signatures: vec![lsp::SignatureInformation {
+                    label: "fn foo(a: u32, b: u32)".to_string(),
+                    documentation: None,
+                    parameters: Some(vec![
+                        lsp::ParameterInformation {
+                            label: lsp::ParameterLabel::Simple("a: u32".to_string()),
+                            documentation: None,
+                        },
+                        lsp::ParameterInformation {
+                            label: lsp::ParameterLabel::LabelOffsets([15, 21]),
+                            documentation: None,
+                        },
+                    ]),
+                    active_parameter: None,
+                }],
+                active_signature: Some(0),
+                active_parameter: Some(if position.character > 5 { 1 } else { 0 }),
+            }))
+        });
+    let active_parameter = |cx: &mut EditorLspTestContext| {
+        cx.editor(|editor, _| {
+            let popover = editor.signature_help()?;
+            let signature = popover.help.active_signature()?;
+            let range = signature.active_parameter_range()?;
+            Some(signature.label[range].to_string())
+        })
+    };
+
+    cx.set_state(indoc! {"
+        fooˇ
+        bar
+    "});
+    cx.simulate_keystroke("1");
+    cx.run_until_parked();
+    assert_eq!(active_parameter(&mut cx), None, "Not a trigger character");
+
+    cx.simulate_keystroke("(");
+    cx.run_until_parked();
+    assert_eq!(active_parameter(&mut cx).as_deref(), Some("a: u32"));
+
+    // The active parameter follows the edits.
+    cx.simulate_keystroke("1");
+    cx.run_until_parked();
+    assert_eq!(active_parameter(&mut cx).as_deref(), Some("a: u32"));
+    cx.simulate_keystroke(",");
+    cx.run_until_parked();
+    assert_eq!(active_parameter(&mut cx).as_deref(), Some("b: u32"));
+
+    // Signature help is hidden once the cursor leaves the call.
+    cx.update_editor(|editor, cx| editor.move_down(&MoveDown, cx));
+    cx.run_until_parked();
+    cx.editor(|editor, _| assert!(editor.signature_help().is_none()));
+
+    // It can also be dismissed explicitly.
+    cx.update_editor(|editor, cx| editor.move_up(&MoveUp, cx));
+    cx.simulate_keystroke("(");
+    cx.run_until_parked();
+    assert!(active_parameter(&mut cx).is_some());
+    cx.update_editor(|editor, cx| editor.cancel(&Cancel, cx));
+    cx.editor(|editor, _| assert!(editor.signature_help().is_none()));
+}
+
+#[gpui::test]
+async fn test_code_lens(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    _ = cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.code_lens = Some(true);
+            });
+        })
+    });
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            code_lens_provider: Some(lsp::CodeLensOptions {
+                resolve_provider: Some(true),
+            }),
+            execute_command_provider: Some(lsp::ExecuteCommandOptions {
+                commands: vec!["test.run".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+
+    // The second lens is only given a command once it's resolved.
+    let lens_range = lsp::Range::new(lsp::Position::new(1, 3), lsp::Position::new(1, 11));
+    let _lens_requests =
+        cx.handle_request::<lsp::request::CodeLensRequest, _, _>(move |_, _, _| async move {
+            Ok(Some(vec![
+                lsp::CodeLens {
+                    range: lens_range,
+                    command: Some(lsp::Command {
+                        title: "Run".to_string(),
+                        command: "test.run".to_string(),
+                        arguments: None,
+                    }),
+                    data: None,
+                },
+                lsp::CodeLens {
+                    range: lens_range,
+                    command: None,
+                    data: Some(json!(1)),
+                },
+            ]))
+        });
+    let _resolve_requests =
+        cx.handle_request::<lsp::request::CodeLensResolve, _, _>(|_, lens, _| async move {
+            Ok(lsp::CodeLens {
+                command: Some(lsp::Command {
+                    title: "1 reference".to_string(),
+                    command: "editor.action.showReferences".to_string(),
+                    arguments: None,
+                }),
+                ..lens
+            })
+        });
+    let lenses = |cx: &mut EditorLspTestContext| {
+        cx.update_editor(|editor, cx| {
+            editor
+                .code_lens_state
+                .lenses()
+                .map(|(buffer, lens)| {
+                    (
+                        lens.range.start.to_point(buffer.read(cx)),
+                        lens.command().unwrap().title.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    cx.set_state(indoc! {"
+        ˇ// tests
+        fn test_foo() {}
+    "});
+    cx.executor().advance_clock(CODE_LENS_DEBOUNCE_TIMEOUT);
+    cx.run_until_parked();
+    assert_eq!(
+        lenses(&mut cx),
+        vec![
+            (Point::new(1, 3), "Run".to_string()),
+            (Point::new(1, 3), "1 reference".to_string()),
+        ]
+    );
+
+    // The lenses move with the text before they're requested again.
+    cx.update_editor(|editor, cx| editor.newline(&Newline, cx));
+    assert_eq!(
+        lenses(&mut cx),
+        vec![
+            (Point::new(2, 3), "Run".to_string()),
+            (Point::new(2, 3), "1 reference".to_string()),
+        ]
+    );
+
+    // Commands that the editor doesn't implement are executed by the server.
+    let mut execute_requests =
+        cx.handle_request::<lsp::request::ExecuteCommand, _, _>(|_, params, _| async move {
+            assert_eq!(params.command, "test.run");
+            Ok(None)
+        });
+    cx.update_editor(|editor, cx| {
+        let (buffer, lens) = editor.code_lens_state.lenses().next().cloned().unwrap();
+        run_code_lens(editor, buffer, lens, cx);
+    });
+    execute_requests.next().await.unwrap();
+}
+
+#[gpui::test]
+async fn test_color_swatches(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            color_provider: Some(lsp::ColorProviderCapability::Simple(true)),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+
+    // The server reports a named color once, and no colors afterwards.
+    let request_count = Arc::new(AtomicUsize::new(0));
+    let _color_requests = cx.handle_request::<lsp::request::DocumentColor, _, _>({
+        let request_count = request_count.clone();
+        move |_, _, _| {
+            let is_first_request = request_count.fetch_add(1, atomic::Ordering::SeqCst) == 0;
+            async move {
+                Ok(if is_first_request {
+                    vec![lsp::ColorInformation {
+                        range: lsp::Range::new(
+                            lsp::Position::new(0, 17),
+                            lsp::Position::new(0, 20),
+                        ),
+                        color: lsp::Color {
+                            red: 1.,
+                            green: 0.,
+                            blue: 0.,
+                            alpha: 1.,
+                        },
+                    }]
+                } else {
+                    Vec::new()
+                })
+            }
+        }
+    });
+    let swatches = |cx: &mut EditorLspTestContext| {
+        cx.update_editor(|editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            editor
+                .color_swatches()
+                .iter()
+                .map(|(range, color)| {
+                    (
+                        snapshot.text_for_range(range.clone()).collect::<String>(),
+                        *color,
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    cx.set_state(r#"const A: &str = "ˇred";"#);
+    cx.executor().advance_clock(COLOR_SWATCHES_DEBOUNCE_TIMEOUT);
+    cx.run_until_parked();
+    assert_eq!(
+        swatches(&mut cx),
+        [("red".to_string(), gpui::rgb(0xff0000))]
+    );
+
+    // Colors that aren't in one of the formats become hex, and then cycle through the
+    // formats. Once the server stops reporting colors, they're found in the text.
+    for expected_text in [
+        r##"const A: &str = "#ff0000";"##,
+        r#"const A: &str = "rgb(255, 0, 0)";"#,
+        r#"const A: &str = "hsl(0, 100%, 50%)";"#,
+        r##"const A: &str = "#ff0000";"##,
+    ] {
+        cx.update_editor(|editor, cx| editor.convert_color_format(&ConvertColorFormat, cx));
+        assert_eq!(cx.buffer_text(), expected_text);
+        cx.executor().advance_clock(COLOR_SWATCHES_DEBOUNCE_TIMEOUT);
+        cx.run_until_parked();
+        assert_eq!(swatches(&mut cx).len(), 1);
+    }
+}
+
+#[gpui::test]
+async fn test_inline_diagnostics(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    _ = cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.inline_diagnostics = Some(editor_settings::InlineDiagnosticsContent {
+                    enabled: Some(true),
+                    max_length: Some(12),
+                });
+            });
+        })
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let project = cx.update_editor(|editor, _| editor.project.clone().unwrap());
+    cx.set_state(indoc! {"
+        ˇlet a = b;
+        let c = d;
+        let e = f;
+    "});
+
+    let diagnostic = |row, severity, message: &str| lsp::Diagnostic {
+        range: lsp::Range::new(lsp::Position::new(row, 4), lsp::Position::new(row, 5)),
+        severity: Some(severity),
+        message: message.to_string(),
+        ..Default::default()
+    };
+    _ = cx.update(|cx| {
+        project.update(cx, |project, cx| {
+            project
+                .update_diagnostics(
+                    LanguageServerId(0),
+                    lsp::PublishDiagnosticsParams {
+                        uri: lsp::Url::from_file_path("/root/file").unwrap(),
+                        version: None,
+                        diagnostics: vec![
+                            diagnostic(0, lsp::DiagnosticSeverity::WARNING, "unused"),
+                            diagnostic(0, lsp::DiagnosticSeverity::ERROR, "not found"),
+                            diagnostic(1, lsp::DiagnosticSeverity::WARNING, "a long message"),
+                            diagnostic(2, lsp::DiagnosticSeverity::HINT, "hint"),
+                            diagnostic(2, lsp::DiagnosticSeverity::INFORMATION, "one\ntwo"),
+                        ],
+                    },
+                    &[],
+                    cx,
+                )
+                .unwrap()
+        })
+    });
+    cx.run_until_parked();
+
+    let inline_diagnostics = |cx: &mut EditorTestContext| {
+        cx.update_editor(|editor, cx| {
+            editor
+                .inline_diagnostics(MultiBufferRow(0)..MultiBufferRow(3), cx)
+                .into_iter()
+                .map(|diagnostic| {
+                    (
+                        diagnostic.row.0,
+                        diagnostic.severity,
+                        diagnostic.message.to_string(),
+                        diagnostic.is_truncated,
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(
+        inline_diagnostics(&mut cx),
+        [
+            (
+                0,
+                lsp::DiagnosticSeverity::ERROR,
+                "not found".to_string(),
+                false
+            ),
+            (
+                1,
+                lsp::DiagnosticSeverity::WARNING,
+                "a long mess…".to_string(),
+                true
+            ),
+            (
+                2,
+                lsp::DiagnosticSeverity::INFORMATION,
+                "one".to_string(),
+                true
+            ),
+        ]
+    );
 
-    // Wait for both format requests to complete
-    cx.executor().advance_clock(Duration::from_millis(200));
-    cx.executor().start_waiting();
-    format_1.await.unwrap();
-    cx.executor().start_waiting();
-    format_2.await.unwrap();
+    // Diagnostics follow their lines as they're edited.
+    cx.simulate_input("\n");
+    assert_eq!(
+        inline_diagnostics(&mut cx)
+            .into_iter()
+            .map(|(row, ..)| row)
+            .collect::<Vec<_>>(),
+        [1, 2]
+    );
 
-    // The formatting edits only happens once.
-    cx.assert_editor_state(indoc! {"
-        one
-            .twoˇ
-    "});
+    _ = cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.inline_diagnostics = Some(editor_settings::InlineDiagnosticsContent {
+                    enabled: Some(false),
+                    max_length: None,
+                });
+            });
+        })
+    });
+    assert_eq!(inline_diagnostics(&mut cx), []);
 }
 
 #[gpui::test]
-async fn test_strip_whitespace_and_format_via_lsp(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |settings| {
-        settings.defaults.formatter = Some(language_settings::Formatter::Auto)
-    });
+async fn test_document_highlights_on_cursor_idle(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
 
     let mut cx = EditorLspTestContext::new_rust(
         lsp::ServerCapabilities {
-            document_formatting_provider: Some(lsp::OneOf::Left(true)),
+            document_highlight_provider: Some(lsp::OneOf::Left(true)),
             ..Default::default()
         },
         cx,
     )
     .await;
-
-    // Set up a buffer white some trailing whitespace and no trailing newline.
-    cx.set_state(
-        &[
-            "one ",   //
-            "twoˇ",   //
-            "three ", //
-            "four",   //
-        ]
-        .join("\n"),
-    );
-
-    // Submit a format request.
-    let format = cx
-        .update_editor(|editor, cx| editor.format(&Format, cx))
-        .unwrap();
-
-    // Record which buffer changes have been sent to the language server
-    let buffer_changes = Arc::new(Mutex::new(Vec::new()));
-    cx.lsp
-        .handle_notification::<lsp::notification::DidChangeTextDocument, _>({
-            let buffer_changes = buffer_changes.clone();
-            move |params, _| {
-                buffer_changes.lock().extend(
-                    params
-                        .content_changes
-                        .into_iter()
-                        .map(|e| (e.range.unwrap(), e.text)),
-                );
-            }
+    let mut requests =
+        cx.handle_request::<lsp::request::DocumentHighlightRequest, _, _>(|_, _, _| async move {
+            let highlight = |line, kind| lsp::DocumentHighlight {
+                range: lsp::Range::new(lsp::Position::new(line, 4), lsp::Position::new(line, 5)),
+                kind: Some(kind),
+            };
+            Ok(Some(vec![
+                highlight(0, lsp::DocumentHighlightKind::WRITE),
+                highlight(1, lsp::DocumentHighlightKind::READ),
+            ]))
         });
 
-    // Handle formatting requests to the language server.
-    cx.lsp.handle_request::<lsp::request::Formatting, _, _>({
-        let buffer_changes = buffer_changes.clone();
-        move |_, _| {
-            // When formatting is requested, trailing whitespace has already been stripped,
-            // and the trailing newline has already been added.
-            assert_eq!(
-                &buffer_changes.lock()[1..],
-                &[
-                    (
-                        lsp::Range::new(lsp::Position::new(0, 3), lsp::Position::new(0, 4)),
-                        "".into()
-                    ),
-                    (
-                        lsp::Range::new(lsp::Position::new(2, 5), lsp::Position::new(2, 6)),
-                        "".into()
-                    ),
-                    (
-                        lsp::Range::new(lsp::Position::new(3, 4), lsp::Position::new(3, 4)),
-                        "\n".into()
-                    ),
-                ]
-            );
-
-            // Insert blank lines between each line of the buffer.
-            async move {
-                Ok(Some(vec![
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(1, 0)),
-                        new_text: "\n".into(),
-                    },
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(2, 0), lsp::Position::new(2, 0)),
-                        new_text: "\n".into(),
-                    },
-                ]))
-            }
-        }
+    cx.set_state(indoc! {"
+        let ˇa = 1;
+        f(a);
+    "});
+    cx.executor()
+        .advance_clock(DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT);
+    requests.next().await;
+    cx.run_until_parked();
+    cx.assert_editor_background_highlights::<DocumentHighlightWrite>(indoc! {"
+        let «a» = 1;
+        f(a);
+    "});
+    cx.assert_editor_background_highlights::<DocumentHighlightRead>(indoc! {"
+        let a = 1;
+        f(«a»);
+    "});
+    cx.editor(|editor, _| {
+        let (read, write) = editor.document_highlights();
+        assert_eq!((read.len(), write.len()), (1, 1));
     });
+}
 
-    // After formatting the buffer, the trailing whitespace is stripped,
-    // a newline is appended, and the edits provided by the language server
-    // have been applied.
-    format.await.unwrap();
-    cx.assert_editor_state(
-        &[
-            "one",   //
-            "",      //
-            "twoˇ",  //
-            "",      //
-            "three", //
-            "four",  //
-            "",      //
-        ]
-        .join("\n"),
-    );
+#[gpui::test]
+async fn test_document_highlights_fall_back_to_word_occurrences(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
 
-    // Undoing the formatting undoes the trailing whitespace removal, the
-    // trailing newline, and the LSP edits.
-    cx.update_buffer(|buffer, cx| buffer.undo(cx));
-    cx.assert_editor_state(
-        &[
-            "one ",   //
-            "twoˇ",   //
-            "three ", //
-            "four",   //
-        ]
-        .join("\n"),
-    );
+    // The language server can't find the occurrences of symbols.
+    let mut cx = EditorLspTestContext::new_rust(Default::default(), cx).await;
+
+    cx.set_state(indoc! {"
+        let ˇab = 1;
+        f(ab, abc);
+    "});
+    cx.executor()
+        .advance_clock(DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT);
+    cx.run_until_parked();
+    cx.assert_editor_background_highlights::<DocumentHighlightRead>(indoc! {"
+        let «ab» = 1;
+        f(«ab», abc);
+    "});
+    cx.editor(|editor, _| assert!(editor.document_highlights().1.is_empty()));
 }
 
 #[gpui::test]
@@ -8383,6 +9331,58 @@ async fn test_on_type_formatting_not_triggered(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_on_type_formatting_undone_with_trigger(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            document_on_type_formatting_provider: Some(lsp::DocumentOnTypeFormattingOptions {
+                first_trigger_character: ";".to_string(),
+                more_trigger_character: Some(vec!["\n".to_string()]),
+            }),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+    let _requests =
+        cx.handle_request::<lsp::request::OnTypeFormatting, _, _>(|_, params, _| async move {
+            let edit = match params.ch.as_str() {
+                ";" => lsp::TextEdit {
+                    range: lsp::Range::new(lsp::Position::new(0, 5), lsp::Position::new(0, 6)),
+                    new_text: " = ".to_string(),
+                },
+                "\n" => lsp::TextEdit {
+                    range: lsp::Range::new(lsp::Position::new(0, 10), lsp::Position::new(0, 10)),
+                    new_text: " // done".to_string(),
+                },
+                _ => return Ok(None),
+            };
+            Ok(Some(vec![edit]))
+        });
+
+    cx.set_state("let a=1ˇ");
+    cx.update_buffer(|buffer, _| buffer.finalize_last_transaction());
+    cx.simulate_keystroke(";");
+    cx.run_until_parked();
+    cx.assert_editor_state("let a = 1;ˇ");
+
+    // Undoing removes the formatting along with the character that triggered it.
+    cx.update_editor(|editor, cx| editor.undo(&Undo, cx));
+    assert_eq!(cx.buffer_text(), "let a=1");
+    cx.update_editor(|editor, cx| editor.redo(&Redo, cx));
+    assert_eq!(cx.buffer_text(), "let a = 1;");
+
+    // Newlines trigger formatting too.
+    cx.update_buffer(|buffer, _| buffer.finalize_last_transaction());
+    cx.update_editor(|editor, cx| editor.newline(&Newline, cx));
+    cx.run_until_parked();
+    assert_eq!(cx.buffer_text(), "let a = 1; // done\n");
+    cx.update_editor(|editor, cx| editor.undo(&Undo, cx));
+    assert_eq!(cx.buffer_text(), "let a = 1;");
+}
+
 #[gpui::test]
 async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -12250,3 +13250,170 @@ fn assert_hunk_revert(
     cx.assert_editor_state(expected_reverted_text_with_selections);
     assert_eq!(reverted_hunk_statuses, expected_not_reverted_hunk_statuses);
 }
+
+#[gpui::test]
+fn test_minimap_lines(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let buffer = cx.update(|cx| MultiBuffer::build_simple("fn a() {\n    b\n}", cx));
+    let editor = cx.add_window(|cx| build_editor(buffer.clone(), cx));
+    let minimap_lines = |cx: &mut TestAppContext| {
+        editor
+            .update(cx, |editor, cx| {
+                editor
+                    .minimap_lines(cx)
+                    .iter()
+                    .map(|line| {
+                        let runs = line.runs.iter().map(|(range, _)| range.clone());
+                        (line.len, runs.collect::<Vec<_>>())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap()
+    };
+    assert_eq!(
+        minimap_lines(cx),
+        [
+            (8, vec![0..2, 3..6, 7..8]),
+            (5, vec![4..5]),
+            (1, vec![0..1])
+        ]
+    );
+
+    // Only the edited lines are summarized again, and the following lines are shifted.
+    _ = editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 5)..Point::new(1, 5)])
+        });
+        editor.insert("c\n    d", cx);
+    });
+    assert_eq!(
+        minimap_lines(cx),
+        [
+            (8, vec![0..2, 3..6, 7..8]),
+            (6, vec![4..6]),
+            (5, vec![4..5]),
+            (1, vec![0..1])
+        ]
+    );
+
+    _ = buffer.update(cx, |buffer, cx| {
+        buffer.edit([(Point::new(0, 0)..Point::new(2, 0), "")], None, cx);
+    });
+    assert_eq!(minimap_lines(cx), [(5, vec![4..5]), (1, vec![0..1])]);
+}
+
+#[gpui::test]
+async fn test_accessible_document_and_announcements(
+    executor: BackgroundExecutor,
+    cx: &mut gpui::TestAppContext,
+) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let project = cx.update_editor(|editor, _| editor.project.clone().unwrap());
+    let announcements = Rc::new(RefCell::new(Vec::new()));
+    let editor = cx.editor.clone();
+    cx.update(|cx| {
+        let announcements = announcements.clone();
+        cx.subscribe(&editor, move |_, event: &EditorEvent, _| {
+            if let EditorEvent::Announce(announcement) = event {
+                announcements.borrow_mut().push(announcement.clone());
+            }
+        })
+        .detach();
+    });
+
+    cx.set_state(indoc! {"
+        fn a() {
+            «bˇ»
+        }
+    "});
+
+    let publish_diagnostics = |severities: &[lsp::DiagnosticSeverity],
+                               cx: &mut EditorTestContext| {
+        let diagnostics = severities
+            .iter()
+            .map(|severity| lsp::Diagnostic {
+                range: lsp::Range::new(lsp::Position::new(1, 4), lsp::Position::new(1, 5)),
+                severity: Some(*severity),
+                message: "unknown name".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        _ = cx.update(|cx| {
+            project.update(cx, |project, cx| {
+                project
+                    .update_diagnostics(
+                        LanguageServerId(0),
+                        lsp::PublishDiagnosticsParams {
+                            uri: lsp::Url::from_file_path("/root/file").unwrap(),
+                            version: None,
+                            diagnostics,
+                        },
+                        &[],
+                        cx,
+                    )
+                    .unwrap()
+            })
+        });
+    };
+
+    publish_diagnostics(&[lsp::DiagnosticSeverity::ERROR], &mut cx);
+    executor.run_until_parked();
+    assert_eq!(
+        announcements.borrow_mut().drain(..).collect::<Vec<_>>(),
+        [Announcement {
+            message: "1 error, 0 warnings".into(),
+            priority: AnnouncementPriority::Assertive,
+        }]
+    );
+
+    cx.update_editor(|editor, cx| {
+        let document = editor.accessible_document_around_cursor(0, cx);
+        assert_eq!(document.line_count, 4);
+        assert_eq!(
+            document.lines,
+            [AccessibleLine {
+                row: 1,
+                text: "    b".to_string(),
+                diagnostics: vec![AccessibleDiagnostic {
+                    severity: lsp::DiagnosticSeverity::ERROR,
+                    message: "unknown name".to_string(),
+                }],
+            }]
+        );
+        assert_eq!(
+            document.selections,
+            [AccessibleSelection {
+                start: Point::new(1, 4),
+                end: Point::new(1, 5),
+                reversed: false,
+                text: "b".to_string(),
+            }]
+        );
+    });
+
+    // Updates that don't change the number of problems aren't announced.
+    publish_diagnostics(&[lsp::DiagnosticSeverity::ERROR], &mut cx);
+    executor.run_until_parked();
+    assert!(announcements.borrow().is_empty());
+
+    publish_diagnostics(&[lsp::DiagnosticSeverity::WARNING], &mut cx);
+    executor.run_until_parked();
+    publish_diagnostics(&[], &mut cx);
+    executor.run_until_parked();
+    assert_eq!(
+        announcements.borrow_mut().drain(..).collect::<Vec<_>>(),
+        [
+            Announcement {
+                message: "0 errors, 1 warning".into(),
+                priority: AnnouncementPriority::Polite,
+            },
+            Announcement {
+                message: "No problems".into(),
+                priority: AnnouncementPriority::Polite,
+            },
+        ]
+    );
+}
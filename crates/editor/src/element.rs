@@ -6,18 +6,21 @@ use crate::{
     editor_settings::{
         CurrentLineHighlight, DoubleClickInMultibuffer, MultiCursorModifier, ShowScrollbar,
     },
+    export,
     git::{
         blame::{CommitDetails, GitBlame},
         diff_hunk_to_display, DisplayDiffHunk,
     },
+    highlight_occurrences,
     hover_popover::{
         self, hover_at, HOVER_POPOVER_GAP, MIN_POPOVER_CHARACTER_WIDTH, MIN_POPOVER_LINE_HEIGHT,
     },
     hunk_status,
     items::BufferSearchHighlights,
+    marks,
     mouse_context_menu::{self, MouseContextMenu},
     scroll::scroll_amount::ScrollAmount,
-    CodeActionsMenu, CursorShape, DisplayPoint, DisplayRow, DocumentHighlightRead,
+    textual_rename, CodeActionsMenu, CursorShape, DisplayPoint, DisplayRow, DocumentHighlightRead,
     DocumentHighlightWrite, Editor, EditorMode, EditorSettings, EditorSnapshot, EditorStyle,
     ExpandExcerpts, GutterDimensions, HalfPageDown, HalfPageUp, HoveredCursor, HunkToExpand,
     LineDown, LineUp, OpenExcerpts, PageDown, PageUp, Point, RowExt, RowRangeExt, SelectPhase,
@@ -181,12 +184,16 @@ impl EditorElement {
         register_action(view, cx, Editor::join_lines);
         register_action(view, cx, Editor::sort_lines_case_sensitive);
         register_action(view, cx, Editor::sort_lines_case_insensitive);
+        register_action(view, cx, Editor::sort_lines_numerically);
+        register_action(view, cx, Editor::sort_lines_naturally);
+        register_action(view, cx, Editor::align_lines);
         register_action(view, cx, Editor::reverse_lines);
         register_action(view, cx, Editor::shuffle_lines);
         register_action(view, cx, Editor::convert_to_upper_case);
         register_action(view, cx, Editor::convert_to_lower_case);
         register_action(view, cx, Editor::convert_to_title_case);
         register_action(view, cx, Editor::convert_to_snake_case);
+        register_action(view, cx, Editor::convert_color_format);
         register_action(view, cx, Editor::convert_to_kebab_case);
         register_action(view, cx, Editor::convert_to_upper_camel_case);
         register_action(view, cx, Editor::convert_to_lower_camel_case);
@@ -206,6 +213,8 @@ impl EditorElement {
         register_action(view, cx, Editor::cut);
         register_action(view, cx, Editor::copy);
         register_action(view, cx, Editor::paste);
+        register_action(view, cx, Editor::paste_below);
+        register_action(view, cx, Editor::paste_and_match_indentation);
         register_action(view, cx, Editor::undo);
         register_action(view, cx, Editor::redo);
         register_action(view, cx, Editor::move_page_up);
@@ -273,6 +282,8 @@ impl EditorElement {
         register_action(view, cx, Editor::toggle_comments);
         register_action(view, cx, Editor::select_larger_syntax_node);
         register_action(view, cx, Editor::select_smaller_syntax_node);
+        register_action(view, cx, Editor::select_larger_bracket_scope);
+        register_action(view, cx, Editor::select_smaller_bracket_scope);
         register_action(view, cx, Editor::move_to_enclosing_bracket);
         register_action(view, cx, Editor::undo_selection);
         register_action(view, cx, Editor::redo_selection);
@@ -315,6 +326,7 @@ impl EditorElement {
         register_action(view, cx, Editor::fold_selected_ranges);
         register_action(view, cx, Editor::show_completions);
         register_action(view, cx, Editor::toggle_code_actions);
+        register_action(view, cx, Editor::toggle_breakpoint);
         register_action(view, cx, Editor::open_excerpts);
         register_action(view, cx, Editor::open_excerpts_in_split);
         register_action(view, cx, Editor::toggle_soft_wrap);
@@ -322,6 +334,15 @@ impl EditorElement {
         register_action(view, cx, Editor::toggle_indent_guides);
         register_action(view, cx, Editor::toggle_inlay_hints);
         register_action(view, cx, hover_popover::hover);
+        register_action(
+            view,
+            cx,
+            highlight_occurrences::select_highlighted_occurrences,
+        );
+        register_action(view, cx, marks::set_mark);
+        register_action(view, cx, export::export_to_html);
+        register_action(view, cx, export::export_to_pdf);
+        register_action(view, cx, textual_rename::rename_textually);
         register_action(view, cx, Editor::reveal_in_finder);
         register_action(view, cx, Editor::copy_path);
         register_action(view, cx, Editor::copy_relative_path);
@@ -389,6 +410,10 @@ impl EditorElement {
         register_action(view, cx, Editor::accept_partial_inline_completion);
         register_action(view, cx, Editor::accept_inline_completion);
         register_action(view, cx, Editor::revert_selected_hunks);
+        register_action(view, cx, Editor::stage_hunk);
+        register_action(view, cx, Editor::unstage_hunk);
+        register_action(view, cx, Editor::stage_file);
+        register_action(view, cx, Editor::unstage_file);
         register_action(view, cx, Editor::open_active_item_in_terminal)
     }
 
@@ -1179,6 +1204,45 @@ impl EditorElement {
             .collect()
     }
 
+    /// Lays out the hunks that are staged in the index, which are only tracked for singleton
+    /// buffers.
+    fn layout_staged_git_gutters(
+        &self,
+        display_rows: Range<DisplayRow>,
+        snapshot: &EditorSnapshot,
+        cx: &mut WindowContext,
+    ) -> Vec<DisplayDiffHunk> {
+        let buffer_snapshot = &snapshot.buffer_snapshot;
+        let Some((excerpt_id, _, _)) = buffer_snapshot.as_singleton() else {
+            return Vec::new();
+        };
+
+        self.editor.update(cx, |editor, _| {
+            editor
+                .staged_hunks
+                .hunks()
+                .iter()
+                .filter_map(|hunk| {
+                    let start =
+                        buffer_snapshot.anchor_in_excerpt(*excerpt_id, hunk.buffer_range.start)?;
+                    let end =
+                        buffer_snapshot.anchor_in_excerpt(*excerpt_id, hunk.buffer_range.end)?;
+                    let start_row = start.to_display_point(snapshot).row();
+                    let end_row = end.to_display_point(snapshot).row();
+                    if start_row >= display_rows.end || end_row < display_rows.start {
+                        return None;
+                    }
+                    Some(DisplayDiffHunk::Unfolded {
+                        diff_base_byte_range: hunk.head_byte_range.clone(),
+                        display_row_range: start_row..end_row,
+                        multi_buffer_range: start..end,
+                        status: hunk.status,
+                    })
+                })
+                .collect()
+        })
+    }
+
     // Folds contained in a hunk are ignored apart from shrinking visual size
     // If a fold contains any hunks then that fold line is marked as modified
     fn layout_git_gutters(
@@ -2877,12 +2941,45 @@ impl EditorElement {
         layout: &EditorLayout,
         cx: &mut WindowContext,
     ) {
-        if layout.display_hunks.is_empty() {
+        if layout.display_hunks.is_empty() && layout.staged_display_hunks.is_empty() {
             return;
         }
 
         let line_height = layout.position_map.line_height;
         cx.paint_layer(layout.gutter_hitbox.bounds, |cx| {
+            // Staged hunks are drawn as outlines, beneath any unstaged changes to the same rows.
+            for hunk in &layout.staged_display_hunks {
+                let DisplayDiffHunk::Unfolded { status, .. } = hunk else {
+                    continue;
+                };
+                let (border_color, corner_radii) = match status {
+                    DiffHunkStatus::Added => (
+                        cx.theme().status().created,
+                        Corners::all(0.05 * line_height),
+                    ),
+                    DiffHunkStatus::Modified => (
+                        cx.theme().status().modified,
+                        Corners::all(0.05 * line_height),
+                    ),
+                    DiffHunkStatus::Removed => {
+                        (cx.theme().status().deleted, Corners::all(1. * line_height))
+                    }
+                };
+                let hunk_bounds = Self::diff_hunk_bounds(
+                    &layout.position_map.snapshot,
+                    line_height,
+                    gutter_bounds,
+                    hunk,
+                );
+                cx.paint_quad(quad(
+                    hunk_bounds,
+                    corner_radii,
+                    transparent_black(),
+                    Edges::all(px(1.)),
+                    border_color,
+                ));
+            }
+
             for (hunk, hitbox) in &layout.display_hunks {
                 let hunk_to_paint = match hunk {
                     DisplayDiffHunk::Folded { .. } => {
@@ -3033,6 +3130,7 @@ impl EditorElement {
 
                 let invisible_display_ranges = self.paint_highlights(layout, cx);
                 self.paint_lines(&invisible_display_ranges, layout, cx);
+                self.paint_color_swatches(layout, cx);
                 self.paint_redactions(layout, cx);
                 self.paint_cursors(layout, cx);
                 self.paint_inline_blame(layout, cx);
@@ -3141,6 +3239,38 @@ impl EditorElement {
         });
     }
 
+    /// Paints a bar in each color under the text that describes it.
+    fn paint_color_swatches(&mut self, layout: &EditorLayout, cx: &mut WindowContext) {
+        let start_row = layout.visible_display_row_range.start;
+        let end_row = layout.visible_display_row_range.end;
+        let line_height = layout.position_map.line_height;
+        let thickness = (0.12 * line_height).max(px(2.));
+        let scroll_position = layout.position_map.scroll_pixel_position;
+        for (range, color) in &layout.color_swatches {
+            let row = range.start.row();
+            if row < start_row || row >= end_row {
+                continue;
+            }
+            let line_layout = &layout.position_map.line_layouts[row.minus(start_row) as usize];
+            let start_x = line_layout.x_for_index(range.start.column() as usize);
+            let end_x = if range.end.row() == row {
+                line_layout.x_for_index(range.end.column() as usize)
+            } else {
+                line_layout.width
+            };
+            let origin = point(
+                layout.content_origin.x + start_x - scroll_position.x,
+                layout.content_origin.y + (row.as_f32() + 1.) * line_height
+                    - thickness
+                    - scroll_position.y,
+            );
+            cx.paint_quad(fill(
+                Bounds::new(origin, size(end_x - start_x, thickness)),
+                *color,
+            ));
+        }
+    }
+
     fn paint_cursors(&mut self, layout: &mut EditorLayout, cx: &mut WindowContext) {
         for cursor in &mut layout.visible_cursors {
             cursor.paint(layout.content_origin, cx);
@@ -3374,15 +3504,17 @@ impl EditorElement {
                             {
                                 let is_search_highlights = *background_highlight_id
                                     == TypeId::of::<BufferSearchHighlights>();
-                                let is_symbol_occurrences = *background_highlight_id
-                                    == TypeId::of::<DocumentHighlightRead>()
+                                let is_symbol_writes = *background_highlight_id
+                                    == TypeId::of::<DocumentHighlightWrite>();
+                                let is_symbol_occurrences = is_symbol_writes
                                     || *background_highlight_id
-                                        == TypeId::of::<DocumentHighlightWrite>();
+                                        == TypeId::of::<DocumentHighlightRead>();
                                 if (is_search_highlights && scrollbar_settings.search_results)
                                     || (is_symbol_occurrences && scrollbar_settings.selected_symbol)
                                 {
+                                    // Writes to the symbol stand out from its reads.
                                     let mut color = theme.status().info;
-                                    if is_symbol_occurrences {
+                                    if is_symbol_occurrences && !is_symbol_writes {
                                         color.fade_out(0.5);
                                     }
                                     let marker_row_ranges =
@@ -4560,6 +4692,10 @@ impl Element for EditorElement {
                         &snapshot.display_snapshot,
                         cx,
                     );
+                    let color_swatches = self.editor.read(cx).color_swatches_in_range(
+                        start_anchor..end_anchor,
+                        &snapshot.display_snapshot,
+                    );
 
                     let (selections, active_rows, newest_selection_head) = self.layout_selections(
                         start_anchor,
@@ -4600,6 +4736,8 @@ impl Element for EditorElement {
                         &snapshot,
                         cx,
                     );
+                    let staged_display_hunks =
+                        self.layout_staged_git_gutters(start_row..end_row, &snapshot, cx);
 
                     let mut max_visible_line_width = Pixels::ZERO;
                     let mut line_layouts =
@@ -4915,9 +5053,11 @@ impl Element for EditorElement {
                         highlighted_rows,
                         highlighted_ranges,
                         redacted_ranges,
+                        color_swatches,
                         line_elements,
                         line_numbers,
                         display_hunks,
+                        staged_display_hunks,
                         blamed_display_rows,
                         inline_blame,
                         blocks,
@@ -5040,11 +5180,13 @@ pub struct EditorLayout {
     line_elements: SmallVec<[AnyElement; 1]>,
     line_numbers: Vec<Option<ShapedLine>>,
     display_hunks: Vec<(DisplayDiffHunk, Option<Hitbox>)>,
+    staged_display_hunks: Vec<DisplayDiffHunk>,
     blamed_display_rows: Option<Vec<AnyElement>>,
     inline_blame: Option<AnyElement>,
     blocks: Vec<BlockLayout>,
     highlighted_ranges: Vec<(Range<DisplayPoint>, Hsla)>,
     redacted_ranges: Vec<Range<DisplayPoint>>,
+    color_swatches: Vec<(Range<DisplayPoint>, Hsla)>,
     cursors: Vec<(DisplayPoint, Hsla)>,
     visible_cursors: Vec<CursorLayout>,
     selections: Vec<(PlayerColor, Vec<SelectionLayout>)>,
@@ -0,0 +1,457 @@
+//! Exporting a buffer's text, with its syntax highlighting, to standalone documents.
+
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use gpui::{FontStyle, FontWeight, Hsla, Rgba, SharedString, ViewContext};
+use language::{BufferSnapshot, LineEnding, Rope};
+use project::{Durability, WorktreeId};
+use theme::SyntaxTheme;
+
+use crate::{Editor, ExportToHtml, ExportToPdf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExportFormat {
+    Html,
+    Pdf,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Html => "html",
+            ExportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// A piece of a line's text that is rendered in a single style.
+#[derive(Clone, Debug, PartialEq)]
+struct StyledRun {
+    text: String,
+    color: Option<Hsla>,
+    bold: bool,
+    italic: bool,
+}
+
+/// The colors and font of the exported document, taken from the editor's theme.
+struct DocumentStyle {
+    title: String,
+    font_family: SharedString,
+    foreground: Hsla,
+    background: Hsla,
+}
+
+pub fn export_to_html(editor: &mut Editor, _: &ExportToHtml, cx: &mut ViewContext<Editor>) {
+    export(editor, ExportFormat::Html, cx);
+}
+
+pub fn export_to_pdf(editor: &mut Editor, _: &ExportToPdf, cx: &mut ViewContext<Editor>) {
+    export(editor, ExportFormat::Pdf, cx);
+}
+
+/// Renders the buffer on the background executor and saves the result next to the buffer's
+/// file, e.g. as `main.rs.html`, or as `main.rs 1.html` if that file already exists.
+fn export(editor: &mut Editor, format: ExportFormat, cx: &mut ViewContext<Editor>) {
+    let Some(buffer) = editor.buffer.read(cx).as_singleton() else {
+        return;
+    };
+    let (Some(project), Some(style)) = (editor.project.clone(), editor.style.as_ref()) else {
+        return;
+    };
+    let buffer = buffer.read(cx);
+    let Some(file) = buffer.file() else {
+        return;
+    };
+    let file_name = file.file_name(cx).to_string_lossy().to_string();
+    let worktree_id = WorktreeId::from_usize(file.worktree_id());
+    let Some(worktree) = project.read(cx).worktree_for_id(worktree_id, cx) else {
+        return;
+    };
+    let path = export_path(file.path(), format, |path| {
+        worktree.read(cx).entry_for_path(path).is_some()
+    });
+
+    let snapshot = buffer.snapshot();
+    let syntax = style.syntax.clone();
+    let document_style = DocumentStyle {
+        title: file_name,
+        font_family: style.text.font_family.clone(),
+        foreground: style.text.color,
+        background: style.background,
+    };
+    let render = cx.background_executor().spawn(async move {
+        let lines = styled_lines(&snapshot, &syntax);
+        match format {
+            ExportFormat::Html => render_html(&lines, &document_style),
+            ExportFormat::Pdf => render_pdf(&lines, &document_style),
+        }
+    });
+
+    cx.spawn(|_, mut cx| async move {
+        let document = render.await;
+        worktree
+            .update(&mut cx, |worktree, cx| {
+                let worktree = worktree
+                    .as_local()
+                    .context("exporting is only supported in local projects")?;
+                anyhow::Ok(worktree.write_file(
+                    path,
+                    Rope::from(document.as_str()),
+                    LineEnding::Unix,
+                    Durability::Buffered,
+                    cx,
+                ))
+            })??
+            .await?;
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+/// The path of the document exported from the file at `path`, numbered so that it doesn't
+/// replace an existing file.
+fn export_path(path: &Path, format: ExportFormat, exists: impl Fn(&Path) -> bool) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut export_path = path.with_file_name(format!("{file_name}.{}", format.extension()));
+    let mut ix = 1;
+    while exists(&export_path) {
+        export_path = path.with_file_name(format!("{file_name} {ix}.{}", format.extension()));
+        ix += 1;
+    }
+    export_path
+}
+
+/// Splits the buffer into lines of runs that share the same syntax highlighting.
+fn styled_lines(snapshot: &BufferSnapshot, syntax: &SyntaxTheme) -> Vec<Vec<StyledRun>> {
+    let mut lines = vec![Vec::new()];
+    for chunk in snapshot.chunks(0..snapshot.len(), true) {
+        let style = chunk
+            .syntax_highlight_id
+            .and_then(|id| id.style(syntax))
+            .unwrap_or_default();
+        let color = style.color;
+        let bold = style
+            .font_weight
+            .map_or(false, |weight| weight.0 >= FontWeight::SEMIBOLD.0);
+        let italic = style.font_style == Some(FontStyle::Italic);
+
+        for (ix, text) in chunk.text.split('\n').enumerate() {
+            if ix > 0 {
+                lines.push(Vec::new());
+            }
+            if text.is_empty() {
+                continue;
+            }
+            let line = lines.last_mut().unwrap();
+            match line.last_mut() {
+                Some(run) if run.color == color && run.bold == bold && run.italic == italic => {
+                    run.text.push_str(text)
+                }
+                _ => line.push(StyledRun {
+                    text: text.to_string(),
+                    color,
+                    bold,
+                    italic,
+                }),
+            }
+        }
+    }
+    lines
+}
+
+fn css_color(color: Hsla) -> String {
+    let color = Rgba::from(color);
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        (color.r * 255.).round() as u8,
+        (color.g * 255.).round() as u8,
+        (color.b * 255.).round() as u8
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_html(lines: &[Vec<StyledRun>], style: &DocumentStyle) -> String {
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").ok();
+    writeln!(html, "<html>").ok();
+    writeln!(html, "<head>").ok();
+    writeln!(html, "<meta charset=\"utf-8\">").ok();
+    writeln!(html, "<title>{}</title>", escape_html(&style.title)).ok();
+    writeln!(
+        html,
+        "<style>body {{ margin: 0; background: {}; }} pre {{ margin: 0; padding: 1em; color: {}; font-family: \"{}\", monospace; }}</style>",
+        css_color(style.background),
+        css_color(style.foreground),
+        escape_html(&style.font_family),
+    )
+    .ok();
+    writeln!(html, "</head>").ok();
+    writeln!(html, "<body>").ok();
+    html.push_str("<pre>");
+    for (ix, line) in lines.iter().enumerate() {
+        if ix > 0 {
+            html.push('\n');
+        }
+        for run in line {
+            let mut css = String::new();
+            if let Some(color) = run.color {
+                write!(css, "color: {};", css_color(color)).ok();
+            }
+            if run.bold {
+                css.push_str("font-weight: bold;");
+            }
+            if run.italic {
+                css.push_str("font-style: italic;");
+            }
+            if css.is_empty() {
+                html.push_str(&escape_html(&run.text));
+            } else {
+                write!(
+                    html,
+                    "<span style=\"{css}\">{}</span>",
+                    escape_html(&run.text)
+                )
+                .ok();
+            }
+        }
+    }
+    html.push_str("</pre>\n");
+    writeln!(html, "</body>").ok();
+    writeln!(html, "</html>").ok();
+    html
+}
+
+const PDF_PAGE_WIDTH: f32 = 595.;
+const PDF_PAGE_HEIGHT: f32 = 842.;
+const PDF_MARGIN: f32 = 40.;
+const PDF_FONT_SIZE: f32 = 9.;
+const PDF_LINE_HEIGHT: f32 = 11.;
+
+/// Escapes text for a PDF string literal. The standard fonts only cover Latin-1, and the
+/// document is written as text, so other characters are replaced.
+fn escape_pdf(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\t' => escaped.push_str("    "),
+            ' '..='~' => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+fn pdf_color(color: Hsla) -> String {
+    let color = Rgba::from(color);
+    format!("{:.3} {:.3} {:.3}", color.r, color.g, color.b)
+}
+
+/// Renders the lines as a minimal PDF document, using the built-in Courier fonts so that no
+/// fonts need to be embedded. Lines that don't fit the page's width are clipped.
+fn render_pdf(lines: &[Vec<StyledRun>], style: &DocumentStyle) -> String {
+    let lines_per_page = ((PDF_PAGE_HEIGHT - 2. * PDF_MARGIN) / PDF_LINE_HEIGHT) as usize;
+    let pages = lines.chunks(lines_per_page.max(1)).collect::<Vec<_>>();
+
+    // Objects 1 and 2 are the catalog and the page tree, 3 to 6 are the fonts, and each
+    // page is followed by its content stream.
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+    ];
+    for font in [
+        "Courier",
+        "Courier-Bold",
+        "Courier-Oblique",
+        "Courier-BoldOblique",
+    ] {
+        objects.push(format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+        ));
+    }
+
+    let mut page_ids = Vec::new();
+    for page_lines in &pages {
+        let mut content = String::new();
+        writeln!(
+            content,
+            "{} rg 0 0 {PDF_PAGE_WIDTH} {PDF_PAGE_HEIGHT} re f",
+            pdf_color(style.background)
+        )
+        .ok();
+        writeln!(
+            content,
+            "BT {PDF_LINE_HEIGHT} TL {PDF_MARGIN} {} Td",
+            PDF_PAGE_HEIGHT - PDF_MARGIN - PDF_FONT_SIZE
+        )
+        .ok();
+        for line in page_lines.iter() {
+            for run in line {
+                let font = match (run.bold, run.italic) {
+                    (false, false) => "F1",
+                    (true, false) => "F2",
+                    (false, true) => "F3",
+                    (true, true) => "F4",
+                };
+                writeln!(
+                    content,
+                    "/{font} {PDF_FONT_SIZE} Tf {} rg ({}) Tj",
+                    pdf_color(run.color.unwrap_or(style.foreground)),
+                    escape_pdf(&run.text)
+                )
+                .ok();
+            }
+            writeln!(content, "T*").ok();
+        }
+        writeln!(content, "ET").ok();
+
+        let page_id = objects.len() + 1;
+        page_ids.push(page_id);
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PDF_PAGE_WIDTH} {PDF_PAGE_HEIGHT}] /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R /F4 6 0 R >> >> /Contents {} 0 R >>",
+            page_id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{content}endstream",
+            content.len()
+        ));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        page_ids
+            .iter()
+            .map(|id| format!("{id} 0 R"))
+            .collect::<Vec<_>>()
+            .join(" "),
+        page_ids.len()
+    );
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (ix, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        writeln!(pdf, "{} 0 obj\n{object}\nendobj", ix + 1).ok();
+    }
+    let xref_offset = pdf.len();
+    writeln!(pdf, "xref\n0 {}", objects.len() + 1).ok();
+    writeln!(pdf, "0000000000 65535 f ").ok();
+    for offset in offsets {
+        writeln!(pdf, "{offset:010} 00000 n ").ok();
+    }
+    writeln!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF",
+        objects.len() + 1
+    )
+    .ok();
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{black, white};
+
+    fn document_style() -> DocumentStyle {
+        DocumentStyle {
+            title: "main.rs".into(),
+            font_family: "Zed Mono".into(),
+            foreground: black(),
+            background: white(),
+        }
+    }
+
+    fn lines() -> Vec<Vec<StyledRun>> {
+        vec![
+            vec![
+                StyledRun {
+                    text: "fn".into(),
+                    color: Some(Hsla::from(Rgba {
+                        r: 1.,
+                        g: 0.,
+                        b: 0.,
+                        a: 1.,
+                    })),
+                    bold: true,
+                    italic: false,
+                },
+                StyledRun {
+                    text: " a<b>() {}".into(),
+                    color: None,
+                    bold: false,
+                    italic: false,
+                },
+            ],
+            vec![],
+        ]
+    }
+
+    #[test]
+    fn test_export_path() {
+        let existing_paths = [
+            Path::new("src/main.rs.html"),
+            Path::new("src/main.rs 1.html"),
+        ];
+        let exists = |path: &Path| existing_paths.contains(&path);
+        assert_eq!(
+            export_path(Path::new("src/main.rs"), ExportFormat::Pdf, exists),
+            Path::new("src/main.rs.pdf")
+        );
+        assert_eq!(
+            export_path(Path::new("src/main.rs"), ExportFormat::Html, exists),
+            Path::new("src/main.rs 2.html")
+        );
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = render_html(&lines(), &document_style());
+        assert!(html.contains("<title>main.rs</title>"));
+        assert!(html.contains(
+            "<pre><span style=\"color: #ff0000;font-weight: bold;\">fn</span> a&lt;b&gt;() {}\n</pre>"
+        ));
+    }
+
+    #[test]
+    fn test_render_pdf() {
+        let pdf = render_pdf(&lines(), &document_style());
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.contains("/F2 9 Tf 1.000 0.000 0.000 rg (fn) Tj"));
+        assert!(pdf.contains("/F1 9 Tf 0.000 0.000 0.000 rg ( a<b>\\(\\) {}) Tj"));
+        assert!(pdf.contains("/Count 1"));
+
+        // The cross-reference table points at each object.
+        let xref_offset = pdf
+            .lines()
+            .skip_while(|line| *line != "startxref")
+            .nth(1)
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        assert!(pdf[xref_offset..].starts_with("xref"));
+        let second_object_offset = pdf[xref_offset..].lines().nth(4).unwrap()[..10]
+            .parse::<usize>()
+            .unwrap();
+        assert!(pdf[second_object_offset..].starts_with("2 0 obj"));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod blame;
+pub(crate) mod staging;
 
 use std::ops::Range;
 
@@ -0,0 +1,418 @@
+//! Staging and unstaging changes from the editor, one hunk or one file at a time.
+//!
+//! The git gutter shows the buffer's changes relative to the index, which are the unstaged
+//! changes. Changes that have been staged are only visible when comparing the index with the
+//! HEAD commit, so those are computed here too, in order to mark them in the gutter.
+//!
+//! Like `git add`, staging takes the file's saved contents, so changes that haven't been saved
+//! yet aren't staged.
+
+use std::ops::Range;
+
+use git::diff::{BufferDiff, DiffHunkStatus};
+use gpui::{Model, Task, ViewContext};
+use language::{Buffer, OffsetRangeExt, Point};
+use project::Project;
+use text::{Anchor, BufferId, Rope};
+use util::ResultExt;
+
+use crate::{hunks_for_selections, Editor, StageFile, StageHunk, UnstageFile, UnstageHunk};
+
+/// A change between the HEAD commit and the index.
+#[derive(Clone, Debug)]
+pub(crate) struct StagedHunk {
+    /// The rows of the buffer that contain the staged text.
+    pub buffer_range: Range<Anchor>,
+    pub status: DiffHunkStatus,
+    pub index_byte_range: Range<usize>,
+    pub head_byte_range: Range<usize>,
+}
+
+#[derive(Default)]
+pub(crate) struct StagedHunks {
+    head_text: Option<Rope>,
+    hunks: Vec<StagedHunk>,
+    _update_task: Option<Task<()>>,
+}
+
+impl StagedHunks {
+    pub fn hunks(&self) -> &[StagedHunk] {
+        &self.hunks
+    }
+}
+
+impl Editor {
+    /// Recomputes the staged hunks of a singleton buffer, reloading the HEAD text if
+    /// `reload_head` is true or it hasn't been loaded yet.
+    pub(crate) fn refresh_staged_hunks(&mut self, reload_head: bool, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let Some(buffer) = self.buffer.read(cx).as_singleton() else {
+            return;
+        };
+        if !project.read(cx).is_local() {
+            return;
+        }
+
+        let head_text = if reload_head {
+            None
+        } else {
+            self.staged_hunks.head_text.clone()
+        };
+        let load_head_text = head_text
+            .is_none()
+            .then(|| project.update(cx, |project, cx| project.load_committed_text(&buffer, cx)));
+        let snapshot = buffer.read(cx).snapshot();
+        let index_text = buffer.read(cx).diff_base().cloned();
+        // The unstaged hunks, as pairs of buffer rows and index rows.
+        let unstaged_rows = index_text
+            .as_ref()
+            .map(|index_text| {
+                snapshot
+                    .git_diff_hunks_in_row_range(0..u32::MAX)
+                    .map(|hunk| {
+                        let index_start =
+                            index_text.offset_to_point(hunk.diff_base_byte_range.start);
+                        let index_end = index_text.offset_to_point(hunk.diff_base_byte_range.end);
+                        (hunk.associated_range, index_start.row..index_end.row)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        self.staged_hunks._update_task = Some(cx.spawn(|editor, mut cx| async move {
+            let head_text = match (head_text, load_head_text) {
+                (Some(head_text), _) => Some(head_text),
+                (None, Some(load_head_text)) => load_head_text
+                    .await
+                    .log_err()
+                    .flatten()
+                    .map(|text| Rope::from(text.as_str())),
+                (None, None) => None,
+            };
+            let hunks = match (&head_text, index_text) {
+                (Some(head_text), Some(index_text)) => {
+                    let head_text = head_text.clone();
+                    cx.background_executor()
+                        .spawn(async move {
+                            staged_hunks(&head_text, &index_text, &unstaged_rows, &snapshot).await
+                        })
+                        .await
+                }
+                _ => Vec::new(),
+            };
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.staged_hunks.head_text = head_text;
+                    editor.staged_hunks.hunks = hunks;
+                    cx.notify();
+                })
+                .ok();
+        }));
+    }
+
+    pub fn stage_hunk(&mut self, _: &StageHunk, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let multi_buffer = self.buffer.read(cx);
+        let snapshot = multi_buffer.snapshot(cx);
+        let mut hunks_by_buffer = Vec::<(Model<Buffer>, Vec<_>)>::new();
+        for hunk in hunks_for_selections(&snapshot, &self.selections.disjoint_anchors()) {
+            let Some(buffer) = multi_buffer.buffer(hunk.buffer_id) else {
+                continue;
+            };
+            match hunks_by_buffer.iter_mut().find(|(b, _)| *b == buffer) {
+                Some((_, hunks)) => hunks.push(hunk),
+                None => hunks_by_buffer.push((buffer, vec![hunk])),
+            }
+        }
+
+        for (buffer, mut hunks) in hunks_by_buffer {
+            let buffer_snapshot = buffer.read(cx).snapshot();
+            let Some(index_text) = buffer.read(cx).diff_base() else {
+                continue;
+            };
+            // The text of hunks with unsaved edits differs from the file's contents.
+            let saved_version = buffer.read(cx).saved_version().clone();
+            hunks.retain(|hunk| {
+                !buffer_snapshot.has_edits_since_in_range(&saved_version, hunk.buffer_range.clone())
+            });
+            if hunks.is_empty() {
+                continue;
+            }
+            // Replace each hunk's text in the index with its text in the buffer, starting from
+            // the end so that earlier byte ranges stay valid.
+            hunks.sort_by_key(|hunk| hunk.diff_base_byte_range.start);
+            let mut new_index_text = index_text.to_string();
+            for hunk in hunks.iter().rev() {
+                let buffer_text = buffer_snapshot
+                    .text_for_range(hunk.buffer_range.to_offset(&buffer_snapshot))
+                    .collect::<String>();
+                new_index_text.replace_range(hunk.diff_base_byte_range.clone(), &buffer_text);
+            }
+            set_index_text(&project, &buffer, Some(new_index_text), cx);
+        }
+    }
+
+    pub fn unstage_hunk(&mut self, _: &UnstageHunk, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let Some(buffer) = self.buffer.read(cx).as_singleton() else {
+            return;
+        };
+        let (Some(head_text), Some(index_text)) = (
+            self.staged_hunks.head_text.as_ref(),
+            buffer.read(cx).diff_base(),
+        ) else {
+            return;
+        };
+
+        let snapshot = buffer.read(cx).snapshot();
+        let selected_rows = self
+            .selections
+            .all::<Point>(cx)
+            .into_iter()
+            .map(|selection| selection.start.row..selection.end.row + 1)
+            .collect::<Vec<_>>();
+        let hunks = self
+            .staged_hunks
+            .hunks
+            .iter()
+            .filter(|hunk| {
+                let hunk_rows = hunk.buffer_range.to_point(&snapshot);
+                let hunk_rows = hunk_rows.start.row..hunk_rows.end.row.max(hunk_rows.start.row + 1);
+                selected_rows
+                    .iter()
+                    .any(|rows| rows.start < hunk_rows.end && hunk_rows.start < rows.end)
+            })
+            .collect::<Vec<_>>();
+        if hunks.is_empty() {
+            return;
+        }
+
+        // Restore each hunk's text in the index to its text in HEAD, starting from the end so
+        // that earlier byte ranges stay valid.
+        let mut new_index_text = index_text.to_string();
+        for hunk in hunks.iter().rev() {
+            let head_text = head_text
+                .chunks_in_range(hunk.head_byte_range.clone())
+                .collect::<String>();
+            new_index_text.replace_range(hunk.index_byte_range.clone(), &head_text);
+        }
+        set_index_text(&project, &buffer, Some(new_index_text), cx);
+    }
+
+    pub fn stage_file(&mut self, _: &StageFile, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        for buffer in self.buffer.read(cx).all_buffers() {
+            let Some(load_saved_text) = buffer
+                .read(cx)
+                .file()
+                .and_then(|file| file.as_local())
+                .map(|file| file.load(cx))
+            else {
+                continue;
+            };
+            let project = project.clone();
+            cx.spawn(|_, mut cx| async move {
+                let saved_text = load_saved_text.await?;
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.set_index_text(&buffer, Some(saved_text), cx)
+                    })?
+                    .await
+            })
+            .detach_and_log_err(cx);
+        }
+    }
+
+    pub fn unstage_file(&mut self, _: &UnstageFile, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        for buffer in self.buffer.read(cx).all_buffers() {
+            let load_head_text =
+                project.update(cx, |project, cx| project.load_committed_text(&buffer, cx));
+            let project = project.clone();
+            cx.spawn(|_, mut cx| async move {
+                // Files that aren't in HEAD are removed from the index entirely.
+                let head_text = load_head_text.await?;
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.set_index_text(&buffer, head_text, cx)
+                    })?
+                    .await
+            })
+            .detach_and_log_err(cx);
+        }
+    }
+}
+
+fn set_index_text(
+    project: &Model<Project>,
+    buffer: &Model<Buffer>,
+    text: Option<String>,
+    cx: &mut ViewContext<Editor>,
+) {
+    project
+        .update(cx, |project, cx| project.set_index_text(buffer, text, cx))
+        .detach_and_log_err(cx);
+}
+
+/// Computes the changes between the HEAD and the index, positioned in the buffer.
+async fn staged_hunks(
+    head_text: &Rope,
+    index_text: &Rope,
+    unstaged_rows: &[(Range<u32>, Range<u32>)],
+    snapshot: &text::BufferSnapshot,
+) -> Vec<StagedHunk> {
+    let index_buffer = text::Buffer::new(0, BufferId::new(1).unwrap(), index_text.to_string());
+    let index_snapshot = index_buffer.snapshot();
+    let mut diff = BufferDiff::new();
+    diff.update(head_text, &index_snapshot).await;
+
+    diff.hunks_in_row_range(0..u32::MAX, &index_snapshot)
+        .map(|hunk| {
+            let index_rows = hunk.associated_range.clone();
+            let start_row = index_row_to_buffer_row(index_rows.start, unstaged_rows);
+            let end_row = if index_rows.is_empty() {
+                start_row
+            } else {
+                index_row_to_buffer_row(index_rows.end - 1, unstaged_rows) + 1
+            };
+            let status = if hunk.diff_base_byte_range.is_empty() {
+                DiffHunkStatus::Added
+            } else if index_rows.is_empty() {
+                DiffHunkStatus::Removed
+            } else {
+                DiffHunkStatus::Modified
+            };
+            StagedHunk {
+                buffer_range: snapshot.anchor_before(Point::new(start_row, 0))
+                    ..snapshot.anchor_before(Point::new(end_row, 0)),
+                status,
+                index_byte_range: hunk.buffer_range.to_offset(&index_snapshot),
+                head_byte_range: hunk.diff_base_byte_range,
+            }
+        })
+        .collect()
+}
+
+/// Maps a row of the index text to the corresponding row of the buffer, given the unstaged
+/// changes between them as pairs of buffer rows and index rows. Rows that were changed in the
+/// buffer map to the start of that change.
+fn index_row_to_buffer_row(index_row: u32, unstaged_rows: &[(Range<u32>, Range<u32>)]) -> u32 {
+    let mut delta = 0i64;
+    for (buffer_rows, index_rows) in unstaged_rows {
+        if index_row < index_rows.start {
+            break;
+        } else if index_row < index_rows.end {
+            return buffer_rows.start;
+        }
+        delta = buffer_rows.end as i64 - index_rows.end as i64;
+    }
+    (index_row as i64 + delta).max(0) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor_tests::init_test;
+    use project::FakeFs;
+    use serde_json::json;
+    use std::path::Path;
+
+    #[test]
+    fn test_index_row_to_buffer_row() {
+        // Two rows were inserted at buffer row 2, and index rows 5..7 were replaced by one row.
+        let unstaged_rows = [(2..4, 2..2), (7..8, 5..7)];
+        assert_eq!(index_row_to_buffer_row(1, &unstaged_rows), 1);
+        assert_eq!(index_row_to_buffer_row(2, &unstaged_rows), 4);
+        assert_eq!(index_row_to_buffer_row(5, &unstaged_rows), 7);
+        assert_eq!(index_row_to_buffer_row(6, &unstaged_rows), 7);
+        assert_eq!(index_row_to_buffer_row(7, &unstaged_rows), 8);
+    }
+
+    #[gpui::test]
+    async fn test_staging_hunks(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+
+        let head_text = "one\ntwo\nthree\nfour\n".to_string();
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                ".git": {},
+                "file.txt": "one\nTWO\nthree\nFOUR\n",
+            }),
+        )
+        .await;
+        fs.set_index_for_repo(
+            Path::new("/dir/.git"),
+            &[(Path::new("file.txt"), head_text.clone())],
+        );
+        fs.set_head_for_repo(
+            Path::new("/dir/.git"),
+            &[(Path::new("file.txt"), head_text.clone())],
+        );
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/dir/file.txt", cx)
+            })
+            .await
+            .unwrap();
+        let editor = cx.add_window(|cx| Editor::for_buffer(buffer.clone(), Some(project), cx));
+        cx.run_until_parked();
+
+        let index_text = |cx: &mut gpui::TestAppContext| {
+            buffer.read_with(cx, |buffer, _| {
+                buffer.diff_base().map(|text| text.to_string())
+            })
+        };
+
+        // Stage the second of the two changed rows.
+        _ = editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.select_ranges([Point::new(3, 2)..Point::new(3, 2)])
+            });
+            editor.stage_hunk(&StageHunk, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(index_text(cx).as_deref(), Some("one\ntwo\nthree\nFOUR\n"));
+        _ = editor.update(cx, |editor, _| {
+            let staged_hunks = editor
+                .staged_hunks
+                .hunks()
+                .iter()
+                .map(|hunk| (hunk.status, hunk.head_byte_range.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(staged_hunks, vec![(DiffHunkStatus::Modified, 14..19)]);
+        });
+
+        // Unstage it again.
+        _ = editor.update(cx, |editor, cx| editor.unstage_hunk(&UnstageHunk, cx));
+        cx.run_until_parked();
+        assert_eq!(index_text(cx), Some(head_text.clone()));
+
+        // Unsaved edits aren't staged, neither with their hunks nor with the whole file.
+        buffer.update(cx, |buffer, cx| {
+            buffer.edit([(Point::new(3, 4)..Point::new(3, 4), "!")], None, cx)
+        });
+        cx.run_until_parked();
+        _ = editor.update(cx, |editor, cx| editor.stage_hunk(&StageHunk, cx));
+        cx.run_until_parked();
+        assert_eq!(index_text(cx), Some(head_text.clone()));
+        _ = editor.update(cx, |editor, cx| editor.stage_file(&StageFile, cx));
+        cx.run_until_parked();
+        assert_eq!(index_text(cx).as_deref(), Some("one\nTWO\nthree\nFOUR\n"));
+        _ = editor.update(cx, |editor, cx| editor.unstage_file(&UnstageFile, cx));
+        cx.run_until_parked();
+        assert_eq!(index_text(cx), Some(head_text));
+    }
+}
@@ -0,0 +1,260 @@
+use std::ops::Range;
+
+use aho_corasick::AhoCorasickBuilder;
+use gpui::ViewContext;
+use language::{char_kind, CharKind};
+use multi_buffer::{MultiBufferSnapshot, ToOffset};
+use settings::Settings;
+use text::{Bias, Selection, SelectionGoal};
+use util::RangeExt;
+
+use crate::{
+    scroll::Autoscroll, Anchor, DisplayPoint, DisplayRow, Editor, EditorSettings, RangeToAnchorExt,
+    SelectHighlightedOccurrences, DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT,
+};
+
+pub(crate) enum OccurrenceHighlight {}
+
+/// Schedules highlighting of the other occurrences of the word under the newest cursor, or of
+/// the newest selection's text, once the cursor has been idle for a short while.
+pub fn refresh_occurrence_highlights(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    editor.clear_background_highlights::<OccurrenceHighlight>(cx);
+    if !EditorSettings::get_global(cx).highlight_occurrences {
+        editor.occurrence_highlights_task = None;
+        return;
+    }
+
+    editor.occurrence_highlights_task = Some(cx.spawn(|editor, mut cx| async move {
+        cx.background_executor()
+            .timer(DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT)
+            .await;
+        editor
+            .update(&mut cx, |editor, cx| highlight_occurrences(editor, cx))
+            .ok();
+    }));
+}
+
+fn highlight_occurrences(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let ranges = visible_occurrences(editor, false, cx);
+    if !ranges.is_empty() {
+        editor.highlight_background::<OccurrenceHighlight>(
+            &ranges,
+            |theme| theme.editor_document_highlight_read_background,
+            cx,
+        );
+    }
+}
+
+/// Returns the visible occurrences of the word under the newest cursor, or of the newest
+/// selection's text unless `words_only` is set. A lone occurrence is the one under the
+/// cursor, which is not worth highlighting, so nothing is returned then.
+pub(crate) fn visible_occurrences(
+    editor: &mut Editor,
+    words_only: bool,
+    cx: &mut ViewContext<Editor>,
+) -> Vec<Range<Anchor>> {
+    let newest_selection = editor.selections.newest::<usize>(cx);
+    let snapshot = editor.snapshot(cx);
+    let buffer = &snapshot.buffer_snapshot;
+
+    // Whole words are only matched against other whole words, while selected text
+    // matches anywhere. Both are case-sensitive unless configured otherwise.
+    let (query, wordwise) = if newest_selection.is_empty() {
+        let (word_range, kind) = buffer.surrounding_word(newest_selection.head());
+        if kind != Some(CharKind::Word) || word_range.is_empty() {
+            return Vec::new();
+        }
+        (buffer.text_for_range(word_range).collect::<String>(), true)
+    } else {
+        let text = buffer
+            .text_for_range(newest_selection.range())
+            .collect::<String>();
+        if words_only || text.contains('\n') || text.trim().is_empty() {
+            return Vec::new();
+        }
+        (text, false)
+    };
+
+    let search_range = match editor.visible_line_count() {
+        Some(visible_line_count) => {
+            let scroll_top = snapshot.scroll_position().y;
+            let start = DisplayPoint::new(DisplayRow(scroll_top.floor() as u32), 0);
+            let end = DisplayPoint::new(
+                DisplayRow((scroll_top + visible_line_count).ceil() as u32 + 1),
+                0,
+            );
+            start.to_offset(&snapshot, Bias::Left)
+                ..snapshot
+                    .clip_point(end, Bias::Right)
+                    .to_offset(&snapshot, Bias::Right)
+        }
+        None => 0..buffer.len(),
+    };
+
+    let case_sensitive = EditorSettings::get_global(cx).highlight_occurrences_case_sensitive;
+    let ranges = find_occurrences(buffer, search_range, &query, wordwise, case_sensitive);
+    if ranges.len() > 1 {
+        ranges
+            .into_iter()
+            .map(|range| range.to_anchors(buffer))
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+fn find_occurrences(
+    buffer: &MultiBufferSnapshot,
+    range: Range<usize>,
+    query: &str,
+    wordwise: bool,
+    case_sensitive: bool,
+) -> Vec<Range<usize>> {
+    let Ok(matcher) = AhoCorasickBuilder::new()
+        .ascii_case_insensitive(!case_sensitive)
+        .build([query])
+    else {
+        return Vec::new();
+    };
+    let text = buffer.text_for_range(range.clone()).collect::<String>();
+    let is_word_boundary = |offset: usize, c: Option<char>| {
+        c.map_or(true, |c| {
+            char_kind(&buffer.language_scope_at(offset), c) != CharKind::Word
+        })
+    };
+
+    matcher
+        .find_iter(&text)
+        .map(|occurrence| range.start + occurrence.start()..range.start + occurrence.end())
+        .filter(|occurrence| {
+            !wordwise
+                || (is_word_boundary(
+                    occurrence.start,
+                    buffer.reversed_chars_at(occurrence.start).next(),
+                ) && is_word_boundary(occurrence.end, buffer.chars_at(occurrence.end).next()))
+        })
+        .collect()
+}
+
+/// Adds a selection for every highlighted occurrence that isn't already selected.
+pub fn select_highlighted_occurrences(
+    editor: &mut Editor,
+    _: &SelectHighlightedOccurrences,
+    cx: &mut ViewContext<Editor>,
+) {
+    let Some(ranges) = editor
+        .background_highlights
+        .get(&std::any::TypeId::of::<OccurrenceHighlight>())
+        .map(|(_, ranges)| ranges.clone())
+    else {
+        return;
+    };
+
+    editor.push_to_selection_history();
+    let buffer = editor.buffer.read(cx).snapshot(cx);
+    let mut selections = editor.selections.all::<usize>(cx);
+    for range in ranges.iter() {
+        let range = range.start.to_offset(&buffer)..range.end.to_offset(&buffer);
+        if selections
+            .iter()
+            .any(|selection| selection.range().overlaps(&range))
+        {
+            continue;
+        }
+        editor.selections.change_with(cx, |s| {
+            selections.push(Selection {
+                id: s.new_selection_id(),
+                start: range.start,
+                end: range.end,
+                reversed: false,
+                goal: SelectionGoal::None,
+            });
+        });
+    }
+
+    editor.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{editor_tests::init_test, test::editor_test_context::EditorTestContext};
+    use indoc::indoc;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_occurrence_highlights(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        _ = cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<EditorSettings>(cx, |settings| {
+                    settings.highlight_occurrences = Some(true);
+                });
+            })
+        });
+
+        let mut cx = EditorTestContext::new(cx).await;
+
+        // Words under the cursor only match whole words.
+        cx.set_state(indoc! {"
+            let aˇbc = abc + abcd;
+            abc.def()
+        "});
+        cx.executor()
+            .advance_clock(DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT);
+        cx.run_until_parked();
+        cx.assert_editor_background_highlights::<OccurrenceHighlight>(indoc! {"
+            let «abc» = «abc» + abcd;
+            «abc».def()
+        "});
+
+        // Selected text matches anywhere.
+        cx.set_state(indoc! {"
+            let «abcˇ» = abc + abcd;
+            abc.def()
+        "});
+        cx.executor()
+            .advance_clock(DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT);
+        cx.run_until_parked();
+        cx.assert_editor_background_highlights::<OccurrenceHighlight>(indoc! {"
+            let «abc» = «abc» + «abc»d;
+            «abc».def()
+        "});
+
+        cx.update_editor(|editor, cx| {
+            select_highlighted_occurrences(editor, &SelectHighlightedOccurrences, cx)
+        });
+        cx.assert_editor_state(indoc! {"
+            let «abcˇ» = «abcˇ» + «abcˇ»d;
+            «abcˇ».def()
+        "});
+
+        // Occurrences only match in case by default.
+        cx.set_state(indoc! {"
+            let aˇbc = ABC + Abc;
+        "});
+        cx.executor()
+            .advance_clock(DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT);
+        cx.run_until_parked();
+        cx.assert_editor_background_highlights::<OccurrenceHighlight>(indoc! {"
+            let abc = ABC + Abc;
+        "});
+
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<EditorSettings>(cx, |settings| {
+                    settings.highlight_occurrences_case_sensitive = Some(false);
+                });
+            })
+        });
+        cx.set_state(indoc! {"
+            let aˇbc = ABC + Abc;
+        "});
+        cx.executor()
+            .advance_clock(DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT);
+        cx.run_until_parked();
+        cx.assert_editor_background_highlights::<OccurrenceHighlight>(indoc! {"
+            let «abc» = «ABC» + «Abc»;
+        "});
+    }
+}
@@ -4,12 +4,12 @@ use crate::{
     PointForPosition, SelectPhase,
 };
 use gpui::{px, AppContext, AsyncWindowContext, Model, Modifiers, Task, ViewContext};
-use language::{Bias, ToOffset};
+use language::{Bias, Point, ToOffset};
 use linkify::{LinkFinder, LinkKind};
 use lsp::LanguageServerId;
 use project::{
     HoverBlock, HoverBlockKind, InlayHintLabelPartTooltip, InlayHintTooltip, LocationLink,
-    ResolveState,
+    ProjectPath, ResolveState,
 };
 use std::ops::Range;
 use theme::ActiveTheme as _;
@@ -58,6 +58,7 @@ impl RangeInEditor {
 #[derive(Debug, Clone)]
 pub enum HoverLink {
     Url(String),
+    File(ProjectPath, Point),
     Text(LocationLink),
     InlayHint(lsp::Location, LanguageServerId),
 }
@@ -468,7 +469,7 @@ pub fn show_link_definition(
         || hovered_link_state
             .links
             .first()
-            .is_some_and(|d| matches!(d, HoverLink::Url(_)));
+            .is_some_and(|d| matches!(d, HoverLink::Url(_) | HoverLink::File(..)));
 
     if same_kind {
         if is_cached && (&hovered_link_state.last_trigger_point == &trigger_point)
@@ -486,13 +487,18 @@ pub fn show_link_definition(
         editor.hide_hovered_link(cx)
     }
     let project = editor.project.clone();
+    let detected_link = editor.detected_link_at(*trigger_anchor, &snapshot.buffer_snapshot);
 
     let snapshot = snapshot.buffer_snapshot.clone();
     hovered_link_state.task = Some(cx.spawn(|this, mut cx| {
         async move {
             let result = match &trigger_point {
                 TriggerPoint::Text(_) => {
-                    if let Some((url_range, url)) = find_url(&buffer, buffer_position, cx.clone()) {
+                    if let Some((range, link)) = detected_link {
+                        Some((Some(RangeInEditor::Text(range)), vec![link]))
+                    } else if let Some((url_range, url)) =
+                        find_url(&buffer, buffer_position, cx.clone())
+                    {
                         this.update(&mut cx, |_, _| {
                             let range = maybe!({
                                 let start =
@@ -1247,4 +1253,46 @@ mod tests {
         cx.simulate_click(screen_coord, Modifiers::secondary_key());
         assert_eq!(cx.opened_url(), Some("https://zed.dev/releases".into()));
     }
+
+    #[gpui::test]
+    async fn test_file_references(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+
+        cx.set_state(indoc! {"
+            // See file.rs:2:4 for ˇdetails.
+            fn a() {}
+            fn b() {}
+        "});
+        cx.executor()
+            .advance_clock(crate::DETECTED_LINKS_DEBOUNCE_TIMEOUT);
+        cx.run_until_parked();
+
+        let screen_coord = cx.pixel_position(indoc! {"
+            // See fileˇ.rs:2:4 for details.
+            fn a() {}
+            fn b() {}
+        "});
+
+        cx.simulate_mouse_move(screen_coord, None, Modifiers::secondary_key());
+        cx.assert_editor_text_highlights::<HoveredLinkState>(indoc! {"
+            // See «file.rs:2:4ˇ» for details.
+            fn a() {}
+            fn b() {}
+        "});
+
+        cx.simulate_click(screen_coord, Modifiers::secondary_key());
+        cx.run_until_parked();
+        cx.assert_editor_state(indoc! {"
+            // See file.rs:2:4 for details.
+            fn ˇa() {}
+            fn b() {}
+        "});
+    }
 }
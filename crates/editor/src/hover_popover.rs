@@ -7,8 +7,7 @@ use crate::{
 use futures::{stream::FuturesUnordered, FutureExt};
 use gpui::{
     div, px, AnyElement, CursorStyle, Hsla, InteractiveElement, IntoElement, MouseButton,
-    ParentElement, Pixels, SharedString, Size, StatefulInteractiveElement, Styled, Task,
-    ViewContext, WeakView,
+    ParentElement, Pixels, Size, StatefulInteractiveElement, Styled, Task, ViewContext, WeakView,
 };
 use language::{markdown, DiagnosticEntry, Language, LanguageRegistry, ParsedMarkdown};
 
@@ -277,16 +276,33 @@ fn show_hover(
                     })
             });
 
+            let language_registry = project.update(&mut cx, |p, _| p.languages().clone())?;
+            let diagnostic_popover = if let Some(local_diagnostic) = local_diagnostic {
+                let diagnostic = &local_diagnostic.diagnostic;
+                // Diagnostic messages are plain text, since the language server protocol has no
+                // way to send them as markup, so characters like `*` are shown as they are.
+                let text = match &diagnostic.source {
+                    Some(source) => format!("{source}: {}", diagnostic.message),
+                    None => diagnostic.message.clone(),
+                };
+                let parsed_message = ParsedMarkdown {
+                    text,
+                    ..Default::default()
+                };
+                Some(DiagnosticPopover {
+                    local_diagnostic,
+                    primary_diagnostic,
+                    parsed_message,
+                })
+            } else {
+                None
+            };
+
             this.update(&mut cx, |this, _| {
-                this.hover_state.diagnostic_popover =
-                    local_diagnostic.map(|local_diagnostic| DiagnosticPopover {
-                        local_diagnostic,
-                        primary_diagnostic,
-                    });
+                this.hover_state.diagnostic_popover = diagnostic_popover;
             })?;
 
             let hovers_response = hover_request.await;
-            let language_registry = project.update(&mut cx, |p, _| p.languages().clone())?;
             let snapshot = this.update(&mut cx, |this, cx| this.snapshot(cx))?;
             let mut hover_highlights = Vec::with_capacity(hovers_response.len());
             let mut info_popovers = Vec::with_capacity(hovers_response.len());
@@ -477,7 +493,7 @@ impl HoverState {
         let mut elements = Vec::new();
 
         if let Some(diagnostic_popover) = self.diagnostic_popover.as_ref() {
-            elements.push(diagnostic_popover.render(style, max_size, cx));
+            elements.push(diagnostic_popover.render(style, max_size, workspace.clone(), cx));
         }
         for info_popover in &mut self.info_popovers {
             elements.push(info_popover.render(style, max_size, workspace.clone(), cx));
@@ -527,6 +543,7 @@ impl InfoPopover {
 pub struct DiagnosticPopover {
     local_diagnostic: DiagnosticEntry<Anchor>,
     primary_diagnostic: Option<DiagnosticEntry<Anchor>>,
+    parsed_message: ParsedMarkdown,
 }
 
 impl DiagnosticPopover {
@@ -534,13 +551,9 @@ impl DiagnosticPopover {
         &self,
         style: &EditorStyle,
         max_size: Size<Pixels>,
+        workspace: Option<WeakView<Workspace>>,
         cx: &mut ViewContext<Editor>,
     ) -> AnyElement {
-        let text = match &self.local_diagnostic.diagnostic.source {
-            Some(source) => format!("{source}: {}", self.local_diagnostic.diagnostic.message),
-            None => self.local_diagnostic.diagnostic.message.clone(),
-        };
-
         let status_colors = cx.theme().status();
 
         struct DiagnosticColors {
@@ -594,7 +607,13 @@ impl DiagnosticPopover {
             // because that would move the cursor.
             .on_mouse_down(MouseButton::Left, |_, cx| cx.stop_propagation())
             .on_click(cx.listener(|editor, _, cx| editor.go_to_diagnostic(&Default::default(), cx)))
-            .child(SharedString::from(text))
+            .child(crate::render_parsed_markdown(
+                "diagnostic_message",
+                &self.parsed_message,
+                style,
+                workspace,
+                cx,
+            ))
             .into_any_element()
     }
 
@@ -1093,7 +1112,7 @@ mod tests {
                 vec![DiagnosticEntry {
                     range,
                     diagnostic: Diagnostic {
-                        message: "A test diagnostic message.".to_string(),
+                        message: "A test diagnostic message about `*ptr`.".to_string(),
                         ..Default::default()
                     },
                 }],
@@ -1109,7 +1128,18 @@ mod tests {
         cx.editor(|Editor { hover_state, .. }, _| {
             assert!(
                 hover_state.diagnostic_popover.is_some() && hover_state.info_popovers.is_empty()
-            )
+            );
+            // The message is plain text, so it's not rendered as Markdown.
+            let parsed_message = &hover_state
+                .diagnostic_popover
+                .as_ref()
+                .unwrap()
+                .parsed_message;
+            assert_eq!(
+                parsed_message.text,
+                "A test diagnostic message about `*ptr`."
+            );
+            assert!(parsed_message.highlights.is_empty());
         });
 
         // Info Popover shows after request responded to
@@ -2,7 +2,7 @@ use std::{ops::Range, time::Duration};
 
 use collections::HashSet;
 use gpui::{AppContext, Task};
-use language::BufferRow;
+use language::{AlignmentGuide, BufferRow, ElasticTabstops};
 use multi_buffer::{MultiBufferIndentGuide, MultiBufferRow};
 use text::{BufferId, LineIndent, Point};
 use ui::ViewContext;
@@ -46,6 +46,40 @@ impl Editor {
         }
     }
 
+    /// The guides along the elastic tabstop columns of the visible rows, when elastic tabstops
+    /// are enabled. The layout is kept between calls, and only laid out again around the rows
+    /// that were edited in the meantime. Only supported in editors of a single buffer.
+    ///
+    /// The display map doesn't use this layout, so the columns are where the tabs would end if
+    /// they were elastic rather than where they're displayed.
+    pub fn alignment_guides(
+        &mut self,
+        visible_buffer_range: Range<MultiBufferRow>,
+        cx: &mut ViewContext<Editor>,
+    ) -> Option<Vec<AlignmentGuide>> {
+        let multi_buffer = self.buffer.read(cx);
+        let settings = multi_buffer.settings_at(0, cx);
+        let tab_size = settings.tab_size.get();
+        if !settings.indent_guides.elastic_tabstops {
+            self.elastic_tabstops = None;
+            return None;
+        }
+        let snapshot = multi_buffer.as_singleton()?.read(cx).text_snapshot();
+
+        let elastic_tabstops = match &mut self.elastic_tabstops {
+            Some(elastic_tabstops) if elastic_tabstops.tab_size() == tab_size => {
+                elastic_tabstops.sync(&snapshot);
+                elastic_tabstops
+            }
+            elastic_tabstops => elastic_tabstops.insert(ElasticTabstops::new(&snapshot, tab_size)),
+        };
+        Some(
+            elastic_tabstops.alignment_guides_in_range(
+                visible_buffer_range.start.0..visible_buffer_range.end.0,
+            ),
+        )
+    }
+
     pub fn find_active_indent_guide_indices(
         &mut self,
         indent_guides: &[MultiBufferIndentGuide],
@@ -0,0 +1,184 @@
+//! The most severe diagnostic of each line can be shown after the line, like virtual text.
+//! A buffer's diagnostics are only collected again when they change, and their messages are
+//! cut to their first line and to the configured length.
+
+use std::ops::Range;
+
+use collections::{BTreeMap, HashMap};
+use gpui::{AppContext, SharedString, ViewContext};
+use language::{Bias, Point, ToOffset};
+use lsp::DiagnosticSeverity;
+use multi_buffer::{MultiBufferRow, ToPoint};
+use settings::Settings;
+use text::BufferId;
+
+use crate::{Editor, EditorMode, EditorSettings};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlineDiagnostic {
+    pub row: MultiBufferRow,
+    pub severity: DiagnosticSeverity,
+    pub message: SharedString,
+    /// Whether the message was cut because it has several lines or is too long.
+    pub is_truncated: bool,
+}
+
+#[derive(Default)]
+pub struct InlineDiagnosticsState {
+    enabled: bool,
+    max_length: usize,
+    buffers: HashMap<BufferId, BufferDiagnostics>,
+}
+
+struct BufferDiagnostics {
+    update_count: usize,
+    /// The starts, severities and messages of the buffer's primary diagnostics, in the order
+    /// they appear.
+    diagnostics: Vec<(text::Anchor, DiagnosticSeverity, SharedString)>,
+}
+
+impl InlineDiagnosticsState {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Collects the diagnostics of the editor's buffers whose diagnostics changed since they were
+/// last collected, or forgets them if inline diagnostics are disabled.
+pub(crate) fn refresh_inline_diagnostics(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let settings = EditorSettings::get_global(cx).inline_diagnostics;
+    let state = &mut editor.inline_diagnostics_state;
+    state.enabled = settings.enabled && editor.mode == EditorMode::Full;
+    state.max_length = settings.max_length;
+    if !state.enabled {
+        state.buffers.clear();
+        return;
+    }
+
+    let buffers = editor
+        .buffer
+        .read(cx)
+        .all_buffers()
+        .into_iter()
+        .map(|buffer| buffer.read(cx).snapshot())
+        .collect::<Vec<_>>();
+    state.buffers.retain(|buffer_id, _| {
+        buffers
+            .iter()
+            .any(|buffer| buffer.remote_id() == *buffer_id)
+    });
+    for buffer in buffers {
+        let update_count = buffer.diagnostics_update_count();
+        if state
+            .buffers
+            .get(&buffer.remote_id())
+            .is_some_and(|diagnostics| diagnostics.update_count == update_count)
+        {
+            continue;
+        }
+
+        let diagnostics = buffer
+            .diagnostics_in_range::<_, text::Anchor>(0..buffer.len(), false)
+            .filter(|entry| entry.diagnostic.is_primary)
+            .map(|entry| {
+                (
+                    entry.range.start,
+                    entry.diagnostic.severity,
+                    SharedString::from(entry.diagnostic.message),
+                )
+            })
+            .collect();
+        state.buffers.insert(
+            buffer.remote_id(),
+            BufferDiagnostics {
+                update_count,
+                diagnostics,
+            },
+        );
+    }
+    cx.notify();
+}
+
+impl Editor {
+    /// The most severe diagnostic starting on each of the rows that has one, for showing it
+    /// after the row. Nothing is returned when inline diagnostics are disabled.
+    pub fn inline_diagnostics(
+        &self,
+        rows: Range<MultiBufferRow>,
+        cx: &AppContext,
+    ) -> Vec<InlineDiagnostic> {
+        let state = &self.inline_diagnostics_state;
+        if !state.enabled || rows.is_empty() {
+            return Vec::new();
+        }
+
+        let multi_buffer = self.buffer.read(cx);
+        let snapshot = multi_buffer.snapshot(cx);
+        let range =
+            Point::new(rows.start.0, 0)..snapshot.clip_point(Point::new(rows.end.0, 0), Bias::Left);
+        let mut by_row = BTreeMap::<MultiBufferRow, (DiagnosticSeverity, &SharedString)>::new();
+        for (buffer, buffer_range, excerpt_id) in multi_buffer.range_to_buffer_ranges(range, cx) {
+            let buffer = buffer.read(cx);
+            let Some(diagnostics) = state.buffers.get(&buffer.remote_id()) else {
+                continue;
+            };
+            let start_ix = diagnostics
+                .diagnostics
+                .partition_point(|(start, _, _)| start.to_offset(buffer) < buffer_range.start);
+            for (start, severity, message) in &diagnostics.diagnostics[start_ix..] {
+                if start.to_offset(buffer) > buffer_range.end {
+                    break;
+                }
+                let Some(start) = snapshot.anchor_in_excerpt(excerpt_id, *start) else {
+                    continue;
+                };
+                let row = MultiBufferRow(start.to_point(&snapshot).row);
+                if !(rows.start..rows.end).contains(&row) {
+                    continue;
+                }
+                by_row
+                    .entry(row)
+                    .and_modify(|most_severe| {
+                        if *severity < most_severe.0 {
+                            *most_severe = (*severity, message);
+                        }
+                    })
+                    .or_insert((*severity, message));
+            }
+        }
+
+        by_row
+            .into_iter()
+            .map(|(row, (severity, message))| {
+                let (message, is_truncated) = truncate_message(message, state.max_length);
+                InlineDiagnostic {
+                    row,
+                    severity,
+                    message,
+                    is_truncated,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Cuts the message to its first line, and to the length, ending it with an ellipsis when
+/// it's too long.
+fn truncate_message(message: &SharedString, max_length: usize) -> (SharedString, bool) {
+    let trimmed = message.trim_end();
+    let first_line = trimmed.lines().next().unwrap_or_default().trim_end();
+    if first_line.chars().count() > max_length {
+        let mut truncated = first_line
+            .chars()
+            .take(max_length.saturating_sub(1))
+            .collect::<String>();
+        truncated.push('…');
+        (truncated.into(), true)
+    } else if first_line.len() < trimmed.len() {
+        (SharedString::from(first_line.to_string()), true)
+    } else if trimmed.len() < message.len() {
+        (SharedString::from(trimmed.to_string()), false)
+    } else {
+        (message.clone(), false)
+    }
+}
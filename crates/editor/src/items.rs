@@ -30,6 +30,7 @@ use std::{
     ops::Range,
     path::Path,
     sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use text::{BufferId, Selection};
 use theme::{Theme, ThemeSettings};
@@ -40,10 +41,14 @@ use workspace::{
     item::{FollowableItem, Item, ItemEvent, ItemHandle, ProjectItem},
     searchable::{Direction, SearchEvent, SearchableItem, SearchableItemHandle},
     ItemId, ItemNavHistory, Pane, ToolbarItemLocation, ViewId, Workspace, WorkspaceId,
+    WorkspaceSettings,
 };
 
 pub const MAX_TAB_TITLE_LEN: usize = 24;
 
+/// How long a buffer must go without edits before its unsaved changes are backed up.
+const BUFFER_BACKUP_DEBOUNCE: Duration = Duration::from_millis(500);
+
 impl FollowableItem for Editor {
     fn remote_id(&self) -> Option<ViewId> {
         self.remote_id
@@ -871,20 +876,33 @@ impl Item for Editor {
 
         if let Some(buffer) = self.buffer().read(cx).as_singleton() {
             serialize(buffer.clone(), workspace_id, item_id, cx);
+            self.schedule_backup(cx);
 
             cx.subscribe(&buffer, |this, buffer, event, cx| {
                 if let Some((_, workspace_id)) = this.workspace.as_ref() {
-                    if let language::Event::FileHandleChanged = event {
-                        serialize(
+                    match event {
+                        language::Event::FileHandleChanged => serialize(
                             buffer,
                             *workspace_id,
                             cx.view().item_id().as_u64() as ItemId,
                             cx,
-                        );
+                        ),
+                        language::Event::Edited | language::Event::DirtyChanged => {
+                            this.schedule_backup(cx)
+                        }
+                        _ => {}
                     }
                 }
             })
             .detach();
+
+            // The changes of an editor closed without saving them are discarded.
+            cx.on_release(move |_, _, cx| {
+                cx.background_executor()
+                    .spawn(DB.delete_buffer_backup(item_id, workspace_id))
+                    .detach_and_log_err(cx);
+            })
+            .detach();
         }
     }
 
@@ -933,6 +951,9 @@ impl Item for Editor {
         item_id: ItemId,
         cx: &mut ViewContext<Pane>,
     ) -> Task<Result<View<Self>>> {
+        let backup = cx
+            .background_executor()
+            .spawn(async move { DB.get_buffer_backup(item_id, workspace_id) });
         let project_item: Result<_> = project.update(cx, |project, cx| {
             // Look up the path with this key associated, create a self with that path
             let path = DB
@@ -957,8 +978,29 @@ impl Item for Editor {
                     let buffer = project_item
                         .downcast::<Buffer>()
                         .map_err(|_| anyhow!("Project item at stored path was not a buffer"))?;
+                    let backup = backup.await.log_err().flatten();
                     pane.update(&mut cx, |_, cx| {
                         cx.new_view(|cx| {
+                            // Unsaved changes were backed up when the previous session crashed.
+                            if let Some((contents, backed_up_at)) = backup {
+                                buffer.update(cx, |buffer, cx| {
+                                    let backed_up_at =
+                                        UNIX_EPOCH + Duration::from_millis(backed_up_at as u64);
+                                    // The file changed on disk after the backup, so applying the
+                                    // backup would revert those changes.
+                                    let modified_since_backup = buffer
+                                        .file()
+                                        .and_then(|file| file.mtime())
+                                        .map_or(false, |mtime| mtime > backed_up_at);
+                                    if modified_since_backup {
+                                        cx.background_executor()
+                                            .spawn(DB.delete_buffer_backup(item_id, workspace_id))
+                                            .detach_and_log_err(cx);
+                                    } else if buffer.text() != contents {
+                                        buffer.set_text(contents, cx);
+                                    }
+                                });
+                            }
                             let mut editor = Editor::for_buffer(buffer, Some(project), cx);
 
                             editor.read_scroll_position_from_db(item_id, workspace_id, cx);
@@ -971,6 +1013,54 @@ impl Item for Editor {
     }
 }
 
+impl Editor {
+    /// Backs up the unsaved changes of the editor's buffer once it goes without edits for a
+    /// while, so that they can be restored if the app crashes before they're saved.
+    fn schedule_backup(&mut self, cx: &mut ViewContext<Self>) {
+        let Some((_, workspace_id)) = self.workspace.clone() else {
+            return;
+        };
+        let Some(buffer) = self.buffer().read(cx).as_singleton() else {
+            return;
+        };
+        if !WorkspaceSettings::get_global(cx).restore_after_crash {
+            self.backup_task = None;
+            return;
+        }
+
+        let item_id = cx.view().item_id().as_u64() as ItemId;
+        self.backup_task = Some(cx.spawn(|_, mut cx| async move {
+            cx.background_executor().timer(BUFFER_BACKUP_DEBOUNCE).await;
+            let Ok(rope) = buffer.update(&mut cx, |buffer, _| {
+                buffer.is_dirty().then(|| buffer.as_rope().clone())
+            }) else {
+                return;
+            };
+            cx.background_executor()
+                .spawn(async move {
+                    match rope {
+                        Some(rope) => {
+                            let backed_up_at = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_millis() as i64;
+                            DB.save_buffer_backup(
+                                item_id,
+                                workspace_id,
+                                rope.to_string(),
+                                backed_up_at,
+                            )
+                            .await
+                        }
+                        None => DB.delete_buffer_backup(item_id, workspace_id).await,
+                    }
+                })
+                .await
+                .log_err();
+        }));
+    }
+}
+
 impl ProjectItem for Editor {
     type Item = Buffer;
 
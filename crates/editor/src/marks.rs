@@ -0,0 +1,306 @@
+//! Marks are named positions in files, which can be jumped back to from anywhere in the
+//! workspace. They are stored globally and persisted, so they survive restarts.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use collections::BTreeMap;
+use gpui::{
+    AppContext, Context, Global, Model, ModelContext, SharedString, Subscription, Task,
+    ViewContext, WeakModel,
+};
+use language::{Buffer, Point, ToPoint};
+use text::Bias;
+use util::ResultExt;
+use workspace::Workspace;
+
+use crate::{persistence::DB, scroll::Autoscroll, Editor, GoToMark, SetMark};
+
+struct GlobalMarks(Model<Marks>);
+
+impl Global for GlobalMarks {}
+
+/// All of the marks that have been set, keyed by their name.
+pub struct Marks {
+    marks: BTreeMap<SharedString, Mark>,
+}
+
+struct Mark {
+    abs_path: Arc<Path>,
+    /// The mark's position while its buffer is open, so that it moves along with edits.
+    anchor: Option<(WeakModel<Buffer>, text::Anchor)>,
+    /// The mark's last known position, used once its buffer has been released.
+    point: Point,
+    _buffer_subscription: Option<Subscription>,
+}
+
+/// A mark, resolved to its current position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkLocation {
+    pub name: SharedString,
+    pub abs_path: Arc<Path>,
+    pub point: Point,
+}
+
+impl Marks {
+    pub fn init(cx: &mut AppContext) {
+        let marks = cx.new_model(|cx| {
+            let saved_marks = cx.background_executor().spawn(async { DB.get_marks() });
+            cx.spawn(|this, mut cx| async move {
+                let saved_marks = saved_marks.await?;
+                // Marks set while loading are newer than the saved ones with the same names.
+                this.update(&mut cx, |this, _| {
+                    for (name, abs_path, row, column) in saved_marks {
+                        this.marks.entry(name.into()).or_insert_with(|| Mark {
+                            abs_path: abs_path.into(),
+                            anchor: None,
+                            point: Point::new(row, column),
+                            _buffer_subscription: None,
+                        });
+                    }
+                })
+            })
+            .detach_and_log_err(cx);
+
+            Self {
+                marks: BTreeMap::default(),
+            }
+        });
+        cx.set_global(GlobalMarks(marks));
+    }
+
+    pub fn global(cx: &AppContext) -> Option<Model<Self>> {
+        cx.try_global::<GlobalMarks>().map(|marks| marks.0.clone())
+    }
+
+    /// Returns the smallest positive number that isn't used as the name of a mark yet.
+    pub fn unused_number(&self) -> SharedString {
+        (1..)
+            .map(|number: usize| number.to_string())
+            .find(|name| !self.marks.contains_key(name.as_str()))
+            .expect("there are infinitely many numbers")
+            .into()
+    }
+
+    /// Sets the mark with the given name to `position` in `buffer`, replacing any previous
+    /// mark with the same name.
+    pub fn set(
+        &mut self,
+        name: SharedString,
+        buffer: &Model<Buffer>,
+        position: text::Anchor,
+        abs_path: Arc<Path>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        // Persist the mark's position whenever its buffer is written to disk, which is when
+        // that position matches the file that it will be restored in.
+        let subscription = cx.subscribe(buffer, {
+            let name = name.clone();
+            move |this, _, event, cx| {
+                if let language::Event::Saved = event {
+                    this.save_current_position(&name, cx);
+                }
+            }
+        });
+        let point = position.to_point(buffer.read(cx));
+        self.marks.insert(
+            name.clone(),
+            Mark {
+                abs_path: abs_path.clone(),
+                anchor: Some((buffer.downgrade(), position)),
+                point,
+                _buffer_subscription: Some(subscription),
+            },
+        );
+        self.save_mark(name, abs_path.to_path_buf(), point, cx);
+        cx.notify();
+    }
+
+    pub fn remove(&mut self, name: &str, cx: &mut ModelContext<Self>) {
+        if self.marks.remove(name).is_some() {
+            let name = name.to_string();
+            cx.background_executor()
+                .spawn(async move { DB.delete_mark(name).await.log_err() })
+                .detach();
+            cx.notify();
+        }
+    }
+
+    pub fn location(&self, name: &str, cx: &AppContext) -> Option<MarkLocation> {
+        let (name, mark) = self.marks.get_key_value(name)?;
+        Some(MarkLocation {
+            name: name.clone(),
+            abs_path: mark.abs_path.clone(),
+            point: mark.current_point(cx),
+        })
+    }
+
+    /// Returns the locations of all marks, ordered by their names.
+    pub fn locations(&self, cx: &AppContext) -> Vec<MarkLocation> {
+        self.marks
+            .iter()
+            .map(|(name, mark)| MarkLocation {
+                name: name.clone(),
+                abs_path: mark.abs_path.clone(),
+                point: mark.current_point(cx),
+            })
+            .collect()
+    }
+
+    fn save_current_position(&mut self, name: &SharedString, cx: &mut ModelContext<Self>) {
+        let Some(mark) = self.marks.get_mut(name) else {
+            return;
+        };
+        mark.point = mark.current_point(cx);
+        let (abs_path, point) = (mark.abs_path.to_path_buf(), mark.point);
+        self.save_mark(name.clone(), abs_path, point, cx);
+    }
+
+    fn save_mark(
+        &self,
+        name: SharedString,
+        abs_path: PathBuf,
+        point: Point,
+        cx: &mut ModelContext<Self>,
+    ) {
+        cx.background_executor()
+            .spawn(async move {
+                DB.save_mark(name.to_string(), abs_path, point.row, point.column)
+                    .await
+                    .log_err()
+            })
+            .detach();
+    }
+}
+
+impl Mark {
+    fn current_point(&self, cx: &AppContext) -> Point {
+        self.anchor
+            .as_ref()
+            .and_then(|(buffer, anchor)| Some(anchor.to_point(buffer.upgrade()?.read(cx))))
+            .unwrap_or(self.point)
+    }
+}
+
+/// Sets a mark at the newest cursor, named after the action's name or the next unused number.
+pub fn set_mark(editor: &mut Editor, action: &SetMark, cx: &mut ViewContext<Editor>) {
+    let Some(marks) = Marks::global(cx) else {
+        return;
+    };
+    let head = editor.selections.newest_anchor().head();
+    let Some((buffer, position)) = editor.buffer.read(cx).text_anchor_for_position(head, cx) else {
+        return;
+    };
+    let Some(abs_path) = buffer
+        .read(cx)
+        .file()
+        .and_then(|file| file.as_local())
+        .map(|file| file.abs_path(cx))
+    else {
+        return;
+    };
+
+    marks.update(cx, |marks, cx| {
+        let name = match &action.name {
+            Some(name) => SharedString::from(name.clone()),
+            None => marks.unused_number(),
+        };
+        marks.set(name, &buffer, position, abs_path.into(), cx);
+    });
+}
+
+pub fn go_to_mark(workspace: &mut Workspace, action: &GoToMark, cx: &mut ViewContext<Workspace>) {
+    let Some(location) =
+        Marks::global(cx).and_then(|marks| marks.read(cx).location(&action.name, cx))
+    else {
+        return;
+    };
+    open_mark(workspace, location, cx).detach_and_log_err(cx);
+}
+
+/// Opens the file containing the given mark and moves the cursor to it.
+pub fn open_mark(
+    workspace: &mut Workspace,
+    location: MarkLocation,
+    cx: &mut ViewContext<Workspace>,
+) -> Task<Result<()>> {
+    open_location(workspace, location.abs_path, location.point, cx)
+}
+
+/// Opens the file at `abs_path` and moves the cursor to `point`.
+pub(crate) fn open_location(
+    workspace: &mut Workspace,
+    abs_path: Arc<Path>,
+    point: Point,
+    cx: &mut ViewContext<Workspace>,
+) -> Task<Result<()>> {
+    let open_task = workspace.open_abs_path(abs_path.to_path_buf(), true, cx);
+    cx.spawn(|_, mut cx| async move {
+        let item = open_task.await?;
+        if let Some(editor) = item.downcast::<Editor>() {
+            editor.update(&mut cx, |editor, cx| {
+                let buffer = editor.buffer.read(cx).snapshot(cx);
+                let point = buffer.clip_point(point, Bias::Left);
+                editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                    s.select_ranges([point..point])
+                });
+            })?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor_tests::init_test;
+
+    #[gpui::test]
+    async fn test_marks_follow_edits(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        cx.run_until_parked();
+
+        let marks = cx.update(|cx| Marks::global(cx).unwrap());
+        let buffer = cx.new_model(|cx| Buffer::local("one\ntwo\nthree", cx));
+        let abs_path: Arc<Path> = Path::new("/root/file.txt").into();
+
+        marks.update(cx, |marks, cx| {
+            let name = marks.unused_number();
+            assert_eq!(name.as_ref(), "1");
+            let position = buffer.read(cx).anchor_before(Point::new(1, 1));
+            marks.set(name, &buffer, position, abs_path.clone(), cx);
+
+            let position = buffer.read(cx).anchor_before(Point::new(2, 0));
+            marks.set("a".into(), &buffer, position, abs_path.clone(), cx);
+            assert_eq!(marks.unused_number().as_ref(), "2");
+        });
+
+        buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "zero\n")], None, cx));
+        marks.read_with(cx, |marks, cx| {
+            assert_eq!(
+                marks.locations(cx),
+                vec![
+                    MarkLocation {
+                        name: "1".into(),
+                        abs_path: abs_path.clone(),
+                        point: Point::new(2, 1),
+                    },
+                    MarkLocation {
+                        name: "a".into(),
+                        abs_path: abs_path.clone(),
+                        point: Point::new(3, 0),
+                    },
+                ]
+            );
+        });
+
+        marks.update(cx, |marks, cx| marks.remove("1", cx));
+        marks.read_with(cx, |marks, cx| {
+            assert_eq!(marks.location("1", cx), None);
+            assert_eq!(marks.unused_number().as_ref(), "1");
+        });
+    }
+}
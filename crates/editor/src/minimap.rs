@@ -0,0 +1,114 @@
+//! A line-by-line summary of the editor's text for drawing a minimap: each line's length and
+//! the runs of highlighted text on it. Only the lines touched by edits since the summary was
+//! last requested are summarized again, unless the buffers were reparsed.
+
+use std::ops::Range;
+
+use gpui::ViewContext;
+use language::{HighlightId, Point, Subscription as BufferSubscription};
+use multi_buffer::{MultiBufferRow, MultiBufferSnapshot};
+
+use crate::Editor;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MinimapLine {
+    /// The line's length, in characters.
+    pub len: u32,
+    /// The runs of non-whitespace text on the line as ranges of columns, in characters, with
+    /// the syntax highlight of the text. Runs that touch have different highlights.
+    pub runs: Vec<(Range<u32>, Option<HighlightId>)>,
+}
+
+#[derive(Default)]
+pub struct MinimapState {
+    lines: Vec<MinimapLine>,
+    /// The snapshot that the lines summarize, and the edits made since it was taken.
+    synced: Option<(MultiBufferSnapshot, BufferSubscription)>,
+}
+
+impl MinimapState {
+    /// Summarizes every line again the next time the lines are requested, for when their
+    /// highlights changed without edits.
+    pub(crate) fn invalidate(&mut self) {
+        self.synced = None;
+    }
+}
+
+impl Editor {
+    /// The summary of each of the editor's lines, for drawing a minimap.
+    pub fn minimap_lines(&mut self, cx: &mut ViewContext<Self>) -> &[MinimapLine] {
+        let state = &mut self.minimap_state;
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        match state.synced.as_mut() {
+            Some((old_snapshot, subscription)) => {
+                let edits = subscription.consume().into_inner();
+                if !edits.is_empty() {
+                    // Rows touched by several edits are summarized once.
+                    let mut row_edits: Vec<(Range<u32>, Range<u32>)> = Vec::new();
+                    for edit in edits {
+                        let old_rows = old_snapshot.offset_to_point(edit.old.start).row
+                            ..old_snapshot.offset_to_point(edit.old.end).row + 1;
+                        let new_rows = snapshot.offset_to_point(edit.new.start).row
+                            ..snapshot.offset_to_point(edit.new.end).row + 1;
+                        match row_edits.last_mut() {
+                            Some((last_old, last_new)) if old_rows.start < last_old.end => {
+                                last_old.end = last_old.end.max(old_rows.end);
+                                last_new.end = last_new.end.max(new_rows.end);
+                            }
+                            _ => row_edits.push((old_rows, new_rows)),
+                        }
+                    }
+
+                    let mut old_lines = std::mem::take(&mut state.lines).into_iter();
+                    let mut old_row = 0;
+                    for (old_rows, new_rows) in row_edits {
+                        state
+                            .lines
+                            .extend(old_lines.by_ref().take((old_rows.start - old_row) as usize));
+                        old_lines.by_ref().nth(old_rows.len().saturating_sub(1));
+                        old_row = old_rows.end;
+                        state.lines.extend(
+                            new_rows.map(|row| summarize_line(&snapshot, MultiBufferRow(row))),
+                        );
+                    }
+                    state.lines.extend(old_lines);
+                }
+                *old_snapshot = snapshot;
+            }
+            None => {
+                let subscription = self.buffer.update(cx, |buffer, _| buffer.subscribe());
+                state.lines = (0..=snapshot.max_point().row)
+                    .map(|row| summarize_line(&snapshot, MultiBufferRow(row)))
+                    .collect();
+                state.synced = Some((snapshot, subscription));
+            }
+        }
+        &self.minimap_state.lines
+    }
+}
+
+fn summarize_line(snapshot: &MultiBufferSnapshot, row: MultiBufferRow) -> MinimapLine {
+    let mut line = MinimapLine::default();
+    let range = Point::new(row.0, 0)..Point::new(row.0, snapshot.line_len(row));
+    for chunk in snapshot.chunks(range, true) {
+        for (ix, text) in chunk.text.split(char::is_whitespace).enumerate() {
+            if ix > 0 {
+                line.len += 1;
+            }
+            if text.is_empty() {
+                continue;
+            }
+            let start = line.len;
+            line.len += text.chars().count() as u32;
+            match line.runs.last_mut() {
+                Some((run, highlight))
+                    if run.end == start && *highlight == chunk.syntax_highlight_id =>
+                {
+                    run.end = line.len;
+                }
+                _ => line.runs.push((start..line.len, chunk.syntax_highlight_id)),
+            }
+        }
+    }
+    line
+}
@@ -9,6 +9,7 @@ use gpui::{px, Pixels, WindowTextSystem};
 use language::Point;
 use multi_buffer::{MultiBufferRow, MultiBufferSnapshot};
 use serde::Deserialize;
+use text::{has_rtl, visual_offset_left, visual_offset_right};
 
 use std::{ops::Range, sync::Arc};
 
@@ -78,6 +79,46 @@ pub fn saturating_right(map: &DisplaySnapshot, mut point: DisplayPoint) -> Displ
     map.clip_point(point, Bias::Right)
 }
 
+/// Returns the point displayed to the left of the current point, which follows it rather
+/// than precedes it in right-to-left text. Wraps to the end of the previous line if
+/// that point is at the left edge of the line.
+pub fn visual_left(map: &DisplaySnapshot, point: DisplayPoint) -> DisplayPoint {
+    let line = map.line(point.row());
+    if !has_rtl(&line) {
+        return left(map, point);
+    }
+    match visual_offset_left(&line, point.column() as usize) {
+        Some(column) => clip_in_direction(map, point, column as u32),
+        None => left(map, DisplayPoint::new(point.row(), 0)),
+    }
+}
+
+/// Returns the point displayed to the right of the current point, which precedes it rather
+/// than follows it in right-to-left text. Wraps to the start of the next line if
+/// that point is at the right edge of the line.
+pub fn visual_right(map: &DisplaySnapshot, point: DisplayPoint) -> DisplayPoint {
+    let line = map.line(point.row());
+    if !has_rtl(&line) {
+        return right(map, point);
+    }
+    match visual_offset_right(&line, point.column() as usize) {
+        Some(column) => clip_in_direction(map, point, column as u32),
+        None => right(
+            map,
+            DisplayPoint::new(point.row(), map.line_len(point.row())),
+        ),
+    }
+}
+
+fn clip_in_direction(map: &DisplaySnapshot, point: DisplayPoint, column: u32) -> DisplayPoint {
+    let bias = if column < point.column() {
+        Bias::Left
+    } else {
+        Bias::Right
+    };
+    map.clip_point(DisplayPoint::new(point.row(), column), bias)
+}
+
 /// Returns a display point for the preceding displayed line (which might be a soft-wrapped line).
 pub fn up(
     map: &DisplaySnapshot,
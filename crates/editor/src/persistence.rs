@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use anyhow::Result;
+
 use db::sqlez_macros::sql;
 use db::{define_connection, query};
 
@@ -15,6 +17,28 @@ define_connection!(
     //   scroll_vertical_offset: f32,
     //   scroll_horizontal_offset: f32,
     // )
+    //
+    // marks(
+    //   name: String,
+    //   path: PathBuf,
+    //   point_row: u32,
+    //   point_column: u32,
+    // )
+    //
+    // recent_edits(
+    //   workspace_id: usize,
+    //   path: PathBuf,
+    //   point_row: u32,
+    //   point_column: u32,
+    //   edited_at: i64,
+    // )
+    //
+    // buffer_backups(
+    //   item_id: usize,
+    //   workspace_id: usize,
+    //   contents: String,
+    //   backed_up_at: i64,
+    // )
     pub static ref DB: EditorDb<WorkspaceDb> =
         &[sql! (
             CREATE TABLE editors(
@@ -31,6 +55,38 @@ define_connection!(
             ALTER TABLE editors ADD COLUMN scroll_top_row INTEGER NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_horizontal_offset REAL NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_vertical_offset REAL NOT NULL DEFAULT 0;
+        ),
+        sql! (
+            CREATE TABLE marks(
+                name TEXT NOT NULL PRIMARY KEY,
+                path BLOB NOT NULL,
+                point_row INTEGER NOT NULL,
+                point_column INTEGER NOT NULL
+            ) STRICT;
+        ),
+        sql! (
+            CREATE TABLE recent_edits(
+                workspace_id INTEGER NOT NULL,
+                path BLOB NOT NULL,
+                point_row INTEGER NOT NULL,
+                point_column INTEGER NOT NULL,
+                edited_at INTEGER NOT NULL,
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+                ON UPDATE CASCADE
+            ) STRICT;
+        ),
+        sql! (
+            CREATE TABLE buffer_backups(
+                item_id INTEGER NOT NULL,
+                workspace_id INTEGER NOT NULL,
+                contents TEXT NOT NULL,
+                backed_up_at INTEGER NOT NULL,
+                PRIMARY KEY(item_id, workspace_id),
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+                ON UPDATE CASCADE
+            ) STRICT;
         )];
 );
 
@@ -80,4 +136,101 @@ impl EditorDb {
             WHERE item_id = ?1 AND workspace_id = ?2
         }
     }
+
+    query! {
+        pub fn get_buffer_backup(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<(String, i64)>> {
+            SELECT contents, backed_up_at FROM buffer_backups
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+
+    query! {
+        pub async fn save_buffer_backup(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            contents: String,
+            backed_up_at: i64
+        ) -> Result<()> {
+            INSERT INTO buffer_backups
+                (item_id, workspace_id, contents, backed_up_at)
+            VALUES
+                (?1, ?2, ?3, ?4)
+            ON CONFLICT DO UPDATE SET
+                contents = ?3,
+                backed_up_at = ?4
+        }
+    }
+
+    query! {
+        pub async fn delete_buffer_backup(item_id: ItemId, workspace_id: WorkspaceId) -> Result<()> {
+            DELETE FROM buffer_backups
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+
+    query! {
+        pub async fn delete_buffer_backups() -> Result<()> {
+            DELETE FROM buffer_backups
+        }
+    }
+
+    query! {
+        pub fn get_marks() -> Result<Vec<(String, PathBuf, u32, u32)>> {
+            SELECT name, path, point_row, point_column
+            FROM marks
+        }
+    }
+
+    query! {
+        pub async fn save_mark(name: String, path: PathBuf, row: u32, column: u32) -> Result<()> {
+            INSERT INTO marks
+                (name, path, point_row, point_column)
+            VALUES
+                (?1, ?2, ?3, ?4)
+            ON CONFLICT DO UPDATE SET
+                path = ?2,
+                point_row = ?3,
+                point_column = ?4
+        }
+    }
+
+    query! {
+        pub async fn delete_mark(name: String) -> Result<()> {
+            DELETE FROM marks
+            WHERE name = ?
+        }
+    }
+
+    query! {
+        pub fn get_recent_edits() -> Result<Vec<(WorkspaceId, PathBuf, u32, u32, i64)>> {
+            SELECT workspace_id, path, point_row, point_column, edited_at
+            FROM recent_edits
+            ORDER BY workspace_id, edited_at
+        }
+    }
+
+    /// Replaces the recent edits of a workspace.
+    pub async fn save_recent_edits(
+        &self,
+        workspace_id: WorkspaceId,
+        edits: Vec<(PathBuf, u32, u32, i64)>,
+    ) -> Result<()> {
+        self.write(move |conn| {
+            conn.with_savepoint("save_recent_edits", || {
+                conn.exec_bound(sql!(
+                    DELETE FROM recent_edits WHERE workspace_id = ?
+                ))?(workspace_id)?;
+                for (path, row, column, edited_at) in edits {
+                    conn.exec_bound(sql!(
+                        INSERT INTO recent_edits
+                            (workspace_id, path, point_row, point_column, edited_at)
+                        VALUES
+                            (?1, ?2, ?3, ?4, ?5)
+                    ))?((workspace_id, path, row, column, edited_at))?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
 }
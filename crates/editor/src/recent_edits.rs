@@ -0,0 +1,317 @@
+//! A timeline of the locations that were recently edited in each workspace, which can be
+//! jumped back to across files. It is persisted along with the workspace.
+
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use collections::HashMap;
+use gpui::{AppContext, Context, Global, Model, ModelContext, Task, ViewContext, WeakModel};
+use language::{Buffer, Point, ToPoint};
+use util::ResultExt;
+use workspace::{Workspace, WorkspaceId};
+
+use crate::{marks, persistence::DB, Editor, GoToLastEdit};
+
+/// The maximum number of edits kept in the timeline of each workspace.
+const MAX_RECENT_EDITS: usize = 100;
+/// Edits this close to the latest edit in the same file replace it, so that typing a
+/// paragraph doesn't fill the timeline with one entry per keystroke.
+const MAX_COALESCED_ROW_DISTANCE: u32 = 5;
+const SAVE_DEBOUNCE: Duration = Duration::from_secs(1);
+
+struct GlobalRecentEdits(Model<RecentEdits>);
+
+impl Global for GlobalRecentEdits {}
+
+/// The recently edited locations of each workspace, oldest first.
+pub struct RecentEdits {
+    timelines: HashMap<WorkspaceId, VecDeque<RecentEdit>>,
+    pending_saves: HashMap<WorkspaceId, Task<()>>,
+}
+
+struct RecentEdit {
+    abs_path: Arc<Path>,
+    /// The edit's position while its buffer is open, so that it moves along with later edits.
+    anchor: Option<(WeakModel<Buffer>, text::Anchor)>,
+    /// The edit's last known position, used once its buffer has been released.
+    point: Point,
+    edited_at: SystemTime,
+}
+
+/// A recent edit, resolved to its current position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecentEditLocation {
+    pub abs_path: Arc<Path>,
+    pub point: Point,
+    pub edited_at: SystemTime,
+}
+
+impl RecentEdits {
+    pub fn init(cx: &mut AppContext) {
+        let recent_edits = cx.new_model(|cx| {
+            let saved_edits = cx
+                .background_executor()
+                .spawn(async { DB.get_recent_edits() });
+            cx.spawn(|this, mut cx| async move {
+                let saved_edits = saved_edits.await?;
+                let mut saved_timelines = HashMap::<WorkspaceId, VecDeque<RecentEdit>>::default();
+                for (workspace_id, abs_path, row, column, edited_at) in saved_edits {
+                    saved_timelines
+                        .entry(workspace_id)
+                        .or_default()
+                        .push_back(RecentEdit {
+                            abs_path: abs_path.into(),
+                            anchor: None,
+                            point: Point::new(row, column),
+                            edited_at: UNIX_EPOCH + Duration::from_millis(edited_at as u64),
+                        });
+                }
+                this.update(&mut cx, |this, _| {
+                    for (workspace_id, mut timeline) in saved_timelines {
+                        // Edits recorded while loading are more recent than the saved ones.
+                        if let Some(recorded_timeline) = this.timelines.remove(&workspace_id) {
+                            timeline.extend(recorded_timeline);
+                        }
+                        while timeline.len() > MAX_RECENT_EDITS {
+                            timeline.pop_front();
+                        }
+                        this.timelines.insert(workspace_id, timeline);
+                    }
+                })
+            })
+            .detach_and_log_err(cx);
+
+            Self {
+                timelines: HashMap::default(),
+                pending_saves: HashMap::default(),
+            }
+        });
+        cx.set_global(GlobalRecentEdits(recent_edits));
+    }
+
+    pub fn global(cx: &AppContext) -> Option<Model<Self>> {
+        cx.try_global::<GlobalRecentEdits>()
+            .map(|recent_edits| recent_edits.0.clone())
+    }
+
+    /// Records an edit at `position` in `buffer`, replacing the latest edit if it was made
+    /// nearby in the same file.
+    pub fn record(
+        &mut self,
+        workspace_id: WorkspaceId,
+        buffer: &Model<Buffer>,
+        position: text::Anchor,
+        abs_path: Arc<Path>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let point = position.to_point(buffer.read(cx));
+        let edit = RecentEdit {
+            abs_path,
+            anchor: Some((buffer.downgrade(), position)),
+            point,
+            edited_at: SystemTime::now(),
+        };
+
+        let timeline = self.timelines.entry(workspace_id).or_default();
+        if let Some(latest_edit) = timeline.back() {
+            if latest_edit.abs_path == edit.abs_path
+                && latest_edit.current_point(cx).row.abs_diff(point.row)
+                    <= MAX_COALESCED_ROW_DISTANCE
+            {
+                timeline.pop_back();
+            }
+        }
+        timeline.push_back(edit);
+        if timeline.len() > MAX_RECENT_EDITS {
+            timeline.pop_front();
+        }
+
+        self.schedule_save(workspace_id, cx);
+        cx.notify();
+    }
+
+    /// Returns the locations of the given workspace's recent edits, most recent first.
+    pub fn locations(&self, workspace_id: WorkspaceId, cx: &AppContext) -> Vec<RecentEditLocation> {
+        self.timelines
+            .get(&workspace_id)
+            .into_iter()
+            .flat_map(|timeline| timeline.iter().rev())
+            .map(|edit| edit.location(cx))
+            .collect()
+    }
+
+    pub fn last_location(
+        &self,
+        workspace_id: WorkspaceId,
+        cx: &AppContext,
+    ) -> Option<RecentEditLocation> {
+        Some(self.timelines.get(&workspace_id)?.back()?.location(cx))
+    }
+
+    fn schedule_save(&mut self, workspace_id: WorkspaceId, cx: &mut ModelContext<Self>) {
+        let task = cx.spawn(|this, mut cx| async move {
+            cx.background_executor().timer(SAVE_DEBOUNCE).await;
+            let Some(edits) = this
+                .update(&mut cx, |this, cx| {
+                    this.pending_saves.remove(&workspace_id);
+                    this.saved_edits(workspace_id, cx)
+                })
+                .ok()
+            else {
+                return;
+            };
+            DB.save_recent_edits(workspace_id, edits).await.log_err();
+        });
+        self.pending_saves.insert(workspace_id, task);
+    }
+
+    fn saved_edits(
+        &self,
+        workspace_id: WorkspaceId,
+        cx: &AppContext,
+    ) -> Vec<(PathBuf, u32, u32, i64)> {
+        self.timelines
+            .get(&workspace_id)
+            .into_iter()
+            .flatten()
+            .map(|edit| {
+                let point = edit.current_point(cx);
+                let edited_at = edit
+                    .edited_at
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as i64;
+                (
+                    edit.abs_path.to_path_buf(),
+                    point.row,
+                    point.column,
+                    edited_at,
+                )
+            })
+            .collect()
+    }
+}
+
+impl RecentEdit {
+    fn current_point(&self, cx: &AppContext) -> Point {
+        self.anchor
+            .as_ref()
+            .and_then(|(buffer, anchor)| Some(anchor.to_point(buffer.upgrade()?.read(cx))))
+            .unwrap_or(self.point)
+    }
+
+    fn location(&self, cx: &AppContext) -> RecentEditLocation {
+        RecentEditLocation {
+            abs_path: self.abs_path.clone(),
+            point: self.current_point(cx),
+            edited_at: self.edited_at,
+        }
+    }
+}
+
+/// Records an edit at the newest cursor of an editor in a workspace.
+pub(crate) fn record_edit(editor: &Editor, cx: &mut ViewContext<Editor>) {
+    let Some(recent_edits) = RecentEdits::global(cx) else {
+        return;
+    };
+    let Some(workspace_id) = editor.workspace.as_ref().map(|(_, id)| *id) else {
+        return;
+    };
+    let head = editor.selections.newest_anchor().head();
+    let Some((buffer, position)) = editor.buffer.read(cx).text_anchor_for_position(head, cx) else {
+        return;
+    };
+    let Some(abs_path) = buffer
+        .read(cx)
+        .file()
+        .and_then(|file| file.as_local())
+        .map(|file| file.abs_path(cx))
+    else {
+        return;
+    };
+
+    recent_edits.update(cx, |recent_edits, cx| {
+        recent_edits.record(workspace_id, &buffer, position, abs_path.into(), cx);
+    });
+}
+
+pub fn go_to_last_edit(
+    workspace: &mut Workspace,
+    _: &GoToLastEdit,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let workspace_id = workspace.database_id();
+    let Some(location) = RecentEdits::global(cx)
+        .and_then(|recent_edits| recent_edits.read(cx).last_location(workspace_id, cx))
+    else {
+        return;
+    };
+    open_recent_edit(workspace, location, cx).detach_and_log_err(cx);
+}
+
+/// Opens the file containing the given edit and moves the cursor to it.
+pub fn open_recent_edit(
+    workspace: &mut Workspace,
+    location: RecentEditLocation,
+    cx: &mut ViewContext<Workspace>,
+) -> Task<Result<()>> {
+    marks::open_location(workspace, location.abs_path, location.point, cx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor_tests::init_test;
+
+    #[gpui::test]
+    async fn test_recent_edits_timeline(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        cx.run_until_parked();
+
+        let recent_edits = cx.update(|cx| RecentEdits::global(cx).unwrap());
+        let buffer = cx.new_model(|cx| Buffer::local("one\n".repeat(20), cx));
+        let abs_path: Arc<Path> = Path::new("/root/file.txt").into();
+        let other_abs_path: Arc<Path> = Path::new("/root/other.txt").into();
+        let workspace_id = WorkspaceId::default();
+
+        recent_edits.update(cx, |recent_edits, cx| {
+            let position = buffer.read(cx).anchor_before(Point::new(1, 0));
+            recent_edits.record(workspace_id, &buffer, position, abs_path.clone(), cx);
+            // Nearby edits in the same file are coalesced.
+            let position = buffer.read(cx).anchor_before(Point::new(3, 2));
+            recent_edits.record(workspace_id, &buffer, position, abs_path.clone(), cx);
+            let position = buffer.read(cx).anchor_before(Point::new(15, 0));
+            recent_edits.record(workspace_id, &buffer, position, abs_path.clone(), cx);
+            let position = buffer.read(cx).anchor_before(Point::new(15, 1));
+            recent_edits.record(workspace_id, &buffer, position, other_abs_path.clone(), cx);
+        });
+
+        buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "zero\n")], None, cx));
+        recent_edits.read_with(cx, |recent_edits, cx| {
+            let locations = recent_edits
+                .locations(workspace_id, cx)
+                .into_iter()
+                .map(|location| (location.abs_path, location.point))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                locations,
+                vec![
+                    (other_abs_path.clone(), Point::new(16, 1)),
+                    (abs_path.clone(), Point::new(16, 0)),
+                    (abs_path.clone(), Point::new(4, 2)),
+                ]
+            );
+            assert_eq!(
+                recent_edits
+                    .last_location(workspace_id, cx)
+                    .map(|location| location.abs_path),
+                Some(other_abs_path.clone())
+            );
+        });
+    }
+}
@@ -3,6 +3,7 @@ pub(crate) mod autoscroll;
 pub(crate) mod scroll_amount;
 
 use crate::{
+    detected_links::refresh_detected_links,
     display_map::{DisplaySnapshot, ToDisplayPoint},
     hover_popover::hide_hover,
     persistence::DB,
@@ -337,7 +338,8 @@ impl Editor {
             cx.spawn(|editor, mut cx| async move {
                 editor
                     .update(&mut cx, |editor, cx| {
-                        editor.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx)
+                        editor.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                        refresh_detected_links(editor, cx);
                     })
                     .ok()
             })
@@ -400,6 +402,7 @@ impl Editor {
         );
 
         self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+        refresh_detected_links(self, cx);
     }
 
     pub fn scroll_position(&self, cx: &mut ViewContext<Self>) -> gpui::Point<f32> {
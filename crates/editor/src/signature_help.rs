@@ -0,0 +1,125 @@
+//! Signature help for the call being typed. Typing one of the language server's trigger
+//! characters, such as `(`, requests the signatures of the function being called. They're
+//! requested again whenever the newest cursor moves, so that the active parameter follows
+//! the edits, until the server reports that the cursor has left the call.
+
+use gpui::{Task, ViewContext};
+use multi_buffer::Anchor;
+use project::SignatureHelp;
+use settings::Settings;
+use util::ResultExt;
+
+use crate::{Editor, EditorSettings};
+
+#[derive(Default)]
+pub struct SignatureHelpState {
+    popover: Option<SignatureHelpPopover>,
+    task: Option<Task<()>>,
+}
+
+/// The signatures to show in a popover next to the cursor.
+#[derive(Clone, Debug)]
+pub struct SignatureHelpPopover {
+    pub help: SignatureHelp,
+    /// The position of the cursor that the signatures were requested for.
+    pub anchor: Anchor,
+}
+
+impl SignatureHelpState {
+    pub fn popover(&self) -> Option<&SignatureHelpPopover> {
+        self.popover.as_ref()
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.popover.is_some()
+    }
+}
+
+/// Requests signature help if the input is one of the characters that trigger it, or one
+/// that retriggers it while it's shown.
+pub(crate) fn show_signature_help_on_input(
+    editor: &mut Editor,
+    text: &str,
+    cx: &mut ViewContext<Editor>,
+) {
+    if !EditorSettings::get_global(cx).show_signature_help_on_input {
+        return;
+    }
+    let Some(project) = editor.project.as_ref() else {
+        return;
+    };
+    let position = editor.selections.newest_anchor().head();
+    let Some((buffer, _)) = editor
+        .buffer
+        .read(cx)
+        .text_anchor_for_position(position, cx)
+    else {
+        return;
+    };
+    let Some(options) = project.read(cx).signature_help_options(&buffer, cx) else {
+        return;
+    };
+
+    let is_trigger = options
+        .trigger_characters
+        .iter()
+        .flatten()
+        .any(|trigger| trigger == text);
+    let is_retrigger = editor.signature_help_state.is_shown()
+        && options
+            .retrigger_characters
+            .iter()
+            .flatten()
+            .any(|trigger| trigger == text);
+    if is_trigger || is_retrigger {
+        request_signature_help(editor, cx);
+    }
+}
+
+/// Requests signature help again for the newest cursor if it's shown, updating its active
+/// parameter or hiding it once the cursor has left the call.
+pub(crate) fn refresh_signature_help(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    if editor.signature_help_state.is_shown() {
+        request_signature_help(editor, cx);
+    }
+}
+
+/// Hides signature help, returning whether it was shown.
+pub fn hide_signature_help(editor: &mut Editor, cx: &mut ViewContext<Editor>) -> bool {
+    editor.signature_help_state.task = None;
+    let was_shown = editor.signature_help_state.popover.take().is_some();
+    if was_shown {
+        cx.notify();
+    }
+    was_shown
+}
+
+fn request_signature_help(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let position = editor.selections.newest_anchor().head();
+    let Some((buffer, buffer_position)) = editor
+        .buffer
+        .read(cx)
+        .text_anchor_for_position(position, cx)
+    else {
+        return;
+    };
+
+    let help = project.update(cx, |project, cx| {
+        project.signature_help(&buffer, buffer_position, cx)
+    });
+    editor.signature_help_state.task = Some(cx.spawn(|editor, mut cx| async move {
+        let help = help.await.log_err().flatten();
+        editor
+            .update(&mut cx, |editor, cx| {
+                editor.signature_help_state.popover = help.map(|help| SignatureHelpPopover {
+                    help,
+                    anchor: position,
+                });
+                cx.notify();
+            })
+            .ok();
+    }));
+}
@@ -0,0 +1,129 @@
+//! Helpers for the line-oriented text manipulation commands, like sorting and aligning lines.
+
+use std::{borrow::Cow, cmp::Ordering, iter::Peekable, str::Chars};
+
+/// Compares two strings so that runs of ASCII digits are ordered by their numeric value,
+/// e.g. `"file2"` sorts before `"file10"`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_char), Some(b_char)) => {
+                let ordering = if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+                    let a_digits = take_digits(&mut a);
+                    let b_digits = take_digits(&mut b);
+                    let a_number = a_digits.trim_start_matches('0');
+                    let b_number = b_digits.trim_start_matches('0');
+                    a_number
+                        .len()
+                        .cmp(&b_number.len())
+                        .then_with(|| a_number.cmp(b_number))
+                        .then_with(|| a_digits.len().cmp(&b_digits.len()))
+                } else {
+                    a.next();
+                    b.next();
+                    a_char.cmp(&b_char)
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+    digits
+}
+
+/// Returns the number that a line starts with, ignoring leading whitespace.
+pub fn leading_number(line: &str) -> Option<f64> {
+    let line = line.trim_start();
+    let end = line
+        .char_indices()
+        .find(|&(ix, c)| !(c.is_ascii_digit() || c == '.' || (ix == 0 && (c == '-' || c == '+'))))
+        .map_or(line.len(), |(ix, _)| ix);
+    line[..end].parse().ok()
+}
+
+/// Orders lines by the number they start with. Lines that don't start with a number are
+/// placed after the ones that do.
+pub fn numeric_cmp(a: &str, b: &str) -> Ordering {
+    match (leading_number(a), leading_number(b)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Pads the lines containing `delimiter` so that its first occurrence on each of them
+/// starts at the same column. Lines without the delimiter are left untouched.
+pub fn align_on(lines: &mut [Cow<str>], delimiter: char) {
+    let column = |line: &str| {
+        line.find(delimiter)
+            .map(|ix| line[..ix].trim_end().chars().count())
+    };
+    let Some(target_column) = lines.iter().filter_map(|line| column(line)).max() else {
+        return;
+    };
+
+    for line in lines.iter_mut() {
+        let Some(ix) = line.find(delimiter) else {
+            continue;
+        };
+        let prefix = line[..ix].trim_end();
+        let padding = target_column - prefix.chars().count();
+        *line = Cow::Owned(format!("{prefix}{} {}", " ".repeat(padding), &line[ix..]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut lines = vec!["file10", "file2", "File3", "file02", "file1a", "file"];
+        lines.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            lines,
+            vec!["File3", "file", "file1a", "file2", "file02", "file10"]
+        );
+    }
+
+    #[test]
+    fn test_numeric_cmp() {
+        let mut lines = vec!["10 apples", "none", "-3", "2.5 pears", "  7"];
+        lines.sort_by(|a, b| numeric_cmp(a, b));
+        assert_eq!(lines, vec!["-3", "2.5 pears", "  7", "10 apples", "none"]);
+    }
+
+    #[test]
+    fn test_align_on() {
+        let mut lines = vec![
+            Cow::Borrowed("let a = 1;"),
+            Cow::Borrowed("let long_name  = 2;"),
+            Cow::Borrowed("// no delimiter"),
+            Cow::Borrowed("x=3;"),
+        ];
+        align_on(&mut lines, '=');
+        assert_eq!(
+            lines,
+            vec![
+                "let a         = 1;",
+                "let long_name = 2;",
+                "// no delimiter",
+                "x             =3;",
+            ]
+        );
+    }
+}
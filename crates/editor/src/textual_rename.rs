@@ -0,0 +1,231 @@
+//! A project-wide rename that works on plain text, through its own action, for buffers without
+//! a language server that could perform a semantic rename.
+
+use std::ops::Range;
+
+use anyhow::Result;
+use gpui::{AppContext, Context, Model, Task, ViewContext};
+use language::{char_kind, Buffer, BufferSnapshot, CharKind, OffsetRangeExt};
+use multi_buffer::MultiBuffer;
+use project::{search::SearchQuery, Project, SearchResult};
+use workspace::Workspace;
+
+use crate::{scroll::Autoscroll, Editor, RenameTextually, DEFAULT_MULTIBUFFER_CONTEXT};
+
+enum SubstringMatchHighlight {}
+
+/// How likely it is that a textual match refers to the identifier being renamed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchConfidence {
+    /// The match is a whole token, e.g. `foo` in `foo.bar()`.
+    ExactToken,
+    /// The match is part of a larger token, e.g. `foo` in `foo_bar`.
+    Substring,
+}
+
+/// The textual matches of an identifier within one buffer.
+pub struct TextualMatches {
+    pub buffer: Model<Buffer>,
+    pub ranges: Vec<(Range<usize>, MatchConfidence)>,
+}
+
+impl TextualMatches {
+    fn has_exact_token(&self) -> bool {
+        self.ranges
+            .iter()
+            .any(|(_, confidence)| *confidence == MatchConfidence::ExactToken)
+    }
+}
+
+/// Finds all occurrences of `identifier` in the project, classifying each one by whether it
+/// is a whole token or part of a larger one. Buffers with whole-token matches come first.
+pub fn find_textual_matches(
+    project: &Model<Project>,
+    identifier: &str,
+    cx: &mut AppContext,
+) -> Task<Result<Vec<TextualMatches>>> {
+    let query = match SearchQuery::text(identifier, false, true, false, Vec::new(), Vec::new()) {
+        Ok(query) => query,
+        Err(error) => return Task::ready(Err(error)),
+    };
+    let results = project.update(cx, |project, cx| project.search(query, cx));
+    cx.spawn(|mut cx| async move {
+        let mut matches = Vec::new();
+        while let Ok(result) = results.recv().await {
+            let SearchResult::Buffer { buffer, ranges } = result else {
+                continue;
+            };
+            let snapshot = buffer.update(&mut cx, |buffer, _| buffer.snapshot())?;
+            let ranges = ranges
+                .iter()
+                .map(|range| {
+                    let range = range.to_offset(&snapshot);
+                    let confidence = match_confidence(&snapshot, &range);
+                    (range, confidence)
+                })
+                .collect();
+            matches.push(TextualMatches { buffer, ranges });
+        }
+        // Search results are ordered by path, and sorting is stable.
+        matches.sort_by_key(|matches| !matches.has_exact_token());
+        Ok(matches)
+    })
+}
+
+fn match_confidence(snapshot: &BufferSnapshot, range: &Range<usize>) -> MatchConfidence {
+    let scope = snapshot.language_scope_at(range.start);
+    let is_word = |c: Option<char>| c.map_or(false, |c| char_kind(&scope, c) == CharKind::Word);
+    let previous = snapshot.reversed_chars_at(range.start).next();
+    let next = snapshot.chars_at(range.end).next();
+    if is_word(previous) || is_word(next) {
+        MatchConfidence::Substring
+    } else {
+        MatchConfidence::ExactToken
+    }
+}
+
+/// Opens every occurrence of the identifier under the cursor in a multibuffer, with a cursor on
+/// each whole-token match, so that typing the new name renames all of them in one transaction.
+/// Matches inside larger tokens are highlighted, so that they can be added selectively.
+pub fn rename_textually(editor: &mut Editor, _: &RenameTextually, cx: &mut ViewContext<Editor>) {
+    let Some(workspace) = editor.workspace() else {
+        return;
+    };
+    let project = workspace.read(cx).project().clone();
+    let buffer = editor.buffer.read(cx).snapshot(cx);
+    let selection = editor.selections.newest::<usize>(cx);
+    let range = if selection.is_empty() {
+        match buffer.surrounding_word(selection.head()) {
+            (range, Some(CharKind::Word)) => range,
+            _ => return,
+        }
+    } else {
+        selection.range()
+    };
+    let identifier = buffer.text_for_range(range).collect::<String>();
+    if identifier.trim().is_empty() {
+        return;
+    }
+
+    let find_matches = find_textual_matches(&project, &identifier, cx);
+    let replica_id = editor.replica_id(cx);
+    cx.spawn(|_, mut cx| async move {
+        let matches = find_matches.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            open_textual_matches(workspace, &identifier, matches, replica_id, cx)
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+fn open_textual_matches(
+    workspace: &mut Workspace,
+    identifier: &str,
+    matches: Vec<TextualMatches>,
+    replica_id: text::ReplicaId,
+    cx: &mut ViewContext<Workspace>,
+) {
+    if matches.is_empty() {
+        return;
+    }
+
+    let capability = workspace.project().read(cx).capability();
+    let mut exact_ranges = Vec::new();
+    let mut substring_ranges = Vec::new();
+    let excerpt_buffer = cx.new_model(|cx| {
+        let mut multibuffer = MultiBuffer::new(replica_id, capability);
+        for TextualMatches { buffer, ranges } in matches {
+            let (ranges, confidences): (Vec<_>, Vec<_>) = ranges.into_iter().unzip();
+            let excerpt_ranges = multibuffer.push_excerpts_with_context_lines(
+                buffer,
+                ranges,
+                DEFAULT_MULTIBUFFER_CONTEXT,
+                cx,
+            );
+            for (range, confidence) in excerpt_ranges.into_iter().zip(confidences) {
+                match confidence {
+                    MatchConfidence::ExactToken => exact_ranges.push(range),
+                    MatchConfidence::Substring => substring_ranges.push(range),
+                }
+            }
+        }
+        multibuffer.with_title(format!("Rename '{identifier}'"))
+    });
+
+    let editor = cx.new_view(|cx| {
+        Editor::for_multibuffer(excerpt_buffer, Some(workspace.project().clone()), true, cx)
+    });
+    editor.update(cx, |editor, cx| {
+        if !exact_ranges.is_empty() {
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_anchor_ranges(exact_ranges)
+            });
+        }
+        editor.highlight_background::<SubstringMatchHighlight>(
+            &substring_ranges,
+            |theme| theme.editor_document_highlight_read_background,
+            cx,
+        );
+    });
+    workspace.add_item_to_active_pane(Box::new(editor), None, cx);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor_tests::init_test;
+    use project::FakeFs;
+    use serde_json::json;
+
+    #[gpui::test]
+    async fn test_find_textual_matches(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "a.txt": "let foo_bar = 1;\n",
+                "b.txt": "foo(1);\nfoo.bar();\nsnafoo\n",
+                "c.txt": "nothing here\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+        let matches = cx
+            .update(|cx| find_textual_matches(&project, "foo", cx))
+            .await
+            .unwrap();
+        let matches = cx.update(|cx| {
+            matches
+                .iter()
+                .map(|matches| {
+                    let buffer = matches.buffer.read(cx);
+                    let path = buffer.file().unwrap().path().to_string_lossy().to_string();
+                    let ranges = matches
+                        .ranges
+                        .iter()
+                        .map(|(range, confidence)| (range.start, *confidence))
+                        .collect::<Vec<_>>();
+                    (path, ranges)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(
+            matches,
+            vec![
+                (
+                    "b.txt".to_string(),
+                    vec![
+                        (0, MatchConfidence::ExactToken),
+                        (8, MatchConfidence::ExactToken),
+                        (22, MatchConfidence::Substring),
+                    ]
+                ),
+                ("a.txt".to_string(), vec![(4, MatchConfidence::Substring)]),
+            ]
+        );
+    }
+}
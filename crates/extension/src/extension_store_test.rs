@@ -199,6 +199,7 @@ async fn test_extension_store(cx: &mut TestAppContext) {
                     matcher: LanguageMatcher {
                         path_suffixes: vec!["erb".into()],
                         first_line_pattern: None,
+                        modeline_aliases: Vec::new(),
                     },
                 },
             ),
@@ -211,6 +212,7 @@ async fn test_extension_store(cx: &mut TestAppContext) {
                     matcher: LanguageMatcher {
                         path_suffixes: vec!["rb".into()],
                         first_line_pattern: None,
+                        modeline_aliases: Vec::new(),
                     },
                 },
             ),
@@ -1,10 +1,12 @@
 #[cfg(test)]
 mod file_finder_tests;
 
+mod file_finder_settings;
 mod new_path_prompt;
 
 use collections::{BTreeSet, HashMap};
 use editor::{scroll::Autoscroll, Bias, Editor};
+use file_finder_settings::FileFinderSettings;
 use fuzzy::{CharBag, PathMatch, PathMatchCandidate};
 use gpui::{
     actions, impl_actions, rems, Action, AnyElement, AppContext, DismissEvent, EventEmitter,
@@ -14,7 +16,9 @@ use gpui::{
 use itertools::Itertools;
 use new_path_prompt::NewPathPrompt;
 use picker::{Picker, PickerDelegate};
-use project::{PathMatchCandidateSet, Project, ProjectPath, WorktreeId};
+use project::{
+    LocalWorktree, PathMatchCandidateSet, Project, ProjectPath, WorktreeId, WorktreeSettings,
+};
 use settings::Settings;
 use std::{
     cmp,
@@ -29,7 +33,7 @@ use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
 use util::{paths::PathLikeWithPosition, post_inc, ResultExt};
 use workspace::{item::PreviewTabsSettings, ModalView, Workspace};
 
-actions!(file_finder, [SelectPrev]);
+actions!(file_finder, [SelectPrev, ToggleIncludeIgnored]);
 impl_actions!(file_finder, [Toggle]);
 
 #[derive(Default, PartialEq, Eq, Clone, serde::Deserialize)]
@@ -46,6 +50,7 @@ pub struct FileFinder {
 }
 
 pub fn init(cx: &mut AppContext) {
+    FileFinderSettings::register(cx);
     cx.observe_new_views(FileFinder::register).detach();
     cx.observe_new_views(NewPathPrompt::register).detach();
 }
@@ -134,6 +139,19 @@ impl FileFinder {
         self.init_modifiers = Some(cx.modifiers());
         cx.dispatch_action(Box::new(menu::SelectPrev));
     }
+
+    fn handle_toggle_include_ignored(
+        &mut self,
+        _: &ToggleIncludeIgnored,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.picker.update(cx, |picker, cx| {
+            let delegate = &mut picker.delegate;
+            delegate.include_ignored = !delegate.include_ignored;
+            picker.refresh_placeholder(cx);
+            picker.refresh(cx);
+        });
+    }
 }
 
 impl EventEmitter<DismissEvent> for FileFinder {}
@@ -151,6 +169,7 @@ impl Render for FileFinder {
             .w(rems(34.))
             .on_modifiers_changed(cx.listener(Self::handle_modifiers_changed))
             .on_action(cx.listener(Self::handle_select_prev))
+            .on_action(cx.listener(Self::handle_toggle_include_ignored))
             .child(self.picker.clone())
     }
 }
@@ -170,6 +189,9 @@ pub struct FileFinderDelegate {
     cancel_flag: Arc<AtomicBool>,
     history_items: Vec<FoundPath>,
     separate_history: bool,
+    /// Whether the files in ignored directories are searched too. The ignored directories are
+    /// loaded lazily, as the query leads into them.
+    include_ignored: bool,
 }
 
 /// Use a custom ordering for file finder: the regular one
@@ -395,6 +417,46 @@ fn history_file_exists(abs_path: &PathBuf) -> bool {
     !abs_path.ends_with("nonexistent.rs")
 }
 
+/// Loads the ignored directories that files matching the query could be in. The outermost
+/// ignored directories are loaded one level deep, and the directories nested in them once each
+/// of their path components starts with the corresponding directory component of the query.
+fn load_ignored_directories(
+    worktree: &LocalWorktree,
+    query_dirs: &[String],
+    include_root_name: bool,
+) {
+    let query_dirs = match query_dirs.split_first() {
+        Some((root_name, query_dirs))
+            if include_root_name && worktree.root_name().to_lowercase() == *root_name =>
+        {
+            query_dirs
+        }
+        _ => query_dirs,
+    };
+    worktree.load_ignored_directories(|entry| {
+        let parent_is_ignored = entry
+            .path
+            .parent()
+            .and_then(|parent| worktree.entry_for_path(parent))
+            .map_or(false, |parent| parent.is_ignored);
+        if !parent_is_ignored {
+            return true;
+        }
+        let components = entry.path.components().collect::<Vec<_>>();
+        components.len() <= query_dirs.len()
+            && components
+                .iter()
+                .zip(query_dirs)
+                .all(|(component, query_dir)| {
+                    component
+                        .as_os_str()
+                        .to_string_lossy()
+                        .to_lowercase()
+                        .starts_with(query_dir.as_str())
+                })
+    });
+}
+
 pub enum Event {
     Selected(ProjectPath),
     Dismissed,
@@ -441,6 +503,7 @@ impl FileFinderDelegate {
             cancel_flag: Arc::new(AtomicBool::new(false)),
             history_items,
             separate_history,
+            include_ignored: false,
         }
     }
 
@@ -473,17 +536,37 @@ impl FileFinderDelegate {
             .visible_worktrees(cx)
             .collect::<Vec<_>>();
         let include_root_name = worktrees.len() > 1;
+        let file_finder_settings = FileFinderSettings::get_global(cx);
+        let score_penalties = file_finder_settings.score_penalties.clone();
+        let include_hidden = file_finder_settings.include_hidden;
+        let transient_files = WorktreeSettings::get_global(cx).transient_file_matchers();
+        let query_dirs = Path::new(query.path_like.path_query())
+            .parent()
+            .into_iter()
+            .flat_map(|parent| parent.components())
+            .map(|component| component.as_os_str().to_string_lossy().to_lowercase())
+            .collect::<Vec<_>>();
         let candidate_sets = worktrees
             .into_iter()
             .map(|worktree| {
                 let worktree = worktree.read(cx);
+                if self.include_ignored {
+                    // The matches are refreshed as the ignored directories get loaded.
+                    if let Some(worktree) = worktree.as_local() {
+                        load_ignored_directories(worktree, &query_dirs, include_root_name);
+                    }
+                }
                 PathMatchCandidateSet {
                     snapshot: worktree.snapshot(),
-                    include_ignored: worktree
-                        .root_entry()
-                        .map_or(false, |entry| entry.is_ignored),
+                    include_ignored: self.include_ignored
+                        || worktree
+                            .root_entry()
+                            .map_or(false, |entry| entry.is_ignored),
+                    include_hidden,
                     include_root_name,
                     directories_only: false,
+                    score_penalties: score_penalties.clone(),
+                    excluded_paths: transient_files.clone(),
                 }
             })
             .collect::<Vec<_>>();
@@ -734,7 +817,11 @@ impl PickerDelegate for FileFinderDelegate {
     type ListItem = ListItem;
 
     fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
-        "Search project files...".into()
+        if self.include_ignored {
+            "Search project files, including ignored ones...".into()
+        } else {
+            "Search project files...".into()
+        }
     }
 
     fn match_count(&self) -> usize {
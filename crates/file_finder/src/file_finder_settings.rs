@@ -0,0 +1,54 @@
+use collections::HashMap;
+use project::PathScorePenalty;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+use util::{paths::PathMatcher, ResultExt};
+
+#[derive(Clone, Debug, Default)]
+pub struct FileFinderSettings {
+    pub score_penalties: Vec<PathScorePenalty>,
+    pub include_hidden: bool,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct FileFinderSettingsContent {
+    /// Globs of paths, such as generated or minified files, that should rank below other
+    /// matches of equal score, mapped to the fraction of the score to deduct (between 0 and 1).
+    /// Matching files are still shown.
+    ///
+    /// Default: {}
+    pub score_penalties: Option<HashMap<String, f64>>,
+    /// Whether dotfiles, and files that the file system marks as hidden, are matched.
+    ///
+    /// Default: true
+    pub include_hidden: Option<bool>,
+}
+
+impl Settings for FileFinderSettings {
+    const KEY: Option<&'static str> = Some("file_finder");
+
+    type FileContent = FileFinderSettingsContent;
+
+    fn load(
+        sources: SettingsSources<Self::FileContent>,
+        _: &mut gpui::AppContext,
+    ) -> anyhow::Result<Self> {
+        let content: FileFinderSettingsContent = sources.json_merge()?;
+        let score_penalties = content
+            .score_penalties
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(glob, penalty)| {
+                Some(PathScorePenalty {
+                    matcher: PathMatcher::new(&glob).log_err()?,
+                    penalty: penalty.clamp(0., 1.),
+                })
+            })
+            .collect();
+        Ok(Self {
+            score_penalties,
+            include_hidden: content.include_hidden.unwrap_or(true),
+        })
+    }
+}
@@ -6,6 +6,7 @@ use gpui::{Entity, TestAppContext, VisualTestContext};
 use menu::{Confirm, SelectNext, SelectPrev};
 use project::FS_WATCH_LATENCY;
 use serde_json::json;
+use settings::SettingsStore;
 use workspace::{AppState, Workspace};
 
 #[ctor::ctor]
@@ -559,6 +560,221 @@ async fn test_path_distance_ordering(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_score_penalties(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/root",
+            json!({
+                "generated": { "main.rs": "" },
+                "hand_made": { "main.rs": "" },
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+    set_score_penalties(&[("**/generated/**", 0.5)], cx);
+    let finder = open_file_picker(&workspace, cx);
+    finder
+        .update(cx, |f, cx| {
+            f.delegate.spawn_search(test_path_like("main.rs"), cx)
+        })
+        .await;
+    finder.update(cx, |picker, _| {
+        let matches = collect_search_matches(picker).search_paths_only();
+        assert_eq!(
+            matches,
+            vec![
+                PathBuf::from("hand_made/main.rs"),
+                PathBuf::from("generated/main.rs"),
+            ]
+        );
+    });
+
+    set_score_penalties(&[("hand_made/**", 0.5)], cx);
+    finder
+        .update(cx, |f, cx| {
+            f.delegate.spawn_search(test_path_like("main.rs"), cx)
+        })
+        .await;
+    finder.update(cx, |picker, _| {
+        let matches = collect_search_matches(picker).search_paths_only();
+        assert_eq!(
+            matches,
+            vec![
+                PathBuf::from("generated/main.rs"),
+                PathBuf::from("hand_made/main.rs"),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_transient_files_are_excluded(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/root",
+            json!({
+                "src": {
+                    "main.rs": "",
+                },
+                ".main.rs.swp": "",
+            }),
+        )
+        .await;
+    // Transient files matching better than the others don't take up the limited results.
+    for ix in 0..120 {
+        app_state
+            .fs
+            .as_fake()
+            .insert_file(format!("/root/main{ix}.swp"), Vec::new())
+            .await;
+    }
+
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+    let finder = open_file_picker(&workspace, cx);
+    finder
+        .update(cx, |f, cx| {
+            f.delegate.spawn_search(test_path_like("main"), cx)
+        })
+        .await;
+    finder.update(cx, |picker, _| {
+        let matches = collect_search_matches(picker).search_paths_only();
+        assert_eq!(matches, vec![PathBuf::from("src/main.rs")]);
+    });
+}
+
+#[gpui::test]
+async fn test_toggle_include_ignored(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            "/root",
+            json!({
+                ".gitignore": "target",
+                "src": {
+                    "main.rs": "",
+                },
+                "target": {
+                    "debug": {
+                        "build.rs": "",
+                    },
+                },
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let (picker, _, cx) = build_find_picker(project, cx);
+
+    picker
+        .update(cx, |picker, cx| {
+            picker.delegate.spawn_search(test_path_like(".rs"), cx)
+        })
+        .await;
+    picker.update(cx, |picker, _| {
+        let matches = collect_search_matches(picker).search_paths_only();
+        assert_eq!(matches, vec![PathBuf::from("src/main.rs")]);
+    });
+
+    // The outermost ignored directories are loaded one level deep.
+    cx.dispatch_action(ToggleIncludeIgnored);
+    picker
+        .update(cx, |picker, cx| {
+            picker.delegate.spawn_search(test_path_like(".rs"), cx)
+        })
+        .await;
+    cx.run_until_parked();
+    picker.update(cx, |picker, cx| {
+        let worktree = picker.delegate.project.read(cx).worktrees().next().unwrap();
+        let worktree = worktree.read(cx);
+        assert!(worktree.entry_for_path("target/debug").unwrap().is_ignored);
+        assert!(worktree.entry_for_path("target/debug/build.rs").is_none());
+    });
+
+    // Nested ignored directories are loaded as the query leads into them, and their files are
+    // matched.
+    for _ in 0..2 {
+        picker
+            .update(cx, |picker, cx| {
+                picker
+                    .delegate
+                    .spawn_search(test_path_like("tar/deb/.rs"), cx)
+            })
+            .await;
+        cx.run_until_parked();
+    }
+    picker.update(cx, |picker, _| {
+        let matches = collect_search_matches(picker).search_paths_only();
+        assert_eq!(matches, vec![PathBuf::from("target/debug/build.rs")]);
+    });
+
+    cx.dispatch_action(ToggleIncludeIgnored);
+    picker
+        .update(cx, |picker, cx| {
+            picker.delegate.spawn_search(test_path_like(".rs"), cx)
+        })
+        .await;
+    picker.update(cx, |picker, _| {
+        let matches = collect_search_matches(picker).search_paths_only();
+        assert_eq!(matches, vec![PathBuf::from("src/main.rs")]);
+    });
+}
+
+#[gpui::test]
+async fn test_toggle_include_ignored_keybindings(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree("/root", json!({ "a.rs": "" }))
+        .await;
+    let project = Project::test(app_state.fs.clone(), ["/root".as_ref()], cx).await;
+    let (picker, _, cx) = build_find_picker(project, cx);
+
+    // The keystrokes aren't taken by the bindings of the query editor, such as `editor::Format`.
+    for (keymap, keystroke) in [
+        ("keymaps/default-macos.json", "ctrl-i"),
+        ("keymaps/default-linux.json", "alt-i"),
+    ] {
+        cx.update(|cx| {
+            cx.clear_key_bindings();
+            settings::KeymapFile::load_asset(keymap, cx).unwrap();
+        });
+        cx.simulate_keystrokes(keystroke);
+        picker.update(cx, |picker, _| assert!(picker.delegate.include_ignored));
+        cx.simulate_keystrokes(keystroke);
+        picker.update(cx, |picker, _| assert!(!picker.delegate.include_ignored));
+    }
+}
+
+fn set_score_penalties(penalties: &[(&str, f64)], cx: &mut VisualTestContext) {
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<FileFinderSettings>(cx, |settings| {
+                settings.score_penalties = Some(
+                    penalties
+                        .iter()
+                        .map(|(glob, penalty)| (glob.to_string(), *penalty))
+                        .collect(),
+                );
+            });
+        });
+    });
+}
+
 #[gpui::test]
 async fn test_search_worktree_without_files(cx: &mut TestAppContext) {
     let app_state = init_test(cx);
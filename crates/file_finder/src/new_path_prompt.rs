@@ -277,8 +277,11 @@ impl PickerDelegate for NewPathDelegate {
                     include_ignored: worktree
                         .root_entry()
                         .map_or(false, |entry| entry.is_ignored),
+                    include_hidden: true,
                     include_root_name,
                     directories_only: true,
+                    score_penalties: Vec::new(),
+                    excluded_paths: Vec::new(),
                 }
             })
             .collect::<Vec<_>>();
@@ -1,3 +1,7 @@
+mod polling;
+#[cfg(unix)]
+mod watchman;
+
 use anyhow::{anyhow, Result};
 use git::GitHostingProviderRegistry;
 
@@ -8,8 +12,10 @@ use async_tar::Archive;
 use futures::{future::BoxFuture, AsyncRead, Stream, StreamExt};
 use git::repository::{GitRepository, RealGitRepository};
 use git2::Repository as LibGitRepository;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use rope::Rope;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 #[cfg(any(test, feature = "test-support"))]
 use smol::io::AsyncReadExt;
 use smol::io::AsyncWriteExt;
@@ -26,7 +32,7 @@ use text::LineEnding;
 use util::{paths, ResultExt};
 
 #[cfg(any(test, feature = "test-support"))]
-use collections::{btree_map, BTreeMap};
+use collections::{btree_map, BTreeMap, HashSet};
 #[cfg(any(test, feature = "test-support"))]
 use git::repository::{FakeGitRepositoryState, GitFileStatus};
 #[cfg(any(test, feature = "test-support"))]
@@ -60,7 +66,13 @@ pub trait Fs: Send + Sync {
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read>>;
     async fn load(&self, path: &Path) -> Result<String>;
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
-    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
+    async fn save(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        durability: Durability,
+    ) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
     async fn is_file(&self, path: &Path) -> bool;
     async fn is_dir(&self, path: &Path) -> bool;
@@ -70,12 +82,45 @@ pub trait Fs: Send + Sync {
         &self,
         path: &Path,
     ) -> Result<Pin<Box<dyn Send + Stream<Item = Result<PathBuf>>>>>;
+    /// Lists the entries of a directory along with their metadata. Implementations can
+    /// retrieve the metadata of all entries in a batch, which is much cheaper than calling
+    /// [`Fs::metadata`] for each of them on network file systems.
+    async fn read_dir_metadata(&self, path: &Path) -> Result<Vec<DirEntryMetadata>> {
+        let mut entries = Vec::new();
+        let mut paths = self.read_dir(path).await?;
+        while let Some(path) = paths.next().await {
+            let Some(path) = path.log_err() else {
+                continue;
+            };
+            let metadata = self.metadata(&path).await;
+            entries.push(DirEntryMetadata { path, metadata });
+        }
+        Ok(entries)
+    }
 
     async fn watch(
         &self,
         path: &Path,
         latency: Duration,
     ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>>;
+    /// Like [`Fs::watch`], but with options for how `path` is scanned when changes are
+    /// detected by polling.
+    async fn watch_with_poll_options(
+        &self,
+        path: &Path,
+        latency: Duration,
+        _poll_options: PollOptions,
+    ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
+        self.watch(path, latency).await
+    }
+
+    /// Like [`Fs::watch`], but only reports changes to the directory at `path` and to the
+    /// entries directly inside of it, without watching its subdirectories.
+    async fn watch_children(
+        &self,
+        path: &Path,
+        latency: Duration,
+    ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>>;
 
     fn open_repo(&self, abs_dot_git: &Path) -> Option<Arc<Mutex<dyn GitRepository>>>;
     fn is_fake(&self) -> bool;
@@ -102,24 +147,110 @@ pub struct RenameOptions {
     pub ignore_if_exists: bool,
 }
 
+/// How durable a write must be before it's reported as done.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Durability {
+    /// Done once the contents are handed to the operating system, which flushes them to disk
+    /// later. A power loss or a crash of the system before then can lose the write, or leave a
+    /// replaced file empty on some file systems.
+    #[default]
+    Buffered,
+    /// Done once the file and its parent directory are flushed to disk, so that the contents
+    /// survive a crash or power loss. Slower, especially on network file systems.
+    Synced,
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct RemoveOptions {
     pub recursive: bool,
     pub ignore_if_not_exists: bool,
 }
 
+/// Identifies a file independently of its path, so that it can be recognized after it's
+/// renamed. On Unix this is the file's inode number and the device it's on. On Windows it's
+/// the file index and the serial number of the volume it's on, since file indices are only
+/// unique within a volume.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId {
+    volume: u64,
+    index: u64,
+}
+
+impl FileId {
+    pub fn new(volume: u64, index: u64) -> Self {
+        Self { volume, index }
+    }
+
+    /// The device or volume that the file is on.
+    pub fn volume(&self) -> u64 {
+        self.volume
+    }
+
+    /// The file's inode number or file index, which is unique within its volume.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct Metadata {
-    pub inode: u64,
+    pub inode: FileId,
     pub mtime: SystemTime,
     pub is_symlink: bool,
     pub is_dir: bool,
+    /// The size of the file, in bytes.
+    pub len: u64,
+    /// The file's unix permission bits, on platforms that have them.
+    pub mode: Option<u32>,
+    /// Whether the file system flags the file as hidden. Dotfiles are only hidden by
+    /// convention, so this is false for them unless they're also flagged.
+    pub is_hidden: bool,
+}
+
+/// An entry returned by [`Fs::read_dir_metadata`].
+#[derive(Debug)]
+pub struct DirEntryMetadata {
+    pub path: PathBuf,
+    /// The entry's metadata, or `None` if it was removed after the directory was read.
+    pub metadata: Result<Option<Metadata>>,
+}
+
+/// The source of the file system events reported by [`RealFs`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WatcherBackend {
+    /// The platform's file system events: FSEvents on macOS, inotify on Linux.
+    #[default]
+    Native,
+    /// A running Watchman server, which applies its own ignore configuration. Falls back to
+    /// native events if Watchman can't be reached.
+    Watchman,
+    /// Periodically scanning the watched paths, for environments without file system events.
+    Polling,
+}
+
+/// How often the watched paths are scanned when using [`WatcherBackend::Polling`].
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shorter poll intervals are raised to this, since every scan walks the whole watched tree.
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How the watched paths are scanned when using [`WatcherBackend::Polling`].
+#[derive(Clone, Default)]
+pub struct PollOptions {
+    /// Overrides how often the watched paths are scanned.
+    pub interval: Option<Duration>,
+    /// Returns whether the contents of a directory should be left out of the scans, because
+    /// changes to them would be discarded anyway.
+    pub skip_dir: Option<Arc<dyn Fn(&Path) -> bool + Send + Sync>>,
 }
 
 #[derive(Default)]
 pub struct RealFs {
     git_hosting_provider_registry: Arc<GitHostingProviderRegistry>,
     git_binary_path: Option<PathBuf>,
+    watcher_backend: RwLock<WatcherBackend>,
+    poll_interval: RwLock<Option<Duration>>,
 }
 
 impl RealFs {
@@ -130,8 +261,230 @@ impl RealFs {
         Self {
             git_hosting_provider_registry,
             git_binary_path,
+            watcher_backend: Default::default(),
+            poll_interval: Default::default(),
         }
     }
+
+    /// Sets the source of the events reported by subsequent calls to [`Fs::watch`].
+    pub fn set_watcher_backend(&self, backend: WatcherBackend) {
+        *self.watcher_backend.write() = backend;
+    }
+
+    /// Sets how often the watched paths are scanned when using [`WatcherBackend::Polling`].
+    pub fn set_poll_interval(&self, interval: Duration) {
+        *self.poll_interval.write() = Some(interval);
+    }
+
+    #[cfg(target_os = "macos")]
+    async fn watch_native(
+        &self,
+        path: &Path,
+        latency: Duration,
+    ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
+        use fsevent::{EventStream, StreamFlags};
+
+        let (tx, rx) = smol::channel::unbounded();
+        let (stream, handle) = EventStream::new(&[path], latency);
+        let watched_path = path.to_path_buf();
+        std::thread::spawn(move || {
+            stream.run(move |events| {
+                let paths = events
+                    .into_iter()
+                    .filter_map(|event| {
+                        // When events are coalesced or dropped, FSEvents flags an event for the
+                        // directory whose contents must be rescanned, which may be an ancestor
+                        // of the watched path. Reporting the directory itself is enough, since
+                        // changed directories are rescanned recursively.
+                        if event.flags.intersects(
+                            StreamFlags::MUST_SCAN_SUBDIRS
+                                | StreamFlags::USER_DROPPED
+                                | StreamFlags::KERNEL_DROPPED,
+                        ) {
+                            log::info!("file system events were dropped for {:?}", event.path);
+                            path_to_rescan(&event.path, &watched_path)
+                        } else {
+                            Some(event.path)
+                        }
+                    })
+                    .collect();
+                smol::block_on(tx.send(paths)).is_ok()
+            });
+        });
+
+        Box::pin(rx.chain(futures::stream::once(async move {
+            drop(handle);
+            vec![]
+        })))
+    }
+
+    /// FSEvents can't watch a directory without its subdirectories, so their events are
+    /// filtered out afterwards.
+    #[cfg(target_os = "macos")]
+    async fn watch_children_native(
+        &self,
+        path: &Path,
+        latency: Duration,
+    ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
+        self.watch_native(path, latency).await
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    async fn watch_children_native(
+        &self,
+        path: &Path,
+        _latency: Duration,
+    ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
+        use notify::Watcher;
+
+        let (tx, rx) = smol::channel::unbounded();
+        let mut watcher = notify::recommended_watcher({
+            let watched_path = path.to_path_buf();
+            move |event: Result<notify::Event, _>| {
+                if let Some(event) = event.log_err() {
+                    if event.need_rescan() {
+                        log::info!("file system events were dropped for {watched_path:?}");
+                        tx.try_send(vec![watched_path.clone()]).ok();
+                    } else {
+                        tx.try_send(event.paths).ok();
+                    }
+                }
+            }
+        })
+        .expect("Could not start file watcher");
+        watcher
+            .watch(path, notify::RecursiveMode::NonRecursive)
+            .log_err();
+
+        Box::pin(rx.chain(futures::stream::once(async move {
+            drop(watcher);
+            vec![]
+        })))
+    }
+
+    /// Watches the path with `notify`'s recommended watcher, which uses `inotify` on Linux and
+    /// `ReadDirectoryChangesW` on Windows.
+    #[cfg(not(target_os = "macos"))]
+    async fn watch_native(
+        &self,
+        path: &Path,
+        _latency: Duration,
+    ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
+        use notify::{event::EventKind, event::ModifyKind, Watcher};
+        // todo(linux): This spawns two threads, while the macOS impl
+        // only spawns one. Can we use a OnceLock or some such to make
+        // this better
+
+        let (tx, rx) = smol::channel::unbounded();
+
+        let mut file_watcher = notify::recommended_watcher({
+            let tx = tx.clone();
+            let watched_path = path.to_path_buf();
+            move |event: Result<notify::Event, _>| {
+                if let Some(event) = event.log_err() {
+                    // When the event queue overflows, the events are dropped and replaced with
+                    // one that may not have any paths, so rescan everything that's watched.
+                    if event.need_rescan() {
+                        log::info!("file system events were dropped for {watched_path:?}");
+                        let paths = if event.paths.is_empty() {
+                            vec![watched_path.clone()]
+                        } else {
+                            event
+                                .paths
+                                .iter()
+                                .filter_map(|path| path_to_rescan(path, &watched_path))
+                                .collect()
+                        };
+                        tx.try_send(paths).ok();
+                    } else {
+                        tx.try_send(event.paths).ok();
+                    }
+                }
+            }
+        })
+        .expect("Could not start file watcher");
+
+        file_watcher
+            .watch(path, notify::RecursiveMode::Recursive)
+            .ok(); // It's ok if this fails, the parent watcher will add it.
+
+        let mut parent_watcher = notify::recommended_watcher({
+            let watched_path = path.to_path_buf();
+            let tx = tx.clone();
+            move |event: Result<notify::Event, _>| {
+                if let Some(event) = event.ok() {
+                    if event.paths.into_iter().any(|path| *path == watched_path) {
+                        match event.kind {
+                            EventKind::Modify(ev) => {
+                                if matches!(ev, ModifyKind::Name(_)) {
+                                    file_watcher
+                                        .watch(
+                                            watched_path.as_path(),
+                                            notify::RecursiveMode::Recursive,
+                                        )
+                                        .log_err();
+                                    let _ = tx.try_send(vec![watched_path.clone()]).ok();
+                                }
+                            }
+                            EventKind::Create(_) => {
+                                file_watcher
+                                    .watch(watched_path.as_path(), notify::RecursiveMode::Recursive)
+                                    .log_err();
+                                let _ = tx.try_send(vec![watched_path.clone()]).ok();
+                            }
+                            EventKind::Remove(_) => {
+                                file_watcher.unwatch(&watched_path).log_err();
+                                let _ = tx.try_send(vec![watched_path.clone()]).ok();
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        })
+        .expect("Could not start file watcher");
+
+        parent_watcher
+            .watch(
+                path.parent()
+                    .expect("Watching root is probably not what you want"),
+                notify::RecursiveMode::NonRecursive,
+            )
+            .expect("Could not start watcher on parent directory");
+
+        Box::pin(rx.chain(futures::stream::once(async move {
+            drop(parent_watcher);
+            vec![]
+        })))
+    }
+}
+
+/// Keeps the events for the directory at `dir_path` and its direct children, dropping the
+/// batches that are left empty.
+fn children_events(
+    events: Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>>,
+    dir_path: &Path,
+) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
+    let dir_path = dir_path.to_path_buf();
+    Box::pin(events.filter_map(move |paths| {
+        let paths = paths
+            .into_iter()
+            .filter(|path| *path == dir_path || path.parent() == Some(dir_path.as_path()))
+            .collect::<Vec<_>>();
+        futures::future::ready((!paths.is_empty()).then_some(paths))
+    }))
+}
+
+/// Returns the part of the subtree at `path`, whose events were dropped, that is within the
+/// `watched_path`, or `None` if they don't overlap.
+fn path_to_rescan(path: &Path, watched_path: &Path) -> Option<PathBuf> {
+    if path.starts_with(watched_path) {
+        Some(path.to_path_buf())
+    } else if watched_path.starts_with(path) {
+        Some(watched_path.to_path_buf())
+    } else {
+        None
+    }
 }
 
 #[async_trait::async_trait]
@@ -278,6 +631,16 @@ impl Fs for RealFs {
         self.trash_file(path, options).await
     }
 
+    #[cfg(target_os = "linux")]
+    async fn trash_file(&self, path: &Path, options: RemoveOptions) -> Result<()> {
+        move_to_freedesktop_trash(path, options).await
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn trash_dir(&self, path: &Path, options: RemoveOptions) -> Result<()> {
+        move_to_freedesktop_trash(path, options).await
+    }
+
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read>> {
         Ok(Box::new(std::fs::File::open(path)?))
     }
@@ -307,17 +670,44 @@ impl Fs for RealFs {
         Ok(())
     }
 
-    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
+    async fn save(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        durability: Durability,
+    ) -> Result<()> {
         let buffer_size = text.summary().len.min(10 * 1024);
         if let Some(path) = path.parent() {
             self.create_dir(path).await?;
         }
-        let file = smol::fs::File::create(path).await?;
+
+        // Write through symlinks rather than replacing them.
+        let path = smol::fs::canonicalize(path)
+            .await
+            .unwrap_or_else(|_| path.to_path_buf());
+        let atomic_write = smol::unblock({
+            let path = path.clone();
+            let text = text.clone();
+            move || write_atomically(&path, &text, line_ending, durability)
+        });
+        match atomic_write.await {
+            Ok(()) => return Ok(()),
+            Err(error) => {
+                log::warn!("failed to save {path:?} atomically, overwriting it: {error:#}")
+            }
+        }
+
+        let file = smol::fs::File::create(&path).await?;
         let mut writer = smol::io::BufWriter::with_capacity(buffer_size, file);
         for chunk in chunks(text, line_ending) {
             writer.write_all(chunk.as_bytes()).await?;
         }
         writer.flush().await?;
+        if durability == Durability::Synced {
+            writer.into_inner().sync_all().await?;
+            smol::unblock(move || sync_parent_dir(&path)).await?;
+        }
         Ok(())
     }
 
@@ -350,6 +740,7 @@ impl Fs for RealFs {
         };
 
         let is_symlink = symlink_metadata.file_type().is_symlink();
+        let is_hidden = is_hidden(&symlink_metadata);
         let metadata = if is_symlink {
             smol::fs::metadata(path).await?
         } else {
@@ -357,19 +748,75 @@ impl Fs for RealFs {
         };
 
         #[cfg(unix)]
-        let inode = metadata.ino();
+        let inode = FileId::new(metadata.dev(), metadata.ino());
 
         #[cfg(windows)]
         let inode = file_id(path).await?;
 
+        #[cfg(unix)]
+        let mode = Some(metadata.mode() & 0o7777);
+
+        #[cfg(windows)]
+        let mode = None;
+
         Ok(Some(Metadata {
             inode,
             mtime: metadata.modified().unwrap(),
             is_symlink,
             is_dir: metadata.file_type().is_dir(),
+            len: metadata.len(),
+            mode,
+            is_hidden,
         }))
     }
 
+    #[cfg(unix)]
+    async fn read_dir_metadata(&self, path: &Path) -> Result<Vec<DirEntryMetadata>> {
+        // Read the directory and stat its entries on a single blocking thread. Statting an
+        // entry relative to its directory avoids resolving the whole path again, which is a
+        // round trip per path component on network file systems.
+        let path = path.to_path_buf();
+        smol::unblock(move || {
+            let mut entries = Vec::new();
+            for entry in std::fs::read_dir(&path)? {
+                // Skip the entries that can't be read, such as ones deleted during the scan,
+                // rather than failing to list the whole directory.
+                let Some(entry) = entry.log_err() else {
+                    continue;
+                };
+                let path = entry.path();
+                let metadata = entry
+                    .metadata()
+                    .and_then(|symlink_metadata| {
+                        let is_symlink = symlink_metadata.file_type().is_symlink();
+                        let is_hidden = is_hidden(&symlink_metadata);
+                        let metadata = if is_symlink {
+                            std::fs::metadata(&path)?
+                        } else {
+                            symlink_metadata
+                        };
+                        Ok(Some(Metadata {
+                            inode: FileId::new(metadata.dev(), metadata.ino()),
+                            mtime: metadata.modified()?,
+                            is_symlink,
+                            is_dir: metadata.file_type().is_dir(),
+                            len: metadata.len(),
+                            mode: Some(metadata.mode() & 0o7777),
+                            is_hidden,
+                        }))
+                    })
+                    .or_else(|err| match (err.kind(), err.raw_os_error()) {
+                        (io::ErrorKind::NotFound, _) => Ok(None),
+                        (io::ErrorKind::Other, Some(libc::ENOTDIR)) => Ok(None),
+                        _ => Err(anyhow::Error::new(err)),
+                    });
+                entries.push(DirEntryMetadata { path, metadata });
+            }
+            Ok(entries)
+        })
+        .await
+    }
+
     async fn read_link(&self, path: &Path) -> Result<PathBuf> {
         let path = smol::fs::read_link(path).await?;
         Ok(path)
@@ -386,104 +833,58 @@ impl Fs for RealFs {
         Ok(Box::pin(result))
     }
 
-    #[cfg(target_os = "macos")]
     async fn watch(
         &self,
         path: &Path,
         latency: Duration,
     ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
-        use fsevent::EventStream;
-
-        let (tx, rx) = smol::channel::unbounded();
-        let (stream, handle) = EventStream::new(&[path], latency);
-        std::thread::spawn(move || {
-            stream.run(move |events| {
-                smol::block_on(tx.send(events.into_iter().map(|event| event.path).collect()))
-                    .is_ok()
-            });
-        });
-
-        Box::pin(rx.chain(futures::stream::once(async move {
-            drop(handle);
-            vec![]
-        })))
+        self.watch_with_poll_options(path, latency, PollOptions::default())
+            .await
     }
 
-    #[cfg(not(target_os = "macos"))]
-    async fn watch(
+    async fn watch_with_poll_options(
         &self,
         path: &Path,
-        _latency: Duration,
+        latency: Duration,
+        poll_options: PollOptions,
     ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
-        use notify::{event::EventKind, event::ModifyKind, Watcher};
-        // todo(linux): This spawns two threads, while the macOS impl
-        // only spawns one. Can we use a OnceLock or some such to make
-        // this better
-
-        let (tx, rx) = smol::channel::unbounded();
-
-        let mut file_watcher = notify::recommended_watcher({
-            let tx = tx.clone();
-            move |event: Result<notify::Event, _>| {
-                if let Some(event) = event.log_err() {
-                    tx.try_send(event.paths).ok();
+        let backend = *self.watcher_backend.read();
+        match backend {
+            WatcherBackend::Native => {}
+            WatcherBackend::Watchman => {
+                #[cfg(unix)]
+                match watchman::watch(path) {
+                    Ok(events) => return events,
+                    Err(error) => log::error!(
+                        "failed to watch {path:?} with watchman, falling back to native file system events: {error:?}"
+                    ),
                 }
+                #[cfg(not(unix))]
+                log::error!("watchman is not supported on this platform, falling back to native file system events");
             }
-        })
-        .expect("Could not start file watcher");
-
-        file_watcher
-            .watch(path, notify::RecursiveMode::Recursive)
-            .ok(); // It's ok if this fails, the parent watcher will add it.
-
-        let mut parent_watcher = notify::recommended_watcher({
-            let watched_path = path.to_path_buf();
-            let tx = tx.clone();
-            move |event: Result<notify::Event, _>| {
-                if let Some(event) = event.ok() {
-                    if event.paths.into_iter().any(|path| *path == watched_path) {
-                        match event.kind {
-                            EventKind::Modify(ev) => {
-                                if matches!(ev, ModifyKind::Name(_)) {
-                                    file_watcher
-                                        .watch(
-                                            watched_path.as_path(),
-                                            notify::RecursiveMode::Recursive,
-                                        )
-                                        .log_err();
-                                    let _ = tx.try_send(vec![watched_path.clone()]).ok();
-                                }
-                            }
-                            EventKind::Create(_) => {
-                                file_watcher
-                                    .watch(watched_path.as_path(), notify::RecursiveMode::Recursive)
-                                    .log_err();
-                                let _ = tx.try_send(vec![watched_path.clone()]).ok();
-                            }
-                            EventKind::Remove(_) => {
-                                file_watcher.unwatch(&watched_path).log_err();
-                                let _ = tx.try_send(vec![watched_path.clone()]).ok();
-                            }
-                            _ => {}
-                        }
-                    }
-                }
+            WatcherBackend::Polling => {
+                let interval = poll_options
+                    .interval
+                    .or(*self.poll_interval.read())
+                    .unwrap_or(DEFAULT_POLL_INTERVAL);
+                return polling::watch(path, interval, poll_options.skip_dir);
             }
-        })
-        .expect("Could not start file watcher");
-
-        parent_watcher
-            .watch(
-                path.parent()
-                    .expect("Watching root is probably not what you want"),
-                notify::RecursiveMode::NonRecursive,
-            )
-            .expect("Could not start watcher on parent directory");
+        }
+        self.watch_native(path, latency).await
+    }
 
-        Box::pin(rx.chain(futures::stream::once(async move {
-            drop(parent_watcher);
-            vec![]
-        })))
+    async fn watch_children(
+        &self,
+        path: &Path,
+        latency: Duration,
+    ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
+        let events = if matches!(*self.watcher_backend.read(), WatcherBackend::Polling) {
+            let interval = self.poll_interval.read().unwrap_or(DEFAULT_POLL_INTERVAL);
+            polling::watch(path, interval, Some(Arc::new(|_: &Path| true)))
+        } else {
+            self.watch_children_native(path, latency).await
+        };
+        children_events(events, path)
     }
 
     fn open_repo(&self, dotgit_path: &Path) -> Option<Arc<Mutex<dyn GitRepository>>> {
@@ -545,6 +946,12 @@ impl Fs for RealFs {
     }
 }
 
+#[cfg(any(test, feature = "test-support"))]
+const DEFAULT_FAKE_FILE_MODE: u32 = 0o644;
+
+#[cfg(any(test, feature = "test-support"))]
+const DEFAULT_FAKE_DIR_MODE: u32 = 0o755;
+
 #[cfg(any(test, feature = "test-support"))]
 pub struct FakeFs {
     // Use an unfair lock to ensure tests are deterministic.
@@ -562,6 +969,10 @@ struct FakeFsState {
     buffered_events: Vec<PathBuf>,
     metadata_call_count: usize,
     read_dir_call_count: usize,
+    save_call_count: usize,
+    synced_paths: Vec<PathBuf>,
+    /// The directories whose contents can't be read, as if their permissions denied it.
+    unreadable_dirs: HashSet<PathBuf>,
 }
 
 #[cfg(any(test, feature = "test-support"))]
@@ -570,6 +981,7 @@ enum FakeFsEntry {
     File {
         inode: u64,
         mtime: SystemTime,
+        mode: u32,
         content: Vec<u8>,
     },
     Dir {
@@ -585,6 +997,60 @@ enum FakeFsEntry {
 
 #[cfg(any(test, feature = "test-support"))]
 impl FakeFsState {
+    fn check_dir_readable(&self, path: &Path) -> Result<()> {
+        if self.unreadable_dirs.contains(path) {
+            Err(
+                anyhow::Error::new(io::Error::from(io::ErrorKind::PermissionDenied))
+                    .context(format!("failed to read directory {}", path.display())),
+            )
+        } else {
+            Ok(())
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> Option<Metadata> {
+        if let Some((mut entry, _)) = self.try_read_path(path, false) {
+            let is_symlink = entry.lock().is_symlink();
+            if is_symlink {
+                if let Some(e) = self.try_read_path(path, true).map(|e| e.0) {
+                    entry = e;
+                } else {
+                    return None;
+                }
+            }
+
+            let entry = entry.lock();
+            Some(match &*entry {
+                FakeFsEntry::File {
+                    inode,
+                    mtime,
+                    mode,
+                    content,
+                } => Metadata {
+                    inode: FileId::new(0, *inode),
+                    mtime: *mtime,
+                    is_dir: false,
+                    is_symlink,
+                    len: content.len() as u64,
+                    mode: Some(*mode),
+                    is_hidden: false,
+                },
+                FakeFsEntry::Dir { inode, mtime, .. } => Metadata {
+                    inode: FileId::new(0, *inode),
+                    mtime: *mtime,
+                    is_dir: true,
+                    is_symlink,
+                    len: 0,
+                    mode: Some(DEFAULT_FAKE_DIR_MODE),
+                    is_hidden: false,
+                },
+                FakeFsEntry::Symlink { .. } => unreachable!(),
+            })
+        } else {
+            None
+        }
+    }
+
     fn read_path(&self, target: &Path) -> Result<Arc<Mutex<FakeFsEntry>>> {
         Ok(self
             .try_read_path(target, true)
@@ -713,12 +1179,55 @@ impl FakeFs {
                 events_paused: false,
                 read_dir_call_count: 0,
                 metadata_call_count: 0,
+                save_call_count: 0,
+                synced_paths: Vec::new(),
+                unreadable_dirs: Default::default(),
             }),
         })
     }
 
     pub async fn insert_file(&self, path: impl AsRef<Path>, content: Vec<u8>) {
-        self.write_file_internal(path, content).unwrap()
+        self.write_file_internal(path, content, None).unwrap()
+    }
+
+    /// Creates a file with the given inode, like file systems do when they reuse the inode of a
+    /// deleted file.
+    pub async fn insert_file_with_inode(
+        &self,
+        path: impl AsRef<Path>,
+        content: Vec<u8>,
+        inode: FileId,
+    ) {
+        self.write_file_internal(path, content, Some(inode.index()))
+            .unwrap()
+    }
+
+    /// Sets the unix permission bits of the file at `path`, which are `0o644` by default.
+    pub fn set_file_mode(&self, path: impl AsRef<Path>, new_mode: u32) -> Result<()> {
+        let path = normalize_path(path.as_ref());
+        let mut state = self.state.lock();
+        let entry = state.read_path(&path)?;
+        let mut entry = entry.lock();
+        let FakeFsEntry::File { mode, .. } = &mut *entry else {
+            return Err(anyhow!("not a file: {}", path.display()));
+        };
+        *mode = new_mode;
+        drop(entry);
+        state.emit_event([path]);
+        Ok(())
+    }
+
+    /// Makes reading the contents of the directory at `path` fail with a permission error, or
+    /// succeed again.
+    pub fn set_dir_readable(&self, path: impl AsRef<Path>, readable: bool) {
+        let path = normalize_path(path.as_ref());
+        let mut state = self.state.lock();
+        if readable {
+            state.unreadable_dirs.remove(&path);
+        } else {
+            state.unreadable_dirs.insert(path.clone());
+        }
+        state.emit_event([path]);
     }
 
     pub async fn insert_symlink(&self, path: impl AsRef<Path>, target: PathBuf) {
@@ -740,16 +1249,21 @@ impl FakeFs {
         state.emit_event([path]);
     }
 
-    fn write_file_internal(&self, path: impl AsRef<Path>, content: Vec<u8>) -> Result<()> {
+    fn write_file_internal(
+        &self,
+        path: impl AsRef<Path>,
+        content: Vec<u8>,
+        inode: Option<u64>,
+    ) -> Result<()> {
         let mut state = self.state.lock();
         let path = path.as_ref();
-        let inode = state.next_inode;
+        let inode = inode.unwrap_or_else(|| util::post_inc(&mut state.next_inode));
         let mtime = state.next_mtime;
-        state.next_inode += 1;
         state.next_mtime += Duration::from_nanos(1);
         let file = Arc::new(Mutex::new(FakeFsEntry::File {
             inode,
             mtime,
+            mode: DEFAULT_FAKE_FILE_MODE,
             content,
         }));
         state.write_path(path, move |entry| {
@@ -758,6 +1272,12 @@ impl FakeFs {
                     e.insert(file);
                 }
                 btree_map::Entry::Occupied(mut e) => {
+                    // Like saves on a real file system, replacing a file keeps its permissions.
+                    if let FakeFsEntry::File { mode: old_mode, .. } = &*e.get().lock() {
+                        if let FakeFsEntry::File { mode, .. } = &mut *file.lock() {
+                            *mode = *old_mode;
+                        }
+                    }
                     *e.get_mut() = file;
                 }
             }
@@ -896,6 +1416,17 @@ impl FakeFs {
         });
     }
 
+    pub fn set_head_for_repo(&self, dot_git: &Path, head_state: &[(&Path, String)]) {
+        self.with_git_state(dot_git, true, |state| {
+            state.committed_contents.clear();
+            state.committed_contents.extend(
+                head_state
+                    .iter()
+                    .map(|(path, content)| (path.to_path_buf(), content.clone())),
+            );
+        });
+    }
+
     pub fn set_blame_for_repo(&self, dot_git: &Path, blames: Vec<(&Path, git::blame::Blame)>) {
         self.with_git_state(dot_git, true, |state| {
             state.blames.clear();
@@ -1013,6 +1544,23 @@ impl FakeFs {
         self.state.lock().metadata_call_count
     }
 
+    /// How many `save` calls have been issued.
+    pub fn save_call_count(&self) -> usize {
+        self.state.lock().save_call_count
+    }
+
+    /// How many of the streams returned by `watch` haven't been dropped.
+    pub fn watcher_count(&self) -> usize {
+        let mut state = self.state.lock();
+        state.event_txs.retain(|tx| !tx.is_closed());
+        state.event_txs.len()
+    }
+
+    /// The paths that were saved with [`Durability::Synced`], in order.
+    pub fn synced_paths(&self) -> Vec<PathBuf> {
+        self.state.lock().synced_paths.clone()
+    }
+
     fn simulate_random_delay(&self) -> impl futures::Future<Output = ()> {
         self.executor.simulate_random_delay()
     }
@@ -1105,6 +1653,7 @@ impl Fs for FakeFs {
         let file = Arc::new(Mutex::new(FakeFsEntry::File {
             inode,
             mtime,
+            mode: DEFAULT_FAKE_FILE_MODE,
             content: Vec::new(),
         }));
         state.write_path(path, |entry| {
@@ -1152,7 +1701,7 @@ impl Fs for FakeFs {
     ) -> Result<()> {
         let mut bytes = Vec::new();
         content.read_to_end(&mut bytes).await?;
-        self.write_file_internal(path, bytes)?;
+        self.write_file_internal(path, bytes, None)?;
         Ok(())
     }
 
@@ -1169,7 +1718,7 @@ impl Fs for FakeFs {
                 let mut bytes = Vec::new();
                 entry.read_to_end(&mut bytes).await?;
                 self.create_dir(path.parent().unwrap()).await?;
-                self.write_file_internal(&path, bytes)?;
+                self.write_file_internal(&path, bytes, None)?;
             }
         }
         Ok(())
@@ -1245,6 +1794,7 @@ impl Fs for FakeFs {
                 e.insert(Arc::new(Mutex::new(FakeFsEntry::File {
                     inode,
                     mtime,
+                    mode: DEFAULT_FAKE_FILE_MODE,
                     content: Vec::new(),
                 })))
                 .clone(),
@@ -1336,18 +1886,28 @@ impl Fs for FakeFs {
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path.as_path());
-        self.write_file_internal(path, data.into_bytes())?;
+        self.write_file_internal(path, data.into_bytes(), None)?;
         Ok(())
     }
 
-    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
+    async fn save(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        durability: Durability,
+    ) -> Result<()> {
+        self.state.lock().save_call_count += 1;
         self.simulate_random_delay().await;
         let path = normalize_path(path);
         let content = chunks(text, line_ending).collect::<String>();
         if let Some(path) = path.parent() {
             self.create_dir(path).await?;
         }
-        self.write_file_internal(path, content.into_bytes())?;
+        self.write_file_internal(path.clone(), content.into_bytes(), None)?;
+        if durability == Durability::Synced {
+            self.state.lock().synced_paths.push(path);
+        }
         Ok(())
     }
 
@@ -1384,35 +1944,7 @@ impl Fs for FakeFs {
         let path = normalize_path(path);
         let mut state = self.state.lock();
         state.metadata_call_count += 1;
-        if let Some((mut entry, _)) = state.try_read_path(&path, false) {
-            let is_symlink = entry.lock().is_symlink();
-            if is_symlink {
-                if let Some(e) = state.try_read_path(&path, true).map(|e| e.0) {
-                    entry = e;
-                } else {
-                    return Ok(None);
-                }
-            }
-
-            let entry = entry.lock();
-            Ok(Some(match &*entry {
-                FakeFsEntry::File { inode, mtime, .. } => Metadata {
-                    inode: *inode,
-                    mtime: *mtime,
-                    is_dir: false,
-                    is_symlink,
-                },
-                FakeFsEntry::Dir { inode, mtime, .. } => Metadata {
-                    inode: *inode,
-                    mtime: *mtime,
-                    is_dir: true,
-                    is_symlink,
-                },
-                FakeFsEntry::Symlink { .. } => unreachable!(),
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(state.metadata(&path))
     }
 
     async fn read_link(&self, path: &Path) -> Result<PathBuf> {
@@ -1439,6 +1971,7 @@ impl Fs for FakeFs {
         let path = normalize_path(path);
         let mut state = self.state.lock();
         state.read_dir_call_count += 1;
+        state.check_dir_readable(&path)?;
         let entry = state.read_path(&path)?;
         let mut entry = entry.lock();
         let children = entry.dir_entries(&path)?;
@@ -1449,6 +1982,29 @@ impl Fs for FakeFs {
         Ok(Box::pin(futures::stream::iter(paths)))
     }
 
+    async fn read_dir_metadata(&self, path: &Path) -> Result<Vec<DirEntryMetadata>> {
+        self.simulate_random_delay().await;
+        let path = normalize_path(path);
+        let mut state = self.state.lock();
+        state.read_dir_call_count += 1;
+        state.check_dir_readable(&path)?;
+        let entry = state.read_path(&path)?;
+        let file_names = entry
+            .lock()
+            .dir_entries(&path)?
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok(file_names
+            .into_iter()
+            .map(|file_name| {
+                let path = path.join(file_name);
+                let metadata = Ok(state.metadata(&path));
+                DirEntryMetadata { path, metadata }
+            })
+            .collect())
+    }
+
     async fn watch(
         &self,
         path: &Path,
@@ -1469,6 +2025,14 @@ impl Fs for FakeFs {
         }))
     }
 
+    async fn watch_children(
+        &self,
+        path: &Path,
+        latency: Duration,
+    ) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
+        children_events(self.watch(path, latency).await, path)
+    }
+
     fn open_repo(&self, abs_dot_git: &Path) -> Option<Arc<Mutex<dyn GitRepository>>> {
         let state = self.state.lock();
         let entry = state.read_path(abs_dot_git).unwrap();
@@ -1497,6 +2061,80 @@ impl Fs for FakeFs {
     }
 }
 
+/// Writes the text to a temporary file next to `path` and renames it over `path`, so that the
+/// program crashing never leaves the file partially written. The temporary file is only flushed
+/// before the rename for [`Durability::Synced`] writes, so a power loss or a crash of the system
+/// can still leave the file empty on file systems that may persist the rename before the
+/// contents, like XFS and btrfs. The file's permissions and group are
+/// preserved, but its extended attributes and ACLs aren't, since the file is a new one. Fails
+/// without touching `path` when the file can't be replaced without losing more than that: when
+/// it has other hard links, belongs to another user, or the file system doesn't support renaming
+/// over it.
+fn write_atomically(
+    path: &Path,
+    text: &Rope,
+    line_ending: LineEnding,
+    durability: Durability,
+) -> Result<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| anyhow!("{path:?} has no parent directory"))?;
+    let metadata = std::fs::metadata(path).ok();
+    #[cfg(unix)]
+    if let Some(metadata) = &metadata {
+        if metadata.nlink() > 1 {
+            return Err(anyhow!("{path:?} has other hard links"));
+        }
+        if metadata.uid() != unsafe { libc::geteuid() } {
+            return Err(anyhow!("{path:?} belongs to another user"));
+        }
+    }
+
+    let mut temp_file = tempfile::Builder::new()
+        .prefix(".zed-save-")
+        .tempfile_in(dir)?;
+    {
+        let mut writer = io::BufWriter::new(temp_file.as_file_mut());
+        for chunk in chunks(text, line_ending) {
+            writer.write_all(chunk.as_bytes())?;
+        }
+        writer.flush()?;
+    }
+    if let Some(metadata) = metadata {
+        #[cfg(unix)]
+        if temp_file.as_file().metadata()?.gid() != metadata.gid() {
+            std::os::unix::fs::fchown(temp_file.as_file(), None, Some(metadata.gid()))?;
+        }
+        temp_file
+            .as_file()
+            .set_permissions(metadata.permissions())?;
+    }
+    if durability == Durability::Synced {
+        temp_file.as_file().sync_all()?;
+    }
+    temp_file.persist(path)?;
+    if durability == Durability::Synced {
+        sync_parent_dir(path)?;
+    }
+    Ok(())
+}
+
+/// Flushes the directory containing `path` to disk, so that the file's entry in it is durable.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Directories can't be opened as files on Windows, where renames are durable once the file is
+/// flushed.
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
 fn chunks(rope: &Rope, line_ending: LineEnding) -> impl Iterator<Item = &str> {
     rope.chunks().flat_map(move |chunk| {
         let mut newline = false;
@@ -1589,11 +2227,280 @@ pub fn copy_recursive<'a>(
     .boxed()
 }
 
+/// Moves a file or directory to the trash, as described by the FreeDesktop.org trash
+/// specification, so that file managers can list and restore it.
+#[cfg(target_os = "linux")]
+async fn move_to_freedesktop_trash(path: &Path, options: RemoveOptions) -> Result<()> {
+    let home_trash_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| paths::HOME.join(".local/share"))
+        .join("Trash");
+    move_to_trash(path, &home_trash_dir, options).await
+}
+
+/// Moves `path` to `home_trash_dir` if they're on the same device. Otherwise, it's moved to the
+/// trash at the top of its mount, or copied to `home_trash_dir` if that trash can't be used.
+#[cfg(target_os = "linux")]
+async fn move_to_trash(path: &Path, home_trash_dir: &Path, options: RemoveOptions) -> Result<()> {
+    let metadata = match smol::fs::symlink_metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == io::ErrorKind::NotFound && options.ignore_if_not_exists => {
+            return Ok(())
+        }
+        Err(error) => return Err(error)?,
+    };
+    if metadata.is_dir()
+        && !options.recursive
+        && smol::fs::read_dir(path).await?.next().await.is_some()
+    {
+        return Err(anyhow!("{path:?} is not empty"));
+    }
+
+    // Only the parent is canonicalized, so that a symlink is trashed rather than its target.
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("cannot move {path:?} to the trash"))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let path = smol::fs::canonicalize(parent).await?.join(file_name);
+
+    smol::fs::create_dir_all(home_trash_dir).await?;
+    if smol::fs::metadata(home_trash_dir).await?.dev() != metadata.dev() {
+        match top_dir_trash(&path, metadata.dev()).await {
+            Ok((trash_dir, top_dir)) => {
+                return move_to_trash_dir(&path, &trash_dir, Some(&top_dir)).await;
+            }
+            Err(error) => log::warn!(
+                "can't use the trash of the mount containing {path:?}, copying it to the home trash: {error:#}"
+            ),
+        }
+    }
+    move_to_trash_dir(&path, home_trash_dir, None).await
+}
+
+/// Returns the user's trash directory at the top of the mount containing `path`, which is on
+/// the given device, creating it if needed, along with the top of the mount. That's
+/// `.Trash/$uid` if the administrator set up a shared `.Trash` directory, and `.Trash-$uid`
+/// otherwise.
+#[cfg(target_os = "linux")]
+async fn top_dir_trash(path: &Path, device: u64) -> Result<(PathBuf, PathBuf)> {
+    let mut top_dir = path
+        .parent()
+        .ok_or_else(|| anyhow!("{path:?} has no parent directory"))?;
+    while let Some(parent) = top_dir.parent() {
+        if smol::fs::metadata(parent).await?.dev() != device {
+            break;
+        }
+        top_dir = parent;
+    }
+
+    let uid = unsafe { libc::getuid() };
+    // The spec only allows using the shared directory if it isn't a symlink, and has the
+    // sticky bit set, so that users can't remove each other's files.
+    let shared_trash_dir = top_dir.join(".Trash");
+    if let Ok(metadata) = smol::fs::symlink_metadata(&shared_trash_dir).await {
+        if metadata.is_dir() && metadata.mode() & libc::S_ISVTX != 0 {
+            let trash_dir = shared_trash_dir.join(uid.to_string());
+            match create_user_trash_dir(&trash_dir, uid).await {
+                Ok(()) => return Ok((trash_dir, top_dir.to_path_buf())),
+                Err(error) => log::warn!("can't use the trash directory {trash_dir:?}: {error:#}"),
+            }
+        }
+    }
+
+    let trash_dir = top_dir.join(format!(".Trash-{uid}"));
+    create_user_trash_dir(&trash_dir, uid).await?;
+    Ok((trash_dir, top_dir.to_path_buf()))
+}
+
+/// Creates a trash directory that only the user with the given uid can access, or checks that
+/// the existing one is owned by them.
+#[cfg(target_os = "linux")]
+async fn create_user_trash_dir(trash_dir: &Path, uid: u32) -> Result<()> {
+    use std::os::unix::fs::DirBuilderExt as _;
+
+    match std::fs::DirBuilder::new().mode(0o700).create(trash_dir) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(error) => return Err(error)?,
+    }
+    // The spec requires the directory to belong to the user, and not to be a symlink.
+    let metadata = smol::fs::symlink_metadata(trash_dir).await?;
+    if !metadata.is_dir() || metadata.uid() != uid {
+        return Err(anyhow!("{trash_dir:?} isn't a directory owned by the user"));
+    }
+    Ok(())
+}
+
+/// Returns the current time in the local time zone, which the trash spec requires deletion
+/// dates to be in. The `time` crate refuses to look up the local offset in programs with
+/// several threads, so it's read with `localtime_r` instead.
+#[cfg(target_os = "linux")]
+fn now_local() -> time::OffsetDateTime {
+    let now = time::OffsetDateTime::now_utc();
+    let timestamp = now.unix_timestamp() as libc::time_t;
+    let mut tm = unsafe { std::mem::zeroed::<libc::tm>() };
+    let offset = if unsafe { libc::localtime_r(&timestamp, &mut tm) }.is_null() {
+        time::UtcOffset::UTC
+    } else {
+        time::UtcOffset::from_whole_seconds(tm.tm_gmtoff as i32).unwrap_or(time::UtcOffset::UTC)
+    };
+    now.to_offset(offset)
+}
+
+/// Moves `path` to the trash directory `trash_dir`, recording its path relative to `top_dir` if
+/// it's the trash of a mount, and its absolute path otherwise.
+#[cfg(target_os = "linux")]
+async fn move_to_trash_dir(path: &Path, trash_dir: &Path, top_dir: Option<&Path>) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("cannot move {path:?} to the trash"))?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    smol::fs::create_dir_all(&files_dir).await?;
+    smol::fs::create_dir_all(&info_dir).await?;
+
+    let deletion_date = now_local().format(time::macros::format_description!(
+        "[year]-[month]-[day]T[hour]:[minute]:[second]"
+    ))?;
+    let recorded_path = top_dir
+        .and_then(|top_dir| path.strip_prefix(top_dir).ok())
+        .unwrap_or(path);
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={deletion_date}\n",
+        percent_encode_path(recorded_path)
+    );
+
+    // Creating the info file reserves the name in the trash, so that concurrent deletions of
+    // files with the same name don't overwrite each other.
+    let mut suffix = 1;
+    loop {
+        let mut trashed_name = file_name.to_os_string();
+        if suffix > 1 {
+            trashed_name.push(format!(".{suffix}"));
+        }
+        let mut info_name = trashed_name.clone();
+        info_name.push(".trashinfo");
+        let info_path = info_dir.join(info_name);
+        match smol::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+            .await
+        {
+            Ok(mut info_file) => {
+                info_file.write_all(info.as_bytes()).await?;
+                info_file.flush().await?;
+                if let Err(error) = move_path(path, &files_dir.join(&trashed_name)).await {
+                    smol::fs::remove_file(&info_path).await.log_err();
+                    return Err(error);
+                }
+                return Ok(());
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => suffix += 1,
+            Err(error) => return Err(error)?,
+        }
+    }
+}
+
+/// Renames `source` to `target`, or copies it and removes it when they're on different devices.
+#[cfg(target_os = "linux")]
+async fn move_path(source: &Path, target: &Path) -> Result<()> {
+    match smol::fs::rename(source, target).await {
+        Ok(()) => Ok(()),
+        Err(error) if error.raw_os_error() == Some(libc::EXDEV) => {
+            let source = source.to_path_buf();
+            let target = target.to_path_buf();
+            smol::unblock(move || {
+                if let Err(error) = copy_preserving_symlinks(&source, &target) {
+                    remove_path(&target).log_err();
+                    return Err(error)?;
+                }
+                remove_path(&source)?;
+                Ok::<(), anyhow::Error>(())
+            })
+            .await
+        }
+        Err(error) => Err(error)?,
+    }
+}
+
+/// Copies a file or directory, recreating the symlinks in it rather than copying their targets.
+#[cfg(target_os = "linux")]
+fn copy_preserving_symlinks(source: &Path, target: &Path) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(source)?;
+    if metadata.is_symlink() {
+        std::os::unix::fs::symlink(std::fs::read_link(source)?, target)
+    } else if metadata.is_dir() {
+        std::fs::create_dir(target)?;
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            copy_preserving_symlinks(&entry.path(), &target.join(entry.file_name()))?;
+        }
+        std::fs::set_permissions(target, metadata.permissions())
+    } else {
+        std::fs::copy(source, target).map(drop)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn remove_path(path: &Path) -> io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Escapes a path the way the `Path` key of a `.trashinfo` file requires.
+#[cfg(target_os = "linux")]
+fn percent_encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let mut encoded = String::new();
+    for byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(*byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Whether the file system flags a file as hidden, with `chflags hidden` on macOS or the hidden
+/// attribute on Windows.
+fn is_hidden(metadata: &std::fs::Metadata) -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::macos::fs::MetadataExt as _;
+        const UF_HIDDEN: u32 = 0x8000;
+        metadata.st_flags() & UF_HIDDEN != 0
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::MetadataExt as _;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
 // todo(windows)
 // can we get file id not open the file twice?
 // https://github.com/rust-lang/rust/issues/63010
 #[cfg(target_os = "windows")]
-async fn file_id(path: impl AsRef<Path>) -> Result<u64> {
+async fn file_id(path: impl AsRef<Path>) -> Result<FileId> {
     use std::os::windows::io::AsRawHandle;
 
     use smol::fs::windows::OpenOptionsExt;
@@ -1616,7 +2523,10 @@ async fn file_id(path: impl AsRef<Path>) -> Result<u64> {
     smol::unblock(move || {
         unsafe { GetFileInformationByHandle(HANDLE(file.as_raw_handle() as _), &mut info)? };
 
-        Ok(((info.nFileIndexHigh as u64) << 32) | (info.nFileIndexLow as u64))
+        Ok(FileId::new(
+            info.dwVolumeSerialNumber as u64,
+            ((info.nFileIndexHigh as u64) << 32) | (info.nFileIndexLow as u64),
+        ))
     })
     .await
 }
@@ -1627,6 +2537,163 @@ mod tests {
     use gpui::BackgroundExecutor;
     use serde_json::json;
 
+    #[test]
+    fn test_path_to_rescan() {
+        let watched_path = Path::new("/root/project");
+        assert_eq!(
+            path_to_rescan(Path::new("/root/project/src"), watched_path),
+            Some(PathBuf::from("/root/project/src"))
+        );
+        assert_eq!(
+            path_to_rescan(Path::new("/root"), watched_path),
+            Some(PathBuf::from("/root/project"))
+        );
+        assert_eq!(path_to_rescan(Path::new("/other"), watched_path), None);
+    }
+
+    #[test]
+    fn test_file_id_survives_rename() {
+        smol::block_on(async {
+            let dir = TempDir::new().unwrap();
+            let fs = RealFs::default();
+            let a = dir.path().join("a.txt");
+            let b = dir.path().join("b.txt");
+            let c = dir.path().join("c.txt");
+            fs.create_file(&a, Default::default()).await.unwrap();
+            fs.create_file(&b, Default::default()).await.unwrap();
+
+            let a_id = fs.metadata(&a).await.unwrap().unwrap().inode;
+            let b_id = fs.metadata(&b).await.unwrap().unwrap().inode;
+            assert_ne!(a_id, b_id);
+
+            fs.rename(&a, &c, Default::default()).await.unwrap();
+            assert_eq!(fs.metadata(&c).await.unwrap().unwrap().inode, a_id);
+        });
+    }
+
+    #[test]
+    fn test_save_replaces_file_atomically() {
+        smol::block_on(async {
+            let dir = TempDir::new().unwrap();
+            let fs = RealFs::default();
+            let path = dir.path().join("a.txt");
+            std::fs::write(&path, "old contents that are longer").unwrap();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt as _;
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750)).unwrap();
+            }
+
+            fs.save(
+                &path,
+                &"new\ncontents".into(),
+                LineEnding::Windows,
+                Default::default(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "new\r\ncontents");
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt as _;
+                let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o750);
+            }
+
+            // Files with other hard links are overwritten in place, so the links keep sharing
+            // the contents.
+            #[cfg(unix)]
+            {
+                let hard_link = dir.path().join("hard_link.txt");
+                std::fs::hard_link(&path, &hard_link).unwrap();
+                let inode = std::fs::metadata(&path).unwrap().ino();
+                fs.save(
+                    &path,
+                    &"shared".into(),
+                    LineEnding::Unix,
+                    Default::default(),
+                )
+                .await
+                .unwrap();
+                assert_eq!(std::fs::metadata(&path).unwrap().ino(), inode);
+                assert_eq!(std::fs::read_to_string(&hard_link).unwrap(), "shared");
+                std::fs::remove_file(&hard_link).unwrap();
+            }
+
+            // Symlinks are written through, and no temporary files are left behind.
+            #[cfg(unix)]
+            {
+                let link = dir.path().join("link.txt");
+                std::os::unix::fs::symlink(&path, &link).unwrap();
+                fs.save(
+                    &link,
+                    &"linked".into(),
+                    LineEnding::Unix,
+                    Default::default(),
+                )
+                .await
+                .unwrap();
+                assert!(std::fs::symlink_metadata(&link)
+                    .unwrap()
+                    .file_type()
+                    .is_symlink());
+                assert_eq!(std::fs::read_to_string(&path).unwrap(), "linked");
+            }
+            let mut file_names = std::fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect::<Vec<_>>();
+            file_names.sort();
+            assert!(file_names
+                .iter()
+                .all(|name| !name.to_string_lossy().starts_with(".zed-save-")));
+        });
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_move_to_trash() {
+        smol::block_on(async {
+            let dir = TempDir::new().unwrap();
+            let trash_dir = dir.path().join("Trash");
+            let target = dir.path().join("target.txt");
+            let link = dir.path().join("link.txt");
+            std::fs::write(&target, "contents").unwrap();
+            std::os::unix::fs::symlink(&target, &link).unwrap();
+
+            // Symlinks are trashed themselves rather than their targets.
+            move_to_trash(&link, &trash_dir, Default::default())
+                .await
+                .unwrap();
+            assert!(std::fs::symlink_metadata(&link).is_err());
+            assert_eq!(std::fs::read_to_string(&target).unwrap(), "contents");
+            assert_eq!(
+                std::fs::read_link(trash_dir.join("files/link.txt")).unwrap(),
+                target
+            );
+            let info = std::fs::read_to_string(trash_dir.join("info/link.txt.trashinfo")).unwrap();
+            assert!(info.contains(&format!(
+                "Path={}\n",
+                percent_encode_path(&dir.path().canonicalize().unwrap().join("link.txt"))
+            )));
+
+            let missing = dir.path().join("missing.txt");
+            assert!(move_to_trash(&missing, &trash_dir, Default::default())
+                .await
+                .is_err());
+            move_to_trash(
+                &missing,
+                &trash_dir,
+                RemoveOptions {
+                    recursive: false,
+                    ignore_if_not_exists: true,
+                },
+            )
+            .await
+            .unwrap();
+        });
+    }
+
     #[gpui::test]
     async fn test_fake_fs(executor: BackgroundExecutor) {
         let fs = FakeFs::new(executor.clone());
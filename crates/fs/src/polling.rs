@@ -0,0 +1,238 @@
+//! A file watcher that periodically scans the watched path instead of relying on file system
+//! events, for environments where those aren't available, such as some containers and network
+//! file systems.
+
+use crate::MIN_POLL_INTERVAL;
+use futures::Stream;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::Hasher,
+    io::Read,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+/// Files up to this size are hashed, so that rewriting a file with the same contents isn't
+/// reported as a change, and changes that don't affect the mtime (whose granularity can be as
+/// coarse as a second) are still detected.
+const MAX_HASHED_FILE_SIZE: u64 = 64 * 1024;
+
+/// Watches `path` by scanning it every `interval`, reporting the paths that were created,
+/// removed, or modified since the previous scan. The contents of the directories for which
+/// `skip_dir` returns true aren't scanned.
+pub(crate) fn watch(
+    path: &Path,
+    interval: Duration,
+    skip_dir: Option<Arc<dyn Fn(&Path) -> bool + Send + Sync>>,
+) -> Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>> {
+    let path = path.to_path_buf();
+    let interval = interval.max(MIN_POLL_INTERVAL);
+    let skip_dir = skip_dir.unwrap_or_else(|| Arc::new(|_: &Path| false));
+    let (tx, rx) = smol::channel::unbounded();
+    std::thread::spawn(move || {
+        let mut snapshot = Snapshot::scan(&path, None, &*skip_dir);
+        loop {
+            std::thread::sleep(interval);
+            if tx.is_closed() {
+                return;
+            }
+            let new_snapshot = Snapshot::scan(&path, Some(&snapshot), &*skip_dir);
+            let changed_paths = snapshot.changed_paths(&new_snapshot);
+            snapshot = new_snapshot;
+            if !changed_paths.is_empty() && smol::block_on(tx.send(changed_paths)).is_err() {
+                return;
+            }
+        }
+    });
+    Box::pin(rx)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct FileState {
+    mtime: Option<SystemTime>,
+    len: u64,
+    is_dir: bool,
+    content_hash: Option<u64>,
+}
+
+/// The state of every file under a path at the time it was scanned.
+#[derive(Default)]
+struct Snapshot {
+    files: HashMap<PathBuf, FileState>,
+}
+
+impl Snapshot {
+    /// Scans `root` without following symlinks. Small files are only re-read if their metadata
+    /// differs from the `previous` snapshot, or if they were modified too recently for their
+    /// mtime to be trusted. The contents of directories other than `root` for which `skip_dir`
+    /// returns true are left out.
+    fn scan(root: &Path, previous: Option<&Snapshot>, skip_dir: &dyn Fn(&Path) -> bool) -> Self {
+        let mut files = HashMap::default();
+        let mut stack = vec![root.to_path_buf()];
+        while let Some(path) = stack.pop() {
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            let mtime = metadata.modified().ok();
+            let len = metadata.len();
+            let is_dir = metadata.is_dir();
+
+            if is_dir && (path == root || !skip_dir(&path)) {
+                if let Ok(entries) = fs::read_dir(&path) {
+                    stack.extend(entries.flatten().map(|entry| entry.path()));
+                }
+            }
+
+            let content_hash = if metadata.is_file() && len <= MAX_HASHED_FILE_SIZE {
+                let previous_state = previous.and_then(|previous| previous.files.get(&path));
+                match previous_state {
+                    Some(state)
+                        if state.mtime == mtime
+                            && state.len == len
+                            && !modified_recently(mtime) =>
+                    {
+                        state.content_hash
+                    }
+                    _ => hash_file(&path),
+                }
+            } else {
+                None
+            };
+
+            files.insert(
+                path,
+                FileState {
+                    mtime,
+                    len,
+                    is_dir,
+                    content_hash,
+                },
+            );
+        }
+        Self { files }
+    }
+
+    /// Returns the paths whose state differs between this snapshot and `new`.
+    fn changed_paths(&self, new: &Snapshot) -> Vec<PathBuf> {
+        let mut changed_paths = Vec::new();
+        for (path, new_state) in &new.files {
+            match self.files.get(path) {
+                Some(old_state) if !has_changed(old_state, new_state) => {}
+                _ => changed_paths.push(path.clone()),
+            }
+        }
+        for path in self.files.keys() {
+            if !new.files.contains_key(path) {
+                changed_paths.push(path.clone());
+            }
+        }
+        changed_paths.sort();
+        changed_paths
+    }
+}
+
+fn has_changed(old: &FileState, new: &FileState) -> bool {
+    if old.is_dir != new.is_dir {
+        return true;
+    }
+    // A directory's mtime changes when its children do, but those are reported themselves.
+    if new.is_dir {
+        return false;
+    }
+    match (old.content_hash, new.content_hash) {
+        (Some(old_hash), Some(new_hash)) => old_hash != new_hash,
+        _ => old.mtime != new.mtime || old.len != new.len,
+    }
+}
+
+/// Whether a file was modified so recently that a further change within the file system's
+/// timestamp granularity wouldn't update its mtime.
+fn modified_recently(mtime: Option<SystemTime>) -> bool {
+    mtime.map_or(true, |mtime| {
+        SystemTime::now()
+            .duration_since(mtime)
+            .map_or(true, |elapsed| elapsed < Duration::from_secs(2))
+    })
+}
+
+fn hash_file(path: &Path) -> Option<u64> {
+    let mut contents = Vec::new();
+    fs::File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut contents))
+        .ok()?;
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&contents);
+    Some(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("README.md"), "# Readme").unwrap();
+        fs::write(root.join("LICENSE"), "MIT").unwrap();
+
+        let snapshot = Snapshot::scan(root, None, &|_| false);
+        assert!(snapshot
+            .changed_paths(&Snapshot::scan(root, Some(&snapshot), &|_| false))
+            .is_empty());
+
+        // Rewriting a file with the same contents isn't a change.
+        fs::write(root.join("README.md"), "# Readme").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() { }").unwrap();
+        fs::remove_file(root.join("LICENSE")).unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+
+        let new_snapshot = Snapshot::scan(root, Some(&snapshot), &|_| false);
+        assert_eq!(
+            snapshot.changed_paths(&new_snapshot),
+            vec![
+                root.join("LICENSE"),
+                root.join("src/lib.rs"),
+                root.join("src/main.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skipped_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("node_modules/lib")).unwrap();
+        fs::write(root.join("node_modules/lib/index.js"), "").unwrap();
+        fs::write(root.join("main.js"), "").unwrap();
+
+        let skip_dir = |path: &Path| path.ends_with("node_modules");
+        let snapshot = Snapshot::scan(root, None, &skip_dir);
+        let mut paths = snapshot.files.keys().cloned().collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                root.to_path_buf(),
+                root.join("main.js"),
+                root.join("node_modules"),
+            ]
+        );
+
+        fs::write(
+            root.join("node_modules/lib/index.js"),
+            "module.exports = {}",
+        )
+        .unwrap();
+        fs::write(root.join("main.js"), "require('lib')").unwrap();
+        assert_eq!(
+            snapshot.changed_paths(&Snapshot::scan(root, Some(&snapshot), &skip_dir)),
+            vec![root.join("main.js")]
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! A file watcher that subscribes to a running Watchman server instead of listening to the
+//! platform's file system events. In large repositories that already run Watchman, this avoids
+//! crawling the tree a second time, and only reports changes to paths that Watchman doesn't
+//! ignore.
+
+use anyhow::{anyhow, Context, Result};
+use futures::Stream;
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    pin::Pin,
+    process::Command,
+    time::Duration,
+};
+
+const SUBSCRIPTION_NAME: &str = "zed";
+/// How long to wait for a notification before checking whether the watch has been dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches `path` for changes using Watchman, returning an error if the server can't be
+/// reached. If the connection is lost afterwards, the watcher reconnects and resubscribes from
+/// the last clock it saw, so that no changes are missed in between.
+pub(crate) fn watch(path: &Path) -> Result<Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>>> {
+    let path = path.to_path_buf();
+    let mut connection = Connection::connect()?;
+    let mut clock = connection.subscribe(&path, None)?;
+    let mut resubscribed = false;
+
+    let (tx, rx) = smol::channel::unbounded();
+    std::thread::spawn(move || loop {
+        match connection.receive() {
+            Ok(Some(response)) => {
+                let Some(notification) = Notification::parse(&response, &path, resubscribed) else {
+                    continue;
+                };
+                clock = Some(notification.clock);
+                resubscribed = false;
+                if !notification.paths.is_empty()
+                    && smol::block_on(tx.send(notification.paths)).is_err()
+                {
+                    return;
+                }
+            }
+            Ok(None) => {
+                if tx.is_closed() {
+                    return;
+                }
+            }
+            Err(error) => {
+                log::warn!("lost connection to watchman, reconnecting: {error:?}");
+                connection = loop {
+                    std::thread::sleep(RECONNECT_INTERVAL);
+                    if tx.is_closed() {
+                        return;
+                    }
+                    let resubscribed = Connection::connect().and_then(|mut connection| {
+                        let new_clock = connection.subscribe(&path, clock.as_deref())?;
+                        Ok((connection, new_clock))
+                    });
+                    match resubscribed {
+                        Ok((connection, new_clock)) => {
+                            clock = clock.or(new_clock);
+                            resubscribed = true;
+                            break connection;
+                        }
+                        Err(error) => log::debug!("failed to reconnect to watchman: {error:?}"),
+                    }
+                };
+            }
+        }
+    });
+
+    Ok(Box::pin(rx))
+}
+
+/// A connection to the Watchman server, speaking its JSON protocol.
+struct Connection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    /// The part of the current response that has been read so far.
+    line: String,
+}
+
+impl Connection {
+    fn connect() -> Result<Self> {
+        let socket_path = socket_path()?;
+        let stream = UnixStream::connect(&socket_path)
+            .with_context(|| format!("failed to connect to watchman at {socket_path:?}"))?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            line: String::new(),
+        })
+    }
+
+    /// Starts watching the project containing `path`, and subscribes to changes within `path`
+    /// since `since`, or from now on if it's `None`. Returns the clock at which the
+    /// subscription started, if Watchman reported one.
+    fn subscribe(&mut self, path: &Path, since: Option<&str>) -> Result<Option<String>> {
+        let response = self.request(json!(["watch-project", path]))?;
+        let root = response["watch"]
+            .as_str()
+            .context("watch-project response has no root")?
+            .to_string();
+
+        let mut query = json!({
+            "fields": ["name"],
+            "empty_on_fresh_instance": true,
+        });
+        if let Some(relative_path) = response["relative_path"].as_str() {
+            query["relative_root"] = json!(relative_path);
+        }
+        if let Some(since) = since {
+            query["since"] = json!(since);
+        }
+        let response = self.request(json!(["subscribe", root, SUBSCRIPTION_NAME, query]))?;
+        Ok(response["clock"].as_str().map(ToString::to_string))
+    }
+
+    /// Sends a command and waits for its response, skipping any notifications.
+    fn request(&mut self, command: Value) -> Result<Value> {
+        serde_json::to_writer(&mut self.writer, &command)?;
+        self.writer.write_all(b"\n")?;
+        loop {
+            if let Some(response) = self.receive()? {
+                if !is_unilateral(&response) {
+                    return Ok(response);
+                }
+            }
+        }
+    }
+
+    /// Reads the next response, returning `None` if none arrived before the read timed out.
+    fn receive(&mut self) -> Result<Option<Value>> {
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => Err(anyhow!("watchman closed the connection")),
+            Ok(_) => {
+                let response: Value = serde_json::from_str(&self.line)?;
+                self.line.clear();
+                if let Some(error) = response["error"].as_str() {
+                    return Err(anyhow!("watchman error: {error}"));
+                }
+                Ok(Some(response))
+            }
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+fn is_unilateral(response: &Value) -> bool {
+    response["unilateral"].as_bool().unwrap_or(false)
+        || response.get("subscription").is_some()
+        || response.get("log").is_some()
+}
+
+/// Returns the path of the Watchman server's socket, starting the server if necessary.
+fn socket_path() -> Result<PathBuf> {
+    if let Some(socket_path) = std::env::var_os("WATCHMAN_SOCK") {
+        return Ok(socket_path.into());
+    }
+
+    let output = Command::new("watchman")
+        .args(["--output-encoding=json", "--no-pretty", "get-sockname"])
+        .output()
+        .context("failed to run watchman")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "watchman get-sockname failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let response: Value = serde_json::from_slice(&output.stdout)?;
+    response["unix_domain"]
+        .as_str()
+        .or_else(|| response["sockname"].as_str())
+        .map(PathBuf::from)
+        .context("watchman didn't report its socket path")
+}
+
+/// The changes reported in a subscription notification.
+#[derive(Debug, PartialEq)]
+struct Notification {
+    clock: String,
+    paths: Vec<PathBuf>,
+}
+
+impl Notification {
+    /// Parses a subscription notification, resolving the changed paths against the watched
+    /// `path`. A fresh instance after resubscribing means that Watchman lost track of the
+    /// changes since the previous clock, e.g. because it restarted, in which case the whole
+    /// watched path is reported as changed so that it gets rescanned.
+    fn parse(response: &Value, path: &Path, resubscribed: bool) -> Option<Self> {
+        if response["subscription"].as_str() != Some(SUBSCRIPTION_NAME) {
+            return None;
+        }
+        let clock = response["clock"].as_str()?.to_string();
+        let paths = if response["is_fresh_instance"].as_bool().unwrap_or(false) {
+            if resubscribed {
+                vec![path.to_path_buf()]
+            } else {
+                Vec::new()
+            }
+        } else {
+            response["files"]
+                .as_array()?
+                .iter()
+                .filter_map(|file| Some(path.join(file.as_str()?)))
+                .collect()
+        };
+        Some(Self { clock, paths })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_notification() {
+        let path = Path::new("/repo/project");
+        let notification = json!({
+            "subscription": "zed",
+            "clock": "c:1:2",
+            "files": ["src/main.rs", "Cargo.toml"],
+            "is_fresh_instance": false,
+        });
+        assert_eq!(
+            Notification::parse(&notification, path, true),
+            Some(Notification {
+                clock: "c:1:2".into(),
+                paths: vec![
+                    PathBuf::from("/repo/project/src/main.rs"),
+                    PathBuf::from("/repo/project/Cargo.toml")
+                ],
+            })
+        );
+
+        let fresh_instance = json!({
+            "subscription": "zed",
+            "clock": "c:3:4",
+            "files": [],
+            "is_fresh_instance": true,
+        });
+        assert_eq!(
+            Notification::parse(&fresh_instance, path, false).map(|n| n.paths),
+            Some(vec![])
+        );
+        assert_eq!(
+            Notification::parse(&fresh_instance, path, true).map(|n| n.paths),
+            Some(vec![path.to_path_buf()])
+        );
+
+        assert_eq!(
+            Notification::parse(
+                &json!({ "subscription": "other", "clock": "c:5:6" }),
+                path,
+                true
+            ),
+            None
+        );
+    }
+}
@@ -38,6 +38,16 @@ pub trait PathMatchCandidateSet<'a>: Send + Sync {
     }
     fn prefix(&self) -> Arc<str>;
     fn candidates(&'a self, start: usize) -> Self::Candidates;
+    /// The factor by which to scale the score of a candidate matching the query, allowing
+    /// some paths to rank below others of equal textual score without excluding them.
+    fn score_multiplier(&self, _path: &Path) -> f64 {
+        1.0
+    }
+    /// Whether to leave a candidate out of the matches. Unlike filtering the returned matches,
+    /// this keeps excluded paths from taking the place of others within the maximum results.
+    fn is_excluded(&self, _path: &Path) -> bool {
+        false
+    }
 }
 
 impl Match for PathMatch {
@@ -176,7 +186,10 @@ pub async fn match_path_sets<'a, Set: PathMatchCandidateSet<'a>>(
                         if tree_start < segment_end && segment_start < tree_end {
                             let start = cmp::max(tree_start, segment_start) - tree_start;
                             let end = cmp::min(tree_end, segment_end) - tree_start;
-                            let candidates = candidate_set.candidates(start).take(end - start);
+                            let candidates = candidate_set
+                                .candidates(start)
+                                .take(end - start)
+                                .filter(|candidate| !candidate_set.is_excluded(candidate.path));
 
                             let worktree_id = candidate_set.id();
                             let prefix = candidate_set.prefix().chars().collect::<Vec<_>>();
@@ -191,7 +204,7 @@ pub async fn match_path_sets<'a, Set: PathMatchCandidateSet<'a>>(
                                 results,
                                 cancel_flag,
                                 |candidate, score| PathMatch {
-                                    score,
+                                    score: score * candidate_set.score_multiplier(candidate.path),
                                     worktree_id,
                                     positions: Vec::new(),
                                     path: Arc::from(candidate.path),
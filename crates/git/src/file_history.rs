@@ -0,0 +1,116 @@
+use crate::Oid;
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+/// Separates the commits in the output of `git log`.
+const RECORD_SEPARATOR: char = '\x1e';
+/// Separates the fields of a commit in the output of `git log`.
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// A commit that changed a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileHistoryEntry {
+    pub sha: Oid,
+    pub author_name: String,
+    pub author_email: String,
+    /// The time the commit was authored, as a Unix timestamp.
+    pub author_time: i64,
+    pub summary: String,
+    /// The path of the file in this commit, relative to the repository's working directory.
+    /// It differs from the file's current path if the file was renamed since.
+    pub path: PathBuf,
+}
+
+/// Returns the commits that changed the file at `path`, newest first, following the file
+/// across renames.
+pub fn file_history(
+    git_binary: &Path,
+    working_directory: &Path,
+    path: &Path,
+) -> Result<Vec<FileHistoryEntry>> {
+    let mut command = Command::new(git_binary);
+
+    command
+        .current_dir(working_directory)
+        .arg("log")
+        .arg("--follow")
+        .arg("--name-only")
+        .arg(format!(
+            "--format={RECORD_SEPARATOR}%H{FIELD_SEPARATOR}%an{FIELD_SEPARATOR}%ae{FIELD_SEPARATOR}%at{FIELD_SEPARATOR}%s"
+        ))
+        .arg("--")
+        .arg(path.as_os_str());
+
+    #[cfg(windows)]
+    command.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
+
+    let output = command
+        .output()
+        .map_err(|e| anyhow!("Failed to start git log process: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("git log process failed: {}", stderr));
+    }
+
+    parse_file_history(&String::from_utf8(output.stdout)?)
+}
+
+fn parse_file_history(output: &str) -> Result<Vec<FileHistoryEntry>> {
+    output
+        .split(RECORD_SEPARATOR)
+        .filter(|record| !record.trim().is_empty())
+        .map(|record| {
+            let mut lines = record.lines();
+            let header = lines.next().context("missing commit header")?;
+            let mut fields = header.splitn(5, FIELD_SEPARATOR);
+            let mut field = |name| fields.next().with_context(|| format!("missing {name}"));
+
+            let sha = field("sha")?.parse()?;
+            let author_name = field("author name")?.to_string();
+            let author_email = field("author email")?.to_string();
+            let author_time = field("author time")?.parse()?;
+            let summary = field("summary")?.to_string();
+            let path = lines
+                .find(|line| !line.is_empty())
+                .context("missing path")?
+                .into();
+
+            Ok(FileHistoryEntry {
+                sha,
+                author_name,
+                author_email,
+                author_time,
+                summary,
+                path,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_history() {
+        let output = "\x1e1b1a0e1c5e2d9f2d5b0e4b1e6c0c6f3a9d8e7f6a\x1fJane Doe\x1fjane@example.com\x1f1714000000\x1fRename main.rs\n\nsrc/main.rs\n\x1e0c4d2b9a8f7e6d5c4b3a29180f7e6d5c4b3a2918\x1fJohn Doe\x1fjohn@example.com\x1f1713000000\x1fAdd main: with a \x1f separator\n\nmain.rs\n";
+
+        let entries = parse_file_history(output).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].sha.to_string(),
+            "1b1a0e1c5e2d9f2d5b0e4b1e6c0c6f3a9d8e7f6a"
+        );
+        assert_eq!(entries[0].author_name, "Jane Doe");
+        assert_eq!(entries[0].author_time, 1714000000);
+        assert_eq!(entries[0].summary, "Rename main.rs");
+        assert_eq!(entries[0].path, Path::new("src/main.rs"));
+        assert_eq!(entries[1].summary, "Add main: with a \x1f separator");
+        assert_eq!(entries[1].path, Path::new("main.rs"));
+    }
+}
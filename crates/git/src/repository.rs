@@ -1,4 +1,5 @@
 use crate::blame::Blame;
+use crate::file_history::FileHistoryEntry;
 use crate::GitHostingProviderRegistry;
 use anyhow::{Context, Result};
 use collections::HashMap;
@@ -25,6 +26,17 @@ pub struct Branch {
     pub unix_timestamp: Option<i64>,
 }
 
+/// The versions of a file that git stores in the index while a merge leaves the file conflicted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeVersions {
+    /// The common ancestor of both sides, missing if the file was added on both sides.
+    pub base: Option<String>,
+    /// The version from the branch that is being merged into.
+    pub ours: Option<String>,
+    /// The version from the branch that is being merged.
+    pub theirs: Option<String>,
+}
+
 pub trait GitRepository: Send {
     fn reload_index(&self);
 
@@ -32,6 +44,17 @@ pub trait GitRepository: Send {
     /// Note that for symlink entries, this will return the contents of the symlink, not the target.
     fn load_index_text(&self, relative_file_path: &Path) -> Option<String>;
 
+    /// Loads a file's contents as of the HEAD commit.
+    fn load_committed_text(&self, relative_file_path: &Path) -> Option<String>;
+
+    /// Loads the versions of a file that is conflicted by an ongoing merge. Returns `None` if
+    /// the file isn't conflicted.
+    fn load_merge_versions(&self, relative_file_path: &Path) -> Option<MergeVersions>;
+
+    /// Replaces a file's contents in the index, which stages or unstages changes to it.
+    /// Removes the file from the index if `content` is `None`.
+    fn set_index_text(&self, relative_file_path: &Path, content: Option<String>) -> Result<()>;
+
     /// Returns the URL of the remote with the given name.
     fn remote_url(&self, name: &str) -> Option<String>;
     fn branch_name(&self) -> Option<String>;
@@ -62,6 +85,18 @@ pub trait GitRepository: Send {
     fn create_branch(&self, _: &str) -> Result<()>;
 
     fn blame(&self, path: &Path, content: Rope) -> Result<crate::blame::Blame>;
+
+    /// Returns the commits that changed a file, newest first, following it across renames.
+    fn file_history(&self, relative_file_path: &Path) -> Result<Vec<FileHistoryEntry>>;
+
+    /// Loads a file's contents as of the given commit, where `relative_file_path` is the
+    /// file's path in that commit.
+    fn load_text_at_revision(&self, sha: &str, relative_file_path: &Path)
+        -> Result<Option<String>>;
+
+    /// Creates a commit of the index on top of HEAD with the given message, returning its SHA.
+    /// Hooks are not run.
+    fn commit(&self, message: &str) -> Result<String>;
 }
 
 impl std::fmt::Debug for dyn GitRepository {
@@ -121,6 +156,109 @@ impl GitRepository for RealGitRepository {
         None
     }
 
+    fn load_committed_text(&self, relative_file_path: &Path) -> Option<String> {
+        fn logic(repo: &LibGitRepository, relative_file_path: &Path) -> Result<Option<String>> {
+            check_path_to_repo_path_errors(relative_file_path)?;
+
+            let tree = repo.head()?.peel_to_tree()?;
+            let oid = match tree.get_path(relative_file_path) {
+                Ok(entry) => entry.id(),
+                Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+
+            let content = repo.find_blob(oid)?.content().to_owned();
+            Ok(Some(String::from_utf8(content)?))
+        }
+
+        match logic(&self.repository, relative_file_path) {
+            Ok(value) => return value,
+            Err(err) => log::error!("Error loading committed text: {:?}", err),
+        }
+        None
+    }
+
+    fn load_merge_versions(&self, relative_file_path: &Path) -> Option<MergeVersions> {
+        fn logic(
+            repo: &LibGitRepository,
+            relative_file_path: &Path,
+        ) -> Result<Option<MergeVersions>> {
+            const STAGE_BASE: i32 = 1;
+            const STAGE_OURS: i32 = 2;
+            const STAGE_THEIRS: i32 = 3;
+            let index = repo.index()?;
+
+            // This check is required because index.get_path() unwraps internally :(
+            check_path_to_repo_path_errors(relative_file_path)?;
+
+            let load_stage = |stage| -> Result<Option<String>> {
+                let Some(entry) = index.get_path(relative_file_path, stage) else {
+                    return Ok(None);
+                };
+                let content = repo.find_blob(entry.id)?.content().to_owned();
+                Ok(Some(String::from_utf8(content)?))
+            };
+            let versions = MergeVersions {
+                base: load_stage(STAGE_BASE)?,
+                ours: load_stage(STAGE_OURS)?,
+                theirs: load_stage(STAGE_THEIRS)?,
+            };
+            if versions.ours.is_none() && versions.theirs.is_none() {
+                return Ok(None);
+            }
+            Ok(Some(versions))
+        }
+
+        match logic(&self.repository, relative_file_path) {
+            Ok(value) => return value,
+            Err(err) => log::error!("Error loading merge versions: {:?}", err),
+        }
+        None
+    }
+
+    fn set_index_text(&self, relative_file_path: &Path, content: Option<String>) -> Result<()> {
+        const STAGE_NORMAL: i32 = 0;
+        const DEFAULT_FILE_MODE: u32 = 0o100644;
+
+        // This check is required because index.get_path() unwraps internally :(
+        check_path_to_repo_path_errors(relative_file_path)?;
+
+        let mut index = self.repository.index()?;
+        match content {
+            Some(content) => {
+                let mode = index
+                    .get_path(relative_file_path, STAGE_NORMAL)
+                    .map_or(DEFAULT_FILE_MODE, |entry| entry.mode);
+                let path = relative_file_path
+                    .components()
+                    .map(|component| component.as_os_str().to_str())
+                    .collect::<Option<Vec<_>>>()
+                    .context("path is not valid UTF-8")?
+                    .join("/");
+                // Leaving the stat fields empty makes git re-check the file's contents the
+                // next time it compares the index with the working copy.
+                let entry = git2::IndexEntry {
+                    ctime: git2::IndexTime::new(0, 0),
+                    mtime: git2::IndexTime::new(0, 0),
+                    dev: 0,
+                    ino: 0,
+                    mode,
+                    uid: 0,
+                    gid: 0,
+                    file_size: content.len() as u32,
+                    id: git2::Oid::zero(),
+                    flags: 0,
+                    flags_extended: 0,
+                    path: path.into_bytes(),
+                };
+                index.add_frombuffer(&entry, content.as_bytes())?;
+            }
+            None => index.remove_path(relative_file_path)?,
+        }
+        index.write()?;
+        Ok(())
+    }
+
     fn remote_url(&self, name: &str) -> Option<String> {
         let remote = self.repository.find_remote(name).ok()?;
         remote.url().map(|url| url.to_string())
@@ -259,6 +397,57 @@ impl GitRepository for RealGitRepository {
             self.hosting_provider_registry.clone(),
         )
     }
+
+    fn file_history(&self, relative_file_path: &Path) -> Result<Vec<FileHistoryEntry>> {
+        let working_directory = self.repository.workdir().with_context(|| {
+            format!("failed to get git working directory for file {relative_file_path:?}")
+        })?;
+
+        crate::file_history::file_history(
+            &self.git_binary_path,
+            working_directory,
+            relative_file_path,
+        )
+    }
+
+    fn load_text_at_revision(
+        &self,
+        sha: &str,
+        relative_file_path: &Path,
+    ) -> Result<Option<String>> {
+        let commit = self.repository.revparse_single(sha)?.peel_to_commit()?;
+        let entry = match commit.tree()?.get_path(relative_file_path) {
+            Ok(entry) => entry,
+            Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let blob = entry.to_object(&self.repository)?.peel_to_blob()?;
+        Ok(Some(String::from_utf8(blob.content().to_owned())?))
+    }
+
+    fn commit(&self, message: &str) -> Result<String> {
+        let signature = self
+            .repository
+            .signature()
+            .context("failed to determine the commit author, is user.name configured?")?;
+        let mut index = self.repository.index()?;
+        let tree = self.repository.find_tree(index.write_tree()?)?;
+        // There is no HEAD commit yet in a repository without commits.
+        let parent = match self.repository.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(error) if error.code() == git2::ErrorCode::UnbornBranch => None,
+            Err(error) => return Err(error.into()),
+        };
+        let oid = self.repository.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parent.iter().collect::<Vec<_>>(),
+        )?;
+        Ok(oid.to_string())
+    }
 }
 
 fn matches_index(repo: &LibGitRepository, path: &RepoPath, mtime: SystemTime) -> bool {
@@ -301,9 +490,15 @@ pub struct FakeGitRepository {
 #[derive(Debug, Clone, Default)]
 pub struct FakeGitRepositoryState {
     pub index_contents: HashMap<PathBuf, String>,
+    pub committed_contents: HashMap<PathBuf, String>,
+    pub merge_versions: HashMap<PathBuf, MergeVersions>,
     pub blames: HashMap<PathBuf, Blame>,
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub branch_name: Option<String>,
+    pub commit_messages: Vec<String>,
+    pub file_histories: HashMap<PathBuf, Vec<FileHistoryEntry>>,
+    /// The contents of files in past commits, keyed by commit SHA and path.
+    pub revision_contents: HashMap<(String, PathBuf), String>,
 }
 
 impl FakeGitRepository {
@@ -320,6 +515,25 @@ impl GitRepository for FakeGitRepository {
         state.index_contents.get(path).cloned()
     }
 
+    fn load_committed_text(&self, path: &Path) -> Option<String> {
+        let state = self.state.lock();
+        state.committed_contents.get(path).cloned()
+    }
+
+    fn load_merge_versions(&self, path: &Path) -> Option<MergeVersions> {
+        let state = self.state.lock();
+        state.merge_versions.get(path).cloned()
+    }
+
+    fn set_index_text(&self, path: &Path, content: Option<String>) -> Result<()> {
+        let mut state = self.state.lock();
+        match content {
+            Some(content) => state.index_contents.insert(path.to_path_buf(), content),
+            None => state.index_contents.remove(path),
+        };
+        Ok(())
+    }
+
     fn remote_url(&self, _name: &str) -> Option<String> {
         None
     }
@@ -377,6 +591,26 @@ impl GitRepository for FakeGitRepository {
             .with_context(|| format!("failed to get blame for {:?}", path))
             .cloned()
     }
+
+    fn file_history(&self, path: &Path) -> Result<Vec<FileHistoryEntry>> {
+        let state = self.state.lock();
+        Ok(state.file_histories.get(path).cloned().unwrap_or_default())
+    }
+
+    fn load_text_at_revision(&self, sha: &str, path: &Path) -> Result<Option<String>> {
+        let state = self.state.lock();
+        Ok(state
+            .revision_contents
+            .get(&(sha.to_owned(), path.to_path_buf()))
+            .cloned())
+    }
+
+    fn commit(&self, message: &str) -> Result<String> {
+        let mut state = self.state.lock();
+        state.committed_contents = state.index_contents.clone();
+        state.commit_messages.push(message.to_owned());
+        Ok(format!("{:040x}", state.commit_messages.len()))
+    }
 }
 
 fn check_path_to_repo_path_errors(relative_file_path: &Path) -> Result<()> {
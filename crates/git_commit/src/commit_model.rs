@@ -0,0 +1,348 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use git::{
+    diff::{BufferDiff, DiffHunk},
+    repository::{GitFileStatus, GitRepository, RepoPath},
+};
+use gpui::{AppContext, Context, EventEmitter, Model, ModelContext, Subscription, Task};
+use language::{Buffer, Rope};
+use parking_lot::Mutex;
+use project::Project;
+
+/// The conventional maximum length of a commit message's subject line.
+pub const SUBJECT_LINE_LIMIT: usize = 50;
+/// The conventional maximum length of the lines in a commit message's body.
+pub const BODY_LINE_LIMIT: usize = 72;
+
+/// A deviation of a commit message from the conventional format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageLint {
+    SubjectTooLong {
+        length: usize,
+    },
+    /// The subject isn't separated from the body by a blank line.
+    MissingBlankLine,
+    BodyLineTooLong {
+        row: u32,
+        length: usize,
+    },
+}
+
+/// A file whose changes in the index will be committed.
+#[derive(Clone, Debug)]
+pub struct StagedFile {
+    pub repo_path: RepoPath,
+    pub status: GitFileStatus,
+    pub diff: FileDiff,
+}
+
+/// The changes to a file between HEAD and the index.
+#[derive(Clone, Debug, Default)]
+pub struct FileDiff {
+    pub head_text: Option<String>,
+    pub index_text: Option<String>,
+    /// The changed rows of the index text, and the byte ranges they replaced in the HEAD text.
+    pub hunks: Vec<DiffHunk<u32>>,
+    pub added_lines: u32,
+    pub removed_lines: u32,
+}
+
+pub enum Event {
+    StagedFilesChanged,
+    Committed { sha: String },
+}
+
+/// The state of a commit being authored: its message, and the staged changes it will contain.
+pub struct CommitModel {
+    repository: Arc<Mutex<dyn GitRepository>>,
+    message_buffer: Model<Buffer>,
+    staged_files: Vec<StagedFile>,
+    committing: bool,
+    _refresh_task: Task<()>,
+    _subscription: Subscription,
+}
+
+impl EventEmitter<Event> for CommitModel {}
+
+impl CommitModel {
+    pub fn new(
+        project: Model<Project>,
+        repository: Arc<Mutex<dyn GitRepository>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Self {
+        let message_buffer = cx.new_model(|cx| Buffer::local("", cx));
+        let subscription = cx.subscribe(&project, |this, _, event, cx| {
+            if let project::Event::WorktreeUpdatedGitRepositories = event {
+                this.refresh(cx);
+            }
+        });
+
+        let mut this = Self {
+            repository,
+            message_buffer,
+            staged_files: Vec::new(),
+            committing: false,
+            _refresh_task: Task::ready(()),
+            _subscription: subscription,
+        };
+        this.refresh(cx);
+        this
+    }
+
+    pub fn message_buffer(&self) -> &Model<Buffer> {
+        &self.message_buffer
+    }
+
+    pub fn staged_files(&self) -> &[StagedFile] {
+        &self.staged_files
+    }
+
+    pub fn is_committing(&self) -> bool {
+        self.committing
+    }
+
+    /// Reloads the staged files and their diffs from the repository.
+    pub fn refresh(&mut self, cx: &mut ModelContext<Self>) {
+        let repository = self.repository.clone();
+        let load_staged_files = cx
+            .background_executor()
+            .spawn(async move { load_staged_files(&repository).await });
+        self._refresh_task = cx.spawn(|this, mut cx| async move {
+            let staged_files = load_staged_files.await;
+            this.update(&mut cx, |this, cx| {
+                this.staged_files = staged_files;
+                cx.emit(Event::StagedFilesChanged);
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
+    /// Returns the ways in which the current message deviates from the conventional format.
+    pub fn message_lints(&self, cx: &AppContext) -> Vec<MessageLint> {
+        message_lints(&self.message_buffer.read(cx).text())
+    }
+
+    /// Commits the staged changes with the current message, clearing it on success.
+    pub fn commit(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<String>> {
+        if self.committing {
+            return Task::ready(Err(anyhow!("a commit is already in progress")));
+        }
+        if self.staged_files.is_empty() {
+            return Task::ready(Err(anyhow!("there are no staged changes to commit")));
+        }
+        let message = clean_message(&self.message_buffer.read(cx).text());
+        if message.is_empty() {
+            return Task::ready(Err(anyhow!("aborting commit due to empty commit message")));
+        }
+
+        self.committing = true;
+        cx.notify();
+        let repository = self.repository.clone();
+        let commit = cx
+            .background_executor()
+            .spawn(async move { repository.lock().commit(&message) });
+        cx.spawn(|this, mut cx| async move {
+            let result = commit.await;
+            this.update(&mut cx, |this, cx| {
+                this.committing = false;
+                if let Ok(sha) = &result {
+                    this.message_buffer
+                        .update(cx, |buffer, cx| buffer.set_text("", cx));
+                    cx.emit(Event::Committed { sha: sha.clone() });
+                    this.refresh(cx);
+                }
+                cx.notify();
+            })?;
+            result
+        })
+    }
+}
+
+async fn load_staged_files(repository: &Mutex<dyn GitRepository>) -> Vec<StagedFile> {
+    let statuses = repository.lock().staged_statuses(Path::new(""));
+    let mut staged_files = Vec::new();
+    for (repo_path, status) in statuses.iter() {
+        let (head_text, index_text) = {
+            let repository = repository.lock();
+            (
+                repository.load_committed_text(repo_path),
+                repository.load_index_text(repo_path),
+            )
+        };
+        let diff = file_diff(head_text, index_text).await;
+        staged_files.push(StagedFile {
+            repo_path: repo_path.clone(),
+            status: *status,
+            diff,
+        });
+    }
+    staged_files
+}
+
+async fn file_diff(head_text: Option<String>, index_text: Option<String>) -> FileDiff {
+    let head_rope = Rope::from(head_text.as_deref().unwrap_or(""));
+    let index_buffer = text::Buffer::new(
+        0,
+        text::BufferId::new(1).unwrap(),
+        index_text.clone().unwrap_or_default(),
+    );
+    let index_snapshot = index_buffer.snapshot();
+    let mut diff = BufferDiff::new();
+    diff.update(&head_rope, &index_snapshot).await;
+
+    let hunks = diff
+        .hunks_in_row_range(0..u32::MAX, &index_snapshot)
+        .collect::<Vec<_>>();
+    let mut added_lines = 0;
+    let mut removed_lines = 0;
+    for hunk in &hunks {
+        added_lines += hunk.associated_range.len() as u32;
+        let removed_start = head_rope.offset_to_point(hunk.diff_base_byte_range.start);
+        let removed_end = head_rope.offset_to_point(hunk.diff_base_byte_range.end);
+        removed_lines += removed_end.row - removed_start.row;
+    }
+
+    FileDiff {
+        head_text,
+        index_text,
+        hunks,
+        added_lines,
+        removed_lines,
+    }
+}
+
+/// Removes comment lines and trailing whitespace from a commit message, as git does by default.
+pub fn clean_message(text: &str) -> String {
+    let lines = text
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(str::trim_end)
+        .collect::<Vec<_>>();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+pub fn message_lints(text: &str) -> Vec<MessageLint> {
+    let mut lints = Vec::new();
+    for (row, line) in text.lines().enumerate() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let length = line.chars().count();
+        match row {
+            0 if length > SUBJECT_LINE_LIMIT => lints.push(MessageLint::SubjectTooLong { length }),
+            1 if length > 0 => lints.push(MessageLint::MissingBlankLine),
+            _ if row > 0 && length > BODY_LINE_LIMIT => lints.push(MessageLint::BodyLineTooLong {
+                row: row as u32,
+                length,
+            }),
+            _ => {}
+        }
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    #[test]
+    fn test_message_lints() {
+        let subject = "x".repeat(SUBJECT_LINE_LIMIT + 1);
+        let body_line = "y".repeat(BODY_LINE_LIMIT + 1);
+        assert_eq!(
+            message_lints(&format!("{subject}\nbody\n\n{body_line}\n# {body_line}")),
+            vec![
+                MessageLint::SubjectTooLong {
+                    length: SUBJECT_LINE_LIMIT + 1
+                },
+                MessageLint::MissingBlankLine,
+                MessageLint::BodyLineTooLong {
+                    row: 3,
+                    length: BODY_LINE_LIMIT + 1
+                },
+            ]
+        );
+        assert_eq!(message_lints("Fix a bug\n\nDetails."), vec![]);
+        assert_eq!(
+            clean_message("\nFix a bug  \n# comment\n\nDetails.\n\n"),
+            "Fix a bug\n\nDetails."
+        );
+    }
+
+    #[gpui::test]
+    async fn test_commit(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings = SettingsStore::test(cx);
+            cx.set_global(settings);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                ".git": {},
+                "a.txt": "one\nTWO\nthree\n",
+            }),
+        )
+        .await;
+        let dot_git = Path::new("/dir/.git");
+        fs.set_head_for_repo(dot_git, &[(Path::new("a.txt"), "one\ntwo\n".into())]);
+        fs.set_index_for_repo(dot_git, &[(Path::new("a.txt"), "one\nTWO\nthree\n".into())]);
+        fs.set_status_for_repo_via_git_operation(
+            dot_git,
+            &[(Path::new("a.txt"), GitFileStatus::Modified)],
+        );
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        cx.run_until_parked();
+
+        let repository = project
+            .read_with(cx, |project, cx| project.get_first_worktree_root_repo(cx))
+            .unwrap();
+        let model = cx.new_model(|cx| CommitModel::new(project, repository, cx));
+        cx.run_until_parked();
+        model.read_with(cx, |model, _| {
+            let [file] = model.staged_files() else {
+                panic!("expected one staged file");
+            };
+            assert_eq!(file.repo_path.as_ref(), Path::new("a.txt"));
+            assert_eq!((file.diff.added_lines, file.diff.removed_lines), (2, 1));
+        });
+
+        let error = model
+            .update(cx, |model, cx| model.commit(cx))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "aborting commit due to empty commit message"
+        );
+
+        model.update(cx, |model, cx| {
+            model.message_buffer().update(cx, |buffer, cx| {
+                buffer.set_text("Change a.txt\n# comment\n", cx)
+            })
+        });
+        model
+            .update(cx, |model, cx| model.commit(cx))
+            .await
+            .unwrap();
+        model.read_with(cx, |model, cx| {
+            assert_eq!(model.message_buffer().read(cx).text(), "");
+        });
+        fs.with_git_state(dot_git, false, |state| {
+            assert_eq!(state.commit_messages, vec!["Change a.txt".to_string()]);
+            assert_eq!(
+                state.committed_contents.get(Path::new("a.txt")).unwrap(),
+                "one\nTWO\nthree\n"
+            );
+        });
+    }
+}
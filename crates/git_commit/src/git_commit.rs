@@ -0,0 +1,197 @@
+mod commit_model;
+
+use anyhow::Context as _;
+use editor::{Editor, EditorEvent};
+use gpui::{
+    actions, AnyElement, AppContext, Context, EventEmitter, FocusHandle, FocusableView,
+    IntoElement, Model, ParentElement, Render, SharedString, Styled, Subscription, View,
+    ViewContext, VisualContext, WindowContext,
+};
+use project::Project;
+use ui::{prelude::*, Button, Label};
+use workspace::{
+    item::{Item, ItemEvent, TabContentParams},
+    notifications::{NotifyResultExt, NotifyTaskExt},
+    Workspace,
+};
+
+pub use commit_model::*;
+
+actions!(git_commit, [OpenCommitView, Commit]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(CommitView::register).detach();
+}
+
+/// A workspace item for writing a commit message and committing the staged changes.
+pub struct CommitView {
+    model: Model<CommitModel>,
+    message_editor: View<Editor>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl CommitView {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(|workspace, _: &OpenCommitView, cx| {
+            if let Some(existing) = workspace.item_of_type::<CommitView>(cx) {
+                workspace.activate_item(&existing, cx);
+                return;
+            }
+            let project = workspace.project().clone();
+            let Some(repository) = project
+                .read(cx)
+                .get_first_worktree_root_repo(cx)
+                .context("failed to get root repository for first worktree")
+                .notify_err(workspace, cx)
+            else {
+                return;
+            };
+            let model = cx.new_model(|cx| CommitModel::new(project.clone(), repository, cx));
+            let view = cx.new_view(|cx| CommitView::new(model, project, cx));
+            workspace.add_item_to_active_pane(Box::new(view), None, cx);
+        });
+    }
+
+    pub fn new(
+        model: Model<CommitModel>,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let message_buffer = model.read(cx).message_buffer().clone();
+        let message_editor = cx.new_view(|cx| {
+            let mut editor = Editor::for_buffer(message_buffer, Some(project), cx);
+            editor.set_placeholder_text("Commit message", cx);
+            editor.set_show_gutter(false, cx);
+            editor.set_wrap_guides(vec![SUBJECT_LINE_LIMIT, BODY_LINE_LIMIT], cx);
+            editor
+        });
+        let subscriptions = vec![
+            cx.observe(&model, |_, _, cx| cx.notify()),
+            cx.subscribe(&message_editor, |_, _, event: &EditorEvent, cx| {
+                if let EditorEvent::BufferEdited = event {
+                    cx.notify();
+                }
+            }),
+        ];
+
+        Self {
+            model,
+            message_editor,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn commit(&mut self, _: &Commit, cx: &mut ViewContext<Self>) {
+        let commit = self.model.update(cx, |model, cx| model.commit(cx));
+        cx.spawn(|_, _| async move { commit.await.map(|_| ()) })
+            .detach_and_notify_err(cx);
+    }
+
+    fn render_lints(&self, cx: &ViewContext<Self>) -> impl IntoElement {
+        let lints = self.model.read(cx).message_lints(cx);
+        v_flex().children(lints.into_iter().map(|lint| {
+            let message = match lint {
+                MessageLint::SubjectTooLong { length } => format!(
+                    "The subject line is {length} characters long, consider keeping it under {SUBJECT_LINE_LIMIT}"
+                ),
+                MessageLint::MissingBlankLine => {
+                    "Separate the subject from the body with a blank line".to_string()
+                }
+                MessageLint::BodyLineTooLong { row, length } => format!(
+                    "Line {} is {length} characters long, consider wrapping it at {BODY_LINE_LIMIT}",
+                    row + 1
+                ),
+            };
+            Label::new(message)
+                .size(LabelSize::Small)
+                .color(Color::Warning)
+        }))
+    }
+
+    fn render_staged_files(&self, cx: &ViewContext<Self>) -> impl IntoElement {
+        let staged_files = self.model.read(cx).staged_files();
+        v_flex()
+            .gap_1()
+            .child(
+                Label::new(format!("Staged changes ({})", staged_files.len()))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .children(staged_files.iter().map(|file| {
+                let status = match file.status {
+                    git::repository::GitFileStatus::Added => ("A", Color::Created),
+                    git::repository::GitFileStatus::Modified => ("M", Color::Modified),
+                    git::repository::GitFileStatus::Conflict => ("C", Color::Conflict),
+                };
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(status.0).color(status.1))
+                    .child(Label::new(file.repo_path.to_string_lossy().to_string()))
+                    .child(
+                        Label::new(format!("+{}", file.diff.added_lines))
+                            .size(LabelSize::Small)
+                            .color(Color::Created),
+                    )
+                    .child(
+                        Label::new(format!("-{}", file.diff.removed_lines))
+                            .size(LabelSize::Small)
+                            .color(Color::Deleted),
+                    )
+            }))
+    }
+}
+
+impl EventEmitter<ItemEvent> for CommitView {}
+
+impl FocusableView for CommitView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.message_editor.focus_handle(cx)
+    }
+}
+
+impl Item for CommitView {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn tab_content(&self, params: TabContentParams, _cx: &WindowContext) -> AnyElement {
+        Label::new(SharedString::from("Commit"))
+            .single_line()
+            .color(if params.selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("commit view")
+    }
+}
+
+impl Render for CommitView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let model = self.model.read(cx);
+        let can_commit = !model.is_committing() && !model.staged_files().is_empty();
+
+        v_flex()
+            .key_context("CommitView")
+            .on_action(cx.listener(Self::commit))
+            .size_full()
+            .p_2()
+            .gap_2()
+            .child(div().h_40().child(self.message_editor.clone()))
+            .child(self.render_lints(cx))
+            .child(
+                h_flex().justify_end().child(
+                    Button::new("commit", "Commit")
+                        .disabled(!can_commit)
+                        .on_click(cx.listener(|this, _, cx| this.commit(&Commit, cx))),
+                ),
+            )
+            .child(self.render_staged_files(cx))
+    }
+}
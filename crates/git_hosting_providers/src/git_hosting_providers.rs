@@ -1,11 +1,16 @@
 mod providers;
+mod settings;
 
 use std::sync::Arc;
 
+use ::settings::{Settings, SettingsStore};
 use git::GitHostingProviderRegistry;
 use gpui::AppContext;
+use url::Url;
+use util::ResultExt;
 
 pub use crate::providers::*;
+pub use crate::settings::*;
 
 /// Initializes the Git hosting providers.
 pub fn init(cx: &mut AppContext) {
@@ -23,4 +28,50 @@ pub fn init(cx: &mut AppContext) {
     provider_registry.register_hosting_provider(Arc::new(Bitbucket));
     provider_registry.register_hosting_provider(Arc::new(Sourcehut));
     provider_registry.register_hosting_provider(Arc::new(Codeberg));
+
+    GitHostingProviderSettings::register(cx);
+
+    let mut self_hosted_providers = Vec::new();
+    register_self_hosted_providers(&provider_registry, &mut self_hosted_providers, cx);
+    cx.observe_global::<SettingsStore>(move |cx| {
+        register_self_hosted_providers(&provider_registry, &mut self_hosted_providers, cx);
+    })
+    .detach();
+}
+
+/// Replaces the previously registered self-hosted providers with the ones currently
+/// configured in the settings.
+fn register_self_hosted_providers(
+    provider_registry: &GitHostingProviderRegistry,
+    registered: &mut Vec<GitHostingProviderConfig>,
+    cx: &mut AppContext,
+) {
+    let configs = &GitHostingProviderSettings::get_global(cx).git_hosting_providers;
+    if configs == registered {
+        return;
+    }
+
+    for config in registered.drain(..) {
+        provider_registry.unregister_hosting_provider(&config.name);
+    }
+
+    for config in configs {
+        let Some(base_url) = Url::parse(&config.base_url)
+            .map_err(|error| {
+                anyhow::anyhow!(
+                    "invalid base URL for Git hosting provider {:?}: {error}",
+                    config.name
+                )
+            })
+            .log_err()
+        else {
+            continue;
+        };
+        provider_registry.register_hosting_provider(Arc::new(SelfHosted::new(
+            config.name.clone(),
+            config.provider,
+            base_url,
+        )));
+    }
+    registered.clone_from(configs);
 }
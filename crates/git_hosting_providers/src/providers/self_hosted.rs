@@ -0,0 +1,229 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use git::{BuildCommitPermalinkParams, BuildPermalinkParams, GitHostingProvider, ParsedGitRemote};
+
+/// The kind of software a self-hosted Git server runs, which determines the shape of its URLs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfHostedKind {
+    Github,
+    Gitlab,
+    Sourcehut,
+}
+
+/// A Git hosting provider running on a custom host, such as GitHub Enterprise or a
+/// self-managed GitLab instance.
+pub struct SelfHosted {
+    name: String,
+    kind: SelfHostedKind,
+    base_url: Url,
+}
+
+impl SelfHosted {
+    pub fn new(name: impl Into<String>, kind: SelfHostedKind, mut base_url: Url) -> Self {
+        // Ensure that joining paths onto the base URL keeps any path prefix it has.
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        Self {
+            name: name.into(),
+            kind,
+            base_url,
+        }
+    }
+
+    /// Returns the prefixes that remote URLs pointing at this host start with.
+    fn remote_url_prefixes(&self) -> Option<[String; 3]> {
+        let host = self.base_url.host_str()?;
+        let path = self.base_url.path();
+
+        Some([
+            format!("git@{host}:"),
+            format!("ssh://git@{host}/"),
+            format!("{}://{host}{path}", self.base_url.scheme()),
+        ])
+    }
+}
+
+impl GitHostingProvider for SelfHosted {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn base_url(&self) -> Url {
+        self.base_url.clone()
+    }
+
+    fn supports_avatars(&self) -> bool {
+        false
+    }
+
+    fn format_line_number(&self, line: u32) -> String {
+        format!("L{line}")
+    }
+
+    fn format_line_numbers(&self, start_line: u32, end_line: u32) -> String {
+        match self.kind {
+            SelfHostedKind::Github => format!("L{start_line}-L{end_line}"),
+            SelfHostedKind::Gitlab | SelfHostedKind::Sourcehut => {
+                format!("L{start_line}-{end_line}")
+            }
+        }
+    }
+
+    fn parse_remote_url<'a>(&self, url: &'a str) -> Option<ParsedGitRemote<'a>> {
+        let repo_with_owner = self
+            .remote_url_prefixes()?
+            .iter()
+            .find_map(|prefix| url.strip_prefix(prefix.as_str()))?;
+
+        let repo_with_owner = match self.kind {
+            SelfHostedKind::Github | SelfHostedKind::Gitlab => {
+                repo_with_owner.trim_end_matches(".git")
+            }
+            // sourcehut treats a repo with a '.git' suffix as a separate repo.
+            SelfHostedKind::Sourcehut => repo_with_owner.trim_start_matches('~'),
+        };
+
+        // GitLab groups can be nested, in which case everything up to the repo is the owner.
+        let (owner, repo) = repo_with_owner.rsplit_once('/')?;
+        if owner.is_empty() || repo.is_empty() {
+            return None;
+        }
+
+        Some(ParsedGitRemote { owner, repo })
+    }
+
+    fn build_commit_permalink(
+        &self,
+        remote: &ParsedGitRemote,
+        params: BuildCommitPermalinkParams,
+    ) -> Url {
+        let BuildCommitPermalinkParams { sha } = params;
+        let ParsedGitRemote { owner, repo } = remote;
+
+        let path = match self.kind {
+            SelfHostedKind::Github => format!("{owner}/{repo}/commit/{sha}"),
+            SelfHostedKind::Gitlab => format!("{owner}/{repo}/-/commit/{sha}"),
+            SelfHostedKind::Sourcehut => format!("~{owner}/{repo}/commit/{sha}"),
+        };
+        self.base_url().join(&path).unwrap()
+    }
+
+    fn build_permalink(&self, remote: ParsedGitRemote, params: BuildPermalinkParams) -> Url {
+        let ParsedGitRemote { owner, repo } = remote;
+        let BuildPermalinkParams {
+            sha,
+            path,
+            selection,
+        } = params;
+
+        let path = match self.kind {
+            SelfHostedKind::Github => format!("{owner}/{repo}/blob/{sha}/{path}"),
+            SelfHostedKind::Gitlab => format!("{owner}/{repo}/-/blob/{sha}/{path}"),
+            SelfHostedKind::Sourcehut => format!("~{owner}/{repo}/tree/{sha}/item/{path}"),
+        };
+        let mut permalink = self.base_url().join(&path).unwrap();
+        permalink.set_fragment(
+            selection
+                .map(|selection| self.line_fragment(&selection))
+                .as_deref(),
+        );
+        permalink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(kind: SelfHostedKind, base_url: &str) -> SelfHosted {
+        SelfHosted::new("Example", kind, Url::parse(base_url).unwrap())
+    }
+
+    #[test]
+    fn test_parse_remote_url() {
+        let gitlab = provider(SelfHostedKind::Gitlab, "https://git.example.com");
+
+        let remote = gitlab
+            .parse_remote_url("git@git.example.com:group/subgroup/project.git")
+            .unwrap();
+        assert_eq!((remote.owner, remote.repo), ("group/subgroup", "project"));
+
+        let remote = gitlab
+            .parse_remote_url("https://git.example.com/group/project.git")
+            .unwrap();
+        assert_eq!((remote.owner, remote.repo), ("group", "project"));
+
+        let remote = gitlab
+            .parse_remote_url("ssh://git@git.example.com/group/project")
+            .unwrap();
+        assert_eq!((remote.owner, remote.repo), ("group", "project"));
+
+        assert!(gitlab
+            .parse_remote_url("git@gitlab.com:group/project.git")
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_remote_url_with_base_path() {
+        let github = provider(SelfHostedKind::Github, "https://example.com/git");
+
+        let remote = github
+            .parse_remote_url("https://example.com/git/owner/repo.git")
+            .unwrap();
+        assert_eq!((remote.owner, remote.repo), ("owner", "repo"));
+
+        let permalink = github.build_permalink(
+            remote,
+            BuildPermalinkParams {
+                sha: "b2efec9824c45fcc90c9a7eb107a50d1772a60aa",
+                path: "src/main.rs",
+                selection: Some(23..47),
+            },
+        );
+        assert_eq!(
+            permalink.to_string(),
+            "https://example.com/git/owner/repo/blob/b2efec9824c45fcc90c9a7eb107a50d1772a60aa/src/main.rs#L24-L48"
+        );
+    }
+
+    #[test]
+    fn test_build_permalink() {
+        let remote = || ParsedGitRemote {
+            owner: "zed-industries",
+            repo: "zed",
+        };
+        let params = || BuildPermalinkParams {
+            sha: "e6ebe7974deb6bb6cc0e2595c8ec31f0c71084b7",
+            path: "crates/zed/src/main.rs",
+            selection: Some(6..9),
+        };
+
+        let gitlab = provider(SelfHostedKind::Gitlab, "https://git.example.com");
+        assert_eq!(
+            gitlab.build_permalink(remote(), params()).to_string(),
+            "https://git.example.com/zed-industries/zed/-/blob/e6ebe7974deb6bb6cc0e2595c8ec31f0c71084b7/crates/zed/src/main.rs#L7-10"
+        );
+
+        let sourcehut = provider(SelfHostedKind::Sourcehut, "https://git.example.com");
+        assert_eq!(
+            sourcehut.build_permalink(remote(), params()).to_string(),
+            "https://git.example.com/~zed-industries/zed/tree/e6ebe7974deb6bb6cc0e2595c8ec31f0c71084b7/item/crates/zed/src/main.rs#L7-10"
+        );
+        assert_eq!(
+            sourcehut
+                .build_commit_permalink(
+                    &remote(),
+                    BuildCommitPermalinkParams {
+                        sha: "e6ebe7974deb6bb6cc0e2595c8ec31f0c71084b7"
+                    }
+                )
+                .to_string(),
+            "https://git.example.com/~zed-industries/zed/commit/e6ebe7974deb6bb6cc0e2595c8ec31f0c71084b7"
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+
+use crate::SelfHostedKind;
+
+#[derive(Serialize, Deserialize, PartialEq, Default, Clone, JsonSchema)]
+pub struct GitHostingProviderSettings {
+    /// Additional Git hosting providers, used for building permalinks to remotes on
+    /// self-hosted servers.
+    ///
+    /// Default: []
+    #[serde(default)]
+    pub git_hosting_providers: Vec<GitHostingProviderConfig>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, JsonSchema)]
+pub struct GitHostingProviderConfig {
+    /// The software the server runs, which determines the shape of its URLs.
+    pub provider: SelfHostedKind,
+    /// The URL that the server's web interface is served from, e.g. "https://git.example.com".
+    pub base_url: String,
+    /// The name shown for the provider.
+    pub name: String,
+}
+
+impl Settings for GitHostingProviderSettings {
+    const KEY: Option<&'static str> = None;
+
+    type FileContent = Self;
+
+    fn load(
+        sources: SettingsSources<Self::FileContent>,
+        _: &mut gpui::AppContext,
+    ) -> gpui::Result<Self> {
+        sources.json_merge()
+    }
+}
@@ -643,6 +643,22 @@ impl AppContext {
         self.platform.reveal_path(path)
     }
 
+    /// Opens the specified path with the platform's default application for it.
+    pub fn open_with_system(&self, path: &Path) {
+        self.platform.open_with_system(path)
+    }
+
+    /// Returns whether the platform supports [`Self::write_files_to_clipboard`].
+    pub fn can_write_files_to_clipboard(&self) -> bool {
+        self.platform.can_write_files_to_clipboard()
+    }
+
+    /// Writes the specified files themselves to the platform clipboard, so that they can be
+    /// pasted into the platform's file manager. Fails on platforms that don't support it.
+    pub fn write_files_to_clipboard(&self, paths: &[PathBuf]) -> Result<()> {
+        self.platform.write_files_to_clipboard(paths)
+    }
+
     /// Returns whether the user has configured scrollbars to auto-hide at the platform level.
     pub fn should_auto_hide_scrollbars(&self) -> bool {
         self.platform.should_auto_hide_scrollbars()
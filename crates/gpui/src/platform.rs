@@ -40,7 +40,7 @@ use seahash::SeaHasher;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::hash::{Hash, Hasher};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{
     fmt::{self, Debug},
     ops::Range,
@@ -130,6 +130,7 @@ pub(crate) trait Platform: 'static {
     ) -> oneshot::Receiver<Option<Vec<PathBuf>>>;
     fn prompt_for_new_path(&self, directory: &Path) -> oneshot::Receiver<Option<PathBuf>>;
     fn reveal_path(&self, path: &Path);
+    fn open_with_system(&self, path: &Path);
 
     fn on_quit(&self, callback: Box<dyn FnMut()>);
     fn on_reopen(&self, callback: Box<dyn FnMut()>);
@@ -157,6 +158,14 @@ pub(crate) trait Platform: 'static {
     #[cfg(target_os = "linux")]
     fn read_from_primary(&self) -> Option<ClipboardItem>;
     fn read_from_clipboard(&self) -> Option<ClipboardItem>;
+    fn can_write_files_to_clipboard(&self) -> bool {
+        false
+    }
+    fn write_files_to_clipboard(&self, _paths: &[PathBuf]) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "copying files to the clipboard isn't supported on this platform"
+        ))
+    }
 
     fn write_credentials(&self, url: &str, username: &str, password: &[u8]) -> Task<Result<()>>;
     fn read_credentials(&self, url: &str) -> Task<Result<Option<(String, Vec<u8>)>>>;
@@ -262,6 +271,11 @@ pub trait PlatformDispatcher: Send + Sync {
     fn park(&self, timeout: Option<Duration>) -> bool;
     fn unparker(&self) -> Unparker;
 
+    /// The current time, which only advances along with the virtual clock in tests.
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     fn as_test(&self) -> Option<&TestDispatcher> {
         None
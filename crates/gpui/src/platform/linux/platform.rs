@@ -330,6 +330,10 @@ impl<P: LinuxClient + 'static> Platform for P {
         open::that_detached(dir);
     }
 
+    fn open_with_system(&self, path: &Path) {
+        open::that_detached(path).log_err();
+    }
+
     fn on_quit(&self, callback: Box<dyn FnMut()>) {
         self.with_common(|common| {
             common.callbacks.quit = Some(callback);
@@ -685,6 +685,17 @@ impl Platform for MacPlatform {
         }
     }
 
+    fn open_with_system(&self, path: &Path) {
+        let Some(path) = path.to_str() else {
+            return;
+        };
+        unsafe {
+            let url = NSURL::fileURLWithPath_(nil, ns_string(path));
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let _: BOOL = msg_send![workspace, openURL: url];
+        }
+    }
+
     fn on_quit(&self, callback: Box<dyn FnMut()>) {
         self.0.lock().quit = Some(callback);
     }
@@ -894,6 +905,32 @@ impl Platform for MacPlatform {
         }
     }
 
+    fn can_write_files_to_clipboard(&self) -> bool {
+        true
+    }
+
+    fn write_files_to_clipboard(&self, paths: &[PathBuf]) -> anyhow::Result<()> {
+        let state = self.0.lock();
+        unsafe {
+            let urls = paths
+                .iter()
+                .map(|path| {
+                    let path = path
+                        .to_str()
+                        .ok_or_else(|| anyhow!("invalid path {path:?}"))?;
+                    Ok(NSURL::fileURLWithPath_(nil, ns_string(path)))
+                })
+                .collect::<anyhow::Result<Vec<id>>>()?;
+            state.pasteboard.clearContents();
+            let urls = NSArray::arrayWithObjects(nil, &urls);
+            let written: BOOL = msg_send![state.pasteboard, writeObjects: urls];
+            if written != YES {
+                return Err(anyhow!("failed to write files to the pasteboard"));
+            }
+        }
+        Ok(())
+    }
+
     fn read_from_clipboard(&self) -> Option<ClipboardItem> {
         let state = self.0.lock();
         unsafe {
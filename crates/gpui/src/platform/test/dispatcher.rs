@@ -11,7 +11,7 @@ use std::{
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use util::post_inc;
 
@@ -32,6 +32,8 @@ struct TestDispatcherState {
     background: Vec<Runnable>,
     deprioritized_background: Vec<Runnable>,
     delayed: Vec<(Duration, Runnable)>,
+    /// The instant at which the virtual clock started, which `time` is relative to.
+    start_time: Instant,
     time: Duration,
     is_main_thread: bool,
     next_id: TestDispatcherId,
@@ -51,6 +53,7 @@ impl TestDispatcher {
             background: Vec::new(),
             deprioritized_background: Vec::new(),
             delayed: Vec::new(),
+            start_time: Instant::now(),
             time: Duration::ZERO,
             is_main_thread: true,
             next_id: TestDispatcherId(1),
@@ -283,6 +286,11 @@ impl PlatformDispatcher for TestDispatcher {
         };
         state.delayed.insert(ix, (next_time, runnable));
     }
+    fn now(&self) -> Instant {
+        let state = self.state.lock();
+        state.start_time + state.time
+    }
+
     fn park(&self, _: Option<std::time::Duration>) -> bool {
         self.parker.lock().park();
         true
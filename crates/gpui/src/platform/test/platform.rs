@@ -232,6 +232,10 @@ impl Platform for TestPlatform {
         unimplemented!()
     }
 
+    fn open_with_system(&self, _path: &std::path::Path) {
+        unimplemented!()
+    }
+
     fn on_quit(&self, _callback: Box<dyn FnMut()>) {}
 
     fn on_reopen(&self, _callback: Box<dyn FnMut()>) {
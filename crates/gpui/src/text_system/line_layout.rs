@@ -91,16 +91,33 @@ impl LineLayout {
         self.len
     }
 
-    /// The x position of the character at the given index
+    /// The x position of the character at the given index. Right-to-left glyphs are laid out
+    /// in the reverse order of their indices, so the position of their character is at their
+    /// right edge.
     pub fn x_for_index(&self, index: usize) -> Pixels {
-        for run in &self.runs {
-            for glyph in &run.glyphs {
-                if glyph.index >= index {
-                    return glyph.position.x;
+        let mut glyphs = self.runs.iter().flat_map(|run| &run.glyphs).peekable();
+        let mut previous_index = None;
+        let mut closest: Option<(usize, Pixels)> = None;
+        while let Some(glyph) = glyphs.next() {
+            let next = glyphs.peek().copied();
+            if glyph.index >= index
+                && closest.map_or(true, |(closest_index, _)| glyph.index < closest_index)
+            {
+                let is_rtl = next.map_or(false, |next| next.index < glyph.index)
+                    || previous_index.map_or(false, |previous| previous > glyph.index);
+                let x = if is_rtl {
+                    next.map_or(self.width, |next| next.position.x)
+                } else {
+                    glyph.position.x
+                };
+                if glyph.index == index {
+                    return x;
                 }
+                closest = Some((glyph.index, x));
             }
+            previous_index = Some(glyph.index);
         }
-        self.width
+        closest.map_or(self.width, |(_, x)| x)
     }
 
     /// The corresponding Font at the given index
@@ -114,6 +114,8 @@ pub struct Buffer {
     completion_triggers: Vec<String>,
     completion_triggers_timestamp: clock::Lamport,
     deferred_ops: OperationQueue<Operation>,
+    /// The operations that weren't sent to collaborators yet, while they're held back.
+    held_ops: Option<Vec<Operation>>,
     capability: Capability,
     has_conflict: bool,
     diff_base_version: usize,
@@ -535,6 +537,17 @@ pub struct Runnable {
     pub buffer: BufferId,
 }
 
+/// A pair of matching brackets, along with how deeply it is nested within other pairs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BracketMatch {
+    /// The range of the opening bracket.
+    pub open_range: Range<usize>,
+    /// The range of the closing bracket.
+    pub close_range: Range<usize>,
+    /// The number of bracket pairs enclosing this one. Outermost pairs have a depth of zero.
+    pub depth: usize,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct IndentGuide {
     pub buffer_id: BufferId,
@@ -751,6 +764,7 @@ impl Buffer {
             completion_triggers: Default::default(),
             completion_triggers_timestamp: Default::default(),
             deferred_ops: OperationQueue::new(),
+            held_ops: None,
             has_conflict: false,
         }
     }
@@ -779,6 +793,32 @@ impl Buffer {
         }
     }
 
+    /// Parses the given text without creating a buffer, to read the syntax of files that
+    /// aren't open. Parsing is synchronous, so this should be called on a background thread.
+    pub fn build_snapshot(
+        text: String,
+        language: Arc<Language>,
+        language_registry: Option<Arc<LanguageRegistry>>,
+    ) -> BufferSnapshot {
+        let text = TextBuffer::new(0, BufferId::new(1).unwrap(), text).snapshot();
+        let mut syntax = SyntaxMap::new().snapshot();
+        syntax.reparse(&text, language_registry, language.clone());
+        BufferSnapshot {
+            text,
+            syntax,
+            git_diff: git::diff::BufferDiff::new(),
+            file: None,
+            remote_selections: Default::default(),
+            diagnostics: Default::default(),
+            diagnostics_update_count: 0,
+            file_update_count: 0,
+            git_diff_update_count: 0,
+            language: Some(language),
+            parse_count: 0,
+            selections_update_count: 0,
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn as_text_snapshot(&self) -> &text::BufferSnapshot {
         &self.text
@@ -2041,7 +2081,29 @@ impl Buffer {
     }
 
     fn send_operation(&mut self, operation: Operation, cx: &mut ModelContext<Self>) {
-        cx.emit(Event::Operation(operation));
+        if let Some(held_ops) = &mut self.held_ops {
+            held_ops.push(operation);
+        } else {
+            cx.emit(Event::Operation(operation));
+        }
+    }
+
+    /// Holds back the operations that would be sent to collaborators, until they're released
+    /// with [`Buffer::release_held_operations`]. Used for edits that aren't final yet, such as
+    /// the text being composed by an input method.
+    pub fn hold_operations(&mut self) {
+        self.held_ops.get_or_insert_with(Vec::new);
+    }
+
+    /// Sends the operations that were held back, in order, and stops holding them.
+    pub fn release_held_operations(&mut self, cx: &mut ModelContext<Self>) {
+        for operation in self.held_ops.take().into_iter().flatten() {
+            cx.emit(Event::Operation(operation));
+        }
+    }
+
+    pub fn is_holding_operations(&self) -> bool {
+        self.held_ops.is_some()
     }
 
     /// Removes the selections for a given peer.
@@ -3006,6 +3068,42 @@ impl BufferSnapshot {
         result
     }
 
+    /// Returns the bracket pairs overlapping or adjacent to `range`, ordered by the position
+    /// of their opening bracket, along with their nesting depth.
+    ///
+    /// Pairs enclosing `range` always overlap it, so the depths are correct even when the
+    /// outer pairs start before `range` does.
+    pub fn bracket_matches<T: ToOffset>(&self, range: Range<T>) -> Vec<BracketMatch> {
+        let mut pairs = self.bracket_ranges(range).collect::<Vec<_>>();
+        pairs.sort_by(|(open_a, close_a), (open_b, close_b)| {
+            open_a
+                .start
+                .cmp(&open_b.start)
+                .then_with(|| close_b.end.cmp(&close_a.end))
+        });
+        pairs.dedup();
+
+        let mut enclosing_ends: Vec<usize> = Vec::new();
+        pairs
+            .into_iter()
+            .map(|(open_range, close_range)| {
+                while enclosing_ends
+                    .last()
+                    .map_or(false, |&end| end <= open_range.start)
+                {
+                    enclosing_ends.pop();
+                }
+                let depth = enclosing_ends.len();
+                enclosing_ends.push(close_range.end);
+                BracketMatch {
+                    open_range,
+                    close_range,
+                    depth,
+                }
+            })
+            .collect()
+    }
+
     /// Returns anchor ranges for any matches of the redaction query.
     /// The buffer can be associated with multiple languages, and the redaction query associated with each
     /// will be run on the relevant section of the buffer.
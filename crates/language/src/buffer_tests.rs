@@ -153,6 +153,7 @@ async fn test_first_line_pattern(cx: &mut TestAppContext) {
         matcher: LanguageMatcher {
             path_suffixes: vec!["js".into()],
             first_line_pattern: Some(Regex::new(r"\bnode\b").unwrap()),
+            ..Default::default()
         },
         ..Default::default()
     });
@@ -177,6 +178,82 @@ async fn test_first_line_pattern(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_modeline(cx: &mut TestAppContext) {
+    cx.update(|cx| init_settings(cx, |_| {}));
+
+    let languages = Arc::new(LanguageRegistry::test(cx.executor()));
+    for config in [
+        LanguageConfig {
+            name: "Python".into(),
+            matcher: LanguageMatcher {
+                path_suffixes: vec!["py".into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        LanguageConfig {
+            name: "Shell Script".into(),
+            matcher: LanguageMatcher {
+                path_suffixes: vec!["sh".into()],
+                modeline_aliases: vec!["shell-script".into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    ] {
+        languages.register_test_language(config);
+    }
+
+    let language_name = |content: &str, cx: &mut TestAppContext| {
+        let content = content.into();
+        let language =
+            cx.read(|cx| languages.language_for_file(&file("a/file.txt"), Some(&content), cx));
+        async move {
+            language
+                .await
+                .ok()
+                .map(|language| language.name().to_string())
+        }
+    };
+
+    assert_eq!(language_name("plain text", cx).await, None);
+    assert_eq!(
+        language_name("# vim: set ft=python ts=4:\n", cx)
+            .await
+            .as_deref(),
+        Some("Python")
+    );
+    assert_eq!(
+        language_name(
+            "#!/usr/bin/env foo\n# -*- mode: shell-script; coding: utf-8 -*-\n",
+            cx
+        )
+        .await
+        .as_deref(),
+        Some("Shell Script")
+    );
+    assert_eq!(
+        language_name(&format!("{}# vi:ft=sh\n", "line\n".repeat(20)), cx)
+            .await
+            .as_deref(),
+        Some("Shell Script")
+    );
+    // Modelines in the middle of a file are ignored.
+    assert_eq!(
+        language_name(
+            &format!(
+                "{}# -*- python -*-\n{}",
+                "line\n".repeat(10),
+                "line\n".repeat(10)
+            ),
+            cx
+        )
+        .await,
+        None
+    );
+}
+
 #[gpui::test]
 async fn test_language_for_file_with_custom_file_types(cx: &mut TestAppContext) {
     cx.update(|cx| {
@@ -862,6 +939,49 @@ async fn test_symbols_containing(cx: &mut gpui::TestAppContext) {
     }
 }
 
+#[gpui::test]
+fn test_bracket_matches(cx: &mut AppContext) {
+    let text = indoc! {"
+        mod x {
+            mod y {
+                fn z() {}
+            }
+            mod w {}
+        }"};
+    let buffer =
+        cx.new_model(|cx| Buffer::local(text, cx).with_language(Arc::new(rust_lang()), cx));
+    let snapshot = buffer.read(cx).snapshot();
+
+    let depths = |range: Range<Point>| {
+        snapshot
+            .bracket_matches(range)
+            .into_iter()
+            .map(|bracket_match| {
+                (
+                    bracket_match.open_range.start.to_point(&snapshot),
+                    bracket_match.depth,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        depths(Point::zero()..snapshot.max_point()),
+        vec![
+            (Point::new(0, 6), 0),
+            (Point::new(1, 10), 1),
+            (Point::new(2, 15), 2),
+            (Point::new(4, 10), 1),
+        ]
+    );
+
+    // Enclosing pairs that start before the range still contribute to the depth.
+    assert_eq!(
+        depths(Point::new(2, 0)..Point::new(2, 0)),
+        vec![(Point::new(0, 6), 0), (Point::new(1, 10), 1)]
+    );
+}
+
 #[gpui::test]
 fn test_enclosing_bracket_ranges(cx: &mut AppContext) {
     let mut assert = |selection_text, range_markers| {
@@ -1010,6 +1130,105 @@ fn test_enclosing_bracket_ranges_where_brackets_are_not_outermost_children(cx: &
     );
 }
 
+#[gpui::test]
+fn test_elastic_tabstops(cx: &mut AppContext) {
+    let buffer = cx.new_model(|cx| Buffer::local("a\tbb\tc\nlonger\tb\n\nx\ty\n", cx));
+    let mut elastic_tabstops = ElasticTabstops::new(&buffer.read(cx).text_snapshot(), 4);
+    assert_eq!(
+        elastic_tabstops.blocks(),
+        &[
+            TabstopBlock {
+                rows: 0..2,
+                tabstops: vec![vec![7, 11], vec![7]],
+            },
+            TabstopBlock {
+                rows: 3..4,
+                tabstops: vec![vec![4]],
+            },
+        ]
+    );
+    assert_eq!(
+        elastic_tabstops.alignment_guides_in_range(1..5),
+        vec![
+            AlignmentGuide {
+                rows: 1..2,
+                column: 7,
+                tab_index: 0,
+            },
+            AlignmentGuide {
+                rows: 3..4,
+                column: 4,
+                tab_index: 0,
+            },
+        ]
+    );
+
+    // Adding a tab to the empty line joins the blocks around it.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(Point::new(2, 0)..Point::new(2, 0), "\tz")], None, cx)
+    });
+    elastic_tabstops.sync(&buffer.read(cx).text_snapshot());
+    assert_eq!(
+        elastic_tabstops.blocks(),
+        &[TabstopBlock {
+            rows: 0..4,
+            tabstops: vec![vec![7, 11], vec![7], vec![7], vec![7]],
+        }]
+    );
+    assert_eq!(elastic_tabstops.tabstops_for_row(2), &[7]);
+    assert_eq!(elastic_tabstops.tabstops_for_row(4), &[] as &[u32]);
+
+    // Removing the longest cell narrows its column.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(Point::new(1, 0)..Point::new(1, 6), "")], None, cx)
+    });
+    elastic_tabstops.sync(&buffer.read(cx).text_snapshot());
+    assert_eq!(
+        elastic_tabstops.blocks(),
+        &[TabstopBlock {
+            rows: 0..4,
+            tabstops: vec![vec![4, 8], vec![4], vec![4], vec![4]],
+        }]
+    );
+}
+
+#[gpui::test(iterations = 100)]
+fn test_random_elastic_tabstops(cx: &mut AppContext, mut rng: StdRng) {
+    let buffer = cx.new_model(|cx| Buffer::local("a\tb\n\tc\nd\n", cx));
+    let mut elastic_tabstops = ElasticTabstops::new(&buffer.read(cx).text_snapshot(), 4);
+    for _ in 0..10 {
+        buffer.update(cx, |buffer, cx| {
+            let mut edits = Vec::new();
+            let mut start = 0;
+            for _ in 0..rng.gen_range(1..=3) {
+                if start > buffer.len() {
+                    break;
+                }
+                let range_start = rng.gen_range(start..=buffer.len());
+                let range_end = rng.gen_range(range_start..=buffer.len().min(range_start + 4));
+                let text = (0..rng.gen_range(0..4))
+                    .map(|_| ["x", "yyyyy", "\t", "\n"][rng.gen_range(0..4)])
+                    .collect::<String>();
+                edits.push((range_start..range_end, text));
+                start = range_end + 1;
+            }
+            buffer.edit(edits, None, cx);
+        });
+
+        // Sync after some of the edits, so that the layout is updated for several at once.
+        if rng.gen_bool(0.7) {
+            let snapshot = buffer.read(cx).text_snapshot();
+            elastic_tabstops.sync(&snapshot);
+            assert_eq!(
+                elastic_tabstops.blocks(),
+                ElasticTabstops::new(&snapshot, 4).blocks(),
+                "text: {:?}",
+                snapshot.text()
+            );
+        }
+    }
+}
+
 #[gpui::test]
 fn test_range_for_syntax_ancestor(cx: &mut AppContext) {
     cx.new_model(|cx| {
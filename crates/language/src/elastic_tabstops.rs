@@ -0,0 +1,281 @@
+//! Elastic tabstops align the tab-separated cells of consecutive lines into columns. Each tab
+//! ends at the same column as the tabs with the same index on the adjacent lines, so that the
+//! cells of a column are as wide as the widest one. The layout is computed separately for each
+//! block of consecutive lines that contain tabs, and only the blocks around the edited rows are
+//! computed again when the buffer changes.
+
+use std::{cmp, mem, ops::Range};
+
+use text::{BufferSnapshot, Point, WidthCounter};
+
+use crate::BufferRow;
+
+/// The number of columns between the end of a column's widest cell and its tab stop.
+const CELL_PADDING: u32 = 1;
+
+/// The elastic tabstop layout of a buffer's text.
+#[derive(Clone, Debug)]
+pub struct ElasticTabstops {
+    version: clock::Global,
+    tab_size: u32,
+    blocks: Vec<TabstopBlock>,
+}
+
+/// Consecutive lines that contain tabs, whose cells are aligned with each other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TabstopBlock {
+    pub rows: Range<BufferRow>,
+    /// The columns at which the tabs of each of the block's rows end.
+    pub tabstops: Vec<Vec<u32>>,
+}
+
+/// A column at which the tabs of consecutive lines end, for drawing a guide along it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlignmentGuide {
+    pub rows: Range<BufferRow>,
+    pub column: u32,
+    /// The index of the tabs that end at the column within their lines.
+    pub tab_index: usize,
+}
+
+impl ElasticTabstops {
+    /// Lays out the snapshot's text. Columns are at least `tab_size` wide.
+    pub fn new(snapshot: &BufferSnapshot, tab_size: u32) -> Self {
+        let mut this = Self {
+            version: snapshot.version().clone(),
+            tab_size,
+            blocks: Vec::new(),
+        };
+        this.blocks = this.layout_rows(snapshot, 0..snapshot.max_point().row + 1);
+        this
+    }
+
+    pub fn tab_size(&self) -> u32 {
+        self.tab_size
+    }
+
+    pub fn blocks(&self) -> &[TabstopBlock] {
+        &self.blocks
+    }
+
+    /// Updates the layout to the snapshot's text, laying out again only the blocks that
+    /// contain or adjoin the rows edited since the last update.
+    pub fn sync(&mut self, snapshot: &BufferSnapshot) {
+        if !snapshot.version().changed_since(&self.version) {
+            return;
+        }
+
+        let mut blocks = Vec::new();
+        let mut dirty_rows = Vec::<Range<BufferRow>>::new();
+        let mut old_blocks = mem::take(&mut self.blocks).into_iter().peekable();
+        let mut row_delta = 0;
+        for edit in snapshot.edits_since::<Point>(&self.version) {
+            let old_rows = edit.old.start.row..edit.old.end.row + 1;
+            let edit_delta = (edit.new.end.row - edit.new.start.row) as i64
+                - (edit.old.end.row - edit.old.start.row) as i64;
+            let mut new_rows = edit.new.start.row..edit.new.end.row + 1;
+            while let Some(block) = old_blocks.next_if(|block| block.rows.start <= old_rows.end) {
+                if block.rows.end < old_rows.start {
+                    blocks.push(block.shifted(row_delta));
+                } else {
+                    new_rows.start = new_rows.start.min(shift_row(block.rows.start, row_delta));
+                    new_rows.end = new_rows
+                        .end
+                        .max(shift_row(block.rows.end, row_delta + edit_delta));
+                }
+            }
+            row_delta += edit_delta;
+            dirty_rows.push(new_rows);
+        }
+        blocks.extend(old_blocks.map(|block| block.shifted(row_delta)));
+
+        // The blocks around the edits extend to the nearest lines without tabs, which may join
+        // the blocks around separate edits.
+        let row_count = snapshot.max_point().row + 1;
+        dirty_rows.sort_unstable_by_key(|rows| rows.start);
+        let mut layout_ranges = Vec::<Range<BufferRow>>::new();
+        for rows in dirty_rows {
+            let rows =
+                expand_to_blocks(snapshot, rows.start.min(row_count)..rows.end.min(row_count));
+            match layout_ranges.last_mut() {
+                Some(last) if rows.start <= last.end => last.end = cmp::max(last.end, rows.end),
+                _ => layout_ranges.push(rows),
+            }
+        }
+
+        blocks.retain(|block| {
+            !layout_ranges
+                .iter()
+                .any(|rows| rows.start < block.rows.end && block.rows.start < rows.end)
+        });
+        for rows in layout_ranges {
+            blocks.extend(self.layout_rows(snapshot, rows));
+        }
+        blocks.sort_unstable_by_key(|block| block.rows.start);
+        self.blocks = blocks;
+        self.version = snapshot.version().clone();
+    }
+
+    /// The columns at which the tabs of the row end, which is empty when it has no tabs.
+    pub fn tabstops_for_row(&self, row: BufferRow) -> &[u32] {
+        let ix = self.blocks.partition_point(|block| block.rows.end <= row);
+        match self.blocks.get(ix) {
+            Some(block) if block.rows.contains(&row) => block.tabstops_for_row(row),
+            _ => &[],
+        }
+    }
+
+    /// The guides along the columns of the blocks that intersect the rows, clipped to them.
+    pub fn alignment_guides_in_range(&self, rows: Range<BufferRow>) -> Vec<AlignmentGuide> {
+        let start_ix = self
+            .blocks
+            .partition_point(|block| block.rows.end <= rows.start);
+        self.blocks[start_ix..]
+            .iter()
+            .take_while(|block| block.rows.start < rows.end)
+            .flat_map(|block| block.alignment_guides())
+            .filter_map(|mut guide| {
+                guide.rows.start = guide.rows.start.max(rows.start);
+                guide.rows.end = guide.rows.end.min(rows.end);
+                (!guide.rows.is_empty()).then_some(guide)
+            })
+            .collect()
+    }
+
+    fn layout_rows(&self, snapshot: &BufferSnapshot, rows: Range<BufferRow>) -> Vec<TabstopBlock> {
+        let mut blocks = Vec::new();
+        let mut block_start = rows.start;
+        let mut block_cells = Vec::new();
+        for row in rows.clone() {
+            let cells = cell_widths(snapshot, row);
+            if cells.is_empty() {
+                if !block_cells.is_empty() {
+                    blocks.push(self.layout_block(block_start, mem::take(&mut block_cells)));
+                }
+                block_start = row + 1;
+            } else {
+                block_cells.push(cells);
+            }
+        }
+        if !block_cells.is_empty() {
+            blocks.push(self.layout_block(block_start, block_cells));
+        }
+        blocks
+    }
+
+    /// Lays out the cells of consecutive rows, given the width of each cell that ends with a
+    /// tab. The rows that have a cell at an index share its tab stop with the adjacent rows
+    /// that also have one, so they all share the tab stops of the preceding cells as well.
+    fn layout_block(&self, start_row: BufferRow, cells: Vec<Vec<u32>>) -> TabstopBlock {
+        let mut tabstops = cells
+            .iter()
+            .map(|row_cells| Vec::with_capacity(row_cells.len()))
+            .collect::<Vec<Vec<u32>>>();
+        let column_count = cells.iter().map(Vec::len).max().unwrap_or(0);
+        for tab_index in 0..column_count {
+            let mut run_start = 0;
+            while run_start < cells.len() {
+                if cells[run_start].len() <= tab_index {
+                    run_start += 1;
+                    continue;
+                }
+
+                let mut run_end = run_start;
+                let mut width = self.tab_size;
+                while run_end < cells.len() && cells[run_end].len() > tab_index {
+                    width = width.max(cells[run_end][tab_index] + CELL_PADDING);
+                    run_end += 1;
+                }
+                for row_tabstops in &mut tabstops[run_start..run_end] {
+                    let start = row_tabstops.last().copied().unwrap_or(0);
+                    row_tabstops.push(start + width);
+                }
+                run_start = run_end;
+            }
+        }
+
+        TabstopBlock {
+            rows: start_row..start_row + cells.len() as u32,
+            tabstops,
+        }
+    }
+}
+
+impl TabstopBlock {
+    pub fn tabstops_for_row(&self, row: BufferRow) -> &[u32] {
+        row.checked_sub(self.rows.start)
+            .and_then(|ix| self.tabstops.get(ix as usize))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The guides along the block's columns, ordered by the index of their tabs.
+    pub fn alignment_guides(&self) -> Vec<AlignmentGuide> {
+        let mut guides = Vec::new();
+        let column_count = self.tabstops.iter().map(Vec::len).max().unwrap_or(0);
+        for tab_index in 0..column_count {
+            let mut current: Option<AlignmentGuide> = None;
+            for (row, row_tabstops) in self.rows.clone().zip(&self.tabstops) {
+                match (row_tabstops.get(tab_index), current.as_mut()) {
+                    (Some(_), Some(guide)) => guide.rows.end = row + 1,
+                    (Some(&column), None) => {
+                        current = Some(AlignmentGuide {
+                            rows: row..row + 1,
+                            column,
+                            tab_index,
+                        })
+                    }
+                    (None, _) => guides.extend(current.take()),
+                }
+            }
+            guides.extend(current);
+        }
+        guides
+    }
+
+    fn shifted(mut self, row_delta: i64) -> Self {
+        self.rows = shift_row(self.rows.start, row_delta)..shift_row(self.rows.end, row_delta);
+        self
+    }
+}
+
+fn shift_row(row: BufferRow, row_delta: i64) -> BufferRow {
+    (row as i64 + row_delta).max(0) as BufferRow
+}
+
+/// The widths of the row's cells that end with a tab.
+fn cell_widths(snapshot: &BufferSnapshot, row: BufferRow) -> Vec<u32> {
+    let mut cells = Vec::new();
+    let mut width = 0;
+    let mut widths = WidthCounter::default();
+    let line_end = Point::new(row, snapshot.line_len(row));
+    for chunk in snapshot.text_for_range(Point::new(row, 0)..line_end) {
+        for c in chunk.chars() {
+            if c == '\t' {
+                cells.push(width);
+                width = 0;
+                widths = WidthCounter::default();
+            } else {
+                width += widths.width(c);
+            }
+        }
+    }
+    cells
+}
+
+fn row_has_tab(snapshot: &BufferSnapshot, row: BufferRow) -> bool {
+    let line_end = Point::new(row, snapshot.line_len(row));
+    snapshot
+        .text_for_range(Point::new(row, 0)..line_end)
+        .any(|chunk| chunk.contains('\t'))
+}
+
+fn expand_to_blocks(snapshot: &BufferSnapshot, mut rows: Range<BufferRow>) -> Range<BufferRow> {
+    let row_count = snapshot.max_point().row + 1;
+    while rows.start > 0 && row_has_tab(snapshot, rows.start - 1) {
+        rows.start -= 1;
+    }
+    while rows.end < row_count && row_has_tab(snapshot, rows.end) {
+        rows.end += 1;
+    }
+    rows
+}
@@ -8,6 +8,7 @@
 //! Notably we do *not* assign a single language to a single file; in real world a single file can consist of multiple programming languages - HTML is a good example of that - and `language` crate tends to reflect that status quo in its API.
 mod buffer;
 mod diagnostic_set;
+mod elastic_tabstops;
 mod highlight_map;
 mod language_registry;
 pub mod language_settings;
@@ -65,6 +66,7 @@ use tree_sitter::{self, wasmtime, Query, QueryCursor, WasmStore};
 pub use buffer::Operation;
 pub use buffer::*;
 pub use diagnostic_set::DiagnosticEntry;
+pub use elastic_tabstops::{AlignmentGuide, ElasticTabstops, TabstopBlock};
 pub use language_registry::{
     LanguageNotFound, LanguageQueries, LanguageRegistry, LanguageServerBinaryStatus,
     PendingLanguageServer, QUERY_FILENAME_PREFIXES,
@@ -614,6 +616,10 @@ pub struct LanguageConfig {
     /// If there's a parser name in the language settings, that will be used instead.
     #[serde(default)]
     pub prettier_parser_name: Option<String>,
+    /// Whether indentation is part of this language's syntax, as in Python or YAML.
+    /// Pasted blocks keep the relative indentation of their lines in such languages.
+    #[serde(default)]
+    pub significant_indentation: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default, JsonSchema)]
@@ -629,6 +635,10 @@ pub struct LanguageMatcher {
     )]
     #[schemars(schema_with = "regex_json_schema")]
     pub first_line_pattern: Option<Regex>,
+    /// Additional names that a Vim or Emacs modeline may use to refer to this language,
+    /// besides its name and path suffixes.
+    #[serde(default)]
+    pub modeline_aliases: Vec<String>,
 }
 
 /// Represents a language for the given range. Some languages (e.g. HTML)
@@ -700,6 +710,7 @@ impl Default for LanguageConfig {
             tab_size: None,
             soft_wrap: None,
             prettier_parser_name: None,
+            significant_indentation: false,
         }
     }
 }
@@ -1381,6 +1392,10 @@ impl Language {
     pub fn prettier_parser_name(&self) -> Option<&str> {
         self.config.prettier_parser_name.as_deref()
     }
+
+    pub fn significant_indentation(&self) -> bool {
+        self.config.significant_indentation
+    }
 }
 
 impl LanguageScope {
@@ -1549,6 +1564,7 @@ impl Ord for LanguageMatcher {
                 .as_ref()
                 .map(Regex::as_str)
                 .cmp(&other.first_line_pattern.as_ref().map(Regex::as_str))
+                .then_with(|| self.modeline_aliases.cmp(&other.modeline_aliases))
         })
     }
 }
@@ -1566,6 +1582,7 @@ impl PartialEq for LanguageMatcher {
         self.path_suffixes == other.path_suffixes
             && self.first_line_pattern.as_ref().map(Regex::as_str)
                 == other.first_line_pattern.as_ref().map(Regex::as_str)
+            && self.modeline_aliases == other.modeline_aliases
     }
 }
 
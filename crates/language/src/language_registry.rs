@@ -16,9 +16,11 @@ use futures::{
 };
 use globset::GlobSet;
 use gpui::{AppContext, BackgroundExecutor, Task};
+use lazy_static::lazy_static;
 use lsp::LanguageServerId;
 use parking_lot::{Mutex, RwLock};
 use postage::watch;
+use regex::Regex;
 use std::{
     borrow::Cow,
     ffi::OsStr,
@@ -142,6 +144,40 @@ pub struct LanguageQueries {
     pub runnables: Option<Cow<'static, str>>,
 }
 
+impl LanguageQueries {
+    /// Loads the queries from the `.scm` files in a directory, concatenating the files whose
+    /// names share a query's prefix, such as `highlights.scm` and `highlights-extra.scm`.
+    pub fn load_from_dir(root_path: &Path) -> Self {
+        let mut result = Self::default();
+        if let Some(entries) = std::fs::read_dir(root_path).log_err() {
+            for entry in entries {
+                let Some(entry) = entry.log_err() else {
+                    continue;
+                };
+                let path = entry.path();
+                if let Some(remainder) = path.strip_prefix(root_path).ok().and_then(|p| p.to_str())
+                {
+                    if !remainder.ends_with(".scm") {
+                        continue;
+                    }
+                    for (name, query) in QUERY_FILENAME_PREFIXES {
+                        if remainder.starts_with(name) {
+                            if let Some(contents) = std::fs::read_to_string(&path).log_err() {
+                                match query(&mut result) {
+                                    None => *query(&mut result) = Some(contents.into()),
+                                    Some(r) => r.to_mut().push_str(contents.as_ref()),
+                                }
+                            }
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
 #[derive(Clone, Default)]
 struct LspBinaryStatusSender {
     txs: Arc<Mutex<Vec<mpsc::UnboundedSender<(LanguageServerName, LanguageServerBinaryStatus)>>>>,
@@ -503,6 +539,25 @@ impl LanguageRegistry {
             .map_err(|error| error.context(format!("language for file path {}", path.display())))
     }
 
+    /// Whether any language is known for files at the given path, judging by the path's
+    /// extension or file name alone.
+    pub fn has_language_for_file_path(&self, path: &Path) -> bool {
+        let filename = path.file_name().and_then(|name| name.to_str());
+        let extension = path.extension_or_hidden_file_name();
+        let path_suffixes = [extension, filename];
+        self.state
+            .read()
+            .available_languages
+            .iter()
+            .any(|language| {
+                language
+                    .matcher
+                    .path_suffixes
+                    .iter()
+                    .any(|suffix| path_suffixes.contains(&Some(suffix.as_str())))
+            })
+    }
+
     fn language_for_file_internal(
         self: &Arc<Self>,
         path: &Path,
@@ -513,6 +568,7 @@ impl LanguageRegistry {
         let extension = path.extension_or_hidden_file_name();
         let path_suffixes = [extension, filename];
         let empty = GlobSet::empty();
+        let modeline_language = content.and_then(modeline_language).map(UniCase::new);
 
         let rx = self.get_or_load_language(move |language_name, config| {
             let path_matches_default_suffix = config
@@ -535,7 +591,20 @@ impl LanguageRegistry {
                     pattern.is_match(&text)
                 },
             );
-            if path_matches_custom_suffix {
+            // A modeline names the file's language explicitly, so it takes precedence.
+            let modeline_matches = modeline_language
+                .as_ref()
+                .map_or(false, |modeline_language| {
+                    UniCase::new(language_name) == *modeline_language
+                        || config
+                            .path_suffixes
+                            .iter()
+                            .chain(&config.modeline_aliases)
+                            .any(|alias| UniCase::new(alias.as_str()) == *modeline_language)
+                });
+            if modeline_matches {
+                3
+            } else if path_matches_custom_suffix {
                 2
             } else if path_matches_default_suffix || content_matches {
                 1
@@ -771,7 +840,7 @@ impl LanguageRegistry {
 
                 delegate.update_status(adapter.name.clone(), LanguageServerBinaryStatus::None);
 
-                let binary = binary_result?;
+                let mut binary = binary_result?;
                 let options = adapter
                     .adapter
                     .clone()
@@ -833,6 +902,13 @@ impl LanguageRegistry {
                 }
 
                 drop(this);
+
+                // Servers run in the worktree's environment, so that they can find the toolchains
+                // the user's shell sets up there, but the binary's own variables take precedence.
+                let mut env = delegate.shell_env().await;
+                env.extend(binary.env.take().unwrap_or_default());
+                binary.env = Some(env);
+
                 Ok((
                     lsp::LanguageServer::new(
                         stderr_capture,
@@ -886,6 +962,48 @@ impl LanguageRegistry {
     }
 }
 
+/// The number of lines at the start and end of a file that are searched for modelines,
+/// matching Vim's default.
+const MODELINE_LINE_COUNT: u32 = 5;
+
+lazy_static! {
+    static ref VIM_MODELINE_REGEX: Regex =
+        Regex::new(r"(?:^|\s)(?:vi|vim|ex)(?:[<=>]?\d+)?:.*?\b(?:ft|filetype)=([\w+\-]+)").unwrap();
+    static ref EMACS_MODELINE_REGEX: Regex = Regex::new(r"-\*-\s*(.*?)\s*-\*-").unwrap();
+    static ref EMACS_MODE_REGEX: Regex = Regex::new(r"(?:^|;)\s*mode:\s*([\w+\-]+)").unwrap();
+}
+
+/// Returns the language named by a Vim or Emacs modeline in the first or last lines of the
+/// given text, if there is one.
+fn modeline_language(content: &Rope) -> Option<String> {
+    let max_row = content.max_point().row;
+    let last_lines_start = max_row.saturating_sub(MODELINE_LINE_COUNT - 1);
+    let rows = (0..MODELINE_LINE_COUNT.min(max_row + 1))
+        .chain(last_lines_start.max(MODELINE_LINE_COUNT)..=max_row);
+
+    rows.filter_map(|row| {
+        let start = content.point_to_offset(Point::new(row, 0));
+        let end = content.point_to_offset(content.clip_point(Point::new(row, 256), Bias::Left));
+        let line = content.chunks_in_range(start..end).collect::<String>();
+        modeline_language_in_line(&line)
+    })
+    .next()
+}
+
+fn modeline_language_in_line(line: &str) -> Option<String> {
+    if let Some(captures) = VIM_MODELINE_REGEX.captures(line) {
+        return Some(captures[1].to_string());
+    }
+
+    let variables = EMACS_MODELINE_REGEX.captures(line)?.get(1)?.as_str();
+    if variables.contains(':') {
+        Some(EMACS_MODE_REGEX.captures(variables)?[1].to_string())
+    } else {
+        // `-*- python -*-` is a shorthand for `-*- mode: python -*-`.
+        Some(variables.to_string()).filter(|mode| !mode.is_empty())
+    }
+}
+
 impl LanguageRegistryState {
     fn next_language_server_id(&mut self) -> LanguageServerId {
         LanguageServerId(post_inc(&mut self.next_language_server_id))
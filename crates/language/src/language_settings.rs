@@ -12,7 +12,12 @@ use schemars::{
 };
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsLocation, SettingsSources};
-use std::{num::NonZeroU32, path::Path, sync::Arc};
+use std::{
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use util::serde::default_true;
 
 impl<'a> Into<SettingsLocation<'a>> for &'a dyn File {
@@ -88,6 +93,8 @@ pub struct LanguageSettings {
     /// Whether or not to ensure there's a single newline at the end of a buffer
     /// when saving it.
     pub ensure_final_newline_on_save: bool,
+    /// The hooks to run when saving a buffer.
+    pub save_hooks: SaveHooks,
     /// How to perform a buffer format.
     pub formatter: Formatter,
     /// Zed's Prettier integration settings.
@@ -116,12 +123,32 @@ pub struct LanguageSettings {
     pub always_treat_brackets_as_autoclosed: bool,
     /// Which code actions to run on save
     pub code_actions_on_format: HashMap<String, bool>,
+    /// Local documentation files to look symbols up in when language servers don't
+    /// document them.
+    pub docsets: Vec<PathBuf>,
 }
 
 impl LanguageSettings {
     /// A token representing the rest of the available language servers.
     const REST_OF_LANGUAGE_SERVERS: &'static str = "...";
 
+    /// Returns the hooks to run before saving a buffer, derived from the individual
+    /// on-save settings unless they're configured explicitly.
+    pub fn pre_save_hooks(&self) -> Vec<SaveHook> {
+        if let Some(hooks) = &self.save_hooks.pre_save {
+            return hooks.clone();
+        }
+        let mut hooks = Vec::new();
+        if self.remove_trailing_whitespace_on_save {
+            hooks.push(SaveHook::new(SaveHookKind::RemoveTrailingWhitespace));
+        }
+        if self.ensure_final_newline_on_save {
+            hooks.push(SaveHook::new(SaveHookKind::EnsureFinalNewline));
+        }
+        hooks.push(SaveHook::new(SaveHookKind::Format));
+        hooks
+    }
+
     /// Returns the customized list of language servers from the list of
     /// available language servers.
     pub fn customized_language_servers(
@@ -264,6 +291,11 @@ pub struct LanguageSettingsContent {
     /// Default: true
     #[serde(default)]
     pub ensure_final_newline_on_save: Option<bool>,
+    /// The hooks to run when saving a buffer, in order.
+    ///
+    /// Default: {"post_save": []}
+    #[serde(default)]
+    pub save_hooks: Option<SaveHooks>,
     /// How to perform a buffer format.
     ///
     /// Default: auto
@@ -326,6 +358,13 @@ pub struct LanguageSettingsContent {
     ///
     /// Default: {} (or {"source.organizeImports": true} for Go).
     pub code_actions_on_format: Option<HashMap<String, bool>>,
+    /// Local documentation files to look symbols up in when language servers don't
+    /// document them. Each file is either rustdoc's JSON output, or a JSON object
+    /// whose keys are names and whose values are their documentation in Markdown,
+    /// such as a dump of MDN.
+    ///
+    /// Default: []
+    pub docsets: Option<Vec<PathBuf>>,
 }
 
 /// The contents of the inline completion settings.
@@ -381,6 +420,96 @@ pub enum FormatOnSave {
     CodeActions(HashMap<String, bool>),
 }
 
+/// The hooks to run when saving a buffer.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct SaveHooks {
+    /// The hooks to run before the buffer is written, in order. These can edit the buffer,
+    /// and a failing hook whose policy is to abort prevents the buffer from being written.
+    ///
+    /// When unset, trailing whitespace is removed, a final newline is ensured and the buffer
+    /// is formatted, according to `remove_trailing_whitespace_on_save`,
+    /// `ensure_final_newline_on_save` and `format_on_save`.
+    #[serde(default)]
+    pub pre_save: Option<Vec<SaveHook>>,
+    /// The hooks to run after the buffer is written, in order. A failing hook whose policy is
+    /// to abort prevents the remaining hooks from running.
+    #[serde(default)]
+    pub post_save: Vec<SaveHook>,
+}
+
+/// A step that's run when saving a buffer.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct SaveHook {
+    /// What the hook does.
+    #[serde(flatten)]
+    pub kind: SaveHookKind,
+    /// How long the hook may run before it's considered to have failed, in milliseconds.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// What to do when the hook fails or times out.
+    #[serde(default)]
+    pub on_failure: SaveHookFailurePolicy,
+}
+
+/// What a save hook does.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "hook", rename_all = "snake_case")]
+pub enum SaveHookKind {
+    /// Remove any trailing whitespace from the buffer's lines.
+    RemoveTrailingWhitespace,
+    /// Ensure there's a single newline at the end of the buffer.
+    EnsureFinalNewline,
+    /// Format the buffer according to `formatter` and `format_on_save`.
+    Format,
+    /// Run an external program, such as a linter, which fails the hook if it exits
+    /// unsuccessfully. `{buffer_path}` in the arguments is replaced with the buffer's path,
+    /// and the buffer's contents are written to the program's standard input.
+    Command {
+        /// The external program to run.
+        command: Arc<str>,
+        /// The arguments to pass to the program.
+        #[serde(default)]
+        arguments: Arc<[String]>,
+    },
+    /// Refresh the git status of the buffer's file.
+    RefreshGitStatus,
+}
+
+impl SaveHookKind {
+    /// Whether the hook edits the buffer, rather than acting on the file.
+    pub fn edits_buffer(&self) -> bool {
+        matches!(
+            self,
+            Self::RemoveTrailingWhitespace | Self::EnsureFinalNewline | Self::Format
+        )
+    }
+}
+
+/// What to do when a save hook fails.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SaveHookFailurePolicy {
+    /// Stop running hooks and fail the save.
+    #[default]
+    Abort,
+    /// Log the failure and run the remaining hooks.
+    Continue,
+}
+
+impl SaveHook {
+    fn new(kind: SaveHookKind) -> Self {
+        Self {
+            kind,
+            timeout_ms: None,
+            on_failure: SaveHookFailurePolicy::default(),
+        }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
+}
+
 /// Controls how whitespace should be displayedin the editor.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -439,6 +568,13 @@ pub struct IndentGuideSettings {
     /// Default: Disabled
     #[serde(default)]
     pub background_coloring: IndentGuideBackgroundColoring,
+    /// Whether to compute the elastic tabstop layout of the tab-separated cells of
+    /// consecutive lines, for drawing alignment guides along their columns. Tabs
+    /// are still displayed at multiples of the tab size.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub elastic_tabstops: bool,
 }
 
 fn line_width() -> u32 {
@@ -794,6 +930,7 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         &mut settings.ensure_final_newline_on_save,
         src.ensure_final_newline_on_save,
     );
+    merge(&mut settings.save_hooks, src.save_hooks.clone());
     merge(
         &mut settings.enable_language_server,
         src.enable_language_server,
@@ -809,6 +946,7 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         src.extend_comment_on_newline,
     );
     merge(&mut settings.inlay_hints, src.inlay_hints);
+    merge(&mut settings.docsets, src.docsets.clone());
 }
 
 /// Allows to enable/disable formatting with Prettier
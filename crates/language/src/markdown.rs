@@ -1,11 +1,18 @@
 //! Provides Markdown-related constructs.
 
 use std::sync::Arc;
-use std::{ops::Range, path::PathBuf};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 use crate::{HighlightId, Language, LanguageRegistry};
-use gpui::{px, FontStyle, FontWeight, HighlightStyle, StrikethroughStyle, UnderlineStyle};
+use gpui::{
+    px, AppContext, ElementId, FontStyle, FontWeight, HighlightStyle, Hsla, InteractiveText,
+    StrikethroughStyle, StyledText, TextStyle, UnderlineStyle, WindowContext,
+};
 use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
+use theme::{ActiveTheme, SyntaxTheme};
 
 /// Parsed Markdown content.
 #[derive(Debug, Clone, Default)]
@@ -118,6 +125,92 @@ impl Link {
     }
 }
 
+/// The style that [`ParsedMarkdown`] is rendered with.
+#[derive(Clone)]
+pub struct MarkdownStyle {
+    /// The style of the text, which highlights are applied on top of.
+    pub text: TextStyle,
+    /// The theme used to highlight code.
+    pub syntax: Arc<SyntaxTheme>,
+    /// The background of inline code.
+    pub code_span_background: Hsla,
+    /// The background of code blocks.
+    pub code_block_background: Hsla,
+}
+
+impl MarkdownStyle {
+    /// Returns the style for rendering Markdown with the given text style in the active theme.
+    pub fn themed(text: TextStyle, cx: &AppContext) -> Self {
+        let theme = cx.theme();
+        Self {
+            text,
+            syntax: theme.syntax().clone(),
+            code_span_background: theme.colors().editor_document_highlight_read_background,
+            code_block_background: theme.colors().surface_background,
+        }
+    }
+}
+
+impl ParsedMarkdown {
+    /// Returns the styles of the highlighted ranges of the text, including the
+    /// backgrounds of inline code.
+    pub fn highlight_styles(&self, style: &MarkdownStyle) -> Vec<(Range<usize>, HighlightStyle)> {
+        gpui::combine_highlights(
+            self.highlights.iter().filter_map(|(range, highlight)| {
+                let highlight = highlight.to_highlight_style(&style.syntax)?;
+                Some((range.clone(), highlight))
+            }),
+            self.regions
+                .iter()
+                .zip(&self.region_ranges)
+                .filter_map(|(region, range)| {
+                    region.code.then(|| {
+                        (
+                            range.clone(),
+                            HighlightStyle {
+                                background_color: Some(style.code_span_background),
+                                ..Default::default()
+                            },
+                        )
+                    })
+                }),
+        )
+        .collect()
+    }
+
+    /// Returns the ranges of the text that are links, along with their targets.
+    pub fn links(&self) -> (Vec<Range<usize>>, Vec<Link>) {
+        self.region_ranges
+            .iter()
+            .zip(&self.regions)
+            .filter_map(|(range, region)| Some((range.clone(), region.link.clone()?)))
+            .unzip()
+    }
+}
+
+/// Renders parsed Markdown as styled text, for places like hover popovers and completion
+/// documentation. Web links are opened in the browser when clicked, while clicking a link
+/// to a path calls `open_path`.
+pub fn render_parsed_markdown(
+    element_id: impl Into<ElementId>,
+    parsed: &ParsedMarkdown,
+    style: &MarkdownStyle,
+    open_path: impl Fn(&Path, &mut WindowContext) + 'static,
+) -> InteractiveText {
+    let (link_ranges, links) = parsed.links();
+    InteractiveText::new(
+        element_id,
+        StyledText::new(parsed.text.clone())
+            .with_highlights(&style.text, parsed.highlight_styles(style)),
+    )
+    .on_click(link_ranges, move |clicked_range_ix, cx| {
+        match &links[clicked_range_ix] {
+            Link::Web { url } => cx.open_url(url),
+            Link::Path { path } => open_path(path, cx),
+        }
+    })
+}
+
 /// Parses a string of Markdown.
 pub async fn parse_markdown(
     markdown: &str,
@@ -363,6 +456,51 @@ pub fn new_paragraph(text: &mut String, list_stack: &mut Vec<(Option<u64>, bool)
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use gpui::{hsla, TestAppContext};
+
+    #[gpui::test]
+    async fn test_parsed_markdown_links_and_highlights(cx: &mut TestAppContext) {
+        let language_registry = Arc::new(LanguageRegistry::test(cx.executor()));
+        let parsed = parse_markdown(
+            "See `foo` in [the docs](https://zed.dev) or [a file](/a/b.rs).",
+            &language_registry,
+            None,
+        )
+        .await;
+        assert_eq!(parsed.text, "See foo in the docs or a file.");
+
+        let (link_ranges, links) = parsed.links();
+        assert_eq!(
+            link_ranges
+                .iter()
+                .map(|range| &parsed.text[range.clone()])
+                .collect::<Vec<_>>(),
+            ["the docs", "a file"]
+        );
+        assert!(matches!(&links[0], Link::Web { url } if url == "https://zed.dev"));
+        assert!(matches!(&links[1], Link::Path { path } if path == Path::new("/a/b.rs")));
+
+        let code_span_background = hsla(0.5, 0.5, 0.5, 1.);
+        let style = MarkdownStyle {
+            text: TextStyle::default(),
+            syntax: Arc::new(SyntaxTheme::default()),
+            code_span_background,
+            code_block_background: hsla(0., 0., 0., 1.),
+        };
+        let highlight_styles = parsed.highlight_styles(&style);
+        let code_span_style = highlight_styles
+            .iter()
+            .find(|(range, _)| &parsed.text[range.clone()] == "foo")
+            .map(|(_, style)| style.background_color);
+        assert_eq!(code_span_style, Some(Some(code_span_background)));
+        let link_styles = highlight_styles
+            .iter()
+            .filter(|(_, style)| style.underline.is_some())
+            .map(|(range, _)| &parsed.text[range.clone()])
+            .collect::<Vec<_>>();
+        assert_eq!(link_styles, ["the docs", "a file"]);
+    }
 
     #[test]
     fn test_dividers() {
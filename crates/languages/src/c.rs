@@ -38,6 +38,7 @@ impl super::LspAdapter for CLspAdapter {
         if let Ok(Some(BinarySettings {
             path: Some(path),
             arguments,
+            env,
         })) = configured_binary
         {
             Some(LanguageServerBinary {
@@ -47,7 +48,7 @@ impl super::LspAdapter for CLspAdapter {
                     .iter()
                     .map(|arg| arg.into())
                     .collect(),
-                env: None,
+                env: crate::configured_binary_env(env, delegate).await,
             })
         } else {
             let env = delegate.shell_env().await;
@@ -81,6 +81,7 @@ impl super::LspAdapter for GoLspAdapter {
         if let Ok(Some(BinarySettings {
             path: Some(path),
             arguments,
+            env,
         })) = configured_binary
         {
             Some(LanguageServerBinary {
@@ -90,7 +91,7 @@ impl super::LspAdapter for GoLspAdapter {
                     .iter()
                     .map(|arg| arg.into())
                     .collect(),
-                env: None,
+                env: crate::configured_binary_env(env, delegate).await,
             })
         } else {
             let env = delegate.shell_env().await;
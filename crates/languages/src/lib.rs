@@ -1,4 +1,5 @@
 use anyhow::Context;
+use collections::HashMap;
 use gpui::{AppContext, UpdateGlobal};
 use json::json_task_context;
 pub use language::*;
@@ -23,6 +24,7 @@ mod python;
 mod rust;
 mod tailwind;
 mod typescript;
+pub mod user_languages;
 mod yaml;
 
 #[derive(RustEmbed)]
@@ -247,6 +249,18 @@ pub fn init(
     .detach();
 }
 
+/// Returns the environment to run a language server binary configured in the settings with:
+/// the login shell's environment with the configured variables on top, if there are any.
+async fn configured_binary_env(
+    env: Option<HashMap<String, String>>,
+    delegate: &dyn LspAdapterDelegate,
+) -> Option<HashMap<String, String>> {
+    let configured_env = env?;
+    let mut env = delegate.shell_env().await;
+    env.extend(configured_env);
+    Some(env)
+}
+
 #[cfg(any(test, feature = "test-support"))]
 pub fn language(name: &str, grammar: tree_sitter::Language) -> Arc<Language> {
     Arc::new(
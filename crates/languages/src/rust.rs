@@ -35,7 +35,7 @@ impl LspAdapter for RustLspAdapter {
 
     async fn check_if_user_installed(
         &self,
-        _delegate: &dyn LspAdapterDelegate,
+        delegate: &dyn LspAdapterDelegate,
         cx: &AsyncAppContext,
     ) -> Option<LanguageServerBinary> {
         let binary = cx
@@ -56,7 +56,7 @@ impl LspAdapter for RustLspAdapter {
                 .iter()
                 .map(|arg| arg.into())
                 .collect(),
-            env: None,
+            env: crate::configured_binary_env(binary.env, delegate).await,
         })
     }
 
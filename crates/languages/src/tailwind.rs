@@ -44,7 +44,7 @@ impl LspAdapter for TailwindLspAdapter {
 
     async fn check_if_user_installed(
         &self,
-        _delegate: &dyn LspAdapterDelegate,
+        delegate: &dyn LspAdapterDelegate,
         cx: &AsyncAppContext,
     ) -> Option<LanguageServerBinary> {
         let configured_binary = cx
@@ -72,7 +72,7 @@ impl LspAdapter for TailwindLspAdapter {
         Some(LanguageServerBinary {
             path,
             arguments,
-            env: None,
+            env: crate::configured_binary_env(configured_binary.env, delegate).await,
         })
     }
 
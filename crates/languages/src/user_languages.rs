@@ -0,0 +1,210 @@
+//! Languages defined in the user's languages directory, which are loaded at runtime so that
+//! adding support for a language doesn't require rebuilding Zed. Each subdirectory defines a
+//! language with a `config.toml` and its query files, like the built-in languages do, and the
+//! `grammars` subdirectory contains tree-sitter grammars compiled to WebAssembly, named after
+//! the grammar they provide.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use fs::Fs;
+use gpui::AppContext;
+use language::{LanguageConfig, LanguageQueries, LanguageRegistry};
+use smol::stream::StreamExt;
+use util::ResultExt;
+
+const GRAMMARS_DIR_NAME: &str = "grammars";
+
+/// The languages and grammars registered from a user languages directory.
+pub struct UserLanguages {
+    dir: PathBuf,
+    fs: Arc<dyn Fs>,
+    languages: Arc<LanguageRegistry>,
+    language_names: Vec<Arc<str>>,
+    grammar_names: Vec<Arc<str>>,
+}
+
+impl UserLanguages {
+    pub fn new(dir: PathBuf, fs: Arc<dyn Fs>, languages: Arc<LanguageRegistry>) -> Self {
+        Self {
+            dir,
+            fs,
+            languages,
+            language_names: Vec::new(),
+            grammar_names: Vec::new(),
+        }
+    }
+
+    pub fn language_names(&self) -> &[Arc<str>] {
+        &self.language_names
+    }
+
+    pub fn grammar_names(&self) -> &[Arc<str>] {
+        &self.grammar_names
+    }
+
+    /// Replaces the previously registered languages and grammars with the ones currently in
+    /// the directory. Languages that were already loaded are loaded again when next needed.
+    pub async fn reload(&mut self) -> Result<()> {
+        let mut configs = Vec::new();
+        let mut grammars = Vec::new();
+        let mut entries = self
+            .fs
+            .read_dir(&self.dir)
+            .await
+            .with_context(|| format!("reading languages from {:?}", self.dir))?;
+        while let Some(entry) = entries.next().await {
+            let Some(path) = entry.log_err() else {
+                continue;
+            };
+            if !self.fs.is_dir(&path).await {
+                continue;
+            }
+            if path
+                .file_name()
+                .map_or(false, |name| name == GRAMMARS_DIR_NAME)
+            {
+                grammars.extend(
+                    self.read_grammars(&path)
+                        .await
+                        .log_err()
+                        .unwrap_or_default(),
+                );
+            } else if let Some(config) = self.read_config(&path).await.log_err() {
+                configs.push((path, config));
+            }
+        }
+
+        self.languages
+            .remove_languages(&self.language_names, &self.grammar_names);
+        self.grammar_names = grammars.iter().map(|(name, _)| name.clone()).collect();
+        self.languages.register_wasm_grammars(grammars);
+        self.language_names.clear();
+        for (language_dir, config) in configs {
+            self.language_names.push(config.name.clone());
+            self.languages.register_language(
+                config.name.clone(),
+                config.grammar.clone(),
+                config.matcher.clone(),
+                move || {
+                    let queries = LanguageQueries::load_from_dir(&language_dir);
+                    Ok((config.clone(), queries, None))
+                },
+            );
+        }
+        Ok(())
+    }
+
+    async fn read_config(&self, language_dir: &Path) -> Result<LanguageConfig> {
+        let config_path = language_dir.join("config.toml");
+        let config = self.fs.load(&config_path).await?;
+        ::toml::from_str(&config).with_context(|| format!("parsing {config_path:?}"))
+    }
+
+    async fn read_grammars(&self, grammars_dir: &Path) -> Result<Vec<(Arc<str>, PathBuf)>> {
+        let mut grammars = Vec::new();
+        let mut entries = self.fs.read_dir(grammars_dir).await?;
+        while let Some(entry) = entries.next().await {
+            let path = entry?;
+            if path
+                .extension()
+                .map_or(false, |extension| extension == "wasm")
+            {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    grammars.push((name.into(), path.clone()));
+                }
+            }
+        }
+        Ok(grammars)
+    }
+}
+
+/// Registers the languages in the given directory, creating it if needed, and registers them
+/// again whenever the directory's contents change.
+pub fn watch(dir: PathBuf, fs: Arc<dyn Fs>, languages: Arc<LanguageRegistry>, cx: &AppContext) {
+    cx.background_executor()
+        .spawn(async move {
+            if !fs.is_dir(&dir).await {
+                fs.create_dir(&dir)
+                    .await
+                    .with_context(|| format!("creating languages dir at {dir:?}"))?;
+            }
+
+            let mut events = fs.watch(&dir, Duration::from_millis(100)).await;
+            let mut user_languages = UserLanguages::new(dir, fs, languages);
+            user_languages.reload().await.log_err();
+            while events.next().await.is_some() {
+                user_languages.reload().await.log_err();
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use serde_json::json;
+
+    #[gpui::test]
+    async fn test_reload_user_languages(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/languages",
+            json!({
+                "grammars": {
+                    "foo.wasm": "",
+                    "README.md": "",
+                },
+                "foo": {
+                    "config.toml": "name = \"Foo\"\ngrammar = \"foo\"\npath_suffixes = [\"foo\"]",
+                    "highlights.scm": "",
+                },
+                "broken": {
+                    "config.toml": "name = ",
+                },
+            }),
+        )
+        .await;
+        let languages = Arc::new(LanguageRegistry::test(cx.executor()));
+        let mut user_languages =
+            UserLanguages::new(PathBuf::from("/languages"), fs.clone(), languages.clone());
+
+        user_languages.reload().await.unwrap();
+        assert_eq!(user_languages.language_names(), [Arc::from("Foo")]);
+        assert_eq!(user_languages.grammar_names(), [Arc::from("foo")]);
+        assert!(languages.language_names().contains(&"Foo".to_string()));
+        assert!(languages.grammar_names().contains(&Arc::from("foo")));
+
+        // Changed definitions replace the previous ones.
+        fs.insert_file(
+            "/languages/foo/config.toml",
+            "name = \"Bar\"\ngrammar = \"bar\"\npath_suffixes = [\"bar\"]"
+                .as_bytes()
+                .to_vec(),
+        )
+        .await;
+        fs.remove_file(
+            Path::new("/languages/grammars/foo.wasm"),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        fs.insert_file("/languages/grammars/bar.wasm", Vec::new())
+            .await;
+
+        user_languages.reload().await.unwrap();
+        assert_eq!(user_languages.language_names(), [Arc::from("Bar")]);
+        assert_eq!(user_languages.grammar_names(), [Arc::from("bar")]);
+        assert!(!languages.language_names().contains(&"Foo".to_string()));
+        assert!(languages.language_names().contains(&"Bar".to_string()));
+        assert!(!languages.grammar_names().contains(&Arc::from("foo")));
+        assert!(languages.grammar_names().contains(&Arc::from("bar")));
+    }
+}
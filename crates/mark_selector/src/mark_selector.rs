@@ -0,0 +1,205 @@
+use editor::marks::{self, MarkLocation, Marks};
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, ParentElement,
+    Render, Styled, View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::{paths::PathExt, ResultExt};
+use workspace::{ModalView, Workspace};
+
+actions!(mark_selector, [Toggle]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(MarkSelector::register).detach();
+}
+
+pub struct MarkSelector {
+    picker: View<Picker<MarkSelectorDelegate>>,
+}
+
+impl MarkSelector {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(move |workspace, _: &Toggle, cx| {
+            Self::toggle(workspace, cx);
+        });
+    }
+
+    fn toggle(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> Option<()> {
+        let locations = Marks::global(cx)?.read(cx).locations(cx);
+        let workspace_handle = cx.view().downgrade();
+        workspace.toggle_modal(cx, move |cx| {
+            MarkSelector::new(workspace_handle, locations, cx)
+        });
+        Some(())
+    }
+
+    fn new(
+        workspace: WeakView<Workspace>,
+        locations: Vec<MarkLocation>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = MarkSelectorDelegate::new(cx.view().downgrade(), workspace, locations);
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for MarkSelector {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for MarkSelector {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for MarkSelector {}
+impl ModalView for MarkSelector {}
+
+pub struct MarkSelectorDelegate {
+    mark_selector: WeakView<MarkSelector>,
+    workspace: WeakView<Workspace>,
+    locations: Vec<MarkLocation>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl MarkSelectorDelegate {
+    fn new(
+        mark_selector: WeakView<MarkSelector>,
+        workspace: WeakView<Workspace>,
+        locations: Vec<MarkLocation>,
+    ) -> Self {
+        let candidates = locations
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, location)| {
+                StringMatchCandidate::new(
+                    candidate_id,
+                    format!(
+                        "{} {}:{}",
+                        location.name,
+                        location.abs_path.compact().display(),
+                        location.point.row + 1
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            mark_selector,
+            workspace,
+            locations,
+            candidates,
+            matches: vec![],
+            selected_index: 0,
+        }
+    }
+}
+
+impl PickerDelegate for MarkSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Go to mark...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let location = self.locations[mat.candidate_id].clone();
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    marks::open_mark(workspace, location, cx).detach_and_log_err(cx);
+                })
+                .log_err();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.mark_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
@@ -9,6 +9,7 @@ use gpui::{
     HighlightStyle, Hsla, InteractiveText, IntoElement, Keystroke, Modifiers, ParentElement,
     SharedString, Styled, StyledText, TextStyle, WeakView, WindowContext,
 };
+use language::markdown::MarkdownStyle;
 use std::{
     ops::{Mul, Range},
     sync::Arc,
@@ -39,18 +40,19 @@ pub struct RenderContext {
 impl RenderContext {
     pub fn new(workspace: Option<WeakView<Workspace>>, cx: &WindowContext) -> RenderContext {
         let theme = cx.theme().clone();
+        let markdown_style = MarkdownStyle::themed(cx.text_style(), cx);
 
         RenderContext {
             workspace,
             next_id: 0,
             indent: 0,
-            text_style: cx.text_style(),
-            syntax_theme: theme.syntax().clone(),
+            text_style: markdown_style.text,
+            syntax_theme: markdown_style.syntax,
             border_color: theme.colors().border,
             text_color: theme.colors().text,
             text_muted_color: theme.colors().text_muted,
-            code_block_background_color: theme.colors().surface_background,
-            code_span_background_color: theme.colors().editor_document_highlight_read_background,
+            code_block_background_color: markdown_style.code_block_background,
+            code_span_background_color: markdown_style.code_span_background,
             checkbox_clicked_callback: None,
         }
     }
@@ -11,10 +11,11 @@ use itertools::Itertools;
 use language::{
     char_kind,
     language_settings::{language_settings, LanguageSettings},
-    AutoindentMode, Buffer, BufferChunks, BufferRow, BufferSnapshot, Capability, CharKind, Chunk,
-    CursorShape, DiagnosticEntry, File, IndentGuide, IndentSize, Language, LanguageScope,
-    OffsetRangeExt, OffsetUtf16, Outline, OutlineItem, Point, PointUtf16, Selection, TextDimension,
-    ToOffset as _, ToOffsetUtf16 as _, ToPoint as _, ToPointUtf16 as _, TransactionId, Unclipped,
+    AutoindentMode, BracketMatch, Buffer, BufferChunks, BufferRow, BufferSnapshot, Capability,
+    CharKind, Chunk, CursorShape, DiagnosticEntry, File, IndentGuide, IndentSize, Language,
+    LanguageScope, OffsetRangeExt, OffsetUtf16, Outline, OutlineItem, Point, PointUtf16, Selection,
+    TextDimension, ToOffset as _, ToOffsetUtf16 as _, ToPoint as _, ToPointUtf16 as _,
+    TransactionId, Unclipped,
 };
 use smallvec::SmallVec;
 use std::{
@@ -822,6 +823,38 @@ impl MultiBuffer {
         }
     }
 
+    /// Removes a transaction from the undo history, without undoing it.
+    pub fn forget_transaction(
+        &mut self,
+        transaction_id: TransactionId,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(buffer) = self.as_singleton() {
+            buffer.update(cx, |buffer, _| buffer.forget_transaction(transaction_id));
+        } else if let Some(transaction) = self.history.forget(transaction_id) {
+            for (buffer_id, buffer_transaction_id) in transaction.buffer_transactions {
+                if let Some(state) = self.buffers.borrow().get(&buffer_id) {
+                    state.buffer.update(cx, |buffer, _| {
+                        buffer.forget_transaction(buffer_transaction_id)
+                    });
+                }
+            }
+        }
+    }
+
+    /// Holds back the operations of every buffer, see [`Buffer::hold_operations`].
+    pub fn hold_operations(&mut self, cx: &mut ModelContext<Self>) {
+        for BufferState { buffer, .. } in self.buffers.borrow().values() {
+            buffer.update(cx, |buffer, _| buffer.hold_operations());
+        }
+    }
+
+    pub fn release_held_operations(&mut self, cx: &mut ModelContext<Self>) {
+        for BufferState { buffer, .. } in self.buffers.borrow().values() {
+            buffer.update(cx, |buffer, cx| buffer.release_held_operations(cx));
+        }
+    }
+
     pub fn finalize_last_transaction(&mut self, cx: &mut ModelContext<Self>) {
         self.history.finalize_last_transaction();
         for BufferState { buffer, .. } in self.buffers.borrow().values() {
@@ -3219,6 +3252,34 @@ impl MultiBufferSnapshot {
         )
     }
 
+    /// Returns the bracket pairs overlapping the given `range`, along with their nesting depth,
+    /// or returns None if the `range` is not contained in a single excerpt
+    ///
+    /// Only pairs that fit within the excerpt are returned, but the depths still account for
+    /// any pairs that enclose the excerpt.
+    pub fn bracket_matches<T: ToOffset>(&self, range: Range<T>) -> Option<Vec<BracketMatch>> {
+        let range = range.start.to_offset(self)..range.end.to_offset(self);
+        let excerpt = self.excerpt_containing(range.clone())?;
+
+        Some(
+            excerpt
+                .buffer()
+                .bracket_matches(excerpt.map_range_to_buffer(range))
+                .into_iter()
+                .filter(|bracket_match| {
+                    excerpt.contains_buffer_range(
+                        bracket_match.open_range.start..bracket_match.close_range.end,
+                    )
+                })
+                .map(|bracket_match| BracketMatch {
+                    open_range: excerpt.map_range_from_buffer(bracket_match.open_range),
+                    close_range: excerpt.map_range_from_buffer(bracket_match.close_range),
+                    depth: bracket_match.depth,
+                })
+                .collect(),
+        )
+    }
+
     pub fn redacted_ranges<'a, T: ToOffset>(
         &'a self,
         range: Range<T>,
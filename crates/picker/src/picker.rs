@@ -437,6 +437,16 @@ impl<D: PickerDelegate> Picker<D> {
         }
     }
 
+    /// Shows the delegate's placeholder text again, for when it changed.
+    pub fn refresh_placeholder(&mut self, cx: &mut ViewContext<Self>) {
+        if let Head::Editor(ref editor) = &self.head {
+            let placeholder_text = self.delegate.placeholder_text(cx);
+            editor.update(cx, |editor, cx| {
+                editor.set_placeholder_text(placeholder_text, cx)
+            });
+        }
+    }
+
     pub fn set_query(&self, query: impl Into<Arc<str>>, cx: &mut ViewContext<Self>) {
         if let Head::Editor(ref editor) = &self.head {
             editor.update(cx, |editor, cx| {
@@ -0,0 +1,379 @@
+//! The build targets of the packages in local worktrees, as declared by their `Cargo.toml` and
+//! `package.json` manifests. Each manifest's targets are offered as tasks, and the manifests at
+//! the root of a worktree tell what kinds of project it is. Manifests are parsed again whenever
+//! they change on disk.
+
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use serde::Deserialize;
+use task::{TaskTemplate, TaskTemplates};
+
+use crate::WorktreeId;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProjectKind {
+    Cargo,
+    Npm,
+}
+
+impl ProjectKind {
+    /// The kind of project whose manifest is at the given path, if it's a manifest.
+    pub fn for_manifest(path: &Path) -> Option<Self> {
+        match path.file_name()?.to_str()? {
+            "Cargo.toml" => Some(Self::Cargo),
+            "package.json" => Some(Self::Npm),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Cargo => "Rust",
+            Self::Npm => "Node",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildTargetKind {
+    Library,
+    Binary,
+    Example,
+    Test,
+    Bench,
+    /// A script of a `package.json`.
+    Script,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildTarget {
+    pub name: String,
+    pub kind: BuildTargetKind,
+}
+
+/// A parsed `Cargo.toml` or `package.json`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProjectManifest {
+    pub kind: ProjectKind,
+    /// The name of the package, which is missing for virtual Cargo workspaces.
+    pub package_name: Option<String>,
+    pub targets: Vec<BuildTarget>,
+    /// The program that runs the scripts of a `package.json`, such as `npm` or `pnpm`.
+    package_manager: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CargoManifest {
+    package: Option<CargoPackage>,
+    lib: Option<CargoTarget>,
+    #[serde(default)]
+    bin: Vec<CargoTarget>,
+    #[serde(default)]
+    example: Vec<CargoTarget>,
+    #[serde(default)]
+    test: Vec<CargoTarget>,
+    #[serde(default)]
+    bench: Vec<CargoTarget>,
+}
+
+#[derive(Deserialize)]
+struct CargoPackage {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct CargoTarget {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageJson {
+    name: Option<String>,
+    #[serde(default)]
+    scripts: BTreeMap<String, String>,
+    package_manager: Option<String>,
+}
+
+impl ProjectManifest {
+    /// Parses the manifest's contents. `is_file` tells whether a path relative to the
+    /// manifest's directory is a file, to find the targets Cargo infers from the source layout.
+    pub fn parse(
+        kind: ProjectKind,
+        contents: &str,
+        is_file: impl Fn(&Path) -> bool,
+    ) -> Result<Self> {
+        match kind {
+            ProjectKind::Cargo => Self::parse_cargo(contents, is_file),
+            ProjectKind::Npm => Self::parse_package_json(contents),
+        }
+    }
+
+    fn parse_cargo(contents: &str, is_file: impl Fn(&Path) -> bool) -> Result<Self> {
+        let manifest: CargoManifest = toml::from_str(contents).context("invalid Cargo.toml")?;
+        let package_name = manifest.package.map(|package| package.name);
+        let mut targets = Vec::new();
+        if let Some(package_name) = &package_name {
+            if manifest.lib.is_some() || is_file(Path::new("src/lib.rs")) {
+                let name = manifest
+                    .lib
+                    .and_then(|lib| lib.name)
+                    .unwrap_or_else(|| package_name.replace('-', "_"));
+                targets.push(BuildTarget {
+                    name,
+                    kind: BuildTargetKind::Library,
+                });
+            }
+            let has_main_binary = manifest
+                .bin
+                .iter()
+                .any(|bin| bin.name.as_ref() == Some(package_name));
+            if !has_main_binary && is_file(Path::new("src/main.rs")) {
+                targets.push(BuildTarget {
+                    name: package_name.clone(),
+                    kind: BuildTargetKind::Binary,
+                });
+            }
+        }
+        for (cargo_targets, kind) in [
+            (manifest.bin, BuildTargetKind::Binary),
+            (manifest.example, BuildTargetKind::Example),
+            (manifest.test, BuildTargetKind::Test),
+            (manifest.bench, BuildTargetKind::Bench),
+        ] {
+            targets.extend(cargo_targets.into_iter().filter_map(|target| {
+                Some(BuildTarget {
+                    name: target.name?,
+                    kind,
+                })
+            }));
+        }
+        Ok(Self {
+            kind: ProjectKind::Cargo,
+            package_name,
+            targets,
+            package_manager: None,
+        })
+    }
+
+    fn parse_package_json(contents: &str) -> Result<Self> {
+        let manifest: PackageJson =
+            serde_json::from_str(contents).context("invalid package.json")?;
+        // Corepack's `packageManager` field looks like `pnpm@9.1.0`.
+        let package_manager = manifest.package_manager.and_then(|package_manager| {
+            let name = package_manager.split('@').next()?;
+            ["npm", "pnpm", "yarn", "bun"]
+                .contains(&name)
+                .then(|| name.to_string())
+        });
+        Ok(Self {
+            kind: ProjectKind::Npm,
+            package_name: manifest.name,
+            targets: manifest
+                .scripts
+                .into_keys()
+                .map(|name| BuildTarget {
+                    name,
+                    kind: BuildTargetKind::Script,
+                })
+                .collect(),
+            package_manager,
+        })
+    }
+
+    /// The tasks that build, test and run the manifest's targets from the manifest's
+    /// directory. Their labels end with the package's name, to tell apart the tasks of the
+    /// packages of a worktree.
+    pub fn task_templates(&self, manifest_dir: &Path) -> TaskTemplates {
+        let package_label = self.package_name.clone().unwrap_or_else(|| {
+            manifest_dir
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+        });
+        let task = |command: &str, args: Vec<&str>| {
+            let command_line = std::iter::once(command)
+                .chain(args.iter().copied())
+                .collect::<Vec<_>>()
+                .join(" ");
+            TaskTemplate {
+                label: format!("{command_line} ({package_label})"),
+                command: command.to_string(),
+                args: args.into_iter().map(ToString::to_string).collect(),
+                cwd: Some(manifest_dir.to_string_lossy().into_owned()),
+                ..TaskTemplate::default()
+            }
+        };
+
+        let mut templates = Vec::new();
+        match self.kind {
+            ProjectKind::Cargo => {
+                if self.package_name.is_some() {
+                    templates.push(task("cargo", vec!["build"]));
+                    templates.push(task("cargo", vec!["test"]));
+                } else {
+                    templates.push(task("cargo", vec!["build", "--workspace"]));
+                    templates.push(task("cargo", vec!["test", "--workspace"]));
+                }
+                for target in &self.targets {
+                    let name = target.name.as_str();
+                    templates.extend(match target.kind {
+                        BuildTargetKind::Binary => Some(task("cargo", vec!["run", "--bin", name])),
+                        BuildTargetKind::Example => {
+                            Some(task("cargo", vec!["run", "--example", name]))
+                        }
+                        BuildTargetKind::Test => Some(task("cargo", vec!["test", "--test", name])),
+                        BuildTargetKind::Bench => {
+                            Some(task("cargo", vec!["bench", "--bench", name]))
+                        }
+                        BuildTargetKind::Library | BuildTargetKind::Script => None,
+                    });
+                }
+            }
+            ProjectKind::Npm => {
+                let package_manager = self.package_manager.as_deref().unwrap_or("npm");
+                for target in &self.targets {
+                    templates.push(task(package_manager, vec!["run", target.name.as_str()]));
+                }
+            }
+        }
+        TaskTemplates(templates)
+    }
+}
+
+/// The manifests of the local worktrees, by their paths relative to their worktree.
+#[derive(Default)]
+pub(crate) struct BuildTargets {
+    manifests: HashMap<WorktreeId, BTreeMap<Arc<Path>, ProjectManifest>>,
+}
+
+impl BuildTargets {
+    pub(crate) fn manifests(
+        &self,
+        worktree_id: WorktreeId,
+    ) -> impl Iterator<Item = (&Arc<Path>, &ProjectManifest)> {
+        self.manifests.get(&worktree_id).into_iter().flatten()
+    }
+
+    /// The kinds of the projects whose manifests are at the root of the worktree.
+    pub(crate) fn project_kinds(&self, worktree_id: WorktreeId) -> Vec<ProjectKind> {
+        let mut kinds = self
+            .manifests(worktree_id)
+            .filter(|(path, _)| path.parent() == Some(Path::new("")))
+            .map(|(_, manifest)| manifest.kind)
+            .collect::<Vec<_>>();
+        kinds.sort();
+        kinds.dedup();
+        kinds
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        worktree_id: WorktreeId,
+        path: Arc<Path>,
+        manifest: ProjectManifest,
+    ) {
+        self.manifests
+            .entry(worktree_id)
+            .or_default()
+            .insert(path, manifest);
+    }
+
+    /// Forgets the manifest at the given path, returning whether it was known.
+    pub(crate) fn remove(&mut self, worktree_id: WorktreeId, path: &Path) -> bool {
+        self.manifests
+            .get_mut(&worktree_id)
+            .map_or(false, |manifests| manifests.remove(path).is_some())
+    }
+
+    pub(crate) fn remove_worktree(&mut self, worktree_id: WorktreeId) {
+        self.manifests.remove(&worktree_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unindent::Unindent as _;
+
+    #[test]
+    fn test_parse_cargo_manifest() {
+        let contents = r#"
+            [package]
+            name = "my-app"
+
+            [[bin]]
+            name = "helper"
+            path = "src/helper.rs"
+
+            [[example]]
+            name = "demo"
+        "#
+        .unindent();
+        let manifest = ProjectManifest::parse(ProjectKind::Cargo, &contents, |path| {
+            path == Path::new("src/main.rs") || path == Path::new("src/lib.rs")
+        })
+        .unwrap();
+        assert_eq!(manifest.package_name.as_deref(), Some("my-app"));
+        assert_eq!(
+            manifest
+                .targets
+                .iter()
+                .map(|target| (target.name.as_str(), target.kind))
+                .collect::<Vec<_>>(),
+            [
+                ("my_app", BuildTargetKind::Library),
+                ("my-app", BuildTargetKind::Binary),
+                ("helper", BuildTargetKind::Binary),
+                ("demo", BuildTargetKind::Example),
+            ]
+        );
+        assert_eq!(
+            manifest
+                .task_templates(Path::new("/code/my-app"))
+                .0
+                .iter()
+                .map(|template| template.label.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "cargo build (my-app)",
+                "cargo test (my-app)",
+                "cargo run --bin my-app (my-app)",
+                "cargo run --bin helper (my-app)",
+                "cargo run --example demo (my-app)",
+            ]
+        );
+
+        let workspace = ProjectManifest::parse(
+            ProjectKind::Cargo,
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+            |_| true,
+        )
+        .unwrap();
+        assert_eq!(workspace.package_name, None);
+        assert_eq!(workspace.targets, []);
+        assert!(ProjectManifest::parse(ProjectKind::Cargo, "[package", |_| false).is_err());
+    }
+
+    #[test]
+    fn test_parse_package_json() {
+        let contents = r#"{
+            "name": "web",
+            "packageManager": "pnpm@9.1.0",
+            "scripts": { "test": "vitest", "build": "vite build" }
+        }"#;
+        let manifest = ProjectManifest::parse(ProjectKind::Npm, contents, |_| false).unwrap();
+        let templates = manifest.task_templates(Path::new("/code/web")).0;
+        assert_eq!(
+            templates
+                .iter()
+                .map(|template| template.label.as_str())
+                .collect::<Vec<_>>(),
+            ["pnpm run build (web)", "pnpm run test (web)"]
+        );
+        assert_eq!(templates[0].command, "pnpm");
+        assert_eq!(templates[0].cwd.as_deref(), Some("/code/web"));
+    }
+}
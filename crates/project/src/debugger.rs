@@ -0,0 +1,701 @@
+//! Debugging sessions, each driving a debug adapter through the Debug Adapter Protocol, and the
+//! breakpoints that the sessions set. Breakpoints are anchored in buffers, so that they stay on
+//! their lines while the buffers are edited, and they're sent to the adapters as line numbers.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use dap::{
+    protocol::{
+        Attach, Capabilities, ConfigurationDone, Continue, Continued, Disconnect,
+        DisconnectArguments, Exited, Initialize, InitializeArguments, Initialized, Launch, Next,
+        Output, Pause, Request, Scope, Scopes, ScopesArguments, SetBreakpoints,
+        SetBreakpointsArguments, Source, SourceBreakpoint, StackFrame, StackTrace,
+        StackTraceArguments, StepIn, StepOut, Stopped, StoppedEventBody, Terminated, Thread,
+        ThreadArguments, Threads, Variable, Variables, VariablesArguments,
+    },
+    DebugAdapterClient, DebugAdapterId, DebugConfig, DebugConfigs, DebugRequestKind,
+};
+use futures::{channel::oneshot, future, FutureExt as _};
+use gpui::{AppContext, Context as _, EventEmitter, Model, ModelContext, Task, WeakModel};
+use language::{Buffer, Point, ToPoint as _};
+use serde_json::Value;
+use text::{Anchor, BufferId};
+use util::{paths::LOCAL_DEBUG_RELATIVE_PATH, post_inc, ResultExt};
+use worktree::WorktreeId;
+
+use crate::{Event, Project};
+
+/// The breakpoints of the project. The buffers that have breakpoints are kept open, so that the
+/// breakpoints aren't lost when the buffers are closed.
+#[derive(Default)]
+pub(crate) struct Breakpoints {
+    by_buffer: HashMap<BufferId, (Model<Buffer>, Vec<Anchor>)>,
+}
+
+impl Breakpoints {
+    /// Adds a breakpoint on the row if there isn't one, and removes it otherwise.
+    fn toggle(&mut self, buffer: &Model<Buffer>, row: u32, cx: &AppContext) {
+        let snapshot = buffer.read(cx);
+        let (_, positions) = self
+            .by_buffer
+            .entry(snapshot.remote_id())
+            .or_insert_with(|| (buffer.clone(), Vec::new()));
+        if let Some(ix) = positions
+            .iter()
+            .position(|position| position.to_point(snapshot).row == row)
+        {
+            positions.remove(ix);
+            if positions.is_empty() {
+                self.by_buffer.remove(&snapshot.remote_id());
+            }
+        } else {
+            positions.push(snapshot.anchor_before(Point::new(row, 0)));
+        }
+    }
+
+    fn rows(&self, buffer: &Buffer) -> Vec<u32> {
+        let mut rows = self
+            .by_buffer
+            .get(&buffer.remote_id())
+            .map(|(_, positions)| {
+                positions
+                    .iter()
+                    .map(|position| position.to_point(buffer).row)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        rows.sort_unstable();
+        rows.dedup();
+        rows
+    }
+
+    /// The breakpoints of a buffer, as they're sent to debug adapters.
+    fn for_buffer(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Option<(PathBuf, Vec<SourceBreakpoint>)> {
+        let buffer = buffer.read(cx);
+        let abs_path = buffer.file()?.as_local()?.abs_path(cx);
+        let breakpoints = self
+            .rows(buffer)
+            .into_iter()
+            .map(|row| SourceBreakpoint {
+                line: row + 1,
+                condition: None,
+            })
+            .collect();
+        Some((abs_path, breakpoints))
+    }
+
+    fn all(&self, cx: &AppContext) -> Vec<(PathBuf, Vec<SourceBreakpoint>)> {
+        self.by_buffer
+            .values()
+            .filter_map(|(buffer, _)| self.for_buffer(buffer, cx))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DebugSessionStatus {
+    Starting,
+    Running,
+    Stopped { thread_id: u64, reason: String },
+    Exited,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DebugSessionEvent {
+    /// The debuggee stopped, and the stack of the stopped thread was loaded.
+    Stopped,
+    Continued,
+    Output,
+    Exited,
+}
+
+/// A debug adapter that's debugging a program, and the state of the program when it's stopped:
+/// its threads, the stack of the thread that stopped, and the scopes and variables of its
+/// frames, which are loaded as they're requested.
+pub struct DebugSession {
+    id: DebugAdapterId,
+    worktree_id: WorktreeId,
+    config: DebugConfig,
+    client: Arc<DebugAdapterClient>,
+    capabilities: Capabilities,
+    status: DebugSessionStatus,
+    threads: Vec<Thread>,
+    stack_frames: Vec<StackFrame>,
+    scopes: HashMap<u64, Vec<Scope>>,
+    variables: HashMap<u64, Vec<Variable>>,
+    output: String,
+    initialized_tx: Option<oneshot::Sender<()>>,
+    _load_stack: Task<()>,
+}
+
+impl EventEmitter<DebugSessionEvent> for DebugSession {}
+
+impl DebugSession {
+    fn new(
+        id: DebugAdapterId,
+        worktree_id: WorktreeId,
+        config: DebugConfig,
+        client: Arc<DebugAdapterClient>,
+        cx: &mut ModelContext<Self>,
+    ) -> Self {
+        let this = cx.weak_model();
+        client.on_event::<Initialized, _>({
+            let this = this.clone();
+            move |_, mut cx| {
+                this.update(&mut cx, |this, _| {
+                    if let Some(initialized_tx) = this.initialized_tx.take() {
+                        initialized_tx.send(()).ok();
+                    }
+                })
+                .ok();
+            }
+        });
+        client.on_event::<Stopped, _>({
+            let this = this.clone();
+            move |body, mut cx| {
+                this.update(&mut cx, |this, cx| this.handle_stopped(body, cx))
+                    .ok();
+            }
+        });
+        client.on_event::<Continued, _>({
+            let this = this.clone();
+            move |_, mut cx| {
+                this.update(&mut cx, |this, cx| this.handle_continued(cx))
+                    .ok();
+            }
+        });
+        client.on_event::<Output, _>({
+            let this = this.clone();
+            move |body, mut cx| {
+                this.update(&mut cx, |this, cx| {
+                    this.output.push_str(&body.output);
+                    cx.emit(DebugSessionEvent::Output);
+                    cx.notify();
+                })
+                .ok();
+            }
+        });
+        client.on_event::<Exited, _>({
+            let this = this.clone();
+            move |body, mut cx| {
+                this.update(&mut cx, |this, cx| {
+                    this.output
+                        .push_str(&format!("Exited with code {}\n", body.exit_code));
+                    cx.notify();
+                })
+                .ok();
+            }
+        });
+        client.on_event::<Terminated, _>(move |_, mut cx| {
+            this.update(&mut cx, |this, cx| this.handle_exited(cx)).ok();
+        });
+
+        Self {
+            id,
+            worktree_id,
+            config,
+            client,
+            capabilities: Capabilities::default(),
+            status: DebugSessionStatus::Starting,
+            threads: Vec::new(),
+            stack_frames: Vec::new(),
+            scopes: HashMap::default(),
+            variables: HashMap::default(),
+            output: String::new(),
+            initialized_tx: None,
+            _load_stack: Task::ready(()),
+        }
+    }
+
+    pub fn id(&self) -> DebugAdapterId {
+        self.id
+    }
+
+    pub fn worktree_id(&self) -> WorktreeId {
+        self.worktree_id
+    }
+
+    pub fn config(&self) -> &DebugConfig {
+        &self.config
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    pub fn status(&self) -> &DebugSessionStatus {
+        &self.status
+    }
+
+    pub fn threads(&self) -> &[Thread] {
+        &self.threads
+    }
+
+    /// The stack of the stopped thread, innermost frame first.
+    pub fn stack_frames(&self) -> &[StackFrame] {
+        &self.stack_frames
+    }
+
+    /// The scopes of a frame, if they've been loaded with [`Self::load_scopes`].
+    pub fn scopes(&self, frame_id: u64) -> Option<&[Scope]> {
+        self.scopes.get(&frame_id).map(Vec::as_slice)
+    }
+
+    /// The variables of a scope or of a structured variable, if they've been loaded with
+    /// [`Self::load_variables`].
+    pub fn variables(&self, variables_reference: u64) -> Option<&[Variable]> {
+        self.variables.get(&variables_reference).map(Vec::as_slice)
+    }
+
+    /// What the debuggee and the adapter printed.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Initializes the adapter, sets the project's breakpoints, and then launches or attaches
+    /// to the debuggee.
+    fn start(
+        &mut self,
+        project: WeakModel<Project>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let client = self.client.clone();
+        let config = self.config.clone();
+        let (initialized_tx, initialized_rx) = oneshot::channel();
+        self.initialized_tx = Some(initialized_tx);
+        cx.spawn(|this, mut cx| async move {
+            let capabilities = client
+                .request::<Initialize>(InitializeArguments {
+                    client_id: Some("zed".into()),
+                    client_name: Some("Zed".into()),
+                    adapter_id: config.adapter.adapter_id(),
+                    lines_start_at1: Some(true),
+                    columns_start_at1: Some(true),
+                    path_format: Some("path".into()),
+                    supports_variable_type: Some(true),
+                })
+                .await?;
+            this.update(&mut cx, |this, _| this.capabilities = capabilities.clone())?;
+
+            // Adapters may only answer the launch request once they're configured, so the
+            // breakpoints are set as soon as the adapter is initialized.
+            let arguments = Value::Object(config.arguments.clone());
+            let mut start = match config.request {
+                DebugRequestKind::Launch => client.request::<Launch>(arguments).boxed(),
+                DebugRequestKind::Attach => client.request::<Attach>(arguments).boxed(),
+            }
+            .fuse();
+            let mut initialized = initialized_rx.fuse();
+            let started = futures::select_biased! {
+                _ = initialized => false,
+                result = start => {
+                    result?;
+                    true
+                }
+            };
+            if started {
+                initialized.await.ok();
+            }
+
+            let breakpoints = project.update(&mut cx, |project, cx| project.breakpoints.all(cx))?;
+            future::join_all(breakpoints.into_iter().map(|(abs_path, breakpoints)| {
+                client
+                    .request::<SetBreakpoints>(set_breakpoints_arguments(&abs_path, breakpoints))
+                    .map(|response| response.log_err())
+            }))
+            .await;
+            if capabilities.supports_configuration_done_request == Some(true) {
+                client.request::<ConfigurationDone>(()).await?;
+            }
+            if !started {
+                start.await?;
+            }
+
+            this.update(&mut cx, |this, cx| {
+                if this.status == DebugSessionStatus::Starting {
+                    this.status = DebugSessionStatus::Running;
+                }
+                cx.notify();
+            })
+        })
+    }
+
+    fn handle_stopped(&mut self, body: StoppedEventBody, cx: &mut ModelContext<Self>) {
+        let client = self.client.clone();
+        self._load_stack = cx.spawn(|this, mut cx| {
+            async move {
+                let threads = client.request::<Threads>(()).await?.threads;
+                let thread_id = body
+                    .thread_id
+                    .or_else(|| threads.first().map(|thread| thread.id))
+                    .context("no stopped thread")?;
+                let stack_frames = client
+                    .request::<StackTrace>(StackTraceArguments {
+                        thread_id,
+                        start_frame: None,
+                        levels: None,
+                    })
+                    .await?
+                    .stack_frames;
+                let top_frame_id = stack_frames.first().map(|frame| frame.id);
+
+                this.update(&mut cx, |this, cx| {
+                    this.status = DebugSessionStatus::Stopped {
+                        thread_id,
+                        reason: body.reason,
+                    };
+                    this.threads = threads;
+                    this.stack_frames = stack_frames;
+                    this.scopes.clear();
+                    this.variables.clear();
+                    cx.emit(DebugSessionEvent::Stopped);
+                    cx.notify();
+                })?;
+
+                if let Some(frame_id) = top_frame_id {
+                    this.update(&mut cx, |this, cx| this.load_scopes(frame_id, cx))?
+                        .await?;
+                }
+                anyhow::Ok(())
+            }
+            .map(|result| {
+                result.log_err();
+            })
+        });
+    }
+
+    fn handle_continued(&mut self, cx: &mut ModelContext<Self>) {
+        if self.status == DebugSessionStatus::Exited {
+            return;
+        }
+        self.status = DebugSessionStatus::Running;
+        self.stack_frames.clear();
+        self.scopes.clear();
+        self.variables.clear();
+        self._load_stack = Task::ready(());
+        cx.emit(DebugSessionEvent::Continued);
+        cx.notify();
+    }
+
+    fn handle_exited(&mut self, cx: &mut ModelContext<Self>) {
+        if self.status == DebugSessionStatus::Exited {
+            return;
+        }
+        self.status = DebugSessionStatus::Exited;
+        self.stack_frames.clear();
+        self.scopes.clear();
+        self.variables.clear();
+        self._load_stack = Task::ready(());
+        cx.emit(DebugSessionEvent::Exited);
+        cx.notify();
+    }
+
+    /// Loads the scopes of a frame, along with the variables of the scopes that are cheap to
+    /// load.
+    pub fn load_scopes(&mut self, frame_id: u64, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        let scopes = self.client.request::<Scopes>(ScopesArguments { frame_id });
+        cx.spawn(|this, mut cx| async move {
+            let scopes = scopes.await?.scopes;
+            let load_variables = this.update(&mut cx, |this, cx| {
+                let load_variables = scopes
+                    .iter()
+                    .filter(|scope| !scope.expensive)
+                    .map(|scope| this.load_variables(scope.variables_reference, cx))
+                    .collect::<Vec<_>>();
+                this.scopes.insert(frame_id, scopes);
+                cx.notify();
+                load_variables
+            })?;
+            for result in future::join_all(load_variables).await {
+                result.log_err();
+            }
+            Ok(())
+        })
+    }
+
+    /// Loads the variables of a scope or the children of a structured variable.
+    pub fn load_variables(
+        &mut self,
+        variables_reference: u64,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let variables = self.client.request::<Variables>(VariablesArguments {
+            variables_reference,
+        });
+        cx.spawn(|this, mut cx| async move {
+            let variables = variables.await?.variables;
+            this.update(&mut cx, |this, cx| {
+                this.variables.insert(variables_reference, variables);
+                cx.notify();
+            })
+        })
+    }
+
+    pub fn continue_(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<Continue>(cx)
+    }
+
+    pub fn step_over(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<Next>(cx)
+    }
+
+    pub fn step_into(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<StepIn>(cx)
+    }
+
+    pub fn step_out(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<StepOut>(cx)
+    }
+
+    /// Resumes the stopped thread with a request that continues or steps it.
+    fn resume<R>(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>>
+    where
+        R: Request<Arguments = ThreadArguments>,
+    {
+        let DebugSessionStatus::Stopped { thread_id, .. } = self.status else {
+            return Task::ready(Err(anyhow!("the debuggee isn't stopped")));
+        };
+        let request = self.client.request::<R>(ThreadArguments { thread_id });
+        self.handle_continued(cx);
+        cx.background_executor().spawn(async move {
+            request.await?;
+            Ok(())
+        })
+    }
+
+    pub fn pause(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        if self.status != DebugSessionStatus::Running {
+            return Task::ready(Err(anyhow!("the debuggee isn't running")));
+        }
+        let client = self.client.clone();
+        let thread_id = self.threads.first().map(|thread| thread.id);
+        cx.background_executor().spawn(async move {
+            let thread_id = match thread_id {
+                Some(thread_id) => thread_id,
+                None => {
+                    client
+                        .request::<Threads>(())
+                        .await?
+                        .threads
+                        .first()
+                        .context("no threads to pause")?
+                        .id
+                }
+            };
+            client.request::<Pause>(ThreadArguments { thread_id }).await
+        })
+    }
+
+    /// Ends the session, terminating the debuggee if the adapter launched it.
+    pub fn stop(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        let disconnect = self.client.request::<Disconnect>(DisconnectArguments {
+            restart: Some(false),
+            terminate_debuggee: (self.config.request == DebugRequestKind::Launch).then_some(true),
+        });
+        cx.spawn(|this, mut cx| async move {
+            let result = disconnect.await;
+            this.update(&mut cx, |this, cx| {
+                this.handle_exited(cx);
+                this.client.kill();
+            })?;
+            result
+        })
+    }
+
+    fn set_breakpoints(
+        &mut self,
+        abs_path: &Path,
+        breakpoints: Vec<SourceBreakpoint>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if matches!(
+            self.status,
+            DebugSessionStatus::Starting | DebugSessionStatus::Exited
+        ) {
+            return;
+        }
+        let request = self
+            .client
+            .request::<SetBreakpoints>(set_breakpoints_arguments(abs_path, breakpoints));
+        cx.background_executor()
+            .spawn(async move { request.await.map(drop) })
+            .detach_and_log_err(cx);
+    }
+}
+
+fn set_breakpoints_arguments(
+    abs_path: &Path,
+    breakpoints: Vec<SourceBreakpoint>,
+) -> SetBreakpointsArguments {
+    SetBreakpointsArguments {
+        source: Source {
+            name: abs_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            path: Some(abs_path.to_string_lossy().into_owned()),
+            source_reference: None,
+        },
+        breakpoints,
+    }
+}
+
+impl Project {
+    /// The debug configurations of the project's worktrees, from their `.zed/debug.json` files.
+    pub fn debug_configs(&self, cx: &AppContext) -> Task<Vec<(WorktreeId, DebugConfig)>> {
+        let fs = self.fs.clone();
+        let config_paths = self
+            .visible_worktrees(cx)
+            .filter_map(|worktree| {
+                let worktree = worktree.read(cx).as_local()?;
+                Some((
+                    worktree.id(),
+                    worktree.abs_path().join(*LOCAL_DEBUG_RELATIVE_PATH),
+                ))
+            })
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            let mut configs = Vec::new();
+            for (worktree_id, config_path) in config_paths {
+                let Ok(contents) = fs.load(&config_path).await else {
+                    continue;
+                };
+                if let Some(DebugConfigs(worktree_configs)) =
+                    serde_json_lenient::from_str::<DebugConfigs>(&contents)
+                        .with_context(|| format!("invalid debug configurations in {config_path:?}"))
+                        .log_err()
+                {
+                    configs.extend(
+                        worktree_configs
+                            .into_iter()
+                            .map(|config| (worktree_id, config)),
+                    );
+                }
+            }
+            configs
+        })
+    }
+
+    /// Starts the configuration's debug adapter in the worktree, and launches or attaches to
+    /// the debuggee with it.
+    pub fn start_debug_session(
+        &mut self,
+        worktree_id: WorktreeId,
+        config: DebugConfig,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<DebugSession>>> {
+        if !self.is_local() {
+            return Task::ready(Err(anyhow!(
+                "debugging is only supported in local projects"
+            )));
+        }
+        let Some(working_dir) = self
+            .worktree_for_id(worktree_id, cx)
+            .and_then(|worktree| Some(worktree.read(cx).as_local()?.abs_path().to_path_buf()))
+        else {
+            return Task::ready(Err(anyhow!("no worktree found with id {worktree_id:?}")));
+        };
+        let id = DebugAdapterId(post_inc(&mut self.next_debug_adapter_id));
+
+        cx.spawn(move |this, mut cx| async move {
+            let client = Arc::new(DebugAdapterClient::new(
+                id,
+                &config.adapter.binary(),
+                &working_dir,
+                cx.clone(),
+            )?);
+            let session = this.update(&mut cx, |this, cx| {
+                this.add_debug_session(worktree_id, config, client, cx)
+            })?;
+            let start = session.update(&mut cx, |session, cx| session.start(this.clone(), cx))?;
+            if let Err(error) = start.await {
+                session
+                    .update(&mut cx, |session, cx| session.stop(cx))?
+                    .await
+                    .log_err();
+                return Err(error);
+            }
+            Ok(session)
+        })
+    }
+
+    fn add_debug_session(
+        &mut self,
+        worktree_id: WorktreeId,
+        config: DebugConfig,
+        client: Arc<DebugAdapterClient>,
+        cx: &mut ModelContext<Self>,
+    ) -> Model<DebugSession> {
+        let session =
+            cx.new_model(|cx| DebugSession::new(client.id(), worktree_id, config, client, cx));
+        cx.subscribe(&session, |this, session, event, cx| {
+            if event == &DebugSessionEvent::Exited {
+                this.debug_sessions.retain(|other| other != &session);
+                cx.notify();
+            }
+        })
+        .detach();
+        self.debug_sessions.push(session.clone());
+        cx.notify();
+        session
+    }
+
+    /// The sessions that are debugging, in the order they were started.
+    pub fn debug_sessions(&self) -> &[Model<DebugSession>] {
+        &self.debug_sessions
+    }
+
+    /// Adds a breakpoint on the buffer's row, or removes the one that's there, and updates
+    /// the breakpoints of the running sessions.
+    pub fn toggle_breakpoint(
+        &mut self,
+        buffer: &Model<Buffer>,
+        row: u32,
+        cx: &mut ModelContext<Self>,
+    ) {
+        self.breakpoints.toggle(buffer, row, cx);
+        if let Some((abs_path, breakpoints)) = self.breakpoints.for_buffer(buffer, cx) {
+            for session in &self.debug_sessions {
+                session.update(cx, |session, cx| {
+                    session.set_breakpoints(&abs_path, breakpoints.clone(), cx)
+                });
+            }
+        }
+        cx.emit(Event::BreakpointsChanged(buffer.read(cx).remote_id()));
+    }
+
+    /// The rows of the buffer that have breakpoints, in ascending order.
+    pub fn breakpoint_rows(&self, buffer: &Buffer) -> Vec<u32> {
+        self.breakpoints.rows(buffer)
+    }
+}
+
+#[cfg(test)]
+impl Project {
+    pub(crate) fn add_fake_debug_session(
+        &mut self,
+        worktree_id: WorktreeId,
+        config: DebugConfig,
+        cx: &mut ModelContext<Self>,
+    ) -> (Model<DebugSession>, dap::FakeDebugAdapter) {
+        let id = DebugAdapterId(post_inc(&mut self.next_debug_adapter_id));
+        let (client, adapter) = dap::FakeDebugAdapter::new(id, cx.to_async());
+        let session = self.add_debug_session(worktree_id, config, Arc::new(client), cx);
+        (session, adapter)
+    }
+
+    pub(crate) fn start_fake_debug_session(
+        &mut self,
+        session: &Model<DebugSession>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let project = cx.weak_model();
+        session.update(cx, |session, cx| session.start(project, cx))
+    }
+}
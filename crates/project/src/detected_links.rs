@@ -0,0 +1,133 @@
+//! Links written in buffers' text: URLs, and references to files in the project such as
+//! `src/main.rs:10:5`, which point at a position in the file.
+
+use std::{ops::Range, sync::OnceLock};
+
+use language::{Anchor, Point};
+use linkify::{LinkFinder, LinkKind};
+use regex::Regex;
+
+use crate::ProjectPath;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetectedLink {
+    pub range: Range<Anchor>,
+    pub target: LinkTarget,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkTarget {
+    Url(String),
+    /// A file in the project, along with the position the reference points at. The position
+    /// may lie past the end of its line or of the file.
+    File(ProjectPath, Point),
+}
+
+/// A link found in text, before file references are resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LinkText<'a> {
+    Url(&'a str),
+    /// A path with a 1-based line number and an optional column.
+    Path {
+        path: &'a str,
+        row: u32,
+        column: Option<u32>,
+    },
+}
+
+/// The 0-based position that a file reference with a 1-based line and column points at.
+pub(crate) fn position_in_file(row: u32, column: Option<u32>) -> Point {
+    Point::new(row.saturating_sub(1), column.unwrap_or(1).saturating_sub(1))
+}
+
+/// Finds the URLs in the text, and the file references with a line number, in the order they
+/// appear. Paths need a `/` or a `.`, so that labels such as `line:10` aren't mistaken for
+/// them.
+pub(crate) fn find_links(text: &str) -> Vec<(Range<usize>, LinkText<'_>)> {
+    static PATH_REGEX: OnceLock<Regex> = OnceLock::new();
+    let path_regex = PATH_REGEX
+        .get_or_init(|| Regex::new(r"(?:[A-Za-z]:)?[\w.\-/\\]+:(\d+)(?::(\d+))?").unwrap());
+
+    let mut finder = LinkFinder::new();
+    finder.kinds(&[LinkKind::Url]);
+    let mut links = finder
+        .links(text)
+        .map(|link| (link.start()..link.end(), LinkText::Url(link.as_str())))
+        .collect::<Vec<_>>();
+
+    for captures in path_regex.captures_iter(text) {
+        let found = captures.get(0).unwrap();
+        let range = found.start()..found.end();
+        if links
+            .iter()
+            .any(|(url_range, _)| url_range.start < range.end && range.start < url_range.end)
+        {
+            continue;
+        }
+        let path_end = captures.get(1).unwrap().start() - 1;
+        let path = &text[range.start..path_end];
+        if !path.contains(['/', '\\', '.']) {
+            continue;
+        }
+        let Ok(row) = captures[1].parse() else {
+            continue;
+        };
+        let column = captures
+            .get(2)
+            .and_then(|column| column.as_str().parse().ok());
+        links.push((range, LinkText::Path { path, row, column }));
+    }
+
+    links.sort_by_key(|(range, _)| range.start);
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_links() {
+        let text = "see https://zed.dev/docs at src/main.rs:10:5 (./lib.rs:3), line:4 or \
+            127.0.0.1:8080";
+        let links = find_links(text)
+            .into_iter()
+            .map(|(range, link)| (&text[range], link))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            [
+                (
+                    "https://zed.dev/docs",
+                    LinkText::Url("https://zed.dev/docs")
+                ),
+                (
+                    "src/main.rs:10:5",
+                    LinkText::Path {
+                        path: "src/main.rs",
+                        row: 10,
+                        column: Some(5)
+                    }
+                ),
+                (
+                    "./lib.rs:3",
+                    LinkText::Path {
+                        path: "./lib.rs",
+                        row: 3,
+                        column: None
+                    }
+                ),
+                (
+                    "127.0.0.1:8080",
+                    LinkText::Path {
+                        path: "127.0.0.1",
+                        row: 8080,
+                        column: None
+                    }
+                ),
+            ]
+        );
+        assert_eq!(position_in_file(10, Some(5)), Point::new(9, 4));
+        assert_eq!(position_in_file(0, None), Point::new(0, 0));
+    }
+}
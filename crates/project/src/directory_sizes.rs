@@ -0,0 +1,200 @@
+//! The sizes of the directories in local worktrees, computed on demand by walking the file
+//! system, since ignored and unexpanded directories aren't scanned. A directory's size is
+//! cached until a change within it is observed. Worktrees don't report the changes within
+//! ignored directories, so the worktrees whose sizes are measured are watched separately.
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use collections::HashMap;
+use fs::Fs;
+use futures::{
+    future::{BoxFuture, Shared},
+    FutureExt,
+};
+use gpui::{BackgroundExecutor, Task};
+use util::ResultExt;
+
+use crate::{ProjectPath, WorktreeId};
+
+#[derive(Default)]
+pub(crate) struct DirectorySizes {
+    sizes: HashMap<ProjectPath, Shared<Task<Result<u64, Arc<anyhow::Error>>>>>,
+    /// Incremented whenever sizes are invalidated, so that sizes that were measured while
+    /// the file system changed aren't cached.
+    generation: usize,
+    /// The tasks watching the worktrees whose sizes were measured.
+    watchers: HashMap<WorktreeId, Task<()>>,
+}
+
+/// The size of a file or directory, as listed by [`Project::largest_paths`].
+///
+/// [`Project::largest_paths`]: crate::Project::largest_paths
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathSize {
+    pub path: ProjectPath,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+impl DirectorySizes {
+    /// Returns the size of the directory at `abs_path`, measuring it unless it's cached.
+    /// Failed measurements aren't cached, so they're retried.
+    pub(crate) fn size(
+        &mut self,
+        project_path: ProjectPath,
+        abs_path: PathBuf,
+        fs: Arc<dyn Fs>,
+        executor: &BackgroundExecutor,
+    ) -> Shared<Task<Result<u64, Arc<anyhow::Error>>>> {
+        let failed = self
+            .sizes
+            .get(&project_path)
+            .and_then(|size| size.peek())
+            .map_or(false, |size| size.is_err());
+        if failed {
+            self.sizes.remove(&project_path);
+        }
+        self.sizes
+            .entry(project_path)
+            .or_insert_with(|| {
+                executor
+                    .spawn(async move {
+                        measure_dir(fs.as_ref(), &abs_path, &mut |_, _, _| {})
+                            .await
+                            .map_err(Arc::new)
+                    })
+                    .shared()
+            })
+            .clone()
+    }
+
+    /// Returns the size of the given directory if it was already measured.
+    pub(crate) fn cached_size(&self, project_path: &ProjectPath) -> Option<u64> {
+        self.sizes.get(project_path)?.peek()?.as_ref().ok().copied()
+    }
+
+    pub(crate) fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Caches sizes that were measured when the sizes were at the given generation, unless
+    /// they were invalidated since then.
+    pub(crate) fn insert(
+        &mut self,
+        generation: usize,
+        sizes: impl IntoIterator<Item = (ProjectPath, u64)>,
+    ) {
+        if generation != self.generation {
+            return;
+        }
+        for (project_path, size) in sizes {
+            self.sizes
+                .insert(project_path, Task::ready(Ok(size)).shared());
+        }
+    }
+
+    /// Forgets the sizes of the directories containing any of the changed paths, and of the
+    /// directories within them, which may have been removed or renamed.
+    pub(crate) fn invalidate<'a>(
+        &mut self,
+        worktree_id: WorktreeId,
+        changed_paths: impl IntoIterator<Item = &'a Path>,
+    ) {
+        self.generation += 1;
+        for changed_path in changed_paths {
+            self.sizes.retain(|project_path, _| {
+                project_path.worktree_id != worktree_id
+                    || !(changed_path.starts_with(&project_path.path)
+                        || project_path.path.starts_with(changed_path))
+            });
+        }
+    }
+
+    pub(crate) fn is_watching(&self, worktree_id: WorktreeId) -> bool {
+        self.watchers.contains_key(&worktree_id)
+    }
+
+    /// Keeps the task that invalidates the sizes within a worktree when it changes.
+    pub(crate) fn watch(&mut self, worktree_id: WorktreeId, watcher: Task<()>) {
+        self.watchers.insert(worktree_id, watcher);
+    }
+
+    pub(crate) fn remove_worktree(&mut self, worktree_id: WorktreeId) {
+        self.generation += 1;
+        self.watchers.remove(&worktree_id);
+        self.sizes
+            .retain(|project_path, _| project_path.worktree_id != worktree_id);
+    }
+}
+
+/// Measures the directory at `abs_path`, returning the absolute paths and sizes of the
+/// `limit` largest files and directories within it, largest first, along with the sizes of
+/// the directory itself and of all of the directories within it.
+pub(crate) async fn measure_largest_paths(
+    fs: &dyn Fs,
+    abs_path: &Path,
+    limit: usize,
+) -> Result<(Vec<(PathBuf, u64, bool)>, Vec<(PathBuf, u64)>)> {
+    let mut largest_paths = BinaryHeap::new();
+    let mut dir_sizes = Vec::new();
+    let total_size = measure_dir(fs, abs_path, &mut |path, size, is_dir| {
+        if is_dir {
+            dir_sizes.push((path.to_path_buf(), size));
+        }
+        largest_paths.push(Reverse((size, path.to_path_buf(), is_dir)));
+        if largest_paths.len() > limit {
+            largest_paths.pop();
+        }
+    })
+    .await?;
+    dir_sizes.push((abs_path.to_path_buf(), total_size));
+
+    let largest_paths = largest_paths
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((size, path, is_dir))| (path, size, is_dir))
+        .collect();
+    Ok((largest_paths, dir_sizes))
+}
+
+/// Returns the total size of the files within the directory at `abs_path`, recursively,
+/// reporting the size of each file and directory within it. Symlinks aren't followed, and
+/// subdirectories that can't be read are skipped.
+fn measure_dir<'a, 'f>(
+    fs: &'a dyn Fs,
+    abs_path: &'a Path,
+    on_measured: &'a mut (dyn FnMut(&Path, u64, bool) + Send + 'f),
+) -> BoxFuture<'a, Result<u64>> {
+    async move {
+        let mut total_size = 0;
+        for child in fs.read_dir_metadata(abs_path).await? {
+            let Ok(Some(metadata)) = child.metadata else {
+                continue;
+            };
+            if metadata.is_symlink {
+                continue;
+            }
+            let size = if metadata.is_dir {
+                let Some(size) = measure_dir(fs, &child.path, &mut *on_measured)
+                    .await
+                    .log_err()
+                else {
+                    continue;
+                };
+                size
+            } else {
+                metadata.len
+            };
+            on_measured(&child.path, size, metadata.is_dir);
+            total_size += size;
+        }
+        Ok(total_size)
+    }
+    .boxed()
+}
@@ -0,0 +1,207 @@
+//! Colors written in buffers, such as `#ff8800` or `rgb(255 136 0)`. They're reported by
+//! language servers that support `textDocument/documentColor`, and otherwise found by matching
+//! the CSS color syntax in the buffer's text.
+
+use std::{ops::Range, sync::OnceLock};
+
+use gpui::{hsla, Hsla, Rgba};
+use language::{Anchor, BufferSnapshot};
+use regex::Regex;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocumentColor {
+    pub range: Range<Anchor>,
+    pub color: Rgba,
+}
+
+/// The syntaxes that colors can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorFormat {
+    /// `#rrggbb`, or `#rrggbbaa` for translucent colors.
+    Hex,
+    /// `rgb(r, g, b)`, or `rgba(r, g, b, a)` for translucent colors.
+    Rgb,
+    /// `hsl(h, s%, l%)`, or `hsla(h, s%, l%, a)` for translucent colors.
+    Hsl,
+}
+
+impl ColorFormat {
+    /// The format that color conversions cycle to from this one.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Hex => Self::Rgb,
+            Self::Rgb => Self::Hsl,
+            Self::Hsl => Self::Hex,
+        }
+    }
+}
+
+/// Parses a color written in CSS syntax, as a hex color or with the `rgb()`, `rgba()`, `hsl()`
+/// or `hsla()` functions, returning the color and the format it's written in.
+pub fn parse_color(text: &str) -> Option<(Rgba, ColorFormat)> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix('#') {
+        return parse_hex(hex).map(|color| (color, ColorFormat::Hex));
+    }
+
+    let (function, arguments) = text.strip_suffix(')')?.split_once('(')?;
+    let arguments = arguments
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|argument| !argument.is_empty())
+        .collect::<Vec<_>>();
+    if arguments.len() != 3 && arguments.len() != 4 {
+        return None;
+    }
+    let alpha = match arguments.get(3) {
+        Some(alpha) => parse_component(alpha, 1.)?,
+        None => 1.,
+    };
+    match function.to_ascii_lowercase().as_str() {
+        "rgb" | "rgba" => {
+            let r = parse_component(arguments[0], 255.)?;
+            let g = parse_component(arguments[1], 255.)?;
+            let b = parse_component(arguments[2], 255.)?;
+            Some((Rgba { r, g, b, a: alpha }, ColorFormat::Rgb))
+        }
+        "hsl" | "hsla" => {
+            let hue = arguments[0]
+                .strip_suffix("deg")
+                .unwrap_or(arguments[0])
+                .parse::<f32>()
+                .ok()?;
+            let saturation = parse_component(arguments[1], 100.)?;
+            let lightness = parse_component(arguments[2], 100.)?;
+            let color = hsla(hue.rem_euclid(360.) / 360., saturation, lightness, alpha);
+            Some((color.to_rgb(), ColorFormat::Hsl))
+        }
+        _ => None,
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+        6 | 8 => hex.to_string(),
+        _ => return None,
+    };
+    let value = u32::from_str_radix(&digits, 16).ok()?;
+    let value = if digits.len() == 6 {
+        (value << 8) | 0xff
+    } else {
+        value
+    };
+    Some(gpui::rgba(value))
+}
+
+/// Parses a color component, either as a number from 0 to `max` or as a percentage, into a
+/// fraction from 0 to 1.
+fn parse_component(text: &str, max: f32) -> Option<f32> {
+    let value = match text.strip_suffix('%') {
+        Some(percentage) => percentage.parse::<f32>().ok()? / 100.,
+        None => text.parse::<f32>().ok()? / max,
+    };
+    Some(value.clamp(0., 1.))
+}
+
+/// Writes the color in the given format, the way [`parse_color`] reads it.
+pub fn format_color(color: Rgba, format: ColorFormat) -> String {
+    let is_opaque = color.a >= 1.;
+    let alpha = format_number(color.a);
+    match format {
+        ColorFormat::Hex => {
+            let [r, g, b, a] = [color.r, color.g, color.b, color.a].map(to_byte);
+            if is_opaque {
+                format!("#{r:02x}{g:02x}{b:02x}")
+            } else {
+                format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+            }
+        }
+        ColorFormat::Rgb => {
+            let [r, g, b] = [color.r, color.g, color.b].map(to_byte);
+            if is_opaque {
+                format!("rgb({r}, {g}, {b})")
+            } else {
+                format!("rgba({r}, {g}, {b}, {alpha})")
+            }
+        }
+        ColorFormat::Hsl => {
+            let hsla = Hsla::from(color);
+            let hue = (hsla.h * 360.).round() as u32 % 360;
+            let saturation = (hsla.s * 100.).round() as u32;
+            let lightness = (hsla.l * 100.).round() as u32;
+            if is_opaque {
+                format!("hsl({hue}, {saturation}%, {lightness}%)")
+            } else {
+                format!("hsla({hue}, {saturation}%, {lightness}%, {alpha})")
+            }
+        }
+    }
+}
+
+fn to_byte(component: f32) -> u8 {
+    (component.clamp(0., 1.) * 255.).round() as u8
+}
+
+/// Formats a fraction with at most two decimals, without trailing zeros.
+fn format_number(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Finds the colors written in the buffer's text in CSS syntax.
+pub(crate) fn find_colors(buffer: &BufferSnapshot) -> Vec<DocumentColor> {
+    static COLOR_REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = COLOR_REGEX
+        .get_or_init(|| Regex::new(r"#[0-9a-fA-F]{3,8}\b|\b(?:rgba?|hsla?)\([^()\n]*\)").unwrap());
+    let text = buffer.text();
+    regex
+        .find_iter(&text)
+        .filter_map(|found| {
+            let (color, _) = parse_color(found.as_str())?;
+            Some(DocumentColor {
+                range: buffer.anchor_after(found.start())..buffer.anchor_before(found.end()),
+                color,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_colors() {
+        let orange = gpui::rgb(0xff8800);
+        for text in [
+            "#ff8800",
+            "#F80",
+            "rgb(255, 136, 0)",
+            "rgb(255 136 0)",
+            "rgba(100%, 53.3333%, 0%, 1)",
+        ] {
+            let (color, _) = parse_color(text).unwrap();
+            assert_eq!(format_color(color, ColorFormat::Hex), "#ff8800", "{text}");
+        }
+        assert_eq!(parse_color("#ff8800").unwrap(), (orange, ColorFormat::Hex));
+        assert_eq!(format_color(orange, ColorFormat::Rgb), "rgb(255, 136, 0)");
+        assert_eq!(format_color(orange, ColorFormat::Hsl), "hsl(32, 100%, 50%)");
+        let (color, format) = parse_color("hsl(32deg 100% 50%)").unwrap();
+        assert_eq!(format, ColorFormat::Hsl);
+        assert_eq!(format_color(color, ColorFormat::Rgb), "rgb(255, 136, 0)");
+
+        let (translucent, _) = parse_color("rgba(0, 0, 255, 0.5)").unwrap();
+        assert_eq!(format_color(translucent, ColorFormat::Hex), "#0000ff80");
+        assert_eq!(
+            format_color(translucent, ColorFormat::Hsl),
+            "hsla(240, 100%, 50%, 0.5)"
+        );
+
+        for text in ["#ff888", "#12345", "#ggg", "rgb(1, 2)", "lab(50 0 0)"] {
+            assert_eq!(parse_color(text), None, "{text}");
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! The environments of local worktrees, captured from the user's login shell after `cd`-ing
+//! into each worktree, so that language servers, formatters and tasks can find the toolchains
+//! that the shell sets up (`PATH` entries, rustup and nvm shims, direnv variables, and so on).
+//! Zed itself may have been launched from a GUI, without any of them. Capturing an environment
+//! spawns a shell, so it's only done the first time a worktree's environment is needed, and
+//! shared with everything that needs it afterwards.
+
+use std::{path::Path, sync::Arc};
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use futures::{future::Shared, FutureExt};
+use gpui::{BackgroundExecutor, Task};
+use parking_lot::Mutex;
+use util::ResultExt;
+
+use crate::WorktreeId;
+
+#[derive(Default)]
+pub(crate) struct ProjectEnvironment {
+    shell_environments: Mutex<HashMap<WorktreeId, Shared<Task<Option<HashMap<String, String>>>>>>,
+}
+
+impl ProjectEnvironment {
+    /// Returns the login shell environment of the worktree at `abs_path`, starting to capture
+    /// it unless it already was.
+    pub(crate) fn shell_environment(
+        &self,
+        worktree_id: WorktreeId,
+        abs_path: Arc<Path>,
+        executor: &BackgroundExecutor,
+    ) -> Shared<Task<Option<HashMap<String, String>>>> {
+        self.shell_environments
+            .lock()
+            .entry(worktree_id)
+            .or_insert_with(|| {
+                executor
+                    .spawn(async move {
+                        load_shell_environment(&abs_path)
+                            .await
+                            .with_context(|| {
+                                format!("failed to load login shell environment in {abs_path:?}")
+                            })
+                            .log_err()
+                    })
+                    .shared()
+            })
+            .clone()
+    }
+
+    pub(crate) fn remove_worktree(&mut self, worktree_id: WorktreeId) {
+        self.shell_environments.get_mut().remove(&worktree_id);
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+async fn load_shell_environment(_dir: &Path) -> Result<HashMap<String, String>> {
+    Ok([("ZED_FAKE_TEST_ENV".to_string(), "true".to_string())]
+        .into_iter()
+        .collect())
+}
+
+#[cfg(not(any(test, feature = "test-support")))]
+async fn load_shell_environment(dir: &Path) -> Result<HashMap<String, String>> {
+    use anyhow::anyhow;
+    use std::{env, path::PathBuf};
+    use util::parse_env_output;
+
+    let marker = "ZED_SHELL_START";
+    let shell = env::var("SHELL").context(
+        "SHELL environment variable is not assigned so we can't source login environment variables",
+    )?;
+
+    // What we're doing here is to spawn a shell and then `cd` into
+    // the project directory to get the env in there as if the user
+    // `cd`'d into it. We do that because tools like direnv, asdf, ...
+    // hook into `cd` and only set up the env after that.
+    //
+    // In certain shells we need to execute additional_command in order to
+    // trigger the behavior of direnv, etc.
+    //
+    //
+    // The `exit 0` is the result of hours of debugging, trying to find out
+    // why running this command here, without `exit 0`, would mess
+    // up signal process for our process so that `ctrl-c` doesn't work
+    // anymore.
+    //
+    // We still don't know why `$SHELL -l -i -c '/usr/bin/env -0'`  would
+    // do that, but it does, and `exit 0` helps.
+    let additional_command = PathBuf::from(&shell)
+        .file_name()
+        .and_then(|f| f.to_str())
+        .and_then(|shell| match shell {
+            "fish" => Some("emit fish_prompt;"),
+            _ => None,
+        });
+
+    let command = format!(
+        "cd '{}';{} printf '%s' {marker}; /usr/bin/env; exit 0;",
+        dir.display(),
+        additional_command.unwrap_or("")
+    );
+
+    let output = smol::process::Command::new(&shell)
+        .args(["-i", "-c", &command])
+        .output()
+        .await
+        .context("failed to spawn login shell to source login environment variables")?;
+
+    anyhow::ensure!(
+        output.status.success(),
+        "login shell exited with error {:?}",
+        output.status
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let env_output_start = stdout.find(marker).ok_or_else(|| {
+        anyhow!(
+            "failed to parse output of `env` command in login shell: {}",
+            stdout
+        )
+    })?;
+
+    let mut parsed_env = HashMap::default();
+    let env_output = &stdout[env_output_start + marker.len()..];
+
+    parse_env_output(env_output, |key, value| {
+        parsed_env.insert(key, value);
+    });
+
+    Ok(parsed_env)
+}
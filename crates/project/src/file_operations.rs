@@ -0,0 +1,327 @@
+//! Language servers can ask to be told when files are created, renamed or deleted in the
+//! project, so that they can update the code that refers to them, such as module declarations
+//! or imports. Before an operation, the servers whose filters match the files are asked for the
+//! edits it requires, which are applied to the affected buffers before the files change on disk.
+//! Once the operation is done, the servers are notified that it happened.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use futures::{future, FutureExt as _};
+use globset::GlobBuilder;
+use gpui::{Model, ModelContext, Task};
+use language::CachedLspAdapter;
+use lsp::{
+    notification::{DidCreateFiles, DidDeleteFiles, DidRenameFiles},
+    request::{WillCreateFiles, WillDeleteFiles, WillRenameFiles},
+    LanguageServer,
+};
+use util::ResultExt;
+use worktree::{Entry, ProjectEntryId, Worktree, WorktreeId};
+
+use crate::Project;
+
+/// How long to wait for the edits that servers make before an operation, so that a busy server
+/// doesn't hold up the operation.
+const WILL_FILE_OPERATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileOperation {
+    Create,
+    Rename,
+    Delete,
+}
+
+impl FileOperation {
+    /// The filters that a server registered for the requests sent before the operation and the
+    /// notifications sent after it.
+    fn registrations(
+        self,
+        capabilities: &lsp::ServerCapabilities,
+    ) -> (
+        Option<&lsp::FileOperationRegistrationOptions>,
+        Option<&lsp::FileOperationRegistrationOptions>,
+    ) {
+        let Some(file_operations) = capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.file_operations.as_ref())
+        else {
+            return (None, None);
+        };
+        match self {
+            FileOperation::Create => (
+                file_operations.will_create.as_ref(),
+                file_operations.did_create.as_ref(),
+            ),
+            FileOperation::Rename => (
+                file_operations.will_rename.as_ref(),
+                file_operations.did_rename.as_ref(),
+            ),
+            FileOperation::Delete => (
+                file_operations.will_delete.as_ref(),
+                file_operations.did_delete.as_ref(),
+            ),
+        }
+    }
+}
+
+impl Project {
+    /// Creates a file or directory in a local worktree, letting language servers update the
+    /// project for it.
+    pub(crate) fn create_local_entry(
+        &mut self,
+        worktree: Model<Worktree>,
+        path: Arc<Path>,
+        is_directory: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<Entry>>> {
+        let (worktree_id, abs_path) = {
+            let worktree = worktree.read(cx);
+            (worktree.id(), worktree.absolutize(&path))
+        };
+        let abs_path = match abs_path {
+            Ok(abs_path) => abs_path,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let Some(uri) = file_uri(&abs_path) else {
+            return Task::ready(Err(anyhow!("invalid path {abs_path:?}")));
+        };
+
+        self.perform_file_operation::<WillCreateFiles, DidCreateFiles, _>(
+            FileOperation::Create,
+            worktree_id,
+            &[(abs_path, is_directory)],
+            lsp::CreateFilesParams {
+                files: vec![lsp::FileCreate { uri }],
+            },
+            move |_, cx| {
+                worktree.update(cx, |worktree, cx| {
+                    worktree
+                        .as_local_mut()
+                        .unwrap()
+                        .create_entry(path, is_directory, cx)
+                })
+            },
+            cx,
+        )
+    }
+
+    /// Renames an entry of a local worktree, letting language servers update the references
+    /// to it.
+    pub(crate) fn rename_local_entry(
+        &mut self,
+        worktree: Model<Worktree>,
+        entry_id: ProjectEntryId,
+        new_path: Arc<Path>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<Entry>>> {
+        let (worktree_id, paths) = {
+            let worktree = worktree.read(cx);
+            let Some(entry) = worktree.entry_for_id(entry_id) else {
+                return Task::ready(Err(anyhow!("no entry found with id {entry_id:?}")));
+            };
+            let paths = worktree
+                .absolutize(&entry.path)
+                .and_then(|old_path| Ok((old_path, worktree.absolutize(&new_path)?)));
+            (worktree.id(), paths.map(|paths| (paths, entry.is_dir())))
+        };
+        let ((old_abs_path, new_abs_path), is_dir) = match paths {
+            Ok(paths) => paths,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let (Some(old_uri), Some(new_uri)) = (file_uri(&old_abs_path), file_uri(&new_abs_path))
+        else {
+            return Task::ready(Err(anyhow!("invalid path {new_abs_path:?}")));
+        };
+
+        self.perform_file_operation::<WillRenameFiles, DidRenameFiles, _>(
+            FileOperation::Rename,
+            worktree_id,
+            &[(old_abs_path, is_dir), (new_abs_path, is_dir)],
+            lsp::RenameFilesParams {
+                files: vec![lsp::FileRename { old_uri, new_uri }],
+            },
+            move |_, cx| {
+                worktree.update(cx, |worktree, cx| {
+                    worktree
+                        .as_local_mut()
+                        .unwrap()
+                        .rename_entry(entry_id, new_path, cx)
+                })
+            },
+            cx,
+        )
+    }
+
+    /// Deletes an entry of a local worktree, letting language servers remove the references
+    /// to it. Returns `None` if the entry doesn't exist.
+    pub(crate) fn delete_local_entry(
+        &mut self,
+        worktree: Model<Worktree>,
+        entry_id: ProjectEntryId,
+        trash: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<Task<Result<()>>> {
+        let (worktree_id, abs_path, is_dir) = {
+            let worktree = worktree.read(cx);
+            let entry = worktree.entry_for_id(entry_id)?;
+            (
+                worktree.id(),
+                worktree.absolutize(&entry.path).log_err()?,
+                entry.is_dir(),
+            )
+        };
+        let uri = file_uri(&abs_path)?;
+
+        Some(
+            self.perform_file_operation::<WillDeleteFiles, DidDeleteFiles, _>(
+                FileOperation::Delete,
+                worktree_id,
+                &[(abs_path, is_dir)],
+                lsp::DeleteFilesParams {
+                    files: vec![lsp::FileDelete { uri }],
+                },
+                move |_, cx| {
+                    worktree
+                        .update(cx, |worktree, cx| {
+                            worktree
+                                .as_local_mut()
+                                .unwrap()
+                                .delete_entry(entry_id, trash, cx)
+                        })
+                        .unwrap_or_else(|| {
+                            Task::ready(Err(anyhow!("no entry found with id {entry_id:?}")))
+                        })
+                },
+                cx,
+            ),
+        )
+    }
+
+    /// Applies the edits that the servers interested in the paths make for the operation,
+    /// performs it, and then notifies them of it.
+    fn perform_file_operation<Will, Did, T>(
+        &mut self,
+        operation: FileOperation,
+        worktree_id: WorktreeId,
+        paths: &[(PathBuf, bool)],
+        params: Will::Params,
+        perform: impl 'static + FnOnce(&mut Self, &mut ModelContext<Self>) -> Task<Result<T>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<T>>
+    where
+        Will: lsp::request::Request<Result = Option<lsp::WorkspaceEdit>>,
+        Did: lsp::notification::Notification<Params = Will::Params>,
+        Will::Params: Clone,
+        T: 'static,
+    {
+        let mut will_servers = Vec::new();
+        let mut did_servers = Vec::new();
+        for (adapter, _, server) in self.language_servers_for_worktree(worktree_id) {
+            let (will, did) = operation.registrations(server.capabilities());
+            if will.map_or(false, |registration| paths_match(registration, paths)) {
+                will_servers.push((adapter.clone(), server.clone()));
+            }
+            if did.map_or(false, |registration| paths_match(registration, paths)) {
+                did_servers.push(server.clone());
+            }
+        }
+
+        let will_requests = will_servers
+            .into_iter()
+            .map(|(adapter, server)| {
+                let request = server.request::<Will>(params.clone());
+                async move { (adapter, server, request.await) }
+            })
+            .collect::<Vec<_>>();
+        cx.spawn(move |this, mut cx| async move {
+            if !will_requests.is_empty() {
+                let mut timeout = cx
+                    .background_executor()
+                    .timer(WILL_FILE_OPERATION_TIMEOUT)
+                    .fuse();
+                let responses = futures::select_biased! {
+                    responses = future::join_all(will_requests).fuse() => responses,
+                    _ = timeout => {
+                        log::warn!("timed out waiting for language servers before {operation:?}");
+                        Vec::new()
+                    }
+                };
+                let project = this
+                    .upgrade()
+                    .ok_or_else(|| anyhow!("project was dropped"))?;
+                for (adapter, server, response) in responses {
+                    if let Some(Some(edit)) = response.log_err() {
+                        Self::deserialize_workspace_edit(
+                            project.clone(),
+                            edit,
+                            true,
+                            adapter,
+                            server,
+                            &mut cx,
+                        )
+                        .await
+                        .log_err();
+                    }
+                }
+            }
+
+            let result = this.update(&mut cx, perform)?.await?;
+            for server in did_servers {
+                server.notify::<Did>(params.clone()).log_err();
+            }
+            Ok(result)
+        })
+    }
+}
+
+fn paths_match(
+    registration: &lsp::FileOperationRegistrationOptions,
+    paths: &[(PathBuf, bool)],
+) -> bool {
+    paths
+        .iter()
+        .any(|(path, is_dir)| filters_match(registration, path, *is_dir))
+}
+
+fn filters_match(
+    registration: &lsp::FileOperationRegistrationOptions,
+    abs_path: &Path,
+    is_dir: bool,
+) -> bool {
+    registration.filters.iter().any(|filter| {
+        if filter
+            .scheme
+            .as_deref()
+            .map_or(false, |scheme| scheme != "file")
+        {
+            return false;
+        }
+        let kind_matches = match filter.pattern.matches {
+            Some(lsp::FileOperationPatternKind::File) => !is_dir,
+            Some(lsp::FileOperationPatternKind::Folder) => is_dir,
+            None => true,
+        };
+        let ignore_case = filter
+            .pattern
+            .options
+            .as_ref()
+            .and_then(|options| options.ignore_case)
+            .unwrap_or(false);
+        kind_matches
+            && GlobBuilder::new(&filter.pattern.glob)
+                .case_insensitive(ignore_case)
+                .build()
+                .log_err()
+                .map_or(false, |glob| glob.compile_matcher().is_match(abs_path))
+    })
+}
+
+fn file_uri(abs_path: &Path) -> Option<String> {
+    Some(lsp::Url::from_file_path(abs_path).ok()?.to_string())
+}
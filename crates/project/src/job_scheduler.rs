@@ -0,0 +1,265 @@
+//! Runs a project's long-running jobs on the background executor, a limited number at a time.
+//! Queued jobs start in priority order, so that work the user is waiting on isn't stuck behind
+//! scanning or indexing. Jobs can report their progress, and the jobs belonging to a group,
+//! such as a worktree, can be cancelled together.
+//!
+//! The project measures directory sizes and indexes symbols as jobs. Saving buffers and
+//! scanning worktrees aren't jobs: worktrees perform them in the order their changes happen,
+//! which queueing them behind other work would break.
+
+use std::{cmp::Reverse, future::Future};
+
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use futures::{
+    channel::{mpsc, oneshot},
+    StreamExt,
+};
+use gpui::{EventEmitter, ModelContext, SharedString, Task};
+use util::post_inc;
+
+use crate::WorktreeId;
+
+/// The priority of a job. When a job slot frees up, the queued job with the highest priority
+/// starts first, and jobs with the same priority start in the order they were scheduled.
+/// `Save` and `Scan` are for work that's like saving and scanning, such as measuring the
+/// sizes of directories, rather than for the worktrees' own saves and scans.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JobPriority {
+    Index,
+    Scan,
+    Save,
+    Interactive,
+}
+
+/// A set of jobs that can be cancelled together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JobGroup {
+    Project,
+    Worktree(WorktreeId),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JobId(usize);
+
+impl JobId {
+    pub fn to_usize(&self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobProgress {
+    pub message: Option<SharedString>,
+    /// How much of the job is done, between 0 and 1, if it's known.
+    pub fraction: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobEvent {
+    /// A running job reported its progress.
+    Progress(JobId),
+    /// A job finished running or was cancelled.
+    Finished(JobId),
+}
+
+/// Reports the progress of a running job.
+#[derive(Clone)]
+pub struct JobReporter {
+    job_id: JobId,
+    progress_tx: mpsc::UnboundedSender<(JobId, JobProgress)>,
+}
+
+impl JobReporter {
+    pub fn report(&self, message: impl Into<SharedString>, fraction: Option<f32>) {
+        self.progress_tx
+            .unbounded_send((
+                self.job_id,
+                JobProgress {
+                    message: Some(message.into()),
+                    fraction: fraction.map(|fraction| fraction.clamp(0., 1.)),
+                },
+            ))
+            .ok();
+    }
+}
+
+pub struct JobScheduler {
+    max_running_jobs: usize,
+    next_job_id: usize,
+    queued_jobs: Vec<QueuedJob>,
+    running_jobs: HashMap<JobId, RunningJob>,
+    progress_tx: mpsc::UnboundedSender<(JobId, JobProgress)>,
+    _maintain_progress: Task<()>,
+}
+
+type StartJob = Box<dyn FnOnce(JobReporter, &mut ModelContext<JobScheduler>) -> Task<()>>;
+
+struct QueuedJob {
+    id: JobId,
+    name: SharedString,
+    priority: JobPriority,
+    group: JobGroup,
+    start: StartJob,
+}
+
+struct RunningJob {
+    name: SharedString,
+    group: JobGroup,
+    progress: Option<JobProgress>,
+    _task: Task<()>,
+}
+
+impl EventEmitter<JobEvent> for JobScheduler {}
+
+impl JobScheduler {
+    pub fn new(cx: &mut ModelContext<Self>) -> Self {
+        let (progress_tx, mut progress_rx) = mpsc::unbounded();
+        let _maintain_progress = cx.spawn(|this, mut cx| async move {
+            while let Some((job_id, progress)) = progress_rx.next().await {
+                let updated = this.update(&mut cx, |this, cx| {
+                    if let Some(job) = this.running_jobs.get_mut(&job_id) {
+                        job.progress = Some(progress);
+                        cx.emit(JobEvent::Progress(job_id));
+                    }
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            max_running_jobs: cx.background_executor().num_cpus().max(1),
+            next_job_id: 0,
+            queued_jobs: Vec::new(),
+            running_jobs: HashMap::default(),
+            progress_tx,
+            _maintain_progress,
+        }
+    }
+
+    /// Sets how many jobs can run at once. Jobs that are already running aren't affected.
+    pub fn set_max_running_jobs(&mut self, max_running_jobs: usize, cx: &mut ModelContext<Self>) {
+        self.max_running_jobs = max_running_jobs.max(1);
+        self.start_queued_jobs(cx);
+    }
+
+    /// Schedules a job to run on the background executor once a job slot is available. The
+    /// returned task resolves with the job's output, or with an error if the job is cancelled
+    /// before it completes.
+    pub fn schedule<F, Fut, R>(
+        &mut self,
+        name: impl Into<SharedString>,
+        priority: JobPriority,
+        group: JobGroup,
+        job: F,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<R>>
+    where
+        F: 'static + Send + FnOnce(JobReporter) -> Fut,
+        Fut: 'static + Send + Future<Output = R>,
+        R: 'static + Send,
+    {
+        let id = JobId(post_inc(&mut self.next_job_id));
+        let (output_tx, output_rx) = oneshot::channel();
+        let start: StartJob = Box::new(move |reporter, cx| {
+            let job = cx.background_executor().spawn(job(reporter));
+            cx.spawn(|this, mut cx| async move {
+                output_tx.send(job.await).ok();
+                this.update(&mut cx, |this, cx| this.job_finished(id, cx))
+                    .ok();
+            })
+        });
+        self.queued_jobs.push(QueuedJob {
+            id,
+            name: name.into(),
+            priority,
+            group,
+            start,
+        });
+        self.start_queued_jobs(cx);
+
+        cx.background_executor().spawn(async move {
+            output_rx
+                .await
+                .map_err(|_| anyhow!("job {} was cancelled", id.0))
+        })
+    }
+
+    /// Cancels all of the group's jobs, whether they're running or queued.
+    pub fn cancel_group(&mut self, group: JobGroup, cx: &mut ModelContext<Self>) {
+        self.queued_jobs.retain(|job| job.group != group);
+        let cancelled_job_ids = self
+            .running_jobs
+            .iter()
+            .filter(|(_, job)| job.group == group)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in cancelled_job_ids {
+            self.running_jobs.remove(&id);
+            cx.emit(JobEvent::Finished(id));
+        }
+        self.start_queued_jobs(cx);
+    }
+
+    pub fn running_job_count(&self) -> usize {
+        self.running_jobs.len()
+    }
+
+    pub fn queued_job_count(&self) -> usize {
+        self.queued_jobs.len()
+    }
+
+    pub fn job_name(&self, id: JobId) -> Option<&SharedString> {
+        if let Some(job) = self.running_jobs.get(&id) {
+            Some(&job.name)
+        } else {
+            self.queued_jobs
+                .iter()
+                .find(|job| job.id == id)
+                .map(|job| &job.name)
+        }
+    }
+
+    /// Returns the progress last reported by a running job.
+    pub fn job_progress(&self, id: JobId) -> Option<&JobProgress> {
+        self.running_jobs.get(&id)?.progress.as_ref()
+    }
+
+    fn job_finished(&mut self, id: JobId, cx: &mut ModelContext<Self>) {
+        if self.running_jobs.remove(&id).is_some() {
+            cx.emit(JobEvent::Finished(id));
+        }
+        self.start_queued_jobs(cx);
+    }
+
+    fn start_queued_jobs(&mut self, cx: &mut ModelContext<Self>) {
+        while self.running_jobs.len() < self.max_running_jobs {
+            let Some(next_ix) = self
+                .queued_jobs
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, job)| (job.priority, Reverse(job.id)))
+                .map(|(ix, _)| ix)
+            else {
+                break;
+            };
+            let job = self.queued_jobs.remove(next_ix);
+            let reporter = JobReporter {
+                job_id: job.id,
+                progress_tx: self.progress_tx.clone(),
+            };
+            let task = (job.start)(reporter, cx);
+            self.running_jobs.insert(
+                job.id,
+                RunningJob {
+                    name: job.name,
+                    group: job.group,
+                    progress: None,
+                    _task: task,
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! Copies of the contents of local files, taken whenever they're saved and independent of
+//! version control, so that earlier versions can be recovered after a bad save.
+//!
+//! Contents are stored once per distinct hash, and an index maps each file's absolute path to
+//! its versions, oldest first.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use anyhow::{Context as _, Result};
+use collections::{BTreeMap, HashMap, HashSet};
+use fs::{Fs, RemoveOptions};
+use gpui::{AppContext, Global};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+use util::{paths::LOCAL_HISTORY_DIR, ResultExt};
+
+use crate::project_settings::LocalHistorySettings;
+
+const INDEX_FILE_NAME: &str = "index.json";
+const CONTENTS_DIR_NAME: &str = "contents";
+
+/// The local history shared by all projects, since they record into the same directory and
+/// each instance has its own copy of the index.
+struct GlobalLocalHistory(Arc<LocalHistory>);
+
+impl Global for GlobalLocalHistory {}
+
+pub struct LocalHistory {
+    fs: Arc<dyn Fs>,
+    dir: PathBuf,
+    /// The index, loaded from disk on first use.
+    index: smol::lock::Mutex<Option<LocalHistoryIndex>>,
+}
+
+/// A saved version of a file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalHistoryVersion {
+    /// The SHA-256 hash of the version's contents, under which they're stored.
+    pub content_hash: String,
+    pub saved_at: SystemTime,
+    pub len: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct LocalHistoryIndex {
+    files: BTreeMap<PathBuf, Vec<LocalHistoryVersion>>,
+}
+
+impl LocalHistory {
+    pub fn new(fs: Arc<dyn Fs>, dir: PathBuf) -> Self {
+        Self {
+            fs,
+            dir,
+            index: Default::default(),
+        }
+    }
+
+    /// Returns the local history stored in Zed's support directory, creating it on first use.
+    pub fn global(fs: Arc<dyn Fs>, cx: &mut AppContext) -> Arc<Self> {
+        if let Some(local_history) = cx.try_global::<GlobalLocalHistory>() {
+            return local_history.0.clone();
+        }
+        let local_history = Arc::new(Self::new(fs, LOCAL_HISTORY_DIR.clone()));
+        cx.set_global(GlobalLocalHistory(local_history.clone()));
+        local_history
+    }
+
+    /// Records `content` as the latest version of the file at `abs_path`, unless it's
+    /// unchanged since the previous version, and removes the versions exceeding the limits
+    /// of `settings`.
+    pub async fn record(
+        &self,
+        abs_path: &Path,
+        content: String,
+        settings: &LocalHistorySettings,
+    ) -> Result<()> {
+        let len = content.len() as u64;
+        if len > settings.max_size_bytes() {
+            return Ok(());
+        }
+        let content_hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+
+        let mut index = self.index.lock().await;
+        let index = self.loaded_index(&mut index).await?;
+        let versions = index.files.entry(abs_path.to_path_buf()).or_default();
+        if versions
+            .last()
+            .map_or(false, |version| version.content_hash == content_hash)
+        {
+            return Ok(());
+        }
+
+        let content_path = self.content_path(&content_hash);
+        if !self.fs.is_file(&content_path).await {
+            self.fs
+                .create_dir(&self.dir.join(CONTENTS_DIR_NAME))
+                .await?;
+            self.fs.atomic_write(content_path, content).await?;
+        }
+        versions.push(LocalHistoryVersion {
+            content_hash,
+            saved_at: SystemTime::now(),
+            len,
+        });
+
+        let unreferenced_hashes =
+            index.prune(settings.max_versions_per_file(), settings.max_size_bytes());
+        self.save_index(index).await?;
+        for content_hash in unreferenced_hashes {
+            self.fs
+                .remove_file(
+                    &self.content_path(&content_hash),
+                    RemoveOptions {
+                        recursive: false,
+                        ignore_if_not_exists: true,
+                    },
+                )
+                .await
+                .log_err();
+        }
+        Ok(())
+    }
+
+    /// Returns the recorded versions of the file at `abs_path`, most recent first.
+    pub async fn versions(&self, abs_path: &Path) -> Result<Vec<LocalHistoryVersion>> {
+        let mut index = self.index.lock().await;
+        let index = self.loaded_index(&mut index).await?;
+        Ok(index
+            .files
+            .get(abs_path)
+            .into_iter()
+            .flat_map(|versions| versions.iter().rev().cloned())
+            .collect())
+    }
+
+    pub async fn load(&self, version: &LocalHistoryVersion) -> Result<String> {
+        self.fs
+            .load(&self.content_path(&version.content_hash))
+            .await
+            .with_context(|| format!("loading local history version {}", version.content_hash))
+    }
+
+    fn content_path(&self, content_hash: &str) -> PathBuf {
+        self.dir.join(CONTENTS_DIR_NAME).join(content_hash)
+    }
+
+    async fn loaded_index<'a>(
+        &self,
+        index: &'a mut Option<LocalHistoryIndex>,
+    ) -> Result<&'a mut LocalHistoryIndex> {
+        if index.is_none() {
+            let index_path = self.dir.join(INDEX_FILE_NAME);
+            let loaded_index = if self.fs.is_file(&index_path).await {
+                let content = self.fs.load(&index_path).await?;
+                serde_json::from_str(&content)
+                    .context("parsing local history index")
+                    .log_err()
+                    .unwrap_or_default()
+            } else {
+                LocalHistoryIndex::default()
+            };
+            *index = Some(loaded_index);
+        }
+        Ok(index.get_or_insert_with(Default::default))
+    }
+
+    async fn save_index(&self, index: &LocalHistoryIndex) -> Result<()> {
+        self.fs.create_dir(&self.dir).await?;
+        self.fs
+            .atomic_write(
+                self.dir.join(INDEX_FILE_NAME),
+                serde_json::to_string(index)?,
+            )
+            .await
+    }
+}
+
+impl LocalHistoryIndex {
+    /// Removes the versions beyond each file's limit and then, oldest first, the versions
+    /// that don't fit within the size limit. Returns the hashes of the contents that are no
+    /// longer referenced by any version.
+    fn prune(&mut self, max_versions_per_file: usize, max_size: u64) -> Vec<String> {
+        let mut removed_hashes = Vec::new();
+        for versions in self.files.values_mut() {
+            let excess = versions.len().saturating_sub(max_versions_per_file);
+            removed_hashes.extend(versions.drain(..excess).map(|version| version.content_hash));
+        }
+        while self.total_size() > max_size {
+            let Some(versions) = self
+                .files
+                .values_mut()
+                .filter(|versions| !versions.is_empty())
+                .min_by_key(|versions| versions[0].saved_at)
+            else {
+                break;
+            };
+            removed_hashes.push(versions.remove(0).content_hash);
+        }
+        self.files.retain(|_, versions| !versions.is_empty());
+
+        let referenced_hashes = self
+            .files
+            .values()
+            .flatten()
+            .map(|version| version.content_hash.as_str())
+            .collect::<HashSet<_>>();
+        removed_hashes.retain(|content_hash| !referenced_hashes.contains(content_hash.as_str()));
+        removed_hashes.sort();
+        removed_hashes.dedup();
+        removed_hashes
+    }
+
+    /// The size of the stored contents, each of which may be shared by several versions.
+    fn total_size(&self) -> u64 {
+        self.files
+            .values()
+            .flatten()
+            .map(|version| (version.content_hash.as_str(), version.len))
+            .collect::<HashMap<_, _>>()
+            .values()
+            .sum()
+    }
+}
+
+/// Returns a unified diff from an earlier version of a file to its current contents.
+pub fn diff_versions(old_text: &str, new_text: &str) -> String {
+    TextDiff::from_lines(old_text, new_text)
+        .unified_diff()
+        .header("saved version", "current")
+        .to_string()
+}
@@ -1,13 +1,14 @@
 use crate::{
-    CodeAction, CoreCompletion, DocumentHighlight, Hover, HoverBlock, HoverBlockKind, InlayHint,
-    InlayHintLabel, InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip, Location,
-    LocationLink, MarkupContent, Project, ProjectTransaction, ResolveState,
+    CallHierarchyCall, CallHierarchyItem, CodeAction, CodeLens, CoreCompletion, DocumentColor,
+    DocumentHighlight, Hover, HoverBlock, HoverBlockKind, InlayHint, InlayHintLabel,
+    InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip, Location, LocationLink,
+    MarkupContent, Project, ProjectTransaction, ResolveState, SignatureHelp, SignatureInformation,
 };
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use client::proto::{self, PeerId};
 use futures::future;
-use gpui::{AppContext, AsyncAppContext, Model};
+use gpui::{AppContext, AsyncAppContext, Model, Rgba};
 use language::{
     language_settings::{language_settings, InlayHintKind},
     point_from_lsp, point_to_lsp,
@@ -21,6 +22,7 @@ use lsp::{
 };
 use std::{cmp::Reverse, ops::Range, path::Path, sync::Arc};
 use text::{BufferId, LineEnding};
+use util::ResultExt;
 
 pub fn lsp_formatting_options(tab_size: u32) -> lsp::FormattingOptions {
     lsp::FormattingOptions {
@@ -125,6 +127,22 @@ pub(crate) struct GetHover {
     pub position: PointUtf16,
 }
 
+pub(crate) struct GetSignatureHelp {
+    pub position: PointUtf16,
+}
+
+pub(crate) struct PrepareCallHierarchy {
+    pub position: PointUtf16,
+}
+
+pub(crate) struct GetIncomingCalls {
+    pub item: lsp::CallHierarchyItem,
+}
+
+pub(crate) struct GetOutgoingCalls {
+    pub item: lsp::CallHierarchyItem,
+}
+
 pub(crate) struct GetCompletions {
     pub position: PointUtf16,
 }
@@ -135,6 +153,10 @@ pub(crate) struct GetCodeActions {
     pub kinds: Option<Vec<lsp::CodeActionKind>>,
 }
 
+pub(crate) struct GetCodeLens;
+
+pub(crate) struct GetDocumentColor;
+
 pub(crate) struct OnTypeFormatting {
     pub position: PointUtf16,
     pub trigger: String,
@@ -507,6 +529,14 @@ impl LspCommand for GetImplementation {
     type LspRequest = lsp::request::GotoImplementation;
     type ProtoRequest = proto::GetImplementation;
 
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        match &capabilities.implementation_provider {
+            None => false,
+            Some(lsp::ImplementationProviderCapability::Simple(false)) => false,
+            _ => true,
+        }
+    }
+
     fn to_lsp(
         &self,
         path: &Path,
@@ -1443,10 +1473,14 @@ impl LspCommand for GetHover {
 }
 
 #[async_trait(?Send)]
-impl LspCommand for GetCompletions {
-    type Response = Vec<CoreCompletion>;
-    type LspRequest = lsp::request::Completion;
-    type ProtoRequest = proto::GetCompletions;
+impl LspCommand for GetSignatureHelp {
+    type Response = Option<SignatureHelp>;
+    type LspRequest = lsp::request::SignatureHelpRequest;
+    type ProtoRequest = proto::GetSignatureHelp;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities.signature_help_provider.is_some()
+    }
 
     fn to_lsp(
         &self,
@@ -1454,227 +1488,966 @@ impl LspCommand for GetCompletions {
         _: &Buffer,
         _: &Arc<LanguageServer>,
         _: &AppContext,
-    ) -> lsp::CompletionParams {
-        lsp::CompletionParams {
-            text_document_position: lsp::TextDocumentPositionParams::new(
-                lsp::TextDocumentIdentifier::new(lsp::Url::from_file_path(path).unwrap()),
-                point_to_lsp(self.position),
-            ),
-            context: Default::default(),
+    ) -> lsp::SignatureHelpParams {
+        lsp::SignatureHelpParams {
+            context: None,
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
             work_done_progress_params: Default::default(),
-            partial_result_params: Default::default(),
         }
     }
 
     async fn response_from_lsp(
         self,
-        completions: Option<lsp::CompletionResponse>,
-        project: Model<Project>,
-        buffer: Model<Buffer>,
-        server_id: LanguageServerId,
-        mut cx: AsyncAppContext,
+        message: Option<lsp::SignatureHelp>,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: LanguageServerId,
+        _: AsyncAppContext,
     ) -> Result<Self::Response> {
-        let mut response_list = None;
-        let mut completions = if let Some(completions) = completions {
-            match completions {
-                lsp::CompletionResponse::Array(completions) => completions,
-
-                lsp::CompletionResponse::List(mut list) => {
-                    let items = std::mem::take(&mut list.items);
-                    response_list = Some(list);
-                    items
-                }
-            }
-        } else {
-            Default::default()
+        let Some(help) = message else {
+            return Ok(None);
         };
 
-        let language_server_adapter = project
-            .update(&mut cx, |project, _cx| {
-                project.language_server_adapter_for_id(server_id)
-            })?
-            .ok_or_else(|| anyhow!("no such language server"))?;
-
-        let item_defaults = response_list
-            .as_ref()
-            .and_then(|list| list.item_defaults.as_ref());
-
-        if let Some(item_defaults) = item_defaults {
-            let default_data = item_defaults.data.as_ref();
-            let default_commit_characters = item_defaults.commit_characters.as_ref();
-            let default_insert_text_mode = item_defaults.insert_text_mode.as_ref();
-
-            if default_data.is_some()
-                || default_commit_characters.is_some()
-                || default_insert_text_mode.is_some()
-            {
-                for item in completions.iter_mut() {
-                    if let Some(data) = default_data {
-                        item.data = Some(data.clone())
-                    }
-                    if let Some(characters) = default_commit_characters {
-                        item.commit_characters = Some(characters.clone())
-                    }
-                    if let Some(text_mode) = default_insert_text_mode {
-                        item.insert_text_mode = Some(*text_mode)
-                    }
-                }
-            }
-        }
-
-        let mut completion_edits = Vec::new();
-        buffer.update(&mut cx, |buffer, _cx| {
-            let snapshot = buffer.snapshot();
-            let clipped_position = buffer.clip_point_utf16(Unclipped(self.position), Bias::Left);
-
-            let mut range_for_token = None;
-            completions.retain_mut(|lsp_completion| {
-                let edit = match lsp_completion.text_edit.as_ref() {
-                    // If the language server provides a range to overwrite, then
-                    // check that the range is valid.
-                    Some(completion_text_edit) => {
-                        match parse_completion_text_edit(completion_text_edit, &snapshot) {
-                            Some(edit) => edit,
-                            None => return false,
-                        }
-                    }
-
-                    // If the language server does not provide a range, then infer
-                    // the range based on the syntax tree.
-                    None => {
-                        if self.position != clipped_position {
-                            log::info!("completion out of expected range");
-                            return false;
-                        }
-
-                        let default_edit_range = response_list
-                            .as_ref()
-                            .and_then(|list| list.item_defaults.as_ref())
-                            .and_then(|defaults| defaults.edit_range.as_ref())
-                            .and_then(|range| match range {
-                                CompletionListItemDefaultsEditRange::Range(r) => Some(r),
-                                _ => None,
-                            });
-
-                        let range = if let Some(range) = default_edit_range {
-                            let range = range_from_lsp(*range);
-                            let start = snapshot.clip_point_utf16(range.start, Bias::Left);
-                            let end = snapshot.clip_point_utf16(range.end, Bias::Left);
-                            if start != range.start.0 || end != range.end.0 {
-                                log::info!("completion out of expected range");
-                                return false;
-                            }
-
-                            snapshot.anchor_before(start)..snapshot.anchor_after(end)
-                        } else {
-                            range_for_token
-                                .get_or_insert_with(|| {
-                                    let offset = self.position.to_offset(&snapshot);
-                                    let (range, kind) = snapshot.surrounding_word(offset);
-                                    let range = if kind == Some(CharKind::Word) {
-                                        range
-                                    } else {
-                                        offset..offset
-                                    };
-
-                                    snapshot.anchor_before(range.start)
-                                        ..snapshot.anchor_after(range.end)
-                                })
-                                .clone()
-                        };
-
-                        let text = lsp_completion
-                            .insert_text
-                            .as_ref()
-                            .unwrap_or(&lsp_completion.label)
-                            .clone();
-                        (range, text)
-                    }
-                };
-
-                completion_edits.push(edit);
-                true
-            });
-        })?;
-
-        language_server_adapter
-            .process_completions(&mut completions)
-            .await;
-
-        Ok(completions
+        let signatures = help
+            .signatures
             .into_iter()
-            .zip(completion_edits)
-            .map(|(lsp_completion, (old_range, mut new_text))| {
-                LineEnding::normalize(&mut new_text);
-                CoreCompletion {
-                    old_range,
-                    new_text,
-                    server_id,
-                    lsp_completion,
+            .map(|signature| {
+                let parameters = signature_parameter_ranges(
+                    &signature.label,
+                    signature.parameters.as_deref().unwrap_or_default(),
+                );
+                let documentation = signature.documentation.and_then(|documentation| {
+                    let block = match documentation {
+                        lsp::Documentation::String(text) => HoverBlock {
+                            text,
+                            kind: HoverBlockKind::PlainText,
+                        },
+                        lsp::Documentation::MarkupContent(markup) => HoverBlock {
+                            text: markup.value,
+                            kind: if markup.kind == lsp::MarkupKind::Markdown {
+                                HoverBlockKind::Markdown
+                            } else {
+                                HoverBlockKind::PlainText
+                            },
+                        },
+                    };
+                    (!block.text.is_empty()).then_some(block)
+                });
+                // A signature's own active parameter takes precedence over the one for the
+                // whole response.
+                let active_parameter = signature
+                    .active_parameter
+                    .or(help.active_parameter)
+                    .map(|ix| ix as usize)
+                    .filter(|ix| *ix < parameters.len());
+                SignatureInformation {
+                    label: signature.label,
+                    documentation,
+                    parameters,
+                    active_parameter,
                 }
             })
-            .collect())
+            .collect::<Vec<_>>();
+        if signatures.is_empty() {
+            return Ok(None);
+        }
+
+        let active_signature = help
+            .active_signature
+            .map_or(0, |ix| ix as usize)
+            .min(signatures.len() - 1);
+        Ok(Some(SignatureHelp {
+            signatures,
+            active_signature,
+        }))
     }
 
-    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetCompletions {
-        let anchor = buffer.anchor_after(self.position);
-        proto::GetCompletions {
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> Self::ProtoRequest {
+        proto::GetSignatureHelp {
             project_id,
             buffer_id: buffer.remote_id().into(),
-            position: Some(language::proto::serialize_anchor(&anchor)),
-            version: serialize_version(&buffer.version()),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+            version: serialize_version(&buffer.version),
         }
     }
 
     async fn from_proto(
-        message: proto::GetCompletions,
+        message: Self::ProtoRequest,
         _: Model<Project>,
         buffer: Model<Buffer>,
         mut cx: AsyncAppContext,
     ) -> Result<Self> {
-        let version = deserialize_version(&message.version);
-        buffer
-            .update(&mut cx, |buffer, _| buffer.wait_for_version(version))?
-            .await?;
         let position = message
             .position
-            .and_then(language::proto::deserialize_anchor)
-            .map(|p| {
-                buffer.update(&mut cx, |buffer, _| {
-                    buffer.clip_point_utf16(Unclipped(p.to_point_utf16(buffer)), Bias::Left)
-                })
-            })
-            .ok_or_else(|| anyhow!("invalid position"))??;
-        Ok(Self { position })
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
     }
 
     fn response_to_proto(
-        completions: Vec<CoreCompletion>,
+        response: Self::Response,
         _: &mut Project,
         _: PeerId,
-        buffer_version: &clock::Global,
+        _: &clock::Global,
         _: &mut AppContext,
-    ) -> proto::GetCompletionsResponse {
-        proto::GetCompletionsResponse {
-            completions: completions
-                .iter()
-                .map(Project::serialize_completion)
+    ) -> proto::GetSignatureHelpResponse {
+        let Some(response) = response else {
+            return proto::GetSignatureHelpResponse::default();
+        };
+        proto::GetSignatureHelpResponse {
+            signatures: response
+                .signatures
+                .into_iter()
+                .map(|signature| proto::SignatureInformation {
+                    label: signature.label,
+                    documentation_is_markdown: signature
+                        .documentation
+                        .as_ref()
+                        .map_or(false, |block| block.kind == HoverBlockKind::Markdown),
+                    documentation: signature.documentation.map(|block| block.text),
+                    parameters: signature
+                        .parameters
+                        .into_iter()
+                        .map(|range| proto::Range {
+                            start: range.start as u64,
+                            end: range.end as u64,
+                        })
+                        .collect(),
+                    active_parameter: signature.active_parameter.map(|ix| ix as u64),
+                })
                 .collect(),
-            version: serialize_version(buffer_version),
+            active_signature: response.active_signature as u64,
         }
     }
 
     async fn response_from_proto(
         self,
-        message: proto::GetCompletionsResponse,
-        _project: Model<Project>,
-        buffer: Model<Buffer>,
-        mut cx: AsyncAppContext,
+        message: proto::GetSignatureHelpResponse,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
     ) -> Result<Self::Response> {
-        buffer
-            .update(&mut cx, |buffer, _| {
-                buffer.wait_for_version(deserialize_version(&message.version))
-            })?
+        let signatures = message
+            .signatures
+            .into_iter()
+            .map(|signature| SignatureInformation {
+                documentation: signature.documentation.map(|text| HoverBlock {
+                    text,
+                    kind: if signature.documentation_is_markdown {
+                        HoverBlockKind::Markdown
+                    } else {
+                        HoverBlockKind::PlainText
+                    },
+                }),
+                parameters: signature
+                    .parameters
+                    .into_iter()
+                    .map(|range| range.start as usize..range.end as usize)
+                    .collect(),
+                active_parameter: signature.active_parameter.map(|ix| ix as usize),
+                label: signature.label,
+            })
+            .collect::<Vec<_>>();
+        if signatures.is_empty() {
+            return Ok(None);
+        }
+
+        let active_signature = (message.active_signature as usize).min(signatures.len() - 1);
+        Ok(Some(SignatureHelp {
+            signatures,
+            active_signature,
+        }))
+    }
+
+    fn buffer_id_from_proto(message: &Self::ProtoRequest) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+/// Returns the byte ranges of a signature's parameters within its label. Parameters whose
+/// label can't be found in the signature's label get an empty range.
+fn signature_parameter_ranges(
+    label: &str,
+    parameters: &[lsp::ParameterInformation],
+) -> Vec<Range<usize>> {
+    let mut ranges = Vec::with_capacity(parameters.len());
+    let mut search_start = 0;
+    for parameter in parameters {
+        let range = match &parameter.label {
+            lsp::ParameterLabel::Simple(parameter_label) => label
+                .get(search_start..)
+                .and_then(|rest| rest.find(parameter_label.as_str()))
+                .map(|ix| search_start + ix..search_start + ix + parameter_label.len()),
+            lsp::ParameterLabel::LabelOffsets([start, end]) => {
+                let start = utf16_offset_to_byte_offset(label, *start as usize);
+                let end = utf16_offset_to_byte_offset(label, *end as usize);
+                start.zip(end).map(|(start, end)| start..end)
+            }
+        };
+        match range.filter(|range| range.start <= range.end) {
+            Some(range) => {
+                search_start = range.end;
+                ranges.push(range);
+            }
+            None => ranges.push(search_start..search_start),
+        }
+    }
+    ranges
+}
+
+fn utf16_offset_to_byte_offset(text: &str, utf16_offset: usize) -> Option<usize> {
+    let mut utf16_len = 0;
+    for (ix, char) in text.char_indices() {
+        if utf16_len == utf16_offset {
+            return Some(ix);
+        }
+        utf16_len += char.len_utf16();
+    }
+    (utf16_len == utf16_offset).then_some(text.len())
+}
+
+#[async_trait(?Send)]
+impl LspCommand for PrepareCallHierarchy {
+    type Response = Vec<CallHierarchyItem>;
+    type LspRequest = lsp::request::CallHierarchyPrepare;
+    type ProtoRequest = proto::PrepareCallHierarchy;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyPrepareParams {
+        lsp::CallHierarchyPrepareParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        items: Option<Vec<lsp::CallHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyItem>> {
+        let (lsp_adapter, language_server) =
+            language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let mut result = Vec::new();
+        for item in items.into_iter().flatten() {
+            result.push(
+                call_hierarchy_item_from_lsp(
+                    item,
+                    &project,
+                    &lsp_adapter,
+                    &language_server,
+                    &mut cx,
+                )
+                .await?,
+            );
+        }
+        Ok(result)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::PrepareCallHierarchy {
+        proto::PrepareCallHierarchy {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::PrepareCallHierarchy,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<CallHierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::PrepareCallHierarchyResponse {
+        proto::PrepareCallHierarchyResponse {
+            items: response
+                .iter()
+                .map(|item| call_hierarchy_item_to_proto(item, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::PrepareCallHierarchyResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyItem>> {
+        let mut items = Vec::new();
+        for item in message.items {
+            items.push(call_hierarchy_item_from_proto(item, &project, &mut cx).await?);
+        }
+        Ok(items)
+    }
+
+    fn buffer_id_from_proto(message: &proto::PrepareCallHierarchy) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetIncomingCalls {
+    type Response = Vec<CallHierarchyCall>;
+    type LspRequest = lsp::request::CallHierarchyIncomingCalls;
+    type ProtoRequest = proto::GetIncomingCalls;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyIncomingCallsParams {
+        lsp::CallHierarchyIncomingCallsParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        calls: Option<Vec<lsp::CallHierarchyIncomingCall>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyCall>> {
+        let (lsp_adapter, language_server) =
+            language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let mut result = Vec::new();
+        for call in calls.into_iter().flatten() {
+            let item = call_hierarchy_item_from_lsp(
+                call.from,
+                &project,
+                &lsp_adapter,
+                &language_server,
+                &mut cx,
+            )
+            .await?;
+            // The calls are made from within the caller.
+            let call_sites = item.location.buffer.update(&mut cx, |caller, _| {
+                call_sites_from_lsp(&item.location.buffer, caller, call.from_ranges)
+            })?;
+            result.push(CallHierarchyCall { item, call_sites });
+        }
+        Ok(result)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetIncomingCalls {
+        proto::GetIncomingCalls {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetIncomingCalls,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<CallHierarchyCall>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetIncomingCallsResponse {
+        proto::GetIncomingCallsResponse {
+            calls: response
+                .iter()
+                .map(|call| call_hierarchy_call_to_proto(call, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetIncomingCallsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyCall>> {
+        let mut calls = Vec::new();
+        for call in message.calls {
+            calls.push(call_hierarchy_call_from_proto(call, &project, &mut cx).await?);
+        }
+        Ok(calls)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetIncomingCalls) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetOutgoingCalls {
+    type Response = Vec<CallHierarchyCall>;
+    type LspRequest = lsp::request::CallHierarchyOutgoingCalls;
+    type ProtoRequest = proto::GetOutgoingCalls;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyOutgoingCallsParams {
+        lsp::CallHierarchyOutgoingCallsParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        calls: Option<Vec<lsp::CallHierarchyOutgoingCall>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyCall>> {
+        let (lsp_adapter, language_server) =
+            language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let mut result = Vec::new();
+        for call in calls.into_iter().flatten() {
+            let item = call_hierarchy_item_from_lsp(
+                call.to,
+                &project,
+                &lsp_adapter,
+                &language_server,
+                &mut cx,
+            )
+            .await?;
+            // The calls are made from within the item that was asked for, whose buffer this
+            // request was sent for.
+            let call_sites = buffer.update(&mut cx, |caller, _| {
+                call_sites_from_lsp(&buffer, caller, call.from_ranges)
+            })?;
+            result.push(CallHierarchyCall { item, call_sites });
+        }
+        Ok(result)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetOutgoingCalls {
+        proto::GetOutgoingCalls {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetOutgoingCalls,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<CallHierarchyCall>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetOutgoingCallsResponse {
+        proto::GetOutgoingCallsResponse {
+            calls: response
+                .iter()
+                .map(|call| call_hierarchy_call_to_proto(call, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetOutgoingCallsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyCall>> {
+        let mut calls = Vec::new();
+        for call in message.calls {
+            calls.push(call_hierarchy_call_from_proto(call, &project, &mut cx).await?);
+        }
+        Ok(calls)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetOutgoingCalls) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+fn supports_call_hierarchy(capabilities: &ServerCapabilities) -> bool {
+    match &capabilities.call_hierarchy_provider {
+        None => false,
+        Some(lsp::CallHierarchyServerCapability::Simple(false)) => false,
+        _ => true,
+    }
+}
+
+async fn call_hierarchy_item_from_lsp(
+    lsp_item: lsp::CallHierarchyItem,
+    project: &Model<Project>,
+    lsp_adapter: &Arc<CachedLspAdapter>,
+    language_server: &Arc<LanguageServer>,
+    cx: &mut AsyncAppContext,
+) -> Result<CallHierarchyItem> {
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_via_lsp(
+                lsp_item.uri.clone(),
+                language_server.server_id(),
+                lsp_adapter.name.clone(),
+                cx,
+            )
+        })?
+        .await?;
+    let range = buffer.update(cx, |buffer, _| {
+        let start =
+            buffer.clip_point_utf16(point_from_lsp(lsp_item.selection_range.start), Bias::Left);
+        let end = buffer.clip_point_utf16(point_from_lsp(lsp_item.selection_range.end), Bias::Left);
+        buffer.anchor_after(start)..buffer.anchor_before(end)
+    })?;
+    Ok(CallHierarchyItem::new(lsp_item, Location { buffer, range }))
+}
+
+fn call_sites_from_lsp(
+    buffer_handle: &Model<Buffer>,
+    buffer: &Buffer,
+    ranges: Vec<lsp::Range>,
+) -> Vec<Location> {
+    ranges
+        .into_iter()
+        .map(|range| {
+            let start = buffer.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+            let end = buffer.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+            Location {
+                buffer: buffer_handle.clone(),
+                range: buffer.anchor_after(start)..buffer.anchor_before(end),
+            }
+        })
+        .collect()
+}
+
+fn location_to_proto(
+    location: &Location,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::Location {
+    let buffer_id = project.create_buffer_for_peer(&location.buffer, peer_id, cx);
+    proto::Location {
+        start: Some(serialize_anchor(&location.range.start)),
+        end: Some(serialize_anchor(&location.range.end)),
+        buffer_id: buffer_id.into(),
+    }
+}
+
+async fn location_from_proto(
+    location: proto::Location,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<Location> {
+    let buffer_id = BufferId::new(location.buffer_id)?;
+    let buffer = project
+        .update(cx, |this, cx| this.wait_for_remote_buffer(buffer_id, cx))?
+        .await?;
+    let start = location
+        .start
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("missing location start"))?;
+    let end = location
+        .end
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("missing location end"))?;
+    buffer
+        .update(cx, |buffer, _| buffer.wait_for_anchors([start, end]))?
+        .await?;
+    Ok(Location {
+        buffer,
+        range: start..end,
+    })
+}
+
+fn call_hierarchy_item_to_proto(
+    item: &CallHierarchyItem,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::CallHierarchyItem {
+    proto::CallHierarchyItem {
+        location: Some(location_to_proto(&item.location, project, peer_id, cx)),
+        lsp_item: serde_json::to_vec(&item.lsp_item).unwrap(),
+    }
+}
+
+async fn call_hierarchy_item_from_proto(
+    item: proto::CallHierarchyItem,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<CallHierarchyItem> {
+    let location = item
+        .location
+        .ok_or_else(|| anyhow!("missing call hierarchy item location"))?;
+    let location = location_from_proto(location, project, cx).await?;
+    let lsp_item = serde_json::from_slice(&item.lsp_item)?;
+    Ok(CallHierarchyItem::new(lsp_item, location))
+}
+
+fn call_hierarchy_call_to_proto(
+    call: &CallHierarchyCall,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::CallHierarchyCall {
+    proto::CallHierarchyCall {
+        item: Some(call_hierarchy_item_to_proto(
+            &call.item, project, peer_id, cx,
+        )),
+        call_sites: call
+            .call_sites
+            .iter()
+            .map(|location| location_to_proto(location, project, peer_id, cx))
+            .collect(),
+    }
+}
+
+async fn call_hierarchy_call_from_proto(
+    call: proto::CallHierarchyCall,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<CallHierarchyCall> {
+    let item = call
+        .item
+        .ok_or_else(|| anyhow!("missing call hierarchy item"))?;
+    let item = call_hierarchy_item_from_proto(item, project, cx).await?;
+    let mut call_sites = Vec::new();
+    for location in call.call_sites {
+        call_sites.push(location_from_proto(location, project, cx).await?);
+    }
+    Ok(CallHierarchyCall { item, call_sites })
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetCompletions {
+    type Response = Vec<CoreCompletion>;
+    type LspRequest = lsp::request::Completion;
+    type ProtoRequest = proto::GetCompletions;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CompletionParams {
+        lsp::CompletionParams {
+            text_document_position: lsp::TextDocumentPositionParams::new(
+                lsp::TextDocumentIdentifier::new(lsp::Url::from_file_path(path).unwrap()),
+                point_to_lsp(self.position),
+            ),
+            context: Default::default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        completions: Option<lsp::CompletionResponse>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self::Response> {
+        let mut response_list = None;
+        let mut completions = if let Some(completions) = completions {
+            match completions {
+                lsp::CompletionResponse::Array(completions) => completions,
+
+                lsp::CompletionResponse::List(mut list) => {
+                    let items = std::mem::take(&mut list.items);
+                    response_list = Some(list);
+                    items
+                }
+            }
+        } else {
+            Default::default()
+        };
+
+        let language_server_adapter = project
+            .update(&mut cx, |project, _cx| {
+                project.language_server_adapter_for_id(server_id)
+            })?
+            .ok_or_else(|| anyhow!("no such language server"))?;
+
+        let item_defaults = response_list
+            .as_ref()
+            .and_then(|list| list.item_defaults.as_ref());
+
+        if let Some(item_defaults) = item_defaults {
+            let default_data = item_defaults.data.as_ref();
+            let default_commit_characters = item_defaults.commit_characters.as_ref();
+            let default_insert_text_mode = item_defaults.insert_text_mode.as_ref();
+
+            if default_data.is_some()
+                || default_commit_characters.is_some()
+                || default_insert_text_mode.is_some()
+            {
+                for item in completions.iter_mut() {
+                    if let Some(data) = default_data {
+                        item.data = Some(data.clone())
+                    }
+                    if let Some(characters) = default_commit_characters {
+                        item.commit_characters = Some(characters.clone())
+                    }
+                    if let Some(text_mode) = default_insert_text_mode {
+                        item.insert_text_mode = Some(*text_mode)
+                    }
+                }
+            }
+        }
+
+        let mut completion_edits = Vec::new();
+        buffer.update(&mut cx, |buffer, _cx| {
+            let snapshot = buffer.snapshot();
+            let clipped_position = buffer.clip_point_utf16(Unclipped(self.position), Bias::Left);
+
+            let mut range_for_token = None;
+            completions.retain_mut(|lsp_completion| {
+                let edit = match lsp_completion.text_edit.as_ref() {
+                    // If the language server provides a range to overwrite, then
+                    // check that the range is valid.
+                    Some(completion_text_edit) => {
+                        match parse_completion_text_edit(completion_text_edit, &snapshot) {
+                            Some(edit) => edit,
+                            None => return false,
+                        }
+                    }
+
+                    // If the language server does not provide a range, then infer
+                    // the range based on the syntax tree.
+                    None => {
+                        if self.position != clipped_position {
+                            log::info!("completion out of expected range");
+                            return false;
+                        }
+
+                        let default_edit_range = response_list
+                            .as_ref()
+                            .and_then(|list| list.item_defaults.as_ref())
+                            .and_then(|defaults| defaults.edit_range.as_ref())
+                            .and_then(|range| match range {
+                                CompletionListItemDefaultsEditRange::Range(r) => Some(r),
+                                _ => None,
+                            });
+
+                        let range = if let Some(range) = default_edit_range {
+                            let range = range_from_lsp(*range);
+                            let start = snapshot.clip_point_utf16(range.start, Bias::Left);
+                            let end = snapshot.clip_point_utf16(range.end, Bias::Left);
+                            if start != range.start.0 || end != range.end.0 {
+                                log::info!("completion out of expected range");
+                                return false;
+                            }
+
+                            snapshot.anchor_before(start)..snapshot.anchor_after(end)
+                        } else {
+                            range_for_token
+                                .get_or_insert_with(|| {
+                                    let offset = self.position.to_offset(&snapshot);
+                                    let (range, kind) = snapshot.surrounding_word(offset);
+                                    let range = if kind == Some(CharKind::Word) {
+                                        range
+                                    } else {
+                                        offset..offset
+                                    };
+
+                                    snapshot.anchor_before(range.start)
+                                        ..snapshot.anchor_after(range.end)
+                                })
+                                .clone()
+                        };
+
+                        let text = lsp_completion
+                            .insert_text
+                            .as_ref()
+                            .unwrap_or(&lsp_completion.label)
+                            .clone();
+                        (range, text)
+                    }
+                };
+
+                completion_edits.push(edit);
+                true
+            });
+        })?;
+
+        language_server_adapter
+            .process_completions(&mut completions)
+            .await;
+
+        Ok(completions
+            .into_iter()
+            .zip(completion_edits)
+            .map(|(lsp_completion, (old_range, mut new_text))| {
+                LineEnding::normalize(&mut new_text);
+                CoreCompletion {
+                    old_range,
+                    new_text,
+                    server_id,
+                    lsp_completion,
+                }
+            })
+            .collect())
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetCompletions {
+        let anchor = buffer.anchor_after(self.position);
+        proto::GetCompletions {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(&anchor)),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetCompletions,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let version = deserialize_version(&message.version);
+        buffer
+            .update(&mut cx, |buffer, _| buffer.wait_for_version(version))?
+            .await?;
+        let position = message
+            .position
+            .and_then(language::proto::deserialize_anchor)
+            .map(|p| {
+                buffer.update(&mut cx, |buffer, _| {
+                    buffer.clip_point_utf16(Unclipped(p.to_point_utf16(buffer)), Bias::Left)
+                })
+            })
+            .ok_or_else(|| anyhow!("invalid position"))??;
+        Ok(Self { position })
+    }
+
+    fn response_to_proto(
+        completions: Vec<CoreCompletion>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetCompletionsResponse {
+        proto::GetCompletionsResponse {
+            completions: completions
+                .iter()
+                .map(Project::serialize_completion)
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetCompletionsResponse,
+        _project: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self::Response> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
             .await?;
 
         message
@@ -1897,6 +2670,325 @@ impl GetCodeActions {
     }
 }
 
+#[async_trait(?Send)]
+impl LspCommand for GetCodeLens {
+    type Response = Vec<CodeLens>;
+    type LspRequest = lsp::request::CodeLensRequest;
+    type ProtoRequest = proto::GetCodeLens;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities.code_lens_provider.is_some()
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CodeLensParams {
+        lsp::CodeLensParams {
+            text_document: lsp::TextDocumentIdentifier::new(
+                lsp::Url::from_file_path(path).unwrap(),
+            ),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        lsp_lenses: Option<Vec<lsp::CodeLens>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CodeLens>> {
+        let language_server = project
+            .update(&mut cx, |project, _| {
+                project.language_server_for_id(server_id)
+            })?
+            .ok_or_else(|| anyhow!("no language server found for buffer"))?;
+
+        // Lenses are shown as soon as they're received, so resolve the commands of the ones
+        // that the server left out up front. A lens that fails to resolve is kept without one.
+        let can_resolve = Self::can_resolve_lenses(&language_server.capabilities());
+        let lsp_lenses =
+            future::join_all(lsp_lenses.unwrap_or_default().into_iter().map(|lsp_lens| {
+                let language_server = language_server.clone();
+                async move {
+                    if lsp_lens.command.is_none() && can_resolve {
+                        if let Some(resolved_lens) = language_server
+                            .request::<lsp::request::CodeLensResolve>(lsp_lens.clone())
+                            .await
+                            .log_err()
+                        {
+                            return resolved_lens;
+                        }
+                    }
+                    lsp_lens
+                }
+            }))
+            .await;
+
+        buffer.update(&mut cx, |buffer, _| {
+            lsp_lenses
+                .into_iter()
+                .map(|lsp_lens| {
+                    let start =
+                        buffer.clip_point_utf16(point_from_lsp(lsp_lens.range.start), Bias::Left);
+                    let end =
+                        buffer.clip_point_utf16(point_from_lsp(lsp_lens.range.end), Bias::Left);
+                    CodeLens {
+                        server_id,
+                        range: buffer.anchor_after(start)..buffer.anchor_before(end),
+                        lsp_lens,
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetCodeLens {
+        proto::GetCodeLens {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetCodeLens,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        lenses: Vec<CodeLens>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetCodeLensResponse {
+        proto::GetCodeLensResponse {
+            lenses: lenses
+                .iter()
+                .map(|lens| proto::CodeLens {
+                    server_id: lens.server_id.0 as u64,
+                    start: Some(serialize_anchor(&lens.range.start)),
+                    end: Some(serialize_anchor(&lens.range.end)),
+                    lsp_lens: serde_json::to_vec(&lens.lsp_lens).unwrap(),
+                })
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetCodeLensResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CodeLens>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .lenses
+            .into_iter()
+            .map(|lens| {
+                let start = lens
+                    .start
+                    .and_then(deserialize_anchor)
+                    .ok_or_else(|| anyhow!("invalid start"))?;
+                let end = lens
+                    .end
+                    .and_then(deserialize_anchor)
+                    .ok_or_else(|| anyhow!("invalid end"))?;
+                Ok(CodeLens {
+                    server_id: LanguageServerId(lens.server_id as usize),
+                    range: start..end,
+                    lsp_lens: serde_json::from_slice(&lens.lsp_lens)?,
+                })
+            })
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetCodeLens) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+impl GetCodeLens {
+    pub fn can_resolve_lenses(capabilities: &ServerCapabilities) -> bool {
+        capabilities
+            .code_lens_provider
+            .as_ref()
+            .and_then(|options| options.resolve_provider)
+            .unwrap_or(false)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetDocumentColor {
+    type Response = Vec<DocumentColor>;
+    type LspRequest = lsp::request::DocumentColor;
+    type ProtoRequest = proto::GetDocumentColor;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities
+            .color_provider
+            .as_ref()
+            .map_or(false, |provider| match provider {
+                lsp::ColorProviderCapability::Simple(is_supported) => *is_supported,
+                lsp::ColorProviderCapability::ColorProvider(_)
+                | lsp::ColorProviderCapability::Options(_) => true,
+            })
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::DocumentColorParams {
+        lsp::DocumentColorParams {
+            text_document: lsp::TextDocumentIdentifier::new(
+                lsp::Url::from_file_path(path).unwrap(),
+            ),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        colors: Vec<lsp::ColorInformation>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        _: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<DocumentColor>> {
+        buffer.update(&mut cx, |buffer, _| {
+            colors
+                .into_iter()
+                .map(|info| {
+                    let start =
+                        buffer.clip_point_utf16(point_from_lsp(info.range.start), Bias::Left);
+                    let end = buffer.clip_point_utf16(point_from_lsp(info.range.end), Bias::Left);
+                    DocumentColor {
+                        range: buffer.anchor_after(start)..buffer.anchor_before(end),
+                        color: Rgba {
+                            r: info.color.red,
+                            g: info.color.green,
+                            b: info.color.blue,
+                            a: info.color.alpha,
+                        },
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetDocumentColor {
+        proto::GetDocumentColor {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetDocumentColor,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        colors: Vec<DocumentColor>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetDocumentColorResponse {
+        proto::GetDocumentColorResponse {
+            colors: colors
+                .iter()
+                .map(|color| proto::DocumentColor {
+                    start: Some(serialize_anchor(&color.range.start)),
+                    end: Some(serialize_anchor(&color.range.end)),
+                    red: color.color.r,
+                    green: color.color.g,
+                    blue: color.color.b,
+                    alpha: color.color.a,
+                })
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetDocumentColorResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<DocumentColor>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .colors
+            .into_iter()
+            .map(|color| {
+                let start = color
+                    .start
+                    .and_then(deserialize_anchor)
+                    .ok_or_else(|| anyhow!("invalid start"))?;
+                let end = color
+                    .end
+                    .and_then(deserialize_anchor)
+                    .ok_or_else(|| anyhow!("invalid end"))?;
+                Ok(DocumentColor {
+                    range: start..end,
+                    color: Rgba {
+                        r: color.red,
+                        g: color.green,
+                        b: color.blue,
+                        a: color.alpha,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetDocumentColor) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
 #[async_trait(?Send)]
 impl LspCommand for OnTypeFormatting {
     type Response = Option<Transaction>;
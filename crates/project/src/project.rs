@@ -1,12 +1,27 @@
+mod build_targets;
 pub mod connection_manager;
 pub mod debounced_delay;
+mod debugger;
+pub mod detected_links;
+mod directory_sizes;
+pub mod document_colors;
+mod environment;
+mod file_operations;
+pub mod job_scheduler;
+pub mod local_history;
 pub mod lsp_command;
 pub mod lsp_ext_command;
 mod prettier_support;
+pub mod project_driver;
 pub mod project_settings;
+mod save_hooks;
 pub mod search;
+mod settings_lsp_adapter;
+mod symbol_docs;
+mod syntax_index;
 mod task_inventory;
 pub mod terminals;
+mod worktree_store;
 
 #[cfg(test)]
 mod project_tests;
@@ -14,6 +29,7 @@ pub mod search_history;
 
 use anyhow::{anyhow, bail, Context as _, Result};
 use async_trait::async_trait;
+use build_targets::BuildTargets;
 use client::{
     proto, Client, Collaborator, DevServerProjectId, PendingEntitySubscription, ProjectId,
     TypedEnvelope, UserStore,
@@ -21,6 +37,8 @@ use client::{
 use clock::ReplicaId;
 use collections::{hash_map, BTreeMap, HashMap, HashSet, VecDeque};
 use debounced_delay::DebouncedDelay;
+use directory_sizes::DirectorySizes;
+use environment::ProjectEnvironment;
 use futures::{
     channel::{
         mpsc::{self, UnboundedReceiver},
@@ -32,26 +50,34 @@ use futures::{
     AsyncWriteExt, Future, FutureExt, StreamExt, TryFutureExt,
 };
 use fuzzy::CharBag;
-use git::{blame::Blame, repository::GitRepository};
+use git::{
+    blame::Blame,
+    file_history::FileHistoryEntry,
+    repository::{GitRepository, MergeVersions, RepoPath},
+};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use gpui::{
     AnyModel, AppContext, AsyncAppContext, BackgroundExecutor, BorrowAppContext, Context, Entity,
     EventEmitter, Model, ModelContext, PromptLevel, SharedString, Task, WeakModel,
 };
 use itertools::Itertools;
+use job_scheduler::{JobGroup, JobPriority, JobScheduler};
 use language::{
-    language_settings::{language_settings, FormatOnSave, Formatter, InlayHintKind},
+    language_settings::{
+        language_settings, FormatOnSave, Formatter, InlayHintKind, LanguageSettings, SaveHookKind,
+    },
     markdown, point_to_lsp, prepare_completion_documentation,
     proto::{
         deserialize_anchor, deserialize_line_ending, deserialize_version, serialize_anchor,
         serialize_version, split_operations,
     },
-    range_from_lsp, Bias, Buffer, BufferSnapshot, CachedLspAdapter, Capability, CodeLabel,
-    ContextProvider, Diagnostic, DiagnosticEntry, DiagnosticSet, Diff, Documentation,
+    range_from_lsp, Bias, Buffer, BufferSnapshot, CachedLspAdapter, Capability, CharKind,
+    CodeLabel, ContextProvider, Diagnostic, DiagnosticEntry, DiagnosticSet, Diff, Documentation,
     Event as BufferEvent, File as _, Language, LanguageRegistry, LanguageServerName, LocalFile,
     LspAdapterDelegate, Operation, Patch, PendingLanguageServer, PointUtf16, TextBufferSnapshot,
     ToOffset, ToPointUtf16, Transaction, Unclipped,
 };
+use local_history::{LocalHistory, LocalHistoryVersion};
 use log::error;
 use lsp::{
     DiagnosticSeverity, DiagnosticTag, DidChangeWatchedFilesRegistrationOptions,
@@ -67,6 +93,7 @@ use prettier_support::{DefaultPrettier, PrettierInstance};
 use project_settings::{LspSettings, ProjectSettings};
 use rand::prelude::*;
 use search_history::SearchHistory;
+use settings_lsp_adapter::SettingsLspAdapter;
 use snippet::Snippet;
 use worktree::LocalSnapshot;
 
@@ -83,7 +110,6 @@ use std::{
     borrow::Cow,
     cmp::{self, Ordering},
     convert::TryInto,
-    env,
     ffi::OsStr,
     hash::Hash,
     io, iter, mem,
@@ -98,21 +124,30 @@ use std::{
     },
     time::{Duration, Instant},
 };
+use symbol_docs::SymbolDocs;
+use syntax_index::SyntaxIndex;
 use task::{
     static_source::{StaticSource, TrackedFile},
     RevealStrategy, TaskContext, TaskTemplate, TaskVariables, VariableName,
 };
 use terminals::Terminals;
-use text::{Anchor, BufferId, LineEnding};
+use text::{Anchor, BufferId, LineEnding, TransactionId};
 use util::{
-    debug_panic, defer, maybe, merge_json_value_into, parse_env_output,
+    debug_panic, defer, maybe, merge_json_value_into,
     paths::{
-        LOCAL_SETTINGS_RELATIVE_PATH, LOCAL_TASKS_RELATIVE_PATH, LOCAL_VSCODE_TASKS_RELATIVE_PATH,
+        PathMatcher, LOCAL_SETTINGS_RELATIVE_PATH, LOCAL_TASKS_RELATIVE_PATH,
+        LOCAL_VSCODE_TASKS_RELATIVE_PATH,
     },
     post_inc, ResultExt, TryFutureExt as _,
 };
 use worktree::{Snapshot, Traversal};
+use worktree_store::WorktreeStore;
 
+pub use build_targets::{BuildTarget, BuildTargetKind, ProjectKind, ProjectManifest};
+pub use debugger::{DebugSession, DebugSessionEvent, DebugSessionStatus};
+pub use detected_links::{DetectedLink, LinkTarget};
+pub use directory_sizes::PathSize;
+pub use document_colors::{ColorFormat, DocumentColor};
 pub use fs::*;
 pub use language::Location;
 #[cfg(any(test, feature = "test-support"))]
@@ -121,9 +156,10 @@ pub use task_inventory::{
     BasicContextProvider, ContextProviderWithTasks, Inventory, TaskSourceKind,
 };
 pub use worktree::{
-    DiagnosticSummary, Entry, EntryKind, File, LocalWorktree, PathChange, ProjectEntryId,
-    RepositoryEntry, UpdatedEntriesSet, UpdatedGitRepositoriesSet, Worktree, WorktreeId,
-    WorktreeSettings, FS_WATCH_LATENCY,
+    DiagnosticSummary, DirPathResolution, Durability, Entry, EntryKind, File, FileOperation,
+    FileOperationsOutcome, FileOperationsProgress, LoadedFileChange, LocalWorktree, PathChange,
+    ProjectEntryId, RepositoryEntry, SaveConflict, UpdatedEntriesSet, UpdatedGitRepositoriesSet,
+    Worktree, WorktreeId, WorktreeSettings, WorktreeStats, FS_WATCH_LATENCY,
 };
 
 const MAX_SERVER_REINSTALL_ATTEMPT_COUNT: u64 = 4;
@@ -158,8 +194,7 @@ pub enum OpenedBufferEvent {
 ///
 /// Can be either local (for the project opened on the same host) or remote.(for collab projects, browsed by multiple remote users).
 pub struct Project {
-    worktrees: Vec<WorktreeHandle>,
-    worktrees_reordered: bool,
+    worktree_store: WorktreeStore,
     active_entry: Option<ProjectEntryId>,
     buffer_ordered_messages_tx: mpsc::UnboundedSender<BufferOrderedMessage>,
     pending_language_server_update: Option<BufferOrderedMessage>,
@@ -182,6 +217,15 @@ pub struct Project {
     next_diagnostic_group_id: usize,
     user_store: Model<UserStore>,
     fs: Arc<dyn Fs>,
+    local_history: Option<Arc<LocalHistory>>,
+    directory_sizes: DirectorySizes,
+    build_targets: BuildTargets,
+    syntax_index: SyntaxIndex,
+    syntax_index_enabled: bool,
+    syntax_indexing: Option<Task<()>>,
+    symbol_docs: Arc<SymbolDocs>,
+    environment: ProjectEnvironment,
+    job_scheduler: Model<JobScheduler>,
     client_state: ProjectClientState,
     collaborators: HashMap<proto::PeerId, Collaborator>,
     client_subscriptions: Vec<client::Subscription>,
@@ -217,6 +261,9 @@ pub struct Project {
     hosted_project_id: Option<ProjectId>,
     dev_server_project_id: Option<client::DevServerProjectId>,
     search_history: SearchHistory,
+    breakpoints: debugger::Breakpoints,
+    debug_sessions: Vec<Model<DebugSession>>,
+    next_debug_adapter_id: usize,
 }
 
 pub enum LanguageServerToQuery {
@@ -258,12 +305,6 @@ enum OpenBuffer {
     Operations(Vec<Operation>),
 }
 
-#[derive(Clone)]
-enum WorktreeHandle {
-    Strong(Model<Worktree>),
-    Weak(WeakModel<Worktree>),
-}
-
 #[derive(Debug)]
 enum ProjectClientState {
     Local,
@@ -321,6 +362,9 @@ pub enum Event {
     WorktreeRemoved(WorktreeId),
     WorktreeUpdatedEntries(WorktreeId, UpdatedEntriesSet),
     WorktreeUpdatedGitRepositories,
+    WorktreeFileOperationsProgress(WorktreeId, FileOperationsProgress),
+    /// The file of a buffer with unsaved changes was changed on disk by another program.
+    BufferChangedOnDisk(Model<Buffer>),
     DiskBasedDiagnosticsStarted {
         language_server_id: LanguageServerId,
     },
@@ -344,6 +388,8 @@ pub enum Event {
     RefreshInlayHints,
     RevealInProjectPanel(ProjectEntryId),
     SnippetEdit(BufferId, Vec<(lsp::Range, Snippet)>),
+    BreakpointsChanged(BufferId),
+    BuildTargetsChanged(WorktreeId),
 }
 
 pub enum LanguageServerState {
@@ -551,6 +597,90 @@ impl Hover {
     }
 }
 
+/// The signatures of the function or method being called at a position in a buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureHelp {
+    pub signatures: Vec<SignatureInformation>,
+    pub active_signature: usize,
+}
+
+impl SignatureHelp {
+    pub fn active_signature(&self) -> Option<&SignatureInformation> {
+        self.signatures.get(self.active_signature)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureInformation {
+    pub label: String,
+    pub documentation: Option<HoverBlock>,
+    /// The byte range of each parameter within the label.
+    pub parameters: Vec<Range<usize>>,
+    /// The index of the parameter that's being typed.
+    pub active_parameter: Option<usize>,
+}
+
+impl SignatureInformation {
+    /// Returns the byte range of the active parameter within the label.
+    pub fn active_parameter_range(&self) -> Option<Range<usize>> {
+        let range = self.parameters.get(self.active_parameter?)?;
+        self.label.get(range.clone())?;
+        Some(range.clone())
+    }
+}
+
+/// A function, method or other symbol that makes or receives calls.
+#[derive(Debug, Clone)]
+pub struct CallHierarchyItem {
+    pub name: String,
+    pub kind: lsp::SymbolKind,
+    pub detail: Option<String>,
+    /// The location of the symbol's name.
+    pub location: Location,
+    /// The language server's representation of the item, which it needs to find its calls.
+    pub(crate) lsp_item: lsp::CallHierarchyItem,
+}
+
+impl CallHierarchyItem {
+    pub(crate) fn new(lsp_item: lsp::CallHierarchyItem, location: Location) -> Self {
+        Self {
+            name: lsp_item.name.clone(),
+            kind: lsp_item.kind,
+            detail: lsp_item.detail.clone(),
+            location,
+            lsp_item,
+        }
+    }
+}
+
+/// The calls from one item of the call hierarchy to another.
+#[derive(Debug, Clone)]
+pub struct CallHierarchyCall {
+    /// The caller, for incoming calls, or the callee, for outgoing calls.
+    pub item: CallHierarchyItem,
+    /// The ranges of the calls, which are always within the caller.
+    pub call_sites: Vec<Location>,
+}
+
+/// A command that a language server shows above a range of a buffer, such as running the
+/// test defined there or listing the references to the function defined there.
+#[derive(Debug, Clone)]
+pub struct CodeLens {
+    /// The id of the language server that produced this code lens.
+    pub server_id: LanguageServerId,
+    /// The range of the buffer that the code lens applies to.
+    pub range: Range<Anchor>,
+    /// The raw code lens provided by the language server.
+    pub lsp_lens: lsp::CodeLens,
+}
+
+impl CodeLens {
+    /// The command to run when the code lens is clicked, if it was resolved.
+    pub fn command(&self) -> Option<&lsp::Command> {
+        self.lsp_lens.command.as_ref()
+    }
+}
+
 #[derive(Default)]
 pub struct ProjectTransaction(pub HashMap<Model<Buffer>, language::Transaction>);
 
@@ -663,8 +793,15 @@ impl Project {
         client.add_model_request_handler(Self::handle_lsp_command::<GetCodeActions>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetCompletions>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetHover>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSignatureHelp>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetDefinition>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetTypeDefinition>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetImplementation>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareCallHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetIncomingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetOutgoingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetCodeLens>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetDocumentColor>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetDocumentHighlights>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetReferences>);
         client.add_model_request_handler(Self::handle_lsp_command::<PrepareRename>);
@@ -699,8 +836,7 @@ impl Project {
             let tasks = Inventory::new(cx);
 
             Self {
-                worktrees: Vec::new(),
-                worktrees_reordered: false,
+                worktree_store: WorktreeStore::default(),
                 buffer_ordered_messages_tx: tx,
                 flush_language_server_update: None,
                 pending_language_server_update: None,
@@ -728,6 +864,18 @@ impl Project {
                 languages,
                 client,
                 user_store,
+                local_history: Some(LocalHistory::global(fs.clone(), cx)),
+                directory_sizes: Default::default(),
+                build_targets: Default::default(),
+                syntax_index: Default::default(),
+                syntax_index_enabled: ProjectSettings::get_global(cx).syntax_index.enabled(),
+                syntax_indexing: None,
+                symbol_docs: Default::default(),
+                breakpoints: Default::default(),
+                debug_sessions: Vec::new(),
+                next_debug_adapter_id: 0,
+                environment: Default::default(),
+                job_scheduler: cx.new_model(JobScheduler::new),
                 fs,
                 next_entry_id: Default::default(),
                 next_diagnostic_group_id: Default::default(),
@@ -833,8 +981,7 @@ impl Project {
             cx.spawn(move |this, cx| Self::send_buffer_ordered_messages(this, rx, cx))
                 .detach();
             let mut this = Self {
-                worktrees: Vec::new(),
-                worktrees_reordered: false,
+                worktree_store: WorktreeStore::default(),
                 buffer_ordered_messages_tx: tx,
                 pending_language_server_update: None,
                 flush_language_server_update: None,
@@ -853,6 +1000,18 @@ impl Project {
                 languages,
                 user_store: user_store.clone(),
                 fs,
+                local_history: None,
+                directory_sizes: Default::default(),
+                build_targets: Default::default(),
+                syntax_index: Default::default(),
+                syntax_index_enabled: false,
+                syntax_indexing: None,
+                symbol_docs: Default::default(),
+                breakpoints: Default::default(),
+                debug_sessions: Vec::new(),
+                next_debug_adapter_id: 0,
+                environment: Default::default(),
+                job_scheduler: cx.new_model(JobScheduler::new),
                 next_entry_id: Default::default(),
                 next_diagnostic_group_id: Default::default(),
                 client_subscriptions: Default::default(),
@@ -1192,6 +1351,20 @@ impl Project {
             self.restart_language_servers(worktree, language, cx);
         }
 
+        let syntax_index_enabled = ProjectSettings::get_global(cx).syntax_index.enabled();
+        if syntax_index_enabled != self.syntax_index_enabled && self.is_local() {
+            self.syntax_index_enabled = syntax_index_enabled;
+            if syntax_index_enabled {
+                let worktrees = self.visible_worktrees(cx).collect::<Vec<_>>();
+                for worktree in worktrees {
+                    self.index_local_worktree_syntax(&worktree, cx);
+                }
+            } else {
+                self.syntax_index.clear();
+                self.syntax_indexing = None;
+            }
+        }
+
         cx.notify();
     }
 
@@ -1247,6 +1420,64 @@ impl Project {
         &self.fs
     }
 
+    pub fn job_scheduler(&self) -> &Model<JobScheduler> {
+        &self.job_scheduler
+    }
+
+    /// Returns the environment that language servers, formatters and tasks run in for the given
+    /// worktree: the login shell environment captured in the worktree the first time it's
+    /// needed, with the variables from its `env` setting on top.
+    pub fn worktree_environment(
+        &self,
+        worktree_id: WorktreeId,
+        cx: &AppContext,
+    ) -> Task<HashMap<String, String>> {
+        let overrides = self.environment_overrides(worktree_id, cx);
+        let shell_environment = self.worktree_for_id(worktree_id, cx).and_then(|worktree| {
+            let worktree = worktree.read(cx).as_local()?;
+            Some(self.environment.shell_environment(
+                worktree_id,
+                worktree.abs_path().clone(),
+                cx.background_executor(),
+            ))
+        });
+        cx.background_executor().spawn(async move {
+            let mut environment = match shell_environment {
+                Some(shell_environment) => shell_environment.await.unwrap_or_default(),
+                None => HashMap::default(),
+            };
+            environment.extend(overrides);
+            environment
+        })
+    }
+
+    fn buffer_environment(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Task<HashMap<String, String>> {
+        match File::from_dyn(buffer.read(cx).file()) {
+            Some(file) => self.worktree_environment(file.worktree_id(cx), cx),
+            None => Task::ready(HashMap::default()),
+        }
+    }
+
+    fn environment_overrides(
+        &self,
+        worktree_id: WorktreeId,
+        cx: &AppContext,
+    ) -> HashMap<String, String> {
+        ProjectSettings::get(
+            Some(SettingsLocation {
+                worktree_id: worktree_id.to_usize(),
+                path: Path::new(""),
+            }),
+            cx,
+        )
+        .env
+        .clone()
+    }
+
     pub fn remote_id(&self) -> Option<u64> {
         match self.client_state {
             ProjectClientState::Local => None,
@@ -1313,14 +1544,13 @@ impl Project {
     }
 
     pub fn set_worktrees_reordered(&mut self, worktrees_reordered: bool) {
-        self.worktrees_reordered = worktrees_reordered;
+        self.worktree_store
+            .set_worktrees_reordered(worktrees_reordered);
     }
 
     /// Collect all worktrees, including ones that don't appear in the project panel
     pub fn worktrees(&self) -> impl '_ + DoubleEndedIterator<Item = Model<Worktree>> {
-        self.worktrees
-            .iter()
-            .filter_map(move |worktree| worktree.upgrade())
+        self.worktree_store.worktrees()
     }
 
     /// Collect all user-visible worktrees, the ones that appear in the project panel.
@@ -1328,8 +1558,7 @@ impl Project {
         &'a self,
         cx: &'a AppContext,
     ) -> impl 'a + DoubleEndedIterator<Item = Model<Worktree>> {
-        self.worktrees()
-            .filter(|worktree| worktree.read(cx).is_visible())
+        self.worktree_store.visible_worktrees(cx)
     }
 
     pub fn worktree_root_names<'a>(&'a self, cx: &'a AppContext) -> impl Iterator<Item = &'a str> {
@@ -1338,8 +1567,7 @@ impl Project {
     }
 
     pub fn worktree_for_id(&self, id: WorktreeId, cx: &AppContext) -> Option<Model<Worktree>> {
-        self.worktrees()
-            .find(|worktree| worktree.read(cx).id() == id)
+        self.worktree_store.worktree_for_id(id, cx)
     }
 
     pub fn worktree_for_entry(
@@ -1347,8 +1575,7 @@ impl Project {
         entry_id: ProjectEntryId,
         cx: &AppContext,
     ) -> Option<Model<Worktree>> {
-        self.worktrees()
-            .find(|worktree| worktree.read(cx).contains_entry(entry_id))
+        self.worktree_store.worktree_for_entry(entry_id, cx)
     }
 
     pub fn worktree_id_for_entry(
@@ -1403,12 +1630,7 @@ impl Project {
             return Task::ready(Ok(None));
         };
         if self.is_local() {
-            worktree.update(cx, |worktree, cx| {
-                worktree
-                    .as_local_mut()
-                    .unwrap()
-                    .create_entry(project_path.path, is_directory, cx)
-            })
+            self.create_local_entry(worktree, project_path.path, is_directory, cx)
         } else {
             let client = self.client.clone();
             let project_id = self.remote_id().unwrap();
@@ -1495,12 +1717,7 @@ impl Project {
         };
         let new_path = new_path.into();
         if self.is_local() {
-            worktree.update(cx, |worktree, cx| {
-                worktree
-                    .as_local_mut()
-                    .unwrap()
-                    .rename_entry(entry_id, new_path, cx)
-            })
+            self.rename_local_entry(worktree, entry_id, new_path, cx)
         } else {
             let client = self.client.clone();
             let project_id = self.remote_id().unwrap();
@@ -1541,12 +1758,7 @@ impl Project {
         cx.emit(Event::DeletedEntry(entry_id));
 
         if self.is_local() {
-            worktree.update(cx, |worktree, cx| {
-                worktree
-                    .as_local_mut()
-                    .unwrap()
-                    .delete_entry(entry_id, trash, cx)
-            })
+            self.delete_local_entry(worktree, entry_id, trash, cx)
         } else {
             let client = self.client.clone();
             let project_id = self.remote_id().unwrap();
@@ -1571,6 +1783,31 @@ impl Project {
         }
     }
 
+    /// Moves, copies and deletes many files of a local worktree at once, e.g. when dragging a
+    /// folder. See [`LocalWorktree::apply_file_operations`].
+    pub fn apply_file_operations(
+        &mut self,
+        worktree_id: WorktreeId,
+        operations: Vec<FileOperation>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<FileOperationsOutcome>> {
+        let Some(worktree) = self.worktree_for_id(worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no such worktree")));
+        };
+        let apply = worktree.update(cx, |worktree, cx| {
+            worktree
+                .as_local()
+                .map(|worktree| worktree.apply_file_operations(operations, cx))
+        });
+        let Some(apply) = apply else {
+            return Task::ready(Err(anyhow!(
+                "bulk file operations are only supported in local worktrees"
+            )));
+        };
+        cx.background_executor()
+            .spawn(async move { Ok(apply.await) })
+    }
+
     pub fn expand_entry(
         &mut self,
         worktree_id: WorktreeId,
@@ -1636,16 +1873,7 @@ impl Project {
             }
         }
 
-        for worktree_handle in self.worktrees.iter_mut() {
-            match worktree_handle {
-                WorktreeHandle::Strong(_) => {}
-                WorktreeHandle::Weak(worktree) => {
-                    if let Some(worktree) = worktree.upgrade() {
-                        *worktree_handle = WorktreeHandle::Strong(worktree);
-                    }
-                }
-            }
-        }
+        self.worktree_store.retain_all();
 
         for (server_id, status) in &self.language_server_statuses {
             self.client
@@ -1787,10 +2015,8 @@ impl Project {
         cx: &mut ModelContext<Self>,
     ) -> Result<()> {
         cx.update_global::<SettingsStore, _>(|store, cx| {
-            for worktree in &self.worktrees {
-                store
-                    .clear_local_settings(worktree.handle_id(), cx)
-                    .log_err();
+            for handle_id in self.worktree_store.handle_ids() {
+                store.clear_local_settings(handle_id, cx).log_err();
             }
         });
 
@@ -1843,17 +2069,12 @@ impl Project {
             self.shared_buffers.clear();
             self.client_subscriptions.clear();
 
-            for worktree_handle in self.worktrees.iter_mut() {
-                if let WorktreeHandle::Strong(worktree) = worktree_handle {
-                    let is_visible = worktree.update(cx, |worktree, _| {
-                        worktree.as_local_mut().unwrap().unshare();
-                        worktree.is_visible()
-                    });
-                    if !is_visible {
-                        *worktree_handle = WorktreeHandle::Weak(worktree.downgrade());
-                    }
-                }
+            for worktree in self.worktree_store.worktrees() {
+                worktree.update(cx, |worktree, _| {
+                    worktree.as_local_mut().unwrap().unshare();
+                });
             }
+            self.worktree_store.release_hidden(cx);
 
             for open_buffer in self.opened_buffers.values_mut() {
                 // Wake up any tasks waiting for peers' edits to this buffer.
@@ -1911,14 +2132,12 @@ impl Project {
 
             self.collaborators.clear();
 
-            for worktree in &self.worktrees {
-                if let Some(worktree) = worktree.upgrade() {
-                    worktree.update(cx, |worktree, _| {
-                        if let Some(worktree) = worktree.as_remote_mut() {
-                            worktree.disconnected_from_host();
-                        }
-                    });
-                }
+            for worktree in self.worktree_store.worktrees() {
+                worktree.update(cx, |worktree, _| {
+                    if let Some(worktree) = worktree.as_remote_mut() {
+                        worktree.disconnected_from_host();
+                    }
+                });
             }
 
             for open_buffer in self.opened_buffers.values_mut() {
@@ -2249,9 +2468,28 @@ impl Project {
         };
         let worktree = file.worktree.clone();
         let path = file.path.clone();
-        worktree.update(cx, |worktree, cx| match worktree {
-            Worktree::Local(worktree) => worktree.save_buffer(buffer, path, false, cx),
-            Worktree::Remote(worktree) => worktree.save_buffer(buffer, None, cx),
+        let environment = self.worktree_environment(file.worktree_id(cx), cx);
+        let save = worktree.update(cx, |worktree, cx| match worktree {
+            Worktree::Local(worktree) => {
+                let durability = worktree.save_durability(&path);
+                worktree.save_buffer(buffer.clone(), path, false, durability, cx)
+            }
+            Worktree::Remote(worktree) => worktree.save_buffer(buffer.clone(), None, cx),
+        });
+        cx.spawn(move |this, mut cx| async move {
+            save.await?;
+            let environment = environment.await;
+            // The buffer was saved, so failing hooks are reported without failing the save.
+            if let Err(error) =
+                save_hooks::run_post_save_hooks(&buffer, &environment, &mut cx).await
+            {
+                log::error!("{error:?}");
+                this.update(&mut cx, |_, cx| {
+                    cx.emit(Event::Notification(format!("{error:#}")))
+                })
+                .ok();
+            }
+            Ok(())
         })
     }
 
@@ -2277,7 +2515,8 @@ impl Project {
             worktree
                 .update(&mut cx, |worktree, cx| match worktree {
                     Worktree::Local(worktree) => {
-                        worktree.save_buffer(buffer.clone(), path.path, true, cx)
+                        let durability = worktree.save_durability(&path.path);
+                        worktree.save_buffer(buffer.clone(), path.path, true, durability, cx)
                     }
                     Worktree::Remote(worktree) => {
                         worktree.save_buffer(buffer.clone(), Some(path.to_proto()), cx)
@@ -2293,6 +2532,208 @@ impl Project {
         })
     }
 
+    fn record_local_history(
+        &self,
+        buffer: &Model<Buffer>,
+        abs_path: PathBuf,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(local_history) = self.local_history.clone() else {
+            return;
+        };
+        let settings = ProjectSettings::get_global(cx).local_history;
+        if !settings.enabled() {
+            return;
+        }
+        let content = buffer.read(cx).text();
+        cx.background_executor()
+            .spawn(async move {
+                local_history
+                    .record(&abs_path, content, &settings)
+                    .await
+                    .log_err();
+            })
+            .detach();
+    }
+
+    /// Returns the versions of a local file that were recorded whenever it was saved, most
+    /// recent first.
+    pub fn local_history_versions(
+        &self,
+        abs_path: PathBuf,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<LocalHistoryVersion>>> {
+        let Some(local_history) = self.local_history.clone() else {
+            return Task::ready(Err(anyhow!(
+                "local history is only kept for local projects"
+            )));
+        };
+        cx.background_executor()
+            .spawn(async move { local_history.versions(&abs_path).await })
+    }
+
+    pub fn load_local_history_version(
+        &self,
+        version: LocalHistoryVersion,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<String>> {
+        let Some(local_history) = self.local_history.clone() else {
+            return Task::ready(Err(anyhow!(
+                "local history is only kept for local projects"
+            )));
+        };
+        cx.background_executor()
+            .spawn(async move { local_history.load(&version).await })
+    }
+
+    /// Returns a unified diff from a recorded version of a buffer's file to the buffer's
+    /// current contents.
+    pub fn diff_local_history_version(
+        &self,
+        buffer: &Model<Buffer>,
+        version: LocalHistoryVersion,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<String>> {
+        let old_text = self.load_local_history_version(version, cx);
+        let new_text = buffer.read(cx).text();
+        cx.background_executor().spawn(async move {
+            let old_text = old_text.await?;
+            Ok(local_history::diff_versions(&old_text, &new_text))
+        })
+    }
+
+    /// Returns the total size of the files within a directory of a local worktree,
+    /// recursively. Sizes are cached until a change within the directory is observed.
+    pub fn directory_size(
+        &mut self,
+        project_path: ProjectPath,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<u64>> {
+        let Some(worktree_abs_path) = self.local_worktree_abs_path(project_path.worktree_id, cx)
+        else {
+            return Task::ready(Err(anyhow!("directory sizes are only available locally")));
+        };
+        self.watch_directory_sizes(project_path.worktree_id, worktree_abs_path.clone(), cx);
+        let size = self.directory_sizes.size(
+            project_path.clone(),
+            worktree_abs_path.join(&project_path.path),
+            self.fs.clone(),
+            cx.background_executor(),
+        );
+        cx.background_executor()
+            .spawn(async move { size.await.map_err(|error| anyhow!("{}", error)) })
+    }
+
+    /// Returns the size of a directory if it was already measured, without measuring it.
+    pub fn cached_directory_size(&self, project_path: &ProjectPath) -> Option<u64> {
+        self.directory_sizes.cached_size(project_path)
+    }
+
+    /// Returns the `limit` largest files and directories within a directory of a local
+    /// worktree, largest first. The sizes of the directories measured along the way are
+    /// cached.
+    pub fn largest_paths(
+        &mut self,
+        project_path: ProjectPath,
+        limit: usize,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<PathSize>>> {
+        let Some(worktree_abs_path) = self.local_worktree_abs_path(project_path.worktree_id, cx)
+        else {
+            return Task::ready(Err(anyhow!("directory sizes are only available locally")));
+        };
+        let fs = self.fs.clone();
+        let worktree_id = project_path.worktree_id;
+        self.watch_directory_sizes(worktree_id, worktree_abs_path.clone(), cx);
+        let generation = self.directory_sizes.generation();
+        let abs_path = worktree_abs_path.join(&project_path.path);
+        let measure = self.job_scheduler.update(cx, |job_scheduler, cx| {
+            job_scheduler.schedule(
+                "Measuring largest files",
+                JobPriority::Scan,
+                JobGroup::Worktree(worktree_id),
+                move |reporter| async move {
+                    reporter.report(abs_path.to_string_lossy().to_string(), None);
+                    directory_sizes::measure_largest_paths(fs.as_ref(), &abs_path, limit).await
+                },
+                cx,
+            )
+        });
+        cx.spawn(|this, mut cx| async move {
+            let (largest_paths, dir_sizes) = measure.await??;
+            let to_project_path = |abs_path: &Path| {
+                Some(ProjectPath {
+                    worktree_id,
+                    path: abs_path.strip_prefix(&worktree_abs_path).ok()?.into(),
+                })
+            };
+
+            this.update(&mut cx, |this, _| {
+                this.directory_sizes.insert(
+                    generation,
+                    dir_sizes
+                        .iter()
+                        .filter_map(|(abs_path, size)| Some((to_project_path(abs_path)?, *size))),
+                );
+            })?;
+            Ok(largest_paths
+                .into_iter()
+                .filter_map(|(abs_path, size, is_dir)| {
+                    Some(PathSize {
+                        path: to_project_path(&abs_path)?,
+                        size,
+                        is_dir,
+                    })
+                })
+                .collect())
+        })
+    }
+
+    /// Starts invalidating the directory sizes within a worktree when its files change,
+    /// including the files within ignored directories, which worktrees don't report.
+    fn watch_directory_sizes(
+        &mut self,
+        worktree_id: WorktreeId,
+        worktree_abs_path: Arc<Path>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if self.directory_sizes.is_watching(worktree_id) {
+            return;
+        }
+        let fs = self.fs.clone();
+        let watcher = cx.spawn(|this, mut cx| async move {
+            let mut events = fs.watch(&worktree_abs_path, FS_WATCH_LATENCY).await;
+            while let Some(changed_paths) = events.next().await {
+                let changed_paths = changed_paths
+                    .iter()
+                    .filter_map(|path| path.strip_prefix(&worktree_abs_path).ok());
+                if this
+                    .update(&mut cx, |this, _| {
+                        this.directory_sizes.invalidate(worktree_id, changed_paths)
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        self.directory_sizes.watch(worktree_id, watcher);
+    }
+
+    fn local_worktree_abs_path(
+        &self,
+        worktree_id: WorktreeId,
+        cx: &AppContext,
+    ) -> Option<Arc<Path>> {
+        Some(
+            self.worktree_for_id(worktree_id, cx)?
+                .read(cx)
+                .as_local()?
+                .abs_path()
+                .clone(),
+        )
+    }
+
     pub fn get_open_buffer(
         &mut self,
         path: &ProjectPath,
@@ -2757,6 +3198,10 @@ impl Project {
                 let file = File::from_dyn(buffer.read(cx).file())?;
                 let worktree_id = file.worktree_id(cx);
                 let abs_path = file.as_local()?.abs_path(cx);
+                // Private files, such as those holding secrets, aren't copied elsewhere.
+                if !file.is_private() {
+                    self.record_local_history(&buffer, abs_path.clone(), cx);
+                }
                 let text_document = lsp::TextDocumentIdentifier {
                     uri: lsp::Url::from_file_path(abs_path).unwrap(),
                 };
@@ -3008,6 +3453,14 @@ impl Project {
                             for buffer in buffers_with_unknown_injections {
                                 buffer.update(cx, |buffer, cx| buffer.reparse(cx));
                             }
+
+                            // Files are only indexed once a language for them is known.
+                            if project.syntax_index_enabled {
+                                let worktrees = project.visible_worktrees(cx).collect::<Vec<_>>();
+                                for worktree in worktrees {
+                                    project.index_local_worktree_syntax(&worktree, cx);
+                                }
+                            }
                         })
                         .ok();
                 }
@@ -3167,6 +3620,14 @@ impl Project {
                 continue;
             }
 
+            if let Some(adapter) = SettingsLspAdapter::from_settings(&desired_language_server, cx) {
+                let adapter = CachedLspAdapter::new(Arc::new(adapter), true);
+                self.languages()
+                    .register_lsp_adapter(language.name(), adapter.adapter.clone());
+                enabled_lsp_adapters.push(adapter);
+                continue;
+            }
+
             log::warn!(
                 "no language server found matching '{}'",
                 desired_language_server.0
@@ -3319,11 +3780,9 @@ impl Project {
             _ => None,
         };
 
-        for worktree in &self.worktrees {
-            if let Some(worktree) = worktree.upgrade() {
-                let key = (worktree.read(cx).id(), adapter.name.clone());
-                self.language_server_ids.remove(&key);
-            }
+        for worktree in self.worktree_store.worktrees() {
+            let key = (worktree.read(cx).id(), adapter.name.clone());
+            self.language_server_ids.remove(&key);
         }
 
         Some(cx.spawn(move |this, mut cx| async move {
@@ -3346,16 +3805,9 @@ impl Project {
             task.await;
 
             this.update(&mut cx, |this, cx| {
-                let worktrees = this.worktrees.clone();
+                let worktrees = this.worktrees().collect::<Vec<_>>();
                 for worktree in worktrees {
-                    if let Some(worktree) = worktree.upgrade() {
-                        this.start_language_server(
-                            &worktree,
-                            adapter.clone(),
-                            language.clone(),
-                            cx,
-                        );
-                    }
+                    this.start_language_server(&worktree, adapter.clone(), language.clone(), cx);
                 }
             })
             .ok();
@@ -3858,14 +4310,12 @@ impl Project {
                     });
                 }
             }
-            for worktree in &self.worktrees {
-                if let Some(worktree) = worktree.upgrade() {
-                    worktree.update(cx, |worktree, cx| {
-                        if let Some(worktree) = worktree.as_local_mut() {
-                            worktree.clear_diagnostics_for_language_server(server_id, cx);
-                        }
-                    });
-                }
+            for worktree in self.worktree_store.worktrees() {
+                worktree.update(cx, |worktree, cx| {
+                    if let Some(worktree) = worktree.as_local_mut() {
+                        worktree.clear_diagnostics_for_language_server(server_id, cx);
+                    }
+                });
             }
 
             self.language_server_watched_paths.remove(&server_id);
@@ -4328,47 +4778,45 @@ impl Project {
 
         let mut builders = HashMap::default();
         for watcher in watchers.values().flatten() {
-            for worktree in &self.worktrees {
-                if let Some(worktree) = worktree.upgrade() {
-                    let glob_is_inside_worktree = worktree.update(cx, |tree, _| {
-                        if let Some(abs_path) = tree.abs_path().to_str() {
-                            let relative_glob_pattern = match &watcher.glob_pattern {
-                                lsp::GlobPattern::String(s) => Some(
-                                    s.strip_prefix(abs_path)
-                                        .unwrap_or(s)
-                                        .strip_prefix(std::path::MAIN_SEPARATOR)
-                                        .unwrap_or(s),
-                                ),
-                                lsp::GlobPattern::Relative(rp) => {
-                                    let base_uri = match &rp.base_uri {
-                                        lsp::OneOf::Left(workspace_folder) => &workspace_folder.uri,
-                                        lsp::OneOf::Right(base_uri) => base_uri,
-                                    };
-                                    base_uri.to_file_path().ok().and_then(|file_path| {
-                                        (file_path.to_str() == Some(abs_path))
-                                            .then_some(rp.pattern.as_str())
-                                    })
-                                }
-                            };
-                            if let Some(relative_glob_pattern) = relative_glob_pattern {
-                                let literal_prefix = glob_literal_prefix(relative_glob_pattern);
-                                tree.as_local_mut()
-                                    .unwrap()
-                                    .add_path_prefix_to_scan(Path::new(literal_prefix).into());
-                                if let Some(glob) = Glob::new(relative_glob_pattern).log_err() {
-                                    builders
-                                        .entry(tree.id())
-                                        .or_insert_with(|| GlobSetBuilder::new())
-                                        .add(glob);
-                                }
-                                return true;
+            for worktree in self.worktree_store.worktrees() {
+                let glob_is_inside_worktree = worktree.update(cx, |tree, _| {
+                    if let Some(abs_path) = tree.abs_path().to_str() {
+                        let relative_glob_pattern = match &watcher.glob_pattern {
+                            lsp::GlobPattern::String(s) => Some(
+                                s.strip_prefix(abs_path)
+                                    .unwrap_or(s)
+                                    .strip_prefix(std::path::MAIN_SEPARATOR)
+                                    .unwrap_or(s),
+                            ),
+                            lsp::GlobPattern::Relative(rp) => {
+                                let base_uri = match &rp.base_uri {
+                                    lsp::OneOf::Left(workspace_folder) => &workspace_folder.uri,
+                                    lsp::OneOf::Right(base_uri) => base_uri,
+                                };
+                                base_uri.to_file_path().ok().and_then(|file_path| {
+                                    (file_path.to_str() == Some(abs_path))
+                                        .then_some(rp.pattern.as_str())
+                                })
                             }
-                        }
-                        false
-                    });
-                    if glob_is_inside_worktree {
-                        break;
+                        };
+                        if let Some(relative_glob_pattern) = relative_glob_pattern {
+                            let literal_prefix = glob_literal_prefix(relative_glob_pattern);
+                            tree.as_local_mut()
+                                .unwrap()
+                                .add_path_prefix_to_scan(Path::new(literal_prefix).into());
+                            if let Some(glob) = Glob::new(relative_glob_pattern).log_err() {
+                                builders
+                                    .entry(tree.id())
+                                    .or_insert_with(|| GlobSetBuilder::new())
+                                    .add(glob);
+                            }
+                            return true;
+                        }
                     }
+                    false
+                });
+                if glob_is_inside_worktree {
+                    break;
                 }
             }
         }
@@ -4846,207 +5294,282 @@ impl Project {
             let settings = buffer.update(&mut cx, |buffer, cx| {
                 language_settings(buffer.language(), buffer.file(), cx).clone()
             })?;
-
-            let remove_trailing_whitespace = settings.remove_trailing_whitespace_on_save;
-            let ensure_final_newline = settings.ensure_final_newline_on_save;
-            let tab_size = settings.tab_size;
-
-            // First, format buffer's whitespace according to the settings.
-            let trailing_whitespace_diff = if remove_trailing_whitespace {
-                Some(
-                    buffer
-                        .update(&mut cx, |b, cx| b.remove_trailing_whitespace(cx))?
-                        .await,
-                )
-            } else {
-                None
-            };
-            let whitespace_transaction_id = buffer.update(&mut cx, |buffer, cx| {
-                buffer.finalize_last_transaction();
-                buffer.start_transaction();
-                if let Some(diff) = trailing_whitespace_diff {
-                    buffer.apply_diff(diff, cx);
-                }
-                if ensure_final_newline {
-                    buffer.ensure_final_newline(cx);
-                }
-                buffer.end_transaction(cx)
-            })?;
-
-            // Apply the `code_actions_on_format` before we run the formatter.
-            let code_actions = deserialize_code_actions(&settings.code_actions_on_format);
-            #[allow(clippy::nonminimal_bool)]
-            if !code_actions.is_empty()
-                && !(trigger == FormatTrigger::Save && settings.format_on_save == FormatOnSave::Off)
-            {
-                Self::execute_code_actions_on_servers(
-                    &project,
-                    &adapters_and_servers,
-                    code_actions,
-                    buffer,
-                    push_to_history,
-                    &mut project_transaction,
-                    &mut cx,
-                )
-                .await?;
-            }
-
-            // Apply language-specific formatting using either the primary language server
-            // or external command.
-            // Except for code actions, which are applied with all connected language servers.
             let primary_language_server =
                 primary_adapter_and_server.map(|(_adapter, server)| server.clone());
-            let server_and_buffer = primary_language_server
-                .as_ref()
-                .zip(buffer_abs_path.as_ref());
 
-            let mut format_operation = None;
-            let prettier_settings = buffer.read_with(&mut cx, |buffer, cx| {
-                language_settings(buffer.language(), buffer.file(), cx)
-                    .prettier
-                    .clone()
-            })?;
-            match (&settings.formatter, &settings.format_on_save) {
-                (_, FormatOnSave::Off) if trigger == FormatTrigger::Save => {}
-
-                (Formatter::CodeActions(code_actions), FormatOnSave::On | FormatOnSave::Off)
-                | (_, FormatOnSave::CodeActions(code_actions)) => {
-                    let code_actions = deserialize_code_actions(code_actions);
-                    if !code_actions.is_empty() {
-                        Self::execute_code_actions_on_servers(
-                            &project,
-                            &adapters_and_servers,
-                            code_actions,
+            // Run the hooks in order, grouping their edits into a single transaction. When
+            // formatting manually, only the hooks that edit the buffer are run.
+            let mut hooks_transaction_id = None;
+            for hook in settings.pre_save_hooks() {
+                if trigger == FormatTrigger::Manual && !hook.kind.edits_buffer() {
+                    continue;
+                }
+
+                let executor = cx.background_executor().clone();
+                let run_hook = async {
+                    match &hook.kind {
+                        SaveHookKind::RemoveTrailingWhitespace => {
+                            let diff = buffer
+                                .update(&mut cx, |buffer, cx| {
+                                    buffer.remove_trailing_whitespace(cx)
+                                })?
+                                .await;
+                            save_hooks::apply_edits(
+                                buffer,
+                                &mut hooks_transaction_id,
+                                &mut cx,
+                                |buffer, cx| {
+                                    buffer.apply_diff(diff, cx);
+                                },
+                            )
+                        }
+                        SaveHookKind::EnsureFinalNewline => save_hooks::apply_edits(
                             buffer,
-                            push_to_history,
-                            &mut project_transaction,
+                            &mut hooks_transaction_id,
                             &mut cx,
-                        )
-                        .await?;
-                    }
-                }
-                (Formatter::LanguageServer, FormatOnSave::On | FormatOnSave::Off)
-                | (_, FormatOnSave::LanguageServer) => {
-                    if let Some((language_server, buffer_abs_path)) = server_and_buffer {
-                        format_operation = Some(FormatOperation::Lsp(
-                            Self::format_via_lsp(
+                            |buffer, cx| buffer.ensure_final_newline(cx),
+                        ),
+                        SaveHookKind::Format => {
+                            Self::format_with_formatter(
                                 &project,
+                                buffer,
+                                buffer_abs_path.as_deref(),
+                                &settings,
+                                trigger,
+                                primary_language_server.as_ref(),
+                                &adapters_and_servers,
+                                push_to_history,
+                                &mut hooks_transaction_id,
+                                &mut project_transaction,
+                                &mut cx,
+                            )
+                            .await
+                        }
+                        SaveHookKind::Command { command, arguments } => {
+                            let Some(buffer_abs_path) = buffer_abs_path else {
+                                return Ok(());
+                            };
+                            let environment = project
+                                .update(&mut cx, |project, cx| {
+                                    project.buffer_environment(buffer, cx)
+                                })?
+                                .await;
+                            save_hooks::run_command(
                                 buffer,
                                 buffer_abs_path,
-                                language_server,
-                                tab_size,
+                                command,
+                                arguments,
+                                &environment,
                                 &mut cx,
                             )
                             .await
-                            .context("failed to format via language server")?,
-                        ));
+                        }
+                        SaveHookKind::RefreshGitStatus => {
+                            save_hooks::refresh_git_status(buffer, &mut cx).await
+                        }
+                    }
+                };
+                let result = save_hooks::run_with_timeout(&hook, &executor, run_hook).await;
+                save_hooks::check_result(&hook, result)?;
+            }
+
+            buffer.update(&mut cx, |b, _| {
+                if let Some(transaction) = b.finalize_last_transaction().cloned() {
+                    if !push_to_history {
+                        b.forget_transaction(transaction.id);
                     }
+                    project_transaction.0.insert(buffer.clone(), transaction);
                 }
+            })?;
+        }
 
-                (
-                    Formatter::External { command, arguments },
-                    FormatOnSave::On | FormatOnSave::Off,
-                )
-                | (_, FormatOnSave::External { command, arguments }) => {
-                    if let Some(buffer_abs_path) = buffer_abs_path {
-                        format_operation = Self::format_via_external_command(
+        Ok(project_transaction)
+    }
+
+    /// Formats a buffer with the configured formatter, after running the code actions that
+    /// are configured to run on format. The formatter's edits are grouped with the ones of
+    /// the hooks that ran before it, unless the buffer was edited since then.
+    #[allow(clippy::too_many_arguments)]
+    async fn format_with_formatter(
+        project: &WeakModel<Project>,
+        buffer: &Model<Buffer>,
+        buffer_abs_path: Option<&Path>,
+        settings: &LanguageSettings,
+        trigger: FormatTrigger,
+        primary_language_server: Option<&Arc<LanguageServer>>,
+        adapters_and_servers: &Vec<(Arc<CachedLspAdapter>, Arc<LanguageServer>)>,
+        push_to_history: bool,
+        hooks_transaction_id: &mut Option<TransactionId>,
+        project_transaction: &mut ProjectTransaction,
+        cx: &mut AsyncAppContext,
+    ) -> Result<()> {
+        let tab_size = settings.tab_size;
+        // Apply the `code_actions_on_format` before we run the formatter.
+        let code_actions = deserialize_code_actions(&settings.code_actions_on_format);
+        #[allow(clippy::nonminimal_bool)]
+        if !code_actions.is_empty()
+            && !(trigger == FormatTrigger::Save && settings.format_on_save == FormatOnSave::Off)
+        {
+            Self::execute_code_actions_on_servers(
+                project,
+                adapters_and_servers,
+                code_actions,
+                buffer,
+                push_to_history,
+                project_transaction,
+                cx,
+            )
+            .await?;
+        }
+
+        // Apply language-specific formatting using either the primary language server
+        // or external command.
+        // Except for code actions, which are applied with all connected language servers.
+        let server_and_buffer = primary_language_server.zip(buffer_abs_path);
+
+        let mut format_operation = None;
+        let prettier_settings = buffer.read_with(cx, |buffer, cx| {
+            language_settings(buffer.language(), buffer.file(), cx)
+                .prettier
+                .clone()
+        })?;
+        match (&settings.formatter, &settings.format_on_save) {
+            (_, FormatOnSave::Off) if trigger == FormatTrigger::Save => {}
+
+            (Formatter::CodeActions(code_actions), FormatOnSave::On | FormatOnSave::Off)
+            | (_, FormatOnSave::CodeActions(code_actions)) => {
+                let code_actions = deserialize_code_actions(code_actions);
+                if !code_actions.is_empty() {
+                    Self::execute_code_actions_on_servers(
+                        project,
+                        adapters_and_servers,
+                        code_actions,
+                        buffer,
+                        push_to_history,
+                        project_transaction,
+                        cx,
+                    )
+                    .await?;
+                }
+            }
+            (Formatter::LanguageServer, FormatOnSave::On | FormatOnSave::Off)
+            | (_, FormatOnSave::LanguageServer) => {
+                if let Some((language_server, buffer_abs_path)) = server_and_buffer {
+                    format_operation = Some(FormatOperation::Lsp(
+                        Self::format_via_lsp(
+                            project,
                             buffer,
                             buffer_abs_path,
-                            command,
-                            arguments,
-                            &mut cx,
+                            language_server,
+                            tab_size,
+                            cx,
                         )
                         .await
-                        .context(format!(
-                            "failed to format via external command {:?}",
-                            command
-                        ))?
-                        .map(FormatOperation::External);
-                    }
+                        .context("failed to format via language server")?,
+                    ));
                 }
-                (Formatter::Auto, FormatOnSave::On | FormatOnSave::Off) => {
-                    let prettier = if prettier_settings.allowed {
-                        prettier_support::format_with_prettier(&project, buffer, &mut cx)
-                            .await
-                            .transpose()
-                            .ok()
-                            .flatten()
-                    } else {
-                        None
-                    };
+            }
 
-                    if let Some(operation) = prettier {
-                        format_operation = Some(operation);
-                    } else if let Some((language_server, buffer_abs_path)) = server_and_buffer {
-                        format_operation = Some(FormatOperation::Lsp(
-                            Self::format_via_lsp(
-                                &project,
-                                buffer,
-                                buffer_abs_path,
-                                language_server,
-                                tab_size,
-                                &mut cx,
-                            )
-                            .await
-                            .context("failed to format via language server")?,
-                        ));
-                    }
-                }
-                (Formatter::Prettier, FormatOnSave::On | FormatOnSave::Off) => {
-                    if prettier_settings.allowed {
-                        if let Some(operation) =
-                            prettier_support::format_with_prettier(&project, buffer, &mut cx).await
-                        {
-                            format_operation = Some(operation?);
-                        }
-                    }
+            (Formatter::External { command, arguments }, FormatOnSave::On | FormatOnSave::Off)
+            | (_, FormatOnSave::External { command, arguments }) => {
+                if let Some(buffer_abs_path) = buffer_abs_path {
+                    let environment = project
+                        .update(cx, |project, cx| project.buffer_environment(buffer, cx))?
+                        .await;
+                    format_operation = Self::format_via_external_command(
+                        buffer,
+                        buffer_abs_path,
+                        command,
+                        arguments,
+                        &environment,
+                        cx,
+                    )
+                    .await
+                    .context(format!(
+                        "failed to format via external command {:?}",
+                        command
+                    ))?
+                    .map(FormatOperation::External);
                 }
-            };
+            }
+            (Formatter::Auto, FormatOnSave::On | FormatOnSave::Off) => {
+                let prettier = if prettier_settings.allowed {
+                    prettier_support::format_with_prettier(project, buffer, cx)
+                        .await
+                        .transpose()
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                };
 
-            buffer.update(&mut cx, |b, cx| {
-                // If the buffer had its whitespace formatted and was edited while the language-specific
-                // formatting was being computed, avoid applying the language-specific formatting, because
-                // it can't be grouped with the whitespace formatting in the undo history.
-                if let Some(transaction_id) = whitespace_transaction_id {
-                    if b.peek_undo_stack()
-                        .map_or(true, |e| e.transaction_id() != transaction_id)
+                if let Some(operation) = prettier {
+                    format_operation = Some(operation);
+                } else if let Some((language_server, buffer_abs_path)) = server_and_buffer {
+                    format_operation = Some(FormatOperation::Lsp(
+                        Self::format_via_lsp(
+                            project,
+                            buffer,
+                            buffer_abs_path,
+                            language_server,
+                            tab_size,
+                            cx,
+                        )
+                        .await
+                        .context("failed to format via language server")?,
+                    ));
+                }
+            }
+            (Formatter::Prettier, FormatOnSave::On | FormatOnSave::Off) => {
+                if prettier_settings.allowed {
+                    if let Some(operation) =
+                        prettier_support::format_with_prettier(project, buffer, cx).await
                     {
-                        format_operation.take();
+                        format_operation = Some(operation?);
                     }
                 }
+            }
+        };
 
-                // Apply any language-specific formatting, and group the two formatting operations
-                // in the buffer's undo history.
-                if let Some(operation) = format_operation {
-                    match operation {
-                        FormatOperation::Lsp(edits) => {
-                            b.edit(edits, None, cx);
-                        }
-                        FormatOperation::External(diff) => {
-                            b.apply_diff(diff, cx);
-                        }
-                        FormatOperation::Prettier(diff) => {
-                            b.apply_diff(diff, cx);
-                        }
-                    }
+        buffer.update(cx, |b, cx| {
+            // If earlier hooks edited the buffer and it was edited while the language-specific
+            // formatting was being computed, avoid applying the language-specific formatting, because
+            // it can't be grouped with the earlier hooks' edits in the undo history.
+            if let Some(transaction_id) = *hooks_transaction_id {
+                if b.peek_undo_stack()
+                    .map_or(true, |e| e.transaction_id() != transaction_id)
+                {
+                    format_operation.take();
+                }
+            }
 
-                    if let Some(transaction_id) = whitespace_transaction_id {
-                        b.group_until_transaction(transaction_id);
-                    } else if let Some(transaction) = project_transaction.0.get(buffer) {
-                        b.group_until_transaction(transaction.id)
+            // Apply any language-specific formatting, and group it with the earlier hooks' edits
+            // in the buffer's undo history.
+            if let Some(operation) = format_operation {
+                match operation {
+                    FormatOperation::Lsp(edits) => {
+                        b.edit(edits, None, cx);
+                    }
+                    FormatOperation::External(diff) => {
+                        b.apply_diff(diff, cx);
+                    }
+                    FormatOperation::Prettier(diff) => {
+                        b.apply_diff(diff, cx);
                     }
                 }
 
-                if let Some(transaction) = b.finalize_last_transaction().cloned() {
-                    if !push_to_history {
-                        b.forget_transaction(transaction.id);
+                if let Some(transaction_id) = *hooks_transaction_id {
+                    b.group_until_transaction(transaction_id);
+                } else {
+                    if let Some(transaction) = project_transaction.0.get(buffer) {
+                        b.group_until_transaction(transaction.id)
                     }
-                    project_transaction.0.insert(buffer.clone(), transaction);
+                    *hooks_transaction_id = b.peek_undo_stack().map(|e| e.transaction_id());
                 }
-            })?;
-        }
+            }
+        })?;
 
-        Ok(project_transaction)
+        Ok(())
     }
 
     async fn format_via_lsp(
@@ -5104,17 +5627,11 @@ impl Project {
         buffer_abs_path: &Path,
         command: &str,
         arguments: &[String],
+        environment: &HashMap<String, String>,
         cx: &mut AsyncAppContext,
     ) -> Result<Option<Diff>> {
-        let working_dir_path = buffer.update(cx, |buffer, cx| {
-            let file = File::from_dyn(buffer.file())?;
-            let worktree = file.worktree.read(cx).as_local()?;
-            let mut worktree_path = worktree.abs_path().to_path_buf();
-            if worktree.root_entry()?.is_file() {
-                worktree_path.pop();
-            }
-            Some(worktree_path)
-        })?;
+        let working_dir_path =
+            buffer.read_with(cx, |buffer, cx| save_hooks::command_working_dir(buffer, cx))?;
 
         if let Some(working_dir_path) = working_dir_path {
             let mut child =
@@ -5123,6 +5640,7 @@ impl Project {
                         arg.replace("{buffer_path}", &buffer_abs_path.to_string_lossy())
                     }))
                     .current_dir(&working_dir_path)
+                    .envs(environment)
                     .stdin(smol::process::Stdio::piped())
                     .stdout(smol::process::Stdio::piped())
                     .stderr(smol::process::Stdio::piped())
@@ -5277,6 +5795,24 @@ impl Project {
         self.document_highlights_impl(buffer, position, cx)
     }
 
+    /// Whether the buffer's primary language server can find the occurrences of a symbol, or
+    /// `None` if the buffer has no language server. Guests assume that the host's server can.
+    pub fn supports_document_highlights(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Option<bool> {
+        if !self.is_local() {
+            return Some(true);
+        }
+        let (_, server) = self.primary_language_server_for_buffer(buffer.read(cx), cx)?;
+        Some(match server.capabilities().document_highlight_provider {
+            Some(lsp::OneOf::Left(supported)) => supported,
+            Some(lsp::OneOf::Right(_)) => true,
+            None => false,
+        })
+    }
+
     pub fn symbols(&self, query: &str, cx: &mut ModelContext<Self>) -> Task<Result<Vec<Symbol>>> {
         let language_registry = self.languages.clone();
 
@@ -5347,6 +5883,7 @@ impl Project {
                 );
             }
 
+            let indexed_symbols = self.indexed_symbols(query, cx);
             cx.spawn(move |this, mut cx| async move {
                 let responses = futures::future::join_all(requests).await;
                 let this = match this.upgrade() {
@@ -5407,6 +5944,25 @@ impl Project {
                     .await;
                 }
 
+                // Language servers know the symbols of the files they've analyzed better
+                // than their outlines do.
+                let indexed_symbols = indexed_symbols
+                    .into_iter()
+                    .filter(|indexed_symbol| {
+                        !symbols.iter().any(|symbol| {
+                            symbol.path == indexed_symbol.path && symbol.name == indexed_symbol.name
+                        })
+                    })
+                    .collect();
+                populate_labels_for_symbols(
+                    indexed_symbols,
+                    &language_registry,
+                    None,
+                    None,
+                    &mut symbols,
+                )
+                .await;
+
                 Ok(symbols)
             })
         } else if let Some(project_id) = self.remote_id() {
@@ -5437,12 +5993,37 @@ impl Project {
         }
     }
 
+    /// The symbols found by parsing local files in the background whose names match the
+    /// query, if the syntax index is enabled.
+    fn indexed_symbols(&self, query: &str, cx: &AppContext) -> Vec<CoreSymbol> {
+        self.syntax_index
+            .search(query)
+            .filter(|(path, _)| {
+                self.worktree_for_id(path.worktree_id, cx)
+                    .map_or(false, |worktree| worktree.read(cx).is_visible())
+            })
+            .map(|(path, symbol)| CoreSymbol {
+                language_server_name: LanguageServerName(syntax_index::SERVER_NAME.into()),
+                source_worktree_id: path.worktree_id,
+                signature: self.symbol_signature(&path),
+                path,
+                kind: symbol.kind,
+                name: symbol.name.clone(),
+                range: Unclipped(symbol.range.start)..Unclipped(symbol.range.end),
+            })
+            .collect()
+    }
+
     pub fn open_buffer_for_symbol(
         &mut self,
         symbol: &Symbol,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Model<Buffer>>> {
         if self.is_local() {
+            if symbol.language_server_name.0.as_ref() == syntax_index::SERVER_NAME {
+                return self.open_buffer(symbol.path.clone(), cx);
+            }
+
             let language_server_id = if let Some(id) = self.language_server_ids.get(&(
                 symbol.source_worktree_id,
                 symbol.language_server_name.clone(),
@@ -5587,7 +6168,313 @@ impl Project {
         cx: &mut ModelContext<Self>,
     ) -> Task<Vec<Hover>> {
         let position = position.to_point_utf16(buffer.read(cx));
-        self.hover_impl(buffer, position, cx)
+        let hovers = self.hover_impl(buffer, position, cx);
+        self.hover_with_symbol_docs(buffer, position, hovers, cx)
+    }
+
+    /// Remembers the documentation of the symbol at the given position by where it's defined
+    /// when language servers describe it, and falls back to the remembered documentation, or
+    /// the docsets of the language, when they don't.
+    fn hover_with_symbol_docs(
+        &self,
+        buffer_handle: &Model<Buffer>,
+        position: PointUtf16,
+        hovers: Task<Vec<Hover>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Vec<Hover>> {
+        let buffer = buffer_handle.read(cx);
+        let snapshot = buffer.snapshot();
+        let Some(language) = snapshot.language_at(position).cloned() else {
+            return hovers;
+        };
+        let (word_range, kind) = snapshot.surrounding_word(position.to_offset(&snapshot));
+        if word_range.is_empty() || kind != Some(CharKind::Word) {
+            return hovers;
+        }
+        let name = snapshot
+            .text_for_range(word_range.clone())
+            .collect::<String>();
+        let range = snapshot.anchor_before(word_range.start)..snapshot.anchor_after(word_range.end);
+        let docsets = language_settings(Some(&language), buffer.file(), cx)
+            .docsets
+            .clone();
+        let symbol_docs = self.symbol_docs.clone();
+        let fs = self.fs.clone();
+        let buffer = buffer_handle.clone();
+        cx.spawn(|this, mut cx| async move {
+            let hovers = hovers.await;
+            let location = Self::definition_location(this, &buffer, position, &mut cx).await;
+            if !hovers.is_empty() {
+                if let Some(location) = location {
+                    let blocks = hovers
+                        .iter()
+                        .flat_map(|hover| hover.contents.iter().cloned())
+                        .collect();
+                    symbol_docs.cache_hover(location, blocks);
+                }
+                return hovers;
+            }
+
+            let mut contents = location.and_then(|location| symbol_docs.cached_hover(&location));
+            if contents.is_none() {
+                contents = symbol_docs
+                    .lookup_docsets(&name, &docsets, fs.as_ref(), cx.background_executor())
+                    .await;
+            }
+            contents
+                .map(|contents| Hover {
+                    contents,
+                    range: Some(range),
+                    language: Some(language),
+                })
+                .into_iter()
+                .collect()
+        })
+    }
+
+    /// Returns where the symbol at the given position is defined, according to language servers.
+    async fn definition_location(
+        this: WeakModel<Self>,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        cx: &mut AsyncAppContext,
+    ) -> Option<symbol_docs::SymbolLocation> {
+        let definitions = this
+            .update(cx, |this, cx| this.definition(buffer, position, cx))
+            .ok()?
+            .await
+            .ok()?;
+        let target = definitions.into_iter().next()?.target;
+        target
+            .buffer
+            .read_with(cx, |buffer, cx| {
+                let path = buffer.file()?.full_path(cx);
+                Some((path, target.range.start.to_point_utf16(buffer)))
+            })
+            .ok()
+            .flatten()
+    }
+
+    /// Returns the signatures of the call at the given position, as reported by the buffer's
+    /// primary language server.
+    pub fn signature_help<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<SignatureHelp>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetSignatureHelp { position },
+            cx,
+        )
+    }
+
+    /// Returns the characters that request signature help when typed in the buffer, as
+    /// advertised by its primary language server. Guests don't know the capabilities of the
+    /// host's servers, so they use the characters that start and continue calls in most
+    /// languages.
+    pub fn signature_help_options(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Option<lsp::SignatureHelpOptions> {
+        if self.is_local() {
+            let (_, server) = self.primary_language_server_for_buffer(buffer.read(cx), cx)?;
+            server.capabilities().signature_help_provider.clone()
+        } else {
+            Some(lsp::SignatureHelpOptions {
+                trigger_characters: Some(vec!["(".to_string()]),
+                retrigger_characters: Some(vec![",".to_string()]),
+                work_done_progress_options: Default::default(),
+            })
+        }
+    }
+
+    /// Returns the code lenses of the buffer, with their commands resolved where possible.
+    pub fn code_lens(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CodeLens>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetCodeLens,
+            cx,
+        )
+    }
+
+    /// Returns the colors written in the buffer, as reported by its primary language server or,
+    /// when the server reports none, as found by matching CSS color syntax in its text.
+    pub fn document_colors(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<DocumentColor>>> {
+        let request = self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetDocumentColor,
+            cx,
+        );
+        let buffer = buffer.clone();
+        cx.spawn(|_, mut cx| async move {
+            let colors = request.await?;
+            if !colors.is_empty() {
+                return Ok(colors);
+            }
+            let snapshot = buffer.update(&mut cx, |buffer, _| buffer.snapshot())?;
+            Ok(cx
+                .background_executor()
+                .spawn(async move { document_colors::find_colors(&snapshot) })
+                .await)
+        })
+    }
+
+    /// Returns the URLs and the references to files in the project, such as `src/main.rs:10:5`,
+    /// written in the given range of the buffer. File references are resolved relative to the
+    /// buffer's directory and then to the roots of the visible worktrees, and only those to
+    /// existing files are returned.
+    pub fn detect_links(
+        &self,
+        buffer: &Model<Buffer>,
+        range: Range<usize>,
+        cx: &AppContext,
+    ) -> Vec<DetectedLink> {
+        let buffer = buffer.read(cx);
+        let text = buffer.text_for_range(range.clone()).collect::<String>();
+        let buffer_dir = File::from_dyn(buffer.file()).and_then(|file| {
+            Some(ProjectPath {
+                worktree_id: file.worktree_id(cx),
+                path: file.path.parent()?.into(),
+            })
+        });
+        detected_links::find_links(&text)
+            .into_iter()
+            .filter_map(|(link_range, link)| {
+                let target = match link {
+                    detected_links::LinkText::Url(url) => LinkTarget::Url(url.to_string()),
+                    detected_links::LinkText::Path { path, row, column } => {
+                        let project_path =
+                            self.resolve_link_path(Path::new(path), buffer_dir.as_ref(), cx)?;
+                        let position = detected_links::position_in_file(row, column);
+                        LinkTarget::File(project_path, position)
+                    }
+                };
+                let start = buffer.anchor_after(range.start + link_range.start);
+                let end = buffer.anchor_before(range.start + link_range.end);
+                Some(DetectedLink {
+                    range: start..end,
+                    target,
+                })
+            })
+            .collect()
+    }
+
+    /// Finds the existing file that a path written in a buffer in the given directory refers to.
+    fn resolve_link_path(
+        &self,
+        path: &Path,
+        buffer_dir: Option<&ProjectPath>,
+        cx: &AppContext,
+    ) -> Option<ProjectPath> {
+        let is_file = |project_path: &ProjectPath| {
+            self.entry_for_path(project_path, cx)
+                .map_or(false, |entry| entry.is_file())
+        };
+        if path.is_absolute() {
+            return self
+                .project_path_for_absolute_path(path, cx)
+                .filter(is_file);
+        }
+        let in_buffer_dir = buffer_dir.map(|dir| ProjectPath {
+            worktree_id: dir.worktree_id,
+            path: normalize_path(&dir.path.join(path)).into(),
+        });
+        let in_worktree_roots = self.visible_worktrees(cx).map(|worktree| ProjectPath {
+            worktree_id: worktree.read(cx).id(),
+            path: normalize_path(path).into(),
+        });
+        in_buffer_dir
+            .into_iter()
+            .chain(in_worktree_roots)
+            .find(is_file)
+    }
+
+    /// Asks the language server that produced the code lens to execute its command. The
+    /// command runs like that of a code action, so the edits the server applies while it runs
+    /// are returned.
+    pub fn execute_code_lens(
+        &self,
+        buffer: Model<Buffer>,
+        lens: CodeLens,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<ProjectTransaction>> {
+        let Some(command) = lens.lsp_lens.command else {
+            return Task::ready(Ok(ProjectTransaction::default()));
+        };
+        let action = CodeAction {
+            server_id: lens.server_id,
+            range: lens.range,
+            lsp_action: lsp::CodeAction {
+                title: command.title.clone(),
+                command: Some(command),
+                ..Default::default()
+            },
+        };
+        self.apply_code_action(buffer, action, true, cx)
+    }
+
+    /// Returns the items of the call hierarchy at the given position, usually the function
+    /// or method whose name is there.
+    pub fn prepare_call_hierarchy<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareCallHierarchy { position },
+            cx,
+        )
+    }
+
+    /// Returns the items that call the given one.
+    pub fn incoming_calls(
+        &self,
+        item: &CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetIncomingCalls {
+                item: item.lsp_item.clone(),
+            },
+            cx,
+        )
+    }
+
+    /// Returns the items that the given one calls.
+    pub fn outgoing_calls(
+        &self,
+        item: &CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetOutgoingCalls {
+                item: item.lsp_item.clone(),
+            },
+            cx,
+        )
     }
 
     #[inline(never)]
@@ -5605,7 +6492,7 @@ impl Project {
             let scope = snapshot.language_scope_at(offset);
             let language = snapshot.language().cloned();
 
-            let server_ids: Vec<_> = self
+            let mut servers: Vec<_> = self
                 .language_servers_for_buffer(buffer.read(cx), cx)
                 .filter(|(_, server)| server.capabilities().completion_provider.is_some())
                 .filter(|(adapter, _)| {
@@ -5614,8 +6501,29 @@ impl Project {
                         .map(|scope| scope.language_allowed(&adapter.name))
                         .unwrap_or(true)
                 })
-                .map(|(_, server)| server.server_id())
+                .map(|(adapter, server)| (adapter.name.clone(), server.server_id()))
                 .collect();
+            // Query the servers in the order of the `language_servers` setting, so that
+            // completions offered by several servers are attributed to the preferred one.
+            if let Some(language) = language.as_ref() {
+                let file = buffer.read(cx).file();
+                let server_names = servers
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .collect::<Vec<_>>();
+                let preferred_servers = language_settings(Some(language), file, cx)
+                    .customized_language_servers(&server_names);
+                servers.sort_by_key(|(name, _)| {
+                    preferred_servers
+                        .iter()
+                        .position(|preferred| preferred == name)
+                        .unwrap_or(usize::MAX)
+                });
+            }
+            let server_ids = servers
+                .into_iter()
+                .map(|(_, server_id)| server_id)
+                .collect::<Vec<_>>();
 
             let buffer = buffer.clone();
             cx.spawn(move |this, mut cx| async move {
@@ -5648,6 +6556,7 @@ impl Project {
                         .await;
                     }
                 }
+                dedup_completions(&mut completions);
 
                 Ok(completions)
             })
@@ -5710,6 +6619,13 @@ impl Project {
 
         let buffer_id = buffer.read(cx).remote_id();
         let buffer_snapshot = buffer.read(cx).snapshot();
+        let language = buffer.read(cx).language().cloned();
+        let docsets = language_settings(language.as_ref(), buffer.read(cx).file(), cx)
+            .docsets
+            .clone();
+        let symbol_docs = self.symbol_docs.clone();
+        let fs = self.fs.clone();
+        let resolved_indices = completion_indices.clone();
 
         cx.spawn(move |this, mut cx| async move {
             let mut did_resolve = false;
@@ -5782,6 +6698,55 @@ impl Project {
                 }
             }
 
+            // Remember the documentation of the completions that language servers documented,
+            // and fall back to the remembered or docset documentation of the others.
+            if let Some(language) = language {
+                for completion_index in resolved_indices {
+                    let (key, name) = {
+                        let completions = completions.read();
+                        let completion = &completions[completion_index];
+                        let lsp_completion = &completion.lsp_completion;
+                        let key = (
+                            language.name(),
+                            lsp_completion.label.clone(),
+                            lsp_completion.detail.clone(),
+                        );
+                        match &completion.documentation {
+                            None | Some(Documentation::Undocumented) => {}
+                            Some(documentation) => {
+                                symbol_docs.cache_completion(key, documentation.clone());
+                                continue;
+                            }
+                        }
+                        let name = lsp_completion
+                            .filter_text
+                            .clone()
+                            .unwrap_or_else(|| lsp_completion.label.clone());
+                        (key, name)
+                    };
+                    if let Some(documentation) = symbol_docs.cached_completion(&key) {
+                        completions.write()[completion_index].documentation = Some(documentation);
+                        did_resolve = true;
+                        continue;
+                    }
+                    let Some(blocks) = symbol_docs
+                        .lookup_docsets(&name, &docsets, fs.as_ref(), cx.background_executor())
+                        .await
+                    else {
+                        continue;
+                    };
+                    let documentation = markdown::parse_markdown(
+                        &symbol_docs::hover_blocks_to_markdown(&blocks),
+                        &language_registry,
+                        Some(language.clone()),
+                    )
+                    .await;
+                    completions.write()[completion_index].documentation =
+                        Some(Documentation::MultiLineMarkdown(documentation));
+                    did_resolve = true;
+                }
+            }
+
             Ok(did_resolve)
         })
     }
@@ -7251,49 +8216,16 @@ impl Project {
     /// # Errors
     ///
     /// An error will be returned if the worktree or destination worktree are not found.
-    pub fn move_worktree(
-        &mut self,
-        source: WorktreeId,
-        destination: WorktreeId,
-        cx: &mut ModelContext<'_, Self>,
-    ) -> Result<()> {
-        if source == destination {
-            return Ok(());
-        }
-
-        let mut source_index = None;
-        let mut destination_index = None;
-        for (i, worktree) in self.worktrees.iter().enumerate() {
-            if let Some(worktree) = worktree.upgrade() {
-                let worktree_id = worktree.read(cx).id();
-                if worktree_id == source {
-                    source_index = Some(i);
-                    if destination_index.is_some() {
-                        break;
-                    }
-                } else if worktree_id == destination {
-                    destination_index = Some(i);
-                    if source_index.is_some() {
-                        break;
-                    }
-                }
-            }
-        }
-
-        let source_index =
-            source_index.with_context(|| format!("Missing worktree for id {source}"))?;
-        let destination_index =
-            destination_index.with_context(|| format!("Missing worktree for id {destination}"))?;
-
-        if source_index == destination_index {
-            return Ok(());
+    pub fn move_worktree(
+        &mut self,
+        source: WorktreeId,
+        destination: WorktreeId,
+        cx: &mut ModelContext<'_, Self>,
+    ) -> Result<()> {
+        if self.worktree_store.move_worktree(source, destination, cx)? {
+            cx.emit(Event::WorktreeOrderChanged);
+            cx.notify();
         }
-
-        let worktree_to_move = self.worktrees.remove(source_index);
-        self.worktrees.insert(destination_index, worktree_to_move);
-        self.worktrees_reordered = true;
-        cx.emit(Event::WorktreeOrderChanged);
-        cx.notify();
         Ok(())
     }
 
@@ -7318,15 +8250,13 @@ impl Project {
         abs_path: &Path,
         cx: &AppContext,
     ) -> Option<(Model<Worktree>, PathBuf)> {
-        for tree in &self.worktrees {
-            if let Some(tree) = tree.upgrade() {
-                if let Some(relative_path) = tree
-                    .read(cx)
-                    .as_local()
-                    .and_then(|t| abs_path.strip_prefix(t.abs_path()).ok())
-                {
-                    return Some((tree.clone(), relative_path.into()));
-                }
+        for tree in self.worktree_store.worktrees() {
+            if let Some(relative_path) = tree
+                .read(cx)
+                .as_local()
+                .and_then(|t| abs_path.strip_prefix(t.abs_path()).ok())
+            {
+                return Some((tree.clone(), relative_path.into()));
             }
         }
         None
@@ -7397,6 +8327,14 @@ impl Project {
     }
 
     pub fn remove_worktree(&mut self, id_to_remove: WorktreeId, cx: &mut ModelContext<Self>) {
+        self.directory_sizes.remove_worktree(id_to_remove);
+        self.build_targets.remove_worktree(id_to_remove);
+        self.syntax_index.remove_worktree(id_to_remove);
+        self.environment.remove_worktree(id_to_remove);
+        self.job_scheduler.update(cx, |job_scheduler, cx| {
+            job_scheduler.cancel_group(JobGroup::Worktree(id_to_remove), cx)
+        });
+
         let mut servers_to_remove = HashMap::default();
         let mut servers_to_preserve = HashSet::default();
         for ((worktree_id, server_name), &server_id) in &self.language_server_ids {
@@ -7452,19 +8390,9 @@ impl Project {
             inventory.remove_worktree_sources(id_to_remove);
         });
 
-        self.worktrees.retain(|worktree| {
-            if let Some(worktree) = worktree.upgrade() {
-                let id = worktree.read(cx).id();
-                if id == id_to_remove {
-                    cx.emit(Event::WorktreeRemoved(id));
-                    false
-                } else {
-                    true
-                }
-            } else {
-                false
-            }
-        });
+        if self.worktree_store.remove(id_to_remove, cx) {
+            cx.emit(Event::WorktreeRemoved(id_to_remove));
+        }
 
         self.metadata_changed(cx);
     }
@@ -7479,6 +8407,8 @@ impl Project {
                         this.update_local_worktree_buffers(&worktree, changes, cx);
                         this.update_local_worktree_language_servers(&worktree, changes, cx);
                         this.update_local_worktree_settings(&worktree, changes, cx);
+                        this.update_local_worktree_build_targets(&worktree, changes, cx);
+                        this.update_local_worktree_syntax_index(&worktree, changes, cx);
                         this.update_prettier_settings(&worktree, changes, cx);
                     }
 
@@ -7497,6 +8427,26 @@ impl Project {
                     }
                     cx.emit(Event::WorktreeUpdatedGitRepositories);
                 }
+                // Buffers without unsaved changes are reloaded through `UpdatedEntries`, and
+                // deleted or moved files are reflected in their buffers' files.
+                worktree::Event::LoadedFileChanged(path, LoadedFileChange::ChangedOnDisk) => {
+                    let project_path = ProjectPath {
+                        worktree_id: worktree.read(cx).id(),
+                        path: path.clone(),
+                    };
+                    if let Some(buffer) = this.get_open_buffer(&project_path, cx) {
+                        if buffer.read(cx).is_dirty() {
+                            cx.emit(Event::BufferChangedOnDisk(buffer));
+                        }
+                    }
+                }
+                worktree::Event::LoadedFileChanged(..) => {}
+                worktree::Event::FileOperationsProgress(progress) => {
+                    cx.emit(Event::WorktreeFileOperationsProgress(
+                        worktree.read(cx).id(),
+                        *progress,
+                    ));
+                }
             }
         })
         .detach();
@@ -7505,23 +8455,7 @@ impl Project {
             let worktree = worktree.read(cx);
             self.is_shared() || worktree.is_visible() || worktree.is_remote()
         };
-        let handle = if push_strong_handle {
-            WorktreeHandle::Strong(worktree.clone())
-        } else {
-            WorktreeHandle::Weak(worktree.downgrade())
-        };
-        if self.worktrees_reordered {
-            self.worktrees.push(handle);
-        } else {
-            let i = match self
-                .worktrees
-                .binary_search_by_key(&Some(worktree.read(cx).abs_path()), |other| {
-                    other.upgrade().map(|worktree| worktree.read(cx).abs_path())
-                }) {
-                Ok(i) | Err(i) => i,
-            };
-            self.worktrees.insert(i, handle);
-        }
+        self.worktree_store.add(worktree, push_strong_handle, cx);
 
         let handle_id = worktree.entity_id();
         cx.observe_release(worktree, move |this, worktree, cx| {
@@ -7985,6 +8919,232 @@ impl Project {
         .detach();
     }
 
+    /// Parses the manifests that changed, and offers their build targets as tasks. Manifests
+    /// in ignored directories, such as `node_modules`, are skipped.
+    fn update_local_worktree_build_targets(
+        &mut self,
+        worktree: &Model<Worktree>,
+        changes: &UpdatedEntriesSet,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(snapshot) = worktree
+            .read(cx)
+            .as_local()
+            .map(|worktree| worktree.snapshot())
+        else {
+            return;
+        };
+        let worktree_id = snapshot.id();
+        for (path, _, change) in changes.iter() {
+            let Some(kind) = ProjectKind::for_manifest(path) else {
+                continue;
+            };
+            let Ok(abs_path) = snapshot.absolutize(path) else {
+                continue;
+            };
+            let is_ignored = snapshot
+                .entry_for_path(path)
+                .map_or(true, |entry| entry.is_ignored || !entry.is_file());
+            if change == &PathChange::Removed || is_ignored {
+                if self.build_targets.remove(worktree_id, path) {
+                    self.task_inventory().update(cx, |inventory, _| {
+                        inventory.remove_local_static_source(&abs_path)
+                    });
+                    cx.emit(Event::BuildTargetsChanged(worktree_id));
+                }
+                continue;
+            }
+
+            let fs = self.fs.clone();
+            let path = path.clone();
+            let snapshot = snapshot.clone();
+            cx.spawn(|this, mut cx| async move {
+                let contents = fs.load(&abs_path).await?;
+                let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                let manifest = cx
+                    .background_executor()
+                    .spawn(async move {
+                        ProjectManifest::parse(kind, &contents, |relative_path| {
+                            snapshot
+                                .entry_for_path(dir.join(relative_path))
+                                .map_or(false, |entry| entry.is_file())
+                        })
+                    })
+                    .await
+                    .with_context(|| format!("parsing {abs_path:?}"))?;
+                this.update(&mut cx, |this, cx| {
+                    let manifest_dir = abs_path.parent().unwrap_or(&abs_path);
+                    let templates = manifest.task_templates(manifest_dir);
+                    this.build_targets.insert(worktree_id, path, manifest);
+                    this.task_inventory().update(cx, |inventory, cx| {
+                        inventory.remove_local_static_source(&abs_path);
+                        inventory.add_source(
+                            TaskSourceKind::Worktree {
+                                id: worktree_id,
+                                abs_path: abs_path.clone(),
+                                id_base: "build_targets_for_worktree".into(),
+                            },
+                            |_, _| StaticSource::new(TrackedFile::new_static(templates)),
+                            cx,
+                        );
+                    });
+                    cx.emit(Event::BuildTargetsChanged(worktree_id));
+                })
+            })
+            .detach_and_log_err(cx);
+        }
+    }
+
+    /// The manifests of the packages in a local worktree, by their paths relative to the
+    /// worktree.
+    pub fn build_manifests(
+        &self,
+        worktree_id: WorktreeId,
+    ) -> impl Iterator<Item = (&Arc<Path>, &ProjectManifest)> {
+        self.build_targets.manifests(worktree_id)
+    }
+
+    /// The kinds of the projects whose manifests are at the root of a local worktree.
+    pub fn project_kinds(&self, worktree_id: WorktreeId) -> Vec<ProjectKind> {
+        self.build_targets.project_kinds(worktree_id)
+    }
+
+    /// Queues the files of a local worktree that weren't indexed yet to have their symbols
+    /// indexed.
+    fn index_local_worktree_syntax(
+        &mut self,
+        worktree: &Model<Worktree>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let worktree = worktree.read(cx);
+        if !worktree.is_visible() || !worktree.is_local() {
+            return;
+        }
+        let worktree_id = worktree.id();
+        let max_file_size = ProjectSettings::get_global(cx)
+            .syntax_index
+            .max_file_size_bytes();
+        let paths = worktree
+            .files(false, true, 0)
+            .filter(|entry| syntax_index::is_indexable(entry, max_file_size, &self.languages))
+            .map(|entry| ProjectPath {
+                worktree_id,
+                path: entry.path.clone(),
+            })
+            .filter(|project_path| !self.syntax_index.is_indexed(project_path))
+            .collect::<Vec<_>>();
+        self.syntax_index.enqueue(paths);
+        self.start_syntax_indexing(cx);
+    }
+
+    /// Queues the files that changed in a local worktree to have their symbols indexed
+    /// again, and forgets the symbols of the files that were removed.
+    fn update_local_worktree_syntax_index(
+        &mut self,
+        worktree: &Model<Worktree>,
+        changes: &UpdatedEntriesSet,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if !self.syntax_index_enabled || !worktree.read(cx).is_visible() {
+            return;
+        }
+        let Some(snapshot) = worktree
+            .read(cx)
+            .as_local()
+            .map(|worktree| worktree.snapshot())
+        else {
+            return;
+        };
+        let worktree_id = snapshot.id();
+        let max_file_size = ProjectSettings::get_global(cx)
+            .syntax_index
+            .max_file_size_bytes();
+        let mut paths = Vec::new();
+        for (path, _, _) in changes.iter() {
+            let project_path = ProjectPath {
+                worktree_id,
+                path: path.clone(),
+            };
+            let is_indexable = snapshot.entry_for_path(path).map_or(false, |entry| {
+                syntax_index::is_indexable(entry, max_file_size, &self.languages)
+            });
+            if is_indexable {
+                paths.push(project_path);
+            } else {
+                self.syntax_index.remove(&project_path);
+            }
+        }
+        self.syntax_index.enqueue(paths);
+        self.start_syntax_indexing(cx);
+    }
+
+    /// Parses the queued files one at a time, waiting between them so that no more files are
+    /// parsed per second than the settings allow.
+    fn start_syntax_indexing(&mut self, cx: &mut ModelContext<Self>) {
+        if self.syntax_indexing.is_some() {
+            return;
+        }
+        self.syntax_indexing = Some(cx.spawn(|this, mut cx| async move {
+            loop {
+                let Ok(Some((job, interval))) = this.update(&mut cx, |this, cx| {
+                    let job = this.index_next_queued_file(cx)?;
+                    let interval = ProjectSettings::get_global(cx).syntax_index.interval();
+                    Some((job, interval))
+                }) else {
+                    break;
+                };
+                let started_at = Instant::now();
+                job.await.log_err();
+                cx.background_executor()
+                    .timer(interval.saturating_sub(started_at.elapsed()))
+                    .await;
+            }
+            this.update(&mut cx, |this, _| this.syntax_indexing = None)
+                .ok();
+        }));
+    }
+
+    fn index_next_queued_file(&mut self, cx: &mut ModelContext<Self>) -> Option<Task<Result<()>>> {
+        // Files that were removed since they were queued are skipped.
+        let (project_path, abs_path) = loop {
+            let project_path = self.syntax_index.next_queued()?;
+            if self.entry_for_path(&project_path, cx).is_none() {
+                continue;
+            }
+            if let Some(abs_path) = self.absolute_path(&project_path, cx) {
+                break (project_path, abs_path);
+            }
+        };
+        let fs = self.fs.clone();
+        let languages = self.languages.clone();
+        let parse = self.job_scheduler.update(cx, |job_scheduler, cx| {
+            job_scheduler.schedule(
+                "Indexing symbols",
+                JobPriority::Index,
+                JobGroup::Worktree(project_path.worktree_id),
+                move |reporter| async move {
+                    reporter.report(abs_path.to_string_lossy().to_string(), None);
+                    let language = languages.language_for_file_path(&abs_path).await?;
+                    let text = fs.load(&abs_path).await?;
+                    anyhow::Ok(syntax_index::symbols_for_text(
+                        text,
+                        language,
+                        Some(languages),
+                    ))
+                },
+                cx,
+            )
+        });
+        Some(cx.spawn(|this, mut cx| async move {
+            let symbols = parse.await??;
+            this.update(&mut cx, |this, cx| {
+                if this.syntax_index_enabled && this.entry_for_path(&project_path, cx).is_some() {
+                    this.syntax_index.insert(project_path, symbols);
+                }
+            })
+        }))
+    }
+
     pub fn set_active_path(&mut self, entry: Option<ProjectPath>, cx: &mut ModelContext<Self>) {
         let new_active_entry = entry.and_then(|project_path| {
             let worktree = self.worktree_for_id(project_path.worktree_id, cx)?;
@@ -8171,6 +9331,124 @@ impl Project {
         worktree.get_local_repo(&root_entry)?.repo().clone().into()
     }
 
+    /// Loads the contents of the buffer's file as of the HEAD commit of its repository.
+    pub fn load_committed_text(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
+        let params = self.local_repo_for_buffer(buffer, cx);
+        cx.background_executor().spawn(async move {
+            let (repo, relative_path) = params?;
+            let text = repo.lock().load_committed_text(&relative_path);
+            Ok(text)
+        })
+    }
+
+    /// Returns the commits that changed the buffer's file, newest first, following it across
+    /// renames.
+    pub fn file_history(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<FileHistoryEntry>>> {
+        let params = self.local_repo_for_buffer(buffer, cx);
+        cx.background_executor().spawn(async move {
+            let (repo, relative_path) = params?;
+            let history = repo.lock().file_history(&relative_path)?;
+            Ok(history)
+        })
+    }
+
+    /// Loads the contents of a file in the buffer's repository as of the given commit, where
+    /// `path` is the file's path in that commit, e.g. from [`Project::file_history`].
+    pub fn load_text_at_revision(
+        &self,
+        buffer: &Model<Buffer>,
+        sha: String,
+        path: PathBuf,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
+        let params = self.local_repo_for_buffer(buffer, cx);
+        cx.background_executor().spawn(async move {
+            let (repo, _) = params?;
+            let text = repo.lock().load_text_at_revision(&sha, &path)?;
+            Ok(text)
+        })
+    }
+
+    /// Loads the base, our and their versions of the buffer's file while its repository is in
+    /// the middle of a merge that left the file conflicted.
+    pub fn load_merge_versions(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Task<Result<Option<MergeVersions>>> {
+        let params = self.local_repo_for_buffer(buffer, cx);
+        cx.background_executor().spawn(async move {
+            let (repo, relative_path) = params?;
+            let versions = repo.lock().load_merge_versions(&relative_path);
+            Ok(versions)
+        })
+    }
+
+    /// Replaces the contents of the buffer's file in its repository's index, in order to stage
+    /// or unstage changes to it. Removes the file from the index if `text` is `None`.
+    pub fn set_index_text(
+        &self,
+        buffer: &Model<Buffer>,
+        text: Option<String>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let params = self.local_repo_for_buffer(buffer, cx);
+        let buffer = buffer.clone();
+        cx.spawn(|_, mut cx| async move {
+            let (repo, relative_path) = params?;
+            cx.background_executor()
+                .spawn({
+                    let text = text.clone();
+                    async move { repo.lock().set_index_text(&relative_path, text) }
+                })
+                .await?;
+
+            // The buffer's diff base is the index text, so update it right away rather than
+            // waiting for the change to the index to be noticed on disk.
+            buffer.update(&mut cx, |buffer, cx| buffer.set_diff_base(text, cx))?;
+            Ok(())
+        })
+    }
+
+    fn local_repo_for_buffer(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Result<(Arc<Mutex<dyn GitRepository>>, RepoPath)> {
+        if !self.is_local() {
+            return Err(anyhow!(
+                "accessing git repositories is not supported in remote projects"
+            ));
+        }
+
+        let buffer_project_path = buffer
+            .read(cx)
+            .project_path(cx)
+            .context("failed to get buffer project path")?;
+        let worktree = self
+            .worktree_for_id(buffer_project_path.worktree_id, cx)
+            .context("failed to get worktree")?
+            .read(cx)
+            .as_local()
+            .context("worktree was not local")?
+            .snapshot();
+        let (repo_entry, local_repo_entry) = worktree
+            .repo_for_path(&buffer_project_path.path)
+            .ok_or(NoRepositoryError {})?;
+        let relative_path = repo_entry
+            .relativize(&worktree, &buffer_project_path.path)
+            .context("failed to relativize buffer path")?;
+        Ok((local_repo_entry.repo().clone(), relative_path))
+    }
+
     pub fn blame_buffer(
         &self,
         buffer: &Model<Buffer>,
@@ -8571,11 +9849,10 @@ impl Project {
                 .ok_or_else(|| anyhow!("worktree not found"))
         })??;
         let worktree_scan_id = worktree.update(&mut cx, |worktree, _| worktree.scan_id())?;
-        let entry = worktree
-            .update(&mut cx, |worktree, cx| {
-                let worktree = worktree.as_local_mut().unwrap();
+        let entry = this
+            .update(&mut cx, |this, cx| {
                 let path = PathBuf::from(envelope.payload.path);
-                worktree.create_entry(path, envelope.payload.is_directory, cx)
+                this.create_local_entry(worktree, path.into(), envelope.payload.is_directory, cx)
             })?
             .await?;
         Ok(proto::ProjectEntryResponse {
@@ -8596,13 +9873,10 @@ impl Project {
                 .ok_or_else(|| anyhow!("worktree not found"))
         })??;
         let worktree_scan_id = worktree.update(&mut cx, |worktree, _| worktree.scan_id())?;
-        let entry = worktree
-            .update(&mut cx, |worktree, cx| {
+        let entry = this
+            .update(&mut cx, |this, cx| {
                 let new_path = PathBuf::from(envelope.payload.new_path);
-                worktree
-                    .as_local_mut()
-                    .unwrap()
-                    .rename_entry(entry_id, new_path, cx)
+                this.rename_local_entry(worktree, entry_id, new_path.into(), cx)
             })?
             .await?;
         Ok(proto::ProjectEntryResponse {
@@ -8654,15 +9928,11 @@ impl Project {
                 .ok_or_else(|| anyhow!("worktree not found"))
         })??;
         let worktree_scan_id = worktree.update(&mut cx, |worktree, _| worktree.scan_id())?;
-        worktree
-            .update(&mut cx, |worktree, cx| {
-                worktree
-                    .as_local_mut()
-                    .unwrap()
-                    .delete_entry(entry_id, trash, cx)
-                    .ok_or_else(|| anyhow!("invalid entry"))
-            })??
-            .await?;
+        this.update(&mut cx, |this, cx| {
+            this.delete_local_entry(worktree, entry_id, trash, cx)
+                .ok_or_else(|| anyhow!("invalid entry"))
+        })??
+        .await?;
         Ok(proto::ProjectEntryResponse {
             entry: None,
             worktree_scan_id: worktree_scan_id as u64,
@@ -10125,19 +11395,17 @@ impl Project {
         let remote_id = self.remote_id().ok_or_else(|| anyhow!("invalid project"))?;
 
         let mut old_worktrees_by_id = self
-            .worktrees
-            .drain(..)
-            .filter_map(|worktree| {
-                let worktree = worktree.upgrade()?;
-                Some((worktree.read(cx).id(), worktree))
-            })
+            .worktree_store
+            .take()
+            .into_iter()
+            .map(|worktree| (worktree.read(cx).id(), worktree))
             .collect::<HashMap<_, _>>();
 
         for worktree in worktrees {
             if let Some(old_worktree) =
                 old_worktrees_by_id.remove(&WorktreeId::from_proto(worktree.id))
             {
-                self.worktrees.push(WorktreeHandle::Strong(old_worktree));
+                self.worktree_store.push_strong(old_worktree);
             } else {
                 let worktree =
                     Worktree::remote(remote_id, replica_id, worktree, self.client.clone(), cx);
@@ -10838,6 +12106,15 @@ async fn populate_labels_for_symbols(
     }
 }
 
+/// Removes the completions that a higher-priority server already offered, inserting the same
+/// text with the same label.
+fn dedup_completions(completions: &mut Vec<Completion>) {
+    let mut seen = HashSet::default();
+    completions.retain(|completion| {
+        seen.insert((completion.label.text.clone(), completion.new_text.clone()))
+    });
+}
+
 async fn populate_labels_for_completions(
     mut new_completions: Vec<CoreCompletion>,
     language_registry: &Arc<LanguageRegistry>,
@@ -10931,7 +12208,7 @@ async fn search_snapshots(
             let end_in_snapshot = cmp::min(worker_end_ix, snapshot_end_ix) - snapshot_start_ix;
 
             for entry in snapshot
-                .files(false, start_in_snapshot)
+                .files(false, true, start_in_snapshot)
                 .take(end_in_snapshot - start_in_snapshot)
             {
                 if results_tx.is_closed() {
@@ -11064,22 +12341,6 @@ fn glob_literal_prefix(glob: &str) -> &str {
     &glob[..literal_end]
 }
 
-impl WorktreeHandle {
-    pub fn upgrade(&self) -> Option<Model<Worktree>> {
-        match self {
-            WorktreeHandle::Strong(handle) => Some(handle.clone()),
-            WorktreeHandle::Weak(handle) => handle.upgrade(),
-        }
-    }
-
-    pub fn handle_id(&self) -> usize {
-        match self {
-            WorktreeHandle::Strong(handle) => handle.entity_id().as_u64() as usize,
-            WorktreeHandle::Weak(handle) => handle.entity_id().as_u64() as usize,
-        }
-    }
-}
-
 impl OpenBuffer {
     pub fn upgrade(&self) -> Option<Model<Buffer>> {
         match self {
@@ -11093,8 +12354,22 @@ impl OpenBuffer {
 pub struct PathMatchCandidateSet {
     pub snapshot: Snapshot,
     pub include_ignored: bool,
+    /// Whether dotfiles, and the files that the file system marks as hidden, are matched.
+    pub include_hidden: bool,
     pub include_root_name: bool,
     pub directories_only: bool,
+    pub score_penalties: Vec<PathScorePenalty>,
+    /// The paths that are never matched, such as transient files.
+    pub excluded_paths: Vec<PathMatcher>,
+}
+
+/// Lowers the score of the paths matching a glob, such as generated or minified files, so that
+/// they rank below other matches of equal textual score.
+#[derive(Clone, Debug)]
+pub struct PathScorePenalty {
+    pub matcher: PathMatcher,
+    /// The fraction of the score to deduct, between 0 and 1.
+    pub penalty: f64,
 }
 
 impl<'a> fuzzy::PathMatchCandidateSet<'a> for PathMatchCandidateSet {
@@ -11105,11 +12380,8 @@ impl<'a> fuzzy::PathMatchCandidateSet<'a> for PathMatchCandidateSet {
     }
 
     fn len(&self) -> usize {
-        if self.include_ignored {
-            self.snapshot.file_count()
-        } else {
-            self.snapshot.visible_file_count()
-        }
+        self.snapshot
+            .filtered_file_count(self.include_ignored, self.include_hidden)
     }
 
     fn prefix(&self) -> Arc<str> {
@@ -11122,12 +12394,30 @@ impl<'a> fuzzy::PathMatchCandidateSet<'a> for PathMatchCandidateSet {
         }
     }
 
+    fn score_multiplier(&self, path: &Path) -> f64 {
+        let penalty = self
+            .score_penalties
+            .iter()
+            .filter(|penalty| penalty.matcher.is_match(path))
+            .map(|penalty| penalty.penalty)
+            .fold(0., f64::max);
+        1. - penalty
+    }
+
+    fn is_excluded(&self, path: &Path) -> bool {
+        self.excluded_paths
+            .iter()
+            .any(|matcher| matcher.is_match(path))
+    }
+
     fn candidates(&'a self, start: usize) -> Self::Candidates {
         PathMatchCandidateSetIter {
             traversal: if self.directories_only {
-                self.snapshot.directories(self.include_ignored, start)
+                self.snapshot
+                    .directories(self.include_ignored, self.include_hidden, start)
             } else {
-                self.snapshot.files(self.include_ignored, start)
+                self.snapshot
+                    .files(self.include_ignored, self.include_hidden, start)
             },
         }
     }
@@ -11181,7 +12471,7 @@ pub struct ProjectLspAdapterDelegate {
     fs: Arc<dyn Fs>,
     http_client: Arc<dyn HttpClient>,
     language_registry: Arc<LanguageRegistry>,
-    shell_env: Mutex<Option<HashMap<String, String>>>,
+    shell_env: Shared<Task<HashMap<String, String>>>,
 }
 
 impl ProjectLspAdapterDelegate {
@@ -11190,27 +12480,16 @@ impl ProjectLspAdapterDelegate {
         worktree: &Model<Worktree>,
         cx: &ModelContext<Project>,
     ) -> Arc<Self> {
+        let worktree = worktree.read(cx);
         Arc::new(Self {
             project: cx.weak_model(),
-            worktree: worktree.read(cx).snapshot(),
+            worktree: worktree.snapshot(),
             fs: project.fs.clone(),
             http_client: project.client.http_client(),
             language_registry: project.languages.clone(),
-            shell_env: Default::default(),
+            shell_env: project.worktree_environment(worktree.id(), cx).shared(),
         })
     }
-
-    async fn load_shell_env(&self) {
-        let worktree_abs_path = self.worktree.abs_path();
-        let shell_env = load_shell_environment(&worktree_abs_path)
-            .await
-            .with_context(|| {
-                format!("failed to determine load login shell environment in {worktree_abs_path:?}")
-            })
-            .log_err()
-            .unwrap_or_default();
-        *self.shell_env.lock() = Some(shell_env);
-    }
 }
 
 #[async_trait]
@@ -11234,19 +12513,13 @@ impl LspAdapterDelegate for ProjectLspAdapterDelegate {
     }
 
     async fn shell_env(&self) -> HashMap<String, String> {
-        self.load_shell_env().await;
-        self.shell_env.lock().as_ref().cloned().unwrap_or_default()
+        self.shell_env.clone().await
     }
 
     #[cfg(not(target_os = "windows"))]
     async fn which(&self, command: &OsStr) -> Option<PathBuf> {
         let worktree_abs_path = self.worktree.abs_path();
-        self.load_shell_env().await;
-        let shell_path = self
-            .shell_env
-            .lock()
-            .as_ref()
-            .and_then(|shell_env| shell_env.get("PATH").cloned());
+        let shell_path = self.shell_env.clone().await.get("PATH").cloned();
         which::which_in(command, shell_path.as_ref(), &worktree_abs_path).ok()
     }
 
@@ -11409,72 +12682,6 @@ fn include_text(server: &lsp::LanguageServer) -> bool {
         .unwrap_or(false)
 }
 
-async fn load_shell_environment(dir: &Path) -> Result<HashMap<String, String>> {
-    let marker = "ZED_SHELL_START";
-    let shell = env::var("SHELL").context(
-        "SHELL environment variable is not assigned so we can't source login environment variables",
-    )?;
-
-    // What we're doing here is to spawn a shell and then `cd` into
-    // the project directory to get the env in there as if the user
-    // `cd`'d into it. We do that because tools like direnv, asdf, ...
-    // hook into `cd` and only set up the env after that.
-    //
-    // In certain shells we need to execute additional_command in order to
-    // trigger the behavior of direnv, etc.
-    //
-    //
-    // The `exit 0` is the result of hours of debugging, trying to find out
-    // why running this command here, without `exit 0`, would mess
-    // up signal process for our process so that `ctrl-c` doesn't work
-    // anymore.
-    //
-    // We still don't know why `$SHELL -l -i -c '/usr/bin/env -0'`  would
-    // do that, but it does, and `exit 0` helps.
-    let additional_command = PathBuf::from(&shell)
-        .file_name()
-        .and_then(|f| f.to_str())
-        .and_then(|shell| match shell {
-            "fish" => Some("emit fish_prompt;"),
-            _ => None,
-        });
-
-    let command = format!(
-        "cd '{}';{} printf '%s' {marker}; /usr/bin/env; exit 0;",
-        dir.display(),
-        additional_command.unwrap_or("")
-    );
-
-    let output = smol::process::Command::new(&shell)
-        .args(["-i", "-c", &command])
-        .output()
-        .await
-        .context("failed to spawn login shell to source login environment variables")?;
-
-    anyhow::ensure!(
-        output.status.success(),
-        "login shell exited with error {:?}",
-        output.status
-    );
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let env_output_start = stdout.find(marker).ok_or_else(|| {
-        anyhow!(
-            "failed to parse output of `env` command in login shell: {}",
-            stdout
-        )
-    })?;
-
-    let mut parsed_env = HashMap::default();
-    let env_output = &stdout[env_output_start + marker.len()..];
-
-    parse_env_output(env_output, |key, value| {
-        parsed_env.insert(key, value);
-    });
-
-    Ok(parsed_env)
-}
-
 fn serialize_blame_buffer_response(blame: git::blame::Blame) -> proto::BlameBufferResponse {
     let entries = blame
         .entries
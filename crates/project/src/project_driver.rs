@@ -0,0 +1,96 @@
+//! Drives a local project from async code without a window: opening worktrees, waiting for
+//! them to be scanned, and opening, editing and saving buffers. It's meant for tooling and
+//! integration tests, which would otherwise update the models directly and poll for changes.
+
+use std::{ops::Range, path::Path, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use gpui::{AsyncAppContext, Model};
+use language::{Buffer, ToOffset};
+use worktree::Worktree;
+
+use crate::Project;
+
+pub struct ProjectDriver {
+    project: Model<Project>,
+    cx: AsyncAppContext,
+}
+
+impl ProjectDriver {
+    pub fn new(project: Model<Project>, cx: AsyncAppContext) -> Self {
+        Self { project, cx }
+    }
+
+    pub fn project(&self) -> &Model<Project> {
+        &self.project
+    }
+
+    /// Adds a visible worktree for the given path, unless one already contains it, and
+    /// waits until it's scanned.
+    pub async fn open_worktree(&mut self, abs_path: impl AsRef<Path>) -> Result<Model<Worktree>> {
+        let (worktree, _) = self
+            .project
+            .update(&mut self.cx, |project, cx| {
+                project.find_or_create_local_worktree(abs_path, true, cx)
+            })?
+            .await?;
+        Self::wait_for_scan(&worktree, &mut self.cx).await?;
+        Ok(worktree)
+    }
+
+    /// Waits until all of the project's local worktrees are scanned.
+    pub async fn scan_complete(&mut self) -> Result<()> {
+        let worktrees = self.project.read_with(&self.cx, |project, _| {
+            project.worktrees().collect::<Vec<_>>()
+        })?;
+        for worktree in worktrees {
+            Self::wait_for_scan(&worktree, &mut self.cx).await?;
+        }
+        Ok(())
+    }
+
+    /// Opens the buffer for the file at the given path, which must be within one of the
+    /// project's worktrees.
+    pub async fn open_buffer(&mut self, abs_path: impl AsRef<Path>) -> Result<Model<Buffer>> {
+        self.project
+            .update(&mut self.cx, |project, cx| {
+                project.open_local_buffer(abs_path, cx)
+            })?
+            .await
+    }
+
+    pub fn edit<I, S, T>(&mut self, buffer: &Model<Buffer>, edits: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (Range<S>, T)>,
+        S: ToOffset,
+        T: Into<Arc<str>>,
+    {
+        buffer.update(&mut self.cx, |buffer, cx| {
+            buffer.edit(edits, None, cx);
+        })
+    }
+
+    pub fn text(&self, buffer: &Model<Buffer>) -> Result<String> {
+        buffer.read_with(&self.cx, |buffer, _| buffer.text())
+    }
+
+    /// Saves the buffer to its file, running its post-save hooks.
+    pub async fn save(&mut self, buffer: &Model<Buffer>) -> Result<()> {
+        self.project
+            .update(&mut self.cx, |project, cx| {
+                project.save_buffer(buffer.clone(), cx)
+            })?
+            .await
+    }
+
+    async fn wait_for_scan(worktree: &Model<Worktree>, cx: &mut AsyncAppContext) -> Result<()> {
+        let scan_complete = worktree.update(cx, |worktree, _| {
+            worktree
+                .as_local()
+                .map(|worktree| worktree.scan_complete())
+                .ok_or_else(|| anyhow!("worktree isn't local"))
+        })??;
+        scan_complete.await;
+        Ok(())
+    }
+}
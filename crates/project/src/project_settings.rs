@@ -20,6 +20,23 @@ pub struct ProjectSettings {
     /// Configuration for Git-related features
     #[serde(default)]
     pub git: GitSettings,
+
+    /// Configuration for the local history of saved files.
+    #[serde(default)]
+    pub local_history: LocalHistorySettings,
+
+    /// Configuration for indexing the symbols of local files in the background, by
+    /// parsing them, so that they can be found without a language server.
+    #[serde(default)]
+    pub syntax_index: SyntaxIndexSettings,
+
+    /// Environment variables to set for the language servers, formatters and tasks
+    /// that run in a worktree, on top of the environment of the user's login shell
+    /// in the worktree.
+    ///
+    /// Default: {}
+    #[serde(default)]
+    pub env: HashMap<String, String>,
 }
 
 #[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
@@ -55,6 +72,70 @@ impl GitSettings {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LocalHistorySettings {
+    /// Whether to keep a copy of the contents of local files whenever they're saved,
+    /// so that earlier versions can be recovered.
+    ///
+    /// Default: true
+    pub enabled: Option<bool>,
+    /// The maximum total size of the kept copies, in megabytes. The oldest
+    /// versions are removed first.
+    ///
+    /// Default: 256
+    pub max_size_mb: Option<u64>,
+    /// The maximum number of versions kept for each file.
+    ///
+    /// Default: 50
+    pub max_versions_per_file: Option<usize>,
+}
+
+impl LocalHistorySettings {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    pub fn max_size_bytes(&self) -> u64 {
+        self.max_size_mb.unwrap_or(256) * 1024 * 1024
+    }
+
+    pub fn max_versions_per_file(&self) -> usize {
+        self.max_versions_per_file.unwrap_or(50)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SyntaxIndexSettings {
+    /// Whether to parse the files of local worktrees in the background to index
+    /// their symbols.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+    /// The maximum number of files parsed per second.
+    ///
+    /// Default: 20
+    pub max_files_per_second: Option<u32>,
+    /// The size, in kilobytes, above which files aren't indexed.
+    ///
+    /// Default: 512
+    pub max_file_size_kb: Option<u64>,
+}
+
+impl SyntaxIndexSettings {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+
+    /// The minimum time between the starts of two files' parsing.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(1) / self.max_files_per_second.unwrap_or(20).max(1)
+    }
+
+    pub fn max_file_size_bytes(&self) -> u64 {
+        self.max_file_size_kb.unwrap_or(512) * 1024
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum GitGutterSetting {
@@ -93,6 +174,9 @@ const fn true_value() -> bool {
 pub struct BinarySettings {
     pub path: Option<String>,
     pub arguments: Option<Vec<String>>,
+    /// Environment variables to set for the language server. When given, they're added to
+    /// the environment of the login shell in the worktree.
+    pub env: Option<HashMap<String, String>>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
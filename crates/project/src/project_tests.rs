@@ -1,16 +1,25 @@
-use crate::{Event, *};
+use crate::{
+    project_settings::{BinarySettings, LspSettings},
+    settings_lsp_adapter::SettingsLspAdapter,
+    Event, *,
+};
 use fs::FakeFs;
 use futures::{future, StreamExt};
 use gpui::{AppContext, UpdateGlobal};
 use language::{
-    language_settings::{AllLanguageSettings, LanguageSettingsContent},
+    language_settings::{
+        AllLanguageSettings, LanguageSettingsContent, SaveHook, SaveHookFailurePolicy,
+        SaveHookKind, SaveHooks,
+    },
     tree_sitter_rust, tree_sitter_typescript, Diagnostic, FakeLspAdapter, LanguageConfig,
-    LanguageMatcher, LineEnding, OffsetRangeExt, Point, ToPoint,
+    LanguageMatcher, LanguageServerName, LineEnding, LspAdapter as _, OffsetRangeExt, Point,
+    ToPoint,
 };
 use lsp::Url;
 use parking_lot::Mutex;
 use pretty_assertions::assert_eq;
 use serde_json::json;
+use std::ffi::OsString;
 #[cfg(not(windows))]
 use std::os;
 use std::task::Poll;
@@ -295,6 +304,156 @@ async fn test_managing_project_specific_settings(cx: &mut gpui::TestAppContext)
     );
 }
 
+#[gpui::test]
+async fn test_build_targets_from_manifests(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            ".gitignore": "node_modules",
+            "Cargo.toml": "[package]\nname = \"app\"\n",
+            "src": { "main.rs": "fn main() {}" },
+            "web": {
+                "package.json": r#"{ "name": "web", "scripts": { "dev": "vite" } }"#,
+            },
+            "node_modules": {
+                "dep": {
+                    "package.json": r#"{ "name": "dep", "scripts": { "build": "tsc" } }"#,
+                },
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/the-root".as_ref()], cx).await;
+    cx.executor().run_until_parked();
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+    let task_labels = |cx: &mut gpui::TestAppContext| {
+        let tasks =
+            cx.update(|cx| get_all_tasks(&project, Some(worktree_id), &TaskContext::default(), cx));
+        async move {
+            let mut labels = tasks
+                .await
+                .into_iter()
+                .map(|(_, task)| task.resolved_label)
+                .collect::<Vec<_>>();
+            labels.sort();
+            labels
+        }
+    };
+
+    assert_eq!(
+        task_labels(cx).await,
+        [
+            "cargo build (app)",
+            "cargo run --bin app (app)",
+            "cargo test (app)",
+            "npm run dev (web)",
+        ]
+    );
+    project.update(cx, |project, _| {
+        assert_eq!(project.project_kinds(worktree_id), [ProjectKind::Cargo]);
+    });
+
+    // Manifests are parsed again when they change, and forgotten when they're removed.
+    fs.insert_file(
+        "/the-root/Cargo.toml",
+        b"[package]\nname = \"app\"\n\n[[example]]\nname = \"demo\"\n".to_vec(),
+    )
+    .await;
+    fs.remove_file("/the-root/web/package.json".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        task_labels(cx).await,
+        [
+            "cargo build (app)",
+            "cargo run --bin app (app)",
+            "cargo run --example demo (app)",
+            "cargo test (app)",
+        ]
+    );
+    project.update(cx, |project, _| {
+        assert_eq!(
+            project
+                .build_manifests(worktree_id)
+                .map(|(path, _)| path.as_ref())
+                .collect::<Vec<_>>(),
+            [Path::new("Cargo.toml")]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_worktree_environment(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            ".zed": {
+                "settings.json": r#"{ "env": { "RUST_LOG": "info" } }"#,
+            },
+            "a.rs": "",
+        }),
+    )
+    .await;
+    fs.insert_tree("/other-root", json!({ "b.rs": "" })).await;
+
+    let project = Project::test(
+        fs.clone(),
+        ["/the-root".as_ref(), "/other-root".as_ref()],
+        cx,
+    )
+    .await;
+    cx.executor().run_until_parked();
+    let (the_root_id, other_root_id) = project.read_with(cx, |project, cx| {
+        let worktree_id = |abs_path: &str| {
+            project
+                .worktrees()
+                .find(|worktree| worktree.read(cx).abs_path().as_ref() == Path::new(abs_path))
+                .unwrap()
+                .read(cx)
+                .id()
+        };
+        (worktree_id("/the-root"), worktree_id("/other-root"))
+    });
+
+    // The captured login shell environment is extended with each worktree's own variables.
+    let environment = project
+        .read_with(cx, |project, cx| {
+            project.worktree_environment(the_root_id, cx)
+        })
+        .await;
+    assert_eq!(
+        environment.get("ZED_FAKE_TEST_ENV").map(String::as_str),
+        Some("true")
+    );
+    assert_eq!(
+        environment.get("RUST_LOG").map(String::as_str),
+        Some("info")
+    );
+
+    let other_environment = project
+        .read_with(cx, |project, cx| {
+            project.worktree_environment(other_root_id, cx)
+        })
+        .await;
+    assert_eq!(
+        other_environment
+            .get("ZED_FAKE_TEST_ENV")
+            .map(String::as_str),
+        Some("true")
+    );
+    assert_eq!(other_environment.get("RUST_LOG"), None);
+}
+
 #[gpui::test]
 async fn test_managing_language_servers(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2415,6 +2574,252 @@ async fn test_definition(cx: &mut gpui::TestAppContext) {
     }
 }
 
+#[gpui::test]
+async fn test_type_definition_and_implementation(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "struct A; trait T {} impl T for A {}",
+            "b.rs": "fn b(t: &dyn crate::T) { let a = crate::A; }",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                type_definition_provider: Some(lsp::TypeDefinitionProviderCapability::Simple(true)),
+                implementation_provider: Some(lsp::ImplementationProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::GotoTypeDefinition, _, _>(|params, _| async move {
+        let params = params.text_document_position_params;
+        assert_eq!(params.position, lsp::Position::new(0, 30));
+        Ok(Some(lsp::GotoTypeDefinitionResponse::Scalar(
+            lsp::Location::new(
+                lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+                lsp::Range::new(lsp::Position::new(0, 7), lsp::Position::new(0, 8)),
+            ),
+        )))
+    });
+    fake_server.handle_request::<lsp::request::GotoImplementation, _, _>(|params, _| async move {
+        let params = params.text_document_position_params;
+        assert_eq!(params.position, lsp::Position::new(0, 20));
+        Ok(Some(lsp::GotoImplementationResponse::Array(vec![
+            lsp::Location::new(
+                lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+                lsp::Range::new(lsp::Position::new(0, 21), lsp::Position::new(0, 36)),
+            ),
+        ])))
+    });
+
+    let type_definitions = project
+        .update(cx, |project, cx| project.type_definition(&buffer, 30, cx))
+        .await
+        .unwrap();
+    let implementations = project
+        .update(cx, |project, cx| project.implementation(&buffer, 20, cx))
+        .await
+        .unwrap();
+
+    cx.update(|cx| {
+        assert_eq!(type_definitions.len(), 1);
+        let target_buffer = type_definitions[0].target.buffer.read(cx);
+        assert_eq!(
+            target_buffer.file().unwrap().path().as_ref(),
+            Path::new("a.rs")
+        );
+        assert_eq!(
+            type_definitions[0].target.range.to_offset(target_buffer),
+            7..8
+        );
+
+        assert_eq!(implementations.len(), 1);
+        let target_buffer = implementations[0].target.buffer.read(cx);
+        assert_eq!(
+            target_buffer
+                .text_for_range(implementations[0].target.range.to_offset(target_buffer))
+                .collect::<String>(),
+            "impl T for A {}"
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_document_colors(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "const RED: &str = \"#ff0000\";",
+            "b.txt": "background: rgb(0 0 255 / 50%); color: #abcdefg;",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                color_provider: Some(lsp::ColorProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let rust_buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::DocumentColor, _, _>(|_, _| async move {
+        Ok(vec![lsp::ColorInformation {
+            range: lsp::Range::new(lsp::Position::new(0, 20), lsp::Position::new(0, 27)),
+            color: lsp::Color {
+                red: 1.,
+                green: 0.,
+                blue: 0.,
+                alpha: 1.,
+            },
+        }])
+    });
+    let colors = project
+        .update(cx, |project, cx| project.document_colors(&rust_buffer, cx))
+        .await
+        .unwrap();
+    rust_buffer.update(cx, |buffer, _| {
+        assert_eq!(
+            colors
+                .iter()
+                .map(|color| (color.range.to_offset(buffer), color.color))
+                .collect::<Vec<_>>(),
+            [(20..27, gpui::rgb(0xff0000))]
+        );
+    });
+
+    // Without a language server, colors are found in the text.
+    let text_buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/b.txt", cx)
+        })
+        .await
+        .unwrap();
+    let colors = project
+        .update(cx, |project, cx| project.document_colors(&text_buffer, cx))
+        .await
+        .unwrap();
+    text_buffer.update(cx, |buffer, _| {
+        assert_eq!(
+            colors
+                .iter()
+                .map(|color| (
+                    buffer
+                        .text_for_range(color.range.clone())
+                        .collect::<String>(),
+                    color.color
+                ))
+                .collect::<Vec<_>>(),
+            [(
+                "rgb(0 0 255 / 50%)".to_string(),
+                gpui::Rgba {
+                    r: 0.,
+                    g: 0.,
+                    b: 1.,
+                    a: 0.5
+                }
+            )]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_detect_links(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "src": {
+                "main.rs": "// lib.rs:1, src/main.rs:2:3, missing.rs:4 and https://zed.dev",
+                "lib.rs": "",
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/src/main.rs", cx)
+        })
+        .await
+        .unwrap();
+
+    // Paths are resolved relative to the buffer's directory, then to the worktree's root.
+    project.read_with(cx, |project, cx| {
+        let worktree_id = project.worktrees().next().unwrap().read(cx).id();
+        let project_path = |path: &str| ProjectPath {
+            worktree_id,
+            path: Path::new(path).into(),
+        };
+        let len = buffer.read(cx).len();
+        let links = project
+            .detect_links(&buffer, 0..len, cx)
+            .into_iter()
+            .map(|link| {
+                let text = buffer
+                    .read(cx)
+                    .text_for_range(link.range)
+                    .collect::<String>();
+                (text, link.target)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            [
+                (
+                    "lib.rs:1".to_string(),
+                    LinkTarget::File(project_path("src/lib.rs"), Point::new(0, 0))
+                ),
+                (
+                    "src/main.rs:2:3".to_string(),
+                    LinkTarget::File(project_path("src/main.rs"), Point::new(1, 2))
+                ),
+                (
+                    "https://zed.dev".to_string(),
+                    LinkTarget::Url("https://zed.dev".to_string())
+                ),
+            ]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_completions_without_edit_ranges(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2733,36 +3138,640 @@ async fn test_save_file(cx: &mut gpui::TestAppContext) {
     assert_eq!(new_text, buffer.update(cx, |buffer, _| buffer.text()));
 }
 
-#[gpui::test(iterations = 30)]
-async fn test_file_changes_multiple_times_on_disk(cx: &mut gpui::TestAppContext) {
+#[gpui::test(iterations = 10)]
+async fn test_concurrent_saves(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
-    let fs = FakeFs::new(cx.executor().clone());
-    fs.insert_tree(
-        "/dir",
-        json!({
-            "file1": "the original contents",
-        }),
-    )
-    .await;
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "file1": "" })).await;
 
     let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
-    let worktree = project.read_with(cx, |project, _| project.worktrees().next().unwrap());
     let buffer = project
         .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
         .await
         .unwrap();
 
-    // Simulate buffer diffs being slow, so that they don't complete before
-    // the next file change occurs.
-    cx.executor().deprioritize(*language::BUFFER_DIFF_TASK);
-
-    // Change the buffer's file on disk, and then wait for the file change
-    // to be detected by the worktree, so that the buffer starts reloading.
+    // Saves that are written together, in whichever order they complete, leave the buffer
+    // saved at its latest version.
+    let saves = (0..3)
+        .map(|ix| {
+            buffer.update(cx, |buffer, cx| {
+                buffer.edit([(0..0, format!("{ix}\n"))], None, cx)
+            });
+            project.update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        })
+        .collect::<Vec<_>>();
+    for save in saves {
+        save.await.unwrap();
+    }
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.saved_version(), &buffer.version());
+        assert!(!buffer.is_dirty());
+    });
+    assert_eq!(
+        fs.load(Path::new("/dir/file1")).await.unwrap(),
+        buffer.read_with(cx, |buffer, _| buffer.text())
+    );
+}
+
+#[gpui::test]
+async fn test_local_history(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "file1": "one\n" })).await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .await
+        .unwrap();
+
+    let save = |text: &str, cx: &mut gpui::TestAppContext| {
+        buffer.update(cx, |buffer, cx| buffer.set_text(text, cx));
+        project.update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+    };
+    save("one\n", cx).await.unwrap();
+    save("two\n", cx).await.unwrap();
+    // Saving unchanged contents doesn't record another version.
+    save("two\n", cx).await.unwrap();
+    cx.executor().run_until_parked();
+
+    let versions = project
+        .update(cx, |project, cx| {
+            project.local_history_versions(PathBuf::from("/dir/file1"), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(versions.len(), 2);
+    let contents = project
+        .update(cx, |project, cx| {
+            project.load_local_history_version(versions[0].clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(contents, "two\n");
+
+    buffer.update(cx, |buffer, cx| buffer.set_text("three\n", cx));
+    let diff = project
+        .update(cx, |project, cx| {
+            project.diff_local_history_version(&buffer, versions[1].clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert!(diff.contains("-one\n"), "{diff}");
+    assert!(diff.contains("+three\n"), "{diff}");
+
+    // Versions beyond the limit are removed, oldest first.
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.local_history.max_versions_per_file = Some(1);
+            });
+        })
+    });
+    save("four\n", cx).await.unwrap();
+    cx.executor().run_until_parked();
+    let versions = project
+        .update(cx, |project, cx| {
+            project.local_history_versions(PathBuf::from("/dir/file1"), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(versions.len(), 1);
+    let contents = project
+        .update(cx, |project, cx| {
+            project.load_local_history_version(versions[0].clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(contents, "four\n");
+
+    // Private files aren't recorded.
+    fs.insert_file("/dir/.env", b"SECRET=one".to_vec()).await;
+    let private_buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/.env", cx))
+        .await
+        .unwrap();
+    private_buffer.update(cx, |buffer, cx| buffer.set_text("SECRET=two", cx));
+    project
+        .update(cx, |project, cx| {
+            project.save_buffer(private_buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    let versions = project
+        .update(cx, |project, cx| {
+            project.local_history_versions(PathBuf::from("/dir/.env"), cx)
+        })
+        .await
+        .unwrap();
+    assert!(versions.is_empty());
+
+    // All projects share the same history.
+    let other_project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let versions = other_project
+        .update(cx, |project, cx| {
+            project.local_history_versions(PathBuf::from("/dir/file1"), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(versions.len(), 1);
+}
+
+#[gpui::test]
+async fn test_save_hooks(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "file1": "one  \ntwo" }))
+        .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .await
+        .unwrap();
+
+    let set_save_hooks = |save_hooks: SaveHooks, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |settings, cx| {
+                settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                    settings.defaults.save_hooks = Some(save_hooks);
+                });
+            })
+        });
+    };
+    let hook = |kind: SaveHookKind, on_failure: SaveHookFailurePolicy| SaveHook {
+        kind,
+        timeout_ms: None,
+        on_failure,
+    };
+    let format = |cx: &mut gpui::TestAppContext| {
+        project.update(cx, |project, cx| {
+            project.format(
+                HashSet::from_iter([buffer.clone()]),
+                true,
+                FormatTrigger::Save,
+                cx,
+            )
+        })
+    };
+
+    // Only the configured hooks run before saving.
+    set_save_hooks(
+        SaveHooks {
+            pre_save: Some(vec![hook(
+                SaveHookKind::EnsureFinalNewline,
+                SaveHookFailurePolicy::Abort,
+            )]),
+            post_save: Vec::new(),
+        },
+        cx,
+    );
+    format(cx).await.unwrap();
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "one  \ntwo\n"
+    );
+
+    // A failing hook doesn't fail the save, since the buffer was written, but is reported.
+    // Unless its failure is ignored, it stops the remaining hooks.
+    set_save_hooks(
+        SaveHooks {
+            pre_save: None,
+            post_save: vec![hook(SaveHookKind::Format, SaveHookFailurePolicy::Abort)],
+        },
+        cx,
+    );
+    let mut events = cx.events(&project);
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.load("/dir/file1".as_ref()).await.unwrap(),
+        "one  \ntwo\n"
+    );
+    cx.run_until_parked();
+    let mut notifications = Vec::new();
+    while let Ok(Some(event)) = events.try_next() {
+        if let Event::Notification(message) = event {
+            notifications.push(message);
+        }
+    }
+    assert_eq!(notifications.len(), 1, "{notifications:?}");
+    assert!(
+        notifications[0].contains("save hook failed"),
+        "{notifications:?}"
+    );
+    set_save_hooks(
+        SaveHooks {
+            pre_save: None,
+            post_save: vec![
+                hook(SaveHookKind::Format, SaveHookFailurePolicy::Continue),
+                hook(SaveHookKind::RefreshGitStatus, SaveHookFailurePolicy::Abort),
+            ],
+        },
+        cx,
+    );
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+
+    // Without configured hooks, the ones derived from the on-save settings run.
+    format(cx).await.unwrap();
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "one\ntwo\n"
+    );
+}
+
+#[gpui::test]
+async fn test_project_driver(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.txt": "one two",
+            "b": { "c.txt": "three" },
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), [], cx).await;
+
+    let mut driver = project_driver::ProjectDriver::new(project.clone(), cx.to_async());
+    let worktree = driver.open_worktree("/dir").await.unwrap();
+    worktree.read_with(cx, |worktree, _| {
+        assert!(worktree.entry_for_path("b/c.txt").is_some());
+    });
+    driver.scan_complete().await.unwrap();
+
+    let buffer = driver.open_buffer("/dir/a.txt").await.unwrap();
+    driver.edit(&buffer, [(3..7, " four")]).unwrap();
+    assert_eq!(driver.text(&buffer).unwrap(), "one four");
+    driver.save(&buffer).await.unwrap();
+    assert_eq!(fs.load(Path::new("/dir/a.txt")).await.unwrap(), "one four");
+    buffer.read_with(cx, |buffer, _| assert!(!buffer.is_dirty()));
+
+    assert!(driver.open_buffer("/other/d.txt").await.is_err());
+}
+
+#[gpui::test]
+async fn test_job_scheduler(cx: &mut gpui::TestAppContext) {
+    use job_scheduler::{JobEvent, JobGroup, JobPriority, JobScheduler};
+
+    init_test(cx);
+
+    let scheduler = cx.new_model(JobScheduler::new);
+    scheduler.update(cx, |scheduler, cx| scheduler.set_max_running_jobs(1, cx));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    cx.update(|cx| {
+        let events = events.clone();
+        cx.subscribe(&scheduler, move |_, event, _| events.lock().push(*event))
+            .detach();
+    });
+
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let (unblock_tx, unblock_rx) = futures::channel::oneshot::channel::<()>();
+    let schedule = |name: &'static str, priority, group, cx: &mut gpui::TestAppContext| {
+        let started = started.clone();
+        scheduler.update(cx, |scheduler, cx| {
+            scheduler.schedule(
+                name,
+                priority,
+                group,
+                move |_| async move {
+                    started.lock().push(name);
+                    name
+                },
+                cx,
+            )
+        })
+    };
+
+    // The first job occupies the only slot until it's unblocked.
+    let blocking = scheduler.update(cx, |scheduler, cx| {
+        scheduler.schedule(
+            "blocking",
+            JobPriority::Scan,
+            JobGroup::Project,
+            move |reporter| async move {
+                reporter.report("waiting", Some(0.5));
+                unblock_rx.await.ok();
+            },
+            cx,
+        )
+    });
+    let index = schedule("index", JobPriority::Index, JobGroup::Project, cx);
+    let save = schedule("save", JobPriority::Save, JobGroup::Project, cx);
+    let scan = schedule(
+        "scan",
+        JobPriority::Scan,
+        JobGroup::Worktree(WorktreeId::from_usize(1)),
+        cx,
+    );
+    let interactive = schedule(
+        "interactive",
+        JobPriority::Interactive,
+        JobGroup::Project,
+        cx,
+    );
+    cx.executor().run_until_parked();
+
+    let blocking_id = match events.lock().as_slice() {
+        [JobEvent::Progress(id)] => *id,
+        events => panic!("unexpected events {events:?}"),
+    };
+    scheduler.read_with(cx, |scheduler, _| {
+        assert_eq!(scheduler.running_job_count(), 1);
+        assert_eq!(scheduler.queued_job_count(), 4);
+        let progress = scheduler.job_progress(blocking_id).unwrap();
+        assert_eq!(progress.message.as_deref(), Some("waiting"));
+        assert_eq!(progress.fraction, Some(0.5));
+    });
+
+    // Cancelling a group drops its queued jobs.
+    scheduler.update(cx, |scheduler, cx| {
+        scheduler.cancel_group(JobGroup::Worktree(WorktreeId::from_usize(1)), cx)
+    });
+    assert!(scan.await.is_err());
+
+    unblock_tx.send(()).unwrap();
+    blocking.await.unwrap();
+    assert_eq!(interactive.await.unwrap(), "interactive");
+    assert_eq!(save.await.unwrap(), "save");
+    assert_eq!(index.await.unwrap(), "index");
+    assert_eq!(*started.lock(), ["interactive", "save", "index"]);
+    cx.executor().run_until_parked();
+    assert_eq!(
+        events
+            .lock()
+            .iter()
+            .filter(|event| matches!(event, JobEvent::Finished(_)))
+            .count(),
+        4
+    );
+    scheduler.read_with(cx, |scheduler, _| {
+        assert_eq!(scheduler.running_job_count(), 0);
+        assert_eq!(scheduler.queued_job_count(), 0);
+    });
+}
+
+#[gpui::test]
+async fn test_directory_sizes(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a": {
+                "b.txt": "12345",
+                "c": { "d.txt": "123", "e.txt": "1" },
+            },
+            "f.txt": "1",
+            ".gitignore": "out",
+            "out": { "g.txt": "1" },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+    let project_path = |path: &str| ProjectPath {
+        worktree_id,
+        path: Path::new(path).into(),
+    };
+
+    let size = project
+        .update(cx, |project, cx| {
+            project.directory_size(project_path("a"), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(size, 9);
+
+    let largest_paths = project
+        .update(cx, |project, cx| {
+            project.largest_paths(project_path(""), 3, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        largest_paths,
+        vec![
+            PathSize {
+                path: project_path("a"),
+                size: 9,
+                is_dir: true,
+            },
+            PathSize {
+                path: project_path("a/b.txt"),
+                size: 5,
+                is_dir: false,
+            },
+            PathSize {
+                path: project_path("a/c"),
+                size: 4,
+                is_dir: true,
+            },
+        ]
+    );
+    project.read_with(cx, |project, _| {
+        assert_eq!(project.cached_directory_size(&project_path("")), Some(14));
+        assert_eq!(project.cached_directory_size(&project_path("a/c")), Some(4));
+    });
+
+    // Changes within a directory invalidate its size, and the sizes of its ancestors.
+    fs.insert_file("/dir/a/c/h.txt", b"12".to_vec()).await;
+    cx.executor().run_until_parked();
+    project.read_with(cx, |project, _| {
+        assert_eq!(project.cached_directory_size(&project_path("")), None);
+        assert_eq!(project.cached_directory_size(&project_path("a")), None);
+        assert_eq!(project.cached_directory_size(&project_path("a/c")), None);
+        assert_eq!(project.cached_directory_size(&project_path("out")), Some(1));
+    });
+    let size = project
+        .update(cx, |project, cx| {
+            project.directory_size(project_path("a"), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(size, 11);
+
+    // The worktree doesn't report changes within ignored directories, but they invalidate
+    // sizes all the same.
+    fs.insert_file("/dir/out/i.txt", b"12".to_vec()).await;
+    cx.executor().run_until_parked();
+    project.read_with(cx, |project, _| {
+        assert_eq!(project.cached_directory_size(&project_path("out")), None);
+        assert_eq!(project.cached_directory_size(&project_path("a")), Some(11));
+    });
+    let size = project
+        .update(cx, |project, cx| {
+            project.directory_size(project_path("out"), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(size, 3);
+}
+
+#[gpui::test]
+async fn test_syntax_index(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.syntax_index.enabled = Some(true);
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn one() {}\nfn two() {}\n",
+            "b.rs": "fn three() {}\n",
+            "c.txt": "fn four() {}\n",
+        }),
+    )
+    .await;
+
+    let rust_language = |name: &str, path_suffix: &str| {
+        Arc::new(
+            Language::new(
+                LanguageConfig {
+                    name: name.into(),
+                    matcher: LanguageMatcher {
+                        path_suffixes: vec![path_suffix.to_string()],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                Some(tree_sitter_rust::language()),
+            )
+            .with_outline_query(r#"(function_item "fn" @context name: (_) @name) @item"#)
+            .unwrap(),
+        )
+    };
+    let project = Project::test(fs.clone(), [], cx).await;
+    project.read_with(cx, |project, _| {
+        project.languages().add(rust_language("Rust", "rs"))
+    });
+    project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree("/dir", true, cx)
+        })
+        .await
+        .unwrap();
+
+    let symbol_names = |query: &'static str, cx: &mut gpui::TestAppContext| {
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.executor().run_until_parked();
+        let symbols = project.update(cx, |project, cx| project.symbols(query, cx));
+        cx.executor().run_until_parked();
+        let mut names = cx
+            .executor()
+            .block(symbols)
+            .unwrap()
+            .into_iter()
+            .map(|symbol| (symbol.path.path.to_string_lossy().to_string(), symbol.name))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    // Files are parsed without language servers, and files without a language are skipped.
+    assert_eq!(
+        symbol_names("o", cx),
+        [
+            ("a.rs".to_string(), "one".to_string()),
+            ("a.rs".to_string(), "two".to_string()),
+        ]
+    );
+    assert_eq!(
+        symbol_names("tre", cx),
+        [("b.rs".to_string(), "three".to_string())]
+    );
+
+    // Files are parsed again when they change, and forgotten when they're removed.
+    fs.save(
+        "/dir/a.rs".as_ref(),
+        &"fn five() {}\n".into(),
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    fs.remove_file("/dir/b.rs".as_ref(), Default::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        symbol_names("", cx),
+        [("a.rs".to_string(), "five".to_string())]
+    );
+
+    // Indexed symbols are opened without a language server.
+    let symbols = project.update(cx, |project, cx| project.symbols("five", cx));
+    cx.executor().run_until_parked();
+    let symbol = cx.executor().block(symbols).unwrap().remove(0);
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer_for_symbol(&symbol, cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| assert_eq!(buffer.text(), "fn five() {}\n"));
+
+    // Files are indexed once a language for them is known.
+    project.read_with(cx, |project, _| {
+        project.languages().add(rust_language("Rust Text", "txt"))
+    });
+    assert_eq!(
+        symbol_names("four", cx),
+        [("c.txt".to_string(), "four".to_string())]
+    );
+}
+
+#[gpui::test(iterations = 30)]
+async fn test_file_changes_multiple_times_on_disk(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor().clone());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "file1": "the original contents",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let worktree = project.read_with(cx, |project, _| project.worktrees().next().unwrap());
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .await
+        .unwrap();
+
+    // Simulate buffer diffs being slow, so that they don't complete before
+    // the next file change occurs.
+    cx.executor().deprioritize(*language::BUFFER_DIFF_TASK);
+
+    // Change the buffer's file on disk, and then wait for the file change
+    // to be detected by the worktree, so that the buffer starts reloading.
     fs.save(
         "/dir/file1".as_ref(),
         &"the first contents".into(),
         Default::default(),
+        Default::default(),
     )
     .await
     .unwrap();
@@ -2774,6 +3783,7 @@ async fn test_file_changes_multiple_times_on_disk(cx: &mut gpui::TestAppContext)
         "/dir/file1".as_ref(),
         &"the second contents".into(),
         Default::default(),
+        Default::default(),
     )
     .await
     .unwrap();
@@ -2818,6 +3828,7 @@ async fn test_edit_buffer_while_it_reloads(cx: &mut gpui::TestAppContext) {
         "/dir/file1".as_ref(),
         &"the first contents".into(),
         Default::default(),
+        Default::default(),
     )
     .await
     .unwrap();
@@ -3135,6 +4146,111 @@ async fn test_buffer_identity_across_renames(cx: &mut gpui::TestAppContext) {
     buffer.update(cx, |buffer, _| assert!(!buffer.is_dirty()));
 }
 
+#[gpui::test]
+async fn test_rename_entry_keeps_buffer_files(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a": {
+                "file1": "one",
+            },
+            "b": {},
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [Path::new("/dir")], cx).await;
+    let tree = project.update(cx, |project, _| project.worktrees().next().unwrap());
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a/file1", cx)
+        })
+        .await
+        .unwrap();
+    let file_id = tree.read_with(cx, |tree, _| tree.entry_for_path("a/file1").unwrap().id);
+
+    let entry = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .rename_entry(file_id, Path::new("b/moved"), cx)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.id, file_id);
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("a/file1").is_none());
+        assert_eq!(tree.entry_for_path("b/moved").unwrap().id, file_id);
+    });
+
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        let file = buffer.file().unwrap();
+        assert_eq!(file.path().as_ref(), Path::new("b/moved"));
+        assert!(!file.is_deleted());
+        assert!(!buffer.is_dirty());
+    });
+}
+
+#[gpui::test]
+async fn test_trashing_entry_marks_buffer_files_deleted(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a": {
+                "b": {
+                    "file1": "one",
+                },
+                "file2": "two",
+            },
+            "file3": "three",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [Path::new("/dir")], cx).await;
+    let tree = project.update(cx, |project, _| project.worktrees().next().unwrap());
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a/b/file1", cx)
+        })
+        .await
+        .unwrap();
+    let dir_id = tree.read_with(cx, |tree, _| tree.entry_for_path("a").unwrap().id);
+
+    tree.update(cx, |tree, cx| {
+        tree.as_local_mut()
+            .unwrap()
+            .delete_entry(dir_id, true, cx)
+            .unwrap()
+    })
+    .await
+    .unwrap();
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            [Path::new(""), Path::new("file3")]
+        );
+    });
+    assert!(!fs.is_file("/dir/a/b/file1".as_ref()).await);
+
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        let file = buffer.file().unwrap();
+        assert_eq!(file.path().as_ref(), Path::new("a/b/file1"));
+        assert!(file.is_deleted());
+    });
+}
+
 #[gpui::test]
 async fn test_buffer_deduping(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -3340,6 +4456,16 @@ async fn test_buffer_file_changes_on_disk(cx: &mut gpui::TestAppContext) {
         .update(cx, |p, cx| p.open_local_buffer("/dir/the-file", cx))
         .await
         .unwrap();
+    let changed_buffers = Arc::new(Mutex::new(Vec::new()));
+    project.update(cx, |_, cx| {
+        let changed_buffers = changed_buffers.clone();
+        cx.subscribe(&project, move |_, _, event, _| {
+            if let Event::BufferChangedOnDisk(buffer) = event {
+                changed_buffers.lock().push(buffer.clone());
+            }
+        })
+        .detach();
+    });
 
     let anchors = (0..3)
         .map(|row| buffer.update(cx, |b, _| b.anchor_before(Point::new(row, 1))))
@@ -3356,6 +4482,7 @@ async fn test_buffer_file_changes_on_disk(cx: &mut gpui::TestAppContext) {
         "/dir/the-file".as_ref(),
         &new_contents.into(),
         LineEnding::Unix,
+        Default::default(),
     )
     .await
     .unwrap();
@@ -3378,6 +4505,7 @@ async fn test_buffer_file_changes_on_disk(cx: &mut gpui::TestAppContext) {
             [Point::new(1, 1), Point::new(3, 1), Point::new(3, 5)]
         );
     });
+    assert!(changed_buffers.lock().is_empty());
 
     // Modify the buffer
     buffer.update(cx, |buffer, cx| {
@@ -3391,16 +4519,77 @@ async fn test_buffer_file_changes_on_disk(cx: &mut gpui::TestAppContext) {
         "/dir/the-file".as_ref(),
         &"\n\n\nAAAA\naaa\nBB\nbbbbb\n".into(),
         LineEnding::Unix,
+        Default::default(),
     )
     .await
     .unwrap();
 
     // Because the buffer is modified, it doesn't reload from disk, but is
-    // marked as having a conflict.
+    // marked as having a conflict, and reported so that it can be reloaded.
     cx.executor().run_until_parked();
     buffer.update(cx, |buffer, _| {
         assert!(buffer.has_conflict());
     });
+    assert_eq!(*changed_buffers.lock(), [buffer.clone()]);
+}
+
+#[gpui::test]
+async fn test_save_conflicts_with_unseen_changes_on_disk(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "file": "one" })).await;
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file", cx))
+        .await
+        .unwrap();
+
+    // Changes on disk aren't noticed until their events are delivered.
+    fs.pause_events();
+
+    // Rewriting the same contents on disk isn't a conflict.
+    buffer.update(cx, |buffer, cx| buffer.set_text("two", cx));
+    fs.save(
+        "/dir/file".as_ref(),
+        &"one".into(),
+        LineEnding::Unix,
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    assert_eq!(fs.load("/dir/file".as_ref()).await.unwrap(), "two");
+
+    // Other contents aren't overwritten.
+    buffer.update(cx, |buffer, cx| buffer.set_text("three", cx));
+    fs.save(
+        "/dir/file".as_ref(),
+        &"external".into(),
+        LineEnding::Unix,
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let error = project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap_err();
+    assert!(error.downcast_ref::<SaveConflict>().is_some());
+    assert_eq!(fs.load("/dir/file".as_ref()).await.unwrap(), "external");
+
+    // Once the buffer is told about the change, it can be saved over it.
+    fs.flush_events(fs.buffered_event_count());
+    cx.executor().run_until_parked();
+    assert!(buffer.read_with(cx, |buffer, _| buffer.has_conflict()));
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    assert_eq!(fs.load("/dir/file".as_ref()).await.unwrap(), "three");
 }
 
 #[gpui::test]
@@ -3442,6 +4631,7 @@ async fn test_buffer_line_endings(cx: &mut gpui::TestAppContext) {
         "/dir/file1".as_ref(),
         &"aaa\nb\nc\n".into(),
         LineEnding::Windows,
+        Default::default(),
     )
     .await
     .unwrap();
@@ -3817,26 +5007,336 @@ async fn test_rename(cx: &mut gpui::TestAppContext) {
                 ..Default::default()
             }))
         })
-        .next()
+        .next()
+        .await
+        .unwrap();
+    let mut transaction = response.await.unwrap().0;
+    assert_eq!(transaction.len(), 2);
+    assert_eq!(
+        transaction
+            .remove_entry(&buffer)
+            .unwrap()
+            .0
+            .update(cx, |buffer, _| buffer.text()),
+        "const THREE: usize = 1;"
+    );
+    assert_eq!(
+        transaction
+            .into_keys()
+            .next()
+            .unwrap()
+            .update(cx, |buffer, _| buffer.text()),
+        "const TWO: usize = one::THREE + one::THREE;"
+    );
+}
+
+#[gpui::test]
+async fn test_renaming_files_notifies_language_servers(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "one.rs": "const ONE: usize = 1;",
+            "two.rs": "const TWO: usize = one::ONE + one::ONE;"
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let rust_files = lsp::FileOperationRegistrationOptions {
+        filters: vec![lsp::FileOperationFilter {
+            scheme: Some("file".to_string()),
+            pattern: lsp::FileOperationPattern {
+                glob: "**/*.rs".to_string(),
+                matches: Some(lsp::FileOperationPatternKind::File),
+                options: None,
+            },
+        }],
+    };
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                workspace: Some(lsp::WorkspaceServerCapabilities {
+                    workspace_folders: None,
+                    file_operations: Some(lsp::WorkspaceFileOperationsServerCapabilities {
+                        will_rename: Some(rust_files.clone()),
+                        did_rename: Some(rust_files),
+                        ..Default::default()
+                    }),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let _buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/one.rs", cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    let mut will_rename =
+        fake_server.handle_request::<lsp::request::WillRenameFiles, _, _>(|params, _| async move {
+            assert_eq!(
+                params.files,
+                [lsp::FileRename {
+                    old_uri: "file:///dir/one.rs".to_string(),
+                    new_uri: "file:///dir/uno.rs".to_string(),
+                }]
+            );
+            Ok(Some(lsp::WorkspaceEdit {
+                changes: Some(
+                    [(
+                        lsp::Url::from_file_path("/dir/two.rs").unwrap(),
+                        vec![
+                            lsp::TextEdit::new(
+                                lsp::Range::new(
+                                    lsp::Position::new(0, 19),
+                                    lsp::Position::new(0, 22),
+                                ),
+                                "uno".to_string(),
+                            ),
+                            lsp::TextEdit::new(
+                                lsp::Range::new(
+                                    lsp::Position::new(0, 30),
+                                    lsp::Position::new(0, 33),
+                                ),
+                                "uno".to_string(),
+                            ),
+                        ],
+                    )]
+                    .into_iter()
+                    .collect(),
+                ),
+                ..Default::default()
+            }))
+        });
+
+    let entry_id = project.read_with(cx, |project, cx| {
+        let worktree_id = project.worktrees().next().unwrap().read(cx).id();
+        project
+            .entry_for_path(&(worktree_id, "one.rs").into(), cx)
+            .unwrap()
+            .id
+    });
+    project
+        .update(cx, |project, cx| {
+            project.rename_entry(entry_id, Path::new("uno.rs"), cx)
+        })
+        .await
+        .unwrap();
+    will_rename.next().await.unwrap();
+
+    let did_rename = fake_server
+        .receive_notification::<lsp::notification::DidRenameFiles>()
+        .await;
+    assert_eq!(
+        did_rename.files,
+        [lsp::FileRename {
+            old_uri: "file:///dir/one.rs".to_string(),
+            new_uri: "file:///dir/uno.rs".to_string(),
+        }]
+    );
+    assert!(fs.is_file(Path::new("/dir/uno.rs")).await);
+
+    let two_buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/two.rs", cx)
+        })
+        .await
+        .unwrap();
+    two_buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "const TWO: usize = uno::ONE + uno::ONE;");
+        assert!(buffer.is_dirty());
+    });
+}
+
+#[gpui::test]
+async fn test_debug_session_breakpoints_and_stepping(cx: &mut gpui::TestAppContext) {
+    use dap::protocol::*;
+
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "main.rs": "fn main() {\n    let a = 1;\n    let b = 2;\n}\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/main.rs", cx)
+        })
+        .await
+        .unwrap();
+
+    project.update(cx, |project, cx| {
+        project.toggle_breakpoint(&buffer, 1, cx);
+        project.toggle_breakpoint(&buffer, 2, cx);
+        project.toggle_breakpoint(&buffer, 2, cx);
+    });
+
+    // Breakpoints move along with the edits of their buffer.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(0..0, "// main\n")], None, cx)
+    });
+    assert_eq!(
+        project.read_with(cx, |project, cx| project.breakpoint_rows(buffer.read(cx))),
+        vec![2]
+    );
+
+    let config = serde_json::from_value::<dap::DebugConfig>(json!({
+        "label": "Debug main",
+        "adapter": { "command": "fake-dap" },
+        "program": "target/debug/main",
+    }))
+    .unwrap();
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+    let (session, adapter) = project.update(cx, |project, cx| {
+        project.add_fake_debug_session(worktree_id, config, cx)
+    });
+
+    adapter.handle_request::<Initialize, _>(|arguments| {
+        assert_eq!(arguments.adapter_id, "fake-dap");
+        Ok(Capabilities {
+            supports_configuration_done_request: Some(true),
+            ..Default::default()
+        })
+    });
+    adapter.handle_request::<Launch, _>(|arguments| {
+        assert_eq!(arguments["program"], "target/debug/main");
+        Ok(())
+    });
+    adapter.handle_request::<SetBreakpoints, _>(|arguments| {
+        assert_eq!(arguments.source.path.as_deref(), Some("/dir/main.rs"));
+        assert_eq!(
+            arguments.breakpoints,
+            vec![SourceBreakpoint {
+                line: 3,
+                condition: None,
+            }]
+        );
+        Ok(SetBreakpointsResponse {
+            breakpoints: Vec::new(),
+        })
+    });
+    adapter.handle_request::<ConfigurationDone, _>(|()| Ok(()));
+    adapter.handle_request::<Threads, _>(|()| {
+        Ok(ThreadsResponse {
+            threads: vec![Thread {
+                id: 1,
+                name: "main".into(),
+            }],
+        })
+    });
+    adapter.handle_request::<StackTrace, _>(|arguments| {
+        assert_eq!(arguments.thread_id, 1);
+        Ok(StackTraceResponse {
+            stack_frames: vec![StackFrame {
+                id: 10,
+                name: "main".into(),
+                source: Some(Source {
+                    path: Some("/dir/main.rs".into()),
+                    ..Default::default()
+                }),
+                line: 3,
+                column: 5,
+            }],
+            total_frames: Some(1),
+        })
+    });
+    adapter.handle_request::<Scopes, _>(|arguments| {
+        assert_eq!(arguments.frame_id, 10);
+        Ok(ScopesResponse {
+            scopes: vec![Scope {
+                name: "Locals".into(),
+                variables_reference: 100,
+                expensive: false,
+            }],
+        })
+    });
+    adapter.handle_request::<Variables, _>(|arguments| {
+        assert_eq!(arguments.variables_reference, 100);
+        Ok(VariablesResponse {
+            variables: vec![Variable {
+                name: "a".into(),
+                value: "1".into(),
+                type_: Some("i32".into()),
+                variables_reference: 0,
+            }],
+        })
+    });
+    adapter.handle_request::<Next, _>(|arguments| {
+        assert_eq!(arguments.thread_id, 1);
+        Ok(())
+    });
+
+    let start = project.update(cx, |project, cx| {
+        project.start_fake_debug_session(&session, cx)
+    });
+    adapter.send_event::<Initialized>(None);
+    start.await.unwrap();
+    assert_eq!(
+        session.read_with(cx, |session, _| session.status().clone()),
+        DebugSessionStatus::Running
+    );
+
+    adapter.send_event::<Stopped>(StoppedEventBody {
+        reason: "breakpoint".into(),
+        description: None,
+        thread_id: Some(1),
+        all_threads_stopped: Some(true),
+    });
+    cx.run_until_parked();
+    session.read_with(cx, |session, _| {
+        assert_eq!(
+            session.status(),
+            &DebugSessionStatus::Stopped {
+                thread_id: 1,
+                reason: "breakpoint".into(),
+            }
+        );
+        assert_eq!(session.stack_frames()[0].line, 3);
+        assert_eq!(session.scopes(10).unwrap()[0].name, "Locals");
+        assert_eq!(session.variables(100).unwrap()[0].value, "1");
+    });
+
+    session
+        .update(cx, |session, cx| session.step_over(cx))
         .await
         .unwrap();
-    let mut transaction = response.await.unwrap().0;
-    assert_eq!(transaction.len(), 2);
     assert_eq!(
-        transaction
-            .remove_entry(&buffer)
-            .unwrap()
-            .0
-            .update(cx, |buffer, _| buffer.text()),
-        "const THREE: usize = 1;"
+        session.read_with(cx, |session, _| session.status().clone()),
+        DebugSessionStatus::Running
     );
     assert_eq!(
-        transaction
-            .into_keys()
-            .next()
-            .unwrap()
-            .update(cx, |buffer, _| buffer.text()),
-        "const TWO: usize = one::THREE + one::THREE;"
+        adapter.received_commands(),
+        [
+            "initialize",
+            "launch",
+            "setBreakpoints",
+            "configurationDone",
+            "threads",
+            "stackTrace",
+            "scopes",
+            "variables",
+            "next",
+        ]
     );
 }
 
@@ -4509,6 +6009,174 @@ async fn test_create_entry(cx: &mut gpui::TestAppContext) {
     assert!(result.is_err())
 }
 
+#[gpui::test]
+async fn test_completions_from_multiple_language_servers(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.tsx": "",
+        }),
+    )
+    .await;
+
+    // The secondary server is preferred for completions.
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.languages.insert(
+                    Arc::from("tsx"),
+                    LanguageSettingsContent {
+                        language_servers: Some(vec!["ESLintServer".into(), "...".into()]),
+                        ..Default::default()
+                    },
+                );
+            });
+        })
+    });
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(tsx_lang());
+    let capabilities = lsp::ServerCapabilities {
+        completion_provider: Some(lsp::CompletionOptions::default()),
+        ..lsp::ServerCapabilities::default()
+    };
+    let mut fake_tsx_language_servers = language_registry.register_specific_fake_lsp_adapter(
+        "tsx",
+        true,
+        FakeLspAdapter {
+            name: "TypeScriptServer",
+            capabilities: capabilities.clone(),
+            ..FakeLspAdapter::default()
+        },
+    );
+    let _eslint = language_registry.register_specific_fake_lsp_adapter(
+        "tsx",
+        false,
+        FakeLspAdapter {
+            name: "ESLintServer",
+            capabilities,
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.tsx", cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    let mut completion_requests = Vec::new();
+    for _ in 0..2 {
+        let server = fake_tsx_language_servers.next().await.unwrap();
+        let labels: &[&str] = match server.server.name() {
+            "TypeScriptServer" => &["shared", "typescript"],
+            "ESLintServer" => &["shared", "eslint"],
+            unexpected => panic!("Unexpected server name: {unexpected}"),
+        };
+        completion_requests.push(server.handle_request::<lsp::request::Completion, _, _>(
+            move |_, _| async move {
+                Ok(Some(lsp::CompletionResponse::Array(
+                    labels
+                        .iter()
+                        .map(|label| lsp::CompletionItem {
+                            label: label.to_string(),
+                            ..Default::default()
+                        })
+                        .collect(),
+                )))
+            },
+        ));
+    }
+
+    let completions = project.update(cx, |project, cx| project.completions(&buffer, 0, cx));
+    for mut request in completion_requests {
+        request.next().await.unwrap();
+    }
+    assert_eq!(
+        completions
+            .await
+            .unwrap()
+            .iter()
+            .map(|completion| completion.label.text.as_str())
+            .collect::<Vec<_>>(),
+        ["shared", "eslint", "typescript"],
+        "Duplicate completions should be taken from the preferred server"
+    );
+}
+
+#[gpui::test]
+async fn test_language_server_defined_in_settings(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.lsp.insert(
+                    "my-server".into(),
+                    LspSettings {
+                        binary: Some(BinarySettings {
+                            path: Some("my-server".into()),
+                            arguments: Some(vec!["--stdio".into()]),
+                            env: Some(
+                                [
+                                    ("ZED_FAKE_TEST_ENV".to_string(), "false".to_string()),
+                                    ("MY_SERVER_LOG".to_string(), "debug".to_string()),
+                                ]
+                                .into_iter()
+                                .collect(),
+                            ),
+                        }),
+                        ..Default::default()
+                    },
+                );
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "" })).await;
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let (adapter, delegate) = project.update(cx, |project, cx| {
+        // Servers without a binary in the settings aren't defined by them.
+        assert!(
+            SettingsLspAdapter::from_settings(&LanguageServerName("other-server".into()), cx)
+                .is_none()
+        );
+
+        let adapter =
+            SettingsLspAdapter::from_settings(&LanguageServerName("my-server".into()), cx).unwrap();
+        let worktree = project.worktrees().next().unwrap();
+        (
+            adapter,
+            ProjectLspAdapterDelegate::new(project, &worktree, cx),
+        )
+    });
+    let binary = adapter
+        .check_if_user_installed(delegate.as_ref(), &cx.to_async())
+        .await
+        .unwrap();
+    assert_eq!(binary.path, PathBuf::from("my-server"));
+    assert_eq!(binary.arguments, [OsString::from("--stdio")]);
+    // The configured variables are added to the login shell's, replacing the ones it sets.
+    assert_eq!(
+        binary.env,
+        Some(
+            [
+                ("ZED_FAKE_TEST_ENV".to_string(), "false".to_string()),
+                ("MY_SERVER_LOG".to_string(), "debug".to_string()),
+            ]
+            .into_iter()
+            .collect()
+        )
+    );
+}
+
 #[gpui::test]
 async fn test_multiple_language_server_hovers(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -4733,6 +6401,108 @@ async fn test_hovers_with_empty_parts(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_hover_fallback_documentation(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.docsets = Some(vec![PathBuf::from("/docs/std.json")]);
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.ts": "first second third first",
+        }),
+    )
+    .await;
+    fs.insert_tree(
+        "/docs",
+        json!({
+            "std.json": r#"{"index": {"0:1": {"name": "second", "docs": "The second word."}}}"#,
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(typescript_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
+        "TypeScript",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                hover_provider: Some(lsp::HoverProviderCapability::Simple(true)),
+                definition_provider: Some(lsp::OneOf::Left(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.ts", cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    let fake_server = fake_language_servers.next().await.unwrap();
+
+    // The last "first" is a different symbol, defined where "third" is.
+    fake_server.handle_request::<lsp::request::GotoDefinition, _, _>(|params, _| async move {
+        let params = params.text_document_position_params;
+        let definition_column = match params.position.character {
+            0..=5 => 0,
+            19..=24 => 13,
+            _ => return Ok(None),
+        };
+        Ok(Some(lsp::GotoDefinitionResponse::Scalar(
+            lsp::Location::new(
+                params.text_document.uri,
+                lsp::Range::new(
+                    lsp::Position::new(0, definition_column),
+                    lsp::Position::new(0, definition_column + 5),
+                ),
+            ),
+        )))
+    });
+
+    let hover_texts = |column: u32, cx: &mut gpui::TestAppContext| {
+        let hover_task = project.update(cx, |project, cx| {
+            project.hover(&buffer, Point::new(0, column), cx)
+        });
+        cx.executor().run_until_parked();
+        cx.executor()
+            .block(hover_task)
+            .into_iter()
+            .flat_map(|hover| hover.contents)
+            .map(|block| block.text)
+            .collect::<Vec<_>>()
+    };
+
+    fake_server.handle_request::<lsp::request::HoverRequest, _, _>(move |_, _| async move {
+        Ok(Some(lsp::Hover {
+            contents: lsp::HoverContents::Scalar(lsp::MarkedString::String(
+                "The first word.".to_string(),
+            )),
+            range: None,
+        }))
+    });
+    assert_eq!(hover_texts(0, cx), ["The first word."]);
+
+    // When the server stops documenting a symbol, its last documentation is shown, or else
+    // its documentation in the language's docsets.
+    fake_server
+        .handle_request::<lsp::request::HoverRequest, _, _>(move |_, _| async move { Ok(None) });
+    assert_eq!(hover_texts(0, cx), ["The first word."]);
+    assert_eq!(hover_texts(7, cx), ["The second word."]);
+    assert_eq!(hover_texts(14, cx), Vec::<String>::new());
+    assert_eq!(hover_texts(19, cx), Vec::<String>::new());
+}
+
 #[gpui::test]
 async fn test_multiple_language_server_actions(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -5086,6 +6856,60 @@ async fn test_reordering_worktrees(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_entries_across_worktrees(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "one": { "main.rs": "fn main() {}" },
+            "two": { "main.rs": "fn main() {}" },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/root/two".as_ref(), "/root/one".as_ref()], cx).await;
+    project.update(cx, |project, cx| {
+        let worktrees = project.visible_worktrees(cx).collect::<Vec<_>>();
+        let root_names = worktrees
+            .iter()
+            .map(|worktree| worktree.read(cx).root_name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(root_names, ["one", "two"]);
+
+        let entry_ids = worktrees
+            .iter()
+            .map(|worktree| worktree.read(cx).entry_for_path("main.rs").unwrap().id)
+            .collect::<Vec<_>>();
+        assert_ne!(entry_ids[0], entry_ids[1]);
+        for (worktree, entry_id) in worktrees.iter().zip(entry_ids) {
+            assert_eq!(
+                project.worktree_for_entry(entry_id, cx).as_ref(),
+                Some(worktree)
+            );
+            assert_eq!(
+                project.worktree_id_for_entry(entry_id, cx),
+                Some(worktree.read(cx).id())
+            );
+        }
+    });
+
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+    project.update(cx, |project, cx| project.remove_worktree(worktree_id, cx));
+    project.update(cx, |project, cx| {
+        assert!(project.worktree_for_id(worktree_id, cx).is_none());
+        let root_names = project
+            .visible_worktrees(cx)
+            .map(|worktree| worktree.read(cx).root_name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(root_names, ["two"]);
+    });
+}
+
 async fn search(
     project: &Model<Project>,
     query: SearchQuery,
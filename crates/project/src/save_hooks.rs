@@ -0,0 +1,208 @@
+//! The steps that run when saving a buffer, as configured by the `save_hooks` language
+//! setting. Hooks that run before the buffer is written are run by [`Project::format`], since
+//! formatting is one of them, while the others run once the buffer was written.
+//!
+//! [`Project::format`]: crate::Project::format
+
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use collections::HashMap;
+use gpui::{AppContext, AsyncAppContext, BackgroundExecutor, Model, ModelContext};
+use language::{
+    language_settings::{language_settings, SaveHook, SaveHookFailurePolicy, SaveHookKind},
+    Buffer,
+};
+use postage::stream::Stream as _;
+use smol::{future::FutureExt as _, io::AsyncWriteExt as _};
+use text::TransactionId;
+use worktree::File;
+
+/// Runs the hooks that are configured to run after a buffer was written.
+pub(crate) async fn run_post_save_hooks(
+    buffer: &Model<Buffer>,
+    environment: &HashMap<String, String>,
+    cx: &mut AsyncAppContext,
+) -> Result<()> {
+    let (hooks, buffer_abs_path) = buffer.update(cx, |buffer, cx| {
+        let hooks = language_settings(buffer.language(), buffer.file(), cx)
+            .save_hooks
+            .post_save
+            .clone();
+        let buffer_abs_path = File::from_dyn(buffer.file())
+            .and_then(|file| file.as_local())
+            .map(|file| file.abs_path(cx));
+        (hooks, buffer_abs_path)
+    })?;
+    let Some(buffer_abs_path) = buffer_abs_path else {
+        return Ok(());
+    };
+
+    let executor = cx.background_executor().clone();
+    for hook in hooks {
+        let run_hook = async {
+            match &hook.kind {
+                SaveHookKind::Command { command, arguments } => {
+                    run_command(
+                        buffer,
+                        &buffer_abs_path,
+                        command,
+                        arguments,
+                        environment,
+                        cx,
+                    )
+                    .await
+                }
+                SaveHookKind::RefreshGitStatus => refresh_git_status(buffer, cx).await,
+                kind => Err(anyhow!(
+                    "{kind:?} edits the buffer, so it can only run before saving"
+                )),
+            }
+        };
+        check_result(&hook, run_with_timeout(&hook, &executor, run_hook).await)?;
+    }
+    Ok(())
+}
+
+/// Runs a hook, failing if it doesn't complete within the hook's timeout.
+pub(crate) async fn run_with_timeout<T>(
+    hook: &SaveHook,
+    executor: &BackgroundExecutor,
+    run_hook: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let Some(timeout) = hook.timeout() else {
+        return run_hook.await;
+    };
+    let timer = async move {
+        executor.timer(timeout).await;
+        Err(anyhow!("timed out after {timeout:?}"))
+    };
+    run_hook.or(timer).await
+}
+
+/// Applies a hook's failure policy to its result, returning an error if no more hooks
+/// should run.
+pub(crate) fn check_result(hook: &SaveHook, result: Result<()>) -> Result<()> {
+    let Err(error) = result else {
+        return Ok(());
+    };
+    let error = error.context(format!("{:?} save hook failed", hook.kind));
+    match hook.on_failure {
+        SaveHookFailurePolicy::Abort => Err(error),
+        SaveHookFailurePolicy::Continue => {
+            log::warn!("{error:?}");
+            Ok(())
+        }
+    }
+}
+
+/// Applies a hook's edits in a transaction, which is grouped with the earlier hooks' edits
+/// unless the buffer was edited in between.
+pub(crate) fn apply_edits(
+    buffer: &Model<Buffer>,
+    hooks_transaction_id: &mut Option<TransactionId>,
+    cx: &mut AsyncAppContext,
+    edit: impl FnOnce(&mut Buffer, &mut ModelContext<Buffer>),
+) -> Result<()> {
+    buffer.update(cx, |buffer, cx| {
+        let follows_hooks_transaction = hooks_transaction_id.is_some()
+            && buffer.peek_undo_stack().map(|entry| entry.transaction_id())
+                == *hooks_transaction_id;
+        buffer.finalize_last_transaction();
+        buffer.start_transaction();
+        edit(buffer, cx);
+        if let Some(transaction_id) = buffer.end_transaction(cx) {
+            match *hooks_transaction_id {
+                Some(hooks_transaction_id) if follows_hooks_transaction => {
+                    buffer.group_until_transaction(hooks_transaction_id)
+                }
+                _ => *hooks_transaction_id = Some(transaction_id),
+            }
+        }
+    })
+}
+
+/// Runs an external program for the buffer in the given environment, failing if it exits
+/// unsuccessfully. The buffer's contents are written to its standard input, since the file
+/// isn't written yet when the hook runs before saving.
+pub(crate) async fn run_command(
+    buffer: &Model<Buffer>,
+    buffer_abs_path: &Path,
+    command: &str,
+    arguments: &[String],
+    environment: &HashMap<String, String>,
+    cx: &mut AsyncAppContext,
+) -> Result<()> {
+    let Some(working_dir_path) =
+        buffer.read_with(cx, |buffer, cx| command_working_dir(buffer, cx))?
+    else {
+        return Ok(());
+    };
+    let mut child = smol::process::Command::new(command)
+        .args(
+            arguments
+                .iter()
+                .map(|arg| arg.replace("{buffer_path}", &buffer_abs_path.to_string_lossy())),
+        )
+        .current_dir(&working_dir_path)
+        .envs(environment)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to run {command:?}"))?;
+    let mut stdin = child.stdin.take().context("failed to acquire stdin")?;
+    let text = buffer.read_with(cx, |buffer, _| buffer.as_rope().clone())?;
+    // Programs that don't read their input may exit before it was all written, which isn't
+    // an error.
+    let write_stdin = async move {
+        for chunk in text.chunks() {
+            stdin.write_all(chunk.as_bytes()).await?;
+        }
+        stdin.flush().await
+    };
+    let (_, output) = futures::join!(write_stdin, child.output());
+    let output = output.with_context(|| format!("failed to run {command:?}"))?;
+    if !output.status.success() {
+        bail!(
+            "command failed with exit code {:?}:\nstdout: {}\nstderr: {}",
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+    }
+    Ok(())
+}
+
+/// Rescans the buffer's file, which also reloads its git status.
+pub(crate) async fn refresh_git_status(
+    buffer: &Model<Buffer>,
+    cx: &mut AsyncAppContext,
+) -> Result<()> {
+    let refresh = buffer.update(cx, |buffer, cx| {
+        let file = File::from_dyn(buffer.file())?;
+        let worktree = file.worktree.read(cx).as_local()?;
+        Some(worktree.refresh_entries_for_paths(vec![file.path.clone()]))
+    })?;
+    if let Some(mut refresh) = refresh {
+        refresh.recv().await;
+    }
+    Ok(())
+}
+
+/// The directory in which external commands run for a buffer: the root of its worktree, or
+/// the worktree's parent directory when the worktree is a single file.
+pub(crate) fn command_working_dir(buffer: &Buffer, cx: &AppContext) -> Option<PathBuf> {
+    let file = File::from_dyn(buffer.file())?;
+    let worktree = file.worktree.read(cx).as_local()?;
+    let mut worktree_path = worktree.abs_path().to_path_buf();
+    if worktree.root_entry()?.is_file() {
+        worktree_path.pop();
+    }
+    Some(worktree_path)
+}
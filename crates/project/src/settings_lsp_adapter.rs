@@ -0,0 +1,114 @@
+//! Language servers that aren't provided by Zed or an extension, but are defined in the `lsp`
+//! settings by giving them a binary to run. Like any other language server, they're enabled
+//! for a language by listing their name in its `language_servers` setting.
+
+use std::{any::Any, ffi::OsStr, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use gpui::{AppContext, AsyncAppContext};
+use language::{LanguageServerName, LspAdapter, LspAdapterDelegate};
+use lsp::LanguageServerBinary;
+use settings::Settings;
+
+use crate::project_settings::ProjectSettings;
+
+pub(crate) struct SettingsLspAdapter {
+    name: LanguageServerName,
+}
+
+impl SettingsLspAdapter {
+    /// Returns an adapter for the language server with the given name, if the settings define
+    /// the binary to run for it.
+    pub(crate) fn from_settings(name: &LanguageServerName, cx: &AppContext) -> Option<Self> {
+        ProjectSettings::get_global(cx)
+            .lsp
+            .get(&name.0)?
+            .binary
+            .as_ref()?
+            .path
+            .as_ref()?;
+        Some(Self { name: name.clone() })
+    }
+}
+
+#[async_trait(?Send)]
+impl LspAdapter for SettingsLspAdapter {
+    fn name(&self) -> LanguageServerName {
+        self.name.clone()
+    }
+
+    async fn check_if_user_installed(
+        &self,
+        delegate: &dyn LspAdapterDelegate,
+        cx: &AsyncAppContext,
+    ) -> Option<LanguageServerBinary> {
+        let binary = cx
+            .update(|cx| {
+                ProjectSettings::get_global(cx)
+                    .lsp
+                    .get(&self.name.0)
+                    .and_then(|settings| settings.binary.clone())
+            })
+            .ok()??;
+
+        let configured_path = binary.path?;
+        // Commands that aren't paths are looked up in the login shell's `PATH`, since servers
+        // are usually installed by toolchain managers that Zed's own environment doesn't see.
+        let path = match delegate.which(OsStr::new(&configured_path)).await {
+            Some(path) => path,
+            None => PathBuf::from(configured_path),
+        };
+        let mut env = delegate.shell_env().await;
+        env.extend(binary.env.unwrap_or_default());
+
+        Some(LanguageServerBinary {
+            path,
+            arguments: binary
+                .arguments
+                .unwrap_or_default()
+                .iter()
+                .map(|arg| arg.into())
+                .collect(),
+            env: Some(env),
+        })
+    }
+
+    async fn fetch_latest_server_version(
+        &self,
+        _: &dyn LspAdapterDelegate,
+    ) -> Result<Box<dyn 'static + Send + Any>> {
+        Err(anyhow!(
+            "no binary is configured for language server {}",
+            self.name.0
+        ))
+    }
+
+    async fn fetch_server_binary(
+        &self,
+        _: Box<dyn 'static + Send + Any>,
+        _: PathBuf,
+        _: &dyn LspAdapterDelegate,
+    ) -> Result<LanguageServerBinary> {
+        Err(anyhow!(
+            "language server {} can't be downloaded",
+            self.name.0
+        ))
+    }
+
+    async fn cached_server_binary(
+        &self,
+        _: PathBuf,
+        _: &dyn LspAdapterDelegate,
+    ) -> Option<LanguageServerBinary> {
+        None
+    }
+
+    fn can_be_reinstalled(&self) -> bool {
+        false
+    }
+
+    async fn installation_test_binary(&self, _: PathBuf) -> Option<LanguageServerBinary> {
+        None
+    }
+}
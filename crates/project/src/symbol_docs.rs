@@ -0,0 +1,205 @@
+//! Documentation for the symbols that language servers don't document. The hovers that servers
+//! return are remembered by the location where the symbol they describe is defined, and the
+//! documentation of completions by the completion, so that they're shown again when a server
+//! returns nothing for them, e.g. while it's still indexing. Symbols that were never described
+//! are looked up by name in the docsets configured for the language: local JSON files of
+//! documentation, such as rustdoc's output or a dump of MDN.
+
+use std::{
+    collections::VecDeque,
+    hash::Hash,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use fs::Fs;
+use gpui::BackgroundExecutor;
+use language::{Documentation, PointUtf16};
+use parking_lot::Mutex;
+use serde_json::Value;
+use util::{paths::HOME, ResultExt};
+
+use crate::{HoverBlock, HoverBlockKind};
+
+/// How many symbols' and completions' documentation is remembered, per project.
+const MAX_CACHED_SYMBOLS: usize = 1024;
+
+/// Where a symbol is defined: the full path of its file, and the start of its definition.
+pub(crate) type SymbolLocation = (PathBuf, PointUtf16);
+
+/// The completions of a language, by their label and detail, which tell apart the symbols of
+/// the same name.
+pub(crate) type CompletionKey = (Arc<str>, String, Option<String>);
+
+#[derive(Default)]
+pub(crate) struct SymbolDocs {
+    state: Mutex<SymbolDocsState>,
+}
+
+#[derive(Default)]
+struct SymbolDocsState {
+    hovers: Cache<SymbolLocation, Vec<HoverBlock>>,
+    completions: Cache<CompletionKey, Documentation>,
+    /// The loaded docsets, or `None` for the ones that failed to load, so that the failure
+    /// isn't repeated for every lookup.
+    docsets: HashMap<PathBuf, Option<Arc<Docset>>>,
+}
+
+struct Cache<K, V> {
+    entries: HashMap<K, V>,
+    /// The keys of the entries, least recently cached first.
+    order: VecDeque<K>,
+}
+
+impl<K, V> Default for Cache<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::default(),
+            order: VecDeque::new(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Cache<K, V> {
+    fn insert(&mut self, key: K, value: V) {
+        if self.entries.insert(key.clone(), value).is_some() {
+            self.order.retain(|cached_key| cached_key != &key);
+        }
+        self.order.push_back(key);
+        while self.order.len() > MAX_CACHED_SYMBOLS {
+            if let Some(evicted_key) = self.order.pop_front() {
+                self.entries.remove(&evicted_key);
+            }
+        }
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        self.entries.get(key).cloned()
+    }
+}
+
+/// The documentation of the names in a docset file.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Docset {
+    docs: HashMap<String, String>,
+}
+
+impl Docset {
+    /// Parses either rustdoc's JSON output, whose `index` lists items with their names and
+    /// docs, or an object whose keys are names and whose values are Markdown.
+    pub(crate) fn parse(json: &str) -> Result<Self> {
+        let value = serde_json::from_str::<Value>(json)?;
+        let mut docs = HashMap::default();
+        if let Some(index) = value.get("index").and_then(Value::as_object) {
+            for item in index.values() {
+                let name = item.get("name").and_then(Value::as_str);
+                let doc = item.get("docs").and_then(Value::as_str);
+                if let Some((name, doc)) = name.zip(doc).filter(|(_, doc)| !doc.is_empty()) {
+                    docs.entry(name.to_string())
+                        .or_insert_with(|| doc.to_string());
+                }
+            }
+        } else {
+            let names = value
+                .as_object()
+                .context("a docset must be a JSON object")?;
+            for (name, doc) in names {
+                if let Some(doc) = doc.as_str() {
+                    docs.insert(name.clone(), doc.to_string());
+                }
+            }
+        }
+        Ok(Self { docs })
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&str> {
+        self.docs.get(name).map(String::as_str)
+    }
+}
+
+impl SymbolDocs {
+    /// Remembers the hover that a language server gave for the symbol defined at a location.
+    pub(crate) fn cache_hover(&self, location: SymbolLocation, blocks: Vec<HoverBlock>) {
+        self.state.lock().hovers.insert(location, blocks);
+    }
+
+    /// Returns the hover that was last given for the symbol defined at a location.
+    pub(crate) fn cached_hover(&self, location: &SymbolLocation) -> Option<Vec<HoverBlock>> {
+        self.state.lock().hovers.get(location)
+    }
+
+    /// Remembers the documentation that a language server gave for a completion.
+    pub(crate) fn cache_completion(&self, key: CompletionKey, documentation: Documentation) {
+        self.state.lock().completions.insert(key, documentation);
+    }
+
+    /// Returns the documentation that was last given for a completion.
+    pub(crate) fn cached_completion(&self, key: &CompletionKey) -> Option<Documentation> {
+        self.state.lock().completions.get(key)
+    }
+
+    /// Returns the documentation of the name in the first of the docsets that has any.
+    pub(crate) async fn lookup_docsets(
+        &self,
+        name: &str,
+        docset_paths: &[PathBuf],
+        fs: &dyn Fs,
+        executor: &BackgroundExecutor,
+    ) -> Option<Vec<HoverBlock>> {
+        for path in docset_paths {
+            let Some(docset) = self.docset(path, fs, executor).await else {
+                continue;
+            };
+            if let Some(doc) = docset.get(name) {
+                return Some(vec![HoverBlock {
+                    text: doc.to_string(),
+                    kind: HoverBlockKind::Markdown,
+                }]);
+            }
+        }
+        None
+    }
+
+    /// Loads a docset, unless it was already loaded. Docsets aren't reloaded when they change.
+    async fn docset(
+        &self,
+        path: &Path,
+        fs: &dyn Fs,
+        executor: &BackgroundExecutor,
+    ) -> Option<Arc<Docset>> {
+        let path = match path.strip_prefix("~") {
+            Ok(relative_path) => HOME.join(relative_path),
+            Err(_) => path.to_path_buf(),
+        };
+        if let Some(docset) = self.state.lock().docsets.get(&path) {
+            return docset.clone();
+        }
+
+        let docset = async {
+            let json = fs.load(&path).await?;
+            executor
+                .spawn(async move { Docset::parse(&json) })
+                .await
+                .with_context(|| format!("parsing docset {path:?}"))
+        }
+        .await
+        .log_err()
+        .map(Arc::new);
+        self.state.lock().docsets.insert(path, docset.clone());
+        docset
+    }
+}
+
+/// Joins the blocks of a hover into a single Markdown document.
+pub(crate) fn hover_blocks_to_markdown(blocks: &[HoverBlock]) -> String {
+    blocks
+        .iter()
+        .map(|block| match &block.kind {
+            HoverBlockKind::PlainText | HoverBlockKind::Markdown => block.text.clone(),
+            HoverBlockKind::Code { language } => format!("```{language}\n{}\n```", block.text),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
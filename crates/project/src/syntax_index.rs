@@ -0,0 +1,178 @@
+//! An index of the symbols declared in the files of local worktrees, found by parsing the files
+//! with their languages' outline queries instead of asking language servers. When it's enabled,
+//! files are parsed one at a time in the background, at the lowest priority, so that project
+//! symbols can be searched before language servers are ready, or in languages without one.
+//! Files are parsed again whenever they change on disk.
+
+use std::{collections::VecDeque, ops::Range, path::Path, sync::Arc};
+
+use collections::{HashMap, HashSet};
+use language::{Buffer, Language, LanguageRegistry, PointUtf16, ToPointUtf16};
+use worktree::Entry;
+
+use crate::{ProjectPath, WorktreeId};
+
+/// The name given instead of a language server's to the symbols found by the index.
+pub(crate) const SERVER_NAME: &str = "syntax-index";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct IndexedSymbol {
+    pub name: String,
+    pub kind: lsp::SymbolKind,
+    pub range: Range<PointUtf16>,
+}
+
+#[derive(Default)]
+pub(crate) struct SyntaxIndex {
+    symbols: HashMap<WorktreeId, HashMap<Arc<Path>, Vec<IndexedSymbol>>>,
+    queue: VecDeque<ProjectPath>,
+    queued: HashSet<ProjectPath>,
+}
+
+impl SyntaxIndex {
+    /// Queues files to be parsed, after the ones that are already queued.
+    pub(crate) fn enqueue(&mut self, paths: impl IntoIterator<Item = ProjectPath>) {
+        for path in paths {
+            if self.queued.insert(path.clone()) {
+                self.queue.push_back(path);
+            }
+        }
+    }
+
+    pub(crate) fn is_indexed(&self, path: &ProjectPath) -> bool {
+        self.symbols
+            .get(&path.worktree_id)
+            .map_or(false, |symbols| symbols.contains_key(&path.path))
+    }
+
+    pub(crate) fn next_queued(&mut self) -> Option<ProjectPath> {
+        let path = self.queue.pop_front()?;
+        self.queued.remove(&path);
+        Some(path)
+    }
+
+    pub(crate) fn insert(&mut self, path: ProjectPath, symbols: Vec<IndexedSymbol>) {
+        self.symbols
+            .entry(path.worktree_id)
+            .or_default()
+            .insert(path.path, symbols);
+    }
+
+    pub(crate) fn remove(&mut self, path: &ProjectPath) {
+        if let Some(symbols) = self.symbols.get_mut(&path.worktree_id) {
+            symbols.remove(&path.path);
+        }
+        if self.queued.remove(path) {
+            self.queue.retain(|queued_path| queued_path != path);
+        }
+    }
+
+    pub(crate) fn remove_worktree(&mut self, worktree_id: WorktreeId) {
+        self.symbols.remove(&worktree_id);
+        self.queued.retain(|path| path.worktree_id != worktree_id);
+        self.queue.retain(|path| path.worktree_id != worktree_id);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.symbols.clear();
+        self.queue.clear();
+        self.queued.clear();
+    }
+
+    /// Returns the indexed symbols whose names contain the characters of the query, in order
+    /// and ignoring case.
+    pub(crate) fn search<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> impl Iterator<Item = (ProjectPath, &'a IndexedSymbol)> + 'a {
+        self.symbols.iter().flat_map(move |(worktree_id, files)| {
+            files.iter().flat_map(move |(path, symbols)| {
+                symbols
+                    .iter()
+                    .filter(move |symbol| matches_query(&symbol.name, query))
+                    .map(move |symbol| {
+                        (
+                            ProjectPath {
+                                worktree_id: *worktree_id,
+                                path: path.clone(),
+                            },
+                            symbol,
+                        )
+                    })
+            })
+        })
+    }
+}
+
+/// Whether the file of the given entry should be indexed, which requires a language to parse
+/// it with.
+pub(crate) fn is_indexable(
+    entry: &Entry,
+    max_file_size: u64,
+    languages: &LanguageRegistry,
+) -> bool {
+    entry.is_file()
+        && !entry.is_ignored
+        && !entry.is_external
+        && entry.size <= max_file_size
+        && languages.has_language_for_file_path(&entry.path)
+}
+
+/// Parses the text of a file, and returns the symbols in its outline.
+pub(crate) fn symbols_for_text(
+    text: String,
+    language: Arc<Language>,
+    language_registry: Option<Arc<LanguageRegistry>>,
+) -> Vec<IndexedSymbol> {
+    let snapshot = Buffer::build_snapshot(text, language, language_registry);
+    let Some(outline) = snapshot.outline(None) else {
+        return Vec::new();
+    };
+    outline
+        .items
+        .into_iter()
+        .filter_map(|item| {
+            let name_start = item.name_ranges.first()?.start;
+            let name_end = item.name_ranges.last()?.end;
+            Some(IndexedSymbol {
+                name: item.text.get(name_start..name_end)?.to_string(),
+                kind: symbol_kind(&item.text[..name_start], item.depth),
+                range: item.range.start.to_point_utf16(&snapshot)
+                    ..item.range.end.to_point_utf16(&snapshot),
+            })
+        })
+        .collect()
+}
+
+/// Guesses the kind of a symbol from the keywords that precede its name in its outline item,
+/// since outlines don't tell the kinds of their items.
+fn symbol_kind(prefix: &str, depth: usize) -> lsp::SymbolKind {
+    for keyword in prefix.split_whitespace().rev() {
+        match keyword {
+            "fn" | "def" | "func" | "function" if depth > 0 => return lsp::SymbolKind::METHOD,
+            "fn" | "def" | "func" | "function" => return lsp::SymbolKind::FUNCTION,
+            "struct" => return lsp::SymbolKind::STRUCT,
+            "enum" => return lsp::SymbolKind::ENUM,
+            "trait" | "interface" | "protocol" => return lsp::SymbolKind::INTERFACE,
+            "class" => return lsp::SymbolKind::CLASS,
+            "impl" => return lsp::SymbolKind::OBJECT,
+            "mod" | "module" | "namespace" | "package" => return lsp::SymbolKind::MODULE,
+            "const" | "static" => return lsp::SymbolKind::CONSTANT,
+            "type" => return lsp::SymbolKind::TYPE_PARAMETER,
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        lsp::SymbolKind::FIELD
+    } else {
+        lsp::SymbolKind::VARIABLE
+    }
+}
+
+fn matches_query(name: &str, query: &str) -> bool {
+    let mut name_chars = name.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|query_char| name_chars.any(|name_char| name_char == query_char))
+}
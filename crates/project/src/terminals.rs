@@ -1,7 +1,8 @@
-use crate::Project;
+use crate::{Project, Worktree};
 use collections::HashMap;
 use gpui::{
-    AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, SharedString, WeakModel,
+    AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, SharedString, Task,
+    WeakModel,
 };
 use itertools::Itertools;
 use settings::{Settings, SettingsLocation};
@@ -70,19 +71,58 @@ impl Project {
         spawn_task: Option<SpawnInTerminal>,
         window: AnyWindowHandle,
         cx: &mut ModelContext<Self>,
+    ) -> Task<anyhow::Result<Model<Terminal>>> {
+        // Unlike terminals, which start a login shell, local tasks need to be given the
+        // worktree's environment to find the user's toolchains, waiting for it to be captured.
+        let worktree_environment = match (&working_directory, &spawn_task) {
+            (Some(TerminalWorkDir::Ssh { .. }), _) | (_, None) => None,
+            (_, Some(spawn_task)) => self
+                .terminal_worktree(working_directory.as_ref(), Some(spawn_task), cx)
+                .map(|(worktree, _)| self.worktree_environment(worktree.read(cx).id(), cx)),
+        };
+        cx.spawn(|this, mut cx| async move {
+            let worktree_environment = match worktree_environment {
+                Some(worktree_environment) => Some(worktree_environment.await),
+                None => None,
+            };
+            this.update(&mut cx, |this, cx| {
+                this.create_terminal_in_environment(
+                    working_directory,
+                    spawn_task,
+                    worktree_environment,
+                    window,
+                    cx,
+                )
+            })?
+        })
+    }
+
+    fn terminal_worktree(
+        &self,
+        working_directory: Option<&TerminalWorkDir>,
+        spawn_task: Option<&SpawnInTerminal>,
+        cx: &AppContext,
+    ) -> Option<(Model<Worktree>, PathBuf)> {
+        let terminal_cwd = working_directory.and_then(|cwd| cwd.local_path());
+        let task_cwd = spawn_task
+            .and_then(|spawn_task| spawn_task.cwd.as_ref())
+            .and_then(|cwd| cwd.local_path());
+
+        terminal_cwd
+            .and_then(|terminal_cwd| self.find_local_worktree(&terminal_cwd, cx))
+            .or_else(|| task_cwd.and_then(|spawn_cwd| self.find_local_worktree(&spawn_cwd, cx)))
+    }
+
+    fn create_terminal_in_environment(
+        &mut self,
+        working_directory: Option<TerminalWorkDir>,
+        spawn_task: Option<SpawnInTerminal>,
+        worktree_environment: Option<HashMap<String, String>>,
+        window: AnyWindowHandle,
+        cx: &mut ModelContext<Self>,
     ) -> anyhow::Result<Model<Terminal>> {
         // used only for TerminalSettings::get
-        let worktree = {
-            let terminal_cwd = working_directory.as_ref().and_then(|cwd| cwd.local_path());
-            let task_cwd = spawn_task
-                .as_ref()
-                .and_then(|spawn_task| spawn_task.cwd.as_ref())
-                .and_then(|cwd| cwd.local_path());
-
-            terminal_cwd
-                .and_then(|terminal_cwd| self.find_local_worktree(&terminal_cwd, cx))
-                .or_else(|| task_cwd.and_then(|spawn_cwd| self.find_local_worktree(&spawn_cwd, cx)))
-        };
+        let worktree = self.terminal_worktree(working_directory.as_ref(), spawn_task.as_ref(), cx);
 
         let settings_location = worktree.as_ref().map(|(worktree, path)| SettingsLocation {
             worktree_id: worktree.read(cx).id().to_usize(),
@@ -135,6 +175,10 @@ impl Project {
             _ => {
                 if let Some(spawn_task) = spawn_task {
                     log::debug!("Spawning task: {spawn_task:?}");
+                    if let Some(mut task_env) = worktree_environment {
+                        task_env.extend(env);
+                        env = task_env;
+                    }
                     env.extend(spawn_task.env);
                     // Activate minimal Python virtual environment
                     if let Some(python_settings) = &python_settings.as_option() {
@@ -268,7 +312,11 @@ impl Project {
 
             let path_bin = path.join("bin");
             // We need to set the PATH to include the virtual environment's bin directory
-            if let Some(paths) = std::env::var_os("PATH") {
+            let paths = env
+                .get("PATH")
+                .map(|paths| paths.into())
+                .or_else(|| std::env::var_os("PATH"));
+            if let Some(paths) = paths {
                 let paths = std::iter::once(path_bin).chain(std::env::split_paths(&paths));
                 if let Some(new_path) = std::env::join_paths(paths).log_err() {
                     env.insert("PATH".to_string(), new_path.to_string_lossy().to_string());
@@ -0,0 +1,206 @@
+//! The worktrees of a project, one for each folder or file that's been opened in it. Entry ids
+//! are unique across all of a project's worktrees, so an entry can be found without knowing
+//! which worktree it belongs to.
+
+use anyhow::{Context as _, Result};
+use gpui::{AppContext, Model, WeakModel};
+use worktree::{ProjectEntryId, Worktree, WorktreeId};
+
+/// Worktrees that aren't visible are only weakly held when the project isn't shared, so that
+/// they're dropped once nothing else uses them.
+#[derive(Clone)]
+enum WorktreeHandle {
+    Strong(Model<Worktree>),
+    Weak(WeakModel<Worktree>),
+}
+
+impl WorktreeHandle {
+    fn upgrade(&self) -> Option<Model<Worktree>> {
+        match self {
+            WorktreeHandle::Strong(handle) => Some(handle.clone()),
+            WorktreeHandle::Weak(handle) => handle.upgrade(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct WorktreeStore {
+    worktrees: Vec<WorktreeHandle>,
+    /// Whether the user has reordered the worktrees, in which case new worktrees are appended
+    /// instead of being sorted by their path.
+    worktrees_reordered: bool,
+}
+
+impl WorktreeStore {
+    pub(crate) fn set_worktrees_reordered(&mut self, worktrees_reordered: bool) {
+        self.worktrees_reordered = worktrees_reordered;
+    }
+
+    /// All the worktrees that are still alive, in the order they're shown.
+    pub(crate) fn worktrees(&self) -> impl '_ + DoubleEndedIterator<Item = Model<Worktree>> {
+        self.worktrees
+            .iter()
+            .filter_map(move |worktree| worktree.upgrade())
+    }
+
+    pub(crate) fn visible_worktrees<'a>(
+        &'a self,
+        cx: &'a AppContext,
+    ) -> impl 'a + DoubleEndedIterator<Item = Model<Worktree>> {
+        self.worktrees()
+            .filter(|worktree| worktree.read(cx).is_visible())
+    }
+
+    pub(crate) fn worktree_for_id(
+        &self,
+        id: WorktreeId,
+        cx: &AppContext,
+    ) -> Option<Model<Worktree>> {
+        self.worktrees()
+            .find(|worktree| worktree.read(cx).id() == id)
+    }
+
+    pub(crate) fn worktree_for_entry(
+        &self,
+        entry_id: ProjectEntryId,
+        cx: &AppContext,
+    ) -> Option<Model<Worktree>> {
+        self.worktrees()
+            .find(|worktree| worktree.read(cx).contains_entry(entry_id))
+    }
+
+    /// Adds a worktree, keeping the worktrees sorted by path unless they've been reordered.
+    pub(crate) fn add(&mut self, worktree: &Model<Worktree>, strong: bool, cx: &AppContext) {
+        let handle = if strong {
+            WorktreeHandle::Strong(worktree.clone())
+        } else {
+            WorktreeHandle::Weak(worktree.downgrade())
+        };
+        if self.worktrees_reordered {
+            self.worktrees.push(handle);
+        } else {
+            let i = match self
+                .worktrees
+                .binary_search_by_key(&Some(worktree.read(cx).abs_path()), |other| {
+                    other.upgrade().map(|worktree| worktree.read(cx).abs_path())
+                }) {
+                Ok(i) | Err(i) => i,
+            };
+            self.worktrees.insert(i, handle);
+        }
+    }
+
+    /// Removes the worktree with the given id, along with any worktrees that have been
+    /// released. Returns whether the worktree was found.
+    pub(crate) fn remove(&mut self, id_to_remove: WorktreeId, cx: &AppContext) -> bool {
+        let mut removed = false;
+        self.worktrees.retain(|worktree| {
+            if let Some(worktree) = worktree.upgrade() {
+                if worktree.read(cx).id() == id_to_remove {
+                    removed = true;
+                    false
+                } else {
+                    true
+                }
+            } else {
+                false
+            }
+        });
+        removed
+    }
+
+    /// Removes all the worktrees, returning the ones that are still alive.
+    pub(crate) fn take(&mut self) -> Vec<Model<Worktree>> {
+        self.worktrees
+            .drain(..)
+            .filter_map(|worktree| worktree.upgrade())
+            .collect()
+    }
+
+    /// Appends a worktree that's strongly held, regardless of the order of the others.
+    pub(crate) fn push_strong(&mut self, worktree: Model<Worktree>) {
+        self.worktrees.push(WorktreeHandle::Strong(worktree));
+    }
+
+    /// Moves the `source` worktree to the position of the `destination` worktree. Returns
+    /// whether the order changed.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the worktree or destination worktree are not found.
+    pub(crate) fn move_worktree(
+        &mut self,
+        source: WorktreeId,
+        destination: WorktreeId,
+        cx: &AppContext,
+    ) -> Result<bool> {
+        if source == destination {
+            return Ok(false);
+        }
+
+        let mut source_index = None;
+        let mut destination_index = None;
+        for (i, worktree) in self.worktrees.iter().enumerate() {
+            if let Some(worktree) = worktree.upgrade() {
+                let worktree_id = worktree.read(cx).id();
+                if worktree_id == source {
+                    source_index = Some(i);
+                    if destination_index.is_some() {
+                        break;
+                    }
+                } else if worktree_id == destination {
+                    destination_index = Some(i);
+                    if source_index.is_some() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let source_index =
+            source_index.with_context(|| format!("Missing worktree for id {source}"))?;
+        let destination_index =
+            destination_index.with_context(|| format!("Missing worktree for id {destination}"))?;
+
+        if source_index == destination_index {
+            return Ok(false);
+        }
+
+        let worktree_to_move = self.worktrees.remove(source_index);
+        self.worktrees.insert(destination_index, worktree_to_move);
+        self.worktrees_reordered = true;
+        Ok(true)
+    }
+
+    /// Holds every worktree strongly, so that collaborators can keep using hidden worktrees
+    /// while the project is shared.
+    pub(crate) fn retain_all(&mut self) {
+        for worktree_handle in self.worktrees.iter_mut() {
+            if let WorktreeHandle::Weak(worktree) = worktree_handle {
+                if let Some(worktree) = worktree.upgrade() {
+                    *worktree_handle = WorktreeHandle::Strong(worktree);
+                }
+            }
+        }
+    }
+
+    /// Goes back to holding hidden worktrees weakly, once the project is no longer shared.
+    pub(crate) fn release_hidden(&mut self, cx: &AppContext) {
+        for worktree_handle in self.worktrees.iter_mut() {
+            if let WorktreeHandle::Strong(worktree) = worktree_handle {
+                if !worktree.read(cx).is_visible() {
+                    *worktree_handle = WorktreeHandle::Weak(worktree.downgrade());
+                }
+            }
+        }
+    }
+
+    /// The entity ids of all the worktrees, including released ones, which key their local
+    /// settings.
+    pub(crate) fn handle_ids(&self) -> impl '_ + Iterator<Item = usize> {
+        self.worktrees.iter().map(|worktree| match worktree {
+            WorktreeHandle::Strong(handle) => handle.entity_id().as_u64() as usize,
+            WorktreeHandle::Weak(handle) => handle.entity_id().as_u64() as usize,
+        })
+    }
+}
@@ -0,0 +1,232 @@
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model, ParentElement,
+    Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use project::{PathSize, Project, ProjectPath};
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+use crate::format_size;
+
+/// How many of the largest files and directories are listed.
+const LARGEST_PATHS_LIMIT: usize = 100;
+
+/// Lists the largest files and directories within a directory, largest first, revealing the
+/// confirmed one in the project panel.
+pub struct LargestPaths {
+    picker: View<Picker<LargestPathsDelegate>>,
+    _measure_task: Task<()>,
+}
+
+impl LargestPaths {
+    pub fn new(
+        project: Model<Project>,
+        workspace: WeakView<Workspace>,
+        directory: ProjectPath,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = LargestPathsDelegate {
+            largest_paths: cx.view().downgrade(),
+            project: project.clone(),
+            workspace,
+            paths: None,
+            candidates: Vec::new(),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+
+        let measure = project.update(cx, |project, cx| {
+            project.largest_paths(directory, LARGEST_PATHS_LIMIT, cx)
+        });
+        let measure_task = cx.spawn(|this, mut cx| async move {
+            let paths = measure.await.log_err().unwrap_or_default();
+            this.update(&mut cx, |this, cx| {
+                this.picker.update(cx, |picker, cx| {
+                    picker.delegate.set_paths(paths);
+                    picker.refresh(cx);
+                })
+            })
+            .log_err();
+        });
+
+        Self {
+            picker,
+            _measure_task: measure_task,
+        }
+    }
+}
+
+impl Render for LargestPaths {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for LargestPaths {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for LargestPaths {}
+impl ModalView for LargestPaths {}
+
+pub struct LargestPathsDelegate {
+    largest_paths: WeakView<LargestPaths>,
+    project: Model<Project>,
+    workspace: WeakView<Workspace>,
+    /// The measured paths, or `None` while they're being measured.
+    paths: Option<Vec<PathSize>>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl LargestPathsDelegate {
+    fn set_paths(&mut self, paths: Vec<PathSize>) {
+        self.candidates = paths
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, path_size)| {
+                StringMatchCandidate::new(
+                    candidate_id,
+                    path_size.path.path.to_string_lossy().to_string(),
+                )
+            })
+            .collect();
+        self.paths = Some(paths);
+    }
+}
+
+impl PickerDelegate for LargestPathsDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Find in largest files and directories...".into()
+    }
+
+    fn no_matches_text(&self, _cx: &mut WindowContext) -> SharedString {
+        if self.paths.is_some() {
+            "No matches".into()
+        } else {
+            "Measuring sizes…".into()
+        }
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let path_size = self
+            .matches
+            .get(self.selected_index)
+            .zip(self.paths.as_ref())
+            .map(|(mat, paths)| paths[mat.candidate_id].clone());
+        if let Some(path_size) = path_size {
+            // Entries within ignored directories that weren't expanded aren't loaded, so only
+            // files can be opened instead.
+            let entry = self.project.read(cx).entry_for_path(&path_size.path, cx);
+            if let Some(entry) = entry {
+                self.project.update(cx, |_, cx| {
+                    cx.emit(project::Event::RevealInProjectPanel(entry.id))
+                });
+            } else if !path_size.is_dir {
+                self.workspace
+                    .update(cx, |workspace, cx| {
+                        workspace
+                            .open_path(path_size.path, None, true, cx)
+                            .detach_and_log_err(cx);
+                    })
+                    .log_err();
+            }
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.largest_paths
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let mut matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    LARGEST_PATHS_LIMIT,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+            // The paths are listed largest first, whatever the query.
+            matches.sort_by_key(|mat| mat.candidate_id);
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let path_size = self.paths.as_ref()?.get(mat.candidate_id)?;
+        let icon = if path_size.is_dir {
+            IconName::Folder
+        } else {
+            IconName::File
+        };
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .start_slot(Icon::new(icon).color(Color::Muted))
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                ))
+                .end_slot(Label::new(format_size(path_size.size)).color(Color::Muted)),
+        )
+    }
+}
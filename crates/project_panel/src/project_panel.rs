@@ -1,3 +1,4 @@
+mod largest_paths;
 mod project_panel_settings;
 use client::{ErrorCode, ErrorExt};
 use settings::{Settings, SettingsStore};
@@ -5,6 +6,7 @@ use settings::{Settings, SettingsStore};
 use db::kvp::KEY_VALUE_STORE;
 use editor::{actions::Cancel, items::entry_git_aware_label_color, scroll::Autoscroll, Editor};
 use file_icons::FileIcons;
+use largest_paths::LargestPaths;
 
 use anyhow::{anyhow, Result};
 use collections::{hash_map, HashMap};
@@ -17,7 +19,10 @@ use gpui::{
     UniformListScrollHandle, View, ViewContext, VisualContext as _, WeakView, WindowContext,
 };
 use menu::{Confirm, SelectFirst, SelectLast, SelectNext, SelectPrev};
-use project::{Entry, EntryKind, Fs, Project, ProjectEntryId, ProjectPath, Worktree, WorktreeId};
+use project::{
+    DirPathResolution, Entry, EntryKind, FileId, Fs, Project, ProjectEntryId, ProjectPath,
+    Worktree, WorktreeId,
+};
 use project_panel_settings::{ProjectPanelDockPosition, ProjectPanelSettings};
 use serde::{Deserialize, Serialize};
 use std::{
@@ -27,9 +32,10 @@ use std::{
     ops::Range,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 use theme::ThemeSettings;
-use ui::{prelude::*, v_flex, ContextMenu, Icon, KeyBinding, Label, ListItem};
+use ui::{prelude::*, v_flex, ContextMenu, Icon, KeyBinding, Label, ListItem, Tooltip};
 use unicase::UniCase;
 use util::{maybe, NumericPrefixWithSuffix, ResultExt, TryFutureExt};
 use workspace::{
@@ -39,6 +45,9 @@ use workspace::{
 };
 
 const PROJECT_PANEL_KEY: &str = "ProjectPanel";
+const EXPANDED_DIRS_KEY_PREFIX: &str = "ProjectPanelExpandedDirs";
+const EXPANDED_DIRS_SERIALIZATION_DEBOUNCE: Duration = Duration::from_millis(500);
+const REVEALED_PATHS_KEY_PREFIX: &str = "ProjectPanelRevealedIgnoredPaths";
 const NEW_ENTRY_ID: ProjectEntryId = ProjectEntryId::MAX;
 
 pub struct ProjectPanel {
@@ -59,6 +68,13 @@ pub struct ProjectPanel {
     workspace: WeakView<Workspace>,
     width: Option<Pixels>,
     pending_serialization: Task<Option<()>>,
+    /// Whether the directories that were expanded in each worktree in a previous session have
+    /// been loaded, keyed by worktree.
+    expanded_dirs_loaded: HashMap<WorktreeId, bool>,
+    /// Previously expanded directories that haven't been scanned yet.
+    pending_expanded_paths: HashMap<WorktreeId, Vec<Arc<Path>>>,
+    serialized_expanded_dirs: HashMap<Arc<Path>, Vec<Arc<Path>>>,
+    pending_expanded_dirs_serialization: Task<Option<()>>,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -132,6 +148,8 @@ actions!(
         CopyRelativePath,
         Duplicate,
         RevealInFinder,
+        OpenWithSystem,
+        CopyFileToClipboard,
         Cut,
         Paste,
         Rename,
@@ -142,6 +160,10 @@ actions!(
         UnfoldDirectory,
         FoldDirectory,
         SelectParent,
+        Refresh,
+        IncludeIgnoredEntry,
+        IgnoreEntryAgain,
+        ShowLargestPaths,
     ]
 );
 
@@ -207,9 +229,12 @@ impl ProjectPanel {
                 }
                 project::Event::WorktreeRemoved(id) => {
                     this.expanded_dir_ids.remove(id);
+                    this.expanded_dirs_loaded.remove(id);
+                    this.pending_expanded_paths.remove(id);
                     this.update_visible_entries(None, cx);
                     cx.notify();
                 }
+                project::Event::BuildTargetsChanged(_) => cx.notify(),
                 project::Event::WorktreeUpdatedEntries(_, _)
                 | project::Event::WorktreeAdded
                 | project::Event::WorktreeOrderChanged => {
@@ -274,6 +299,10 @@ impl ProjectPanel {
                 workspace: workspace.weak_handle(),
                 width: None,
                 pending_serialization: Task::ready(None),
+                expanded_dirs_loaded: Default::default(),
+                pending_expanded_paths: Default::default(),
+                serialized_expanded_dirs: Default::default(),
+                pending_expanded_dirs_serialization: Task::ready(None),
             };
             this.update_visible_entries(None, cx);
 
@@ -398,6 +427,198 @@ impl ProjectPanel {
         );
     }
 
+    /// Loads the directories that were expanded, and the ignored paths that were included, in
+    /// newly added local worktrees in a previous session.
+    fn load_expanded_dirs(&mut self, cx: &mut ViewContext<Self>) {
+        let worktrees_to_load = self
+            .project
+            .read(cx)
+            .visible_worktrees(cx)
+            .filter_map(|worktree| {
+                let worktree_id = worktree.read(cx).id();
+                (worktree.read(cx).is_local()
+                    && !self.expanded_dirs_loaded.contains_key(&worktree_id))
+                .then(|| (worktree_id, worktree))
+            })
+            .collect::<Vec<_>>();
+        for (worktree_id, worktree) in worktrees_to_load {
+            self.expanded_dirs_loaded.insert(worktree_id, false);
+            let abs_path = worktree.read(cx).abs_path();
+            cx.spawn(|this, mut cx| async move {
+                let (expanded_paths, revealed_paths) = cx
+                    .background_executor()
+                    .spawn(async move {
+                        let read_paths = |key: String| {
+                            KEY_VALUE_STORE
+                                .read_kvp(&key)
+                                .log_err()
+                                .flatten()
+                                .and_then(|paths| {
+                                    serde_json::from_str::<Vec<PathBuf>>(&paths).log_err()
+                                })
+                                .unwrap_or_default()
+                        };
+                        (
+                            read_paths(expanded_dirs_key(&abs_path)),
+                            read_paths(revealed_paths_key(&abs_path)),
+                        )
+                    })
+                    .await;
+
+                worktree
+                    .update(&mut cx, |worktree, _| {
+                        if let Some(worktree) = worktree.as_local_mut() {
+                            for path in revealed_paths {
+                                worktree.reveal_ignored_path(path.into());
+                            }
+                        }
+                    })
+                    .ok();
+                this.update(&mut cx, |this, cx| {
+                    this.restore_expanded_dirs(worktree_id, expanded_paths, cx)
+                })
+                .ok();
+            })
+            .detach();
+        }
+    }
+
+    /// Expands the directories at the given paths once they're scanned.
+    fn restore_expanded_dirs(
+        &mut self,
+        worktree_id: WorktreeId,
+        paths: Vec<PathBuf>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.expanded_dirs_loaded.insert(worktree_id, true);
+        if !paths.is_empty() {
+            self.pending_expanded_paths
+                .insert(worktree_id, paths.into_iter().map(Arc::from).collect());
+            self.update_visible_entries(None, cx);
+            cx.notify();
+        }
+    }
+
+    fn resolve_pending_expanded_paths(&mut self, cx: &mut ViewContext<Self>) {
+        let project = self.project.read(cx);
+        let mut entries_to_load = Vec::new();
+        for (worktree_id, paths) in &mut self.pending_expanded_paths {
+            let Some(worktree) = project.worktree_for_id(*worktree_id, cx) else {
+                paths.clear();
+                continue;
+            };
+            let snapshot = worktree.read(cx).snapshot();
+            paths.retain(|path| match snapshot.resolve_dir_path(path) {
+                DirPathResolution::Found(entry_id) => {
+                    let expanded_dir_ids = self.expanded_dir_ids.entry(*worktree_id).or_default();
+                    if let Err(ix) = expanded_dir_ids.binary_search(&entry_id) {
+                        expanded_dir_ids.insert(ix, entry_id);
+                    }
+                    if snapshot
+                        .entry_for_id(entry_id)
+                        .map_or(false, |entry| entry.kind == EntryKind::UnloadedDir)
+                    {
+                        entries_to_load.push((*worktree_id, entry_id));
+                    }
+                    false
+                }
+                DirPathResolution::Pending => true,
+                DirPathResolution::Missing => false,
+            });
+        }
+        self.pending_expanded_paths
+            .retain(|_, paths| !paths.is_empty());
+
+        if !entries_to_load.is_empty() {
+            self.project.update(cx, |project, cx| {
+                for (worktree_id, entry_id) in entries_to_load {
+                    project.expand_entry(worktree_id, entry_id, cx);
+                }
+            });
+        }
+    }
+
+    /// Persists the expanded directories of local worktrees by their relative paths, if they
+    /// changed since they were last persisted.
+    fn serialize_expanded_dirs(&mut self, cx: &mut ViewContext<Self>) {
+        let project = self.project.read(cx);
+        let mut changed = false;
+        let mut expanded_dirs = Vec::new();
+        for worktree in project.visible_worktrees(cx) {
+            let worktree = worktree.read(cx);
+            let worktree_id = worktree.id();
+            if self.expanded_dirs_loaded.get(&worktree_id) != Some(&true) {
+                continue;
+            }
+            let Some(expanded_dir_ids) = self.expanded_dir_ids.get(&worktree_id) else {
+                continue;
+            };
+
+            let mut paths = expanded_dir_ids
+                .iter()
+                .filter_map(|entry_id| Some(worktree.entry_for_id(*entry_id)?.path.clone()))
+                .chain(
+                    self.pending_expanded_paths
+                        .get(&worktree_id)
+                        .into_iter()
+                        .flatten()
+                        .cloned(),
+                )
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths.dedup();
+
+            let abs_path = worktree.abs_path();
+            if self.serialized_expanded_dirs.get(&abs_path) != Some(&paths) {
+                self.serialized_expanded_dirs
+                    .insert(abs_path.clone(), paths.clone());
+                changed = true;
+            }
+            expanded_dirs.push((expanded_dirs_key(&abs_path), paths));
+        }
+
+        if changed {
+            self.pending_expanded_dirs_serialization = cx.background_executor().spawn({
+                let executor = cx.background_executor().clone();
+                async move {
+                    executor.timer(EXPANDED_DIRS_SERIALIZATION_DEBOUNCE).await;
+                    for (key, paths) in expanded_dirs {
+                        KEY_VALUE_STORE
+                            .write_kvp(key, serde_json::to_string(&paths)?)
+                            .await?;
+                    }
+                    anyhow::Ok(())
+                }
+                .log_err()
+            });
+        }
+    }
+
+    /// Persists the ignored paths that were included in the worktree.
+    fn serialize_revealed_paths(&self, worktree: &Model<Worktree>, cx: &mut ViewContext<Self>) {
+        let worktree = worktree.read(cx);
+        let Some(local_worktree) = worktree.as_local() else {
+            return;
+        };
+        let mut paths = local_worktree
+            .revealed_ignored_paths()
+            .cloned()
+            .collect::<Vec<_>>();
+        paths.sort();
+        let key = revealed_paths_key(&worktree.abs_path());
+        cx.background_executor()
+            .spawn(
+                async move {
+                    KEY_VALUE_STORE
+                        .write_kvp(key, serde_json::to_string(&paths)?)
+                        .await?;
+                    anyhow::Ok(())
+                }
+                .log_err(),
+            )
+            .detach();
+    }
+
     fn focus_in(&mut self, cx: &mut ViewContext<Self>) {
         if !self.focus_handle.contains_focused(cx) {
             cx.emit(Event::Focus);
@@ -430,6 +651,10 @@ impl ProjectPanel {
             let is_dir = entry.is_dir();
             let is_foldable = auto_fold_dirs && self.is_foldable(entry, worktree);
             let is_unfoldable = auto_fold_dirs && self.is_unfoldable(entry, worktree);
+            let is_revealed = worktree
+                .as_local()
+                .map_or(false, |worktree| worktree.is_path_revealed(&entry.path));
+            let is_ignored = entry.is_ignored;
             let worktree_id = worktree.id();
             let is_local = project.is_local();
             let is_read_only = project.is_read_only();
@@ -450,6 +675,22 @@ impl ProjectPanel {
                             .separator()
                             .action("Reveal in Finder", Box::new(RevealInFinder))
                             .action("Open in Terminal", Box::new(OpenInTerminal))
+                            .when(is_local && !is_dir, |menu| {
+                                menu.action(
+                                    "Open with Default Application",
+                                    Box::new(OpenWithSystem),
+                                )
+                            })
+                            .when(is_local && is_dir, |menu| {
+                                menu.action("Refresh", Box::new(Refresh))
+                                    .action("Show Largest Files", Box::new(ShowLargestPaths))
+                            })
+                            .when(is_local && is_ignored, |menu| {
+                                menu.action("Include Ignored Entry", Box::new(IncludeIgnoredEntry))
+                            })
+                            .when(is_local && is_revealed, |menu| {
+                                menu.action("Ignore Entry Again", Box::new(IgnoreEntryAgain))
+                            })
                             .when(is_dir, |menu| {
                                 menu.separator()
                                     .action("Find in Folder…", Box::new(NewSearchInDirectory))
@@ -473,6 +714,9 @@ impl ProjectPanel {
                             .separator()
                             .action("Copy Path", Box::new(CopyPath))
                             .action("Copy Relative Path", Box::new(CopyRelativePath))
+                            .when(is_local && cx.can_write_files_to_clipboard(), |menu| {
+                                menu.action("Copy File", Box::new(CopyFileToClipboard))
+                            })
                             .separator()
                             .action("Rename", Box::new(Rename))
                             .when(!is_root, |menu| {
@@ -782,6 +1026,20 @@ impl ProjectPanel {
         allow_preview: bool,
         cx: &mut ViewContext<Self>,
     ) {
+        // Opening an ignored file shows that it's of interest, so it's included from then on.
+        let ignored_file = self
+            .project
+            .read(cx)
+            .worktree_for_entry(entry_id, cx)
+            .and_then(|worktree| {
+                let entry = worktree.read(cx).entry_for_id(entry_id)?;
+                (entry.is_file() && entry.is_ignored)
+                    .then(|| (worktree.clone(), entry.path.clone()))
+            });
+        if let Some((worktree, path)) = ignored_file {
+            self.set_path_revealed(worktree, path, true, cx);
+        }
+
         cx.emit(Event::OpenedEntry {
             entry_id,
             focus_opened_item,
@@ -1191,6 +1449,122 @@ impl ProjectPanel {
         }
     }
 
+    fn open_with_system(&mut self, _: &OpenWithSystem, cx: &mut ViewContext<Self>) {
+        if let Some((worktree, entry)) = self.selected_entry(cx) {
+            cx.open_with_system(&worktree.abs_path().join(&entry.path));
+        }
+    }
+
+    /// Copies the selected file or directory itself, rather than its path, to the system
+    /// clipboard, so that it can be pasted in the system's file manager.
+    fn copy_file_to_clipboard(&mut self, _: &CopyFileToClipboard, cx: &mut ViewContext<Self>) {
+        if let Some((worktree, entry)) = self.selected_entry(cx) {
+            cx.write_files_to_clipboard(&[worktree.abs_path().join(&entry.path)])
+                .log_err();
+        }
+    }
+
+    /// Rescans the selected directory from disk, or every worktree if nothing is selected.
+    fn refresh(&mut self, _: &Refresh, cx: &mut ViewContext<Self>) {
+        let worktrees = if let Some((worktree, entry)) = self.selected_entry_handle(cx) {
+            vec![(worktree, entry.path.clone())]
+        } else {
+            self.project
+                .read(cx)
+                .visible_worktrees(cx)
+                .map(|worktree| (worktree, Arc::from(Path::new(""))))
+                .collect()
+        };
+        for (worktree, path) in worktrees {
+            if let Some(worktree) = worktree.read(cx).as_local() {
+                worktree.rescan(path);
+            }
+        }
+    }
+
+    fn include_ignored_entry(&mut self, _: &IncludeIgnoredEntry, cx: &mut ViewContext<Self>) {
+        if let Some((worktree, entry)) = self.selected_entry_handle(cx) {
+            if entry.is_ignored {
+                let path = entry.path.clone();
+                self.set_path_revealed(worktree, path, true, cx);
+            }
+        }
+    }
+
+    fn ignore_entry_again(&mut self, _: &IgnoreEntryAgain, cx: &mut ViewContext<Self>) {
+        if let Some((worktree, entry)) = self.selected_entry_handle(cx) {
+            let path = entry.path.clone();
+            self.set_path_revealed(worktree, path, false, cx);
+        }
+    }
+
+    /// Includes an ignored path in the worktree's scans and searches, or ignores it again, and
+    /// remembers the choice for the next session.
+    fn set_path_revealed(
+        &mut self,
+        worktree: Model<Worktree>,
+        path: Arc<Path>,
+        revealed: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let changed = worktree.update(cx, |worktree, _| {
+            let worktree = worktree.as_local_mut()?;
+            if worktree.is_path_revealed(&path) == revealed {
+                return None;
+            }
+            if revealed {
+                worktree.reveal_ignored_path(path);
+            } else {
+                worktree.unreveal_ignored_path(path);
+            }
+            Some(())
+        });
+        if changed.is_some() {
+            self.serialize_revealed_paths(&worktree, cx);
+        }
+    }
+
+    fn measure_directory(&mut self, project_path: ProjectPath, cx: &mut ViewContext<Self>) {
+        if self
+            .project
+            .read(cx)
+            .cached_directory_size(&project_path)
+            .is_some()
+        {
+            return;
+        }
+        let size = self
+            .project
+            .update(cx, |project, cx| project.directory_size(project_path, cx));
+        cx.spawn(|this, mut cx| async move {
+            size.await?;
+            this.update(&mut cx, |_, cx| cx.notify())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn show_largest_paths(&mut self, _: &ShowLargestPaths, cx: &mut ViewContext<Self>) {
+        let Some((worktree, entry)) = self.selected_entry(cx) else {
+            return;
+        };
+        if !entry.is_dir() {
+            return;
+        }
+        let directory = ProjectPath {
+            worktree_id: worktree.id(),
+            path: entry.path.clone(),
+        };
+        let project = self.project.clone();
+        let workspace_handle = self.workspace.clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace.toggle_modal(cx, |cx| {
+                    LargestPaths::new(project, workspace_handle, directory, cx)
+                });
+            })
+            .log_err();
+    }
+
     fn open_in_terminal(&mut self, _: &OpenInTerminal, cx: &mut ViewContext<Self>) {
         if let Some((worktree, entry)) = self.selected_entry(cx) {
             let abs_path = worktree.abs_path().join(&entry.path);
@@ -1375,6 +1749,9 @@ impl ProjectPanel {
         new_selected_entry: Option<(WorktreeId, ProjectEntryId)>,
         cx: &mut ViewContext<Self>,
     ) {
+        self.load_expanded_dirs(cx);
+        self.resolve_pending_expanded_paths(cx);
+
         let auto_collapse_dirs = ProjectPanelSettings::get_global(cx).auto_fold_dirs;
         let project = self.project.read(cx);
         self.last_worktree_root_id = project
@@ -1442,13 +1819,18 @@ impl ProjectPanel {
                         id: NEW_ENTRY_ID,
                         kind: new_entry_kind,
                         path: entry.path.join("\0").into(),
-                        inode: 0,
+                        inode: FileId::default(),
                         mtime: entry.mtime,
+                        size: 0,
+                        mode: None,
                         is_symlink: false,
+                        is_symlink_cycle: false,
                         is_ignored: entry.is_ignored,
                         is_external: false,
                         is_private: false,
+                        is_hidden: entry.is_hidden,
                         git_status: entry.git_status,
+                        scan_error: None,
                     });
                 }
                 if expanded_dir_ids.binary_search(&entry.id).is_err()
@@ -1516,6 +1898,8 @@ impl ProjectPanel {
                 entry_id,
             });
         }
+
+        self.serialize_expanded_dirs(cx);
     }
 
     fn expand_entry(
@@ -1739,8 +2123,48 @@ impl ProjectPanel {
             }
         }
         let depth = details.depth;
+        // Root entries are badged with the kinds of project their manifests declare.
+        let project_kinds = if details.path.as_os_str().is_empty() {
+            let project = self.project.read(cx);
+            project
+                .worktree_id_for_entry(entry_id, cx)
+                .map(|worktree_id| {
+                    project
+                        .project_kinds(worktree_id)
+                        .iter()
+                        .map(|kind| kind.label())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .filter(|label| !label.is_empty())
+        } else {
+            None
+        };
+        let sized_dir_path = if kind.is_dir() && self.project.read(cx).is_local() {
+            self.project.read(cx).path_for_entry(entry_id, cx)
+        } else {
+            None
+        };
         div()
             .id(entry_id.to_proto() as usize)
+            .when_some(sized_dir_path, |div, project_path| {
+                let project = self.project.clone();
+                div.on_hover(cx.listener({
+                    let project_path = project_path.clone();
+                    move |this, hovered: &bool, cx| {
+                        if *hovered {
+                            this.measure_directory(project_path.clone(), cx);
+                        }
+                    }
+                }))
+                .tooltip(move |cx| {
+                    let size = project.read(cx).cached_directory_size(&project_path);
+                    Tooltip::text(
+                        size.map_or("Calculating size…".to_string(), format_size),
+                        cx,
+                    )
+                })
+            })
             .on_drag(entry_id, move |entry_id, cx| {
                 cx.new_view(|_| DraggedProjectEntryView {
                     details: details.clone(),
@@ -1779,6 +2203,11 @@ impl ProjectPanel {
                         }
                         .ml_1(),
                     )
+                    .end_slot::<Label>(project_kinds.map(|project_kinds| {
+                        Label::new(project_kinds)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    }))
                     .on_click(cx.listener(move |this, event: &gpui::ClickEvent, cx| {
                         if event.down.button == MouseButton::Right || event.down.first_mouse {
                             return;
@@ -1894,7 +2323,13 @@ impl Render for ProjectPanel {
                 })
                 .when(project.is_local(), |el| {
                     el.on_action(cx.listener(Self::reveal_in_finder))
+                        .on_action(cx.listener(Self::open_with_system))
+                        .on_action(cx.listener(Self::copy_file_to_clipboard))
                         .on_action(cx.listener(Self::open_in_terminal))
+                        .on_action(cx.listener(Self::refresh))
+                        .on_action(cx.listener(Self::show_largest_paths))
+                        .on_action(cx.listener(Self::include_ignored_entry))
+                        .on_action(cx.listener(Self::ignore_entry_again))
                 })
                 .on_mouse_down(
                     MouseButton::Right,
@@ -2075,6 +2510,38 @@ impl ClipboardEntry {
     }
 }
 
+fn expanded_dirs_key(worktree_abs_path: &Path) -> String {
+    format!(
+        "{}-{}",
+        EXPANDED_DIRS_KEY_PREFIX,
+        worktree_abs_path.to_string_lossy()
+    )
+}
+
+fn revealed_paths_key(worktree_abs_path: &Path) -> String {
+    format!(
+        "{}-{}",
+        REVEALED_PATHS_KEY_PREFIX,
+        worktree_abs_path.to_string_lossy()
+    )
+}
+
+/// Formats a size in bytes using binary units, e.g. `1.5 MB`.
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["bytes", "KB", "MB", "GB", "TB"];
+    let mut scaled_size = size as f64;
+    let mut unit = 0;
+    while scaled_size >= 1024. && unit < UNITS.len() - 1 {
+        scaled_size /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size} {}", UNITS[0])
+    } else {
+        format!("{scaled_size:.1} {}", UNITS[unit])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3515,6 +3982,83 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_restore_expanded_dirs(cx: &mut gpui::TestAppContext) {
+        init_test_with_editor(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/project_root",
+            json!({
+                ".git": {},
+                ".gitignore": "target\n",
+                "dir_1": {
+                    "nested_dir": {
+                        "file_a.py": "# File contents",
+                    },
+                    "other_dir": {},
+                },
+                "dir_2": {},
+                "target": {
+                    "debug": {
+                        "build": {},
+                    },
+                },
+                "file_1.py": "# File contents",
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/project_root".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| ProjectPanel::new(workspace, cx))
+            .unwrap();
+        cx.executor().run_until_parked();
+
+        let worktree_id =
+            cx.update(|cx| project.read(cx).worktrees().next().unwrap().read(cx).id());
+        panel.update(cx, |panel, cx| {
+            panel.restore_expanded_dirs(
+                worktree_id,
+                vec![
+                    PathBuf::from(""),
+                    PathBuf::from("dir_1"),
+                    PathBuf::from("dir_1/nested_dir"),
+                    PathBuf::from("file_1.py"),
+                    PathBuf::from("missing_dir"),
+                    PathBuf::from("target"),
+                    PathBuf::from("target/debug"),
+                ],
+                cx,
+            )
+        });
+        cx.executor().run_until_parked();
+
+        // Paths that don't exist or aren't directories are ignored, and ignored directories are
+        // loaded so that their expanded descendants can be restored.
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..20, cx),
+            &[
+                "v project_root",
+                "    v dir_1",
+                "        v nested_dir",
+                "              file_a.py",
+                "        > other_dir",
+                "    > dir_2",
+                "    v target",
+                "        v debug",
+                "            > build",
+                "      .gitignore",
+                "      file_1.py",
+            ]
+        );
+        panel.update(cx, |panel, _| {
+            assert!(panel.pending_expanded_paths.is_empty());
+        });
+    }
+
     #[gpui::test]
     async fn test_new_file_move(cx: &mut gpui::TestAppContext) {
         init_test(cx);
@@ -0,0 +1,235 @@
+use editor::recent_edits::{self, RecentEditLocation, RecentEdits};
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, ParentElement,
+    Render, Styled, View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use std::sync::Arc;
+use time::{OffsetDateTime, UtcOffset};
+use time_format::TimestampFormat;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::{paths::PathExt, ResultExt};
+use workspace::{ModalView, Workspace};
+
+actions!(recent_edit_selector, [Toggle]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(RecentEditSelector::register).detach();
+}
+
+pub struct RecentEditSelector {
+    picker: View<Picker<RecentEditSelectorDelegate>>,
+}
+
+impl RecentEditSelector {
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(move |workspace, _: &Toggle, cx| {
+            Self::toggle(workspace, cx);
+        });
+    }
+
+    fn toggle(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> Option<()> {
+        let locations = RecentEdits::global(cx)?
+            .read(cx)
+            .locations(workspace.database_id(), cx);
+        let workspace_handle = cx.view().downgrade();
+        workspace.toggle_modal(cx, move |cx| {
+            RecentEditSelector::new(workspace_handle, locations, cx)
+        });
+        Some(())
+    }
+
+    fn new(
+        workspace: WeakView<Workspace>,
+        locations: Vec<RecentEditLocation>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = RecentEditSelectorDelegate::new(
+            cx.view().downgrade(),
+            workspace,
+            locations,
+            cx.local_timezone(),
+        );
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for RecentEditSelector {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for RecentEditSelector {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for RecentEditSelector {}
+impl ModalView for RecentEditSelector {}
+
+pub struct RecentEditSelectorDelegate {
+    recent_edit_selector: WeakView<RecentEditSelector>,
+    workspace: WeakView<Workspace>,
+    locations: Vec<RecentEditLocation>,
+    edited_at_labels: Vec<SharedString>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl RecentEditSelectorDelegate {
+    fn new(
+        recent_edit_selector: WeakView<RecentEditSelector>,
+        workspace: WeakView<Workspace>,
+        locations: Vec<RecentEditLocation>,
+        timezone: UtcOffset,
+    ) -> Self {
+        let candidates = locations
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, location)| {
+                StringMatchCandidate::new(
+                    candidate_id,
+                    format!(
+                        "{}:{}",
+                        location.abs_path.compact().display(),
+                        location.point.row + 1
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let now = OffsetDateTime::now_utc();
+        let edited_at_labels = locations
+            .iter()
+            .map(|location| {
+                time_format::format_localized_timestamp(
+                    OffsetDateTime::from(location.edited_at),
+                    now,
+                    timezone,
+                    TimestampFormat::Relative,
+                )
+                .into()
+            })
+            .collect();
+
+        Self {
+            recent_edit_selector,
+            workspace,
+            locations,
+            edited_at_labels,
+            candidates,
+            matches: vec![],
+            selected_index: 0,
+        }
+    }
+}
+
+impl PickerDelegate for RecentEditSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Go to recent edit...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let location = self.locations[mat.candidate_id].clone();
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    recent_edits::open_recent_edit(workspace, location, cx).detach_and_log_err(cx);
+                })
+                .log_err();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.recent_edit_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let mut matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+            // Keep the most recent edits first, rather than ordering them by score.
+            matches.sort_by_key(|mat| mat.candidate_id);
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                ))
+                .end_slot(
+                    Label::new(self.edited_at_labels[mat.candidate_id].clone()).color(Color::Muted),
+                ),
+        )
+    }
+}
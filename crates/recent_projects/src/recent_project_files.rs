@@ -0,0 +1,449 @@
+//! Fuzzy finding of files in the recent projects that aren't open in any window, using the
+//! files that their worktrees had when they were last closed. Picking a file opens its project
+//! in a new window, and the file in it.
+
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Subscription, Task, View,
+    ViewContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::{paths::PathExt, ResultExt};
+use workspace::{
+    LocalPaths, ModalView, OpenOptions, SerializedWorkspaceLocation, Workspace, WORKSPACE_DB,
+};
+
+gpui::actions!(projects, [FindFileInRecentProjects]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(RecentProjectFiles::register).detach();
+}
+
+pub struct RecentProjectFiles {
+    picker: View<Picker<RecentProjectFilesDelegate>>,
+    _subscription: Subscription,
+}
+
+impl ModalView for RecentProjectFiles {}
+
+impl RecentProjectFiles {
+    fn new(
+        delegate: RecentProjectFilesDelegate,
+        open_roots: HashSet<PathBuf>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        let _subscription = cx.subscribe(&picker, |_, _, _, cx| cx.emit(DismissEvent));
+        cx.spawn(|this, mut cx| async move {
+            let files = cx
+                .background_executor()
+                .spawn(async move {
+                    let projects = WORKSPACE_DB
+                        .recent_workspaces_on_disk()
+                        .await
+                        .log_err()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|(_, location)| match location {
+                            SerializedWorkspaceLocation::Local(paths, _) => Some(paths),
+                            SerializedWorkspaceLocation::DevServer(_) => None,
+                        });
+                    recent_project_files(projects, &open_roots)
+                })
+                .await;
+            this.update(&mut cx, move |this, cx| {
+                this.picker.update(cx, move |picker, cx| {
+                    picker.delegate.set_files(files);
+                    picker.update_matches(picker.query(cx), cx)
+                })
+            })
+            .ok()
+        })
+        .detach();
+        Self {
+            picker,
+            _subscription,
+        }
+    }
+
+    fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(|workspace, _: &FindFileInRecentProjects, cx| {
+            Self::open(workspace, cx);
+        });
+    }
+
+    pub fn open(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+        // The roots of the worktrees that are open don't need to be searched here, as the
+        // file finder searches them.
+        let mut open_roots = HashSet::default();
+        open_roots.extend(worktree_roots(workspace, cx));
+        for window in cx.windows() {
+            if let Some(workspace) = window
+                .downcast::<Workspace>()
+                .and_then(|window| window.read(cx).ok())
+            {
+                open_roots.extend(worktree_roots(workspace, cx));
+            }
+        }
+
+        let weak = cx.view().downgrade();
+        workspace.toggle_modal(cx, |cx| {
+            Self::new(RecentProjectFilesDelegate::new(weak), open_roots, cx)
+        })
+    }
+}
+
+fn worktree_roots(workspace: &Workspace, cx: &AppContext) -> Vec<PathBuf> {
+    workspace
+        .visible_worktrees(cx)
+        .map(|worktree| worktree.read(cx).abs_path().to_path_buf())
+        .collect()
+}
+
+/// The remembered files of the projects' worktrees, skipping the worktrees that are open and
+/// the files that no longer exist.
+fn recent_project_files(
+    projects: impl IntoIterator<Item = LocalPaths>,
+    open_roots: &HashSet<PathBuf>,
+) -> Vec<RecentProjectFile> {
+    let mut files = Vec::new();
+    let mut searched_roots = HashSet::default();
+    for paths in projects {
+        for root in paths.paths().iter() {
+            if open_roots.contains(root) || !searched_roots.insert(root.clone()) {
+                continue;
+            }
+            let Some(root_paths) = WORKSPACE_DB.worktree_files(root).log_err() else {
+                continue;
+            };
+            let root_name = root.file_name().map_or_else(
+                || root.to_string_lossy().into_owned(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+            for path in root_paths {
+                if !root.join(&path).exists() {
+                    continue;
+                }
+                files.push(RecentProjectFile {
+                    project: paths.clone(),
+                    root: root.clone(),
+                    label: format!("{root_name}/{}", path.to_string_lossy()),
+                    path,
+                });
+            }
+        }
+    }
+    files
+}
+
+impl EventEmitter<DismissEvent> for RecentProjectFiles {}
+
+impl FocusableView for RecentProjectFiles {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for RecentProjectFiles {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RecentProjectFile {
+    /// The roots of the project that the file is opened in.
+    project: LocalPaths,
+    root: PathBuf,
+    /// The file's path, relative to the root.
+    path: PathBuf,
+    /// The file's path, starting with the name of the root, which is matched against the query.
+    label: String,
+}
+
+pub struct RecentProjectFilesDelegate {
+    workspace: WeakView<Workspace>,
+    files: Arc<Vec<RecentProjectFile>>,
+    candidates: Arc<Vec<StringMatchCandidate>>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+    is_loading: bool,
+}
+
+impl RecentProjectFilesDelegate {
+    fn new(workspace: WeakView<Workspace>) -> Self {
+        Self {
+            workspace,
+            files: Default::default(),
+            candidates: Default::default(),
+            matches: Vec::new(),
+            selected_index: 0,
+            is_loading: true,
+        }
+    }
+
+    fn set_files(&mut self, files: Vec<RecentProjectFile>) {
+        self.candidates = Arc::new(
+            files
+                .iter()
+                .enumerate()
+                .map(|(id, file)| StringMatchCandidate::new(id, file.label.clone()))
+                .collect(),
+        );
+        self.files = Arc::new(files);
+        self.is_loading = false;
+    }
+}
+
+impl PickerDelegate for RecentProjectFilesDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _: &mut WindowContext) -> Arc<str> {
+        "Search files in recent projects...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let candidates = self.candidates.clone();
+        let executor = cx.background_executor().clone();
+        cx.spawn(|picker, mut cx| async move {
+            let query = query.trim();
+            let smart_case = query.chars().any(|c| c.is_uppercase());
+            let matches = fuzzy::match_strings(
+                &candidates,
+                query,
+                smart_case,
+                100,
+                &AtomicBool::new(false),
+                executor,
+            )
+            .await;
+            picker
+                .update(&mut cx, |picker, cx| {
+                    picker.delegate.matches = matches;
+                    picker.delegate.selected_index = 0;
+                    cx.notify();
+                })
+                .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let Some(file) = self
+            .matches
+            .get(self.selected_index)
+            .and_then(|hit| self.files.get(hit.candidate_id))
+            .cloned()
+        else {
+            return;
+        };
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+
+        let app_state = workspace.read(cx).app_state().clone();
+        let open_project = workspace::open_paths(
+            file.project.paths().as_ref(),
+            app_state,
+            OpenOptions {
+                open_new_workspace: Some(true),
+                ..Default::default()
+            },
+            cx,
+        );
+        cx.spawn(|_, mut cx| async move {
+            let (window, _) = open_project.await?;
+            window
+                .update(&mut cx, |workspace, cx| {
+                    workspace.open_abs_path(file.root.join(&file.path), true, cx)
+                })?
+                .await?;
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+        cx.emit(DismissEvent);
+    }
+
+    fn dismissed(&mut self, _: &mut ViewContext<Picker<Self>>) {}
+
+    fn no_matches_text(&self, _: &mut WindowContext) -> SharedString {
+        if self.is_loading {
+            "Loading files...".into()
+        } else if self.files.is_empty() {
+            "Files of recently closed projects will show up here".into()
+        } else {
+            "No matches".into()
+        }
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let hit = self.matches.get(ix)?;
+        let file = self.files.get(hit.candidate_id)?;
+
+        let file_name = file
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let file_name_start = file.label.chars().count() - file_name.chars().count();
+        let file_name_positions = hit
+            .positions
+            .iter()
+            .filter_map(|position| position.checked_sub(file_name_start))
+            .collect();
+        let project = file
+            .project
+            .paths()
+            .iter()
+            .map(|path| path.compact().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Some(
+            ListItem::new(ix)
+                .spacing(ListItemSpacing::Sparse)
+                .inset(true)
+                .selected(selected)
+                .child(
+                    v_flex()
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .child(HighlightedLabel::new(file_name, file_name_positions))
+                                .child(
+                                    HighlightedLabel::new(
+                                        file.label.clone(),
+                                        hit.positions.clone(),
+                                    )
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                                ),
+                        )
+                        .child(
+                            Label::new(project)
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        ),
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::Project;
+    use serde_json::json;
+    use std::path::Path;
+    use workspace::AppState;
+
+    #[gpui::test]
+    async fn test_finds_files_of_closed_projects(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree("/open", json!({ "a.rs": "" }))
+            .await;
+
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        WORKSPACE_DB
+            .save_worktree_files(
+                root.to_path_buf(),
+                vec![
+                    "Cargo.toml".into(),
+                    "src/deleted.rs".into(),
+                    "src/recent_projects.rs".into(),
+                ],
+            )
+            .await
+            .unwrap();
+
+        // Files that no longer exist are skipped.
+        let files = recent_project_files([LocalPaths::new([root])], &HashSet::default());
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| file.label.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "recent_projects/Cargo.toml",
+                "recent_projects/src/recent_projects.rs"
+            ]
+        );
+        assert_eq!(files[0].root, root);
+
+        // Worktrees that are open aren't searched.
+        assert!(recent_project_files(
+            [LocalPaths::new([root])],
+            &HashSet::from_iter([root.to_path_buf()])
+        )
+        .is_empty());
+
+        let project = Project::test(app_state.fs.clone(), ["/open".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+        workspace.update(cx, |workspace, cx| RecentProjectFiles::open(workspace, cx));
+        cx.run_until_parked();
+        let picker = workspace.update(cx, |workspace, cx| {
+            workspace
+                .active_modal::<RecentProjectFiles>(cx)
+                .unwrap()
+                .read(cx)
+                .picker
+                .clone()
+        });
+        picker
+            .update(cx, |picker, cx| {
+                picker.delegate.set_files(files);
+                picker.delegate.update_matches("recprojrs".into(), cx)
+            })
+            .await;
+        picker.update(cx, |picker, _| {
+            assert_eq!(
+                picker
+                    .delegate
+                    .matches
+                    .iter()
+                    .map(|hit| hit.string.as_str())
+                    .collect::<Vec<_>>(),
+                ["recent_projects/src/recent_projects.rs"]
+            );
+        });
+    }
+
+    fn init_test(cx: &mut TestAppContext) -> Arc<AppState> {
+        cx.update(|cx| {
+            let state = AppState::test(cx);
+            language::init(cx);
+            crate::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            state
+        })
+    }
+}
@@ -1,4 +1,5 @@
 mod dev_servers;
+mod recent_project_files;
 
 use client::ProjectId;
 use dev_servers::reconnect_to_dev_server;
@@ -14,6 +15,7 @@ use picker::{
     highlighted_match_with_paths::{HighlightedMatchWithPaths, HighlightedText},
     Picker, PickerDelegate,
 };
+pub use recent_project_files::{FindFileInRecentProjects, RecentProjectFiles};
 use rpc::proto::DevServerStatus;
 use serde::Deserialize;
 use std::{
@@ -46,6 +48,7 @@ gpui::actions!(projects, [OpenRemote]);
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(RecentProjects::register).detach();
     cx.observe_new_views(DevServerProjects::register).detach();
+    recent_project_files::init(cx);
 }
 
 pub struct RecentProjects {
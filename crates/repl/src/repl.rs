@@ -0,0 +1,279 @@
+mod repl_settings;
+
+use std::ops::Range;
+
+use anyhow::anyhow;
+use collections::HashMap;
+use editor::Editor;
+use gpui::{actions, AppContext, Context as _, Model, ViewContext, WeakModel};
+use language::ToOffset as _;
+use project::File;
+use settings::Settings;
+use task::{RevealStrategy, SpawnInTerminal, TaskId};
+use terminal::{TaskStatus, Terminal};
+use terminal_view::{terminal_panel::TerminalPanel, TerminalView};
+use workspace::Workspace;
+
+pub use repl_settings::*;
+
+actions!(repl, [Run]);
+
+pub fn init(cx: &mut AppContext) {
+    ReplSettings::register(cx);
+    cx.observe_new_views(|workspace: &mut Workspace, cx| {
+        let sessions = cx.new_model(|_| ReplSessions::default());
+        workspace.register_action(move |workspace, _: &Run, cx| {
+            run(&sessions, workspace, cx);
+        });
+    })
+    .detach();
+}
+
+/// The REPL processes of a workspace, by the name of the language they run. Each runs in a
+/// terminal of the terminal panel, where its output appears.
+#[derive(Default)]
+struct ReplSessions {
+    terminals: HashMap<String, WeakModel<Terminal>>,
+}
+
+impl ReplSessions {
+    fn running_terminal(&self, language_name: &str, cx: &AppContext) -> Option<Model<Terminal>> {
+        let terminal = self.terminals.get(language_name)?.upgrade()?;
+        let is_running = terminal
+            .read(cx)
+            .task()
+            .map_or(false, |task| task.status == TaskStatus::Running);
+        is_running.then_some(terminal)
+    }
+}
+
+/// Sends the newest selection of the active editor to the REPL of its language, or the cell
+/// or line around the cursor when nothing is selected. The REPL is started in the terminal
+/// panel the first time, and again whenever it has exited.
+fn run(sessions: &Model<ReplSessions>, workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some((buffer, selection)) = editor.update(cx, |editor, cx| {
+        let selection = editor.selections.newest_anchor().clone();
+        let multibuffer = editor.buffer().read(cx);
+        let (buffer, start) = multibuffer.text_anchor_for_position(selection.start, cx)?;
+        let (_, end) = multibuffer.text_anchor_for_position(selection.end, cx)?;
+        Some((buffer, start..end))
+    }) else {
+        return;
+    };
+
+    let buffer = buffer.read(cx);
+    let selection = selection.start.to_offset(buffer)..selection.end.to_offset(buffer);
+    let Some(language) = buffer.language_at(selection.start) else {
+        return;
+    };
+    let language_name = language.name().to_string();
+    let Some(backend) = ReplSettings::get_global(cx)
+        .backends
+        .get(&language_name)
+        .cloned()
+    else {
+        workspace.show_error(&anyhow!("no REPL is configured for {language_name}"), cx);
+        return;
+    };
+
+    let text = buffer.text();
+    let code_range = if selection.is_empty() {
+        code_range_at(&text, selection.start, backend.cell_marker.as_deref())
+    } else {
+        selection
+    };
+    let input = repl_input(&text[code_range]);
+    if input.is_empty() {
+        return;
+    }
+
+    if let Some(terminal) = sessions.read(cx).running_terminal(&language_name, cx) {
+        terminal.update(cx, |terminal, _| terminal.input(input));
+        reveal_terminal(&terminal, workspace, cx);
+        return;
+    }
+
+    let Some(terminal_panel) = workspace.panel::<TerminalPanel>(cx) else {
+        return;
+    };
+    let worktree_path =
+        File::from_dyn(buffer.file()).map(|file| file.worktree.read(cx).abs_path().to_path_buf());
+    let cwd = workspace
+        .project()
+        .read(cx)
+        .terminal_work_dir_for(worktree_path.as_deref(), cx);
+    let command_label = std::iter::once(backend.command.as_str())
+        .chain(backend.args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let label = format!("{language_name} REPL");
+    let spawn = SpawnInTerminal {
+        id: TaskId(format!("repl-{language_name}")),
+        full_label: label.clone(),
+        label,
+        command: backend.command,
+        args: backend.args,
+        command_label,
+        cwd,
+        env: backend.env,
+        use_new_terminal: true,
+        allow_concurrent_runs: true,
+        reveal: RevealStrategy::Never,
+    };
+    let spawn_terminal = terminal_panel.update(cx, |terminal_panel, cx| {
+        terminal_panel.spawn_in_new_terminal(spawn, cx)
+    });
+    let sessions = sessions.clone();
+    cx.spawn(|workspace, mut cx| async move {
+        let terminal = spawn_terminal.await?;
+        // The terminal buffers the input until the REPL reads it.
+        terminal.update(&mut cx, |terminal, _| terminal.input(input))?;
+        sessions.update(&mut cx, |sessions, _| {
+            sessions
+                .terminals
+                .insert(language_name, terminal.downgrade());
+        })?;
+        workspace.update(&mut cx, |workspace, cx| {
+            reveal_terminal(&terminal, workspace, cx)
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Opens the terminal panel on the REPL's terminal, leaving the focus in the editor.
+fn reveal_terminal(
+    terminal: &Model<Terminal>,
+    workspace: &mut Workspace,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(terminal_panel) = workspace.panel::<TerminalPanel>(cx) else {
+        return;
+    };
+    workspace.open_panel::<TerminalPanel>(cx);
+    let pane = terminal_panel.read(cx).pane().clone();
+    pane.update(cx, |pane, cx| {
+        let index = pane.items().position(|item| {
+            item.act_as::<TerminalView>(cx)
+                .map_or(false, |view| view.read(cx).terminal() == terminal)
+        });
+        if let Some(index) = index {
+            pane.activate_item(index, true, false, cx);
+        }
+    });
+    cx.notify();
+}
+
+/// The code to run at the offset: the cell that contains it, or its line when the text has no
+/// cells. A cell runs from a line that starts with the marker to the next one.
+fn code_range_at(text: &str, offset: usize, cell_marker: Option<&str>) -> Range<usize> {
+    let mut line_start = 0;
+    let mut cell_start = None;
+    let mut cursor_line = 0..text.len();
+    for line in text.split_inclusive('\n') {
+        let line_end = line_start + line.len();
+        let is_marker = cell_marker.map_or(false, |marker| line.starts_with(marker));
+        if is_marker {
+            if line_start > offset {
+                return cell_start.unwrap_or(0)..line_start;
+            }
+            cell_start = Some(line_end);
+        }
+        if (line_start..line_end).contains(&offset) {
+            cursor_line = line_start..line_end;
+        }
+        line_start = line_end;
+    }
+    if offset == text.len() {
+        let last_line_start = text.rfind('\n').map_or(0, |ix| ix + 1);
+        cursor_line = last_line_start..text.len();
+    }
+    match cell_start {
+        Some(cell_start) => cell_start..text.len(),
+        None => cursor_line,
+    }
+}
+
+/// Prepares code to be typed into a REPL. REPLs like Python's end an indented block at the first
+/// blank line, so blank lines are removed, and a single blank line is typed wherever the code
+/// dedents back to the top level instead. A blank line after multiple lines ends the last block.
+fn repl_input(code: &str) -> String {
+    const BLOCK_CONTINUATIONS: &[&str] = &["else", "elif", "except", "finally", ")", "]", "}"];
+
+    let lines = code
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return String::new();
+    }
+    let mut input = String::new();
+    let mut previous_line_indented = false;
+    for line in &lines {
+        let indented = line.starts_with(char::is_whitespace);
+        let continues_block = BLOCK_CONTINUATIONS.iter().any(|continuation| {
+            line.strip_prefix(continuation).map_or(false, |rest| {
+                !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+            })
+        });
+        if previous_line_indented && !indented && !continues_block {
+            input.push('\n');
+        }
+        input.push_str(line);
+        input.push('\n');
+        previous_line_indented = indented;
+    }
+    if lines.len() > 1 {
+        input.push('\n');
+    }
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_code_range_at() {
+        let text = indoc! {"
+            import os
+            # %%
+            a = 1
+            b = 2
+            # %% second cell
+            print(a + b)
+        "};
+        let code_at =
+            |offset: usize, marker: Option<&str>| &text[code_range_at(text, offset, marker)];
+
+        assert_eq!(
+            code_at(text.find("b =").unwrap(), Some("# %%")),
+            "a = 1\nb = 2\n"
+        );
+        assert_eq!(
+            code_at(text.find("print").unwrap(), Some("# %%")),
+            "print(a + b)\n"
+        );
+        // Code before the first marker is its own cell.
+        assert_eq!(code_at(0, Some("# %%")), "import os\n");
+        assert_eq!(code_at(text.find("b =").unwrap(), None), "b = 2\n");
+        assert_eq!(code_at(text.find("b =").unwrap(), Some("// %%")), "b = 2\n");
+    }
+
+    #[test]
+    fn test_repl_input() {
+        assert_eq!(repl_input("print(1)"), "print(1)\n");
+        assert_eq!(
+            repl_input("def f():\n\n    return 1\n\nf()\n"),
+            "def f():\n    return 1\n\nf()\n\n"
+        );
+        assert_eq!(
+            repl_input("if a:\n    b()\nelse:\n    c()\nd = [\n    1,\n]\n"),
+            "if a:\n    b()\nelse:\n    c()\n\nd = [\n    1,\n]\n\n"
+        );
+        assert_eq!(repl_input("\n  \n"), "");
+    }
+}
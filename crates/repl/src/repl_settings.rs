@@ -0,0 +1,44 @@
+use collections::HashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+
+#[derive(Deserialize, Debug)]
+pub struct ReplSettings {
+    pub backends: HashMap<String, ReplBackend>,
+}
+
+/// The interactive process that runs a language's code.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ReplBackend {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// The line prefix that starts a cell, such as `# %%`. Without a selection, the cell
+    /// around the cursor is run, or the cursor's line when the buffer has no cells.
+    #[serde(default)]
+    pub cell_marker: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct ReplSettingsContent {
+    /// The REPLs that run code, by the name of its language.
+    ///
+    /// Default: python3 for Python, node for JavaScript, irb for Ruby
+    pub backends: Option<HashMap<String, ReplBackend>>,
+}
+
+impl Settings for ReplSettings {
+    const KEY: Option<&'static str> = Some("repl");
+
+    type FileContent = ReplSettingsContent;
+
+    fn load(
+        sources: SettingsSources<Self::FileContent>,
+        _: &mut gpui::AppContext,
+    ) -> anyhow::Result<Self> {
+        sources.json_merge()
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    ops::Range,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use futures::AsyncReadExt as _;
+use http::{AsyncBody, HttpClient, Method, Request, StatusCode};
+
+/// A request written in an `.http` buffer:
+///
+/// ```http
+/// POST https://example.com/comments HTTP/1.1
+/// Content-Type: application/json
+///
+/// { "text": "Hello" }
+/// ```
+///
+/// Requests are separated by lines starting with `###`. Lines starting with `#` or `//` before
+/// the request line or among the headers are comments, while the body is sent as it's written.
+/// The method defaults to `GET` when the request line only has a URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub elapsed: Duration,
+}
+
+/// Parses the request whose block contains the given offset.
+pub fn request_at(text: &str, offset: usize) -> Result<HttpRequest> {
+    let range = request_range_at(text, offset);
+    parse_request(&text[range])
+}
+
+/// The range of the block of lines around the offset that's delimited by `###` lines.
+fn request_range_at(text: &str, offset: usize) -> Range<usize> {
+    let mut start = 0;
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let line_end = line_start + line.len();
+        if line.starts_with("###") {
+            if line_start >= offset {
+                return start..line_start;
+            }
+            start = line_end;
+        }
+        line_start = line_end;
+    }
+    start.min(text.len())..text.len()
+}
+
+fn is_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('#') || line.starts_with("//")
+}
+
+fn parse_request(text: &str) -> Result<HttpRequest> {
+    let mut lines = text
+        .lines()
+        .skip_while(|line| line.trim().is_empty() || is_comment(line));
+    let request_line = lines
+        .next()
+        .context("no request was found at the cursor")?
+        .trim();
+
+    let mut parts = request_line.split_whitespace();
+    let first = parts.next().context("the request line is empty")?;
+    let (method, url) = match parts.next() {
+        Some(url) => {
+            let method = Method::from_bytes(first.as_bytes())
+                .map_err(|_| anyhow!("invalid method {first:?}"))?;
+            (method, url)
+        }
+        None => (Method::GET, first),
+    };
+
+    let mut headers = Vec::new();
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+        if is_comment(line) {
+            continue;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .with_context(|| format!("invalid header {line:?}"))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let body = lines.collect::<Vec<_>>().join("\n").trim_end().to_string();
+
+    Ok(HttpRequest {
+        method,
+        url: url.to_string(),
+        headers,
+        body,
+    })
+}
+
+pub async fn send_request(
+    client: Arc<dyn HttpClient>,
+    request: HttpRequest,
+) -> Result<HttpResponse> {
+    let mut builder = Request::builder()
+        .method(request.method)
+        .uri(request.url.as_str());
+    for (name, value) in &request.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    let request = builder.body(AsyncBody::from(request.body))?;
+
+    let start = Instant::now();
+    let mut response = client.send(request).await?;
+    let mut body = Vec::new();
+    response.body_mut().read_to_end(&mut body).await?;
+    let elapsed = start.elapsed();
+
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect();
+    Ok(HttpResponse {
+        status: response.status(),
+        version: format!("{:?}", response.version()),
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+        elapsed,
+    })
+}
+
+impl HttpResponse {
+    pub fn is_json(&self) -> bool {
+        self.headers.iter().any(|(name, value)| {
+            name.eq_ignore_ascii_case("content-type") && value.contains("json")
+        })
+    }
+
+    /// The response as it's shown in its buffer, with JSON bodies pretty-printed.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} {}\n", self.version, self.status);
+        for (name, value) in &self.headers {
+            text.push_str(&format!("{name}: {value}\n"));
+        }
+        text.push('\n');
+        let pretty_body = self
+            .is_json()
+            .then(|| serde_json::from_str::<serde_json::Value>(&self.body).ok())
+            .flatten()
+            .and_then(|value| serde_json::to_string_pretty(&value).ok());
+        text.push_str(pretty_body.as_deref().unwrap_or(&self.body));
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use http::{FakeHttpClient, Response};
+    use indoc::indoc;
+
+    #[test]
+    fn test_request_at() {
+        let text = indoc! {r#"
+            # Fetch the comments.
+            https://example.com/comments
+
+            ###
+            POST https://example.com/comments HTTP/1.1
+            Content-Type: application/json
+            // Comments between headers are skipped.
+            Authorization: Bearer token
+
+            {
+              "text": "Hello"
+            }
+
+            ### Invalid header
+            GET https://example.com
+            Accept
+        "#};
+
+        assert_eq!(
+            request_at(text, 0).unwrap(),
+            HttpRequest {
+                method: Method::GET,
+                url: "https://example.com/comments".into(),
+                headers: Vec::new(),
+                body: String::new(),
+            }
+        );
+
+        let post_request = HttpRequest {
+            method: Method::POST,
+            url: "https://example.com/comments".into(),
+            headers: vec![
+                ("Content-Type".into(), "application/json".into()),
+                ("Authorization".into(), "Bearer token".into()),
+            ],
+            body: "{\n  \"text\": \"Hello\"\n}".into(),
+        };
+        let post_offset = text.find("POST").unwrap();
+        assert_eq!(request_at(text, post_offset).unwrap(), post_request);
+        let body_offset = text.find("Hello").unwrap();
+        assert_eq!(request_at(text, body_offset).unwrap(), post_request);
+
+        let invalid_offset = text.find("Accept").unwrap();
+        assert!(request_at(text, invalid_offset).is_err());
+        assert!(request_at("###\n\n", 4).is_err());
+    }
+
+    #[test]
+    fn test_body_lines_that_look_like_comments() {
+        let text = indoc! {"
+            // The body is a shell script.
+            POST https://example.com/scripts
+            # The script is sent as plain text.
+            Content-Type: text/plain
+
+            #!/bin/sh
+            // not a comment either
+            echo hi
+        "};
+        assert_eq!(
+            request_at(text, 0).unwrap(),
+            HttpRequest {
+                method: Method::POST,
+                url: "https://example.com/scripts".into(),
+                headers: vec![("Content-Type".into(), "text/plain".into())],
+                body: "#!/bin/sh\n// not a comment either\necho hi".into(),
+            }
+        );
+    }
+
+    #[gpui::test]
+    async fn test_send_request(_cx: &mut TestAppContext) {
+        let client = FakeHttpClient::create(|request| async move {
+            assert_eq!(request.method(), Method::POST);
+            assert_eq!(request.uri().to_string(), "https://example.com/comments");
+            assert_eq!(request.headers()["content-type"], "application/json");
+            Ok(Response::builder()
+                .status(201)
+                .header("content-type", "application/json")
+                .body(r#"{"id":1,"text":"Hello"}"#.into())
+                .unwrap())
+        });
+        let request = request_at(
+            indoc! {r#"
+                POST https://example.com/comments
+                Content-Type: application/json
+
+                {"text":"Hello"}
+            "#},
+            0,
+        )
+        .unwrap();
+
+        let response = send_request(client, request).await.unwrap();
+        assert_eq!(response.status, StatusCode::CREATED);
+        assert_eq!(
+            response.to_text(),
+            indoc! {r#"
+                HTTP/1.1 201 Created
+                content-type: application/json
+
+                {
+                  "id": 1,
+                  "text": "Hello"
+                }"#}
+        );
+    }
+}
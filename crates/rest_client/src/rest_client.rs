@@ -0,0 +1,342 @@
+mod http_request;
+
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result};
+use collections::{HashMap, HashSet};
+use editor::Editor;
+use gpui::{
+    actions, AppContext, Context as _, EntityId, Model, ModelContext, View, ViewContext,
+    VisualContext as _, WeakView,
+};
+use http::HttpClient;
+use language::{Buffer, Capability, ToOffset as _};
+use multi_buffer::MultiBuffer;
+use workspace::{notifications::DetachAndPromptErr, SplitDirection, Workspace};
+
+pub use http_request::*;
+
+actions!(rest_client, [NewRequest, SendRequest]);
+
+const NEW_REQUEST_TEXT: &str = r#"# Send the request under the cursor with `rest client: send request`.
+# Requests are separated by lines starting with ###.
+GET https://httpbin.org/get
+Accept: application/json
+
+###
+POST https://httpbin.org/post
+Content-Type: application/json
+
+{
+  "message": "Hello"
+}
+"#;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, cx| {
+        let rest_client = cx.new_model(|_| RestClient::default());
+        workspace
+            .register_action({
+                let rest_client = rest_client.clone();
+                move |workspace, _: &NewRequest, cx| {
+                    new_request(&rest_client, workspace, cx);
+                }
+            })
+            .register_action(move |workspace, _: &SendRequest, cx| {
+                send_request_at_cursor(&rest_client, workspace, cx);
+            });
+    })
+    .detach();
+}
+
+/// The scratch buffers that hold requests, and the editors that show the responses to the
+/// requests of each buffer.
+#[derive(Default)]
+struct RestClient {
+    scratch_buffers: HashSet<EntityId>,
+    response_editors: HashMap<EntityId, WeakView<Editor>>,
+}
+
+impl RestClient {
+    fn add_scratch_buffer(&mut self, buffer: &Model<Buffer>, cx: &mut ModelContext<Self>) {
+        let buffer_id = buffer.entity_id();
+        self.scratch_buffers.insert(buffer_id);
+        cx.observe_release(buffer, move |this, _, _| {
+            this.scratch_buffers.remove(&buffer_id);
+            this.response_editors.remove(&buffer_id);
+        })
+        .detach();
+    }
+
+    /// Whether the buffer is a scratch buffer created for requests, or an `.http` or `.rest`
+    /// file.
+    fn is_request_buffer(&self, buffer: &Model<Buffer>, cx: &AppContext) -> bool {
+        self.scratch_buffers.contains(&buffer.entity_id())
+            || buffer.read(cx).file().map_or(false, |file| {
+                file.path().extension().map_or(false, |extension| {
+                    extension == "http" || extension == "rest"
+                })
+            })
+    }
+}
+
+fn new_request(
+    rest_client: &Model<RestClient>,
+    workspace: &mut Workspace,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let project = workspace.project().clone();
+    let create = project.update(cx, |project, cx| project.create_buffer(cx));
+    let rest_client = rest_client.clone();
+    cx.spawn(|workspace, mut cx| async move {
+        let buffer = create.await?;
+        buffer.update(&mut cx, |buffer, cx| buffer.set_text(NEW_REQUEST_TEXT, cx))?;
+        rest_client.update(&mut cx, |rest_client, cx| {
+            rest_client.add_scratch_buffer(&buffer, cx)
+        })?;
+        workspace.update(&mut cx, |workspace, cx| {
+            let editor = cx.new_view(|cx| Editor::for_buffer(buffer, Some(project), cx));
+            workspace.add_item_to_active_pane(Box::new(editor), None, cx);
+        })
+    })
+    .detach_and_prompt_err("Failed to create a request buffer", cx, |_, _| None);
+}
+
+/// Sends the request under the newest cursor of the active editor, and shows the response in
+/// the editor linked to the request's buffer.
+fn send_request_at_cursor(
+    rest_client: &Model<RestClient>,
+    workspace: &mut Workspace,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+        let head = editor.selections.newest_anchor().head();
+        editor.buffer().read(cx).text_anchor_for_position(head, cx)
+    }) else {
+        return;
+    };
+    if !rest_client.read(cx).is_request_buffer(&buffer, cx) {
+        return;
+    }
+
+    let buffer_id = buffer.entity_id();
+    let text = buffer.read(cx).text();
+    let offset = position.to_offset(buffer.read(cx));
+    let client: Arc<dyn HttpClient> = workspace.client().http_client();
+    let languages = workspace.project().read(cx).languages().clone();
+    let rest_client = rest_client.clone();
+    cx.spawn(|workspace, mut cx| async move {
+        let request = request_at(&text, offset)?;
+        let title = format!("{} {}", request.method, request.url);
+        let response = cx
+            .background_executor()
+            .spawn(send_request(client, request))
+            .await
+            .with_context(|| format!("failed to send {title}"))?;
+        let language = if response.is_json() {
+            languages.language_for_name("JSON").await.ok()
+        } else {
+            None
+        };
+        let title = format!(
+            "{title} ({} in {} ms)",
+            response.status,
+            response.elapsed.as_millis()
+        );
+        let text = response.to_text();
+        workspace.update(&mut cx, |workspace, cx| {
+            let existing_editor = rest_client
+                .read(cx)
+                .response_editors
+                .get(&buffer_id)
+                .and_then(|editor| editor.upgrade());
+            let editor = match existing_editor {
+                Some(editor) => {
+                    update_response_editor(&editor, title, &text, language, cx);
+                    workspace.activate_item(&editor, cx);
+                    editor
+                }
+                None => {
+                    let editor = new_response_editor(workspace, title, &text, language, cx);
+                    workspace.split_item(SplitDirection::Right, Box::new(editor.clone()), cx);
+                    editor
+                }
+            };
+            rest_client.update(cx, |rest_client, _| {
+                rest_client
+                    .response_editors
+                    .retain(|_, editor| editor.upgrade().is_some());
+                rest_client
+                    .response_editors
+                    .insert(buffer_id, editor.downgrade());
+            });
+        })
+    })
+    .detach_and_prompt_err("Failed to send the request", cx, |_, _| None);
+}
+
+fn new_response_editor(
+    workspace: &Workspace,
+    title: String,
+    text: &str,
+    language: Option<Arc<language::Language>>,
+    cx: &mut ViewContext<Workspace>,
+) -> View<Editor> {
+    let project = workspace.project().clone();
+    let buffer = cx.new_model(|cx| {
+        let mut buffer = Buffer::local(text, cx);
+        buffer.set_language(language, cx);
+        buffer.set_capability(Capability::ReadOnly, cx);
+        buffer
+    });
+    let multibuffer = cx.new_model(|cx| MultiBuffer::singleton(buffer, cx).with_title(title));
+    cx.new_view(|cx| {
+        let mut editor = Editor::for_multibuffer(multibuffer, Some(project), true, cx);
+        editor.set_read_only(true);
+        editor
+    })
+}
+
+fn update_response_editor(
+    editor: &View<Editor>,
+    title: String,
+    text: &str,
+    language: Option<Arc<language::Language>>,
+    cx: &mut ViewContext<Workspace>,
+) {
+    editor.update(cx, |editor, cx| {
+        editor.buffer().update(cx, |multibuffer, cx| {
+            multibuffer.set_title(title, cx);
+            if let Some(buffer) = multibuffer.as_singleton() {
+                buffer.update(cx, |buffer, cx| {
+                    buffer.set_text(text, cx);
+                    buffer.set_language(language, cx);
+                    // The response is never saved, so replacing it doesn't make it dirty.
+                    buffer.did_save(buffer.version(), None, cx);
+                });
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::Project;
+    use serde_json::json;
+    use workspace::AppState;
+
+    #[gpui::test]
+    async fn test_send_request_at_cursor(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "requests.http": "GET https://example.com/a\n\n###\nGET https://example.com/b\n",
+                "notes.txt": "GET https://example.com/c\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        // Requests aren't sent from files that aren't `.http` or `.rest` files.
+        open_editor(&workspace, "notes.txt", cx).await;
+        cx.dispatch_action(SendRequest);
+        cx.run_until_parked();
+        workspace.update(cx, |workspace, _| assert_eq!(workspace.panes().len(), 1));
+
+        let editor = open_editor(&workspace, "requests.http", cx).await;
+        editor.update(cx, |editor, cx| {
+            let offset = editor
+                .buffer()
+                .read(cx)
+                .snapshot(cx)
+                .text()
+                .find("/b")
+                .unwrap();
+            editor.change_selections(None, cx, |selections| {
+                selections.select_ranges([offset..offset])
+            });
+        });
+        cx.dispatch_action(SendRequest);
+        cx.run_until_parked();
+        let response_editor = workspace.update(cx, |workspace, cx| {
+            assert_eq!(workspace.panes().len(), 2);
+            workspace.active_item_as::<Editor>(cx).unwrap()
+        });
+        response_editor.update(cx, |response_editor, cx| {
+            let title = response_editor.buffer().read(cx).title(cx).to_string();
+            assert!(
+                title.starts_with("GET https://example.com/b (404 Not Found in "),
+                "unexpected title {title:?}"
+            );
+            assert!(response_editor.read_only(cx));
+        });
+
+        // Sending a request again replaces the previous response.
+        workspace.update(cx, |workspace, cx| {
+            workspace.activate_item(&editor, cx);
+        });
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |selections| selections.select_ranges([0..0]));
+        });
+        cx.dispatch_action(SendRequest);
+        cx.run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(workspace.panes().len(), 2);
+            let active_editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            assert_eq!(active_editor.entity_id(), response_editor.entity_id());
+        });
+        response_editor.update(cx, |response_editor, cx| {
+            let title = response_editor.buffer().read(cx).title(cx).to_string();
+            assert!(
+                title.starts_with("GET https://example.com/a (404 Not Found in "),
+                "unexpected title {title:?}"
+            );
+            assert!(!response_editor.buffer().read(cx).is_dirty(cx));
+        });
+    }
+
+    async fn open_editor(
+        workspace: &View<Workspace>,
+        path: &str,
+        cx: &mut VisualTestContext,
+    ) -> View<Editor> {
+        let worktree_id = workspace.update(cx, |workspace, cx| {
+            workspace
+                .project()
+                .read(cx)
+                .worktrees()
+                .next()
+                .unwrap()
+                .read(cx)
+                .id()
+        });
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path((worktree_id, path), None, true, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap()
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            crate::init(cx);
+        });
+    }
+}
@@ -0,0 +1,185 @@
+use std::ops::Range;
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo};
+use unicode_segmentation::UnicodeSegmentation as _;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TextDirection {
+    LeftToRight,
+    RightToLeft,
+}
+
+/// A run of a line's text that's displayed in a single direction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BidiRun {
+    /// The byte range of the run in the line.
+    pub range: Range<usize>,
+    /// The embedding level of the run. Runs with odd levels are displayed right to left.
+    pub level: u8,
+}
+
+impl BidiRun {
+    pub fn direction(&self) -> TextDirection {
+        if self.level % 2 == 0 {
+            TextDirection::LeftToRight
+        } else {
+            TextDirection::RightToLeft
+        }
+    }
+}
+
+/// Whether the text contains characters that are displayed right to left, such as Arabic or
+/// Hebrew letters, or the controls that reverse the direction of the text.
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(
+            bidi_class(c),
+            BidiClass::R
+                | BidiClass::AL
+                | BidiClass::AN
+                | BidiClass::RLE
+                | BidiClass::RLO
+                | BidiClass::RLI
+        )
+    })
+}
+
+/// Splits a line into the runs of its text that are displayed in a single direction, in the
+/// order they're displayed from left to right. The line's direction is the one of its first
+/// strong character, or left to right when it has none.
+pub fn bidi_runs(line: &str) -> Vec<BidiRun> {
+    if line.is_empty() {
+        return Vec::new();
+    }
+    if !has_rtl(line) {
+        return vec![BidiRun {
+            range: 0..line.len(),
+            level: 0,
+        }];
+    }
+
+    let info = BidiInfo::new(line, None);
+    let mut runs = Vec::new();
+    for paragraph in &info.paragraphs {
+        let (levels, level_runs) = info.visual_runs(paragraph, paragraph.range.clone());
+        runs.extend(level_runs.into_iter().map(|range| BidiRun {
+            level: levels[range.start].number(),
+            range,
+        }));
+    }
+    runs
+}
+
+/// The direction of a line, which is the one of its first strong character, or left to right
+/// when it has none.
+pub fn line_direction(line: &str) -> TextDirection {
+    if has_rtl(line) {
+        let info = BidiInfo::new(line, None);
+        if info
+            .paragraphs
+            .first()
+            .map_or(false, |paragraph| paragraph.level.is_rtl())
+        {
+            return TextDirection::RightToLeft;
+        }
+    }
+    TextDirection::LeftToRight
+}
+
+/// Returns the offset of the caret that's displayed one grapheme cluster to the left of the
+/// given one, or `None` when the caret is at the left edge of the line.
+pub fn visual_offset_left(line: &str, offset: usize) -> Option<usize> {
+    VisualCarets::new(line).step(offset, false)
+}
+
+/// Returns the offset of the caret that's displayed one grapheme cluster to the right of the
+/// given one, or `None` when the caret is at the right edge of the line.
+pub fn visual_offset_right(line: &str, offset: usize) -> Option<usize> {
+    VisualCarets::new(line).step(offset, true)
+}
+
+/// The grapheme clusters of a line in display order. The caret positions of the line are the
+/// slots between them, from the slot at the line's left edge to the one at its right edge.
+/// A caret at an offset is displayed before the cluster that starts at the offset, which is
+/// at the cluster's right edge when the cluster is displayed right to left.
+struct VisualCarets {
+    clusters: Vec<(Range<usize>, TextDirection)>,
+}
+
+impl VisualCarets {
+    fn new(line: &str) -> Self {
+        let mut clusters = Vec::new();
+        for run in bidi_runs(line) {
+            let direction = run.direction();
+            let run_clusters =
+                line[run.range.clone()]
+                    .grapheme_indices(true)
+                    .map(|(ix, grapheme)| {
+                        let start = run.range.start + ix;
+                        (start..start + grapheme.len(), direction)
+                    });
+            match direction {
+                TextDirection::LeftToRight => clusters.extend(run_clusters),
+                TextDirection::RightToLeft => {
+                    clusters.extend(run_clusters.collect::<Vec<_>>().into_iter().rev())
+                }
+            }
+        }
+        Self { clusters }
+    }
+
+    fn step(&self, offset: usize, rightward: bool) -> Option<usize> {
+        let mut slot = self.slot_for_offset(offset);
+        loop {
+            slot = if rightward {
+                slot + 1
+            } else {
+                slot.checked_sub(1)?
+            };
+            let new_offset = self.offset_for_slot(slot)?;
+            if new_offset != offset {
+                return Some(new_offset);
+            }
+        }
+    }
+
+    fn slot_for_offset(&self, offset: usize) -> usize {
+        let starting_at = self
+            .clusters
+            .iter()
+            .position(|(range, _)| range.start == offset);
+        if let Some(ix) = starting_at {
+            match self.clusters[ix].1 {
+                TextDirection::LeftToRight => ix,
+                TextDirection::RightToLeft => ix + 1,
+            }
+        } else if let Some(ix) = self
+            .clusters
+            .iter()
+            .position(|(range, _)| range.contains(&offset) || range.end == offset)
+        {
+            match self.clusters[ix].1 {
+                TextDirection::LeftToRight => ix + 1,
+                TextDirection::RightToLeft => ix,
+            }
+        } else {
+            0
+        }
+    }
+
+    fn offset_for_slot(&self, slot: usize) -> Option<usize> {
+        if let Some((range, direction)) = self.clusters.get(slot) {
+            Some(match direction {
+                TextDirection::LeftToRight => range.start,
+                TextDirection::RightToLeft => range.end,
+            })
+        } else if slot == self.clusters.len() {
+            let (range, direction) = self.clusters.last()?;
+            Some(match direction {
+                TextDirection::LeftToRight => range.end,
+                TextDirection::RightToLeft => range.start,
+            })
+        } else {
+            None
+        }
+    }
+}
@@ -1,7 +1,9 @@
+mod bidi;
 mod offset_utf16;
 mod point;
 mod point_utf16;
 mod unclipped;
+mod width;
 
 use arrayvec::ArrayString;
 use smallvec::SmallVec;
@@ -11,13 +13,18 @@ use std::{
     str,
 };
 use sum_tree::{Bias, Dimension, SumTree};
-use unicode_segmentation::GraphemeCursor;
+use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete};
 use util::debug_panic;
 
+pub use bidi::{
+    bidi_runs, has_rtl, line_direction, visual_offset_left, visual_offset_right, BidiRun,
+    TextDirection,
+};
 pub use offset_utf16::OffsetUtf16;
 pub use point::Point;
 pub use point_utf16::PointUtf16;
 pub use unclipped::Unclipped;
+pub use width::{char_width, grapheme_count, grapheme_width, WidthCounter};
 
 #[cfg(test)]
 const CHUNK_BASE: usize = 6;
@@ -392,12 +399,98 @@ impl Rope {
         cursor.seek(&point, Bias::Right, &());
         if let Some(chunk) = cursor.item() {
             let overshoot = point - cursor.start();
-            *cursor.start() + chunk.clip_point(overshoot, bias)
+            let clipped = *cursor.start() + chunk.clip_point(overshoot, bias);
+
+            // Chunks only clip to the grapheme boundaries within them, so check the clusters
+            // that span chunks.
+            if clipped == *cursor.start() || clipped == cursor.end(&()) {
+                let offset = self.point_to_offset(clipped);
+                if !self.is_grapheme_boundary(offset) {
+                    let offset = match bias {
+                        Bias::Left => self.prev_grapheme_boundary(offset),
+                        Bias::Right => self.next_grapheme_boundary(offset),
+                    };
+                    return self.offset_to_point(offset);
+                }
+            }
+            clipped
         } else {
             self.summary().lines
         }
     }
 
+    /// Whether the offset is between two extended grapheme clusters, which are the characters
+    /// as the user perceives them, e.g. a letter with its combining accents, or an emoji ZWJ
+    /// sequence.
+    pub fn is_grapheme_boundary(&self, offset: usize) -> bool {
+        if offset == 0 || offset >= self.len() {
+            return true;
+        }
+        self.seek_grapheme_boundary(offset, Bias::Right, |cursor, chunk, chunk_start| {
+            cursor.is_boundary(chunk, chunk_start)
+        })
+        .unwrap_or(true)
+    }
+
+    /// Returns the start of the grapheme cluster that precedes the offset.
+    pub fn prev_grapheme_boundary(&self, offset: usize) -> usize {
+        if offset == 0 {
+            return 0;
+        }
+        self.seek_grapheme_boundary(offset, Bias::Left, |cursor, chunk, chunk_start| {
+            cursor.prev_boundary(chunk, chunk_start)
+        })
+        .map_or(offset, |boundary| boundary.unwrap_or(0))
+    }
+
+    /// Returns the end of the grapheme cluster that follows the offset.
+    pub fn next_grapheme_boundary(&self, offset: usize) -> usize {
+        let len = self.len();
+        if offset >= len {
+            return len;
+        }
+        self.seek_grapheme_boundary(offset, Bias::Right, |cursor, chunk, chunk_start| {
+            cursor.next_boundary(chunk, chunk_start)
+        })
+        .map_or(offset, |boundary| boundary.unwrap_or(len))
+    }
+
+    /// Runs a grapheme cursor at the offset, feeding it the chunks it asks for.
+    fn seek_grapheme_boundary<T>(
+        &self,
+        offset: usize,
+        bias: Bias,
+        mut seek: impl FnMut(&mut GraphemeCursor, &str, usize) -> Result<T, GraphemeIncomplete>,
+    ) -> Option<T> {
+        let mut grapheme_cursor = GraphemeCursor::new(offset, self.len(), true);
+        let (mut chunk, mut chunk_start) = self.chunk_at(offset, bias)?;
+        loop {
+            match seek(&mut grapheme_cursor, chunk, chunk_start) {
+                Ok(result) => return Some(result),
+                Err(GraphemeIncomplete::PreContext(context_end)) => {
+                    let (context, context_start) = self.chunk_at(context_end, Bias::Left)?;
+                    grapheme_cursor.provide_context(context, context_start);
+                }
+                Err(GraphemeIncomplete::PrevChunk) if chunk_start > 0 => {
+                    (chunk, chunk_start) = self.chunk_at(chunk_start, Bias::Left)?;
+                }
+                Err(GraphemeIncomplete::NextChunk) => {
+                    (chunk, chunk_start) = self.chunk_at(chunk_start + chunk.len(), Bias::Right)?;
+                }
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Returns the text of the chunk containing the offset, and the offset where it starts.
+    /// At the boundary of two chunks, the bias picks one of them.
+    fn chunk_at(&self, offset: usize, bias: Bias) -> Option<(&str, usize)> {
+        let mut cursor = self.chunks.cursor::<usize>();
+        cursor.seek(&offset, bias, &());
+        let chunk = cursor.item()?;
+        Some((chunk.0.as_str(), *cursor.start()))
+    }
+
     pub fn clip_point_utf16(&self, point: Unclipped<PointUtf16>, bias: Bias) -> PointUtf16 {
         let mut cursor = self.chunks.cursor::<PointUtf16>();
         cursor.seek(&point.0, Bias::Right, &());
@@ -1307,6 +1400,127 @@ mod tests {
         assert_eq!(rope.text(), text);
     }
 
+    #[test]
+    fn test_graphemes_spanning_chunks() {
+        let text = "ab👨\u{200d}👩\u{200d}👧cde\u{301}\u{302}f";
+        let mut rope = Rope::new();
+        rope.push(text);
+        assert!(rope.chunks.summary().text.len > 2 * CHUNK_BASE);
+
+        let family_start = 2;
+        let family_end = family_start + "👨\u{200d}👩\u{200d}👧".len();
+        let accented_start = text.find('e').unwrap();
+        let accented_end = text.find('f').unwrap();
+
+        assert_eq!(rope.next_grapheme_boundary(family_start), family_end);
+        assert_eq!(rope.prev_grapheme_boundary(family_end), family_start);
+        assert_eq!(rope.next_grapheme_boundary(accented_start), accented_end);
+        assert_eq!(rope.prev_grapheme_boundary(accented_end), accented_start);
+        assert_eq!(rope.prev_grapheme_boundary(1), 0);
+        assert_eq!(rope.next_grapheme_boundary(text.len() - 1), text.len());
+        for offset in family_start + 1..family_end {
+            assert!(!rope.is_grapheme_boundary(offset), "{offset}");
+        }
+
+        // Points within a cluster are clipped to its edges, wherever the rope's chunks split it.
+        for column in family_start + 1..family_end {
+            if !text.is_char_boundary(column) {
+                continue;
+            }
+            let point = Point::new(0, column as u32);
+            assert_eq!(
+                rope.clip_point(point, Left),
+                Point::new(0, family_start as u32)
+            );
+            assert_eq!(
+                rope.clip_point(point, Right),
+                Point::new(0, family_end as u32)
+            );
+        }
+    }
+
+    #[test]
+    fn test_widths() {
+        assert_eq!(grapheme_width("a"), 1);
+        assert_eq!(grapheme_width("你"), 2);
+        assert_eq!(grapheme_width("e\u{301}"), 1);
+        assert_eq!(grapheme_width("👨\u{200d}👩\u{200d}👧"), 2);
+        assert_eq!(grapheme_width("\u{2764}\u{fe0f}"), 2);
+        assert_eq!(grapheme_width("🇯🇵"), 2);
+        assert_eq!(grapheme_count("a你e\u{301}👨\u{200d}👩\u{200d}👧"), 4);
+
+        let mut counter = WidthCounter::default();
+        let width = "a你e\u{301}👨\u{200d}👩\u{200d}👧\u{2764}\u{fe0f}"
+            .chars()
+            .map(|c| counter.width(c))
+            .sum::<u32>();
+        assert_eq!(width, 1 + 2 + 1 + 2 + 2);
+    }
+
+    #[test]
+    fn test_bidi() {
+        assert_eq!(bidi_runs(""), vec![]);
+        assert_eq!(
+            bidi_runs("let x;"),
+            vec![BidiRun {
+                range: 0..6,
+                level: 0
+            }]
+        );
+        assert_eq!(line_direction("let x;"), TextDirection::LeftToRight);
+
+        // "abc שלום": the Hebrew word is displayed right to left after the Latin one.
+        let line = "abc \u{5e9}\u{5dc}\u{5d5}\u{5dd}";
+        let runs = bidi_runs(line);
+        assert_eq!(
+            runs,
+            vec![
+                BidiRun {
+                    range: 0..4,
+                    level: 0
+                },
+                BidiRun {
+                    range: 4..12,
+                    level: 1
+                },
+            ]
+        );
+        assert_eq!(runs[1].direction(), TextDirection::RightToLeft);
+        assert_eq!(line_direction(line), TextDirection::LeftToRight);
+
+        // Moving right through the Hebrew word visits its letters from last to first.
+        let mut offset = 0;
+        let mut offsets = Vec::new();
+        while let Some(next_offset) = visual_offset_right(line, offset) {
+            offsets.push(next_offset);
+            offset = next_offset;
+        }
+        assert_eq!(offsets, vec![1, 2, 3, 12, 10, 8, 6, 4]);
+        let mut offsets = Vec::new();
+        while let Some(next_offset) = visual_offset_left(line, offset) {
+            offsets.push(next_offset);
+            offset = next_offset;
+        }
+        assert_eq!(offsets, vec![6, 8, 10, 12, 3, 2, 1, 0]);
+
+        // "שלום abc": the line is displayed right to left, with the Latin word on the left.
+        let line = "\u{5e9}\u{5dc}\u{5d5}\u{5dd} abc";
+        assert_eq!(line_direction(line), TextDirection::RightToLeft);
+        assert_eq!(
+            bidi_runs(line),
+            vec![
+                BidiRun {
+                    range: 9..12,
+                    level: 2
+                },
+                BidiRun {
+                    range: 0..9,
+                    level: 1
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_clip() {
         let rope = Rope::from("🧘");
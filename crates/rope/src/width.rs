@@ -0,0 +1,74 @@
+use unicode_segmentation::UnicodeSegmentation as _;
+use unicode_width::UnicodeWidthChar as _;
+
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{fe0f}';
+
+/// The number of columns a character takes in a monospace grid: two for wide characters,
+/// such as CJK ideographs and most emoji, and zero for combining marks and other characters
+/// that modify the preceding ones. Control characters count as one column.
+pub fn char_width(c: char) -> u32 {
+    c.width().unwrap_or(1) as u32
+}
+
+/// The number of columns an extended grapheme cluster takes in a monospace grid. Emoji ZWJ
+/// sequences, flags, and characters followed by the emoji presentation selector are displayed
+/// as a single wide glyph.
+pub fn grapheme_width(grapheme: &str) -> u32 {
+    let mut chars = grapheme.chars();
+    let Some(first) = chars.next() else {
+        return 0;
+    };
+    let is_regional_indicator = ('\u{1f1e6}'..='\u{1f1ff}').contains(&first);
+    if is_regional_indicator
+        || grapheme.contains(EMOJI_PRESENTATION_SELECTOR)
+        || grapheme.chars().any(|c| char_width(c) == 2)
+    {
+        2
+    } else {
+        char_width(first)
+    }
+}
+
+/// The number of extended grapheme clusters in the text, i.e. the characters as the user
+/// perceives them.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Measures the width of text one character at a time, for the callers that can't segment it
+/// into grapheme clusters, such as the ones iterating over a rope's chunks. The characters
+/// joined to a preceding emoji count as zero columns, and the emoji presentation selector
+/// widens a narrow character.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WidthCounter {
+    last_width: u32,
+    joining: bool,
+}
+
+impl WidthCounter {
+    /// Returns the number of columns the character adds to the text measured so far.
+    pub fn width(&mut self, c: char) -> u32 {
+        if self.joining {
+            self.joining = false;
+            return 0;
+        }
+        match c {
+            ZERO_WIDTH_JOINER => {
+                self.joining = true;
+                0
+            }
+            EMOJI_PRESENTATION_SELECTOR if self.last_width == 1 => {
+                self.last_width = 2;
+                1
+            }
+            _ => {
+                let width = char_width(c);
+                if width > 0 {
+                    self.last_width = width;
+                }
+                width
+            }
+        }
+    }
+}
@@ -193,6 +193,16 @@ messages!(
     (GetDocumentHighlightsResponse, Background),
     (GetHover, Background),
     (GetHoverResponse, Background),
+    (GetSignatureHelp, Background),
+    (GetSignatureHelpResponse, Background),
+    (GetIncomingCalls, Background),
+    (GetIncomingCallsResponse, Background),
+    (GetOutgoingCalls, Background),
+    (GetOutgoingCallsResponse, Background),
+    (GetCodeLens, Background),
+    (GetCodeLensResponse, Background),
+    (GetDocumentColor, Background),
+    (GetDocumentColorResponse, Background),
     (GetNotifications, Foreground),
     (GetNotificationsResponse, Foreground),
     (GetPrivateUserInfo, Foreground),
@@ -240,6 +250,8 @@ messages!(
     (PerformRename, Background),
     (PerformRenameResponse, Background),
     (Ping, Foreground),
+    (PrepareCallHierarchy, Background),
+    (PrepareCallHierarchyResponse, Background),
     (PrepareRename, Background),
     (PrepareRenameResponse, Background),
     (ProjectEntryResponse, Foreground),
@@ -365,6 +377,11 @@ request_messages!(
     (GetImplementation, GetImplementationResponse),
     (GetDocumentHighlights, GetDocumentHighlightsResponse),
     (GetHover, GetHoverResponse),
+    (GetSignatureHelp, GetSignatureHelpResponse),
+    (GetIncomingCalls, GetIncomingCallsResponse),
+    (GetOutgoingCalls, GetOutgoingCallsResponse),
+    (GetCodeLens, GetCodeLensResponse),
+    (GetDocumentColor, GetDocumentColorResponse),
     (GetNotifications, GetNotificationsResponse),
     (GetPrivateUserInfo, GetPrivateUserInfoResponse),
     (GetProjectSymbols, GetProjectSymbolsResponse),
@@ -392,6 +409,7 @@ request_messages!(
     (OpenNewBuffer, OpenBufferResponse),
     (PerformRename, PerformRenameResponse),
     (Ping, Ack),
+    (PrepareCallHierarchy, PrepareCallHierarchyResponse),
     (PrepareRename, PrepareRenameResponse),
     (RefreshInlayHints, Ack),
     (RejoinChannelBuffers, RejoinChannelBuffersResponse),
@@ -465,6 +483,11 @@ entity_messages!(
     GetHover,
     GetProjectSymbols,
     GetReferences,
+    GetSignatureHelp,
+    GetIncomingCalls,
+    GetOutgoingCalls,
+    GetCodeLens,
+    GetDocumentColor,
     GetTypeDefinition,
     InlayHints,
     JoinProject,
@@ -476,6 +499,7 @@ entity_messages!(
     OpenBufferByPath,
     OpenBufferForSymbol,
     PerformRename,
+    PrepareCallHierarchy,
     PrepareRename,
     RefreshInlayHints,
     ReloadBuffers,
@@ -607,7 +607,7 @@ impl WorktreeIndex {
                 .peekable();
 
             let mut deletion_range: Option<(Bound<&str>, Bound<&str>)> = None;
-            for entry in worktree.files(false, 0) {
+            for entry in worktree.files(false, true, 0) {
                 let entry_db_key = db_key_for_path(&entry.path);
 
                 let mut saved_mtime = None;
@@ -65,6 +65,13 @@ impl<T: PartialEq + 'static + Sync> TrackedFile<T> {
         Self { parsed_contents }
     }
 
+    /// Initializes new [`TrackedFile`] with contents that aren't read from a file, and never change.
+    pub fn new_static(contents: T) -> Self {
+        Self {
+            parsed_contents: Arc::new(RwLock::new(contents)),
+        }
+    }
+
     /// Initializes new [`TrackedFile`] with a type that's convertible from another deserializable type.
     pub fn new_convertible<U: for<'a> Deserialize<'a> + TryInto<T, Error = anyhow::Error>>(
         mut tracker: UnboundedReceiver<String>,
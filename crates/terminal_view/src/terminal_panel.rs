@@ -490,19 +490,24 @@ impl TerminalPanel {
 
         cx.spawn(|terminal_panel, mut cx| async move {
             let pane = terminal_panel.update(&mut cx, |this, _| this.pane.clone())?;
+            let terminal = workspace
+                .update(&mut cx, |workspace, cx| {
+                    let working_directory = if let Some(working_directory) = working_directory {
+                        Some(working_directory)
+                    } else {
+                        let working_directory_strategy =
+                            TerminalSettings::get_global(cx).working_directory.clone();
+                        crate::get_working_directory(workspace, cx, working_directory_strategy)
+                    };
+
+                    let window = cx.window_handle();
+                    workspace.project().update(cx, |project, cx| {
+                        project.create_terminal(working_directory, spawn_task, window, cx)
+                    })
+                })?
+                .await;
             let result = workspace.update(&mut cx, |workspace, cx| {
-                let working_directory = if let Some(working_directory) = working_directory {
-                    Some(working_directory)
-                } else {
-                    let working_directory_strategy =
-                        TerminalSettings::get_global(cx).working_directory.clone();
-                    crate::get_working_directory(workspace, cx, working_directory_strategy)
-                };
-
-                let window = cx.window_handle();
-                let terminal = workspace.project().update(cx, |project, cx| {
-                    project.create_terminal(working_directory, spawn_task, window, cx)
-                })?;
+                let terminal = terminal?;
                 let terminal_view = Box::new(cx.new_view(|cx| {
                     TerminalView::new(
                         terminal.clone(),
@@ -588,27 +593,28 @@ impl TerminalPanel {
         let reveal = spawn_task.reveal;
         let window = cx.window_handle();
         let new_terminal = project.update(cx, |project, cx| {
-            project
-                .create_terminal(spawn_task.cwd.clone(), Some(spawn_task), window, cx)
-                .log_err()
-        })?;
-        terminal_to_replace.update(cx, |terminal_to_replace, cx| {
-            terminal_to_replace.set_terminal(new_terminal, cx);
+            project.create_terminal(spawn_task.cwd.clone(), Some(spawn_task), window, cx)
         });
+        let task_workspace = self.workspace.clone();
+        cx.spawn(|terminal_panel, mut cx| async move {
+            let new_terminal = new_terminal.await?;
+            terminal_to_replace.update(&mut cx, |terminal_to_replace, cx| {
+                terminal_to_replace.set_terminal(new_terminal, cx);
+            })?;
 
-        match reveal {
-            RevealStrategy::Always => {
-                self.activate_terminal_view(terminal_item_index, cx);
-                let task_workspace = self.workspace.clone();
-                cx.spawn(|_, mut cx| async move {
+            match reveal {
+                RevealStrategy::Always => {
+                    terminal_panel.update(&mut cx, |terminal_panel, cx| {
+                        terminal_panel.activate_terminal_view(terminal_item_index, cx);
+                    })?;
                     task_workspace
-                        .update(&mut cx, |workspace, cx| workspace.focus_panel::<Self>(cx))
-                        .ok()
-                })
-                .detach();
+                        .update(&mut cx, |workspace, cx| workspace.focus_panel::<Self>(cx))?;
+                }
+                RevealStrategy::Never => {}
             }
-            RevealStrategy::Never => {}
-        }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
 
         Some(())
     }
@@ -119,24 +119,26 @@ impl TerminalView {
             get_working_directory(workspace, cx, strategy.working_directory.clone());
 
         let window = cx.window_handle();
-        let terminal = workspace
-            .project()
-            .update(cx, |project, cx| {
-                project.create_terminal(working_directory, None, window, cx)
+        let terminal = workspace.project().update(cx, |project, cx| {
+            project.create_terminal(working_directory, None, window, cx)
+        });
+        cx.spawn(|workspace, mut cx| async move {
+            let terminal = terminal.await;
+            workspace.update(&mut cx, |workspace, cx| {
+                if let Some(terminal) = terminal.notify_err(workspace, cx) {
+                    let view = cx.new_view(|cx| {
+                        TerminalView::new(
+                            terminal,
+                            workspace.weak_handle(),
+                            workspace.database_id(),
+                            cx,
+                        )
+                    });
+                    workspace.add_item_to_active_pane(Box::new(view), None, cx)
+                }
             })
-            .notify_err(workspace, cx);
-
-        if let Some(terminal) = terminal {
-            let view = cx.new_view(|cx| {
-                TerminalView::new(
-                    terminal,
-                    workspace.weak_handle(),
-                    workspace.database_id(),
-                    cx,
-                )
-            });
-            workspace.add_item_to_active_pane(Box::new(view), None, cx)
-        }
+        })
+        .detach_and_log_err(cx);
     }
 
     pub fn new(
@@ -902,9 +904,11 @@ impl Item for TerminalView {
                 .ok()
                 .flatten();
 
-            let terminal = project.update(&mut cx, |project, cx| {
-                project.create_terminal(cwd, None, window, cx)
-            })??;
+            let terminal = project
+                .update(&mut cx, |project, cx| {
+                    project.create_terminal(cwd, None, window, cx)
+                })?
+                .await?;
             pane.update(&mut cx, |_, cx| {
                 cx.new_view(|cx| TerminalView::new(terminal, workspace, workspace_id, cx))
             })
@@ -0,0 +1,611 @@
+mod test_explorer_settings;
+mod test_index;
+mod test_runner;
+
+use anyhow::Result;
+use collections::HashMap;
+use db::kvp::KEY_VALUE_STORE;
+use editor::{scroll::Autoscroll, Editor, EditorEvent};
+use gpui::{
+    actions, uniform_list, Action, AppContext, AsyncWindowContext, EntityId, EventEmitter,
+    FocusHandle, FocusableView, InteractiveElement, IntoElement, Model, ParentElement, Render,
+    Styled, Subscription, Task, UniformListScrollHandle, View, ViewContext, VisualContext,
+    WeakView, WindowContext,
+};
+use language::Point;
+use menu::{Confirm, SelectNext, SelectPrev};
+use project::Fs;
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use std::sync::Arc;
+use ui::{prelude::*, IconButton, Label, ListItem, Tooltip};
+use util::ResultExt;
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+pub use test_explorer_settings::*;
+pub use test_index::*;
+pub use test_runner::*;
+
+const TEST_EXPLORER_PANEL_KEY: &str = "TestExplorerPanel";
+
+actions!(
+    test_explorer,
+    [ToggleFocus, RefreshTests, RunSelectedTest, RunAllTests]
+);
+
+pub fn init(cx: &mut AppContext) {
+    TestExplorerSettings::register(cx);
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace
+            .register_action(|workspace, _: &ToggleFocus, cx| {
+                workspace.toggle_panel_focus::<TestExplorerPanel>(cx);
+            })
+            .register_action(|workspace, _: &RunAllTests, cx| {
+                if let Some(panel) = workspace.panel::<TestExplorerPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.run_all_tests(&RunAllTests, cx));
+                }
+            });
+    })
+    .detach();
+}
+
+/// Highlights the rows where tests are defined with their last result, and the rows where
+/// failed tests failed.
+enum TestStatusHighlight {}
+
+pub struct TestExplorerPanel {
+    workspace: WeakView<Workspace>,
+    fs: Arc<dyn Fs>,
+    index: Model<TestIndex>,
+    selected: Option<TestId>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+    width: Option<Pixels>,
+    pending_serialization: Task<Option<()>>,
+    editor_subscriptions: HashMap<EntityId, Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedTestExplorerPanel {
+    width: Option<Pixels>,
+}
+
+impl TestExplorerPanel {
+    pub fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let fs = workspace.app_state().fs.clone();
+        let workspace_handle = cx.view().clone();
+        cx.new_view(|cx| {
+            let index = cx.new_model(|cx| {
+                let mut index = TestIndex::new(project);
+                index.refresh(cx);
+                index
+            });
+            let subscriptions = vec![
+                cx.observe(&index, |this, _, cx| {
+                    this.update_highlights(cx);
+                    cx.notify();
+                }),
+                cx.subscribe(&index, |this, _, event, cx| match event {
+                    TestIndexEvent::SpawnTask(spawn) => {
+                        let spawn = spawn.clone();
+                        this.workspace
+                            .update(cx, |_, cx| cx.emit(workspace::Event::SpawnTask(spawn)))
+                            .log_err();
+                    }
+                }),
+                cx.subscribe(&workspace_handle, |this, _, event, cx| {
+                    if let workspace::Event::ItemAdded = event {
+                        this.discover_tests(cx);
+                    }
+                }),
+            ];
+            // The workspace is being updated while the panel is created, so its editors are
+            // read afterwards.
+            cx.defer(|this, cx| this.discover_tests(cx));
+            Self {
+                workspace: workspace_handle.downgrade(),
+                fs,
+                index,
+                selected: None,
+                scroll_handle: UniformListScrollHandle::new(),
+                focus_handle: cx.focus_handle(),
+                width: None,
+                pending_serialization: Task::ready(None),
+                editor_subscriptions: HashMap::default(),
+                _subscriptions: subscriptions,
+            }
+        })
+    }
+
+    pub fn load(
+        workspace: WeakView<Workspace>,
+        cx: AsyncWindowContext,
+    ) -> Task<Result<View<Self>>> {
+        cx.spawn(|mut cx| async move {
+            let serialized_panel = if let Some(panel) = cx
+                .background_executor()
+                .spawn(async move { KEY_VALUE_STORE.read_kvp(TEST_EXPLORER_PANEL_KEY) })
+                .await
+                .log_err()
+                .flatten()
+            {
+                Some(serde_json::from_str::<SerializedTestExplorerPanel>(&panel)?)
+            } else {
+                None
+            };
+
+            workspace.update(&mut cx, |workspace, cx| {
+                let panel = Self::new(workspace, cx);
+                if let Some(serialized_panel) = serialized_panel {
+                    panel.update(cx, |panel, cx| {
+                        panel.width = serialized_panel.width.map(|w| w.round());
+                        cx.notify();
+                    });
+                }
+                panel
+            })
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let width = self.width;
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        TEST_EXPLORER_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedTestExplorerPanel { width })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    pub fn index(&self) -> &Model<TestIndex> {
+        &self.index
+    }
+
+    /// Finds the tests in the buffers of the workspace's editors, rediscovering them whenever
+    /// an editor is saved.
+    fn discover_tests(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(editors) = self
+            .workspace
+            .update(cx, |workspace, cx| {
+                workspace.items_of_type::<Editor>(cx).collect::<Vec<_>>()
+            })
+            .ok()
+        else {
+            return;
+        };
+        self.editor_subscriptions.retain(|editor_id, _| {
+            editors
+                .iter()
+                .any(|editor| editor.entity_id() == *editor_id)
+        });
+        for editor in editors {
+            if self.editor_subscriptions.contains_key(&editor.entity_id()) {
+                continue;
+            }
+            let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
+                continue;
+            };
+            self.index
+                .update(cx, |index, cx| index.discover_in_buffer(&buffer, cx));
+            let subscription = cx.subscribe(&editor, move |this, _, event, cx| {
+                if let EditorEvent::Saved = event {
+                    this.index
+                        .update(cx, |index, cx| index.discover_in_buffer(&buffer, cx));
+                }
+            });
+            self.editor_subscriptions
+                .insert(editor.entity_id(), subscription);
+        }
+        self.update_highlights(cx);
+    }
+
+    /// Highlights the tests' definitions in the workspace's editors with their last results.
+    fn update_highlights(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(editors) = self
+            .workspace
+            .update(cx, |workspace, cx| {
+                workspace.items_of_type::<Editor>(cx).collect::<Vec<_>>()
+            })
+            .ok()
+        else {
+            return;
+        };
+        let status_colors = cx.theme().status();
+        let (passed, failed) = (
+            status_colors.success_background,
+            status_colors.error_background,
+        );
+        let tests = self.index.read(cx).tests().to_vec();
+        for editor in editors {
+            editor.update(cx, |editor, cx| {
+                editor.clear_row_highlights::<TestStatusHighlight>();
+                let Some(abs_path) = editor.buffer().read(cx).as_singleton().and_then(|buffer| {
+                    let file = buffer.read(cx).file()?.as_local()?;
+                    Some(file.abs_path(cx))
+                }) else {
+                    return;
+                };
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let mut highlight = |row: u32, color| {
+                    let position = snapshot.anchor_before(Point::new(row, 0));
+                    editor.highlight_rows::<TestStatusHighlight>(
+                        position..=position,
+                        Some(color),
+                        false,
+                        cx,
+                    );
+                };
+                for test in &tests {
+                    let color = match test.status {
+                        TestStatus::Passed => passed,
+                        TestStatus::Failed => failed,
+                        _ => continue,
+                    };
+                    if let Some(definition) = &test.definition {
+                        if definition.abs_path == abs_path {
+                            highlight(definition.row, color);
+                        }
+                    }
+                    if let Some(location) = test
+                        .failure
+                        .as_ref()
+                        .and_then(|failure| failure.location.as_ref())
+                    {
+                        if location.abs_path == abs_path {
+                            highlight(location.row, failed);
+                        }
+                    }
+                }
+                cx.notify();
+            });
+        }
+    }
+
+    fn refresh_tests(&mut self, _: &RefreshTests, cx: &mut ViewContext<Self>) {
+        self.index.update(cx, |index, cx| index.refresh(cx));
+    }
+
+    fn run_selected_test(&mut self, _: &RunSelectedTest, cx: &mut ViewContext<Self>) {
+        if let Some(selected) = self.selected {
+            self.index
+                .update(cx, |index, cx| index.run(Some(&[selected]), cx));
+        }
+    }
+
+    fn run_all_tests(&mut self, _: &RunAllTests, cx: &mut ViewContext<Self>) {
+        self.index.update(cx, |index, cx| index.run(None, cx));
+    }
+
+    fn select_next(&mut self, _: &SelectNext, cx: &mut ViewContext<Self>) {
+        self.select_offset(1, cx);
+    }
+
+    fn select_prev(&mut self, _: &SelectPrev, cx: &mut ViewContext<Self>) {
+        self.select_offset(-1, cx);
+    }
+
+    fn select_offset(&mut self, offset: isize, cx: &mut ViewContext<Self>) {
+        let tests = self.index.read(cx).tests();
+        if tests.is_empty() {
+            return;
+        }
+        let ix = self
+            .selected
+            .and_then(|selected| tests.iter().position(|test| test.id == selected))
+            .map_or(0, |ix| {
+                ix.saturating_add_signed(offset).min(tests.len() - 1)
+            });
+        self.selected = Some(tests[ix].id);
+        self.scroll_handle.scroll_to_item(ix);
+        cx.notify();
+    }
+
+    fn confirm(&mut self, _: &Confirm, cx: &mut ViewContext<Self>) {
+        if let Some(selected) = self.selected {
+            self.open_test(selected, cx);
+        }
+    }
+
+    /// Opens the location where the test failed, or where it's defined.
+    fn open_test(&mut self, id: TestId, cx: &mut ViewContext<Self>) {
+        let Some(test) = self.index.read(cx).test(id) else {
+            return;
+        };
+        let Some(location) = test
+            .failure
+            .as_ref()
+            .and_then(|failure| failure.location.clone())
+            .or_else(|| test.definition.clone())
+        else {
+            return;
+        };
+        let Some(open_task) = self
+            .workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_abs_path(location.abs_path.clone(), true, cx)
+            })
+            .log_err()
+        else {
+            return;
+        };
+        cx.spawn(|_, mut cx| async move {
+            let item = open_task.await?;
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor.update(&mut cx, |editor, cx| {
+                    let point = Point::new(location.row, location.column);
+                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                        s.select_ranges([point..point])
+                    });
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_test(&self, ix: usize, test: &TestEntry, cx: &mut ViewContext<Self>) -> ListItem {
+        let id = test.id;
+        let (icon, color) = match test.status {
+            TestStatus::NotRun => (IconName::Dash, Color::Muted),
+            TestStatus::Running => (IconName::ArrowCircle, Color::Accent),
+            TestStatus::Passed => (IconName::Check, Color::Success),
+            TestStatus::Failed => (IconName::XCircle, Color::Error),
+            TestStatus::Ignored => (IconName::Dash, Color::Disabled),
+        };
+        ListItem::new(ix)
+            .selected(self.selected == Some(id))
+            .start_slot(Icon::new(icon).size(IconSize::Small).color(color))
+            .on_click(cx.listener(move |this, _, cx| {
+                this.selected = Some(id);
+                this.open_test(id, cx);
+                cx.notify();
+            }))
+            .child(
+                h_flex().gap_2().child(Label::new(test.name.clone())).child(
+                    Label::new(test.runner.name.clone())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+            )
+    }
+}
+
+impl Render for TestExplorerPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let index = self.index.read(cx);
+        let tests = index.tests().to_vec();
+        let is_refreshing = index.is_refreshing();
+        let failure = self
+            .selected
+            .and_then(|selected| index.test(selected))
+            .and_then(|test| test.failure.clone());
+
+        v_flex()
+            .key_context("TestExplorerPanel")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::refresh_tests))
+            .on_action(cx.listener(Self::run_selected_test))
+            .on_action(cx.listener(Self::run_all_tests))
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_prev))
+            .on_action(cx.listener(Self::confirm))
+            .size_full()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .px_2()
+                    .py_1()
+                    // Match the height of the tab bar so they line up.
+                    .h(rems(ui::Tab::CONTAINER_HEIGHT_IN_REMS))
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new("Tests"))
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                IconButton::new("refresh_tests", IconName::Update)
+                                    .icon_size(IconSize::Small)
+                                    .tooltip(|cx| {
+                                        Tooltip::for_action("Refresh Tests", &RefreshTests, cx)
+                                    })
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.refresh_tests(&RefreshTests, cx)
+                                    })),
+                            )
+                            .child(
+                                IconButton::new("run_selected_test", IconName::Play)
+                                    .icon_size(IconSize::Small)
+                                    .disabled(self.selected.is_none())
+                                    .tooltip(|cx| {
+                                        Tooltip::for_action(
+                                            "Run Selected Test",
+                                            &RunSelectedTest,
+                                            cx,
+                                        )
+                                    })
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.run_selected_test(&RunSelectedTest, cx)
+                                    })),
+                            )
+                            .child(
+                                IconButton::new("run_all_tests", IconName::HistoryRerun)
+                                    .icon_size(IconSize::Small)
+                                    .tooltip(|cx| {
+                                        Tooltip::for_action("Run All Tests", &RunAllTests, cx)
+                                    })
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.run_all_tests(&RunAllTests, cx)
+                                    })),
+                            ),
+                    ),
+            )
+            .map(|this| {
+                if tests.is_empty() {
+                    let message = if is_refreshing {
+                        "Loading…"
+                    } else {
+                        "No tests were found by the configured test runners."
+                    };
+                    this.child(
+                        v_flex()
+                            .p_4()
+                            .child(Label::new(message).color(Color::Muted)),
+                    )
+                } else {
+                    this.child(
+                        uniform_list(
+                            cx.view().clone(),
+                            "test_explorer_tests",
+                            tests.len(),
+                            move |this, range, cx| {
+                                range
+                                    .map(|ix| this.render_test(ix, &tests[ix], cx))
+                                    .collect()
+                            },
+                        )
+                        .size_full()
+                        .track_scroll(self.scroll_handle.clone()),
+                    )
+                }
+            })
+            .children(failure.map(|failure| {
+                v_flex()
+                    .p_2()
+                    .max_h(rems(12.))
+                    .overflow_hidden()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(
+                        Label::new(failure.message)
+                            .size(LabelSize::Small)
+                            .color(Color::Error),
+                    )
+            }))
+    }
+}
+
+impl FocusableView for TestExplorerPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for TestExplorerPanel {}
+
+impl Panel for TestExplorerPanel {
+    fn persistent_name() -> &'static str {
+        "TestExplorerPanel"
+    }
+
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        TestExplorerSettings::get_global(cx).dock
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<TestExplorerSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings| settings.dock = Some(position),
+        );
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.width
+            .unwrap_or_else(|| TestExplorerSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.width = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        TestExplorerSettings::get_global(cx)
+            .button
+            .then_some(IconName::Play)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Test Explorer")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::{FakeFs, Project};
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[gpui::test]
+    async fn test_run_all_tests_spawns_task(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings = SettingsStore::test(cx);
+            cx.set_global(settings);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            editor::init_settings(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            crate::init(cx);
+            // A runner that can't list its tests, so that no command runs when refreshing.
+            let runner = serde_json::from_value::<TestRunnerConfig>(json!({
+                "name": "cargo",
+                "detect": "Cargo.toml",
+                "format": "libtest",
+                "run": { "command": "cargo", "args": ["test"] },
+                "run_all": { "command": "cargo", "args": ["test"] }
+            }))
+            .unwrap();
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<TestExplorerSettings>(cx, |settings| {
+                    settings.runners = Some(vec![runner]);
+                });
+            });
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/crate", json!({ "Cargo.toml": "" })).await;
+        let project = Project::test(fs, ["/crate".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+        let panel = workspace.update(cx, |workspace, cx| TestExplorerPanel::new(workspace, cx));
+
+        let spawned_labels = Rc::new(RefCell::new(Vec::new()));
+        cx.update(|cx| {
+            let spawned_labels = spawned_labels.clone();
+            cx.subscribe(&workspace, move |_, event, _| {
+                if let workspace::Event::SpawnTask(spawn) = event {
+                    spawned_labels.borrow_mut().push(spawn.label.clone());
+                }
+            })
+            .detach();
+        });
+
+        panel.update(cx, |panel, cx| panel.run_all_tests(&RunAllTests, cx));
+        cx.run_until_parked();
+        assert_eq!(*spawned_labels.borrow(), ["cargo all tests"]);
+    }
+}
@@ -0,0 +1,48 @@
+use gpui::Pixels;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+use workspace::dock::DockPosition;
+
+use crate::TestRunnerConfig;
+
+#[derive(Deserialize, Debug)]
+pub struct TestExplorerSettings {
+    pub button: bool,
+    pub dock: DockPosition,
+    pub default_width: Pixels,
+    pub runners: Vec<TestRunnerConfig>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct TestExplorerSettingsContent {
+    /// Whether to show the test explorer panel button in the status bar.
+    ///
+    /// Default: true
+    pub button: Option<bool>,
+    /// Where to dock the test explorer panel.
+    ///
+    /// Default: left
+    pub dock: Option<DockPosition>,
+    /// Default width of the test explorer panel in pixels.
+    ///
+    /// Default: 320
+    pub default_width: Option<f32>,
+    /// The test runners that discover and run the tests of worktrees.
+    ///
+    /// Default: runners for cargo and jest
+    pub runners: Option<Vec<TestRunnerConfig>>,
+}
+
+impl Settings for TestExplorerSettings {
+    const KEY: Option<&'static str> = Some("test_explorer");
+
+    type FileContent = TestExplorerSettingsContent;
+
+    fn load(
+        sources: SettingsSources<Self::FileContent>,
+        _: &mut gpui::AppContext,
+    ) -> anyhow::Result<Self> {
+        sources.json_merge()
+    }
+}
@@ -0,0 +1,567 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Output, Stdio},
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use futures::{future, StreamExt as _};
+use gpui::{AppContext, EventEmitter, Model, ModelContext, Task};
+use language::{Anchor, Buffer, File as _, LocalFile as _};
+use project::{File, Fs, Project, RemoveOptions, WorktreeId, FS_WATCH_LATENCY};
+use settings::Settings;
+use task::SpawnInTerminal;
+use util::{paths::TEMP_DIR, ResultExt};
+
+use crate::{
+    parse_test_list, parse_test_results, ResolvedTestCommand, TestExplorerSettings, TestFailure,
+    TestLocation, TestOutcome, TestRunnerConfig, TestStatus,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TestId(usize);
+
+#[derive(Clone, Debug)]
+pub struct TestEntry {
+    pub id: TestId,
+    pub name: String,
+    pub worktree_id: WorktreeId,
+    pub runner: TestRunnerConfig,
+    /// Where the test is defined, once it's been found in an open buffer.
+    pub definition: Option<TestLocation>,
+    pub status: TestStatus,
+    pub failure: Option<TestFailure>,
+    working_dir: PathBuf,
+}
+
+/// The tests of a project's worktrees, as listed by the runners that apply to each worktree
+/// and as found in the syntax of open buffers, along with the results of their last runs.
+pub struct TestIndex {
+    project: Model<Project>,
+    tests: Vec<TestEntry>,
+    next_test_id: usize,
+    next_run_id: usize,
+    refresh_task: Option<Task<()>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum TestIndexEvent {
+    /// Tests are run as tasks in a terminal, which whoever shows the index spawns.
+    SpawnTask(SpawnInTerminal),
+}
+
+impl EventEmitter<TestIndexEvent> for TestIndex {}
+
+impl TestIndex {
+    pub fn new(project: Model<Project>) -> Self {
+        Self {
+            project,
+            tests: Vec::new(),
+            next_test_id: 0,
+            next_run_id: 0,
+            refresh_task: None,
+        }
+    }
+
+    /// The tests, ordered by worktree, runner and name.
+    pub fn tests(&self) -> &[TestEntry] {
+        &self.tests
+    }
+
+    pub fn test(&self, id: TestId) -> Option<&TestEntry> {
+        self.tests.iter().find(|test| test.id == id)
+    }
+
+    pub fn is_refreshing(&self) -> bool {
+        self.refresh_task.is_some()
+    }
+
+    /// The runners that apply to each of the project's local worktrees, with the worktrees'
+    /// root directories.
+    fn worktree_runners(&self, cx: &AppContext) -> Vec<(WorktreeId, PathBuf, TestRunnerConfig)> {
+        let runners = &TestExplorerSettings::get_global(cx).runners;
+        let mut worktree_runners = Vec::new();
+        for worktree in self.project.read(cx).visible_worktrees(cx) {
+            let Some(worktree) = worktree.read(cx).as_local() else {
+                continue;
+            };
+            for runner in runners {
+                let detected = runner
+                    .detect
+                    .as_ref()
+                    .map_or(true, |path| worktree.entry_for_path(path).is_some());
+                if detected {
+                    worktree_runners.push((
+                        worktree.id(),
+                        worktree.abs_path().to_path_buf(),
+                        runner.clone(),
+                    ));
+                }
+            }
+        }
+        worktree_runners
+    }
+
+    /// Lists the tests of every worktree with the runners that can list them, in the
+    /// worktrees' environments.
+    pub fn refresh(&mut self, cx: &mut ModelContext<Self>) {
+        let listings = self
+            .worktree_runners(cx)
+            .into_iter()
+            .filter_map(|(worktree_id, working_dir, runner)| {
+                let list = runner.list.as_ref()?.resolve(
+                    format!("list {} tests", runner.name),
+                    None,
+                    &working_dir,
+                )?;
+                let environment = self.project.read(cx).worktree_environment(worktree_id, cx);
+                Some(async move {
+                    let environment = environment.await;
+                    let output = run_command(&list, environment).await.and_then(|output| {
+                        if output.status.success() {
+                            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+                        } else {
+                            Err(anyhow!(
+                                "{} exited with {}:\n{}",
+                                list.label,
+                                output.status,
+                                String::from_utf8_lossy(&output.stderr)
+                            ))
+                        }
+                    });
+                    (worktree_id, working_dir, runner, output)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        self.refresh_task = Some(cx.spawn(|this, mut cx| async move {
+            let listings = future::join_all(listings).await;
+            this.update(&mut cx, |this, cx| {
+                for (worktree_id, working_dir, runner, output) in listings {
+                    let Some(output) = output.log_err() else {
+                        continue;
+                    };
+                    let names = parse_test_list(runner.format, &output);
+                    this.tests.retain(|test| {
+                        test.worktree_id != worktree_id
+                            || test.runner.name != runner.name
+                            || names.contains(&test.name)
+                    });
+                    for name in names {
+                        this.insert_test(worktree_id, &working_dir, &runner, name);
+                    }
+                }
+                this.refresh_task = None;
+                cx.notify();
+            })
+            .ok();
+        }));
+        cx.notify();
+    }
+
+    /// Finds the tests defined in the buffer, to know where they are. Tests that no runner
+    /// has listed are added to the runners that can't list tests.
+    pub fn discover_in_buffer(&mut self, buffer: &Model<Buffer>, cx: &mut ModelContext<Self>) {
+        let buffer = buffer.read(cx);
+        let Some(file) = File::from_dyn(buffer.file()) else {
+            return;
+        };
+        let Some(abs_path) = file.as_local().map(|file| file.abs_path(cx)) else {
+            return;
+        };
+        let worktree_id = file.worktree_id(cx);
+        let runners = self
+            .worktree_runners(cx)
+            .into_iter()
+            .filter(|(id, _, _)| *id == worktree_id)
+            .collect::<Vec<_>>();
+        if runners.is_empty() {
+            return;
+        }
+
+        let snapshot = buffer.snapshot();
+        let definitions = snapshot
+            .runnable_ranges(Anchor::MIN..Anchor::MAX)
+            .filter(|runnable| {
+                runnable
+                    .runnable
+                    .tags
+                    .iter()
+                    .any(|tag| tag.0.ends_with("-test") && !tag.0.ends_with("-mod-test"))
+            })
+            .map(|runnable| {
+                let start = snapshot.offset_to_point(runnable.run_range.start);
+                let name = snapshot
+                    .text_for_range(runnable.run_range)
+                    .collect::<String>();
+                (
+                    name,
+                    TestLocation {
+                        abs_path: abs_path.clone(),
+                        row: start.row,
+                        column: start.column,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for test in &mut self.tests {
+            if test
+                .definition
+                .as_ref()
+                .map_or(false, |definition| definition.abs_path == abs_path)
+            {
+                test.definition = None;
+            }
+        }
+        for (name, definition) in definitions {
+            let listed_test = self.tests.iter_mut().find(|test| {
+                test.worktree_id == worktree_id
+                    && test.definition.is_none()
+                    && (test.name == name || test.name.ends_with(&format!("::{name}")))
+            });
+            if let Some(test) = listed_test {
+                test.definition = Some(definition);
+                continue;
+            }
+            for (_, working_dir, runner) in &runners {
+                if runner.list.is_none() {
+                    let id = self.insert_test(worktree_id, working_dir, runner, name.clone());
+                    if let Some(test) = self.test_mut(id) {
+                        test.definition = Some(definition.clone());
+                    }
+                }
+            }
+        }
+        cx.notify();
+    }
+
+    /// Runs the given tests, or all of the tests of every worktree when `test_ids` is `None`,
+    /// as tasks in a terminal.
+    pub fn run(&mut self, test_ids: Option<&[TestId]>, cx: &mut ModelContext<Self>) {
+        let mut runs = Vec::new();
+        match test_ids {
+            Some(test_ids) => {
+                for test in self.tests.iter().filter(|test| test_ids.contains(&test.id)) {
+                    let label = format!("{} {}", test.runner.name, test.name);
+                    if let Some(command) =
+                        test.runner
+                            .run
+                            .resolve(label, Some(&test.name), &test.working_dir)
+                    {
+                        runs.push((
+                            command,
+                            test.worktree_id,
+                            test.runner.clone(),
+                            vec![test.id],
+                        ));
+                    }
+                }
+            }
+            None => {
+                for (worktree_id, working_dir, runner) in self.worktree_runners(cx) {
+                    let label = format!("{} all tests", runner.name);
+                    if let Some(command) = runner.run_all.resolve(label, None, &working_dir) {
+                        let test_ids = self
+                            .tests
+                            .iter()
+                            .filter(|test| {
+                                test.worktree_id == worktree_id && test.runner.name == runner.name
+                            })
+                            .map(|test| test.id)
+                            .collect();
+                        runs.push((command, worktree_id, runner, test_ids));
+                    }
+                }
+            }
+        }
+
+        for (command, worktree_id, runner, test_ids) in runs {
+            for test in &mut self.tests {
+                if test_ids.contains(&test.id) {
+                    test.status = TestStatus::Running;
+                    test.failure = None;
+                }
+            }
+            let fs = self.project.read(cx).fs().clone();
+            let run_id = post_inc(&mut self.next_run_id);
+            let run_dir = TEMP_DIR
+                .join("test_explorer")
+                .join(format!("{}-{run_id}", std::process::id()));
+            cx.spawn(|this, mut cx| async move {
+                let output = async {
+                    let output_path = run_dir.join("output");
+                    let done_path = run_dir.join("done");
+                    let spawn = command.spawn_in_terminal(&output_path, &done_path)?;
+                    fs.create_dir(&run_dir).await?;
+                    this.update(&mut cx, |_, cx| cx.emit(TestIndexEvent::SpawnTask(spawn)))?;
+                    wait_for_file(fs.as_ref(), &run_dir, &done_path).await;
+                    let output = fs.load(&output_path).await;
+                    fs.remove_dir(
+                        &run_dir,
+                        RemoveOptions {
+                            recursive: true,
+                            ignore_if_not_exists: true,
+                        },
+                    )
+                    .await
+                    .log_err();
+                    output
+                }
+                .await;
+                this.update(&mut cx, |this, cx| {
+                    let outcomes = match output {
+                        Ok(output) => {
+                            parse_test_results(runner.format, &output, &command.working_dir)
+                        }
+                        Err(error) => {
+                            log::error!("{error:?}");
+                            test_ids
+                                .iter()
+                                .filter_map(|id| this.test(*id))
+                                .map(|test| TestOutcome {
+                                    name: test.name.clone(),
+                                    status: TestStatus::Failed,
+                                    failure: Some(TestFailure {
+                                        message: format!("{error:#}"),
+                                        location: None,
+                                    }),
+                                })
+                                .collect()
+                        }
+                    };
+                    this.apply_outcomes(
+                        worktree_id,
+                        &command.working_dir,
+                        &runner,
+                        &test_ids,
+                        outcomes,
+                        cx,
+                    );
+                })
+                .ok();
+            })
+            .detach();
+        }
+        cx.notify();
+    }
+
+    fn apply_outcomes(
+        &mut self,
+        worktree_id: WorktreeId,
+        working_dir: &Path,
+        runner: &TestRunnerConfig,
+        test_ids: &[TestId],
+        outcomes: Vec<TestOutcome>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        for outcome in outcomes {
+            let id = self.insert_test(worktree_id, working_dir, runner, outcome.name);
+            if let Some(test) = self.test_mut(id) {
+                test.status = outcome.status;
+                test.failure = outcome.failure;
+            }
+        }
+        // Tests that the output didn't mention, such as ones filtered out by the runner.
+        for test in &mut self.tests {
+            if test_ids.contains(&test.id) && test.status == TestStatus::Running {
+                test.status = TestStatus::NotRun;
+            }
+        }
+        cx.notify();
+    }
+
+    fn test_mut(&mut self, id: TestId) -> Option<&mut TestEntry> {
+        self.tests.iter_mut().find(|test| test.id == id)
+    }
+
+    /// Adds a test unless it's indexed already, returning its id.
+    fn insert_test(
+        &mut self,
+        worktree_id: WorktreeId,
+        working_dir: &Path,
+        runner: &TestRunnerConfig,
+        name: String,
+    ) -> TestId {
+        let ix = self.tests.binary_search_by(|test| {
+            test.worktree_id
+                .cmp(&worktree_id)
+                .then_with(|| test.runner.name.cmp(&runner.name))
+                .then_with(|| test.name.cmp(&name))
+        });
+        match ix {
+            Ok(ix) => self.tests[ix].id,
+            Err(ix) => {
+                let id = TestId(post_inc(&mut self.next_test_id));
+                self.tests.insert(
+                    ix,
+                    TestEntry {
+                        id,
+                        name,
+                        worktree_id,
+                        runner: runner.clone(),
+                        definition: None,
+                        status: TestStatus::NotRun,
+                        failure: None,
+                        working_dir: working_dir.to_path_buf(),
+                    },
+                );
+                id
+            }
+        }
+    }
+}
+
+fn post_inc(value: &mut usize) -> usize {
+    let prev = *value;
+    *value += 1;
+    prev
+}
+
+/// Waits until the file at `path`, within the directory at `dir_path`, exists.
+async fn wait_for_file(fs: &dyn Fs, dir_path: &Path, path: &Path) {
+    let mut events = fs.watch(dir_path, FS_WATCH_LATENCY).await;
+    while !fs.is_file(path).await {
+        if events.next().await.is_none() {
+            break;
+        }
+    }
+}
+
+async fn run_command(
+    command: &ResolvedTestCommand,
+    environment: HashMap<String, String>,
+) -> Result<Output> {
+    smol::process::Command::new(&command.command)
+        .args(&command.args)
+        .current_dir(&command.working_dir)
+        .envs(environment)
+        .envs(&command.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .with_context(|| format!("failed to run {:?}", command.label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{Context, TestAppContext};
+    use indoc::indoc;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use task::TerminalWorkDir;
+
+    #[gpui::test]
+    async fn test_run_tests_in_terminal(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings = SettingsStore::test(cx);
+            cx.set_global(settings);
+            language::init(cx);
+            Project::init_settings(cx);
+            TestExplorerSettings::register(cx);
+            // A runner that can't list its tests, so that they're only found by running them.
+            let runner = serde_json::from_value::<TestRunnerConfig>(json!({
+                "name": "cargo",
+                "detect": "Cargo.toml",
+                "format": "libtest",
+                "run": {
+                    "command": "cargo",
+                    "args": ["test", "--", "--exact", "$ZED_CUSTOM_TEST_NAME"]
+                },
+                "run_all": { "command": "cargo", "args": ["test"] }
+            }))
+            .unwrap();
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<TestExplorerSettings>(cx, |settings| {
+                    settings.runners = Some(vec![runner]);
+                });
+            });
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/crate",
+            json!({ "Cargo.toml": "", "src": { "lib.rs": "" } }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/crate".as_ref()], cx).await;
+        let index = cx.new_model(|_| TestIndex::new(project));
+        let mut events = cx.events(&index);
+
+        index.update(cx, |index, cx| index.run(None, cx));
+        cx.run_until_parked();
+        let TestIndexEvent::SpawnTask(spawn) = events.try_next().unwrap().unwrap();
+        assert_eq!(spawn.cwd, Some(TerminalWorkDir::Local("/crate".into())));
+        assert!(spawn.command.starts_with("cargo test | tee "));
+
+        // The terminal writes the output of the command, then marks it as done.
+        let run_dir = TEMP_DIR
+            .join("test_explorer")
+            .join(format!("{}-0", std::process::id()));
+        fs.insert_file(
+            run_dir.join("output"),
+            indoc! {"
+                running 2 tests
+                test tests::adds ... ok
+                test tests::subtracts ... FAILED
+
+                failures:
+
+                ---- tests::subtracts stdout ----
+                thread 'tests::subtracts' panicked at src/lib.rs:12:9:
+                assertion failed
+
+                failures:
+                    tests::subtracts
+
+                test result: FAILED. 1 passed; 1 failed; 0 ignored
+            "}
+            .into(),
+        )
+        .await;
+        fs.insert_file(run_dir.join("done"), Vec::new()).await;
+        cx.run_until_parked();
+
+        index.read_with(cx, |index, _| {
+            assert_eq!(
+                index
+                    .tests()
+                    .iter()
+                    .map(|test| (test.name.as_str(), test.status))
+                    .collect::<Vec<_>>(),
+                [
+                    ("tests::adds", TestStatus::Passed),
+                    ("tests::subtracts", TestStatus::Failed),
+                ]
+            );
+            let failure = index.tests()[1].failure.as_ref().unwrap();
+            assert_eq!(
+                failure.location,
+                Some(TestLocation {
+                    abs_path: "/crate/src/lib.rs".into(),
+                    row: 11,
+                    column: 8,
+                })
+            );
+        });
+        assert!(!fs.is_dir(&run_dir).await);
+
+        // Running a single test spawns its own command.
+        let subtracts = index.read_with(cx, |index, _| index.tests()[1].id);
+        index.update(cx, |index, cx| index.run(Some(&[subtracts]), cx));
+        cx.run_until_parked();
+        let TestIndexEvent::SpawnTask(spawn) = events.try_next().unwrap().unwrap();
+        assert!(spawn
+            .command
+            .starts_with("cargo test -- --exact tests::subtracts | tee "));
+        index.read_with(cx, |index, _| {
+            assert_eq!(index.tests()[0].status, TestStatus::Passed);
+            assert_eq!(index.tests()[1].status, TestStatus::Running);
+        });
+    }
+}
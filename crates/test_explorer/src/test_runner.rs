@@ -0,0 +1,498 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use task::{
+    RevealStrategy, SpawnInTerminal, TaskContext, TaskId, TaskTemplate, TaskVariables,
+    TerminalWorkDir, VariableName,
+};
+
+/// The variable that holds the name of the test to run in a runner's `run` command, as
+/// `$ZED_CUSTOM_TEST_NAME`.
+pub const TEST_NAME_VARIABLE: VariableName = VariableName::Custom(Cow::Borrowed("TEST_NAME"));
+
+/// A test framework's commands for listing and running tests, and the format of their output.
+///
+/// ```json
+/// {
+///   "name": "cargo",
+///   "detect": "Cargo.toml",
+///   "format": "libtest",
+///   "list": { "command": "cargo", "args": ["test", "--", "--list", "--format", "terse"] },
+///   "run": { "command": "cargo", "args": ["test", "--", "--exact", "$ZED_CUSTOM_TEST_NAME"] },
+///   "run_all": { "command": "cargo", "args": ["test"] }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TestRunnerConfig {
+    pub name: String,
+    /// A file at the root of a worktree whose presence means that the runner applies to it.
+    /// When omitted, the runner applies to every worktree.
+    #[serde(default)]
+    pub detect: Option<String>,
+    pub format: TestOutputFormat,
+    /// Lists the tests without running them. Runners without it discover their tests from
+    /// the syntax of open files and from the results of running all of the tests.
+    #[serde(default)]
+    pub list: Option<TestCommand>,
+    /// Runs the test named `$ZED_CUSTOM_TEST_NAME`.
+    pub run: TestCommand,
+    /// Runs all of the tests.
+    pub run_all: TestCommand,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TestCommand {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TestOutputFormat {
+    /// The output of Rust's test harness, as printed by `cargo test`.
+    Libtest,
+    /// The report printed by `jest --json`.
+    Jest,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TestStatus {
+    #[default]
+    NotRun,
+    Running,
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// Why a test failed, and where, when the output says so.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestFailure {
+    pub message: String,
+    pub location: Option<TestLocation>,
+}
+
+/// A zero-based position in a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestLocation {
+    pub abs_path: PathBuf,
+    pub row: u32,
+    pub column: u32,
+}
+
+/// The result of a test, parsed from a runner's output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestOutcome {
+    pub name: String,
+    pub status: TestStatus,
+    pub failure: Option<TestFailure>,
+}
+
+/// A command that's ready to run, with its variables substituted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedTestCommand {
+    pub label: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub working_dir: PathBuf,
+}
+
+impl TestCommand {
+    /// Resolves the command the way tasks are resolved, with the name of the test and the
+    /// worktree's root as variables, to run in `working_dir`.
+    pub fn resolve(
+        &self,
+        label: String,
+        test_name: Option<&str>,
+        working_dir: &Path,
+    ) -> Option<ResolvedTestCommand> {
+        let template = TaskTemplate {
+            label,
+            command: self.command.clone(),
+            args: self.args.clone(),
+            env: self.env.clone(),
+            ..TaskTemplate::default()
+        };
+        let mut task_variables = TaskVariables::default();
+        task_variables.insert(
+            VariableName::WorktreeRoot,
+            working_dir.to_string_lossy().into_owned(),
+        );
+        if let Some(test_name) = test_name {
+            task_variables.insert(TEST_NAME_VARIABLE, test_name.to_owned());
+        }
+        let context = TaskContext {
+            cwd: Some(working_dir.to_path_buf()),
+            task_variables,
+        };
+        let spawn = template.resolve_task("test_explorer", &context)?.resolved?;
+
+        // Tasks leave their arguments for the shell to expand, but tests run without one.
+        let mut variables = spawn
+            .env
+            .iter()
+            .filter(|(name, _)| name.starts_with(task::ZED_VARIABLE_NAME_PREFIX))
+            .collect::<Vec<_>>();
+        variables.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+        let args = spawn
+            .args
+            .iter()
+            .map(|arg| {
+                variables.iter().fold(arg.clone(), |arg, (name, value)| {
+                    arg.replace(&format!("${{{name}}}"), value)
+                        .replace(&format!("${name}"), value)
+                })
+            })
+            .collect();
+        Some(ResolvedTestCommand {
+            label: spawn.full_label,
+            command: spawn.command,
+            args,
+            env: spawn.env,
+            working_dir: working_dir.to_path_buf(),
+        })
+    }
+}
+
+impl ResolvedTestCommand {
+    /// The task that runs the command in a terminal, copying what it prints to `output_path`
+    /// and creating `done_path` once it exits, so that its results can be read.
+    pub fn spawn_in_terminal(
+        &self,
+        output_path: &Path,
+        done_path: &Path,
+    ) -> Result<SpawnInTerminal> {
+        let command = shlex::try_join(
+            std::iter::once(self.command.as_str()).chain(self.args.iter().map(String::as_str)),
+        )
+        .with_context(|| format!("quoting the command of {:?}", self.label))?;
+        let output_path = shlex::try_quote(output_path.to_str().context("invalid output path")?)?;
+        let done_path = shlex::try_quote(done_path.to_str().context("invalid done path")?)?;
+        Ok(SpawnInTerminal {
+            id: TaskId(format!("test_explorer_{}", self.label)),
+            full_label: self.label.clone(),
+            label: self.label.clone(),
+            command: format!("{command} | tee {output_path}; touch {done_path}"),
+            args: Vec::new(),
+            command_label: command,
+            cwd: Some(TerminalWorkDir::Local(self.working_dir.clone())),
+            env: self.env.clone(),
+            use_new_terminal: false,
+            allow_concurrent_runs: false,
+            reveal: RevealStrategy::Never,
+        })
+    }
+}
+
+/// Parses the names of the tests that a runner's `list` command printed.
+pub fn parse_test_list(format: TestOutputFormat, output: &str) -> Vec<String> {
+    match format {
+        TestOutputFormat::Libtest => output
+            .lines()
+            .filter_map(|line| line.strip_suffix(": test"))
+            .map(str::to_owned)
+            .collect(),
+        TestOutputFormat::Jest => Vec::new(),
+    }
+}
+
+/// Parses the results that a runner's `run` or `run_all` command printed. Relative paths in
+/// the output are resolved against `working_dir`.
+pub fn parse_test_results(
+    format: TestOutputFormat,
+    output: &str,
+    working_dir: &Path,
+) -> Vec<TestOutcome> {
+    match format {
+        TestOutputFormat::Libtest => parse_libtest_results(output, working_dir),
+        TestOutputFormat::Jest => parse_jest_results(output, working_dir),
+    }
+}
+
+fn parse_libtest_results(output: &str, working_dir: &Path) -> Vec<TestOutcome> {
+    let mut outcomes = Vec::<TestOutcome>::new();
+    for line in output.lines() {
+        let Some((name, result)) = line
+            .strip_prefix("test ")
+            .and_then(|line| line.split_once(" ... "))
+        else {
+            continue;
+        };
+        let status = match result.trim() {
+            "ok" => TestStatus::Passed,
+            "FAILED" => TestStatus::Failed,
+            "ignored" => TestStatus::Ignored,
+            _ => continue,
+        };
+        outcomes.push(TestOutcome {
+            name: name.to_owned(),
+            status,
+            failure: None,
+        });
+    }
+
+    // The output of each failed test is printed in a section of its own once all of the
+    // tests have run.
+    let mut sections = Vec::<(&str, Vec<&str>)>::new();
+    for line in output.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|line| line.strip_suffix(" stdout ----"))
+        {
+            sections.push((name, Vec::new()));
+        } else if line == "failures:" || line.starts_with("test result:") {
+            sections.push(("", Vec::new()));
+        } else if let Some((_, lines)) = sections.last_mut() {
+            lines.push(line);
+        }
+    }
+    for (name, lines) in sections {
+        let Some(outcome) = outcomes.iter_mut().find(|outcome| outcome.name == name) else {
+            continue;
+        };
+        let message = lines.join("\n").trim().to_owned();
+        let location = panic_location_regex()
+            .captures(&message)
+            .and_then(|captures| {
+                Some(TestLocation {
+                    abs_path: working_dir.join(&captures[1]),
+                    row: captures[2].parse::<u32>().ok()?.saturating_sub(1),
+                    column: captures[3].parse::<u32>().ok()?.saturating_sub(1),
+                })
+            });
+        outcome.failure = Some(TestFailure { message, location });
+    }
+    outcomes
+}
+
+fn panic_location_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    // Older versions of Rust print the message before the location.
+    REGEX.get_or_init(|| Regex::new(r"panicked at (?:'.*', )?([^:\s]+):(\d+):(\d+)").unwrap())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JestReport {
+    test_results: Vec<JestFileResult>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JestFileResult {
+    name: PathBuf,
+    assertion_results: Vec<JestAssertionResult>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JestAssertionResult {
+    full_name: String,
+    status: String,
+    #[serde(default)]
+    failure_messages: Vec<String>,
+    #[serde(default)]
+    location: Option<JestLocation>,
+}
+
+#[derive(Deserialize)]
+struct JestLocation {
+    line: u32,
+    column: u32,
+}
+
+fn parse_jest_results(output: &str, working_dir: &Path) -> Vec<TestOutcome> {
+    // Jest may print other output before its report.
+    let Some(report) = output
+        .find('{')
+        .and_then(|start| serde_json::from_str::<JestReport>(&output[start..]).ok())
+    else {
+        return Vec::new();
+    };
+    report
+        .test_results
+        .into_iter()
+        .flat_map(|file| {
+            let abs_path = working_dir.join(&file.name);
+            file.assertion_results.into_iter().map(move |assertion| {
+                let status = match assertion.status.as_str() {
+                    "passed" => TestStatus::Passed,
+                    "failed" => TestStatus::Failed,
+                    "pending" | "skipped" | "todo" | "disabled" => TestStatus::Ignored,
+                    _ => TestStatus::NotRun,
+                };
+                let failure = (status == TestStatus::Failed).then(|| TestFailure {
+                    message: assertion.failure_messages.join("\n"),
+                    location: assertion.location.map(|location| TestLocation {
+                        abs_path: abs_path.clone(),
+                        row: location.line.saturating_sub(1),
+                        column: location.column.saturating_sub(1),
+                    }),
+                });
+                TestOutcome {
+                    name: assertion.full_name,
+                    status,
+                    failure,
+                }
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_libtest_output() {
+        let list = indoc! {"
+            tests::adds: test
+            tests::subtracts: test
+            benches::sum: benchmark
+
+            2 tests, 1 benchmark
+        "};
+        assert_eq!(
+            parse_test_list(TestOutputFormat::Libtest, list),
+            ["tests::adds", "tests::subtracts"]
+        );
+
+        let output = indoc! {"
+            running 3 tests
+            test tests::adds ... ok
+            test tests::slow ... ignored
+            test tests::subtracts ... FAILED
+
+            failures:
+
+            ---- tests::subtracts stdout ----
+            thread 'tests::subtracts' panicked at src/lib.rs:12:9:
+            assertion `left == right` failed
+              left: 1
+             right: 2
+
+            failures:
+                tests::subtracts
+
+            test result: FAILED. 1 passed; 1 failed; 1 ignored
+        "};
+        assert_eq!(
+            parse_test_results(TestOutputFormat::Libtest, output, Path::new("/crate")),
+            [
+                TestOutcome {
+                    name: "tests::adds".into(),
+                    status: TestStatus::Passed,
+                    failure: None,
+                },
+                TestOutcome {
+                    name: "tests::slow".into(),
+                    status: TestStatus::Ignored,
+                    failure: None,
+                },
+                TestOutcome {
+                    name: "tests::subtracts".into(),
+                    status: TestStatus::Failed,
+                    failure: Some(TestFailure {
+                        message: indoc! {"
+                            thread 'tests::subtracts' panicked at src/lib.rs:12:9:
+                            assertion `left == right` failed
+                              left: 1
+                             right: 2"}
+                        .into(),
+                        location: Some(TestLocation {
+                            abs_path: "/crate/src/lib.rs".into(),
+                            row: 11,
+                            column: 8,
+                        }),
+                    }),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_jest_output() {
+        let output = format!(
+            "Determining test suites to run...\n{}",
+            json!({
+                "numFailedTests": 1,
+                "testResults": [{
+                    "name": "/app/sum.test.js",
+                    "assertionResults": [
+                        {
+                            "fullName": "sum adds",
+                            "status": "passed",
+                            "failureMessages": [],
+                        },
+                        {
+                            "fullName": "sum subtracts",
+                            "status": "failed",
+                            "failureMessages": ["expected 1 to be 2"],
+                            "location": { "line": 7, "column": 3 },
+                        },
+                    ],
+                }],
+            })
+        );
+        assert_eq!(
+            parse_test_results(TestOutputFormat::Jest, &output, Path::new("/app")),
+            [
+                TestOutcome {
+                    name: "sum adds".into(),
+                    status: TestStatus::Passed,
+                    failure: None,
+                },
+                TestOutcome {
+                    name: "sum subtracts".into(),
+                    status: TestStatus::Failed,
+                    failure: Some(TestFailure {
+                        message: "expected 1 to be 2".into(),
+                        location: Some(TestLocation {
+                            abs_path: "/app/sum.test.js".into(),
+                            row: 6,
+                            column: 2,
+                        }),
+                    }),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_run_command() {
+        let command = TestCommand {
+            command: "cargo".into(),
+            args: vec![
+                "test".into(),
+                "--".into(),
+                "--exact".into(),
+                "$ZED_CUSTOM_TEST_NAME".into(),
+            ],
+            env: HashMap::default(),
+        };
+        let resolved = command
+            .resolve(
+                "cargo test".into(),
+                Some("tests::adds"),
+                Path::new("/crate"),
+            )
+            .unwrap();
+        assert_eq!(resolved.command, "cargo");
+        assert_eq!(resolved.args, ["test", "--", "--exact", "tests::adds"]);
+        assert_eq!(resolved.working_dir, Path::new("/crate"));
+    }
+}
@@ -1944,6 +1944,15 @@ impl BufferSnapshot {
         (row_end_offset - row_start_offset) as u32
     }
 
+    /// The runs of the row's text that are displayed in a single direction, in the order
+    /// they're displayed from left to right. Byte ranges are relative to the start of the row.
+    pub fn bidi_runs(&self, row: u32) -> Vec<BidiRun> {
+        let line = self
+            .text_for_range(Point::new(row, 0)..Point::new(row, self.line_len(row)))
+            .collect::<String>();
+        bidi_runs(&line)
+    }
+
     pub fn line_indents_in_row_range(
         &self,
         row_range: Range<u32>,
@@ -0,0 +1,431 @@
+use std::{mem, path::PathBuf, sync::OnceLock};
+
+use collections::BTreeMap;
+use gpui::{
+    AsyncAppContext, EventEmitter, Model, ModelContext, SharedString, Subscription, Task, WeakModel,
+};
+use language::Point;
+use project::{
+    search::SearchQuery, PathChange, Project, ProjectPath, SearchResult, UpdatedEntriesSet,
+    WorktreeId,
+};
+use regex::Regex;
+use util::ResultExt;
+
+/// Comment markers, one of which must precede a keyword on its line for it to count as a todo.
+const COMMENT_MARKERS: &[&str] = &["//", "/*", "#", "--", ";", "<!--", "%"];
+
+fn todo_regex() -> &'static Regex {
+    static TODO_REGEX: OnceLock<Regex> = OnceLock::new();
+
+    TODO_REGEX.get_or_init(|| Regex::new(r"\b(TODO|FIXME|HACK)\b(\([^)]*\))?:?\s*(.*)").unwrap())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TodoKind {
+    Todo,
+    Fixme,
+    Hack,
+}
+
+impl TodoKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TodoKind::Todo => "TODO",
+            TodoKind::Fixme => "FIXME",
+            TodoKind::Hack => "HACK",
+        }
+    }
+}
+
+/// A TODO, FIXME or HACK comment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Todo {
+    pub kind: TodoKind,
+    /// The text of the comment following the keyword.
+    pub text: SharedString,
+    /// The position of the keyword.
+    pub point: Point,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexStatus {
+    NotIndexed,
+    Indexing,
+    Indexed,
+}
+
+pub enum Event {
+    Updated,
+}
+
+/// An index of the todo comments in the visible worktrees of a project, which is kept up to
+/// date as files change on disk.
+pub struct TodoIndex {
+    project: Model<Project>,
+    todos: BTreeMap<ProjectPath, Vec<Todo>>,
+    status: IndexStatus,
+    /// The files that changed since their todos were last loaded, with their absolute paths,
+    /// or `None` for the ones that were removed. Changes to the same file replace each other.
+    pending_updates: BTreeMap<ProjectPath, Option<PathBuf>>,
+    /// Whether a task is loading the pending updates, which it does until there are none.
+    updating: bool,
+    _index_task: Option<Task<()>>,
+    _subscription: Subscription,
+}
+
+impl EventEmitter<Event> for TodoIndex {}
+
+impl TodoIndex {
+    pub fn new(project: Model<Project>, cx: &mut ModelContext<Self>) -> Self {
+        let subscription = cx.subscribe(&project, |this, _, event, cx| {
+            if let project::Event::WorktreeUpdatedEntries(worktree_id, changes) = event {
+                if this.status != IndexStatus::NotIndexed {
+                    this.update_entries(*worktree_id, changes, cx);
+                }
+            }
+        });
+
+        Self {
+            project,
+            todos: BTreeMap::default(),
+            status: IndexStatus::NotIndexed,
+            pending_updates: BTreeMap::default(),
+            updating: false,
+            _index_task: None,
+            _subscription: subscription,
+        }
+    }
+
+    pub fn status(&self) -> IndexStatus {
+        self.status
+    }
+
+    /// Returns all todos, ordered by their paths and positions.
+    pub fn todos(&self) -> impl Iterator<Item = (&ProjectPath, &Todo)> {
+        self.todos
+            .iter()
+            .flat_map(|(path, todos)| todos.iter().map(move |todo| (path, todo)))
+    }
+
+    /// Indexes the project, unless it has been indexed already.
+    pub fn ensure_indexed(&mut self, cx: &mut ModelContext<Self>) {
+        if self.status == IndexStatus::NotIndexed {
+            self.index(cx);
+        }
+    }
+
+    /// Discards the current index and indexes the project again.
+    pub fn index(&mut self, cx: &mut ModelContext<Self>) {
+        // Project search already knows how to walk the worktrees efficiently, skipping ignored
+        // files and preferring the contents of open buffers, so use it to find candidate files.
+        let Some(query) = SearchQuery::regex(
+            r"\b(TODO|FIXME|HACK)\b",
+            false,
+            true,
+            false,
+            Vec::new(),
+            Vec::new(),
+        )
+        .log_err() else {
+            return;
+        };
+        let results = self
+            .project
+            .update(cx, |project, cx| project.search(query, cx));
+
+        self.todos.clear();
+        self.status = IndexStatus::Indexing;
+        self._index_task = Some(cx.spawn(|this, mut cx| async move {
+            while let Ok(result) = results.recv().await {
+                let SearchResult::Buffer { buffer, .. } = result else {
+                    continue;
+                };
+                let Ok(Some((project_path, text))) = buffer.update(&mut cx, |buffer, cx| {
+                    Some((buffer.project_path(cx)?, buffer.as_rope().clone()))
+                }) else {
+                    continue;
+                };
+                let todos = cx
+                    .background_executor()
+                    .spawn(async move { parse_todos(&text.to_string()) })
+                    .await;
+                if this
+                    .update(&mut cx, |this, cx| this.set_todos(project_path, todos, cx))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.status = IndexStatus::Indexed;
+                cx.emit(Event::Updated);
+                cx.notify();
+            })
+            .ok();
+        }));
+        cx.notify();
+    }
+
+    fn update_entries(
+        &mut self,
+        worktree_id: WorktreeId,
+        changes: &UpdatedEntriesSet,
+        cx: &mut ModelContext<Self>,
+    ) {
+        for (path, _, change) in changes.iter() {
+            let project_path = ProjectPath {
+                worktree_id,
+                path: path.clone(),
+            };
+            if *change == PathChange::Removed {
+                self.pending_updates.insert(project_path, None);
+                continue;
+            }
+
+            let project = self.project.read(cx);
+            let Some(entry) = project.entry_for_path(&project_path, cx) else {
+                continue;
+            };
+            if !entry.is_file() || entry.is_ignored {
+                continue;
+            }
+            let Some(abs_path) = project.absolute_path(&project_path, cx) else {
+                continue;
+            };
+            self.pending_updates.insert(project_path, Some(abs_path));
+        }
+
+        if !self.updating && !self.pending_updates.is_empty() {
+            self.updating = true;
+            cx.spawn(|this, cx| Self::load_pending_updates(this, cx))
+                .detach();
+        }
+    }
+
+    /// Loads the todos of the pending updates in batches, one at a time, so that the results
+    /// of older changes can't replace those of newer ones.
+    async fn load_pending_updates(this: WeakModel<Self>, mut cx: AsyncAppContext) {
+        loop {
+            let Ok(Some((updates, fs))) = this.update(&mut cx, |this, cx| {
+                if this.pending_updates.is_empty() {
+                    this.updating = false;
+                    return None;
+                }
+                let fs = this.project.read(cx).fs().clone();
+                Some((mem::take(&mut this.pending_updates), fs))
+            }) else {
+                return;
+            };
+
+            let todos = cx
+                .background_executor()
+                .spawn(async move {
+                    let mut todos = Vec::new();
+                    for (project_path, abs_path) in updates {
+                        let path_todos = match abs_path {
+                            Some(abs_path) => fs
+                                .load(&abs_path)
+                                .await
+                                .map(|text| parse_todos(&text))
+                                .unwrap_or_default(),
+                            None => Vec::new(),
+                        };
+                        todos.push((project_path, path_todos));
+                    }
+                    todos
+                })
+                .await;
+            if this
+                .update(&mut cx, |this, cx| {
+                    for (project_path, todos) in todos {
+                        this.set_todos(project_path, todos, cx);
+                    }
+                })
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+
+    fn set_todos(&mut self, path: ProjectPath, todos: Vec<Todo>, cx: &mut ModelContext<Self>) {
+        let changed = if todos.is_empty() {
+            self.todos.remove(&path).is_some()
+        } else {
+            self.todos.insert(path, todos.clone()).as_ref() != Some(&todos)
+        };
+        if changed {
+            cx.emit(Event::Updated);
+            cx.notify();
+        }
+    }
+}
+
+/// Extracts the todo comments from the given text.
+pub fn parse_todos(text: &str) -> Vec<Todo> {
+    let mut todos = Vec::new();
+    for (row, line) in text.lines().enumerate() {
+        let Some(captures) = todo_regex().captures(line) else {
+            continue;
+        };
+        let keyword = captures.get(1).unwrap();
+        let prefix = &line[..keyword.start()];
+        let in_comment = COMMENT_MARKERS.iter().any(|marker| prefix.contains(marker))
+            || prefix.trim_start().starts_with('*');
+        if !in_comment {
+            continue;
+        }
+
+        let kind = match keyword.as_str() {
+            "TODO" => TodoKind::Todo,
+            "FIXME" => TodoKind::Fixme,
+            _ => TodoKind::Hack,
+        };
+        let text = captures.get(3).map_or("", |text| text.as_str());
+        let text = text
+            .trim_end()
+            .trim_end_matches("*/")
+            .trim_end_matches("-->")
+            .trim_end();
+        todos.push(Todo {
+            kind,
+            text: text.to_string().into(),
+            point: Point::new(row as u32, keyword.start() as u32),
+        });
+    }
+    todos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{Context, TestAppContext};
+    use indoc::indoc;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_todos() {
+        let text = indoc! {r#"
+            // TODO: first
+            fn main() {
+                let todo = "TODO: not a comment";
+                /* FIXME(someone) second */
+            }
+            # HACK third
+            -- XTODO not a keyword
+        "#};
+
+        assert_eq!(
+            parse_todos(text),
+            vec![
+                Todo {
+                    kind: TodoKind::Todo,
+                    text: "first".into(),
+                    point: Point::new(0, 3),
+                },
+                Todo {
+                    kind: TodoKind::Fixme,
+                    text: "second".into(),
+                    point: Point::new(3, 7),
+                },
+                Todo {
+                    kind: TodoKind::Hack,
+                    text: "third".into(),
+                    point: Point::new(5, 2),
+                },
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_todo_index(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings = SettingsStore::test(cx);
+            cx.set_global(settings);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "a.rs": "// TODO: one\nfn a() {}\n",
+                "b.rs": "fn b() {} // FIXME: two\n",
+                "c.rs": "fn c() {}\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        let index = cx.new_model(|cx| TodoIndex::new(project, cx));
+        index.update(cx, |index, cx| index.ensure_indexed(cx));
+        cx.run_until_parked();
+
+        let todos = |index: &Model<TodoIndex>, cx: &mut TestAppContext| {
+            index.read_with(cx, |index, _| {
+                index
+                    .todos()
+                    .map(|(path, todo)| (path.path.to_path_buf(), todo.text.to_string()))
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(
+            index.read_with(cx, |index, _| index.status()),
+            IndexStatus::Indexed
+        );
+        assert_eq!(
+            todos(&index, cx),
+            vec![
+                (Path::new("a.rs").to_path_buf(), "one".to_string()),
+                (Path::new("b.rs").to_path_buf(), "two".to_string()),
+            ]
+        );
+
+        fs.save(
+            "/dir/c.rs".as_ref(),
+            &"// HACK: three\n".into(),
+            Default::default(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        fs.remove_file("/dir/a.rs".as_ref(), Default::default())
+            .await
+            .unwrap();
+        cx.run_until_parked();
+
+        assert_eq!(
+            todos(&index, cx),
+            vec![
+                (Path::new("b.rs").to_path_buf(), "two".to_string()),
+                (Path::new("c.rs").to_path_buf(), "three".to_string()),
+            ]
+        );
+
+        // The todos of a file that changes repeatedly end up matching its latest contents.
+        for text in ["// HACK: four\n", "// HACK: five\n"] {
+            fs.save(
+                "/dir/c.rs".as_ref(),
+                &text.into(),
+                Default::default(),
+                Default::default(),
+            )
+            .await
+            .unwrap();
+        }
+        cx.run_until_parked();
+
+        assert_eq!(
+            todos(&index, cx),
+            vec![
+                (Path::new("b.rs").to_path_buf(), "two".to_string()),
+                (Path::new("c.rs").to_path_buf(), "five".to_string()),
+            ]
+        );
+    }
+}
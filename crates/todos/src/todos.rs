@@ -0,0 +1,252 @@
+mod todo_index;
+
+use editor::{scroll::Autoscroll, Editor};
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    ParentElement, Render, Styled, Subscription, View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use project::ProjectPath;
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+pub use todo_index::*;
+
+actions!(todos, [Toggle, Reindex]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(TodoSelector::register).detach();
+}
+
+pub struct TodoSelector {
+    picker: View<Picker<TodoSelectorDelegate>>,
+    _subscription: Subscription,
+}
+
+impl TodoSelector {
+    fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+        // The index is created with the workspace, but only populated once it's first used.
+        let index = cx.new_model(|cx| TodoIndex::new(workspace.project().clone(), cx));
+        workspace.register_action({
+            let index = index.clone();
+            move |workspace, _: &Toggle, cx| {
+                index.update(cx, |index, cx| index.ensure_indexed(cx));
+                let workspace_handle = cx.view().downgrade();
+                let index = index.clone();
+                workspace
+                    .toggle_modal(cx, move |cx| TodoSelector::new(workspace_handle, index, cx));
+            }
+        });
+        workspace.register_action(move |_, _: &Reindex, cx| {
+            index.update(cx, |index, cx| index.index(cx));
+        });
+    }
+
+    fn new(
+        workspace: WeakView<Workspace>,
+        index: Model<TodoIndex>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = TodoSelectorDelegate::new(cx.view().downgrade(), workspace, &index, cx);
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        // Keep the list current while the project is still being indexed.
+        let subscription = cx.subscribe(&index, {
+            let picker = picker.clone();
+            move |_, index, _: &Event, cx| {
+                picker.update(cx, |picker, cx| {
+                    picker.delegate.set_todos(&index, cx);
+                    picker.refresh(cx);
+                });
+            }
+        });
+        Self {
+            picker,
+            _subscription: subscription,
+        }
+    }
+}
+
+impl Render for TodoSelector {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for TodoSelector {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for TodoSelector {}
+impl ModalView for TodoSelector {}
+
+pub struct TodoSelectorDelegate {
+    todo_selector: WeakView<TodoSelector>,
+    workspace: WeakView<Workspace>,
+    todos: Vec<(ProjectPath, Todo)>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl TodoSelectorDelegate {
+    fn new(
+        todo_selector: WeakView<TodoSelector>,
+        workspace: WeakView<Workspace>,
+        index: &Model<TodoIndex>,
+        cx: &AppContext,
+    ) -> Self {
+        let mut this = Self {
+            todo_selector,
+            workspace,
+            todos: Vec::new(),
+            candidates: Vec::new(),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        this.set_todos(index, cx);
+        this
+    }
+
+    fn set_todos(&mut self, index: &Model<TodoIndex>, cx: &AppContext) {
+        self.todos = index
+            .read(cx)
+            .todos()
+            .map(|(path, todo)| (path.clone(), todo.clone()))
+            .collect();
+        self.candidates = self
+            .todos
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, (path, todo))| {
+                StringMatchCandidate::new(
+                    candidate_id,
+                    format!(
+                        "{} {} {}:{}",
+                        todo.kind.label(),
+                        todo.text,
+                        path.path.display(),
+                        todo.point.row + 1
+                    ),
+                )
+            })
+            .collect();
+    }
+}
+
+impl PickerDelegate for TodoSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Go to todo...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let (path, todo) = self.todos[mat.candidate_id].clone();
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    let open_task = workspace.open_path(path, None, true, cx);
+                    cx.spawn(|_, mut cx| async move {
+                        let item = open_task.await?;
+                        if let Some(editor) = item.downcast::<Editor>() {
+                            editor.update(&mut cx, |editor, cx| {
+                                let point = todo.point;
+                                editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                                    s.select_ranges([point..point])
+                                });
+                            })?;
+                        }
+                        anyhow::Ok(())
+                    })
+                    .detach_and_log_err(cx);
+                })
+                .log_err();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.todo_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
@@ -35,6 +35,7 @@ lazy_static::lazy_static! {
         SUPPORT_DIR.join("embeddings")
     };
     pub static ref THEMES_DIR: PathBuf = CONFIG_DIR.join("themes");
+    pub static ref USER_LANGUAGES_DIR: PathBuf = CONFIG_DIR.join("languages");
 
     pub static ref SUPPORT_DIR: PathBuf = if cfg!(target_os = "macos") {
         HOME.join("Library/Application Support/Zed")
@@ -60,6 +61,8 @@ lazy_static::lazy_static! {
     pub static ref SUPERMAVEN_DIR: PathBuf = SUPPORT_DIR.join("supermaven");
     pub static ref DEFAULT_PRETTIER_DIR: PathBuf = SUPPORT_DIR.join("prettier");
     pub static ref DB_DIR: PathBuf = SUPPORT_DIR.join("db");
+    pub static ref LOCAL_HISTORY_DIR: PathBuf = SUPPORT_DIR.join("local_history");
+    pub static ref SESSION_LOCK: PathBuf = SUPPORT_DIR.join("session.lock");
     pub static ref CRASHES_DIR: Option<PathBuf> = cfg!(target_os = "macos")
         .then_some(HOME.join("Library/Logs/DiagnosticReports"));
     pub static ref CRASHES_RETIRED_DIR: Option<PathBuf> = CRASHES_DIR
@@ -75,6 +78,7 @@ lazy_static::lazy_static! {
     pub static ref LOCAL_SETTINGS_RELATIVE_PATH: &'static Path = Path::new(".zed/settings.json");
     pub static ref LOCAL_TASKS_RELATIVE_PATH: &'static Path = Path::new(".zed/tasks.json");
     pub static ref LOCAL_VSCODE_TASKS_RELATIVE_PATH: &'static Path = Path::new(".vscode/tasks.json");
+    pub static ref LOCAL_DEBUG_RELATIVE_PATH: &'static Path = Path::new(".zed/debug.json");
     pub static ref TEMP_DIR: PathBuf = if cfg!(target_os = "windows") {
         dirs::cache_dir()
             .expect("failed to determine LocalAppData directory")
@@ -0,0 +1,47 @@
+//! A structured description of the workspace's panes and their items, for assistive
+//! technologies such as screen readers, which can't read the rendered tab bars.
+
+use gpui::{AppContext, EntityId, SharedString};
+
+use crate::Workspace;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessiblePane {
+    pub items: Vec<AccessibleItem>,
+    pub active_item_index: usize,
+    pub is_active: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessibleItem {
+    pub id: EntityId,
+    /// The item's description, such as the path of an editor's file, if it has one.
+    pub label: Option<SharedString>,
+    pub is_dirty: bool,
+}
+
+impl Workspace {
+    /// Describes the workspace's center panes, in the order they were created.
+    pub fn accessible_panes(&self, cx: &AppContext) -> Vec<AccessiblePane> {
+        self.panes()
+            .iter()
+            .map(|pane_view| {
+                let pane = pane_view.read(cx);
+                AccessiblePane {
+                    items: pane
+                        .items()
+                        .map(|item| AccessibleItem {
+                            id: item.item_id(),
+                            label: item
+                                .tab_description(0, cx)
+                                .or_else(|| item.tab_tooltip_text(cx)),
+                            is_dirty: item.is_dirty(cx),
+                        })
+                        .collect(),
+                    active_item_index: pane.active_item_index(),
+                    is_active: pane_view == self.active_pane(),
+                }
+            })
+            .collect()
+    }
+}
@@ -114,6 +114,15 @@ impl Settings for PreviewTabsSettings {
     }
 }
 
+/// Whether an item has changes that weren't saved, as shown by the indicator on its tab.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum DirtyState {
+    Clean,
+    Dirty,
+    /// The item has unsaved changes and its file also changed on disk.
+    Conflict,
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
 pub enum ItemEvent {
     CloseItem,
@@ -303,6 +312,7 @@ pub trait ItemHandle: 'static + Send {
     fn to_any(&self) -> AnyView;
     fn is_dirty(&self, cx: &AppContext) -> bool;
     fn has_conflict(&self, cx: &AppContext) -> bool;
+    fn dirty_state(&self, cx: &AppContext) -> DirtyState;
     fn can_save(&self, cx: &AppContext) -> bool;
     fn save(
         &self,
@@ -636,6 +646,17 @@ impl<T: Item> ItemHandle for View<T> {
         self.read(cx).has_conflict(cx)
     }
 
+    fn dirty_state(&self, cx: &AppContext) -> DirtyState {
+        let item = self.read(cx);
+        if item.has_conflict(cx) {
+            DirtyState::Conflict
+        } else if item.is_dirty(cx) {
+            DirtyState::Dirty
+        } else {
+            DirtyState::Clean
+        }
+    }
+
     fn can_save(&self, cx: &AppContext) -> bool {
         self.read(cx).can_save(cx)
     }
@@ -2,10 +2,11 @@ use crate::{Toast, Workspace};
 use collections::HashMap;
 use gpui::{
     svg, AnyView, AppContext, AsyncWindowContext, ClipboardItem, DismissEvent, Entity, EntityId,
-    EventEmitter, Global, PromptLevel, Render, ScrollHandle, Task, View, ViewContext,
-    VisualContext, WindowContext,
+    EventEmitter, Global, Model, PromptLevel, Render, ScrollHandle, Subscription, Task, View,
+    ViewContext, VisualContext, WindowContext,
 };
 use language::DiagnosticSeverity;
+use project::job_scheduler::{JobEvent, JobId, JobScheduler};
 
 use std::{any::TypeId, ops::DerefMut};
 use ui::{prelude::*, Tooltip};
@@ -335,6 +336,68 @@ impl Render for LanguageServerPrompt {
 
 impl EventEmitter<DismissEvent> for LanguageServerPrompt {}
 
+/// Shows the progress reported by a project job, until the job finishes.
+pub struct JobProgressNotification {
+    job_scheduler: Model<JobScheduler>,
+    job_id: JobId,
+    _subscription: Subscription,
+}
+
+impl JobProgressNotification {
+    pub fn new(
+        job_scheduler: Model<JobScheduler>,
+        job_id: JobId,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let _subscription = cx.subscribe(&job_scheduler, move |_, _, event, cx| match event {
+            JobEvent::Progress(id) if *id == job_id => cx.notify(),
+            JobEvent::Finished(id) if *id == job_id => cx.emit(DismissEvent),
+            _ => {}
+        });
+        Self {
+            job_scheduler,
+            job_id,
+            _subscription,
+        }
+    }
+}
+
+impl Render for JobProgressNotification {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let job_scheduler = self.job_scheduler.read(cx);
+        let name = job_scheduler.job_name(self.job_id).cloned();
+        let progress = job_scheduler.job_progress(self.job_id).cloned();
+        let message = progress
+            .as_ref()
+            .and_then(|progress| progress.message.clone());
+        let percentage = progress
+            .and_then(|progress| progress.fraction)
+            .map(|fraction| format!("{:.0}%", fraction * 100.));
+
+        h_flex()
+            .id("job_progress_notification")
+            .occlude()
+            .elevation_3(cx)
+            .p_2()
+            .gap_2()
+            .w_full()
+            .justify_between()
+            .child(
+                v_flex()
+                    .overflow_hidden()
+                    .children(name.map(Label::new))
+                    .children(message.map(|message| {
+                        Label::new(message)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    })),
+            )
+            .children(percentage.map(|percentage| Label::new(percentage).color(Color::Muted)))
+    }
+}
+
+impl EventEmitter<DismissEvent> for JobProgressNotification {}
+
 pub mod simple_message_notification {
     use gpui::{
         div, DismissEvent, EventEmitter, InteractiveElement, ParentElement, Render, SharedString,
@@ -1,7 +1,7 @@
 use crate::{
     item::{
-        ClosePosition, Item, ItemHandle, ItemSettings, PreviewTabsSettings, TabContentParams,
-        WeakItemHandle,
+        ClosePosition, DirtyState, Item, ItemHandle, ItemSettings, PreviewTabsSettings,
+        TabContentParams, WeakItemHandle,
     },
     toolbar::Toolbar,
     workspace_settings::{AutosaveSetting, TabBarSettings, WorkspaceSettings},
@@ -10,7 +10,7 @@ use crate::{
 };
 use anyhow::Result;
 use collections::{HashMap, HashSet, VecDeque};
-use futures::{stream::FuturesUnordered, StreamExt};
+use futures::{stream::FuturesUnordered, Future, StreamExt};
 use gpui::{
     actions, anchored, deferred, impl_actions, prelude::*, Action, AnchorCorner, AnyElement,
     AppContext, AsyncWindowContext, ClickEvent, DismissEvent, Div, DragMoveEvent, EntityId,
@@ -64,6 +64,47 @@ pub enum SaveIntent {
     Skip,
 }
 
+/// The user's answer when asked whether to save dirty items before closing them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CloseConfirmation {
+    Save,
+    DontSave,
+    Cancel,
+}
+
+impl CloseConfirmation {
+    const ANSWERS: [Self; 3] = [Self::Save, Self::DontSave, Self::Cancel];
+
+    fn label(self, item_count: usize) -> &'static str {
+        match (self, item_count) {
+            (Self::Save, 1) => "Save",
+            (Self::Save, _) => "Save all",
+            (Self::DontSave, 1) => "Don't Save",
+            (Self::DontSave, _) => "Discard all",
+            (Self::Cancel, _) => "Cancel",
+        }
+    }
+
+    /// Asks whether to save `item_count` dirty items before closing them. Dismissing the
+    /// prompt cancels closing them.
+    pub fn prompt(
+        prompt: &str,
+        detail: Option<&str>,
+        item_count: usize,
+        cx: &mut WindowContext,
+    ) -> impl Future<Output = Self> {
+        let answers = Self::ANSWERS.map(|answer| answer.label(item_count));
+        let answer = cx.prompt(PromptLevel::Warning, prompt, detail, &answers);
+        async move {
+            answer
+                .await
+                .ok()
+                .and_then(|ix| Self::ANSWERS.get(ix).copied())
+                .unwrap_or(Self::Cancel)
+        }
+    }
+}
+
 #[derive(Clone, Deserialize, PartialEq, Debug)]
 pub struct ActivateItem(pub usize);
 
@@ -126,6 +167,9 @@ actions!(
         SplitUp,
         SplitRight,
         SplitDown,
+        SwapItemLeft,
+        SwapItemRight,
+        TogglePinTab,
         TogglePreviewTab,
     ]
 );
@@ -147,6 +191,7 @@ pub enum Event {
     RemoveItem { item_id: EntityId },
     Split(SplitDirection),
     ChangeItemTitle,
+    ReorderItems,
     Focus,
     ZoomIn,
     ZoomOut,
@@ -173,6 +218,7 @@ impl fmt::Debug for Event {
                 .field("direction", direction)
                 .finish(),
             Event::ChangeItemTitle => f.write_str("ChangeItemTitle"),
+            Event::ReorderItems => f.write_str("ReorderItems"),
             Event::Focus => f.write_str("Focus"),
             Event::ZoomIn => f.write_str("ZoomIn"),
             Event::ZoomOut => f.write_str("ZoomOut"),
@@ -197,6 +243,8 @@ pub struct Pane {
     was_focused: bool,
     active_item_index: usize,
     preview_item_id: Option<EntityId>,
+    /// Pinned items always occupy the first `pinned_tab_count` slots of `items`.
+    pinned_tab_count: usize,
     last_focus_handle_by_item: HashMap<EntityId, WeakFocusHandle>,
     nav_history: NavHistory,
     toolbar: View<Toolbar>,
@@ -308,6 +356,7 @@ impl Pane {
             zoomed: false,
             active_item_index: 0,
             preview_item_id: None,
+            pinned_tab_count: 0,
             last_focus_handle_by_item: Default::default(),
             nav_history: NavHistory(Arc::new(Mutex::new(NavHistoryState {
                 mode: NavigationMode::Normal,
@@ -775,6 +824,11 @@ impl Pane {
         if let Some(existing_item_index) = existing_item_index {
             // If the item already exists, move it to the desired destination and activate it
 
+            // Pinned items are only moved when explicitly asked to
+            if destination_index.is_none() && existing_item_index < self.pinned_tab_count {
+                insertion_index = existing_item_index;
+            }
+
             if existing_item_index != insertion_index {
                 let existing_item_is_active = existing_item_index == self.active_item_index;
 
@@ -783,13 +837,27 @@ impl Pane {
                 if existing_item_is_active && destination_index.is_none() {
                     insertion_index = existing_item_index;
                 } else {
+                    let was_pinned = existing_item_index < self.pinned_tab_count;
                     self.items.remove(existing_item_index);
+                    if was_pinned {
+                        self.pinned_tab_count -= 1;
+                    }
                     if existing_item_index < self.active_item_index {
                         self.active_item_index -= 1;
                     }
                     insertion_index = insertion_index.min(self.items.len());
+                    if destination_index.is_none() {
+                        insertion_index = insertion_index.max(self.pinned_tab_count);
+                    }
 
                     self.items.insert(insertion_index, item.clone());
+                    // Moving an item into the pinned range pins it, and a pinned item stays
+                    // pinned when dropped right after the remaining pinned ones
+                    if insertion_index < self.pinned_tab_count
+                        || (was_pinned && insertion_index == self.pinned_tab_count)
+                    {
+                        self.pinned_tab_count += 1;
+                    }
 
                     if existing_item_is_active {
                         self.active_item_index = insertion_index;
@@ -803,6 +871,13 @@ impl Pane {
 
             self.activate_item(insertion_index, activate_pane, focus_item, cx);
         } else {
+            if insertion_index < self.pinned_tab_count {
+                if destination_index.is_some() {
+                    self.pinned_tab_count += 1;
+                } else {
+                    insertion_index = self.pinned_tab_count;
+                }
+            }
             self.items.insert(insertion_index, item.clone());
 
             if insertion_index <= self.active_item_index
@@ -826,6 +901,93 @@ impl Pane {
         self.items.iter()
     }
 
+    pub fn pinned_tab_count(&self) -> usize {
+        self.pinned_tab_count
+    }
+
+    pub fn is_tab_pinned(&self, ix: usize) -> bool {
+        ix < self.pinned_tab_count
+    }
+
+    /// Pins the item at the given index, moving it after the already pinned items.
+    pub fn pin_tab_at(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        if ix >= self.items.len() || self.is_tab_pinned(ix) {
+            return;
+        }
+        if self.preview_item_id == Some(self.items[ix].item_id()) {
+            self.set_preview_item_id(None, cx);
+        }
+        let destination_ix = self.pinned_tab_count;
+        self.pinned_tab_count += 1;
+        self.reorder_item(ix, destination_ix, cx);
+    }
+
+    /// Unpins the item at the given index, moving it before the other unpinned items.
+    pub fn unpin_tab_at(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        if !self.is_tab_pinned(ix) {
+            return;
+        }
+        self.pinned_tab_count -= 1;
+        let destination_ix = self.pinned_tab_count;
+        self.reorder_item(ix, destination_ix, cx);
+    }
+
+    pub fn toggle_pin_tab(&mut self, _: &TogglePinTab, cx: &mut ViewContext<Self>) {
+        if self.items.is_empty() {
+            return;
+        }
+        let ix = self.active_item_index;
+        if self.is_tab_pinned(ix) {
+            self.unpin_tab_at(ix, cx);
+        } else {
+            self.pin_tab_at(ix, cx);
+        }
+    }
+
+    /// Swaps the active item with its left neighbor, without crossing the boundary between
+    /// pinned and unpinned items.
+    pub fn swap_item_left(&mut self, _: &SwapItemLeft, cx: &mut ViewContext<Self>) {
+        let ix = self.active_item_index;
+        if ix == 0 || ix >= self.items.len() || ix == self.pinned_tab_count {
+            return;
+        }
+        self.reorder_item(ix, ix - 1, cx);
+    }
+
+    /// Swaps the active item with its right neighbor, without crossing the boundary between
+    /// pinned and unpinned items.
+    pub fn swap_item_right(&mut self, _: &SwapItemRight, cx: &mut ViewContext<Self>) {
+        let ix = self.active_item_index;
+        if ix + 1 >= self.items.len() || ix + 1 == self.pinned_tab_count {
+            return;
+        }
+        self.reorder_item(ix, ix + 1, cx);
+    }
+
+    fn reorder_item(&mut self, from_ix: usize, to_ix: usize, cx: &mut ViewContext<Self>) {
+        if from_ix != to_ix {
+            let item = self.items.remove(from_ix);
+            self.items.insert(to_ix, item);
+            if self.active_item_index == from_ix {
+                self.active_item_index = to_ix;
+            } else if from_ix < self.active_item_index && self.active_item_index <= to_ix {
+                self.active_item_index -= 1;
+            } else if to_ix <= self.active_item_index && self.active_item_index < from_ix {
+                self.active_item_index += 1;
+            }
+            self.tab_bar_scroll_handle.scroll_to_item(to_ix);
+        }
+        cx.emit(Event::ReorderItems);
+        cx.notify();
+    }
+
+    fn pinned_item_ids(&self) -> Vec<EntityId> {
+        self.items[..self.pinned_tab_count]
+            .iter()
+            .map(|item| item.item_id())
+            .collect()
+    }
+
     pub fn items_of_type<T: Render>(&self) -> impl '_ + Iterator<Item = View<T>> {
         self.items
             .iter()
@@ -985,10 +1147,11 @@ impl Pane {
         }
 
         let active_item_id = self.items[self.active_item_index].item_id();
+        let pinned_item_ids = self.pinned_item_ids();
         Some(self.close_items(
             cx,
             action.save_intent.unwrap_or(SaveIntent::Close),
-            move |item_id| item_id != active_item_id,
+            move |item_id| item_id != active_item_id && !pinned_item_ids.contains(&item_id),
         ))
     }
 
@@ -999,6 +1162,7 @@ impl Pane {
     ) -> Option<Task<Result<()>>> {
         let item_ids: Vec<_> = self
             .items()
+            .skip(self.pinned_tab_count)
             .filter(|item| !item.is_dirty(cx))
             .map(|item| item.item_id())
             .collect();
@@ -1027,6 +1191,7 @@ impl Pane {
         let item_ids: Vec<_> = self
             .items()
             .take_while(|item| item.item_id() != item_id)
+            .skip(self.pinned_tab_count)
             .map(|item| item.item_id())
             .collect();
         self.close_items(cx, SaveIntent::Close, move |item_id| {
@@ -1053,6 +1218,7 @@ impl Pane {
     ) -> Task<Result<()>> {
         let item_ids: Vec<_> = self
             .items()
+            .skip(self.pinned_tab_count)
             .rev()
             .take_while(|item| item.item_id() != item_id)
             .map(|item| item.item_id())
@@ -1145,17 +1311,12 @@ impl Pane {
                 let answer = pane.update(&mut cx, |_, cx| {
                     let (prompt, detail) =
                         Self::file_names_for_prompt(&mut dirty_items.iter(), dirty_items.len(), cx);
-                    cx.prompt(
-                        PromptLevel::Warning,
-                        &prompt,
-                        Some(&detail),
-                        &["Save all", "Discard all", "Cancel"],
-                    )
+                    CloseConfirmation::prompt(&prompt, Some(&detail), dirty_items.len(), cx)
                 })?;
                 match answer.await {
-                    Ok(0) => save_intent = SaveIntent::SaveAll,
-                    Ok(1) => save_intent = SaveIntent::Skip,
-                    _ => {}
+                    CloseConfirmation::Save => save_intent = SaveIntent::SaveAll,
+                    CloseConfirmation::DontSave => save_intent = SaveIntent::Skip,
+                    CloseConfirmation::Cancel => {}
                 }
             }
             let mut saved_project_items_ids = HashSet::default();
@@ -1232,6 +1393,9 @@ impl Pane {
     ) {
         self.activation_history
             .retain(|entry| entry.entity_id != self.items[item_index].item_id());
+        if item_index < self.pinned_tab_count {
+            self.pinned_tab_count -= 1;
+        }
 
         if item_index == self.active_item_index {
             let index_to_activate = self
@@ -1382,12 +1546,7 @@ impl Pane {
                         if pane.save_modals_spawned.insert(item_id) {
                             pane.activate_item(item_ix, true, true, cx);
                             let prompt = dirty_message_for(item.project_path(cx));
-                            Some(cx.prompt(
-                                PromptLevel::Warning,
-                                &prompt,
-                                None,
-                                &["Save", "Don't Save", "Cancel"],
-                            ))
+                            Some(CloseConfirmation::prompt(&prompt, None, 1, cx))
                         } else {
                             None
                         }
@@ -1402,9 +1561,9 @@ impl Pane {
                             }
                         })?;
                         match answer {
-                            Ok(0) => {}
-                            Ok(1) => return Ok(true), // Don't save this file
-                            _ => return Ok(false),    // Cancel
+                            CloseConfirmation::Save => {}
+                            CloseConfirmation::DontSave => return Ok(true),
+                            CloseConfirmation::Cancel => return Ok(false),
                         }
                     } else {
                         return Ok(false);
@@ -1527,6 +1686,7 @@ impl Pane {
         cx: &mut ViewContext<'_, Pane>,
     ) -> impl IntoElement {
         let is_active = ix == self.active_item_index;
+        let is_pinned = self.is_tab_pinned(ix);
         let is_preview = self
             .preview_item_id
             .map(|id| id == item.item_id())
@@ -1616,6 +1776,13 @@ impl Pane {
                 tab.tooltip(move |cx| Tooltip::text(text.clone(), cx))
             })
             .start_slot::<Indicator>(indicator)
+            .when(is_pinned, |tab| {
+                tab.child(
+                    Icon::new(IconName::Pin)
+                        .size(IconSize::XSmall)
+                        .color(Color::Muted),
+                )
+            })
             .end_slot(
                 IconButton::new("close tab", IconName::Close)
                     .shape(IconButtonShape::Square)
@@ -1656,8 +1823,11 @@ impl Pane {
                             "Close Others",
                             Some(Box::new(CloseInactiveItems { save_intent: None })),
                             cx.handler_for(&pane, move |pane, cx| {
-                                pane.close_items(cx, SaveIntent::Close, |id| id != item_id)
-                                    .detach_and_log_err(cx);
+                                let pinned_item_ids = pane.pinned_item_ids();
+                                pane.close_items(cx, SaveIntent::Close, move |id| {
+                                    id != item_id && !pinned_item_ids.contains(&id)
+                                })
+                                .detach_and_log_err(cx);
                             }),
                         )
                         .separator()
@@ -1697,6 +1867,22 @@ impl Pane {
                                     task.detach_and_log_err(cx)
                                 }
                             }),
+                        )
+                        .separator()
+                        .entry(
+                            if is_pinned { "Unpin Tab" } else { "Pin Tab" },
+                            Some(Box::new(TogglePinTab)),
+                            cx.handler_for(&pane, move |pane, cx| {
+                                if let Some(ix) =
+                                    pane.items.iter().position(|item| item.item_id() == item_id)
+                                {
+                                    if pane.is_tab_pinned(ix) {
+                                        pane.unpin_tab_at(ix, cx);
+                                    } else {
+                                        pane.pin_tab_at(ix, cx);
+                                    }
+                                }
+                            }),
                         );
 
                     if let Some(entry) = single_entry_to_resolve {
@@ -2077,6 +2263,9 @@ impl Render for Pane {
             .on_action(cx.listener(|pane, _: &GoBack, cx| pane.navigate_backward(cx)))
             .on_action(cx.listener(|pane, _: &GoForward, cx| pane.navigate_forward(cx)))
             .on_action(cx.listener(Pane::toggle_zoom))
+            .on_action(cx.listener(Pane::toggle_pin_tab))
+            .on_action(cx.listener(Pane::swap_item_left))
+            .on_action(cx.listener(Pane::swap_item_right))
             .on_action(cx.listener(|pane: &mut Pane, action: &ActivateItem, cx| {
                 pane.activate_item(action.0, true, true, cx);
             }))
@@ -2472,10 +2661,10 @@ pub fn tab_details(items: &Vec<Box<dyn ItemHandle>>, cx: &AppContext) -> Vec<usi
 
 pub fn render_item_indicator(item: Box<dyn ItemHandle>, cx: &WindowContext) -> Option<Indicator> {
     maybe!({
-        let indicator_color = match (item.has_conflict(cx), item.is_dirty(cx)) {
-            (true, _) => Color::Warning,
-            (_, true) => Color::Accent,
-            (false, false) => return None,
+        let indicator_color = match item.dirty_state(cx) {
+            DirtyState::Conflict => Color::Warning,
+            DirtyState::Dirty => Color::Accent,
+            DirtyState::Clean => return None,
         };
 
         Some(Indicator::dot().color(indicator_color))
@@ -2935,6 +3124,95 @@ mod tests {
         assert_item_labels(&pane, [], cx);
     }
 
+    #[gpui::test]
+    async fn test_pinned_tabs(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        set_labeled_items(&pane, ["A", "B", "C*", "D", "E"], cx);
+
+        // Pinned items move after the already pinned ones
+        pane.update(cx, |pane, cx| pane.pin_tab_at(2, cx));
+        assert_item_labels(&pane, ["C*", "A", "B", "D", "E"], cx);
+        pane.update(cx, |pane, cx| pane.pin_tab_at(4, cx));
+        assert_item_labels(&pane, ["C*", "E", "A", "B", "D"], cx);
+        pane.update(cx, |pane, _| assert_eq!(pane.pinned_tab_count(), 2));
+
+        // Closing other items keeps the pinned ones
+        pane.update(cx, |pane, cx| pane.activate_item(4, false, false, cx));
+        pane.update(cx, |pane, cx| {
+            pane.close_inactive_items(&CloseInactiveItems { save_intent: None }, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+        assert_item_labels(&pane, ["C", "E", "D*"], cx);
+
+        // New items are never added among the pinned ones
+        pane.update(cx, |pane, cx| pane.activate_item(0, false, false, cx));
+        add_labeled_item(&pane, "F", false, cx);
+        assert_item_labels(&pane, ["C", "E", "F*", "D"], cx);
+
+        // Unpinned items move before the other unpinned ones
+        pane.update(cx, |pane, cx| {
+            pane.activate_item(0, false, false, cx);
+            pane.toggle_pin_tab(&TogglePinTab, cx);
+        });
+        assert_item_labels(&pane, ["E", "C*", "F", "D"], cx);
+        pane.update(cx, |pane, _| assert_eq!(pane.pinned_tab_count(), 1));
+
+        pane.update(cx, |pane, cx| {
+            pane.close_items_to_the_right(&CloseItemsToTheRight, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+        assert_item_labels(&pane, ["E", "C*"], cx);
+
+        // Closing a pinned item updates the pinned range
+        pane.update(cx, |pane, cx| {
+            pane.close_item_by_id(pane.items[0].item_id(), SaveIntent::Close, cx)
+        })
+        .await
+        .unwrap();
+        assert_item_labels(&pane, ["C*"], cx);
+        pane.update(cx, |pane, _| assert_eq!(pane.pinned_tab_count(), 0));
+    }
+
+    #[gpui::test]
+    async fn test_swap_items_around_pinned_tabs(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        set_labeled_items(&pane, ["A", "B*", "C"], cx);
+        pane.update(cx, |pane, cx| pane.pin_tab_at(1, cx));
+        assert_item_labels(&pane, ["B*", "A", "C"], cx);
+
+        // Items don't cross the boundary between pinned and unpinned items
+        pane.update(cx, |pane, cx| pane.swap_item_right(&SwapItemRight, cx));
+        assert_item_labels(&pane, ["B*", "A", "C"], cx);
+        pane.update(cx, |pane, cx| {
+            pane.activate_item(1, false, false, cx);
+            pane.swap_item_left(&SwapItemLeft, cx);
+        });
+        assert_item_labels(&pane, ["B", "A*", "C"], cx);
+
+        pane.update(cx, |pane, cx| pane.swap_item_right(&SwapItemRight, cx));
+        assert_item_labels(&pane, ["B", "C", "A*"], cx);
+        pane.update(cx, |pane, cx| pane.swap_item_right(&SwapItemRight, cx));
+        assert_item_labels(&pane, ["B", "C", "A*"], cx);
+        pane.update(cx, |pane, cx| pane.swap_item_left(&SwapItemLeft, cx));
+        assert_item_labels(&pane, ["B", "A*", "C"], cx);
+    }
+
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings_store = SettingsStore::test(cx);
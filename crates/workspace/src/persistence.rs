@@ -1,6 +1,6 @@
 pub mod model;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, bail, Context, Result};
 use client::DevServerProjectId;
@@ -207,6 +207,7 @@ define_connection! {
     //     pane_id: usize, // Primary key for panes
     //     workspace_id: usize, // References workspaces table
     //     active: bool,
+    //     pinned_count: usize, // Number of leading items that are pinned
     // )
     //
     // center_panes(
@@ -366,6 +367,19 @@ define_connection! {
     sql!(
         ALTER TABLE workspaces ADD COLUMN local_paths_order BLOB;
     ),
+    sql!(
+        ALTER TABLE panes ADD COLUMN pinned_count INTEGER DEFAULT 0;
+    ),
+    sql!(
+        ALTER TABLE workspaces ADD COLUMN session_id TEXT DEFAULT NULL;
+    ),
+    sql!(
+        CREATE TABLE worktree_files(
+            worktree_root BLOB NOT NULL PRIMARY KEY,
+            paths BLOB NOT NULL,
+            timestamp TEXT DEFAULT CURRENT_TIMESTAMP NOT NULL
+        ) STRICT;
+    ),
     ];
 }
 
@@ -389,6 +403,7 @@ impl WorkspaceDb {
             window_bounds,
             display,
             centered_layout,
+            session_id,
             docks,
         ): (
             WorkspaceId,
@@ -398,6 +413,7 @@ impl WorkspaceDb {
             Option<SerializedWindowBounds>,
             Option<Uuid>,
             Option<bool>,
+            Option<String>,
             DockStructure,
         ) = self
             .select_row_bound(sql! {
@@ -413,6 +429,7 @@ impl WorkspaceDb {
                     window_height,
                     display,
                     centered_layout,
+                    session_id,
                     left_dock_visible,
                     left_dock_active_panel,
                     left_dock_zoom,
@@ -465,6 +482,7 @@ impl WorkspaceDb {
             centered_layout: centered_layout.unwrap_or(false),
             display,
             docks,
+            session_id,
         })
     }
 
@@ -501,9 +519,10 @@ impl WorkspaceDb {
                                 bottom_dock_visible,
                                 bottom_dock_active_panel,
                                 bottom_dock_zoom,
+                                session_id,
                                 timestamp
                             )
-                            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, CURRENT_TIMESTAMP)
+                            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, CURRENT_TIMESTAMP)
                             ON CONFLICT DO
                             UPDATE SET
                                 local_paths = ?2,
@@ -517,8 +536,15 @@ impl WorkspaceDb {
                                 bottom_dock_visible = ?10,
                                 bottom_dock_active_panel = ?11,
                                 bottom_dock_zoom = ?12,
+                                session_id = ?13,
                                 timestamp = CURRENT_TIMESTAMP
-                        ))?((workspace.id, &local_paths, &local_paths_order, workspace.docks))
+                        ))?((
+                            workspace.id,
+                            &local_paths,
+                            &local_paths_order,
+                            workspace.docks,
+                            workspace.session_id,
+                        ))
                         .context("Updating workspace")?;
                     }
                     SerializedWorkspaceLocation::DevServer(dev_server_project) => {
@@ -553,9 +579,10 @@ impl WorkspaceDb {
                                 bottom_dock_visible,
                                 bottom_dock_active_panel,
                                 bottom_dock_zoom,
+                                session_id,
                                 timestamp
                             )
-                            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, CURRENT_TIMESTAMP)
+                            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, CURRENT_TIMESTAMP)
                             ON CONFLICT DO
                             UPDATE SET
                                 dev_server_project_id = ?2,
@@ -568,11 +595,13 @@ impl WorkspaceDb {
                                 bottom_dock_visible = ?9,
                                 bottom_dock_active_panel = ?10,
                                 bottom_dock_zoom = ?11,
+                                session_id = ?12,
                                 timestamp = CURRENT_TIMESTAMP
                         ))?((
                             workspace.id,
                             dev_server_project.id.0,
                             workspace.docks,
+                            workspace.session_id,
                         ))
                         .context("Updating workspace")?;
                     }
@@ -699,6 +728,60 @@ impl WorkspaceDb {
             .next())
     }
 
+    query! {
+        fn session_workspaces(session_id: String) -> Result<Vec<LocalPaths>> {
+            SELECT local_paths
+            FROM workspaces
+            WHERE session_id = ?1 AND local_paths IS NOT NULL
+            ORDER BY timestamp DESC
+        }
+    }
+
+    /// Returns the local paths of the workspaces that were open when the session ended, which
+    /// still exist on disk.
+    pub fn last_session_workspaces(&self, last_session_id: &str) -> Result<Vec<LocalPaths>> {
+        Ok(self
+            .session_workspaces(last_session_id.to_owned())?
+            .into_iter()
+            .filter(|local_paths| {
+                local_paths.paths().iter().all(|path| path.exists())
+                    && local_paths.paths().iter().any(|path| path.is_dir())
+            })
+            .collect())
+    }
+
+    /// Remembers the paths of the files in a worktree, relative to its root, so that they can
+    /// be searched after the worktree is closed.
+    pub async fn save_worktree_files(
+        &self,
+        worktree_root: PathBuf,
+        paths: Vec<PathBuf>,
+    ) -> Result<()> {
+        let paths = bincode::serialize(&paths)?;
+        self.write(move |conn| {
+            conn.exec_bound(sql!(
+                INSERT INTO worktree_files(worktree_root, paths, timestamp)
+                VALUES (?1, ?2, CURRENT_TIMESTAMP)
+                ON CONFLICT DO
+                UPDATE SET
+                    paths = ?2,
+                    timestamp = CURRENT_TIMESTAMP
+            ))?((worktree_root, paths))
+        })
+        .await
+    }
+
+    /// The paths of the files that the worktree had when it was last closed.
+    pub fn worktree_files(&self, worktree_root: &Path) -> Result<Vec<PathBuf>> {
+        let paths = self.select_row_bound::<&Path, Vec<u8>>(sql!(
+            SELECT paths FROM worktree_files WHERE worktree_root = ?
+        ))?(worktree_root)?;
+        match paths {
+            Some(paths) => Ok(bincode::deserialize(&paths)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
     fn get_center_pane_group(&self, workspace_id: WorkspaceId) -> Result<SerializedPaneGroup> {
         Ok(self
             .get_pane_group(workspace_id, None)?
@@ -708,6 +791,7 @@ impl WorkspaceDb {
                 SerializedPaneGroup::Pane(SerializedPane {
                     active: true,
                     children: vec![],
+                    pinned_count: 0,
                 })
             }))
     }
@@ -723,15 +807,17 @@ impl WorkspaceDb {
             Option<SerializedAxis>,
             Option<PaneId>,
             Option<bool>,
+            Option<usize>,
             Option<String>,
         );
         self.select_bound::<GroupKey, GroupOrPane>(sql!(
-            SELECT group_id, axis, pane_id, active, flexes
+            SELECT group_id, axis, pane_id, active, pinned_count, flexes
                 FROM (SELECT
                         group_id,
                         axis,
                         NULL as pane_id,
                         NULL as active,
+                        NULL as pinned_count,
                         position,
                         parent_group_id,
                         workspace_id,
@@ -743,6 +829,7 @@ impl WorkspaceDb {
                         NULL,
                         center_panes.pane_id,
                         panes.active as active,
+                        panes.pinned_count as pinned_count,
                         position,
                         parent_group_id,
                         panes.workspace_id as workspace_id,
@@ -753,7 +840,7 @@ impl WorkspaceDb {
                 ORDER BY position
         ))?((group_id, workspace_id))?
         .into_iter()
-        .map(|(group_id, axis, pane_id, active, flexes)| {
+        .map(|(group_id, axis, pane_id, active, pinned_count, flexes)| {
             if let Some((group_id, axis)) = group_id.zip(axis) {
                 let flexes = flexes
                     .map(|flexes: String| serde_json::from_str::<Vec<f32>>(&flexes))
@@ -768,6 +855,7 @@ impl WorkspaceDb {
                 Ok(SerializedPaneGroup::Pane(SerializedPane::new(
                     self.get_items(pane_id)?,
                     active,
+                    pinned_count.unwrap_or(0),
                 )))
             } else {
                 bail!("Pane Group Child was neither a pane group or a pane");
@@ -839,10 +927,10 @@ impl WorkspaceDb {
         parent: Option<(GroupId, usize)>,
     ) -> Result<PaneId> {
         let pane_id = conn.select_row_bound::<_, i64>(sql!(
-            INSERT INTO panes(workspace_id, active)
-            VALUES (?, ?)
+            INSERT INTO panes(workspace_id, active, pinned_count)
+            VALUES (?, ?, ?)
             RETURNING pane_id
-        ))?((workspace_id, pane.active))?
+        ))?((workspace_id, pane.active, pane.pinned_count))?
         .ok_or_else(|| anyhow!("Could not retrieve inserted pane_id"))?;
 
         let (parent_id, order) = parent.unzip();
@@ -901,6 +989,14 @@ impl WorkspaceDb {
         }
     }
 
+    query! {
+        pub(crate) async fn set_session_id(workspace_id: WorkspaceId, session_id: Option<String>) -> Result<()> {
+            UPDATE workspaces
+            SET session_id = ?2
+            WHERE workspace_id = ?1
+        }
+    }
+
     query! {
         pub(crate) async fn set_centered_layout(workspace_id: WorkspaceId, centered_layout: bool) -> Result<()> {
             UPDATE workspaces
@@ -994,6 +1090,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            session_id: None,
         };
 
         let workspace_2 = SerializedWorkspace {
@@ -1004,6 +1101,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            session_id: None,
         };
 
         db.save_workspace(workspace_1.clone()).await;
@@ -1075,6 +1173,7 @@ mod tests {
                                 SerializedItem::new("Terminal", 6, true, false),
                             ],
                             false,
+                            1,
                         )),
                         SerializedPaneGroup::Pane(SerializedPane::new(
                             vec![
@@ -1082,6 +1181,7 @@ mod tests {
                                 SerializedItem::new("Terminal", 8, false, false),
                             ],
                             false,
+                            0,
                         )),
                     ],
                 ),
@@ -1091,6 +1191,7 @@ mod tests {
                         SerializedItem::new("Terminal", 10, true, false),
                     ],
                     false,
+                    0,
                 )),
             ],
         );
@@ -1106,6 +1207,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            session_id: None,
         };
 
         db.save_workspace(workspace.clone()).await;
@@ -1121,6 +1223,99 @@ mod tests {
         assert_eq!(workspace, round_trip_workspace.unwrap());
     }
 
+    #[gpui::test]
+    async fn test_last_session_workspaces() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_last_session_workspaces").await);
+
+        let workspace_1 = SerializedWorkspace {
+            id: WorkspaceId(1),
+            location: LocalPaths::new(["/tmp"]).into(),
+            center_group: Default::default(),
+            window_bounds: Default::default(),
+            display: Default::default(),
+            docks: Default::default(),
+            centered_layout: false,
+            session_id: Some("session-1".to_string()),
+        };
+        let workspace_2 = SerializedWorkspace {
+            id: WorkspaceId(2),
+            location: LocalPaths::new(["/tmp", "/tmp/zed-missing-directory"]).into(),
+            center_group: Default::default(),
+            window_bounds: Default::default(),
+            display: Default::default(),
+            docks: Default::default(),
+            centered_layout: false,
+            session_id: Some("session-1".to_string()),
+        };
+        let workspace_3 = SerializedWorkspace {
+            id: WorkspaceId(3),
+            location: LocalPaths::new([env!("CARGO_MANIFEST_DIR")]).into(),
+            center_group: Default::default(),
+            window_bounds: Default::default(),
+            display: Default::default(),
+            docks: Default::default(),
+            centered_layout: false,
+            session_id: Some("session-2".to_string()),
+        };
+
+        db.save_workspace(workspace_1.clone()).await;
+        db.save_workspace(workspace_2.clone()).await;
+        db.save_workspace(workspace_3.clone()).await;
+        assert_eq!(db.workspace_for_roots(&["/tmp"]).unwrap(), workspace_1);
+
+        // Workspaces whose paths no longer exist aren't restored.
+        assert_eq!(
+            db.last_session_workspaces("session-1").unwrap(),
+            [LocalPaths::new(["/tmp"])]
+        );
+        assert_eq!(
+            db.last_session_workspaces("session-2").unwrap(),
+            [LocalPaths::new([env!("CARGO_MANIFEST_DIR")])]
+        );
+
+        // Workspaces whose windows were closed during the session aren't restored either.
+        db.set_session_id(workspace_1.id, None).await.unwrap();
+        assert!(db.last_session_workspaces("session-1").unwrap().is_empty());
+        assert_eq!(db.workspace_for_roots(&["/tmp"]).unwrap().session_id, None);
+    }
+
+    #[gpui::test]
+    async fn test_worktree_files() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_worktree_files").await);
+
+        assert_eq!(
+            db.worktree_files(Path::new("/tmp")).unwrap(),
+            Vec::<PathBuf>::new()
+        );
+
+        db.save_worktree_files("/tmp".into(), vec!["a.rs".into(), "src/b.rs".into()])
+            .await
+            .unwrap();
+        db.save_worktree_files("/tmp2".into(), vec!["c.rs".into()])
+            .await
+            .unwrap();
+        assert_eq!(
+            db.worktree_files(Path::new("/tmp")).unwrap(),
+            [PathBuf::from("a.rs"), PathBuf::from("src/b.rs")]
+        );
+
+        db.save_worktree_files("/tmp".into(), vec!["d.rs".into()])
+            .await
+            .unwrap();
+        assert_eq!(
+            db.worktree_files(Path::new("/tmp")).unwrap(),
+            [PathBuf::from("d.rs")]
+        );
+        assert_eq!(
+            db.worktree_files(Path::new("/tmp2")).unwrap(),
+            [PathBuf::from("c.rs")]
+        );
+    }
+
     #[gpui::test]
     async fn test_workspace_assignment() {
         env_logger::try_init().ok();
@@ -1138,6 +1333,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            session_id: None,
         };
 
         let mut workspace_2 = SerializedWorkspace {
@@ -1148,6 +1344,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            session_id: None,
         };
 
         db.save_workspace(workspace_1.clone()).await;
@@ -1188,6 +1385,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            session_id: None,
         };
 
         db.save_workspace(workspace_3.clone()).await;
@@ -1222,6 +1420,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
+            session_id: None,
         }
     }
 
@@ -1248,6 +1447,7 @@ mod tests {
                                 SerializedItem::new("Terminal", 2, true, false),
                             ],
                             false,
+                            0,
                         )),
                         SerializedPaneGroup::Pane(SerializedPane::new(
                             vec![
@@ -1255,6 +1455,7 @@ mod tests {
                                 SerializedItem::new("Terminal", 3, true, false),
                             ],
                             true,
+                            0,
                         )),
                     ],
                 ),
@@ -1264,6 +1465,7 @@ mod tests {
                         SerializedItem::new("Terminal", 6, false, false),
                     ],
                     false,
+                    0,
                 )),
             ],
         );
@@ -1295,6 +1497,7 @@ mod tests {
                                 SerializedItem::new("Terminal", 2, true, false),
                             ],
                             false,
+                            0,
                         )),
                         SerializedPaneGroup::Pane(SerializedPane::new(
                             vec![
@@ -1302,6 +1505,7 @@ mod tests {
                                 SerializedItem::new("Terminal", 3, true, false),
                             ],
                             true,
+                            0,
                         )),
                     ],
                 ),
@@ -1311,6 +1515,7 @@ mod tests {
                         SerializedItem::new("Terminal", 6, true, false),
                     ],
                     false,
+                    0,
                 )),
             ],
         );
@@ -1330,6 +1535,7 @@ mod tests {
                         SerializedItem::new("Terminal", 2, true, false),
                     ],
                     false,
+                    0,
                 )),
                 SerializedPaneGroup::Pane(SerializedPane::new(
                     vec![
@@ -1337,6 +1543,7 @@ mod tests {
                         SerializedItem::new("Terminal", 3, false, false),
                     ],
                     true,
+                    0,
                 )),
             ],
         );
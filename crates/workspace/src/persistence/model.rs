@@ -154,6 +154,8 @@ pub(crate) struct SerializedWorkspace {
     pub(crate) centered_layout: bool,
     pub(crate) display: Option<Uuid>,
     pub(crate) docks: DockStructure,
+    /// The run of the app that the workspace was last open in.
+    pub(crate) session_id: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -234,6 +236,7 @@ impl Default for SerializedPaneGroup {
         Self::Pane(SerializedPane {
             children: vec![SerializedItem::default()],
             active: false,
+            pinned_count: 0,
         })
     }
 }
@@ -310,11 +313,16 @@ impl SerializedPaneGroup {
 pub struct SerializedPane {
     pub(crate) active: bool,
     pub(crate) children: Vec<SerializedItem>,
+    pub(crate) pinned_count: usize,
 }
 
 impl SerializedPane {
-    pub fn new(children: Vec<SerializedItem>, active: bool) -> Self {
-        SerializedPane { children, active }
+    pub fn new(children: Vec<SerializedItem>, active: bool, pinned_count: usize) -> Self {
+        SerializedPane {
+            children,
+            active,
+            pinned_count,
+        }
     }
 
     pub async fn deserialize_to(
@@ -349,17 +357,31 @@ impl SerializedPane {
         }
 
         let mut items = Vec::new();
-        for item_handle in futures::future::join_all(item_tasks).await {
+        let mut pinned_count = 0;
+        for (index, item_handle) in futures::future::join_all(item_tasks)
+            .await
+            .into_iter()
+            .enumerate()
+        {
             let item_handle = item_handle.log_err();
             items.push(item_handle.clone());
 
             if let Some(item_handle) = item_handle {
+                if index < self.pinned_count {
+                    pinned_count += 1;
+                }
                 pane.update(cx, |pane, cx| {
                     pane.add_item(item_handle.clone(), true, true, None, cx);
                 })?;
             }
         }
 
+        pane.update(cx, |pane, cx| {
+            for ix in 0..pinned_count {
+                pane.pin_tab_at(ix, cx);
+            }
+        })?;
+
         if let Some(active_item_index) = active_item_index {
             pane.update(cx, |pane, cx| {
                 pane.activate_item(active_item_index, false, false, cx);
@@ -1,3 +1,4 @@
+mod accessibility;
 pub mod dock;
 pub mod item;
 mod modal_layer;
@@ -12,6 +13,7 @@ pub mod tasks;
 mod toolbar;
 mod workspace_settings;
 
+pub use accessibility::{AccessibleItem, AccessiblePane};
 use anyhow::{anyhow, Context as _, Result};
 use call::{call_settings::CallSettings, ActiveCall};
 use client::{
@@ -35,8 +37,8 @@ use gpui::{
     WindowBounds, WindowHandle, WindowOptions,
 };
 use item::{
-    FollowableItem, FollowableItemHandle, Item, ItemHandle, ItemSettings, PreviewTabsSettings,
-    ProjectItem,
+    DirtyState, FollowableItem, FollowableItemHandle, Item, ItemHandle, ItemSettings,
+    PreviewTabsSettings, ProjectItem,
 };
 use itertools::Itertools;
 use language::{LanguageRegistry, Rope};
@@ -52,9 +54,11 @@ pub use persistence::{
     WorkspaceDb, DB as WORKSPACE_DB,
 };
 use postage::stream::Stream;
-use project::{Project, ProjectEntryId, ProjectPath, Worktree, WorktreeId};
+use project::{
+    job_scheduler::JobEvent, Project, ProjectEntryId, ProjectPath, Worktree, WorktreeId,
+};
 use serde::Deserialize;
-use settings::Settings;
+use settings::{Settings, SettingsStore};
 use shared_screen::SharedScreen;
 use sqlez::{
     bindable::{Bind, Column, StaticColumnCount},
@@ -87,7 +91,8 @@ use ui::{
 use util::{maybe, ResultExt};
 use uuid::Uuid;
 pub use workspace_settings::{
-    AutosaveSetting, RestoreOnStartupBehaviour, TabBarSettings, WorkspaceSettings,
+    AutosaveSetting, LayoutAutosaveSetting, RestoreOnStartupBehaviour, TabBarSettings,
+    WorkspaceSettings,
 };
 
 use crate::persistence::{
@@ -421,10 +426,23 @@ pub struct AppState {
     pub node_runtime: Arc<dyn NodeRuntime>,
 }
 
+/// The most files of a worktree that are remembered for searching it once it's closed.
+const MAX_SAVED_WORKTREE_FILES: usize = 100_000;
+
 struct GlobalAppState(Weak<AppState>);
 
 impl Global for GlobalAppState {}
 
+/// The id of the app's current run, saved with each workspace so that every window that was
+/// open when the app quit can be restored.
+struct GlobalSessionId(String);
+
+impl Global for GlobalSessionId {}
+
+pub fn set_session_id(session_id: String, cx: &mut AppContext) {
+    cx.set_global(GlobalSessionId(session_id));
+}
+
 pub struct WorkspaceStore {
     workspaces: HashSet<WindowHandle<Workspace>>,
     client: Arc<Client>,
@@ -452,7 +470,6 @@ impl AppState {
     #[cfg(any(test, feature = "test-support"))]
     pub fn test(cx: &mut AppContext) -> Arc<Self> {
         use node_runtime::FakeNodeRuntime;
-        use settings::SettingsStore;
         use ui::Context as _;
 
         if !cx.has_global::<SettingsStore>() {
@@ -675,6 +692,44 @@ impl Workspace {
                     )
                 }
 
+                project::Event::BufferChangedOnDisk(buffer) => {
+                    struct BufferChangedOnDisk;
+
+                    let path = buffer
+                        .read(cx)
+                        .file()
+                        .map(|file| file.path().to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let project = this.project.clone();
+                    let buffer = buffer.clone();
+                    this.show_notification(
+                        NotificationId::identified::<BufferChangedOnDisk>(
+                            buffer.entity_id().as_u64() as usize,
+                        ),
+                        cx,
+                        |cx| {
+                            cx.new_view(|_| {
+                                MessageNotification::new(format!(
+                                    "{path} changed on disk while it had unsaved changes."
+                                ))
+                                .with_click_message("Reload")
+                                .on_click(move |cx| {
+                                    project
+                                        .update(cx, |project, cx| {
+                                            project.reload_buffers(
+                                                HashSet::from_iter([buffer.clone()]),
+                                                true,
+                                                cx,
+                                            )
+                                        })
+                                        .detach_and_log_err(cx);
+                                })
+                                .with_secondary_click_message("Keep My Changes")
+                            })
+                        },
+                    );
+                }
+
                 project::Event::LanguageServerPrompt(request) => {
                     struct LanguageServerPrompt;
 
@@ -699,6 +754,27 @@ impl Workspace {
         })
         .detach();
 
+        let job_scheduler = project.read(cx).job_scheduler().clone();
+        cx.subscribe(&job_scheduler, |this, job_scheduler, event, cx| {
+            if let JobEvent::Progress(job_id) = *event {
+                let id = NotificationId::identified::<notifications::JobProgressNotification>(
+                    job_id.to_usize(),
+                );
+                if !this
+                    .notifications
+                    .iter()
+                    .any(|(existing_id, _)| existing_id == &id)
+                {
+                    this.show_notification(id, cx, |cx| {
+                        cx.new_view(|cx| {
+                            notifications::JobProgressNotification::new(job_scheduler, job_id, cx)
+                        })
+                    });
+                }
+            }
+        })
+        .detach();
+
         cx.on_focus_lost(|this, cx| {
             let focus_handle = this.focus_handle(cx);
             cx.focus(&focus_handle);
@@ -783,6 +859,7 @@ impl Workspace {
 
         let subscriptions = vec![
             cx.observe_window_activation(Self::on_window_activation_changed),
+            cx.observe_global::<SettingsStore>(|this, cx| this.update_window_title(cx)),
             cx.observe_window_bounds(move |this, cx| {
                 if this.bounds_save_task_queued.is_some() {
                     return;
@@ -1370,9 +1447,12 @@ impl Workspace {
 
     pub fn close_window(&mut self, _: &CloseWindow, cx: &mut ViewContext<Self>) {
         let window = cx.window_handle();
+        let workspace_id = self.database_id;
         let prepare = self.prepare_to_close(false, cx);
         cx.spawn(|_, mut cx| async move {
             if prepare.await? {
+                // A window closed before quitting isn't restored with the session.
+                DB.set_session_id(workspace_id, None).await.log_err();
                 window.update(&mut cx, |_, cx| {
                     cx.remove_window();
                 })?;
@@ -1423,10 +1503,43 @@ impl Workspace {
                 }
             }
 
-            this.update(&mut cx, |this, cx| {
-                this.save_all_internal(SaveIntent::Close, cx)
-            })?
-            .await
+            let can_close = this
+                .update(&mut cx, |this, cx| {
+                    this.save_all_internal(SaveIntent::Close, cx)
+                })?
+                .await?;
+            if can_close {
+                this.update(&mut cx, |this, cx| this.save_worktree_files(cx))?
+                    .await;
+                this.update(&mut cx, |this, cx| this.serialize_workspace_internal(cx))?
+                    .await;
+            }
+            Ok(can_close)
+        })
+    }
+
+    /// Remembers the files of the project's local worktrees, so that they can be searched
+    /// after the window is closed.
+    fn save_worktree_files(&self, cx: &mut ViewContext<Self>) -> Task<()> {
+        let project = self.project.read(cx);
+        if !project.is_local() {
+            return Task::ready(());
+        }
+        let snapshots = project
+            .visible_worktrees(cx)
+            .map(|worktree| worktree.read(cx).snapshot())
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            for snapshot in snapshots {
+                let paths = snapshot
+                    .files(false, true, 0)
+                    .take(MAX_SAVED_WORKTREE_FILES)
+                    .map(|entry| entry.path.to_path_buf())
+                    .collect();
+                DB.save_worktree_files(snapshot.abs_path().to_path_buf(), paths)
+                    .await
+                    .log_err();
+            }
         })
     }
 
@@ -1507,17 +1620,12 @@ impl Workspace {
                         dirty_items.len(),
                         cx,
                     );
-                    cx.prompt(
-                        PromptLevel::Warning,
-                        &prompt,
-                        Some(&detail),
-                        &["Save all", "Discard all", "Cancel"],
-                    )
+                    CloseConfirmation::prompt(&prompt, Some(&detail), dirty_items.len(), cx)
                 })?;
-                match answer.await.log_err() {
-                    Some(0) => save_intent = SaveIntent::SaveAll,
-                    Some(1) => save_intent = SaveIntent::Skip,
-                    _ => {}
+                match answer.await {
+                    CloseConfirmation::Save => save_intent = SaveIntent::SaveAll,
+                    CloseConfirmation::DontSave => save_intent = SaveIntent::Skip,
+                    CloseConfirmation::Cancel => {}
                 }
             }
             for (pane, item) in dirty_items {
@@ -2166,6 +2274,94 @@ impl Workspace {
         })
     }
 
+    /// Moves the item to another window's workspace: its file is opened there and the item is
+    /// closed here, after asking to save it if it has unsaved changes. Nothing is moved if
+    /// closing the item is cancelled.
+    pub fn move_item_to_workspace(
+        &mut self,
+        item_id: EntityId,
+        target: WindowHandle<Workspace>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let Some(pane) = self
+            .panes_by_item
+            .get(&item_id)
+            .and_then(|pane| pane.upgrade())
+        else {
+            return Task::ready(Err(anyhow!("no item with id {item_id:?} in the workspace")));
+        };
+        let Some(abs_path) = pane
+            .read(cx)
+            .items()
+            .find(|item| item.item_id() == item_id)
+            .and_then(|item| item.project_path(cx))
+            .and_then(|project_path| self.project.read(cx).absolute_path(&project_path, cx))
+        else {
+            return Task::ready(Err(anyhow!("item {item_id:?} isn't a file on disk")));
+        };
+
+        let close = pane.update(cx, |pane, cx| {
+            pane.close_item_by_id(item_id, SaveIntent::Close, cx)
+        });
+        cx.spawn(|_, mut cx| async move {
+            close.await?;
+            let closed = pane.update(&mut cx, |pane, _| {
+                pane.items().all(|item| item.item_id() != item_id)
+            })?;
+            if closed {
+                target
+                    .update(&mut cx, |workspace, cx| {
+                        cx.activate_window();
+                        workspace.open_abs_path(abs_path, false, cx)
+                    })?
+                    .await?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Moves the worktree to another window's workspace, along with the items open in it.
+    pub fn move_worktree_to_workspace(
+        &mut self,
+        worktree_id: WorktreeId,
+        target: WindowHandle<Workspace>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let Some(worktree) = self.project.read(cx).worktree_for_id(worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no worktree with id {worktree_id:?}")));
+        };
+        let abs_path = worktree.read(cx).abs_path().to_path_buf();
+        let item_ids = self
+            .items(cx)
+            .filter(|item| {
+                item.project_path(cx)
+                    .is_some_and(|project_path| project_path.worktree_id == worktree_id)
+            })
+            .map(|item| item.item_id())
+            .collect::<Vec<_>>();
+
+        cx.spawn(|this, mut cx| async move {
+            target
+                .update(&mut cx, |workspace, cx| {
+                    workspace.project.update(cx, |project, cx| {
+                        project.find_or_create_local_worktree(&abs_path, true, cx)
+                    })
+                })?
+                .await?;
+            for item_id in item_ids {
+                this.update(&mut cx, |this, cx| {
+                    this.move_item_to_workspace(item_id, target, cx)
+                })?
+                .await?;
+            }
+            this.update(&mut cx, |this, cx| {
+                this.project
+                    .update(cx, |project, cx| project.remove_worktree(worktree_id, cx))
+            })?;
+            Ok(())
+        })
+    }
+
     pub fn split_abs_path(
         &mut self,
         abs_path: PathBuf,
@@ -2558,6 +2754,7 @@ impl Workspace {
                     self.update_active_view_for_followers(cx);
                 }
             }
+            pane::Event::ReorderItems => {}
             pane::Event::ChangeItemTitle => {
                 if pane == self.active_pane {
                     self.active_item_path_changed(cx);
@@ -2948,34 +3145,46 @@ impl Workspace {
 
     fn update_window_title(&mut self, cx: &mut WindowContext) {
         let project = self.project().read(cx);
-        let mut title = String::new();
-
-        if let Some(path) = self.active_item(cx).and_then(|item| item.project_path(cx)) {
-            let filename = path
-                .path
+        let active_item = self.active_item(cx);
+        let active_path = active_item.as_ref().and_then(|item| item.project_path(cx));
+        let active_worktree = active_path
+            .as_ref()
+            .and_then(|path| project.worktree_for_id(path.worktree_id, cx));
+        let filename = active_path.as_ref().and_then(|path| {
+            path.path
                 .file_name()
                 .map(|s| s.to_string_lossy())
                 .or_else(|| {
                     Some(Cow::Borrowed(
-                        project
-                            .worktree_for_id(path.worktree_id, cx)?
-                            .read(cx)
-                            .root_name(),
+                        active_worktree.as_ref()?.read(cx).root_name(),
                     ))
-                });
-
-            if let Some(filename) = filename {
-                title.push_str(filename.as_ref());
-                title.push_str(" — ");
-            }
-        }
+                })
+        });
+        let project_name = project.worktree_root_names(cx).join(", ");
 
-        for (i, name) in project.worktree_root_names(cx).enumerate() {
-            if i > 0 {
-                title.push_str(", ");
-            }
-            title.push_str(name);
-        }
+        let mut title = if let Some(template) = &WorkspaceSettings::get_global(cx).window_title {
+            let relative_dir = active_path
+                .as_ref()
+                .and_then(|path| Some(path.path.parent()?.to_string_lossy()));
+            format_window_title(
+                template,
+                &WindowTitleFields {
+                    filename: filename.as_deref(),
+                    dirty: active_item.map_or(false, |item| item.is_dirty(cx)),
+                    worktree: active_worktree
+                        .as_ref()
+                        .map_or(project_name.as_str(), |worktree| {
+                            worktree.read(cx).root_name()
+                        }),
+                    relative_dir: relative_dir.as_deref(),
+                    project: &project_name,
+                },
+            )
+        } else if let Some(filename) = filename {
+            format!("{filename} — {project_name}")
+        } else {
+            project_name
+        };
 
         if title.is_empty() {
             title = "empty project".to_string();
@@ -2994,7 +3203,7 @@ impl Workspace {
         let is_edited = !self.project.read(cx).is_disconnected()
             && self
                 .items(cx)
-                .any(|item| item.has_conflict(cx) || item.is_dirty(cx));
+                .any(|item| item.dirty_state(cx) != DirtyState::Clean);
         if is_edited != self.window_edited {
             self.window_edited = is_edited;
             cx.set_window_edited(self.window_edited)
@@ -3551,10 +3760,15 @@ impl Workspace {
     }
 
     fn serialize_workspace(&mut self, cx: &mut ViewContext<Self>) {
+        let LayoutAutosaveSetting::AfterDelay { milliseconds } =
+            WorkspaceSettings::get_global(cx).layout_autosave
+        else {
+            return;
+        };
         if self._schedule_serialize.is_none() {
             self._schedule_serialize = Some(cx.spawn(|this, mut cx| async move {
                 cx.background_executor()
-                    .timer(Duration::from_millis(100))
+                    .timer(Duration::from_millis(milliseconds))
                     .await;
                 this.update(&mut cx, |this, cx| {
                     this.serialize_workspace_internal(cx).detach();
@@ -3567,9 +3781,15 @@ impl Workspace {
 
     fn serialize_workspace_internal(&self, cx: &mut WindowContext) -> Task<()> {
         fn serialize_pane_handle(pane_handle: &View<Pane>, cx: &WindowContext) -> SerializedPane {
-            let (items, active) = {
+            let (items, active, pinned_count) = {
                 let pane = pane_handle.read(cx);
                 let active_item_id = pane.active_item().map(|item| item.item_id());
+                // Only count the pinned items that will actually be restored
+                let pinned_count = pane
+                    .items()
+                    .take(pane.pinned_tab_count())
+                    .filter(|item_handle| item_handle.serialized_item_kind().is_some())
+                    .count();
                 (
                     pane.items()
                         .filter_map(|item_handle| {
@@ -3582,10 +3802,11 @@ impl Workspace {
                         })
                         .collect::<Vec<_>>(),
                     pane.has_focus(cx),
+                    pinned_count,
                 )
             };
 
-            SerializedPane::new(items, active)
+            SerializedPane::new(items, active, pinned_count)
         }
 
         fn build_serialized_pane_group(
@@ -3708,6 +3929,9 @@ impl Workspace {
                 display: Default::default(),
                 docks,
                 centered_layout: self.centered_layout,
+                session_id: cx
+                    .try_global::<GlobalSessionId>()
+                    .map(|session_id| session_id.0.clone()),
             };
             return cx.spawn(|_| persistence::DB.save_workspace(serialized_workspace));
         }
@@ -3958,6 +4182,59 @@ impl Workspace {
     }
 }
 
+/// The values that can be included in the window title by the `window_title` setting.
+struct WindowTitleFields<'a> {
+    filename: Option<&'a str>,
+    dirty: bool,
+    worktree: &'a str,
+    relative_dir: Option<&'a str>,
+    project: &'a str,
+}
+
+impl WindowTitleFields<'_> {
+    fn get(&self, name: &str) -> Option<&str> {
+        match name {
+            "filename" => Some(self.filename.unwrap_or_default()),
+            "dirty" => Some(if self.dirty { "•" } else { "" }),
+            "worktree" => Some(self.worktree),
+            "relative_dir" => Some(self.relative_dir.unwrap_or_default()),
+            "project" => Some(self.project),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the window title from a template, in which `{field}` is replaced by the field's
+/// value and `{field:text}` by `text` if the field isn't empty. Unknown fields are kept as is.
+fn format_window_title(template: &str, fields: &WindowTitleFields) -> String {
+    let mut title = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        title.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let placeholder = &rest[1..end];
+        let (name, text) = match placeholder.split_once(':') {
+            Some((name, text)) => (name, Some(text)),
+            None => (placeholder, None),
+        };
+        match (fields.get(name), text) {
+            (Some(value), None) => title.push_str(value),
+            (Some(value), Some(text)) => {
+                if !value.is_empty() {
+                    title.push_str(text);
+                }
+            }
+            (None, _) => title.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    title.push_str(rest);
+    title
+}
+
 fn window_bounds_env_override() -> Option<Bounds<DevicePixels>> {
     ZED_WINDOW_POSITION
         .zip(*ZED_WINDOW_SIZE)
@@ -4406,10 +4683,13 @@ impl WorkspaceHandle for View<Workspace> {
             .worktrees(cx)
             .flat_map(|worktree| {
                 let worktree_id = worktree.read(cx).id();
-                worktree.read(cx).files(true, 0).map(move |f| ProjectPath {
-                    worktree_id,
-                    path: f.path.clone(),
-                })
+                worktree
+                    .read(cx)
+                    .files(true, true, 0)
+                    .map(move |f| ProjectPath {
+                        worktree_id,
+                        path: f.path.clone(),
+                    })
             })
             .collect::<Vec<_>>()
     }
@@ -4457,6 +4737,13 @@ pub async fn last_opened_workspace_paths() -> Option<LocalPaths> {
     DB.last_workspace().await.log_err().flatten()
 }
 
+/// The paths of the workspaces whose windows were open when the given session ended.
+pub fn last_session_workspace_paths(last_session_id: &str) -> Vec<LocalPaths> {
+    DB.last_session_workspaces(last_session_id)
+        .log_err()
+        .unwrap_or_default()
+}
+
 actions!(collab, [OpenChannelNotes]);
 actions!(zed, [OpenLog]);
 
@@ -4844,8 +5131,13 @@ pub fn create_and_open_local_file(
         let fs = workspace.update(&mut cx, |workspace, _| workspace.app_state().fs.clone())?;
         if !fs.is_file(path).await {
             fs.create_file(path, Default::default()).await?;
-            fs.save(path, &default_content(), Default::default())
-                .await?;
+            fs.save(
+                path,
+                &default_content(),
+                Default::default(),
+                Default::default(),
+            )
+            .await?;
         }
 
         let mut items = workspace
@@ -5266,6 +5558,71 @@ mod tests {
         item3.update(cx, |item, _| assert_eq!(item.tab_detail.get(), Some(3)));
     }
 
+    #[gpui::test]
+    async fn test_accessible_panes(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        let item1 = cx.new_view(|cx| {
+            let mut item = TestItem::new(cx).with_dirty(true);
+            item.tab_descriptions = Some(vec!["a.txt"]);
+            item
+        });
+        let item2 = cx.new_view(|cx| TestItem::new(cx));
+        let item3 = cx.new_view(|cx| {
+            let mut item = TestItem::new(cx);
+            item.tab_descriptions = Some(vec!["b.txt"]);
+            item
+        });
+        let left_pane = workspace.update(cx, |workspace, cx| {
+            let left_pane = workspace.active_pane().clone();
+            workspace.add_item_to_active_pane(Box::new(item1.clone()), None, cx);
+            workspace.add_item_to_active_pane(Box::new(item2.clone()), None, cx);
+            let right_pane = workspace.split_pane(left_pane.clone(), SplitDirection::Right, cx);
+            right_pane.update(cx, |pane, cx| {
+                pane.add_item(Box::new(item3.clone()), true, true, None, cx);
+            });
+            left_pane
+        });
+        cx.focus_view(&left_pane);
+
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(
+                workspace.accessible_panes(cx),
+                vec![
+                    AccessiblePane {
+                        items: vec![
+                            AccessibleItem {
+                                id: item1.entity_id(),
+                                label: Some("a.txt".into()),
+                                is_dirty: true,
+                            },
+                            AccessibleItem {
+                                id: item2.entity_id(),
+                                label: None,
+                                is_dirty: false,
+                            },
+                        ],
+                        active_item_index: 1,
+                        is_active: true,
+                    },
+                    AccessiblePane {
+                        items: vec![AccessibleItem {
+                            id: item3.entity_id(),
+                            label: Some("b.txt".into()),
+                            is_dirty: false,
+                        }],
+                        active_item_index: 0,
+                        is_active: false,
+                    },
+                ]
+            );
+        });
+    }
+
     #[gpui::test]
     async fn test_tracking_active_path(cx: &mut TestAppContext) {
         init_test(cx);
@@ -5359,6 +5716,56 @@ mod tests {
         assert_eq!(cx.window_title().as_deref(), Some("one.txt — root2"));
     }
 
+    #[gpui::test]
+    async fn test_window_title_format(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/root1", json!({ "dir": { "one.txt": "" } }))
+            .await;
+        let project = Project::test(fs, ["root1".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |settings, cx| {
+                settings.update_user_settings::<WorkspaceSettings>(cx, |settings| {
+                    settings.window_title =
+                        Some("{filename}{dirty: •} — {worktree}/{relative_dir} {other}".into());
+                })
+            });
+        });
+        let item = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_project_items(&[TestProjectItem::new(1, "dir/one.txt", cx)])
+        });
+        workspace.update(cx, |workspace, cx| {
+            workspace.add_item_to_active_pane(Box::new(item.clone()), None, cx)
+        });
+        assert_eq!(
+            cx.window_title().as_deref(),
+            Some("one.txt • — root1/dir {other}")
+        );
+
+        item.update(cx, |item, cx| {
+            item.is_dirty = false;
+            cx.emit(ItemEvent::UpdateTab);
+        });
+        assert_eq!(
+            cx.window_title().as_deref(),
+            Some("one.txt — root1/dir {other}")
+        );
+
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |settings, cx| {
+                settings.update_user_settings::<WorkspaceSettings>(cx, |settings| {
+                    settings.window_title = None;
+                })
+            });
+        });
+        assert_eq!(cx.window_title().as_deref(), Some("one.txt — root1"));
+    }
+
     #[gpui::test]
     async fn test_close_window(cx: &mut TestAppContext) {
         init_test(cx);
@@ -12,8 +12,11 @@ pub struct WorkspaceSettings {
     pub show_call_status_icon: bool,
     pub autosave: AutosaveSetting,
     pub restore_on_startup: RestoreOnStartupBehaviour,
+    pub restore_after_crash: bool,
+    pub layout_autosave: LayoutAutosaveSetting,
     pub drop_target_size: f32,
     pub when_closing_with_no_tabs: CloseWindowWhenNoItems,
+    pub window_title: Option<String>,
 }
 
 #[derive(Copy, Clone, Default, Serialize, Deserialize, JsonSchema)]
@@ -46,6 +49,18 @@ pub enum RestoreOnStartupBehaviour {
     /// Restore the workspace that was closed last.
     #[default]
     LastWorkspace,
+    /// Restore every window that was open when Zed was last quit, with its worktrees, panes
+    /// and size.
+    LastSession,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutAutosaveSetting {
+    /// Save the layout only when its window is closed.
+    Off,
+    /// Save the layout `milliseconds` after it changes.
+    AfterDelay { milliseconds: u64 },
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
@@ -71,9 +86,19 @@ pub struct WorkspaceSettingsContent {
     /// Default: off
     pub autosave: Option<AutosaveSetting>,
     /// Controls previous session restoration in freshly launched Zed instance.
-    /// Values: none, last_workspace
+    /// Values: none, last_workspace, last_session
     /// Default: last_workspace
     pub restore_on_startup: Option<RestoreOnStartupBehaviour>,
+    /// Whether to offer restoring the windows of the previous session along with their unsaved
+    /// changes when Zed didn't quit normally, e.g. because it crashed.
+    ///
+    /// Default: true
+    pub restore_after_crash: Option<bool>,
+    /// When to save the layout of the workspace's panes, docks and window, which is restored
+    /// when the workspace is opened again.
+    ///
+    /// Default: { "after_delay": { "milliseconds": 100 } }
+    pub layout_autosave: Option<LayoutAutosaveSetting>,
     /// The size of the workspace split drop targets on the outer edges.
     /// Given as a fraction that will be multiplied by the smaller dimension of the workspace.
     ///
@@ -83,6 +108,13 @@ pub struct WorkspaceSettingsContent {
     ///
     /// Default: auto ("on" on macOS, "off" otherwise)
     pub when_closing_with_no_tabs: Option<CloseWindowWhenNoItems>,
+    /// The format of the window title, in which `{filename}`, `{worktree}`, `{relative_dir}`
+    /// and `{project}` are replaced by the values for the active item, and `{dirty:text}` is
+    /// replaced by `text` when the active item has unsaved changes. When null, the title
+    /// shows the active item's file name followed by the project's name.
+    ///
+    /// Default: null
+    pub window_title: Option<String>,
 }
 
 #[derive(Deserialize)]
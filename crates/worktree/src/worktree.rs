@@ -7,15 +7,16 @@ use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
 use anyhow::{anyhow, Context as _, Result};
 use client::{proto, Client};
 use clock::ReplicaId;
-use collections::{HashMap, HashSet, VecDeque};
+use collections::{hash_map, HashMap, HashSet, VecDeque};
 use fs::Fs;
-use fs::{copy_recursive, RemoveOptions};
+use fs::{copy_recursive, CreateOptions, FileId, PollOptions, RemoveOptions};
 use futures::stream::select;
 use futures::{
     channel::{
         mpsc::{self, UnboundedSender},
         oneshot,
     },
+    future::Shared,
     select_biased,
     task::Poll,
     FutureExt as _, Stream, StreamExt,
@@ -53,12 +54,13 @@ use std::{
     ffi::OsStr,
     fmt,
     future::Future,
-    mem,
+    hash::{DefaultHasher, Hasher},
+    io, mem,
     ops::{AddAssign, Deref, DerefMut, Sub},
     path::{Path, PathBuf},
     pin::Pin,
     sync::{
-        atomic::{AtomicUsize, Ordering::SeqCst},
+        atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst},
         Arc,
     },
     time::{Duration, SystemTime},
@@ -70,7 +72,8 @@ use util::{
     ResultExt,
 };
 
-pub use worktree_settings::WorktreeSettings;
+pub use fs::Durability;
+pub use worktree_settings::{ScanningProfile, SymlinkPolicy, WorktreeSettings};
 
 #[cfg(feature = "test-support")]
 pub const FS_WATCH_LATENCY: Duration = Duration::from_millis(100);
@@ -78,6 +81,11 @@ pub const FS_WATCH_LATENCY: Duration = Duration::from_millis(100);
 pub const FS_WATCH_LATENCY: Duration = Duration::from_millis(100);
 
 const GIT_STATUS_UPDATE_BATCH_SIZE: usize = 100;
+/// The minimum number of changed paths in a directory for which the metadata of its entries is
+/// read in a single batch, rather than statting each path.
+const MIN_PATHS_PER_METADATA_BATCH: usize = 4;
+/// How often to check whether the system was asleep.
+const SLEEP_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
 pub struct WorktreeId(usize);
@@ -102,8 +110,16 @@ pub struct LocalWorktree {
     snapshot: LocalSnapshot,
     scan_requests_tx: channel::Sender<ScanRequest>,
     path_prefixes_to_scan_tx: channel::Sender<Arc<Path>>,
+    pause_events_tx: channel::Sender<bool>,
+    reveal_requests_tx: channel::Sender<RevealRequest>,
+    events_paused: bool,
     is_scanning: (watch::Sender<bool>, watch::Receiver<bool>),
+    scan_progress: ScanProgress,
+    /// Tells the background scanner to stop, which it checks between the directories it scans
+    /// and the batches of events it processes.
+    scanner_cancel_flag: Arc<AtomicBool>,
     _background_scanner_tasks: Vec<Task<()>>,
+    _sleep_detection_task: Task<()>,
     share: Option<ShareState>,
     diagnostics: HashMap<
         Arc<Path>,
@@ -117,12 +133,64 @@ pub struct LocalWorktree {
     fs: Arc<dyn Fs>,
     fs_case_sensitive: bool,
     visible: bool,
+    pending_writes: Arc<Mutex<HashMap<WriteTarget, PendingWrite>>>,
+    /// The files whose contents were loaded or saved, for telling whether they changed on disk
+    /// since then.
+    loaded_files: Arc<Mutex<HashMap<Arc<Path>, LoadedFile>>>,
 
     next_entry_id: Arc<AtomicUsize>,
 }
 
+/// The file that a write goes to. Existing files are identified by their [`FileId`], so that
+/// writes through different paths to the same file are queued together.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum WriteTarget {
+    Inode(FileId),
+    Path(Arc<Path>),
+}
+
+/// The error returned when saving a buffer over a file that another program changed on disk
+/// since the buffer was loaded or last saved, before the buffer learned about the change.
+#[derive(Debug)]
+pub struct SaveConflict {
+    pub path: Arc<Path>,
+}
+
+impl fmt::Display for SaveConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} was changed on disk since it was last loaded or saved",
+            self.path
+        )
+    }
+}
+
+impl std::error::Error for SaveConflict {}
+
+/// A write that is in flight, along with the contents to write once it completes.
+struct PendingWrite {
+    /// The latest contents requested while the write was in flight, replacing any earlier
+    /// ones that weren't written yet. They're synced if any of the replaced writes asked to
+    /// be.
+    next: Option<(Rope, LineEnding, Durability)>,
+    /// Resolves once the latest requested contents have been written.
+    done: Shared<Task<Result<(), Arc<anyhow::Error>>>>,
+}
+
 struct ScanRequest {
     relative_paths: Vec<Arc<Path>>,
+    /// Whether to rescan everything beneath the paths from scratch, rather than only
+    /// reloading the paths themselves.
+    recursive: bool,
+    done: barrier::Sender,
+}
+
+/// Asks the background scanner to treat an ignored path as if it wasn't ignored, or to stop
+/// doing so.
+struct RevealRequest {
+    path: Arc<Path>,
+    revealed: bool,
     done: barrier::Sender,
 }
 
@@ -279,6 +347,33 @@ impl From<ProjectEntryId> for WorkDirectoryEntry {
     }
 }
 
+/// The result of [`Snapshot::resolve_dir_path`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirPathResolution {
+    Found(ProjectEntryId),
+    /// The directory may exist, but it or its parent hasn't been scanned yet.
+    Pending,
+    /// The directory doesn't exist, or the path is a file.
+    Missing,
+}
+
+/// Counts of a worktree's entries, for diagnosing slow scans.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorktreeStats {
+    pub file_count: usize,
+    pub dir_count: usize,
+    /// Directories whose contents haven't been scanned yet, e.g. because they're ignored.
+    pub unloaded_dir_count: usize,
+    pub ignored_count: usize,
+    pub symlink_count: usize,
+    pub external_count: usize,
+    /// How long the most recent scan of the whole worktree took, either the initial one or a
+    /// rescan. Only known for local worktrees.
+    pub last_scan_duration: Option<Duration>,
+    /// The directories containing the most entries, recursively, largest first.
+    pub largest_dirs: Vec<(Arc<Path>, usize)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct LocalSnapshot {
     snapshot: Snapshot,
@@ -291,6 +386,15 @@ pub struct LocalSnapshot {
     file_scan_exclusions: Vec<PathMatcher>,
     private_files: Vec<PathMatcher>,
     share_private_files: bool,
+    transient_files: Vec<PathMatcher>,
+    synced_files: Vec<PathMatcher>,
+    /// The ignored paths that the user chose to include anyway, which are scanned and
+    /// searched like the rest of the worktree.
+    revealed_ignored_paths: HashSet<Arc<Path>>,
+    /// The directories whose entries have a [`ScanError`].
+    dirs_with_scan_errors: HashSet<Arc<Path>>,
+    scanning_profile: Option<ScanningProfile>,
+    last_scan_duration: Option<Duration>,
 }
 
 struct BackgroundScannerState {
@@ -302,7 +406,7 @@ struct BackgroundScannerState {
     /// as part of the current update. These entry ids may be re-used
     /// if the same inode is discovered at a new path, or if the given
     /// path is re-created after being deleted.
-    removed_entry_ids: HashMap<u64, ProjectEntryId>,
+    removed_entry_ids: HashMap<FileId, ProjectEntryId>,
     changed_paths: Vec<Arc<Path>>,
     prev_snapshot: Snapshot,
 }
@@ -343,9 +447,19 @@ enum ScanState {
         changes: UpdatedEntriesSet,
         barrier: Option<barrier::Sender>,
         scanning: bool,
+        progress: ScanProgress,
     },
 }
 
+/// How far the background scanner got, as of its last update.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// The number of files and directories in the worktree's snapshot.
+    pub entries_discovered: usize,
+    /// The number of directories waiting to be scanned. Zero once the scan completes.
+    pub directories_queued: usize,
+}
+
 struct ShareState {
     project_id: u64,
     snapshots_tx:
@@ -358,6 +472,83 @@ struct ShareState {
 pub enum Event {
     UpdatedEntries(UpdatedEntriesSet),
     UpdatedGitRepositories(UpdatedGitRepositoriesSet),
+    /// The file of a buffer that's open in the worktree changed on disk. The buffer is reloaded
+    /// or marked as conflicted through [`Event::UpdatedEntries`] as well.
+    LoadedFileChanged(Arc<Path>, LoadedFileChange),
+    /// More of the operations passed to [`LocalWorktree::apply_file_operations`] were applied.
+    FileOperationsProgress(FileOperationsProgress),
+}
+
+/// A change to the files of a local worktree, which can be applied along with others by
+/// [`LocalWorktree::apply_file_operations`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileOperation {
+    /// Moves or renames the file or directory at `old_path` to `new_path`.
+    Move {
+        old_path: Arc<Path>,
+        new_path: Arc<Path>,
+    },
+    /// Copies the file or directory at `old_path` to `new_path`.
+    Copy {
+        old_path: Arc<Path>,
+        new_path: Arc<Path>,
+    },
+    /// Moves the file or directory at `path` to the system's trash, or deletes it permanently.
+    Delete { path: Arc<Path>, trash: bool },
+}
+
+impl FileOperation {
+    /// The operation that undoes this one after it was applied. Deletions can't be undone,
+    /// since the trash's contents can't be restored through the file system.
+    pub fn reverse(&self) -> Option<FileOperation> {
+        match self {
+            FileOperation::Move { old_path, new_path } => Some(FileOperation::Move {
+                old_path: new_path.clone(),
+                new_path: old_path.clone(),
+            }),
+            FileOperation::Copy { new_path, .. } => Some(FileOperation::Delete {
+                path: new_path.clone(),
+                trash: true,
+            }),
+            FileOperation::Delete { .. } => None,
+        }
+    }
+
+    fn paths(&self) -> Vec<Arc<Path>> {
+        match self {
+            FileOperation::Move { old_path, new_path }
+            | FileOperation::Copy { old_path, new_path } => {
+                vec![old_path.clone(), new_path.clone()]
+            }
+            FileOperation::Delete { path, .. } => vec![path.clone()],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileOperationsProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// The result of [`LocalWorktree::apply_file_operations`]. The operations that didn't fail
+/// were applied, regardless of the failures of the others.
+#[derive(Debug, Default)]
+pub struct FileOperationsOutcome {
+    pub failed: Vec<(FileOperation, anyhow::Error)>,
+    /// The operations that undo the applied ones, in the order they need to be applied.
+    pub undo_operations: Vec<FileOperation>,
+    /// How many of the applied operations can't be undone.
+    pub irreversible_count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadedFileChange {
+    /// The file's contents were changed by another program.
+    ChangedOnDisk,
+    Deleted,
+    /// The file was moved to the given path within the worktree.
+    Moved(Arc<Path>),
 }
 
 impl EventEmitter<Event> for Worktree {}
@@ -390,25 +581,38 @@ impl Worktree {
         cx.new_model(move |cx: &mut ModelContext<Worktree>| {
             cx.observe_global::<SettingsStore>(move |this, cx| {
                 if let Self::Local(this) = this {
-                    let new_file_scan_exclusions = path_matchers(
-                        WorktreeSettings::get_global(cx)
-                            .file_scan_exclusions
-                            .as_deref(),
-                        "file_scan_exclusions",
+                    let settings = WorktreeSettings::get_global(cx);
+                    let local_settings = WorktreeSettings::get(
+                        Some(settings::SettingsLocation {
+                            worktree_id: cx.handle().entity_id().as_u64() as usize,
+                            path: Path::new(""),
+                        }),
+                        cx,
                     );
+                    let new_scanning_profile =
+                        settings.scanning_profile(&this.snapshot.abs_path).cloned();
+                    let new_file_scan_exclusions =
+                        file_scan_exclusions(local_settings, new_scanning_profile.as_ref());
                     let new_private_files = path_matchers(
-                        WorktreeSettings::get(Some(settings::SettingsLocation {
-                            worktree_id: cx.handle().entity_id().as_u64() as usize,
-                            path: Path::new("")
-                        }), cx).private_files.as_deref(),
+                        local_settings.private_files.as_deref(),
                         "private_files",
                     );
+                    let new_transient_files = settings.transient_file_matchers();
+                    // Synced files only affect saves, so they don't require a rescan.
+                    this.snapshot.synced_files = path_matchers(
+                        local_settings.synced_files.as_deref(),
+                        "synced_files",
+                    );
 
                     if new_file_scan_exclusions != this.snapshot.file_scan_exclusions
                         || new_private_files != this.snapshot.private_files
+                        || new_transient_files != this.snapshot.transient_files
+                        || new_scanning_profile != this.snapshot.scanning_profile
                     {
                         this.snapshot.file_scan_exclusions = new_file_scan_exclusions;
                         this.snapshot.private_files = new_private_files;
+                        this.snapshot.transient_files = new_transient_files;
+                        this.snapshot.scanning_profile = new_scanning_profile;
 
                         log::info!(
                             "Re-scanning directories, new scan exclude files: {:?}, new dotenv files: {:?}",
@@ -434,21 +638,34 @@ impl Worktree {
                 .file_name()
                 .map_or(String::new(), |f| f.to_string_lossy().to_string());
 
+            let settings = WorktreeSettings::get_global(cx);
+            let local_settings = WorktreeSettings::get(Some(SettingsLocation {
+                worktree_id: cx.handle().entity_id().as_u64() as usize,
+                path: Path::new(""),
+            }), cx);
+            let scanning_profile = settings.scanning_profile(&abs_path).cloned();
+            if let Some(profile) = &scanning_profile {
+                log::info!("using scanning profile {:?} for {abs_path:?}", profile.name);
+            }
             let mut snapshot = LocalSnapshot {
-                file_scan_exclusions: path_matchers(
-                    WorktreeSettings::get_global(cx)
-                        .file_scan_exclusions
-                        .as_deref(),
-                    "file_scan_exclusions",
+                file_scan_exclusions: file_scan_exclusions(
+                    local_settings,
+                    scanning_profile.as_ref(),
                 ),
                 private_files: path_matchers(
-                    WorktreeSettings::get(Some(SettingsLocation {
-                        worktree_id: cx.handle().entity_id().as_u64() as usize,
-                        path: Path::new(""),
-                    }), cx).private_files.as_deref(),
+                    local_settings.private_files.as_deref(),
                     "private_files",
                 ),
                 share_private_files: false,
+                transient_files: settings.transient_file_matchers(),
+                synced_files: path_matchers(
+                    local_settings.synced_files.as_deref(),
+                    "synced_files",
+                ),
+                revealed_ignored_paths: Default::default(),
+                dirs_with_scan_errors: Default::default(),
+                scanning_profile,
+                last_scan_duration: None,
                 ignores_by_parent_abs_path: Default::default(),
                 git_repositories: Default::default(),
                 snapshot: Snapshot {
@@ -478,29 +695,43 @@ impl Worktree {
 
             let (scan_requests_tx, scan_requests_rx) = channel::unbounded();
             let (path_prefixes_to_scan_tx, path_prefixes_to_scan_rx) = channel::unbounded();
+            let (pause_events_tx, pause_events_rx) = channel::unbounded();
+            let (reveal_requests_tx, reveal_requests_rx) = channel::unbounded();
             let task_snapshot = snapshot.clone();
+            let scanner_cancel_flag = Arc::new(AtomicBool::new(false));
             Worktree::Local(LocalWorktree {
                 next_entry_id: Arc::clone(&next_entry_id),
                 snapshot,
                 is_scanning: watch::channel_with(true),
+                scan_progress: ScanProgress::default(),
                 share: None,
                 scan_requests_tx,
                 path_prefixes_to_scan_tx,
+                pause_events_tx,
+                reveal_requests_tx,
+                events_paused: false,
                 _background_scanner_tasks: start_background_scan_tasks(
                     &abs_path,
                     task_snapshot,
                     scan_requests_rx,
                     path_prefixes_to_scan_rx,
+                    pause_events_rx,
+                    reveal_requests_rx,
                     Arc::clone(&next_entry_id),
                     Arc::clone(&fs),
+                    Arc::clone(&scanner_cancel_flag),
                     cx,
                 ),
+                scanner_cancel_flag,
+                _sleep_detection_task: detect_sleep(cx),
                 diagnostics: Default::default(),
                 diagnostic_summaries: Default::default(),
                 client,
                 fs,
                 fs_case_sensitive,
                 visible,
+                pending_writes: Default::default(),
+                loaded_files: Default::default(),
             })
         })
     }
@@ -682,31 +913,185 @@ impl Worktree {
         }
     }
 
+    /// Counts the worktree's entries on a background thread, and finds the `largest_dir_count`
+    /// directories containing the most entries.
+    pub fn stats(&self, largest_dir_count: usize, cx: &AppContext) -> Task<WorktreeStats> {
+        let snapshot = self.snapshot();
+        let last_scan_duration = match self {
+            Worktree::Local(worktree) => worktree.snapshot.last_scan_duration,
+            Worktree::Remote(_) => None,
+        };
+        cx.background_executor().spawn(async move {
+            WorktreeStats {
+                last_scan_duration,
+                ..snapshot.stats(largest_dir_count)
+            }
+        })
+    }
+
     pub fn root_file(&self, cx: &mut ModelContext<Self>) -> Option<Arc<File>> {
         let entry = self.root_entry()?;
         Some(File::for_entry(entry.clone(), cx.handle()))
     }
 }
 
+impl Snapshot {
+    fn stats(&self, largest_dir_count: usize) -> WorktreeStats {
+        let mut stats = WorktreeStats::default();
+        let mut dir_sizes = HashMap::<&Path, usize>::default();
+        for entry in self.entries(true) {
+            match entry.kind {
+                EntryKind::File(_) => stats.file_count += 1,
+                EntryKind::Dir => stats.dir_count += 1,
+                EntryKind::UnloadedDir | EntryKind::PendingDir => {
+                    stats.dir_count += 1;
+                    stats.unloaded_dir_count += 1;
+                }
+            }
+            if entry.is_ignored {
+                stats.ignored_count += 1;
+            }
+            if entry.is_symlink {
+                stats.symlink_count += 1;
+            }
+            if entry.is_external {
+                stats.external_count += 1;
+            }
+            for ancestor in entry.path.ancestors().skip(1) {
+                if ancestor != Path::new("") {
+                    *dir_sizes.entry(ancestor).or_default() += 1;
+                }
+            }
+        }
+
+        let mut largest_dirs = dir_sizes.into_iter().collect::<Vec<_>>();
+        largest_dirs.sort_by(|(path_a, size_a), (path_b, size_b)| {
+            size_b.cmp(size_a).then_with(|| path_a.cmp(path_b))
+        });
+        stats.largest_dirs = largest_dirs
+            .into_iter()
+            .take(largest_dir_count)
+            .map(|(path, size)| (Arc::from(path), size))
+            .collect();
+        stats
+    }
+}
+
+async fn apply_file_operation(
+    fs: &dyn Fs,
+    root_abs_path: &Path,
+    case_sensitive: bool,
+    operation: &FileOperation,
+) -> Result<()> {
+    let abs_path = |path: &Path| {
+        if path.file_name().is_none()
+            || path
+                .components()
+                .any(|component| !matches!(component, std::path::Component::Normal(_)))
+        {
+            Err(anyhow!("invalid path {path:?}"))
+        } else {
+            Ok(root_abs_path.join(path))
+        }
+    };
+
+    match operation {
+        FileOperation::Move { old_path, new_path } => {
+            let abs_old_path = abs_path(old_path)?;
+            let abs_new_path = abs_path(new_path)?;
+            // A case-only rename on a case-insensitive file system would fail because the
+            // new path already exists.
+            let overwrite = !case_sensitive
+                && abs_old_path != abs_new_path
+                && abs_old_path.to_str().map(str::to_lowercase)
+                    == abs_new_path.to_str().map(str::to_lowercase);
+            fs.rename(
+                &abs_old_path,
+                &abs_new_path,
+                fs::RenameOptions {
+                    overwrite,
+                    ..Default::default()
+                },
+            )
+            .await
+        }
+        FileOperation::Copy { old_path, new_path } => {
+            copy_recursive(
+                fs,
+                &abs_path(old_path)?,
+                &abs_path(new_path)?,
+                Default::default(),
+            )
+            .await
+        }
+        FileOperation::Delete { path, trash } => {
+            let abs_path = abs_path(path)?;
+            let options = RemoveOptions {
+                recursive: true,
+                ignore_if_not_exists: false,
+            };
+            match (fs.is_dir(&abs_path).await, *trash) {
+                (true, true) => fs.trash_dir(&abs_path, options).await,
+                (true, false) => fs.remove_dir(&abs_path, options).await,
+                (false, true) => fs.trash_file(&abs_path, options).await,
+                (false, false) => fs.remove_file(&abs_path, options).await,
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn start_background_scan_tasks(
     abs_path: &Path,
     snapshot: LocalSnapshot,
     scan_requests_rx: channel::Receiver<ScanRequest>,
     path_prefixes_to_scan_rx: channel::Receiver<Arc<Path>>,
+    pause_events_rx: channel::Receiver<bool>,
+    reveal_requests_rx: channel::Receiver<RevealRequest>,
     next_entry_id: Arc<AtomicUsize>,
     fs: Arc<dyn Fs>,
+    cancel_flag: Arc<AtomicBool>,
     cx: &mut ModelContext<'_, Worktree>,
 ) -> Vec<Task<()>> {
     let (scan_states_tx, mut scan_states_rx) = mpsc::unbounded();
+    let poll_interval = snapshot
+        .scanning_profile()
+        .and_then(|profile| profile.poll_interval());
+    // The latest snapshot, which the polling file watcher consults to skip the directories
+    // whose events would be discarded.
+    let polled_snapshot = Arc::new(Mutex::new(snapshot.snapshot.clone()));
+    let file_scan_exclusions = snapshot.file_scan_exclusions.clone();
+    let latency = snapshot
+        .scanning_profile()
+        .and_then(|profile| profile.latency())
+        .unwrap_or_else(|| WorktreeSettings::get_global(cx).watcher_latency());
     let background_scanner = cx.background_executor().spawn({
-        let abs_path = if cfg!(target_os = "windows") {
-            abs_path.canonicalize().unwrap_or_else(|_| abs_path.to_path_buf())
-        } else {
-            abs_path.to_path_buf()
-        };
+        let mut abs_path = abs_path.to_path_buf();
         let background = cx.background_executor().clone();
+        let polled_snapshot = polled_snapshot.clone();
         async move {
-            let events = fs.watch(&abs_path, FS_WATCH_LATENCY).await;
+            if cfg!(target_os = "windows") {
+                if let Ok(canonical_path) = fs.canonicalize(&abs_path).await {
+                    abs_path = canonical_path;
+                }
+            }
+            let poll_options = PollOptions {
+                interval: poll_interval,
+                skip_dir: Some(Arc::new({
+                    let root_abs_path = abs_path.clone();
+                    move |dir_abs_path: &Path| {
+                        is_dir_skipped_by_polling(
+                            &root_abs_path,
+                            dir_abs_path,
+                            &polled_snapshot.lock(),
+                            &file_scan_exclusions,
+                        )
+                    }
+                })),
+            };
+            let events = fs
+                .watch_with_poll_options(&abs_path, latency, poll_options)
+                .await;
             let case_sensitive = fs.is_case_sensitive().await.unwrap_or_else(|e| {
                 log::error!(
                     "Failed to determine whether filesystem is case sensitive (falling back to true) due to error: {e:#}"
@@ -723,6 +1108,10 @@ fn start_background_scan_tasks(
                 background,
                 scan_requests_rx,
                 path_prefixes_to_scan_rx,
+                pause_events_rx,
+                reveal_requests_rx,
+                latency,
+                cancel_flag,
             )
             .run(events)
             .await;
@@ -741,8 +1130,11 @@ fn start_background_scan_tasks(
                         changes,
                         barrier,
                         scanning,
+                        progress,
                     } => {
                         *this.is_scanning.0.borrow_mut() = scanning;
+                        this.scan_progress = progress;
+                        *polled_snapshot.lock() = snapshot.snapshot.clone();
                         this.set_snapshot(snapshot, changes, cx);
                         drop(barrier);
                     }
@@ -755,6 +1147,83 @@ fn start_background_scan_tasks(
     vec![background_scanner, scan_state_updater]
 }
 
+/// Rescans the worktree after the system wakes from sleep, since file system events may have
+/// been dropped in the meantime.
+fn detect_sleep(cx: &mut ModelContext<Worktree>) -> Task<()> {
+    cx.spawn(|this, mut cx| async move {
+        loop {
+            let instant = Instant::now();
+            let system_time = SystemTime::now();
+            cx.background_executor().timer(SLEEP_CHECK_INTERVAL).await;
+
+            // Unlike the system clock, the monotonic clock doesn't advance while asleep.
+            let asleep_for = system_time
+                .elapsed()
+                .unwrap_or_default()
+                .saturating_sub(instant.elapsed());
+            if asleep_for > SLEEP_CHECK_INTERVAL {
+                log::info!("rescanning worktree after the system was asleep for {asleep_for:?}");
+                let rescanned = this.update(&mut cx, |this, _| {
+                    if let Some(this) = this.as_local() {
+                        this.rescan(Path::new("").into());
+                    }
+                });
+                if rescanned.is_err() {
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// Whether the polling file watcher can leave out the contents of a directory, because the
+/// scanner discards the events in it: it's unloaded, or it's excluded and not a git directory.
+fn is_dir_skipped_by_polling(
+    root_abs_path: &Path,
+    dir_abs_path: &Path,
+    snapshot: &Snapshot,
+    file_scan_exclusions: &[PathMatcher],
+) -> bool {
+    let Ok(path) = dir_abs_path.strip_prefix(root_abs_path) else {
+        return false;
+    };
+    if path
+        .components()
+        .any(|component| component.as_os_str() == *DOT_GIT)
+    {
+        return false;
+    }
+    let is_excluded = path.ancestors().any(|ancestor| {
+        file_scan_exclusions
+            .iter()
+            .any(|matcher| matcher.is_match(ancestor))
+    });
+    is_excluded
+        || snapshot
+            .entry_for_path(path)
+            .map_or(false, |entry| entry.kind.is_unloaded())
+}
+
+/// Returns the `file_scan_exclusions` and `extra_file_scan_exclusions` of a worktree's settings,
+/// extended with those of its scanning profile.
+fn file_scan_exclusions(
+    settings: &WorktreeSettings,
+    scanning_profile: Option<&ScanningProfile>,
+) -> Vec<PathMatcher> {
+    let mut patterns = settings.file_scan_exclusions.clone().unwrap_or_default();
+    patterns.extend(
+        settings
+            .extra_file_scan_exclusions
+            .iter()
+            .flatten()
+            .cloned(),
+    );
+    if let Some(profile) = scanning_profile {
+        patterns.extend(profile.file_scan_exclusions.iter().cloned());
+    }
+    path_matchers(Some(&patterns), "file_scan_exclusions")
+}
+
 fn path_matchers(values: Option<&[String]>, context: &'static str) -> Vec<PathMatcher> {
     values
         .unwrap_or(&[])
@@ -773,6 +1242,12 @@ fn path_matchers(values: Option<&[String]>, context: &'static str) -> Vec<PathMa
         .collect()
 }
 
+impl Drop for LocalWorktree {
+    fn drop(&mut self) {
+        self.scanner_cancel_flag.store(true, SeqCst);
+    }
+}
+
 impl LocalWorktree {
     pub fn contains_abs_path(&self, path: &Path) -> bool {
         path.starts_with(&self.abs_path)
@@ -794,15 +1269,35 @@ impl LocalWorktree {
                 .background_executor()
                 .spawn(async move { text::Buffer::new(0, buffer_id, contents) })
                 .await;
-            cx.insert_model(reservation, |_| {
+            let buffer = cx.insert_model(reservation, |_| {
                 Buffer::build(
                     text_buffer,
                     diff_base,
                     Some(Arc::new(file)),
                     Capability::ReadWrite,
                 )
-            })
+            })?;
+            this.update(&mut cx, |_, cx| {
+                LocalWorktree::forget_loaded_file_on_release(&buffer, cx)
+            })?;
+            Ok(buffer)
+        })
+    }
+
+    /// Stops tracking the file of `buffer` once the buffer is released, so that changes on disk
+    /// are only reported for the files that are open.
+    fn forget_loaded_file_on_release(buffer: &Model<Buffer>, cx: &mut ModelContext<Worktree>) {
+        cx.observe_release(buffer, |this, buffer, cx| {
+            let Some(file) = File::from_dyn(buffer.file()) else {
+                return;
+            };
+            if file.worktree.entity_id() == cx.entity_id() {
+                if let Some(this) = this.as_local() {
+                    this.loaded_files.lock().remove(&file.path);
+                }
+            }
         })
+        .detach();
     }
 
     pub fn new_buffer(
@@ -941,20 +1436,36 @@ impl LocalWorktree {
     }
 
     fn restart_background_scanners(&mut self, cx: &mut ModelContext<Worktree>) {
+        if self.is_closed() {
+            return;
+        }
         let (scan_requests_tx, scan_requests_rx) = channel::unbounded();
         let (path_prefixes_to_scan_tx, path_prefixes_to_scan_rx) = channel::unbounded();
+        let (pause_events_tx, pause_events_rx) = channel::unbounded();
+        let (reveal_requests_tx, reveal_requests_rx) = channel::unbounded();
+        if self.events_paused {
+            pause_events_tx.try_send(true).ok();
+        }
         self.scan_requests_tx = scan_requests_tx;
         self.path_prefixes_to_scan_tx = path_prefixes_to_scan_tx;
+        self.pause_events_tx = pause_events_tx;
+        self.reveal_requests_tx = reveal_requests_tx;
+        self.scanner_cancel_flag.store(true, SeqCst);
+        self.scanner_cancel_flag = Arc::new(AtomicBool::new(false));
         self._background_scanner_tasks = start_background_scan_tasks(
             &self.snapshot.abs_path,
             self.snapshot(),
             scan_requests_rx,
             path_prefixes_to_scan_rx,
+            pause_events_rx,
+            reveal_requests_rx,
             Arc::clone(&self.next_entry_id),
             Arc::clone(&self.fs),
+            Arc::clone(&self.scanner_cancel_flag),
             cx,
         );
         self.is_scanning = watch::channel_with(true);
+        self.scan_progress = ScanProgress::default();
     }
 
     fn set_snapshot(
@@ -966,7 +1477,9 @@ impl LocalWorktree {
         let repo_changes = self.changed_repos(&self.snapshot, &new_snapshot);
 
         new_snapshot.share_private_files = self.snapshot.share_private_files;
+        new_snapshot.synced_files = self.snapshot.synced_files.clone();
         self.snapshot = new_snapshot;
+        let loaded_file_changes = self.loaded_file_changes(&entry_changes);
 
         if let Some(share) = self.share.as_mut() {
             share
@@ -985,6 +1498,55 @@ impl LocalWorktree {
         if !repo_changes.is_empty() {
             cx.emit(Event::UpdatedGitRepositories(repo_changes));
         }
+        for (path, change) in loaded_file_changes {
+            cx.emit(Event::LoadedFileChanged(path, change));
+        }
+    }
+
+    /// Finds the loaded files affected by the entry changes. Files are followed to their new
+    /// paths when they're moved, and updates with the modification time of the last load or
+    /// save are ignored, so that saving a file doesn't report it as changed.
+    fn loaded_file_changes(
+        &self,
+        entry_changes: &[(Arc<Path>, ProjectEntryId, PathChange)],
+    ) -> Vec<(Arc<Path>, LoadedFileChange)> {
+        let mut loaded_files = self.loaded_files.lock();
+        let mut changes = Vec::new();
+        for (path, entry_id, change) in entry_changes {
+            match change {
+                PathChange::Removed => {
+                    let Some(loaded_file) = loaded_files.remove(path) else {
+                        continue;
+                    };
+                    let new_path = entry_changes.iter().find_map(|(new_path, new_id, change)| {
+                        (new_id == entry_id
+                            && new_path != path
+                            && matches!(change, PathChange::Added | PathChange::AddedOrUpdated))
+                        .then(|| new_path.clone())
+                    });
+                    if let Some(new_path) = new_path {
+                        loaded_files.insert(new_path.clone(), loaded_file);
+                        changes.push((path.clone(), LoadedFileChange::Moved(new_path)));
+                    } else {
+                        changes.push((path.clone(), LoadedFileChange::Deleted));
+                    }
+                }
+                PathChange::Updated | PathChange::AddedOrUpdated => {
+                    let Some(loaded_file) = loaded_files.get_mut(path) else {
+                        continue;
+                    };
+                    let Some(entry) = self.snapshot.entry_for_path(path) else {
+                        continue;
+                    };
+                    if entry.is_file() && entry.mtime != loaded_file.mtime {
+                        loaded_file.mtime = entry.mtime;
+                        changes.push((path.clone(), LoadedFileChange::ChangedOnDisk));
+                    }
+                }
+                PathChange::Added | PathChange::Loaded => {}
+            }
+        }
+        changes
     }
 
     fn changed_repos(
@@ -1096,6 +1658,22 @@ impl LocalWorktree {
         }
     }
 
+    pub fn scan_progress(&self) -> ScanProgress {
+        self.scan_progress
+    }
+
+    /// Stops scanning the worktree and watching it for changes, without waiting for the
+    /// worktree to be dropped. Its snapshot no longer changes afterwards.
+    pub fn close(&mut self) {
+        self.scanner_cancel_flag.store(true, SeqCst);
+        self._background_scanner_tasks.clear();
+        *self.is_scanning.0.borrow_mut() = false;
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.scanner_cancel_flag.load(SeqCst)
+    }
+
     pub fn snapshot(&self) -> LocalSnapshot {
         self.snapshot.clone()
     }
@@ -1118,10 +1696,23 @@ impl LocalWorktree {
         let abs_path = self.absolutize(&path);
         let fs = self.fs.clone();
         let entry = self.refresh_entry(path.clone(), None, cx);
+        let read = cx.background_executor().spawn({
+            let fs = fs.clone();
+            let path = path.clone();
+            let loaded_files = self.loaded_files.clone();
+            async move {
+                let abs_path = abs_path?;
+                let text = fs.load(&abs_path).await?;
+                let metadata = fs.metadata(&abs_path).await?;
+                loaded_files
+                    .lock()
+                    .insert(path, LoadedFile::new(&text, metadata));
+                anyhow::Ok((abs_path, text))
+            }
+        });
 
         cx.spawn(|this, mut cx| async move {
-            let abs_path = abs_path?;
-            let text = fs.load(&abs_path).await?;
+            let (abs_path, text) = read.await?;
             let mut index_task = None;
             let snapshot = this.update(&mut cx, |this, _| this.as_local().unwrap().snapshot())?;
             if let Some(repo) = snapshot.repository_for_path(&path) {
@@ -1208,6 +1799,7 @@ impl LocalWorktree {
         buffer_handle: Model<Buffer>,
         path: Arc<Path>,
         mut has_changed_file: bool,
+        durability: Durability,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<()>> {
         let buffer = buffer_handle.read(cx);
@@ -1220,14 +1812,28 @@ impl LocalWorktree {
             has_changed_file = true;
         }
 
+        let old_file = File::from_dyn(buffer.file())
+            .filter(|_| has_changed_file)
+            .map(|file| (file.worktree.clone(), file.path.clone()));
         let text = buffer.as_rope().clone();
         let version = buffer.version();
-        let save = self.write_file(path.as_ref(), text, buffer.line_ending(), cx);
+        let line_ending = buffer.line_ending();
+        let check_conflict = (!has_changed_file)
+            .then(|| self.check_save_conflict(&path, buffer, cx.background_executor()));
         let fs = Arc::clone(&self.fs);
         let abs_path = self.absolutize(&path);
         let is_private = self.snapshot.is_path_private(&path);
 
         cx.spawn(move |this, mut cx| async move {
+            if let Some(check_conflict) = check_conflict {
+                check_conflict.await?;
+            }
+            let save = this.update(&mut cx, |this, cx| {
+                let this = this.as_local().unwrap();
+                // The written contents are hashed once the write completes.
+                this.loaded_files.lock().entry(path.clone()).or_default();
+                this.write_file(path.clone(), text, line_ending, durability, cx)
+            })?;
             let entry = save.await?;
             let abs_path = abs_path?;
             let this = this.upgrade().context("worktree dropped")?;
@@ -1251,6 +1857,20 @@ impl LocalWorktree {
             };
 
             if has_changed_file {
+                // The file the buffer had before is no longer open in it.
+                if let Some((old_worktree, old_path)) = old_file {
+                    if old_worktree != this || old_path != path {
+                        old_worktree.update(&mut cx, |old_worktree, _| {
+                            if let Some(old_worktree) = old_worktree.as_local() {
+                                old_worktree.loaded_files.lock().remove(&old_path);
+                            }
+                        })?;
+                    }
+                }
+                this.update(&mut cx, |_, cx| {
+                    LocalWorktree::forget_loaded_file_on_release(&buffer_handle, cx)
+                })?;
+
                 let new_file = Arc::new(File {
                     entry_id,
                     worktree: this,
@@ -1277,6 +1897,15 @@ impl LocalWorktree {
                 })?;
             }
 
+            // Writes that were replaced by later ones complete along with them, so a save of
+            // older contents can finish after the save of newer ones.
+            let is_latest_save = buffer_handle.read_with(&cx, |buffer, _| {
+                version.observed_all(buffer.saved_version())
+            })?;
+            if !is_latest_save {
+                return Ok(());
+            }
+
             if let Some(project_id) = project_id {
                 rpc.send(proto::BufferSaved {
                     project_id,
@@ -1294,6 +1923,56 @@ impl LocalWorktree {
         })
     }
 
+    /// Fails with a [`SaveConflict`] if another program changed the file at `path` since the
+    /// buffer last loaded or saved it, unless the buffer was told about the change, in which
+    /// case it was reloaded or marked as conflicted. Rewriting the same contents isn't a
+    /// conflict. The file is only read and hashed when its modification time and size don't
+    /// tell whether it changed.
+    fn check_save_conflict(
+        &self,
+        path: &Arc<Path>,
+        buffer: &Buffer,
+        executor: &BackgroundExecutor,
+    ) -> Task<Result<()>> {
+        // Our own writes change the file while they're in flight.
+        let is_writing = self
+            .pending_writes
+            .lock()
+            .contains_key(&self.write_target(path));
+        let expected = self
+            .loaded_files
+            .lock()
+            .get(path)
+            .and_then(|loaded_file| Some((loaded_file.content_hash?, loaded_file.len)))
+            .filter(|_| !is_writing);
+        let known_mtimes = [
+            buffer.saved_mtime(),
+            buffer.file().and_then(|file| file.mtime()),
+        ];
+        let abs_path = self.absolutize(path);
+        let fs = self.fs.clone();
+        let path = path.clone();
+        executor.spawn(async move {
+            let Some((expected_hash, expected_len)) = expected else {
+                return Ok(());
+            };
+            let abs_path = abs_path?;
+            let Some(metadata) = fs.metadata(&abs_path).await? else {
+                return Ok(());
+            };
+            if known_mtimes.contains(&Some(metadata.mtime)) {
+                return Ok(());
+            }
+            if expected_len.map_or(true, |len| len == metadata.len) {
+                let text = fs.load(&abs_path).await?;
+                if content_hash([text.as_str()]) == expected_hash {
+                    return Ok(());
+                }
+            }
+            Err(SaveConflict { path }.into())
+        })
+    }
+
     /// Find the lowest path in the worktree's datastructures that is an ancestor
     fn lowest_ancestor(&self, path: &Path) -> PathBuf {
         let mut lowest_ancestor = None;
@@ -1314,18 +1993,33 @@ impl LocalWorktree {
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<Option<Entry>>> {
         let path = path.into();
-        let lowest_ancestor = self.lowest_ancestor(&path);
         let abs_path = self.absolutize(&path);
         let fs = self.fs.clone();
         let write = cx.background_executor().spawn(async move {
             if is_dir {
                 fs.create_dir(&abs_path?).await
             } else {
-                fs.save(&abs_path?, &Default::default(), Default::default())
-                    .await
+                fs.save(
+                    &abs_path?,
+                    &Default::default(),
+                    Default::default(),
+                    Default::default(),
+                )
+                .await
             }
         });
+        self.refresh_created_entry(path, write, cx)
+    }
 
+    /// Refreshes the entry at `path`, along with the ancestors that `write` creates, once
+    /// `write` completes.
+    fn refresh_created_entry(
+        &self,
+        path: Arc<Path>,
+        write: Task<Result<()>>,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<Option<Entry>>> {
+        let lowest_ancestor = self.lowest_ancestor(&path);
         cx.spawn(|this, mut cx| async move {
             write.await?;
             let (result, refreshes) = this.update(&mut cx, |this, cx| {
@@ -1356,22 +2050,188 @@ impl LocalWorktree {
         })
     }
 
-    pub(crate) fn write_file(
+    /// Creates an empty file named `name` in the directory at `parent_dir`, failing if an entry
+    /// with that name exists already. The file's entry is in the snapshot by the time the
+    /// returned task resolves, rather than once the file system reports the new file.
+    pub fn create_file(
+        &self,
+        parent_dir: &Path,
+        name: &str,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<Arc<File>>> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Task::ready(Err(anyhow!("invalid file name {name:?}")));
+        }
+        if !self
+            .entry_for_path(parent_dir)
+            .map_or(false, |entry| entry.is_dir())
+        {
+            return Task::ready(Err(anyhow!("{parent_dir:?} is not a directory")));
+        }
+        let path: Arc<Path> = parent_dir.join(name).into();
+        let abs_path = match self.absolutize(&path) {
+            Ok(abs_path) => abs_path,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let fs = self.fs.clone();
+        let create = cx.background_executor().spawn(async move {
+            fs.create_file(
+                &abs_path,
+                CreateOptions {
+                    overwrite: false,
+                    ignore_if_exists: false,
+                },
+            )
+            .await
+        });
+        let refresh = self.refresh_created_entry(path.clone(), create, cx);
+
+        let worktree = cx.handle();
+        cx.spawn(|_, _| async move {
+            let entry = refresh
+                .await?
+                .ok_or_else(|| anyhow!("{path:?} is excluded from the worktree"))?;
+            Ok(File::for_entry(entry, worktree))
+        })
+    }
+
+    /// Creates the directory at `path` along with any of its missing ancestors, returning the
+    /// inodes of the directories that were created, outermost first. Their entries are in the
+    /// snapshot by the time the returned task resolves, so callers don't race the background
+    /// scanner to find them.
+    pub fn create_dir(
+        &self,
+        path: impl Into<Arc<Path>>,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<Vec<FileId>>> {
+        let path = path.into();
+        let lowest_ancestor = self.lowest_ancestor(&path);
+        if let Some(entry) = self.entry_for_path(&lowest_ancestor) {
+            if !entry.is_dir() {
+                return Task::ready(Err(anyhow!("{lowest_ancestor:?} is not a directory")));
+            }
+        }
+        let abs_path = match self.absolutize(&path) {
+            Ok(abs_path) => abs_path,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let mut new_dirs = path
+            .strip_prefix(&lowest_ancestor)
+            .unwrap()
+            .ancestors()
+            .filter(|ancestor| *ancestor != Path::new(""))
+            .map(|ancestor| Arc::<Path>::from(lowest_ancestor.join(ancestor)))
+            .collect::<Vec<_>>();
+        new_dirs.reverse();
+
+        let fs = self.fs.clone();
+        let create = cx
+            .background_executor()
+            .spawn(async move { fs.create_dir(&abs_path).await });
+        cx.spawn(|this, mut cx| async move {
+            create.await?;
+            let mut inodes = Vec::with_capacity(new_dirs.len());
+            for dir in new_dirs {
+                let entry = this
+                    .update(&mut cx, |this, cx| {
+                        this.as_local_mut()
+                            .unwrap()
+                            .refresh_entry(dir.clone(), None, cx)
+                    })?
+                    .await?
+                    .ok_or_else(|| anyhow!("{dir:?} is excluded from the worktree"))?;
+                inodes.push(entry.inode);
+            }
+            Ok(inodes)
+        })
+    }
+
+    fn write_target(&self, path: &Arc<Path>) -> WriteTarget {
+        match self.entry_for_path(path) {
+            Some(entry) => WriteTarget::Inode(entry.inode),
+            None => WriteTarget::Path(path.clone()),
+        }
+    }
+
+    /// Writes `text` to the file at `path`. Writes to the same file never interleave: while a
+    /// write is in flight, later contents replace any earlier ones still waiting to be
+    /// written, and each caller's task resolves once the latest contents have been written
+    /// with the requested [`Durability`].
+    pub fn write_file(
         &self,
         path: impl Into<Arc<Path>>,
         text: Rope,
         line_ending: LineEnding,
+        durability: Durability,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<Option<Entry>>> {
         let path: Arc<Path> = path.into();
-        let abs_path = self.absolutize(&path);
-        let fs = self.fs.clone();
-        let write = cx
-            .background_executor()
-            .spawn(async move { fs.save(&abs_path?, &text, line_ending).await });
+        let abs_path = match self.absolutize(&path) {
+            Ok(abs_path) => abs_path,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let target = self.write_target(&path);
+
+        let mut pending_writes = self.pending_writes.lock();
+        let write = match pending_writes.entry(target.clone()) {
+            hash_map::Entry::Occupied(mut pending_write) => {
+                let pending_write = pending_write.get_mut();
+                let durability = match pending_write.next.take() {
+                    Some((_, _, Durability::Synced)) => Durability::Synced,
+                    _ => durability,
+                };
+                pending_write.next = Some((text, line_ending, durability));
+                pending_write.done.clone()
+            }
+            hash_map::Entry::Vacant(entry) => {
+                let fs = self.fs.clone();
+                let pending_writes = self.pending_writes.clone();
+                let loaded_files = self.loaded_files.clone();
+                let path = path.clone();
+                let done = cx
+                    .background_executor()
+                    .spawn(async move {
+                        let (mut text, mut line_ending, mut durability) =
+                            (text, line_ending, durability);
+                        loop {
+                            let result = fs.save(&abs_path, &text, line_ending, durability).await;
+                            if result.is_ok() && loaded_files.lock().contains_key(&path) {
+                                // Our own writes aren't reported as changes on disk.
+                                let metadata = fs.metadata(&abs_path).await.ok().flatten();
+                                let content_hash = content_hash(text.chunks());
+                                if let Some(loaded_file) = loaded_files.lock().get_mut(&path) {
+                                    loaded_file.content_hash = Some(content_hash);
+                                    loaded_file.mtime = metadata.as_ref().map(|m| m.mtime);
+                                    loaded_file.len = metadata.map(|metadata| metadata.len);
+                                }
+                            }
+                            let mut pending_writes = pending_writes.lock();
+                            let next = pending_writes
+                                .get_mut(&target)
+                                .and_then(|pending_write| pending_write.next.take());
+                            // Later contents are still written when these failed to be, since
+                            // they replace them.
+                            match next {
+                                Some(next) => (text, line_ending, durability) = next,
+                                None => {
+                                    pending_writes.remove(&target);
+                                    return result.map_err(Arc::new);
+                                }
+                            }
+                        }
+                    })
+                    .shared();
+                entry.insert(PendingWrite {
+                    next: None,
+                    done: done.clone(),
+                });
+                done
+            }
+        };
+        drop(pending_writes);
 
         cx.spawn(|this, mut cx| async move {
-            write.await?;
+            write.await.map_err(|error| anyhow!("{}", error))?;
             this.update(&mut cx, |this, cx| {
                 this.as_local_mut().unwrap().refresh_entry(path, None, cx)
             })?
@@ -1518,6 +2378,71 @@ impl LocalWorktree {
         })
     }
 
+    /// Applies the operations in order on the background executor, reporting the progress with
+    /// [`Event::FileOperationsProgress`]. An operation that fails doesn't stop the ones after
+    /// it. The snapshot has been updated with the changes by the time the returned task
+    /// resolves, and the operations in its outcome undo them, as far as they can be undone.
+    pub fn apply_file_operations(
+        &self,
+        operations: Vec<FileOperation>,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<FileOperationsOutcome> {
+        let total = operations.len();
+        let changed_paths = operations
+            .iter()
+            .flat_map(FileOperation::paths)
+            .unique()
+            .collect::<Vec<_>>();
+        let root_abs_path = self.abs_path().clone();
+        let fs = self.fs.clone();
+        let case_sensitive = self.fs_case_sensitive;
+        let (progress_tx, mut progress_rx) = mpsc::unbounded();
+        let apply = cx.background_executor().spawn(async move {
+            let mut outcome = FileOperationsOutcome::default();
+            for (ix, operation) in operations.into_iter().enumerate() {
+                let result =
+                    apply_file_operation(fs.as_ref(), &root_abs_path, case_sensitive, &operation)
+                        .await;
+                match result {
+                    Ok(()) => match operation.reverse() {
+                        Some(reverse) => outcome.undo_operations.push(reverse),
+                        None => outcome.irreversible_count += 1,
+                    },
+                    Err(error) => {
+                        log::error!("failed to apply {operation:?}: {error:#}");
+                        outcome.failed.push((operation, error));
+                    }
+                }
+                progress_tx.unbounded_send(ix + 1).ok();
+            }
+            outcome.undo_operations.reverse();
+            outcome
+        });
+
+        cx.spawn(|this, mut cx| async move {
+            while let Some(completed) = progress_rx.next().await {
+                this.update(&mut cx, |_, cx| {
+                    cx.emit(Event::FileOperationsProgress(FileOperationsProgress {
+                        completed,
+                        total,
+                    }))
+                })
+                .ok();
+            }
+            let outcome = apply.await;
+
+            // Refreshing the old and new paths together keeps the ids of the moved entries.
+            if let Ok(mut refresh) = this.update(&mut cx, |this, _| {
+                this.as_local()
+                    .unwrap()
+                    .refresh_entries_for_paths(changed_paths)
+            }) {
+                refresh.recv().await;
+            }
+            outcome
+        })
+    }
+
     pub fn expand_entry(
         &mut self,
         entry_id: ProjectEntryId,
@@ -1536,6 +2461,77 @@ impl LocalWorktree {
         self.scan_requests_tx
             .try_send(ScanRequest {
                 relative_paths: paths,
+                recursive: false,
+                done: tx,
+            })
+            .ok();
+        rx
+    }
+
+    /// Rescans the subtree at `path` from scratch, or the whole worktree if `path` is empty,
+    /// instead of relying on file system events to keep it up to date. This is useful when
+    /// events may have been dropped, e.g. while the system was asleep.
+    pub fn rescan(&self, path: Arc<Path>) -> barrier::Receiver {
+        let (tx, rx) = barrier::channel();
+        self.scan_requests_tx
+            .try_send(ScanRequest {
+                relative_paths: vec![path],
+                recursive: true,
+                done: tx,
+            })
+            .ok();
+        rx
+    }
+
+    /// Stops processing file system events until [`Self::resume_events`] is called, e.g. while
+    /// a build or a `git checkout` changes many files. The events received in the meantime are
+    /// processed in one batch on resume, unless there are too many of them to keep, in which
+    /// case the whole worktree is rescanned instead. Scans requested by the worktree's own
+    /// operations are still processed.
+    pub fn pause_events(&mut self) {
+        if !self.events_paused {
+            self.events_paused = true;
+            self.pause_events_tx.try_send(true).ok();
+        }
+    }
+
+    /// Processes the file system events received since [`Self::pause_events`] was called.
+    pub fn resume_events(&mut self) {
+        if self.events_paused {
+            self.events_paused = false;
+            self.pause_events_tx.try_send(false).ok();
+        }
+    }
+
+    pub fn are_events_paused(&self) -> bool {
+        self.events_paused
+    }
+
+    /// Includes the ignored file or directory at `path` as if it wasn't ignored, so that it's
+    /// scanned and kept up to date like the rest of the worktree, and included in searches.
+    /// The paths within a revealed directory are only ignored by the `.gitignore` files in it.
+    pub fn reveal_ignored_path(&mut self, path: Arc<Path>) -> barrier::Receiver {
+        self.set_path_revealed(path, true)
+    }
+
+    /// Ignores a path that was revealed with [`Self::reveal_ignored_path`] again.
+    pub fn unreveal_ignored_path(&mut self, path: Arc<Path>) -> barrier::Receiver {
+        self.set_path_revealed(path, false)
+    }
+
+    fn set_path_revealed(&mut self, path: Arc<Path>, revealed: bool) -> barrier::Receiver {
+        // The scanner's snapshot replaces this one once the request is processed, but a
+        // restarted scanner starts from this one.
+        if revealed {
+            self.snapshot.revealed_ignored_paths.insert(path.clone());
+        } else {
+            self.snapshot.revealed_ignored_paths.remove(&path);
+        }
+        let (tx, rx) = barrier::channel();
+        self.reveal_requests_tx
+            .try_send(RevealRequest {
+                path,
+                revealed,
                 done: tx,
             })
             .ok();
@@ -1546,6 +2542,23 @@ impl LocalWorktree {
         self.path_prefixes_to_scan_tx.try_send(path_prefix).ok();
     }
 
+    /// Loads the direct children of the ignored directories accepted by `should_load`, which
+    /// aren't scanned until they're expanded, so that their files can be searched. Their
+    /// subdirectories are left unloaded, so that ignored directories can be loaded one level at
+    /// a time, as they're needed.
+    pub fn load_ignored_directories(&self, mut should_load: impl FnMut(&Entry) -> bool) {
+        let paths = self
+            .entries(true)
+            .filter(|entry| {
+                entry.kind == EntryKind::UnloadedDir && entry.is_ignored && should_load(entry)
+            })
+            .map(|entry| entry.path.clone())
+            .collect::<Vec<_>>();
+        if !paths.is_empty() {
+            self.refresh_entries_for_paths(paths);
+        }
+    }
+
     fn refresh_entry(
         &self,
         path: Arc<Path>,
@@ -1985,11 +2998,22 @@ impl Snapshot {
         self.entries_by_path.summary().non_ignored_file_count
     }
 
+    /// The number of files that [`Snapshot::files`] traverses with the same options.
+    pub fn filtered_file_count(&self, include_ignored: bool, include_hidden: bool) -> usize {
+        TraversalProgress::from_summary(self.entries_by_path.summary()).count(
+            true,
+            false,
+            include_ignored,
+            include_hidden,
+        )
+    }
+
     fn traverse_from_offset(
         &self,
         include_files: bool,
         include_dirs: bool,
         include_ignored: bool,
+        include_hidden: bool,
         start_offset: usize,
     ) -> Traversal {
         let mut cursor = self.entries_by_path.cursor();
@@ -1999,6 +3023,7 @@ impl Snapshot {
                 include_files,
                 include_dirs,
                 include_ignored,
+                include_hidden,
             },
             Bias::Right,
             &(),
@@ -2008,6 +3033,7 @@ impl Snapshot {
             include_files,
             include_dirs,
             include_ignored,
+            include_hidden,
         }
     }
 
@@ -2025,6 +3051,7 @@ impl Snapshot {
             include_files,
             include_dirs,
             include_ignored,
+            include_hidden: true,
         };
         if traversal.end_offset() == traversal.start_offset() {
             traversal.next();
@@ -2032,16 +3059,22 @@ impl Snapshot {
         traversal
     }
 
-    pub fn files(&self, include_ignored: bool, start: usize) -> Traversal {
-        self.traverse_from_offset(true, false, include_ignored, start)
+    /// Traverses the files from the given offset, among the files that the options include.
+    pub fn files(&self, include_ignored: bool, include_hidden: bool, start: usize) -> Traversal {
+        self.traverse_from_offset(true, false, include_ignored, include_hidden, start)
     }
 
-    pub fn directories(&self, include_ignored: bool, start: usize) -> Traversal {
-        self.traverse_from_offset(false, true, include_ignored, start)
+    pub fn directories(
+        &self,
+        include_ignored: bool,
+        include_hidden: bool,
+        start: usize,
+    ) -> Traversal {
+        self.traverse_from_offset(false, true, include_ignored, include_hidden, start)
     }
 
     pub fn entries(&self, include_ignored: bool) -> Traversal {
-        self.traverse_from_offset(true, true, include_ignored, 0)
+        self.traverse_from_offset(true, true, include_ignored, true, 0)
     }
 
     pub fn repositories(&self) -> impl Iterator<Item = (&Arc<Path>, &RepositoryEntry)> {
@@ -2176,6 +3209,7 @@ impl Snapshot {
             include_files: true,
             include_dirs: true,
             include_ignored: true,
+            include_hidden: true,
         };
         ChildEntriesIter {
             traversal,
@@ -2183,6 +3217,32 @@ impl Snapshot {
         }
     }
 
+    /// Returns the entries within the directory at the given path, at any depth, in the same
+    /// order as [`Self::entries`]. Entries are indexed by path, so finding the first one
+    /// doesn't require walking the directories above it.
+    pub fn entries_under<'a>(
+        &'a self,
+        parent_path: &'a Path,
+        include_ignored: bool,
+    ) -> DescendantEntriesIter<'a> {
+        let mut cursor = self.entries_by_path.cursor();
+        cursor.seek(&TraversalTarget::Path(parent_path), Bias::Right, &());
+        let mut traversal = Traversal {
+            cursor,
+            include_files: true,
+            include_dirs: true,
+            include_ignored,
+            include_hidden: true,
+        };
+        if traversal.end_offset() == traversal.start_offset() {
+            traversal.next();
+        }
+        DescendantEntriesIter {
+            traversal,
+            parent_path,
+        }
+    }
+
     pub fn root_entry(&self) -> Option<&Entry> {
         self.entry_for_path("")
     }
@@ -2205,6 +3265,31 @@ impl Snapshot {
         self.scan_id
     }
 
+    /// Resolves the path of a directory, e.g. one persisted in a previous session, against
+    /// this snapshot. Paths are used rather than entry ids, since the latter aren't stable
+    /// across sessions.
+    pub fn resolve_dir_path(&self, path: &Path) -> DirPathResolution {
+        if let Some(entry) = self.entry_for_path(path) {
+            return if entry.is_dir() {
+                DirPathResolution::Found(entry.id)
+            } else {
+                DirPathResolution::Missing
+            };
+        }
+
+        let is_scanning = self.completed_scan_id < self.scan_id;
+        let ancestor_kind = path
+            .ancestors()
+            .skip(1)
+            .find_map(|ancestor| self.entry_for_path(ancestor))
+            .map(|ancestor| ancestor.kind);
+        match ancestor_kind {
+            Some(EntryKind::PendingDir | EntryKind::UnloadedDir) => DirPathResolution::Pending,
+            Some(EntryKind::Dir) if is_scanning => DirPathResolution::Pending,
+            _ => DirPathResolution::Missing,
+        }
+    }
+
     pub fn entry_for_path(&self, path: impl AsRef<Path>) -> Option<&Entry> {
         let path = path.as_ref();
         self.traverse_from_path(true, true, true, path)
@@ -2223,7 +3308,7 @@ impl Snapshot {
         self.entry_for_path(&entry.path)
     }
 
-    pub fn inode_for_path(&self, path: impl AsRef<Path>) -> Option<u64> {
+    pub fn inode_for_path(&self, path: impl AsRef<Path>) -> Option<FileId> {
         self.entry_for_path(path.as_ref()).map(|e| e.inode)
     }
 }
@@ -2379,7 +3464,7 @@ impl LocalSnapshot {
         entry
     }
 
-    fn ancestor_inodes_for_path(&self, path: &Path) -> TreeSet<u64> {
+    fn ancestor_inodes_for_path(&self, path: &Path) -> TreeSet<FileId> {
         let mut inodes = TreeSet::default();
         for ancestor in path.ancestors().skip(1) {
             if let Some(entry) = self.entry_for_path(ancestor) {
@@ -2389,6 +3474,30 @@ impl LocalSnapshot {
         inodes
     }
 
+    /// Whether the directory is the root of a git repository known to the worktree, whose
+    /// ancestors' ignore rules don't apply inside of it. This is answered from the snapshot
+    /// rather than the file system, since it's needed synchronously.
+    fn is_repository_root(&self, abs_path: &Path) -> bool {
+        let root_abs_path = self.abs_path();
+        match abs_path.strip_prefix(root_abs_path) {
+            Ok(path) => self
+                .repository_entries
+                .get(&RepositoryWorkDirectory(path.into()))
+                .map_or(false, |repository| repository.location_in_repo.is_none()),
+            // The repository that contains the worktree's root is found in one of its ancestors.
+            Err(_) => self
+                .repository_entries
+                .get(&RepositoryWorkDirectory::default())
+                .and_then(|repository| repository.location_in_repo.as_ref())
+                .map_or(false, |location_in_repo| {
+                    root_abs_path
+                        .ancestors()
+                        .nth(location_in_repo.components().count())
+                        == Some(abs_path)
+                }),
+        }
+    }
+
     fn ignore_stack_for_abs_path(&self, abs_path: &Path, is_dir: bool) -> Arc<IgnoreStack> {
         let mut new_ignores = Vec::new();
         for (index, ancestor) in abs_path.ancestors().enumerate() {
@@ -2399,7 +3508,8 @@ impl LocalSnapshot {
                     new_ignores.push((ancestor, None));
                 }
             }
-            if ancestor.join(&*DOT_GIT).is_dir() {
+            // Like a repository's root, a revealed directory isn't affected by the ignores above it.
+            if self.is_repository_root(ancestor) || self.is_abs_path_revealed(ancestor) {
                 break;
             }
         }
@@ -2446,8 +3556,8 @@ impl LocalSnapshot {
             "entries_by_path and entries_by_id are inconsistent"
         );
 
-        let mut files = self.files(true, 0);
-        let mut visible_files = self.files(false, 0);
+        let mut files = self.files(true, true, 0);
+        let mut visible_files = self.files(false, true, 0);
         for entry in self.entries_by_path.cursor::<()>() {
             if entry.is_file() {
                 assert_eq!(files.next().unwrap().inode, entry.inode);
@@ -2529,11 +3639,83 @@ impl LocalSnapshot {
                 .any(|exclude_matcher| exclude_matcher.is_match(&path))
         })
     }
+
+    /// How durably the file at `path` must be written when saving it.
+    pub fn save_durability(&self, path: &Path) -> Durability {
+        if self
+            .synced_files
+            .iter()
+            .any(|matcher| matcher.is_match(path))
+        {
+            Durability::Synced
+        } else {
+            Durability::Buffered
+        }
+    }
+
+    /// Whether the file at `path` is short-lived, like an editor's swap file, so that its
+    /// changes on disk aren't worth processing.
+    pub fn is_path_transient(&self, path: &Path) -> bool {
+        self.transient_files
+            .iter()
+            .any(|transient_matcher| transient_matcher.is_match(path))
+    }
+
+    /// Whether the user chose to include the ignored file or directory at `path` anyway.
+    pub fn is_path_revealed(&self, path: &Path) -> bool {
+        self.revealed_ignored_paths.contains(path)
+    }
+
+    pub fn revealed_ignored_paths(&self) -> impl Iterator<Item = &Arc<Path>> {
+        self.revealed_ignored_paths.iter()
+    }
+
+    /// The directories whose contents couldn't be scanned, in full or in part, by path.
+    pub fn scan_errors(&self) -> Vec<(&Arc<Path>, &ScanError)> {
+        let mut errors = self
+            .dirs_with_scan_errors
+            .iter()
+            .filter_map(|path| {
+                let error = self.entry_for_path(path)?.scan_error.as_ref()?;
+                Some((path, error))
+            })
+            .collect::<Vec<_>>();
+        errors.sort_by(|a, b| a.0.cmp(b.0));
+        errors
+    }
+
+    fn is_abs_path_revealed(&self, abs_path: &Path) -> bool {
+        abs_path
+            .strip_prefix(self.abs_path())
+            .map_or(false, |path| self.is_path_revealed(path))
+    }
+
+    pub fn scanning_profile(&self) -> Option<&ScanningProfile> {
+        self.scanning_profile.as_ref()
+    }
+
+    /// Whether the directory at `path` is too deep to be scanned before it's expanded.
+    fn exceeds_scan_depth(&self, path: &Path) -> bool {
+        self.scanning_profile
+            .as_ref()
+            .and_then(|profile| profile.max_scan_depth)
+            .map_or(false, |max_depth| path.components().count() > max_depth)
+    }
+
+    fn symlink_policy(&self) -> SymlinkPolicy {
+        self.scanning_profile
+            .as_ref()
+            .and_then(|profile| profile.symlinks)
+            .unwrap_or_default()
+    }
 }
 
 impl BackgroundScannerState {
     fn should_scan_directory(&self, entry: &Entry) -> bool {
-        (!entry.is_external && !entry.is_ignored)
+        let follows_external = self.snapshot.symlink_policy() == SymlinkPolicy::Follow;
+        ((!entry.is_external || follows_external)
+            && !entry.is_ignored
+            && !self.snapshot.exceeds_scan_depth(&entry.path))
             || entry.path.file_name() == Some(*DOT_GIT)
             || self.scanned_dirs.contains(&entry.id) // If we've ever scanned it, keep scanning
             || self
@@ -2581,6 +3763,17 @@ impl BackgroundScannerState {
     }
 
     fn reuse_entry_id(&mut self, entry: &mut Entry) {
+        // A file that another file was renamed over is still the file at its path, rather than
+        // a deleted file, even though its inode changed. It keeps its id, so that its buffers
+        // stay bound to it, instead of following the file that replaced it from its old path.
+        if let Some(existing_entry) = self.snapshot.entry_for_path(&entry.path) {
+            if existing_entry.is_file() && entry.is_file() && existing_entry.inode != entry.inode {
+                entry.id = existing_entry.id;
+                self.removed_entry_ids.remove(&entry.inode);
+                return;
+            }
+        }
+
         if let Some(removed_entry_id) = self.removed_entry_ids.remove(&entry.inode) {
             entry.id = removed_entry_id;
         } else if let Some(existing_entry) = self.snapshot.entry_for_path(&entry.path) {
@@ -2606,6 +3799,7 @@ impl BackgroundScannerState {
         parent_path: &Arc<Path>,
         entries: impl IntoIterator<Item = Entry>,
         ignore: Option<Arc<Gitignore>>,
+        scan_error: Option<ScanError>,
     ) {
         let mut parent_entry = if let Some(parent_entry) = self
             .snapshot
@@ -2626,6 +3820,14 @@ impl BackgroundScannerState {
             EntryKind::Dir => {}
             _ => return,
         }
+        if scan_error.is_some() {
+            self.snapshot
+                .dirs_with_scan_errors
+                .insert(parent_path.clone());
+        } else {
+            self.snapshot.dirs_with_scan_errors.remove(parent_path);
+        }
+        parent_entry.scan_error = scan_error;
 
         if let Some(ignore) = ignore {
             let abs_parent_path = self.snapshot.abs_path.join(&parent_path).into();
@@ -2969,8 +4171,17 @@ impl language::LocalFile for File {
         let worktree = self.worktree.read(cx).as_local().unwrap();
         let abs_path = worktree.absolutize(&self.path);
         let fs = worktree.fs.clone();
-        cx.background_executor()
-            .spawn(async move { fs.load(&abs_path?).await })
+        let loaded_files = worktree.loaded_files.clone();
+        let path = self.path.clone();
+        cx.background_executor().spawn(async move {
+            let abs_path = abs_path?;
+            let text = fs.load(&abs_path).await?;
+            let metadata = fs.metadata(&abs_path).await?;
+            loaded_files
+                .lock()
+                .insert(path, LoadedFile::new(&text, metadata));
+            Ok(text)
+        })
     }
 
     fn buffer_reloaded(
@@ -2997,6 +4208,39 @@ impl language::LocalFile for File {
     }
 }
 
+#[derive(Default)]
+struct LoadedFile {
+    /// The hash of the file's contents when it was last loaded or saved, or `None` while the
+    /// first save of a new file is in flight.
+    content_hash: Option<u64>,
+    /// The modification time of the file when it was last loaded or saved.
+    mtime: Option<SystemTime>,
+    /// The size of the file on disk when it was last loaded or saved.
+    len: Option<u64>,
+}
+
+impl LoadedFile {
+    fn new(text: &str, metadata: Option<fs::Metadata>) -> Self {
+        Self {
+            content_hash: Some(content_hash([text])),
+            mtime: metadata.as_ref().map(|metadata| metadata.mtime),
+            len: metadata.map(|metadata| metadata.len),
+        }
+    }
+}
+
+/// Hashes text regardless of its line endings, so that a file's contents on disk can be
+/// compared with a buffer's.
+fn content_hash<'a>(chunks: impl IntoIterator<Item = &'a str>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for chunk in chunks {
+        for part in chunk.split('\r') {
+            hasher.write(part.as_bytes());
+        }
+    }
+    hasher.finish()
+}
+
 impl File {
     pub fn for_entry(entry: Entry, worktree: Model<Worktree>) -> Arc<Self> {
         Arc::new(Self {
@@ -3058,9 +4302,16 @@ pub struct Entry {
     pub id: ProjectEntryId,
     pub kind: EntryKind,
     pub path: Arc<Path>,
-    pub inode: u64,
+    pub inode: FileId,
     pub mtime: Option<SystemTime>,
+    /// The size of the file in bytes, when the entry is a file.
+    pub size: u64,
+    /// The entry's unix permission bits, on platforms that have them.
+    pub mode: Option<u32>,
     pub is_symlink: bool,
+    /// Whether this entry is a symlink to one of the directories containing it. Its contents
+    /// aren't scanned, since they would contain the entry again, and so on forever.
+    pub is_symlink_cycle: bool,
 
     /// Whether this entry is ignored by Git.
     ///
@@ -3079,6 +4330,39 @@ pub struct Entry {
     pub git_status: Option<GitFileStatus>,
     /// Whether this entry is considered to be a `.env` file.
     pub is_private: bool,
+    /// Whether this entry, or a directory containing it within the worktree, is hidden: its
+    /// name starts with a dot, or the file system flags it as hidden. Unlike ignored entries,
+    /// hidden entries are scanned and searched, unless the traversal excludes them.
+    pub is_hidden: bool,
+    /// Why the contents of this directory couldn't be scanned, in full or in part, the last
+    /// time it was scanned. The rest of the worktree is scanned regardless.
+    pub scan_error: Option<ScanError>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanError {
+    pub kind: ScanErrorKind,
+    pub message: Arc<str>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanErrorKind {
+    PermissionDenied,
+    /// Any other error, which may not happen again the next time the directory is scanned.
+    Io,
+}
+
+impl ScanError {
+    fn new(error: &anyhow::Error) -> Self {
+        let kind = match error.downcast_ref::<io::Error>().map(io::Error::kind) {
+            Some(io::ErrorKind::PermissionDenied) => ScanErrorKind::PermissionDenied,
+            _ => ScanErrorKind::Io,
+        };
+        Self {
+            kind,
+            message: format!("{error:#}").into(),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -3120,6 +4404,9 @@ impl Entry {
         next_entry_id: &AtomicUsize,
         root_char_bag: CharBag,
     ) -> Self {
+        // The root is never hidden, since it was opened explicitly.
+        let is_hidden =
+            !path.as_os_str().is_empty() && (metadata.is_hidden || is_path_hidden(&path));
         Self {
             id: ProjectEntryId::new(next_entry_id),
             kind: if metadata.is_dir {
@@ -3130,11 +4417,16 @@ impl Entry {
             path,
             inode: metadata.inode,
             mtime: Some(metadata.mtime),
+            size: if metadata.is_dir { 0 } else { metadata.len },
+            mode: metadata.mode,
             is_symlink: metadata.is_symlink,
+            is_symlink_cycle: false,
             is_ignored: false,
             is_external: false,
             is_private: false,
+            is_hidden,
             git_status: None,
+            scan_error: None,
         }
     }
 
@@ -3150,6 +4442,11 @@ impl Entry {
         self.kind.is_file()
     }
 
+    /// Whether the entry is a file that anyone is allowed to execute.
+    pub fn is_executable(&self) -> bool {
+        self.is_file() && self.mode.is_some_and(|mode| mode & 0o111 != 0)
+    }
+
     pub fn git_status(&self) -> Option<GitFileStatus> {
         self.git_status
     }
@@ -3181,14 +4478,22 @@ impl sum_tree::Item for Entry {
         } else {
             1
         };
+        let non_hidden_count = if self.is_hidden { 0 } else { 1 };
+        let non_ignored_non_hidden_count = non_ignored_count * non_hidden_count;
         let file_count;
         let non_ignored_file_count;
+        let non_hidden_file_count;
+        let non_ignored_non_hidden_file_count;
         if self.is_file() {
             file_count = 1;
             non_ignored_file_count = non_ignored_count;
+            non_hidden_file_count = non_hidden_count;
+            non_ignored_non_hidden_file_count = non_ignored_non_hidden_count;
         } else {
             file_count = 0;
             non_ignored_file_count = 0;
+            non_hidden_file_count = 0;
+            non_ignored_non_hidden_file_count = 0;
         }
 
         let mut statuses = GitStatuses::default();
@@ -3205,8 +4510,12 @@ impl sum_tree::Item for Entry {
             max_path: self.path.clone(),
             count: 1,
             non_ignored_count,
+            non_hidden_count,
+            non_ignored_non_hidden_count,
             file_count,
             non_ignored_file_count,
+            non_hidden_file_count,
+            non_ignored_non_hidden_file_count,
             statuses,
         }
     }
@@ -3225,8 +4534,12 @@ pub struct EntrySummary {
     max_path: Arc<Path>,
     count: usize,
     non_ignored_count: usize,
+    non_hidden_count: usize,
+    non_ignored_non_hidden_count: usize,
     file_count: usize,
     non_ignored_file_count: usize,
+    non_hidden_file_count: usize,
+    non_ignored_non_hidden_file_count: usize,
     statuses: GitStatuses,
 }
 
@@ -3236,8 +4549,12 @@ impl Default for EntrySummary {
             max_path: Arc::from(Path::new("")),
             count: 0,
             non_ignored_count: 0,
+            non_hidden_count: 0,
+            non_ignored_non_hidden_count: 0,
             file_count: 0,
             non_ignored_file_count: 0,
+            non_hidden_file_count: 0,
+            non_ignored_non_hidden_file_count: 0,
             statuses: Default::default(),
         }
     }
@@ -3250,8 +4567,12 @@ impl sum_tree::Summary for EntrySummary {
         self.max_path = rhs.max_path.clone();
         self.count += rhs.count;
         self.non_ignored_count += rhs.non_ignored_count;
+        self.non_hidden_count += rhs.non_hidden_count;
+        self.non_ignored_non_hidden_count += rhs.non_ignored_non_hidden_count;
         self.file_count += rhs.file_count;
         self.non_ignored_file_count += rhs.non_ignored_file_count;
+        self.non_hidden_file_count += rhs.non_hidden_file_count;
+        self.non_ignored_non_hidden_file_count += rhs.non_ignored_non_hidden_file_count;
         self.statuses += rhs.statuses;
     }
 }
@@ -3322,8 +4643,46 @@ struct BackgroundScanner {
     executor: BackgroundExecutor,
     scan_requests_rx: channel::Receiver<ScanRequest>,
     path_prefixes_to_scan_rx: channel::Receiver<Arc<Path>>,
+    pause_events_rx: channel::Receiver<bool>,
+    reveal_requests_rx: channel::Receiver<RevealRequest>,
+    /// The events received while events are paused, or `None` when they aren't.
+    paused_events: Option<PausedEvents>,
     next_entry_id: Arc<AtomicUsize>,
     phase: BackgroundScannerPhase,
+    /// The canonical paths of the `.gitignore` files in the root's ancestors, whose directories
+    /// are watched separately from the root, mapped to the paths of those directories.
+    ancestor_gitignores: HashMap<PathBuf, Arc<Path>>,
+    /// The number of directories in the queue of the current scan, for reporting progress.
+    directories_queued: AtomicUsize,
+    /// How long file system events are collected before they're processed together, which is
+    /// also how often the progress of a scan is reported.
+    latency: Duration,
+    cancel_flag: Arc<AtomicBool>,
+}
+
+/// The most latency windows that the events arriving in a burst are collected for before
+/// they're processed, so that a steady stream of events doesn't hold back the other requests.
+const MAX_COALESCED_LATENCY_WINDOWS: usize = 10;
+
+/// The most paths of file system events that are kept while events are paused, after which
+/// the whole worktree is rescanned on resume instead.
+const MAX_PAUSED_EVENT_PATHS: usize = 1024;
+
+enum PausedEvents {
+    Buffered(Vec<PathBuf>),
+    /// There were too many events to keep.
+    Dropped,
+}
+
+impl PausedEvents {
+    fn push(&mut self, paths: Vec<PathBuf>) {
+        if let PausedEvents::Buffered(buffered_paths) = self {
+            buffered_paths.extend(paths);
+            if buffered_paths.len() > MAX_PAUSED_EVENT_PATHS {
+                *self = PausedEvents::Dropped;
+            }
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -3344,6 +4703,10 @@ impl BackgroundScanner {
         executor: BackgroundExecutor,
         scan_requests_rx: channel::Receiver<ScanRequest>,
         path_prefixes_to_scan_rx: channel::Receiver<Arc<Path>>,
+        pause_events_rx: channel::Receiver<bool>,
+        reveal_requests_rx: channel::Receiver<RevealRequest>,
+        latency: Duration,
+        cancel_flag: Arc<AtomicBool>,
     ) -> Self {
         Self {
             fs,
@@ -3352,6 +4715,9 @@ impl BackgroundScanner {
             executor,
             scan_requests_rx,
             path_prefixes_to_scan_rx,
+            pause_events_rx,
+            reveal_requests_rx,
+            paused_events: None,
             next_entry_id,
             state: Mutex::new(BackgroundScannerState {
                 prev_snapshot: snapshot.snapshot.clone(),
@@ -3363,9 +4729,17 @@ impl BackgroundScanner {
                 changed_paths: Default::default(),
             }),
             phase: BackgroundScannerPhase::InitialScan,
+            ancestor_gitignores: HashMap::default(),
+            directories_queued: AtomicUsize::new(0),
+            latency,
+            cancel_flag,
         }
     }
 
+    fn is_cancelled(&self) -> bool {
+        self.cancel_flag.load(SeqCst)
+    }
+
     async fn run(&mut self, mut fs_events_rx: Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>>) {
         use futures::FutureExt as _;
 
@@ -3373,28 +4747,35 @@ impl BackgroundScanner {
         // the git repository in an ancestor directory. Find any gitignore files
         // in ancestor directories.
         let root_abs_path = self.state.lock().snapshot.abs_path.clone();
+        let mut ancestors = Vec::new();
+        let mut is_in_repository = false;
         for (index, ancestor) in root_abs_path.ancestors().enumerate() {
             if index != 0 {
-                if let Ok(ignore) =
-                    build_gitignore(&ancestor.join(&*GITIGNORE), self.fs.as_ref()).await
+                let gitignore_abs_path = ancestor.join(&*GITIGNORE);
+                let has_gitignore = if let Ok(ignore) =
+                    build_gitignore(&gitignore_abs_path, self.fs.as_ref()).await
                 {
                     self.state
                         .lock()
                         .snapshot
                         .ignores_by_parent_abs_path
                         .insert(ancestor.into(), (ignore.into(), false));
-                }
+                    true
+                } else {
+                    false
+                };
+                ancestors.push((ancestor, has_gitignore));
             }
 
             let ancestor_dot_git = ancestor.join(&*DOT_GIT);
-            if ancestor_dot_git.is_dir() {
+            if self.fs.is_dir(&ancestor_dot_git).await {
                 if index != 0 {
                     // We canonicalize, since the FS events use the canonicalized path.
                     if let Some(ancestor_dot_git) =
                         self.fs.canonicalize(&ancestor_dot_git).await.log_err()
                     {
                         let ancestor_git_events =
-                            self.fs.watch(&ancestor_dot_git, FS_WATCH_LATENCY).await;
+                            self.fs.watch(&ancestor_dot_git, self.latency).await;
                         fs_events_rx = select(fs_events_rx, ancestor_git_events).boxed();
 
                         // We associate the external git repo with our root folder and
@@ -3409,10 +4790,42 @@ impl BackgroundScanner {
                 }
 
                 // Reached root of git repository.
+                is_in_repository = true;
                 break;
             }
         }
 
+        // Changes to the ancestors' ignore rules aren't reported by the root's watcher, so watch
+        // the ancestor directories themselves, which also picks up the `.gitignore` files that
+        // are created later. Outside of a repository, only the ancestors that already have one
+        // are watched, rather than every directory up to the file system's root.
+        for (ancestor, has_gitignore) in ancestors {
+            if !is_in_repository && !has_gitignore {
+                continue;
+            }
+            // We canonicalize, since the FS events use the canonicalized path.
+            let Some(ancestor_abs_path) = self.fs.canonicalize(ancestor).await.log_err() else {
+                continue;
+            };
+            let gitignore_abs_path = ancestor_abs_path.join(&*GITIGNORE);
+            let gitignore_events = self
+                .fs
+                .watch_children(&ancestor_abs_path, self.latency)
+                .await
+                .filter_map({
+                    let gitignore_abs_path = gitignore_abs_path.clone();
+                    move |paths| {
+                        let changed = paths
+                            .iter()
+                            .any(|path| *path == gitignore_abs_path || *path == ancestor_abs_path);
+                        futures::future::ready(changed.then(|| vec![gitignore_abs_path.clone()]))
+                    }
+                });
+            fs_events_rx = select(fs_events_rx, gitignore_events).boxed();
+            self.ancestor_gitignores
+                .insert(gitignore_abs_path, ancestor.into());
+        }
+
         let (scan_job_tx, scan_job_rx) = channel::unbounded();
         {
             let mut state = self.state.lock();
@@ -3431,10 +4844,15 @@ impl BackgroundScanner {
 
         // Perform an initial scan of the directory.
         drop(scan_job_tx);
+        let scan_start = self.executor.now();
         self.scan_dirs(true, scan_job_rx).await;
+        if self.is_cancelled() {
+            return;
+        }
         {
             let mut state = self.state.lock();
             state.snapshot.completed_scan_id = state.snapshot.scan_id;
+            state.snapshot.last_scan_duration = Some(self.executor.now() - scan_start);
         }
 
         self.send_status_update(false, None);
@@ -3443,23 +4861,25 @@ impl BackgroundScanner {
         // For these events, update events cannot be as precise, because we didn't
         // have the previous state loaded yet.
         self.phase = BackgroundScannerPhase::EventsReceivedDuringInitialScan;
-        if let Poll::Ready(Some(mut paths)) = futures::poll!(fs_events_rx.next()) {
-            while let Poll::Ready(Some(more_paths)) = futures::poll!(fs_events_rx.next()) {
-                paths.extend(more_paths);
-            }
+        let paths = ready_events(&mut fs_events_rx).await;
+        if !paths.is_empty() {
             self.process_events(paths).await;
         }
 
-        // Continue processing events until the worktree is dropped.
+        // Continue processing events until the worktree is closed or dropped.
         self.phase = BackgroundScannerPhase::Events;
 
         loop {
+            if self.is_cancelled() {
+                break;
+            }
+
             select_biased! {
                 // Process any path refresh requests from the worktree. Prioritize
                 // these before handling changes reported by the filesystem.
                 request = self.scan_requests_rx.recv().fuse() => {
                     let Ok(request) = request else { break };
-                    if !self.process_scan_request(request, false).await {
+                    if !self.process_worktree_request(request).await {
                         return;
                     }
                 }
@@ -3483,17 +4903,193 @@ impl BackgroundScanner {
                     }
                 }
 
+                request = self.reveal_requests_rx.recv().fuse() => {
+                    let Ok(request) = request else { break };
+                    if !self.process_reveal_request(request).await {
+                        return;
+                    }
+                }
+
+                paused = self.pause_events_rx.recv().fuse() => {
+                    let Ok(paused) = paused else { break };
+                    if paused {
+                        self.paused_events.get_or_insert(PausedEvents::Buffered(Vec::new()));
+                    } else if !self.process_paused_events().await {
+                        return;
+                    }
+                }
+
                 paths = fs_events_rx.next().fuse() => {
                     let Some(mut paths) = paths else { break };
-                    while let Poll::Ready(Some(more_paths)) = futures::poll!(fs_events_rx.next()) {
-                        paths.extend(more_paths);
+                    paths.extend(ready_events(&mut fs_events_rx).await);
+                    if let Some(paused_events) = &mut self.paused_events {
+                        paused_events.push(paths);
+                        continue;
+                    }
+
+                    loop {
+                        self.process_events(paths).await;
+
+                        // Events that arrived while the previous ones were processed are part of
+                        // the same burst of changes, e.g. a branch switch. Rather than processing
+                        // each of their batches in turn, which rescans the same directories over
+                        // and over, wait for the burst to settle and process them together.
+                        paths = ready_events(&mut fs_events_rx).await;
+                        if paths.is_empty() {
+                            break;
+                        }
+                        for _ in 0..MAX_COALESCED_LATENCY_WINDOWS {
+                            // The worktree's requests, e.g. to refresh a created entry, aren't
+                            // held back until the burst settles.
+                            select_biased! {
+                                request = self.scan_requests_rx.recv().fuse() => {
+                                    let Ok(request) = request else { return };
+                                    if !self.process_worktree_request(request).await {
+                                        return;
+                                    }
+                                }
+                                _ = self.latency_timer().fuse() => {}
+                            }
+                            let more_paths = ready_events(&mut fs_events_rx).await;
+                            if more_paths.is_empty() {
+                                break;
+                            }
+                            paths.extend(more_paths);
+                        }
+                        if self.is_cancelled() {
+                            return;
+                        }
                     }
-                    self.process_events(paths.clone()).await;
                 }
             }
         }
     }
 
+    async fn process_worktree_request(&mut self, request: ScanRequest) -> bool {
+        if request.recursive {
+            self.process_rescan_request(request).await
+        } else {
+            self.process_scan_request(request, false).await
+        }
+    }
+
+    /// Processes the events that were received while events were paused in one batch, or
+    /// rescans the whole worktree when there were too many to keep.
+    async fn process_paused_events(&mut self) -> bool {
+        match self.paused_events.take() {
+            None => true,
+            Some(PausedEvents::Buffered(paths)) => {
+                if !paths.is_empty() {
+                    self.process_events(paths).await;
+                }
+                true
+            }
+            Some(PausedEvents::Dropped) => {
+                log::info!("rescanning worktree after too many events while paused");
+                let (done, _) = barrier::channel();
+                self.process_rescan_request(ScanRequest {
+                    relative_paths: vec![Arc::from(Path::new(""))],
+                    recursive: true,
+                    done,
+                })
+                .await
+            }
+        }
+    }
+
+    /// Updates the revealed ignored paths and rescans the path, so that its entries' ignore
+    /// statuses reflect the change.
+    async fn process_reveal_request(&mut self, request: RevealRequest) -> bool {
+        log::debug!(
+            "{} ignored path {:?}",
+            if request.revealed {
+                "revealing"
+            } else {
+                "unrevealing"
+            },
+            request.path
+        );
+
+        {
+            let revealed_paths = &mut self.state.lock().snapshot.revealed_ignored_paths;
+            if request.revealed {
+                revealed_paths.insert(request.path.clone());
+            } else {
+                revealed_paths.remove(&request.path);
+            }
+        }
+        if request.revealed {
+            self.forcibly_load_paths(&[request.path.clone()]).await;
+        }
+
+        self.process_rescan_request(ScanRequest {
+            relative_paths: vec![request.path],
+            recursive: true,
+            done: request.done,
+        })
+        .await
+    }
+
+    /// Rescans the requested subtrees as if every path within them had changed, along with the
+    /// git repositories they contain.
+    async fn process_rescan_request(&mut self, request: ScanRequest) -> bool {
+        log::debug!("rescanning subtrees {:?}", request.relative_paths);
+
+        // Repositories' git directories are relative to the root, unless they're outside of it.
+        let (root_path, git_dir_paths) = {
+            let snapshot = &self.state.lock().snapshot;
+            let git_dir_paths = snapshot
+                .git_repositories
+                .values()
+                .map(|repository| repository.git_dir_path.clone())
+                .collect::<Vec<_>>();
+            (snapshot.abs_path.clone(), git_dir_paths)
+        };
+        let root_canonical_path = match self.fs.canonicalize(&root_path).await {
+            Ok(path) => path,
+            Err(err) => {
+                log::error!("failed to canonicalize root path: {}", err);
+                return true;
+            }
+        };
+
+        let abs_paths = request
+            .relative_paths
+            .iter()
+            .map(|path| {
+                if path.file_name().is_some() {
+                    root_canonical_path.join(path)
+                } else {
+                    root_canonical_path.clone()
+                }
+            })
+            .collect();
+        let dot_git_paths = git_dir_paths
+            .iter()
+            .filter(|git_dir_path| {
+                request
+                    .relative_paths
+                    .iter()
+                    .any(|path| git_dir_path.starts_with(path))
+            })
+            .map(|git_dir_path| git_dir_path.to_path_buf())
+            .collect::<Vec<_>>();
+
+        let is_full_rescan = request
+            .relative_paths
+            .iter()
+            .any(|path| path.as_os_str().is_empty());
+        let scan_start = self.executor.now();
+        self.process_events(abs_paths).await;
+        if !dot_git_paths.is_empty() {
+            self.update_git_repositories(dot_git_paths).await;
+        }
+        if is_full_rescan {
+            self.state.lock().snapshot.last_scan_duration = Some(self.executor.now() - scan_start);
+        }
+        self.send_status_update(false, Some(request.done))
+    }
+
     async fn process_scan_request(&self, mut request: ScanRequest, scanning: bool) -> bool {
         log::debug!("rescanning paths {:?}", request.relative_paths);
 
@@ -3543,9 +5139,15 @@ impl BackgroundScanner {
 
         let mut relative_paths = Vec::with_capacity(abs_paths.len());
         let mut dot_git_paths = Vec::new();
+        let mut ancestor_gitignore_paths = Vec::new();
         abs_paths.sort_unstable();
         abs_paths.dedup_by(|a, b| a.starts_with(&b));
         abs_paths.retain(|abs_path| {
+            if let Some(parent_abs_path) = self.ancestor_gitignores.get(abs_path) {
+                ancestor_gitignore_paths.push((abs_path.clone(), parent_abs_path.clone()));
+                return false;
+            }
+
             let snapshot = &self.state.lock().snapshot;
             {
                 let mut is_git_related = false;
@@ -3596,11 +5198,37 @@ impl BackgroundScanner {
                     return false;
                 }
 
+                // Transient files that were already scanned are kept up to date, so that they
+                // don't linger in the snapshot once they're deleted.
+                if snapshot.is_path_transient(&relative_path)
+                    && snapshot.entry_for_path(&relative_path).is_none()
+                {
+                    log::trace!("ignoring FS event for transient path {relative_path:?}");
+                    return false;
+                }
+
                 relative_paths.push(relative_path);
                 true
             }
         });
 
+        if !ancestor_gitignore_paths.is_empty() {
+            log::debug!("received ancestor gitignore events {ancestor_gitignore_paths:?}");
+            self.state.lock().snapshot.scan_id += 1;
+        }
+        for (gitignore_abs_path, parent_abs_path) in ancestor_gitignore_paths {
+            // A deleted ancestor `.gitignore` stops ignoring anything, but is kept so that the
+            // root's ignore statuses are updated.
+            let ignore = build_gitignore(&gitignore_abs_path, self.fs.as_ref())
+                .await
+                .unwrap_or_else(|_| Gitignore::empty());
+            self.state
+                .lock()
+                .snapshot
+                .ignores_by_parent_abs_path
+                .insert(parent_abs_path, (Arc::new(ignore), true));
+        }
+
         let (scan_job_tx, scan_job_rx) = channel::unbounded();
         if !relative_paths.is_empty() || !dot_git_paths.is_empty() {
             log::debug!("received fs events {:?}", relative_paths);
@@ -3717,11 +5345,15 @@ impl BackgroundScanner {
                                 // Recursively load directories from the file system.
                                 job = scan_jobs_rx.recv().fuse() => {
                                     let Ok(job) = job else { break };
+                                    if self.is_cancelled() {
+                                        break;
+                                    }
                                     if let Err(err) = self.scan_dir(&job).await {
                                         if job.path.as_ref() != Path::new("") {
                                             log::error!("error scanning directory {:?}: {}", job.abs_path, err);
                                         }
                                     }
+                                    self.directories_queued.store(scan_jobs_rx.len(), SeqCst);
                                 }
                             }
                         }
@@ -3729,6 +5361,7 @@ impl BackgroundScanner {
                 }
             })
             .await;
+        self.directories_queued.store(0, SeqCst);
     }
 
     fn send_status_update(&self, scanning: bool, barrier: Option<barrier::Sender>) -> bool {
@@ -3741,6 +5374,14 @@ impl BackgroundScanner {
         let old_snapshot = mem::replace(&mut state.prev_snapshot, new_snapshot.snapshot.clone());
         let changes = self.build_change_set(&old_snapshot, &new_snapshot, &state.changed_paths);
         state.changed_paths.clear();
+        let progress = ScanProgress {
+            entries_discovered: new_snapshot.entries_by_path.summary().count,
+            directories_queued: if scanning {
+                self.directories_queued.load(SeqCst)
+            } else {
+                0
+            },
+        };
 
         self.status_updates_tx
             .unbounded_send(ScanState::Updated {
@@ -3748,6 +5389,7 @@ impl BackgroundScanner {
                 changes,
                 scanning,
                 barrier,
+                progress,
             })
             .is_ok()
     }
@@ -3755,6 +5397,8 @@ impl BackgroundScanner {
     async fn scan_dir(&self, job: &ScanJob) -> Result<()> {
         let root_abs_path;
         let root_char_bag;
+        let symlink_policy;
+        let is_parent_hidden;
         {
             let snapshot = &self.state.lock().snapshot;
             if snapshot.is_path_excluded(&job.path) {
@@ -3764,6 +5408,10 @@ impl BackgroundScanner {
             log::debug!("scanning directory {:?}", job.path);
             root_abs_path = snapshot.abs_path().clone();
             root_char_bag = snapshot.root_char_bag;
+            symlink_policy = snapshot.symlink_policy();
+            is_parent_hidden = snapshot
+                .entry_for_path(&job.path)
+                .map_or(false, |entry| entry.is_hidden);
         }
 
         let next_entry_id = self.next_entry_id.clone();
@@ -3773,26 +5421,25 @@ impl BackgroundScanner {
         let mut root_canonical_path = None;
         let mut new_entries: Vec<Entry> = Vec::new();
         let mut new_jobs: Vec<Option<ScanJob>> = Vec::new();
-        let mut child_paths = self
-            .fs
-            .read_dir(&job.abs_path)
-            .await?
-            .filter_map(|entry| async {
-                match entry {
-                    Ok(entry) => Some(entry),
-                    Err(error) => {
-                        log::error!("error processing entry {:?}", error);
-                        None
-                    }
-                }
-            })
-            .collect::<Vec<_>>()
-            .await;
+        let mut children = match self.fs.read_dir_metadata(&job.abs_path).await {
+            Ok(children) => children,
+            Err(error) => {
+                // The directory is kept without its contents, and scanned again when it changes.
+                self.state.lock().populate_dir(
+                    &job.path,
+                    Vec::new(),
+                    None,
+                    Some(ScanError::new(&error)),
+                );
+                return Err(error);
+            }
+        };
+        let mut child_error = None;
 
         // Ensure .git and gitignore files are processed first.
         let mut ixs_to_move_to_front = Vec::new();
-        for (ix, child_abs_path) in child_paths.iter().enumerate() {
-            let filename = child_abs_path.file_name().unwrap();
+        for (ix, child) in children.iter().enumerate() {
+            let filename = child.path.file_name().unwrap();
             if filename == *DOT_GIT {
                 ixs_to_move_to_front.insert(0, ix);
             } else if filename == *GITIGNORE {
@@ -3800,11 +5447,15 @@ impl BackgroundScanner {
             }
         }
         for (dest_ix, src_ix) in ixs_to_move_to_front.into_iter().enumerate() {
-            child_paths.swap(dest_ix, src_ix);
+            children.swap(dest_ix, src_ix);
         }
 
-        for child_abs_path in child_paths {
-            let child_abs_path: Arc<Path> = child_abs_path.into();
+        for child in children {
+            if self.is_cancelled() {
+                return Ok(());
+            }
+
+            let child_abs_path: Arc<Path> = child.path.into();
             let child_name = child_abs_path.file_name().unwrap();
             let child_path: Arc<Path> = job.path.join(child_name).into();
 
@@ -3847,11 +5498,14 @@ impl BackgroundScanner {
                 }
             }
 
-            let child_metadata = match self.fs.metadata(&child_abs_path).await {
+            let child_metadata = match child.metadata {
                 Ok(Some(metadata)) => metadata,
                 Ok(None) => continue,
                 Err(err) => {
                     log::error!("error processing {child_abs_path:?}: {err:?}");
+                    child_error.get_or_insert_with(|| {
+                        ScanError::new(&err.context(format!("failed to read {child_abs_path:?}")))
+                    });
                     continue;
                 }
             };
@@ -3862,7 +5516,11 @@ impl BackgroundScanner {
                 &next_entry_id,
                 root_char_bag,
             );
+            child_entry.is_hidden |= is_parent_hidden;
 
+            let is_leaf = child_metadata.is_symlink
+                && child_metadata.is_dir
+                && symlink_policy == SymlinkPolicy::Leaf;
             if job.is_external {
                 child_entry.is_external = true;
             } else if child_metadata.is_symlink {
@@ -3896,11 +5554,21 @@ impl BackgroundScanner {
                 }
             }
 
-            if child_entry.is_dir() {
-                child_entry.is_ignored = ignore_stack.is_abs_path_ignored(&child_abs_path, true);
+            let is_ignored =
+                ignore_stack.is_abs_path_ignored(&child_abs_path, child_entry.is_dir());
+            let is_revealed =
+                is_ignored && self.state.lock().snapshot.is_path_revealed(&child_path);
+            child_entry.is_ignored = is_ignored && !is_revealed;
 
-                // Avoid recursing until crash in the case of a recursive symlink
+            if child_entry.is_dir() {
+                // A directory that contains itself can only be reached through a symlink.
+                // It's loaded without its contents, instead of recursing until crashing.
                 if job.ancestor_inodes.contains(&child_entry.inode) {
+                    child_entry.kind = EntryKind::Dir;
+                    child_entry.is_symlink_cycle = true;
+                    new_jobs.push(None);
+                } else if is_leaf {
+                    child_entry.kind = EntryKind::Dir;
                     new_jobs.push(None);
                 } else {
                     let mut ancestor_inodes = job.ancestor_inodes.clone();
@@ -3912,6 +5580,8 @@ impl BackgroundScanner {
                         is_external: child_entry.is_external,
                         ignore_stack: if child_entry.is_ignored {
                             IgnoreStack::all()
+                        } else if is_revealed {
+                            IgnoreStack::none()
                         } else {
                             ignore_stack.clone()
                         },
@@ -3920,18 +5590,15 @@ impl BackgroundScanner {
                         containing_repository: containing_repository.clone(),
                     }));
                 }
-            } else {
-                child_entry.is_ignored = ignore_stack.is_abs_path_ignored(&child_abs_path, false);
-                if !child_entry.is_ignored {
-                    if let Some(repo) = &containing_repository {
-                        if let Ok(repo_path) = child_entry.path.strip_prefix(&repo.work_directory) {
-                            if let Some(mtime) = child_entry.mtime {
-                                let repo_path = RepoPath(repo_path.into());
-                                child_entry.git_status = combine_git_statuses(
-                                    repo.staged_statuses.get(&repo_path).copied(),
-                                    repo.repository.lock().unstaged_status(&repo_path, mtime),
-                                );
-                            }
+            } else if !child_entry.is_ignored {
+                if let Some(repo) = &containing_repository {
+                    if let Ok(repo_path) = child_entry.path.strip_prefix(&repo.work_directory) {
+                        if let Some(mtime) = child_entry.mtime {
+                            let repo_path = RepoPath(repo_path.into());
+                            child_entry.git_status = combine_git_statuses(
+                                repo.staged_statuses.get(&repo_path).copied(),
+                                repo.repository.lock().unstaged_status(&repo_path, mtime),
+                            );
                         }
                     }
                 }
@@ -3957,7 +5624,8 @@ impl BackgroundScanner {
         for entry in &mut new_entries {
             state.reuse_entry_id(entry);
             if entry.is_dir() {
-                if state.should_scan_directory(entry) {
+                // Directories that are loaded without their contents have no job to run.
+                if entry.kind == EntryKind::Dir || state.should_scan_directory(entry) {
                     job_ix += 1;
                 } else {
                     log::debug!("defer scanning directory {:?}", entry.path);
@@ -3967,7 +5635,7 @@ impl BackgroundScanner {
             }
         }
 
-        state.populate_dir(&job.path, new_entries, new_ignore);
+        state.populate_dir(&job.path, new_entries, new_ignore, child_error);
 
         for new_job in new_jobs.into_iter().flatten() {
             job.scan_queue
@@ -3978,6 +5646,44 @@ impl BackgroundScanner {
         Ok(())
     }
 
+    /// Reads the metadata of paths that share a parent directory with enough other paths in a
+    /// single batch per directory, instead of statting each path individually. Paths whose
+    /// metadata couldn't be batched are omitted.
+    async fn batch_metadata(
+        &self,
+        abs_paths: &[PathBuf],
+    ) -> HashMap<PathBuf, Option<fs::Metadata>> {
+        let mut paths_by_parent = HashMap::<&Path, Vec<&PathBuf>>::default();
+        for abs_path in abs_paths {
+            if let Some(parent) = abs_path.parent() {
+                paths_by_parent.entry(parent).or_default().push(abs_path);
+            }
+        }
+
+        let mut batched_metadata = HashMap::default();
+        for (parent, abs_paths) in paths_by_parent {
+            if abs_paths.len() < MIN_PATHS_PER_METADATA_BATCH {
+                continue;
+            }
+            let Ok(children) = self.fs.read_dir_metadata(parent).await else {
+                continue;
+            };
+            let children = children
+                .into_iter()
+                .filter_map(|child| Some((child.path.file_name()?.to_owned(), child.metadata)))
+                .collect::<HashMap<_, _>>();
+            for abs_path in abs_paths {
+                let metadata = match abs_path.file_name().and_then(|name| children.get(name)) {
+                    Some(Ok(metadata)) => *metadata,
+                    Some(Err(_)) => continue,
+                    None => None,
+                };
+                batched_metadata.insert(abs_path.clone(), metadata);
+            }
+        }
+        batched_metadata
+    }
+
     async fn reload_entries_for_paths(
         &self,
         root_abs_path: Arc<Path>,
@@ -3986,11 +5692,15 @@ impl BackgroundScanner {
         abs_paths: Vec<PathBuf>,
         scan_queue_tx: Option<Sender<ScanJob>>,
     ) {
+        let batched_metadata = &self.batch_metadata(&abs_paths).await;
         let metadata = futures::future::join_all(
             abs_paths
                 .iter()
                 .map(|abs_path| async move {
-                    let metadata = self.fs.metadata(abs_path).await?;
+                    let metadata = match batched_metadata.get(abs_path) {
+                        Some(metadata) => *metadata,
+                        None => self.fs.metadata(abs_path).await?,
+                    };
                     if let Some(metadata) = metadata {
                         let canonical_path = self.fs.canonicalize(abs_path).await?;
 
@@ -4054,6 +5764,10 @@ impl BackgroundScanner {
                     fs_entry.is_ignored = ignore_stack.is_abs_path_ignored(&abs_path, is_dir);
                     fs_entry.is_external = !canonical_path.starts_with(&root_canonical_path);
                     fs_entry.is_private = state.snapshot.is_path_private(path);
+                    fs_entry.is_hidden |= path
+                        .parent()
+                        .and_then(|parent| state.snapshot.entry_for_path(parent))
+                        .map_or(false, |parent| parent.is_hidden);
 
                     if !is_dir && !fs_entry.is_ignored && !fs_entry.is_external {
                         if let Some((repo_entry, repo)) = state.snapshot.repo_for_path(path) {
@@ -4066,7 +5780,20 @@ impl BackgroundScanner {
                         }
                     }
 
-                    if let (Some(scan_queue_tx), true) = (&scan_queue_tx, fs_entry.is_dir()) {
+                    if is_dir
+                        && state
+                            .snapshot
+                            .ancestor_inodes_for_path(path)
+                            .contains(&fs_entry.inode)
+                    {
+                        fs_entry.kind = EntryKind::Dir;
+                        fs_entry.is_symlink_cycle = true;
+                    } else if is_dir
+                        && metadata.is_symlink
+                        && state.snapshot.symlink_policy() == SymlinkPolicy::Leaf
+                    {
+                        fs_entry.kind = EntryKind::Dir;
+                    } else if let (Some(scan_queue_tx), true) = (&scan_queue_tx, is_dir) {
                         if state.should_scan_directory(&fs_entry) {
                             state.enqueue_scan_dir(abs_path, &fs_entry, scan_queue_tx);
                         } else {
@@ -4122,6 +5849,7 @@ impl BackgroundScanner {
         let mut snapshot = self.state.lock().snapshot.clone();
         let mut ignores_to_update = Vec::new();
         let mut ignores_to_delete = Vec::new();
+        let mut updated_ancestor_ignores = Vec::new();
         let abs_path = snapshot.abs_path.clone();
         for (parent_abs_path, (_, needs_update)) in &mut snapshot.ignores_by_parent_abs_path {
             if let Ok(parent_path) = parent_abs_path.strip_prefix(&abs_path) {
@@ -4136,9 +5864,27 @@ impl BackgroundScanner {
                 if snapshot.snapshot.entry_for_path(ignore_path).is_none() {
                     ignores_to_delete.push(parent_abs_path.clone());
                 }
+            } else if *needs_update {
+                *needs_update = false;
+                updated_ancestor_ignores.push(parent_abs_path.clone());
             }
         }
 
+        // The ignores of the root's ancestors apply to the whole worktree.
+        if !updated_ancestor_ignores.is_empty() {
+            let mut state = self.state.lock();
+            for parent_abs_path in updated_ancestor_ignores {
+                if let Some((_, needs_update)) = state
+                    .snapshot
+                    .ignores_by_parent_abs_path
+                    .get_mut(&parent_abs_path)
+                {
+                    *needs_update = false;
+                }
+            }
+            ignores_to_update.push(abs_path.clone());
+        }
+
         for parent_abs_path in ignores_to_delete {
             snapshot.ignores_by_parent_abs_path.remove(&parent_abs_path);
             self.state
@@ -4213,10 +5959,14 @@ impl BackgroundScanner {
         for mut entry in snapshot.child_entries(path).cloned() {
             let was_ignored = entry.is_ignored;
             let abs_path: Arc<Path> = snapshot.abs_path().join(&entry.path).into();
-            entry.is_ignored = ignore_stack.is_abs_path_ignored(&abs_path, entry.is_dir());
+            let is_revealed = snapshot.is_path_revealed(&entry.path);
+            entry.is_ignored =
+                !is_revealed && ignore_stack.is_abs_path_ignored(&abs_path, entry.is_dir());
             if entry.is_dir() {
                 let child_ignore_stack = if entry.is_ignored {
                     IgnoreStack::all()
+                } else if is_revealed {
+                    IgnoreStack::none()
                 } else {
                     ignore_stack.clone()
                 };
@@ -4628,14 +6378,39 @@ impl BackgroundScanner {
         if !running {
             return futures::future::pending().await;
         }
+        self.latency_timer().await;
+    }
 
+    async fn latency_timer(&self) {
         #[cfg(any(test, feature = "test-support"))]
         if self.fs.is_fake() {
             return self.executor.simulate_random_delay().await;
         }
 
-        smol::Timer::after(FS_WATCH_LATENCY).await;
+        smol::Timer::after(self.latency).await;
+    }
+}
+
+/// Takes the batches of paths that are ready in the stream of file system events, without
+/// waiting for more.
+async fn ready_events(
+    events: &mut Pin<Box<dyn Send + Stream<Item = Vec<PathBuf>>>>,
+) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    while let Poll::Ready(Some(more_paths)) = futures::poll!(events.next()) {
+        paths.extend(more_paths);
     }
+    paths
+}
+
+/// Whether any component of a worktree-relative path starts with a dot.
+fn is_path_hidden(path: &Path) -> bool {
+    path.components().any(|component| {
+        component
+            .as_os_str()
+            .to_str()
+            .map_or(false, |name| name.starts_with('.'))
+    })
 }
 
 fn char_bag_for_path(root_char_bag: CharBag, path: &Path) -> CharBag {
@@ -4653,7 +6428,7 @@ struct ScanJob {
     path: Arc<Path>,
     ignore_stack: Arc<IgnoreStack>,
     scan_queue: Sender<ScanJob>,
-    ancestor_inodes: TreeSet<u64>,
+    ancestor_inodes: TreeSet<FileId>,
     is_external: bool,
     containing_repository: Option<ScanJobContainingRepository>,
 }
@@ -4699,6 +6474,9 @@ impl WorktreeModelHandle for Model<Worktree> {
     //
     // This function mutates the worktree's directory and waits for those mutations to be picked up,
     // to ensure that all redundant FS events have already been processed.
+    //
+    // The fake file system delivers its events on the test executor, so they're all processed
+    // once it's parked, without polling for the mutations.
     #[cfg(any(test, feature = "test-support"))]
     fn flush_fs_events<'a>(
         &self,
@@ -4713,6 +6491,13 @@ impl WorktreeModelHandle for Model<Worktree> {
         });
 
         async move {
+            if fs.is_fake() {
+                cx.executor().run_until_parked();
+                cx.update(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+                    .await;
+                return;
+            }
+
             fs.create_file(&root_path.join(file_name), Default::default())
                 .await
                 .unwrap();
@@ -4743,6 +6528,10 @@ impl WorktreeModelHandle for Model<Worktree> {
         &self,
         cx: &'a mut gpui::TestAppContext,
     ) -> futures::future::LocalBoxFuture<'a, ()> {
+        if self.read_with(cx, |tree, _| tree.as_local().unwrap().fs.is_fake()) {
+            return self.flush_fs_events(cx);
+        }
+
         let file_name = "fs-event-sentinel";
 
         let tree = self.clone();
@@ -4804,20 +6593,42 @@ struct TraversalProgress<'a> {
     max_path: &'a Path,
     count: usize,
     non_ignored_count: usize,
+    non_hidden_count: usize,
+    non_ignored_non_hidden_count: usize,
     file_count: usize,
     non_ignored_file_count: usize,
+    non_hidden_file_count: usize,
+    non_ignored_non_hidden_file_count: usize,
 }
 
 impl<'a> TraversalProgress<'a> {
-    fn count(&self, include_files: bool, include_dirs: bool, include_ignored: bool) -> usize {
-        match (include_files, include_dirs, include_ignored) {
-            (true, true, true) => self.count,
-            (true, true, false) => self.non_ignored_count,
-            (true, false, true) => self.file_count,
-            (true, false, false) => self.non_ignored_file_count,
-            (false, true, true) => self.count - self.file_count,
-            (false, true, false) => self.non_ignored_count - self.non_ignored_file_count,
-            (false, false, _) => 0,
+    fn from_summary(summary: &'a EntrySummary) -> Self {
+        let mut progress = Self::default();
+        sum_tree::Dimension::add_summary(&mut progress, summary, &());
+        progress
+    }
+
+    fn count(
+        &self,
+        include_files: bool,
+        include_dirs: bool,
+        include_ignored: bool,
+        include_hidden: bool,
+    ) -> usize {
+        let (count, file_count) = match (include_ignored, include_hidden) {
+            (true, true) => (self.count, self.file_count),
+            (false, true) => (self.non_ignored_count, self.non_ignored_file_count),
+            (true, false) => (self.non_hidden_count, self.non_hidden_file_count),
+            (false, false) => (
+                self.non_ignored_non_hidden_count,
+                self.non_ignored_non_hidden_file_count,
+            ),
+        };
+        match (include_files, include_dirs) {
+            (true, true) => count,
+            (true, false) => file_count,
+            (false, true) => count - file_count,
+            (false, false) => 0,
         }
     }
 }
@@ -4827,8 +6638,12 @@ impl<'a> sum_tree::Dimension<'a, EntrySummary> for TraversalProgress<'a> {
         self.max_path = summary.max_path.as_ref();
         self.count += summary.count;
         self.non_ignored_count += summary.non_ignored_count;
+        self.non_hidden_count += summary.non_hidden_count;
+        self.non_ignored_non_hidden_count += summary.non_ignored_non_hidden_count;
         self.file_count += summary.file_count;
         self.non_ignored_file_count += summary.non_ignored_file_count;
+        self.non_hidden_file_count += summary.non_hidden_file_count;
+        self.non_ignored_non_hidden_file_count += summary.non_ignored_non_hidden_file_count;
     }
 }
 
@@ -4838,8 +6653,12 @@ impl<'a> Default for TraversalProgress<'a> {
             max_path: Path::new(""),
             count: 0,
             non_ignored_count: 0,
+            non_hidden_count: 0,
+            non_ignored_non_hidden_count: 0,
             file_count: 0,
             non_ignored_file_count: 0,
+            non_hidden_file_count: 0,
+            non_ignored_non_hidden_file_count: 0,
         }
     }
 }
@@ -4880,6 +6699,7 @@ impl<'a> sum_tree::Dimension<'a, EntrySummary> for GitStatuses {
 pub struct Traversal<'a> {
     cursor: sum_tree::Cursor<'a, Entry, TraversalProgress<'a>>,
     include_ignored: bool,
+    include_hidden: bool,
     include_files: bool,
     include_dirs: bool,
 }
@@ -4896,6 +6716,7 @@ impl<'a> Traversal<'a> {
                 include_dirs: self.include_dirs,
                 include_files: self.include_files,
                 include_ignored: self.include_ignored,
+                include_hidden: self.include_hidden,
             },
             Bias::Left,
             &(),
@@ -4913,6 +6734,7 @@ impl<'a> Traversal<'a> {
                 if (self.include_files || !entry.is_file())
                     && (self.include_dirs || !entry.is_dir())
                     && (self.include_ignored || !entry.is_ignored)
+                    && (self.include_hidden || !entry.is_hidden)
                 {
                     return true;
                 }
@@ -4926,15 +6748,21 @@ impl<'a> Traversal<'a> {
     }
 
     pub fn start_offset(&self) -> usize {
-        self.cursor
-            .start()
-            .count(self.include_files, self.include_dirs, self.include_ignored)
+        self.cursor.start().count(
+            self.include_files,
+            self.include_dirs,
+            self.include_ignored,
+            self.include_hidden,
+        )
     }
 
     pub fn end_offset(&self) -> usize {
-        self.cursor
-            .end(&())
-            .count(self.include_files, self.include_dirs, self.include_ignored)
+        self.cursor.end(&()).count(
+            self.include_files,
+            self.include_dirs,
+            self.include_ignored,
+            self.include_hidden,
+        )
     }
 }
 
@@ -4959,6 +6787,7 @@ enum TraversalTarget<'a> {
         count: usize,
         include_files: bool,
         include_ignored: bool,
+        include_hidden: bool,
         include_dirs: bool,
     },
 }
@@ -4979,9 +6808,15 @@ impl<'a, 'b> SeekTarget<'a, EntrySummary, TraversalProgress<'a>> for TraversalTa
                 include_files,
                 include_dirs,
                 include_ignored,
+                include_hidden,
             } => Ord::cmp(
                 count,
-                &cursor_location.count(*include_files, *include_dirs, *include_ignored),
+                &cursor_location.count(
+                    *include_files,
+                    *include_dirs,
+                    *include_ignored,
+                    *include_hidden,
+                ),
             ),
         }
     }
@@ -5014,18 +6849,42 @@ impl<'a> Iterator for ChildEntriesIter<'a> {
     }
 }
 
+pub struct DescendantEntriesIter<'a> {
+    parent_path: &'a Path,
+    traversal: Traversal<'a>,
+}
+
+impl<'a> Iterator for DescendantEntriesIter<'a> {
+    type Item = &'a Entry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.traversal.entry() {
+            if item.path.starts_with(&self.parent_path) {
+                self.traversal.advance();
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
 impl<'a> From<&'a Entry> for proto::Entry {
     fn from(entry: &'a Entry) -> Self {
         Self {
             id: entry.id.to_proto(),
             is_dir: entry.is_dir(),
             path: entry.path.to_string_lossy().into(),
-            inode: entry.inode,
+            inode: entry.inode.index(),
+            inode_volume: entry.inode.volume(),
             mtime: entry.mtime.map(|time| time.into()),
+            size: entry.size,
+            mode: entry.mode,
             is_symlink: entry.is_symlink,
             is_ignored: entry.is_ignored,
             is_external: entry.is_external,
             git_status: entry.git_status.map(git_status_to_proto),
+            is_symlink_cycle: entry.is_symlink_cycle,
+            is_hidden: entry.is_hidden,
         }
     }
 }
@@ -5046,13 +6905,18 @@ impl<'a> TryFrom<(&'a CharBag, proto::Entry)> for Entry {
             id: ProjectEntryId::from_proto(entry.id),
             kind,
             path,
-            inode: entry.inode,
+            inode: FileId::new(entry.inode_volume, entry.inode),
             mtime: entry.mtime.map(|time| time.into()),
+            size: entry.size,
+            mode: entry.mode,
             is_symlink: entry.is_symlink,
+            is_symlink_cycle: entry.is_symlink_cycle,
             is_ignored: entry.is_ignored,
             is_external: entry.is_external,
             git_status: git_status_from_proto(entry.git_status),
             is_private: false,
+            is_hidden: entry.is_hidden,
+            scan_error: None,
         })
     }
 }
@@ -1,7 +1,11 @@
+use crate::FS_WATCH_LATENCY;
+use fs::{WatcherBackend, DEFAULT_POLL_INTERVAL, MIN_POLL_INTERVAL};
 use gpui::AppContext;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
+use std::{path::Path, time::Duration};
+use util::{paths::PathMatcher, ResultExt};
 
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct WorktreeSettings {
@@ -20,9 +24,164 @@ pub struct WorktreeSettings {
     #[serde(default)]
     pub file_scan_exclusions: Option<Vec<String>>,
 
+    /// Globs of files to ignore completely, like `file_scan_exclusions`, without replacing
+    /// its defaults. Useful for excluding a project's build directories in its own settings.
+    ///
+    /// Default: []
+    pub extra_file_scan_exclusions: Option<Vec<String>>,
+
     /// Treat the files matching these globs as `.env` files.
     /// Default: [ "**/.env*" ]
     pub private_files: Option<Vec<String>>,
+
+    /// Short-lived files, such as editor swap and backup files, whose changes on disk are
+    /// ignored and which are left out of the file finder. They're still shown when browsing
+    /// the directories that contain them.
+    ///
+    /// Default: [
+    ///   "**/*.swp",
+    ///   "**/*.swo",
+    ///   "**/*.swx",
+    ///   "**/*~",
+    ///   "**/4913",
+    ///   "**/*.tmp",
+    ///   "**/.DS_Store"
+    /// ]
+    pub transient_files: Option<Vec<String>>,
+
+    /// Files that are flushed to disk, along with their directory, before their saves are
+    /// reported as done, so that they survive a crash or power loss. Useful for critical files
+    /// or on network file systems, at the cost of slower saves. Other files survive Zed
+    /// crashing, but a power loss or a crash of the system shortly after saving can leave them
+    /// empty on some file systems.
+    ///
+    /// Default: []
+    pub synced_files: Option<Vec<String>>,
+
+    /// How changes to the files on disk are detected.
+    pub file_watcher: Option<FileWatcherSettings>,
+
+    /// Scanning settings that apply to the worktrees whose root path matches one of a
+    /// profile's `root_paths`. The first matching profile is used.
+    ///
+    /// Default: []
+    pub scanning_profiles: Option<Vec<ScanningProfile>>,
+}
+
+/// A named set of scanning settings for worktrees with particular needs, e.g. a directory of
+/// data files that shouldn't be scanned as eagerly as a small repository.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ScanningProfile {
+    pub name: String,
+    /// Globs matched against the absolute path of a worktree's root.
+    pub root_paths: Vec<String>,
+    /// Globs of files to ignore in addition to the global `file_scan_exclusions`.
+    #[serde(default)]
+    pub file_scan_exclusions: Vec<String>,
+    /// How the contents of symlinked directories are scanned.
+    ///
+    /// Default: within_worktree
+    pub symlinks: Option<SymlinkPolicy>,
+    /// How many levels of directories below the root to scan eagerly. Deeper directories are
+    /// only scanned once expanded.
+    ///
+    /// Default: null
+    pub max_scan_depth: Option<usize>,
+    /// How often to scan the worktree for changes, in milliseconds, when using the
+    /// `polling` file watcher backend. Overrides `file_watcher.poll_interval_ms`. Values
+    /// below 100 are raised to 100.
+    pub poll_interval_ms: Option<u64>,
+    /// How long file system events are collected, in milliseconds, before they're processed
+    /// together. Overrides `file_watcher.latency_ms`.
+    pub latency_ms: Option<u64>,
+}
+
+/// How the scanner treats the directories that symlinks point to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkPolicy {
+    /// Scan the contents of every symlinked directory, including the ones outside of the
+    /// worktree. Those are still left out of searches.
+    Follow,
+    /// Show symlinked directories as entries without contents, which can't be expanded.
+    Leaf,
+    /// Scan the contents of the symlinked directories inside the worktree's root. The ones
+    /// outside of it are only scanned once expanded.
+    #[default]
+    WithinWorktree,
+}
+
+impl ScanningProfile {
+    pub fn poll_interval(&self) -> Option<Duration> {
+        self.poll_interval_ms
+            .map(|ms| Duration::from_millis(ms).max(MIN_POLL_INTERVAL))
+    }
+
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency_ms.map(Duration::from_millis)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FileWatcherSettings {
+    /// The source of file system events. Changing it only affects worktrees
+    /// that are opened afterwards.
+    ///
+    /// Default: native
+    pub backend: Option<WatcherBackend>,
+    /// How often to scan the worktree for changes, in milliseconds, when using the
+    /// `polling` backend. Values below 100 are raised to 100.
+    ///
+    /// Default: 1000
+    pub poll_interval_ms: Option<u64>,
+    /// How long file system events are collected, in milliseconds, before the worktree
+    /// processes them together. Changing it only affects worktrees that are opened
+    /// afterwards.
+    ///
+    /// Default: 100
+    pub latency_ms: Option<u64>,
+}
+
+impl WorktreeSettings {
+    pub fn watcher_backend(&self) -> WatcherBackend {
+        self.file_watcher
+            .as_ref()
+            .and_then(|file_watcher| file_watcher.backend)
+            .unwrap_or_default()
+    }
+
+    /// Returns the first scanning profile whose `root_paths` match the given worktree root.
+    pub fn scanning_profile(&self, root_abs_path: &Path) -> Option<&ScanningProfile> {
+        self.scanning_profiles.as_ref()?.iter().find(|profile| {
+            profile.root_paths.iter().any(|pattern| {
+                PathMatcher::new(pattern).map_or(false, |matcher| matcher.is_match(root_abs_path))
+            })
+        })
+    }
+
+    pub fn transient_file_matchers(&self) -> Vec<PathMatcher> {
+        self.transient_files
+            .iter()
+            .flatten()
+            .filter_map(|pattern| PathMatcher::new(pattern).log_err())
+            .collect()
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        self.file_watcher
+            .as_ref()
+            .and_then(|file_watcher| file_watcher.poll_interval_ms)
+            .map_or(DEFAULT_POLL_INTERVAL, |ms| {
+                Duration::from_millis(ms).max(MIN_POLL_INTERVAL)
+            })
+    }
+
+    pub fn watcher_latency(&self) -> Duration {
+        self.file_watcher
+            .as_ref()
+            .and_then(|file_watcher| file_watcher.latency_ms)
+            .map_or(FS_WATCH_LATENCY, Duration::from_millis)
+    }
 }
 
 impl Settings for WorktreeSettings {
@@ -1,9 +1,11 @@
 use crate::{
-    worktree_settings::WorktreeSettings, Entry, EntryKind, Event, PathChange, Snapshot, Worktree,
-    WorktreeModelHandle,
+    worktree_settings::{ScanningProfile, SymlinkPolicy, WorktreeSettings},
+    DirPathResolution, Durability, Entry, EntryKind, Event, FileOperation, LoadedFileChange,
+    PathChange, ScanErrorKind, ScanProgress, Snapshot, Worktree, WorktreeModelHandle,
+    MAX_PAUSED_EVENT_PATHS,
 };
 use anyhow::Result;
-use client::Client;
+use client::{proto, Client};
 use clock::FakeSystemClock;
 use fs::{FakeFs, Fs, RealFs, RemoveOptions};
 use git::{repository::GitFileStatus, GITIGNORE};
@@ -15,7 +17,7 @@ use pretty_assertions::assert_eq;
 use rand::prelude::*;
 use serde_json::json;
 use settings::{Settings, SettingsStore};
-use std::{env, fmt::Write, mem, path::Path, sync::Arc};
+use std::{env, fmt::Write, mem, path::Path, sync::Arc, time::Duration};
 use util::{test::temp_tree, ResultExt};
 
 #[gpui::test]
@@ -74,134 +76,129 @@ async fn test_traversal(cx: &mut TestAppContext) {
     })
 }
 
-#[gpui::test(iterations = 10)]
-async fn test_circular_symlinks(cx: &mut TestAppContext) {
+#[gpui::test]
+async fn test_entries_under(cx: &mut TestAppContext) {
     init_test(cx);
     let fs = FakeFs::new(cx.background_executor.clone());
     fs.insert_tree(
         "/root",
         json!({
-            "lib": {
-                "a": {
-                    "a.txt": ""
-                },
-                "b": {
-                    "b.txt": ""
-                }
-            }
+            ".gitignore": "a/b/ignored.txt\n",
+            "a": {
+                "b": { "c.txt": "", "ignored.txt": "" },
+                "d.txt": "",
+            },
+            "ab": { "e.txt": "" },
         }),
     )
     .await;
-    fs.create_symlink("/root/lib/a/lib".as_ref(), "..".into())
-        .await
-        .unwrap();
-    fs.create_symlink("/root/lib/b/lib".as_ref(), "..".into())
-        .await
-        .unwrap();
 
     let tree = Worktree::local(
         build_client(cx),
         Path::new("/root"),
         true,
-        fs.clone(),
+        fs,
         Default::default(),
         &mut cx.to_async(),
     )
     .await
     .unwrap();
-
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
 
     tree.read_with(cx, |tree, _| {
+        // Entries in sibling directories that share a prefix aren't included.
         assert_eq!(
-            tree.entries(false)
+            tree.entries_under(Path::new("a"), true)
                 .map(|entry| entry.path.as_ref())
                 .collect::<Vec<_>>(),
             vec![
-                Path::new(""),
-                Path::new("lib"),
-                Path::new("lib/a"),
-                Path::new("lib/a/a.txt"),
-                Path::new("lib/a/lib"),
-                Path::new("lib/b"),
-                Path::new("lib/b/b.txt"),
-                Path::new("lib/b/lib"),
+                Path::new("a/b"),
+                Path::new("a/b/c.txt"),
+                Path::new("a/b/ignored.txt"),
+                Path::new("a/d.txt"),
             ]
         );
-    });
+        assert_eq!(
+            tree.entries_under(Path::new("a/b"), false)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![Path::new("a/b/c.txt")]
+        );
+        assert_eq!(
+            tree.entries_under(Path::new(""), false).count(),
+            tree.entries(false).count() - 1
+        );
+        assert_eq!(tree.entries_under(Path::new("a/d.txt"), true).count(), 0);
+        assert_eq!(tree.entries_under(Path::new("missing"), true).count(), 0);
+    })
+}
 
-    fs.rename(
-        Path::new("/root/lib/a/lib"),
-        Path::new("/root/lib/a/lib-2"),
+#[gpui::test]
+async fn test_batched_metadata_reads(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": { "1.txt": "", "2.txt": "", "3.txt": "", "4.txt": "", "5.txt": "" },
+            "b": { "1.txt": "", "2.txt": "", "3.txt": "", "4.txt": "", "5.txt": "" },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
         Default::default(),
+        &mut cx.to_async(),
     )
     .await
     .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // Each directory's entries are statted together with the directory listing.
+    tree.read_with(cx, |tree, _| assert_eq!(tree.entries(true).count(), 13));
+    assert!(fs.metadata_call_count() < 10);
+
+    // Changes to several files in the same directory are reloaded in a single batch.
+    let metadata_call_count = fs.metadata_call_count();
+    fs.pause_events();
+    for name in ["6.txt", "7.txt", "8.txt", "9.txt", "10.txt"] {
+        fs.insert_file(Path::new("/root/a").join(name), Vec::new())
+            .await;
+    }
+    fs.flush_events(fs.buffered_event_count());
     cx.executor().run_until_parked();
     tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(false)
-                .map(|entry| entry.path.as_ref())
-                .collect::<Vec<_>>(),
-            vec![
-                Path::new(""),
-                Path::new("lib"),
-                Path::new("lib/a"),
-                Path::new("lib/a/a.txt"),
-                Path::new("lib/a/lib-2"),
-                Path::new("lib/b"),
-                Path::new("lib/b/b.txt"),
-                Path::new("lib/b/lib"),
-            ]
-        );
+        assert!(tree.entry_for_path("a/10.txt").is_some());
+        assert_eq!(tree.entries(true).count(), 18);
     });
+    assert!(fs.metadata_call_count() - metadata_call_count < 5);
 }
 
 #[gpui::test]
-async fn test_symlinks_pointing_outside(cx: &mut TestAppContext) {
+async fn test_worktree_stats(cx: &mut TestAppContext) {
     init_test(cx);
     let fs = FakeFs::new(cx.background_executor.clone());
     fs.insert_tree(
         "/root",
         json!({
-            "dir1": {
-                "deps": {
-                    // symlinks here
-                },
-                "src": {
-                    "a.rs": "",
-                    "b.rs": "",
-                },
-            },
-            "dir2": {
-                "src": {
-                    "c.rs": "",
-                    "d.rs": "",
-                }
-            },
-            "dir3": {
-                "deps": {},
-                "src": {
-                    "e.rs": "",
-                    "f.rs": "",
-                },
-            }
+            ".gitignore": "target\n",
+            "src": { "a.rs": "", "b.rs": "", "c": { "d.rs": "" } },
+            "target": { "debug": { "e": "" } },
+            "README.md": "",
         }),
     )
     .await;
-
-    // These symlinks point to directories outside of the worktree's root, dir1.
-    fs.create_symlink("/root/dir1/deps/dep-dir2".as_ref(), "../../dir2".into())
-        .await
-        .unwrap();
-    fs.create_symlink("/root/dir1/deps/dep-dir3".as_ref(), "../../dir3".into())
-        .await
-        .unwrap();
+    fs.insert_symlink("/root/link", "/root/src".into()).await;
 
     let tree = Worktree::local(
         build_client(cx),
-        Path::new("/root/dir1"),
+        Path::new("/root"),
         true,
         fs.clone(),
         Default::default(),
@@ -209,150 +206,160 @@ async fn test_symlinks_pointing_outside(cx: &mut TestAppContext) {
     )
     .await
     .unwrap();
-
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
 
-    let tree_updates = Arc::new(Mutex::new(Vec::new()));
-    tree.update(cx, |_, cx| {
-        let tree_updates = tree_updates.clone();
-        cx.subscribe(&tree, move |_, _, event, _| {
-            if let Event::UpdatedEntries(update) = event {
-                tree_updates.lock().extend(
-                    update
-                        .iter()
-                        .map(|(path, _, change)| (path.clone(), *change)),
-                );
-            }
-        })
-        .detach();
-    });
+    let stats = tree.read_with(cx, |tree, cx| tree.stats(2, cx)).await;
+    assert_eq!(stats.file_count, 8);
+    assert_eq!(stats.dir_count, 6);
+    assert_eq!(stats.unloaded_dir_count, 1);
+    assert_eq!(stats.ignored_count, 1);
+    assert_eq!(stats.symlink_count, 1);
+    assert_eq!(stats.external_count, 0);
+    // Scans are timed with the executor's virtual clock, which didn't advance.
+    assert_eq!(stats.last_scan_duration, Some(Duration::ZERO));
+    assert_eq!(
+        stats.largest_dirs,
+        vec![(Path::new("link").into(), 4), (Path::new("src").into(), 4)]
+    );
+}
+
+#[gpui::test]
+async fn test_resolve_dir_path(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "target\n",
+            "src": { "a.rs": "", "b": {} },
+            "target": { "debug": {} },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
 
-    // The symlinked directories are not scanned by default.
     tree.read_with(cx, |tree, _| {
+        let b_id = tree.entry_for_path("src/b").unwrap().id;
         assert_eq!(
-            tree.entries(true)
-                .map(|entry| (entry.path.as_ref(), entry.is_external))
-                .collect::<Vec<_>>(),
-            vec![
-                (Path::new(""), false),
-                (Path::new("deps"), false),
-                (Path::new("deps/dep-dir2"), true),
-                (Path::new("deps/dep-dir3"), true),
-                (Path::new("src"), false),
-                (Path::new("src/a.rs"), false),
-                (Path::new("src/b.rs"), false),
-            ]
+            tree.resolve_dir_path(Path::new("src/b")),
+            DirPathResolution::Found(b_id)
         );
-
         assert_eq!(
-            tree.entry_for_path("deps/dep-dir2").unwrap().kind,
-            EntryKind::UnloadedDir
+            tree.resolve_dir_path(Path::new("src/a.rs")),
+            DirPathResolution::Missing
         );
-    });
-
-    // Expand one of the symlinked directories.
-    tree.read_with(cx, |tree, _| {
-        tree.as_local()
-            .unwrap()
-            .refresh_entries_for_paths(vec![Path::new("deps/dep-dir3").into()])
-    })
-    .recv()
-    .await;
-
-    // The expanded directory's contents are loaded. Subdirectories are
-    // not scanned yet.
-    tree.read_with(cx, |tree, _| {
         assert_eq!(
-            tree.entries(true)
-                .map(|entry| (entry.path.as_ref(), entry.is_external))
-                .collect::<Vec<_>>(),
-            vec![
-                (Path::new(""), false),
-                (Path::new("deps"), false),
-                (Path::new("deps/dep-dir2"), true),
-                (Path::new("deps/dep-dir3"), true),
-                (Path::new("deps/dep-dir3/deps"), true),
-                (Path::new("deps/dep-dir3/src"), true),
-                (Path::new("src"), false),
-                (Path::new("src/a.rs"), false),
-                (Path::new("src/b.rs"), false),
-            ]
+            tree.resolve_dir_path(Path::new("src/c")),
+            DirPathResolution::Missing
+        );
+        assert_eq!(
+            tree.resolve_dir_path(Path::new("src/a.rs/d")),
+            DirPathResolution::Missing
+        );
+        assert_eq!(
+            tree.resolve_dir_path(Path::new("target/debug")),
+            DirPathResolution::Pending
         );
     });
-    assert_eq!(
-        mem::take(&mut *tree_updates.lock()),
-        &[
-            (Path::new("deps/dep-dir3").into(), PathChange::Loaded),
-            (Path::new("deps/dep-dir3/deps").into(), PathChange::Loaded),
-            (Path::new("deps/dep-dir3/src").into(), PathChange::Loaded)
-        ]
-    );
 
-    // Expand a subdirectory of one of the symlinked directories.
+    // Once the ignored directory is loaded, its descendants can be resolved.
     tree.read_with(cx, |tree, _| {
         tree.as_local()
             .unwrap()
-            .refresh_entries_for_paths(vec![Path::new("deps/dep-dir3/src").into()])
+            .refresh_entries_for_paths(vec![Path::new("target/debug").into()])
     })
     .recv()
     .await;
-
-    // The expanded subdirectory's contents are loaded.
     tree.read_with(cx, |tree, _| {
+        let debug_id = tree.entry_for_path("target/debug").unwrap().id;
         assert_eq!(
-            tree.entries(true)
-                .map(|entry| (entry.path.as_ref(), entry.is_external))
-                .collect::<Vec<_>>(),
-            vec![
-                (Path::new(""), false),
-                (Path::new("deps"), false),
-                (Path::new("deps/dep-dir2"), true),
-                (Path::new("deps/dep-dir3"), true),
-                (Path::new("deps/dep-dir3/deps"), true),
-                (Path::new("deps/dep-dir3/src"), true),
-                (Path::new("deps/dep-dir3/src/e.rs"), true),
-                (Path::new("deps/dep-dir3/src/f.rs"), true),
-                (Path::new("src"), false),
-                (Path::new("src/a.rs"), false),
-                (Path::new("src/b.rs"), false),
-            ]
+            tree.resolve_dir_path(Path::new("target/debug")),
+            DirPathResolution::Found(debug_id)
         );
     });
-
-    assert_eq!(
-        mem::take(&mut *tree_updates.lock()),
-        &[
-            (Path::new("deps/dep-dir3/src").into(), PathChange::Loaded),
-            (
-                Path::new("deps/dep-dir3/src/e.rs").into(),
-                PathChange::Loaded
-            ),
-            (
-                Path::new("deps/dep-dir3/src/f.rs").into(),
-                PathChange::Loaded
-            )
-        ]
-    );
 }
 
-#[cfg(target_os = "macos")]
 #[gpui::test]
-async fn test_renaming_case_only(cx: &mut TestAppContext) {
-    cx.executor().allow_parking();
+async fn test_coalescing_writes(cx: &mut TestAppContext) {
     init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a.txt": "" })).await;
 
-    const OLD_NAME: &str = "aaa.rs";
-    const NEW_NAME: &str = "AAA.rs";
-
-    let fs = Arc::new(RealFs::default());
-    let temp_root = temp_tree(json!({
-        OLD_NAME: "",
-    }));
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // Contents requested while a write is in flight replace each other, so only the first
+    // and the latest contents are written.
+    let save_call_count = fs.save_call_count();
+    let writes = tree.update(cx, |tree, cx| {
+        let tree = tree.as_local().unwrap();
+        ["one", "two", "three"]
+            .into_iter()
+            .map(|text| {
+                tree.write_file(
+                    Path::new("a.txt"),
+                    text.into(),
+                    Default::default(),
+                    Durability::Buffered,
+                    cx,
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+    for write in writes {
+        write.await.unwrap();
+    }
+    assert_eq!(fs.save_call_count() - save_call_count, 2);
+    assert_eq!(fs.load(Path::new("/root/a.txt")).await.unwrap(), "three");
+
+    // Once the writes complete, later ones are written right away.
+    tree.update(cx, |tree, cx| {
+        tree.as_local().unwrap().write_file(
+            Path::new("a.txt"),
+            "four".into(),
+            Default::default(),
+            Durability::Buffered,
+            cx,
+        )
+    })
+    .await
+    .unwrap();
+    assert_eq!(fs.save_call_count() - save_call_count, 3);
+    assert_eq!(fs.load(Path::new("/root/a.txt")).await.unwrap(), "four");
+}
+
+#[gpui::test]
+async fn test_synced_writes(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a.txt": "" })).await;
 
     let tree = Worktree::local(
         build_client(cx),
-        temp_root.path(),
+        Path::new("/root"),
         true,
         fs.clone(),
         Default::default(),
@@ -360,72 +367,149 @@ async fn test_renaming_case_only(cx: &mut TestAppContext) {
     )
     .await
     .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // A synced write that's replaced before it's written makes the write replacing it synced.
+    let writes = tree.update(cx, |tree, cx| {
+        let tree = tree.as_local().unwrap();
+        [
+            ("one", Durability::Buffered),
+            ("two", Durability::Synced),
+            ("three", Durability::Buffered),
+        ]
+        .into_iter()
+        .map(|(text, durability)| {
+            tree.write_file(
+                Path::new("a.txt"),
+                text.into(),
+                Default::default(),
+                durability,
+                cx,
+            )
+        })
+        .collect::<Vec<_>>()
+    });
+    for write in writes {
+        write.await.unwrap();
+    }
+    assert_eq!(fs.load(Path::new("/root/a.txt")).await.unwrap(), "three");
+    assert_eq!(fs.synced_paths(), [Path::new("/root/a.txt")]);
+
+    tree.update(cx, |tree, cx| {
+        tree.as_local().unwrap().write_file(
+            Path::new("a.txt"),
+            "four".into(),
+            Default::default(),
+            Durability::Buffered,
+            cx,
+        )
+    })
+    .await
+    .unwrap();
+    assert_eq!(fs.synced_paths().len(), 1);
+}
+
+#[gpui::test]
+async fn test_entry_sizes(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a.txt": "abc", "b": { "c.txt": "" } }))
+        .await;
 
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
-    tree.read_with(cx, |tree, _| {
-        assert_eq!(
+
+    let sizes = |cx: &mut TestAppContext| {
+        tree.read_with(cx, |tree, _| {
             tree.entries(true)
-                .map(|entry| entry.path.as_ref())
-                .collect::<Vec<_>>(),
-            vec![Path::new(""), Path::new(OLD_NAME)]
-        );
-    });
+                .map(|entry| (entry.path.to_string_lossy().into_owned(), entry.size))
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(
+        sizes(cx),
+        [
+            ("".into(), 0),
+            ("a.txt".into(), 3),
+            ("b".into(), 0),
+            ("b/c.txt".into(), 0)
+        ]
+    );
 
-    fs.rename(
-        &temp_root.path().join(OLD_NAME),
-        &temp_root.path().join(NEW_NAME),
-        fs::RenameOptions {
-            overwrite: true,
-            ignore_if_exists: true,
-        },
+    fs.save(
+        Path::new("/root/b/c.txt"),
+        &"hello".into(),
+        Default::default(),
+        Default::default(),
     )
     .await
     .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(sizes(cx)[3], ("b/c.txt".into(), 5));
+}
 
-    tree.flush_fs_events(cx).await;
+#[gpui::test]
+async fn test_executable_files_stay_executable_when_saved(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "run.sh": "echo one", "a.txt": "" }))
+        .await;
+    fs.set_file_mode("/root/run.sh", 0o755).unwrap();
 
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
     tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(true)
-                .map(|entry| entry.path.as_ref())
-                .collect::<Vec<_>>(),
-            vec![Path::new(""), Path::new(NEW_NAME)]
-        );
+        assert!(tree.entry_for_path("run.sh").unwrap().is_executable());
+        assert!(!tree.entry_for_path("a.txt").unwrap().is_executable());
+        assert!(!tree.entry_for_path("").unwrap().is_executable());
+    });
+
+    tree.update(cx, |tree, cx| {
+        tree.as_local().unwrap().write_file(
+            Path::new("run.sh"),
+            "echo two".into(),
+            Default::default(),
+            Durability::Buffered,
+            cx,
+        )
+    })
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path("run.sh").unwrap();
+        assert_eq!(entry.mode, Some(0o755));
+        assert!(entry.is_executable());
     });
 }
 
 #[gpui::test]
-async fn test_open_gitignored_files(cx: &mut TestAppContext) {
+async fn test_loaded_file_changes(cx: &mut TestAppContext) {
     init_test(cx);
     let fs = FakeFs::new(cx.background_executor.clone());
-    fs.insert_tree(
-        "/root",
-        json!({
-            ".gitignore": "node_modules\n",
-            "one": {
-                "node_modules": {
-                    "a": {
-                        "a1.js": "a1",
-                        "a2.js": "a2",
-                    },
-                    "b": {
-                        "b1.js": "b1",
-                        "b2.js": "b2",
-                    },
-                    "c": {
-                        "c1.js": "c1",
-                        "c2.js": "c2",
-                    }
-                },
-            },
-            "two": {
-                "x.js": "",
-                "y.js": "",
-            },
-        }),
-    )
-    .await;
+    fs.insert_tree("/root", json!({ "a.txt": "a", "b.txt": "b", "c.txt": "c" }))
+        .await;
 
     let tree = Worktree::local(
         build_client(cx),
@@ -437,162 +521,1938 @@ async fn test_open_gitignored_files(cx: &mut TestAppContext) {
     )
     .await
     .unwrap();
-
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
 
-    tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(true)
-                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
-                .collect::<Vec<_>>(),
-            vec![
-                (Path::new(""), false),
-                (Path::new(".gitignore"), false),
-                (Path::new("one"), false),
-                (Path::new("one/node_modules"), true),
-                (Path::new("two"), false),
-                (Path::new("two/x.js"), false),
-                (Path::new("two/y.js"), false),
-            ]
-        );
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let changes = changes.clone();
+        cx.subscribe(&tree, move |_, _, event, _| {
+            if let Event::LoadedFileChanged(path, change) = event {
+                changes.lock().push((path.clone(), change.clone()));
+            }
+        })
+        .detach();
     });
 
-    // Open a file that is nested inside of a gitignored directory that
-    // has not yet been expanded.
-    let prev_read_dir_count = fs.read_dir_call_count();
     let buffer = tree
         .update(cx, |tree, cx| {
             tree.as_local_mut()
                 .unwrap()
-                .load_buffer("one/node_modules/b/b1.js".as_ref(), cx)
+                .load_buffer(Path::new("a.txt"), cx)
         })
         .await
         .unwrap();
-
-    tree.read_with(cx, |tree, cx| {
-        assert_eq!(
-            tree.entries(true)
-                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
-                .collect::<Vec<_>>(),
-            vec![
-                (Path::new(""), false),
-                (Path::new(".gitignore"), false),
-                (Path::new("one"), false),
-                (Path::new("one/node_modules"), true),
-                (Path::new("one/node_modules/a"), true),
-                (Path::new("one/node_modules/b"), true),
-                (Path::new("one/node_modules/b/b1.js"), true),
-                (Path::new("one/node_modules/b/b2.js"), true),
-                (Path::new("one/node_modules/c"), true),
-                (Path::new("two"), false),
-                (Path::new("two/x.js"), false),
-                (Path::new("two/y.js"), false),
-            ]
-        );
-
-        assert_eq!(
-            buffer.read(cx).file().unwrap().path().as_ref(),
-            Path::new("one/node_modules/b/b1.js")
-        );
-
-        // Only the newly-expanded directories are scanned.
-        assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 2);
-    });
-
-    // Open another file in a different subdirectory of the same
-    // gitignored directory.
-    let prev_read_dir_count = fs.read_dir_call_count();
-    let buffer = tree
+    let _buffer_b = tree
         .update(cx, |tree, cx| {
             tree.as_local_mut()
                 .unwrap()
-                .load_buffer("one/node_modules/a/a2.js".as_ref(), cx)
+                .load_buffer(Path::new("b.txt"), cx)
+        })
+        .await
+        .unwrap();
+    let buffer_c = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .load_buffer(Path::new("c.txt"), cx)
         })
         .await
         .unwrap();
 
-    tree.read_with(cx, |tree, cx| {
-        assert_eq!(
-            tree.entries(true)
-                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
-                .collect::<Vec<_>>(),
-            vec![
-                (Path::new(""), false),
-                (Path::new(".gitignore"), false),
-                (Path::new("one"), false),
-                (Path::new("one/node_modules"), true),
-                (Path::new("one/node_modules/a"), true),
-                (Path::new("one/node_modules/a/a1.js"), true),
-                (Path::new("one/node_modules/a/a2.js"), true),
-                (Path::new("one/node_modules/b"), true),
-                (Path::new("one/node_modules/b/b1.js"), true),
-                (Path::new("one/node_modules/b/b2.js"), true),
-                (Path::new("one/node_modules/c"), true),
-                (Path::new("two"), false),
-                (Path::new("two/x.js"), false),
-                (Path::new("two/y.js"), false),
-            ]
-        );
+    // Saving a loaded file isn't reported as a change.
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "x")], None, cx));
+    tree.update(cx, |tree, cx| {
+        tree.as_local().unwrap().save_buffer(
+            buffer.clone(),
+            Path::new("a.txt").into(),
+            false,
+            Durability::Buffered,
+            cx,
+        )
+    })
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(changes.lock().drain(..).collect::<Vec<_>>(), []);
 
-        assert_eq!(
-            buffer.read(cx).file().unwrap().path().as_ref(),
-            Path::new("one/node_modules/a/a2.js")
+    // Files whose buffers were released aren't reported.
+    cx.update(|_| drop(buffer_c));
+    fs.save(
+        Path::new("/root/a.txt"),
+        &"y".into(),
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    fs.save(
+        Path::new("/root/c.txt"),
+        &"y".into(),
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        changes.lock().drain(..).collect::<Vec<_>>(),
+        [(Path::new("a.txt").into(), LoadedFileChange::ChangedOnDisk)]
+    );
+
+    // Moved files are followed to their new paths.
+    fs.rename(
+        Path::new("/root/a.txt"),
+        Path::new("/root/d.txt"),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    fs.remove_file(Path::new("/root/b.txt"), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    fs.save(
+        Path::new("/root/d.txt"),
+        &"z".into(),
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    let mut reported = changes.lock().drain(..).collect::<Vec<_>>();
+    reported.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        reported,
+        [
+            (
+                Path::new("a.txt").into(),
+                LoadedFileChange::Moved(Path::new("d.txt").into())
+            ),
+            (Path::new("b.txt").into(), LoadedFileChange::Deleted),
+            (Path::new("d.txt").into(), LoadedFileChange::ChangedOnDisk),
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_rescan(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": { "b": { "c.txt": "" } },
+            "d": { "e.txt": "" },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // Change the file system without reporting any events.
+    fs.pause_events();
+    fs.insert_tree("/root/a/b", json!({ "f": { "g.txt": "" } }))
+        .await;
+    fs.remove_file(Path::new("/root/a/b/c.txt"), Default::default())
+        .await
+        .unwrap();
+    fs.insert_file("/root/d/h.txt", Vec::new()).await;
+
+    // Rescanning a subtree picks up the changes within it, but not elsewhere.
+    tree.read_with(cx, |tree, _| {
+        tree.as_local().unwrap().rescan(Path::new("a").into())
+    })
+    .recv()
+    .await;
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("a"),
+                Path::new("a/b"),
+                Path::new("a/b/f"),
+                Path::new("a/b/f/g.txt"),
+                Path::new("d"),
+                Path::new("d/e.txt"),
+            ]
+        );
+    });
+
+    tree.read_with(cx, |tree, _| {
+        tree.as_local().unwrap().rescan(Path::new("").into())
+    })
+    .recv()
+    .await;
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("d/h.txt").is_some());
+    });
+}
+
+#[gpui::test]
+async fn test_rename_and_remove_storm(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": { "b": { "c.txt": "", "d": { "e.txt": "" } } },
+            "f": { "g": { "h.txt": "" } },
+            "i.txt": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    let entry_id = |path: &str, cx: &mut TestAppContext| {
+        tree.read_with(cx, |tree, _| tree.entry_for_path(path).unwrap().id)
+    };
+    let b_id = entry_id("a/b", cx);
+    let e_id = entry_id("a/b/d/e.txt", cx);
+    let i_id = entry_id("i.txt", cx);
+
+    // Report all of the changes at once, after the file system has settled.
+    fs.pause_events();
+    fs.rename(
+        Path::new("/root/a/b"),
+        Path::new("/root/f/b"),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    fs.rename(
+        Path::new("/root/i.txt"),
+        Path::new("/root/f/b/d/i.txt"),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    fs.remove_dir(
+        Path::new("/root/f/g"),
+        RemoveOptions {
+            recursive: true,
+            ignore_if_not_exists: false,
+        },
+    )
+    .await
+    .unwrap();
+    fs.insert_tree("/root/j", json!({ "k": { "l": { "m.txt": "" } } }))
+        .await;
+    fs.insert_file("/root/a/n.txt", Vec::new()).await;
+    fs.remove_file(Path::new("/root/a/n.txt"), Default::default())
+        .await
+        .unwrap();
+    fs.flush_events(usize::MAX);
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        tree.as_local().unwrap().snapshot().check_invariants(true);
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("a"),
+                Path::new("f"),
+                Path::new("f/b"),
+                Path::new("f/b/c.txt"),
+                Path::new("f/b/d"),
+                Path::new("f/b/d/e.txt"),
+                Path::new("f/b/d/i.txt"),
+                Path::new("j"),
+                Path::new("j/k"),
+                Path::new("j/k/l"),
+                Path::new("j/k/l/m.txt"),
+            ]
+        );
+    });
+
+    // Moved entries keep their ids, along with the entries within them.
+    assert_eq!(entry_id("f/b", cx), b_id);
+    assert_eq!(entry_id("f/b/d/e.txt", cx), e_id);
+    assert_eq!(entry_id("f/b/d/i.txt", cx), i_id);
+}
+
+#[gpui::test]
+async fn test_transient_file_events(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<WorktreeSettings>(cx, |settings| {
+                settings.transient_files = Some(vec!["**/*.swp".to_string()]);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "a.txt": "", ".a.txt.swp": "" }))
+        .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(".a.txt.swp").is_some());
+    });
+
+    fs.insert_file("/root/b.txt", Vec::new()).await;
+    fs.insert_file("/root/.b.txt.swp", Vec::new()).await;
+    fs.remove_file(Path::new("/root/.a.txt.swp"), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("b.txt").is_some());
+        // Events for new transient files are ignored, but not for the ones that were scanned.
+        assert!(tree.entry_for_path(".b.txt.swp").is_none());
+        assert!(tree.entry_for_path(".a.txt.swp").is_none());
+    });
+
+    // Transient files are still found when their directory is scanned.
+    tree.read_with(cx, |tree, _| {
+        tree.as_local().unwrap().rescan(Path::new("").into())
+    })
+    .recv()
+    .await;
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path(".b.txt.swp").is_some());
+    });
+}
+
+#[gpui::test(iterations = 10)]
+async fn test_circular_symlinks(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "lib": {
+                "a": {
+                    "a.txt": ""
+                },
+                "b": {
+                    "b.txt": ""
+                }
+            }
+        }),
+    )
+    .await;
+    fs.create_symlink("/root/lib/a/lib".as_ref(), "..".into())
+        .await
+        .unwrap();
+    fs.create_symlink("/root/lib/b/lib".as_ref(), "..".into())
+        .await
+        .unwrap();
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(false)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("lib"),
+                Path::new("lib/a"),
+                Path::new("lib/a/a.txt"),
+                Path::new("lib/a/lib"),
+                Path::new("lib/b"),
+                Path::new("lib/b/b.txt"),
+                Path::new("lib/b/lib"),
+            ]
+        );
+        assert_eq!(
+            tree.entries(false)
+                .filter(|entry| entry.is_symlink_cycle)
+                .map(|entry| (entry.path.as_ref(), entry.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new("lib/a/lib"), EntryKind::Dir),
+                (Path::new("lib/b/lib"), EntryKind::Dir),
+            ]
+        );
+    });
+
+    fs.rename(
+        Path::new("/root/lib/a/lib"),
+        Path::new("/root/lib/a/lib-2"),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(false)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("lib"),
+                Path::new("lib/a"),
+                Path::new("lib/a/a.txt"),
+                Path::new("lib/a/lib-2"),
+                Path::new("lib/b"),
+                Path::new("lib/b/b.txt"),
+                Path::new("lib/b/lib"),
+            ]
+        );
+        assert!(tree.entry_for_path("lib/a/lib-2").unwrap().is_symlink_cycle);
+
+        // Collaborators see the same cycles.
+        let entry = tree.entry_for_path("lib/a/lib-2").unwrap();
+        let remote_entry =
+            Entry::try_from((&tree.root_char_bag, proto::Entry::from(entry))).unwrap();
+        assert!(remote_entry.is_symlink_cycle);
+    });
+}
+
+#[gpui::test]
+async fn test_symlinks_pointing_outside(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "dir1": {
+                "deps": {
+                    // symlinks here
+                },
+                "src": {
+                    "a.rs": "",
+                    "b.rs": "",
+                },
+            },
+            "dir2": {
+                "src": {
+                    "c.rs": "",
+                    "d.rs": "",
+                }
+            },
+            "dir3": {
+                "deps": {},
+                "src": {
+                    "e.rs": "",
+                    "f.rs": "",
+                },
+            }
+        }),
+    )
+    .await;
+
+    // These symlinks point to directories outside of the worktree's root, dir1.
+    fs.create_symlink("/root/dir1/deps/dep-dir2".as_ref(), "../../dir2".into())
+        .await
+        .unwrap();
+    fs.create_symlink("/root/dir1/deps/dep-dir3".as_ref(), "../../dir3".into())
+        .await
+        .unwrap();
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root/dir1"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let tree_updates = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let tree_updates = tree_updates.clone();
+        cx.subscribe(&tree, move |_, _, event, _| {
+            if let Event::UpdatedEntries(update) = event {
+                tree_updates.lock().extend(
+                    update
+                        .iter()
+                        .map(|(path, _, change)| (path.clone(), *change)),
+                );
+            }
+        })
+        .detach();
+    });
+
+    // The symlinked directories are not scanned by default.
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| (entry.path.as_ref(), entry.is_external))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new(""), false),
+                (Path::new("deps"), false),
+                (Path::new("deps/dep-dir2"), true),
+                (Path::new("deps/dep-dir3"), true),
+                (Path::new("src"), false),
+                (Path::new("src/a.rs"), false),
+                (Path::new("src/b.rs"), false),
+            ]
+        );
+
+        assert_eq!(
+            tree.entry_for_path("deps/dep-dir2").unwrap().kind,
+            EntryKind::UnloadedDir
+        );
+    });
+
+    // Expand one of the symlinked directories.
+    tree.read_with(cx, |tree, _| {
+        tree.as_local()
+            .unwrap()
+            .refresh_entries_for_paths(vec![Path::new("deps/dep-dir3").into()])
+    })
+    .recv()
+    .await;
+
+    // The expanded directory's contents are loaded. Subdirectories are
+    // not scanned yet.
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| (entry.path.as_ref(), entry.is_external))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new(""), false),
+                (Path::new("deps"), false),
+                (Path::new("deps/dep-dir2"), true),
+                (Path::new("deps/dep-dir3"), true),
+                (Path::new("deps/dep-dir3/deps"), true),
+                (Path::new("deps/dep-dir3/src"), true),
+                (Path::new("src"), false),
+                (Path::new("src/a.rs"), false),
+                (Path::new("src/b.rs"), false),
+            ]
+        );
+    });
+    assert_eq!(
+        mem::take(&mut *tree_updates.lock()),
+        &[
+            (Path::new("deps/dep-dir3").into(), PathChange::Loaded),
+            (Path::new("deps/dep-dir3/deps").into(), PathChange::Loaded),
+            (Path::new("deps/dep-dir3/src").into(), PathChange::Loaded)
+        ]
+    );
+
+    // Expand a subdirectory of one of the symlinked directories.
+    tree.read_with(cx, |tree, _| {
+        tree.as_local()
+            .unwrap()
+            .refresh_entries_for_paths(vec![Path::new("deps/dep-dir3/src").into()])
+    })
+    .recv()
+    .await;
+
+    // The expanded subdirectory's contents are loaded.
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| (entry.path.as_ref(), entry.is_external))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new(""), false),
+                (Path::new("deps"), false),
+                (Path::new("deps/dep-dir2"), true),
+                (Path::new("deps/dep-dir3"), true),
+                (Path::new("deps/dep-dir3/deps"), true),
+                (Path::new("deps/dep-dir3/src"), true),
+                (Path::new("deps/dep-dir3/src/e.rs"), true),
+                (Path::new("deps/dep-dir3/src/f.rs"), true),
+                (Path::new("src"), false),
+                (Path::new("src/a.rs"), false),
+                (Path::new("src/b.rs"), false),
+            ]
+        );
+    });
+
+    assert_eq!(
+        mem::take(&mut *tree_updates.lock()),
+        &[
+            (Path::new("deps/dep-dir3/src").into(), PathChange::Loaded),
+            (
+                Path::new("deps/dep-dir3/src/e.rs").into(),
+                PathChange::Loaded
+            ),
+            (
+                Path::new("deps/dep-dir3/src/f.rs").into(),
+                PathChange::Loaded
+            )
+        ]
+    );
+}
+
+#[cfg(target_os = "macos")]
+#[gpui::test]
+async fn test_renaming_case_only(cx: &mut TestAppContext) {
+    cx.executor().allow_parking();
+    init_test(cx);
+
+    const OLD_NAME: &str = "aaa.rs";
+    const NEW_NAME: &str = "AAA.rs";
+
+    let fs = Arc::new(RealFs::default());
+    let temp_root = temp_tree(json!({
+        OLD_NAME: "",
+    }));
+
+    let tree = Worktree::local(
+        build_client(cx),
+        temp_root.path(),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![Path::new(""), Path::new(OLD_NAME)]
+        );
+    });
+
+    fs.rename(
+        &temp_root.path().join(OLD_NAME),
+        &temp_root.path().join(NEW_NAME),
+        fs::RenameOptions {
+            overwrite: true,
+            ignore_if_exists: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    tree.flush_fs_events(cx).await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![Path::new(""), Path::new(NEW_NAME)]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_open_gitignored_files(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "node_modules\n",
+            "one": {
+                "node_modules": {
+                    "a": {
+                        "a1.js": "a1",
+                        "a2.js": "a2",
+                    },
+                    "b": {
+                        "b1.js": "b1",
+                        "b2.js": "b2",
+                    },
+                    "c": {
+                        "c1.js": "c1",
+                        "c2.js": "c2",
+                    }
+                },
+            },
+            "two": {
+                "x.js": "",
+                "y.js": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new(""), false),
+                (Path::new(".gitignore"), false),
+                (Path::new("one"), false),
+                (Path::new("one/node_modules"), true),
+                (Path::new("two"), false),
+                (Path::new("two/x.js"), false),
+                (Path::new("two/y.js"), false),
+            ]
+        );
+    });
+
+    // Open a file that is nested inside of a gitignored directory that
+    // has not yet been expanded.
+    let prev_read_dir_count = fs.read_dir_call_count();
+    let buffer = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .load_buffer("one/node_modules/b/b1.js".as_ref(), cx)
+        })
+        .await
+        .unwrap();
+
+    tree.read_with(cx, |tree, cx| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new(""), false),
+                (Path::new(".gitignore"), false),
+                (Path::new("one"), false),
+                (Path::new("one/node_modules"), true),
+                (Path::new("one/node_modules/a"), true),
+                (Path::new("one/node_modules/b"), true),
+                (Path::new("one/node_modules/b/b1.js"), true),
+                (Path::new("one/node_modules/b/b2.js"), true),
+                (Path::new("one/node_modules/c"), true),
+                (Path::new("two"), false),
+                (Path::new("two/x.js"), false),
+                (Path::new("two/y.js"), false),
+            ]
+        );
+
+        assert_eq!(
+            buffer.read(cx).file().unwrap().path().as_ref(),
+            Path::new("one/node_modules/b/b1.js")
+        );
+
+        // Only the newly-expanded directories are scanned.
+        assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 2);
+    });
+
+    // Open another file in a different subdirectory of the same
+    // gitignored directory.
+    let prev_read_dir_count = fs.read_dir_call_count();
+    let buffer = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .load_buffer("one/node_modules/a/a2.js".as_ref(), cx)
+        })
+        .await
+        .unwrap();
+
+    tree.read_with(cx, |tree, cx| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| (entry.path.as_ref(), entry.is_ignored))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new(""), false),
+                (Path::new(".gitignore"), false),
+                (Path::new("one"), false),
+                (Path::new("one/node_modules"), true),
+                (Path::new("one/node_modules/a"), true),
+                (Path::new("one/node_modules/a/a1.js"), true),
+                (Path::new("one/node_modules/a/a2.js"), true),
+                (Path::new("one/node_modules/b"), true),
+                (Path::new("one/node_modules/b/b1.js"), true),
+                (Path::new("one/node_modules/b/b2.js"), true),
+                (Path::new("one/node_modules/c"), true),
+                (Path::new("two"), false),
+                (Path::new("two/x.js"), false),
+                (Path::new("two/y.js"), false),
+            ]
+        );
+
+        assert_eq!(
+            buffer.read(cx).file().unwrap().path().as_ref(),
+            Path::new("one/node_modules/a/a2.js")
+        );
+
+        // Only the newly-expanded directory is scanned.
+        assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 1);
+    });
+
+    // No work happens when files and directories change within an unloaded directory.
+    let prev_fs_call_count = fs.read_dir_call_count() + fs.metadata_call_count();
+    fs.create_dir("/root/one/node_modules/c/lib".as_ref())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        fs.read_dir_call_count() + fs.metadata_call_count() - prev_fs_call_count,
+        0
+    );
+}
+
+#[gpui::test]
+async fn test_load_ignored_directories(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "node_modules\n",
+            "node_modules": {
+                "a": {
+                    "a1.js": "a1",
+                },
+                "b.js": "b",
+            },
+            "x.js": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("node_modules/b.js").is_none());
+        tree.as_local()
+            .unwrap()
+            .load_ignored_directories(|entry| entry.path.as_ref() == Path::new("node_modules"));
+    });
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| (
+                    entry.path.as_ref(),
+                    entry.kind.is_unloaded(),
+                    entry.is_ignored
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new(""), false, false),
+                (Path::new(".gitignore"), false, false),
+                (Path::new("node_modules"), false, true),
+                (Path::new("node_modules/a"), true, true),
+                (Path::new("node_modules/b.js"), false, true),
+                (Path::new("x.js"), false, false),
+            ]
+        );
+    });
+
+    // Subdirectories are only loaded when they're accepted in turn.
+    tree.read_with(cx, |tree, _| {
+        tree.as_local().unwrap().load_ignored_directories(|_| true);
+    });
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path("node_modules/a/a1.js")
+                .unwrap()
+                .is_ignored
+        );
+    });
+
+    // The loaded directories are kept up to date.
+    fs.create_file("/root/node_modules/a/a2.js".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(
+            tree.entry_for_path("node_modules/a/a2.js")
+                .unwrap()
+                .is_ignored
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_hidden_entries(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".config": {
+                "settings.json": "",
+            },
+            ".gitignore": "target\n",
+            "src": {
+                ".generated.rs": "",
+                "main.rs": "",
+            },
+            "target": {
+                "out.rs": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| (entry.path.as_ref(), entry.is_hidden))
+                .collect::<Vec<_>>(),
+            vec![
+                (Path::new(""), false),
+                (Path::new(".config"), true),
+                (Path::new(".config/settings.json"), true),
+                (Path::new(".gitignore"), true),
+                (Path::new("src"), false),
+                (Path::new("src/.generated.rs"), true),
+                (Path::new("src/main.rs"), false),
+                (Path::new("target"), false),
+            ]
+        );
+
+        // Hidden files are filtered independently of ignored ones.
+        assert_eq!(
+            tree.files(true, false, 0)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![Path::new("src/main.rs")]
+        );
+        assert_eq!(
+            tree.files(false, true, 0)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(".config/settings.json"),
+                Path::new(".gitignore"),
+                Path::new("src/.generated.rs"),
+                Path::new("src/main.rs"),
+            ]
+        );
+        assert_eq!(
+            tree.files(false, false, 1)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            Vec::<&Path>::new()
+        );
+        assert_eq!(
+            tree.directories(true, false, 0)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![Path::new(""), Path::new("src"), Path::new("target")]
+        );
+        assert_eq!(tree.filtered_file_count(true, false), 1);
+        assert_eq!(tree.filtered_file_count(false, true), 4);
+        assert_eq!(tree.filtered_file_count(false, false), 1);
+    });
+
+    fs.create_file("/root/src/.env".as_ref(), Default::default())
+        .await
+        .unwrap();
+    tree.flush_fs_events(cx).await;
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("src/.env").unwrap().is_hidden);
+        assert_eq!(tree.filtered_file_count(false, false), 1);
+    });
+}
+
+#[gpui::test]
+async fn test_dirs_no_longer_ignored(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "node_modules\n",
+            "a": {
+                "a.js": "",
+            },
+            "b": {
+                "b.js": "",
+            },
+            "node_modules": {
+                "c": {
+                    "c.js": "",
+                },
+                "d": {
+                    "d.js": "",
+                    "e": {
+                        "e1.js": "",
+                        "e2.js": "",
+                    },
+                    "f": {
+                        "f1.js": "",
+                        "f2.js": "",
+                    }
+                },
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // Open a file within the gitignored directory, forcing some of its
+    // subdirectories to be read, but not all.
+    let read_dir_count_1 = fs.read_dir_call_count();
+    tree.read_with(cx, |tree, _| {
+        tree.as_local()
+            .unwrap()
+            .refresh_entries_for_paths(vec![Path::new("node_modules/d/d.js").into()])
+    })
+    .recv()
+    .await;
+
+    // Those subdirectories are now loaded.
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|e| (e.path.as_ref(), e.is_ignored))
+                .collect::<Vec<_>>(),
+            &[
+                (Path::new(""), false),
+                (Path::new(".gitignore"), false),
+                (Path::new("a"), false),
+                (Path::new("a/a.js"), false),
+                (Path::new("b"), false),
+                (Path::new("b/b.js"), false),
+                (Path::new("node_modules"), true),
+                (Path::new("node_modules/c"), true),
+                (Path::new("node_modules/d"), true),
+                (Path::new("node_modules/d/d.js"), true),
+                (Path::new("node_modules/d/e"), true),
+                (Path::new("node_modules/d/f"), true),
+            ]
+        );
+    });
+    let read_dir_count_2 = fs.read_dir_call_count();
+    assert_eq!(read_dir_count_2 - read_dir_count_1, 2);
+
+    // Update the gitignore so that node_modules is no longer ignored,
+    // but a subdirectory is ignored
+    fs.save(
+        "/root/.gitignore".as_ref(),
+        &"e".into(),
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+
+    // All of the directories that are no longer ignored are now loaded.
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|e| (e.path.as_ref(), e.is_ignored))
+                .collect::<Vec<_>>(),
+            &[
+                (Path::new(""), false),
+                (Path::new(".gitignore"), false),
+                (Path::new("a"), false),
+                (Path::new("a/a.js"), false),
+                (Path::new("b"), false),
+                (Path::new("b/b.js"), false),
+                // This directory is no longer ignored
+                (Path::new("node_modules"), false),
+                (Path::new("node_modules/c"), false),
+                (Path::new("node_modules/c/c.js"), false),
+                (Path::new("node_modules/d"), false),
+                (Path::new("node_modules/d/d.js"), false),
+                // This subdirectory is now ignored
+                (Path::new("node_modules/d/e"), true),
+                (Path::new("node_modules/d/f"), false),
+                (Path::new("node_modules/d/f/f1.js"), false),
+                (Path::new("node_modules/d/f/f2.js"), false),
+            ]
+        );
+    });
+
+    // Each of the newly-loaded directories is scanned only once.
+    let read_dir_count_3 = fs.read_dir_call_count();
+    assert_eq!(read_dir_count_3 - read_dir_count_2, 2);
+}
+
+#[gpui::test]
+async fn test_scan_progress(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "a1": "",
+                "a2": "",
+                "b": {
+                    "b1": "",
+                },
+            },
+            "c": {
+                "c1": "",
+                "d": {
+                    "d1": "",
+                },
+            },
+            "e": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let progress = progress.clone();
+        cx.observe(&tree, move |tree, _, _| {
+            progress
+                .lock()
+                .push(tree.as_local().unwrap().scan_progress());
+        })
+        .detach();
+    });
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    let progress = progress.lock();
+    assert!(progress
+        .windows(2)
+        .all(|updates| updates[0].entries_discovered <= updates[1].entries_discovered));
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.as_local().unwrap().scan_progress(),
+            ScanProgress {
+                entries_discovered: 11,
+                directories_queued: 0,
+            }
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_closing_worktree(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "a1": "",
+            },
+            "b": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    assert!(fs.watcher_count() > 0);
+
+    tree.update(cx, |tree, _| tree.as_local_mut().unwrap().close());
+    cx.executor().run_until_parked();
+    assert_eq!(fs.watcher_count(), 0);
+
+    // Changes made after the worktree is closed aren't picked up.
+    fs.insert_file("/root/c", Vec::new()).await;
+    fs.remove_file(Path::new("/root/b"), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.as_local().unwrap().is_closed());
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("a"),
+                Path::new("a/a1"),
+                Path::new("b"),
+            ]
+        );
+    });
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+}
+
+#[gpui::test]
+async fn test_pausing_events(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": "",
+            "b": "",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // The events received while paused are processed on resume.
+    tree.update(cx, |tree, _| tree.as_local_mut().unwrap().pause_events());
+    fs.insert_file("/root/c", Vec::new()).await;
+    fs.remove_file(Path::new("/root/a"), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.as_local().unwrap().are_events_paused());
+        assert!(tree.entry_for_path("a").is_some());
+        assert!(tree.entry_for_path("c").is_none());
+    });
+
+    tree.update(cx, |tree, _| tree.as_local_mut().unwrap().resume_events());
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("a").is_none());
+        assert!(tree.entry_for_path("c").is_some());
+    });
+
+    // When too many events are received while paused, the worktree is rescanned on resume.
+    tree.update(cx, |tree, _| tree.as_local_mut().unwrap().pause_events());
+    let mut files = serde_json::Map::new();
+    for ix in 0..MAX_PAUSED_EVENT_PATHS + 1 {
+        files.insert(format!("file-{ix}"), json!(""));
+    }
+    fs.insert_tree("/root/many", serde_json::Value::Object(files))
+        .await;
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| assert!(tree.entry_for_path("many").is_none()));
+
+    tree.update(cx, |tree, _| tree.as_local_mut().unwrap().resume_events());
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(false)
+                .filter(|entry| entry.path.starts_with("many") && entry.is_file())
+                .count(),
+            MAX_PAUSED_EVENT_PATHS + 1
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_coalescing_events(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/root", json!({ "dir": {} })).await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // A burst of events that keep arriving while the previous ones are processed.
+    let initial_scan_id = tree.read_with(cx, |tree, _| tree.scan_id());
+    fs.pause_events();
+    for ix in 0..20 {
+        fs.insert_file(format!("/root/dir/file-{ix}"), Vec::new())
+            .await;
+        if ix % 2 == 0 {
+            fs.remove_file(
+                Path::new(&format!("/root/dir/file-{ix}")),
+                Default::default(),
+            )
+            .await
+            .unwrap();
+        }
+    }
+    let mut event_batch_count = 0;
+    while fs.buffered_event_count() > 0 {
+        fs.flush_events(1);
+        event_batch_count += 1;
+        cx.executor().simulate_random_delay().await;
+    }
+    cx.executor().run_until_parked();
+
+    // Each processed batch of events is a scan, and the burst was processed in far fewer
+    // batches than it was delivered in.
+    let processed_batch_count = tree.read_with(cx, |tree, _| tree.scan_id()) - initial_scan_id;
+    assert!(
+        processed_batch_count < event_batch_count / 2,
+        "{event_batch_count} batches of events were processed in {processed_batch_count} batches"
+    );
+
+    tree.read_with(cx, |tree, _| {
+        let mut file_names = tree
+            .entries(true)
+            .filter(|entry| entry.is_file())
+            .map(|entry| entry.path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        file_names.sort();
+        let mut expected = (0..20)
+            .filter(|ix| ix % 2 == 1)
+            .map(|ix| format!("dir/file-{ix}"))
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(file_names, expected);
+    });
+}
+
+#[gpui::test]
+async fn test_scanning_profile(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<WorktreeSettings>(cx, |settings| {
+                settings.scanning_profiles = Some(vec![ScanningProfile {
+                    name: "data".into(),
+                    root_paths: vec!["/data".into()],
+                    file_scan_exclusions: vec!["**/*.bin".into()],
+                    max_scan_depth: Some(1),
+                    ..Default::default()
+                }]);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    let tree = json!({
+        "a": {
+            "b": { "c": { "d.csv": "" } },
+            "e.csv": "",
+            "f.bin": "",
+        },
+    });
+    fs.insert_tree("/data", tree.clone()).await;
+    fs.insert_tree("/code", tree).await;
+
+    let data_tree = Worktree::local(
+        build_client(cx),
+        Path::new("/data"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    let code_tree = Worktree::local(
+        build_client(cx),
+        Path::new("/code"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| {
+        futures::future::join(
+            data_tree.read(cx).as_local().unwrap().scan_complete(),
+            code_tree.read(cx).as_local().unwrap().scan_complete(),
+        )
+    })
+    .await;
+
+    // Directories below the profile's maximum depth are loaded once expanded, and the
+    // profile's exclusions apply in addition to the global ones.
+    data_tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.as_local().unwrap().scanning_profile().unwrap().name,
+            "data"
+        );
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("a"),
+                Path::new("a/b"),
+                Path::new("a/e.csv"),
+            ]
+        );
+        assert_eq!(
+            tree.entry_for_path("a/b").unwrap().kind,
+            EntryKind::UnloadedDir
+        );
+    });
+    data_tree
+        .read_with(cx, |tree, _| {
+            tree.as_local()
+                .unwrap()
+                .refresh_entries_for_paths(vec![Path::new("a/b").into()])
+        })
+        .recv()
+        .await;
+    data_tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("a/b/c").is_some());
+    });
+
+    // Worktrees that don't match any profile are scanned as usual.
+    code_tree.read_with(cx, |tree, _| {
+        assert!(tree.as_local().unwrap().scanning_profile().is_none());
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("a"),
+                Path::new("a/b"),
+                Path::new("a/b/c"),
+                Path::new("a/b/c/d.csv"),
+                Path::new("a/e.csv"),
+                Path::new("a/f.bin"),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_symlink_policies(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<WorktreeSettings>(cx, |settings| {
+                settings.scanning_profiles = Some(vec![
+                    ScanningProfile {
+                        name: "follow".into(),
+                        root_paths: vec!["/follow".into()],
+                        symlinks: Some(SymlinkPolicy::Follow),
+                        ..Default::default()
+                    },
+                    ScanningProfile {
+                        name: "leaf".into(),
+                        root_paths: vec!["/leaf".into()],
+                        symlinks: Some(SymlinkPolicy::Leaf),
+                        ..Default::default()
+                    },
+                ]);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree("/outside", json!({ "b.txt": "" })).await;
+    for root in ["/follow", "/leaf", "/within"] {
+        fs.insert_tree(root, json!({ "dir": { "a.txt": "" } }))
+            .await;
+        fs.create_symlink(&Path::new(root).join("link-in"), "dir".into())
+            .await
+            .unwrap();
+        fs.create_symlink(&Path::new(root).join("link-out"), "/outside".into())
+            .await
+            .unwrap();
+    }
+
+    let mut entries_by_root = Vec::new();
+    for root in ["/follow", "/leaf", "/within"] {
+        let tree = Worktree::local(
+            build_client(cx),
+            Path::new(root),
+            true,
+            fs.clone(),
+            Default::default(),
+            &mut cx.to_async(),
+        )
+        .await
+        .unwrap();
+        cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+            .await;
+        entries_by_root.push(tree.read_with(cx, |tree, _| {
+            tree.entries(true)
+                .map(|entry| match entry.kind {
+                    EntryKind::File(_) => entry.path.to_string_lossy().into_owned(),
+                    kind => format!("{} {kind:?}", entry.path.display()),
+                })
+                .collect::<Vec<_>>()
+        }));
+    }
+
+    assert_eq!(
+        entries_by_root,
+        [
+            // Every symlinked directory is scanned.
+            vec![
+                " Dir",
+                "dir Dir",
+                "dir/a.txt",
+                "link-in Dir",
+                "link-in/a.txt",
+                "link-out Dir",
+                "link-out/b.txt",
+            ],
+            // Symlinked directories are shown without their contents.
+            vec![
+                " Dir",
+                "dir Dir",
+                "dir/a.txt",
+                "link-in Dir",
+                "link-out Dir"
+            ],
+            // Only the symlinked directories inside the worktree are scanned.
+            vec![
+                " Dir",
+                "dir Dir",
+                "dir/a.txt",
+                "link-in Dir",
+                "link-in/a.txt",
+                "link-out UnloadedDir",
+            ],
+        ]
+    );
+}
+
+#[gpui::test(iterations = 10)]
+async fn test_rescan_with_gitignore(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<WorktreeSettings>(cx, |project_settings| {
+                project_settings.file_scan_exclusions = Some(Vec::new());
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".gitignore": "ancestor-ignored-file1\nancestor-ignored-file2\n",
+            "tree": {
+                ".git": {},
+                ".gitignore": "ignored-dir\n",
+                "tracked-dir": {
+                    "tracked-file1": "",
+                    "ancestor-ignored-file1": "",
+                },
+                "ignored-dir": {
+                    "ignored-file1": ""
+                }
+            }
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        "/root/tree".as_ref(),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        tree.as_local()
+            .unwrap()
+            .refresh_entries_for_paths(vec![Path::new("ignored-dir").into()])
+    })
+    .recv()
+    .await;
+
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert_entry_git_state(tree, "tracked-dir/tracked-file1", None, false);
+        assert_entry_git_state(tree, "tracked-dir/ancestor-ignored-file1", None, true);
+        assert_entry_git_state(tree, "ignored-dir/ignored-file1", None, true);
+    });
+
+    fs.set_status_for_repo_via_working_copy_change(
+        &Path::new("/root/tree/.git"),
+        &[(Path::new("tracked-dir/tracked-file2"), GitFileStatus::Added)],
+    );
+
+    fs.create_file(
+        "/root/tree/tracked-dir/tracked-file2".as_ref(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    fs.create_file(
+        "/root/tree/tracked-dir/ancestor-ignored-file2".as_ref(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    fs.create_file(
+        "/root/tree/ignored-dir/ignored-file2".as_ref(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    cx.executor().run_until_parked();
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert_entry_git_state(
+            tree,
+            "tracked-dir/tracked-file2",
+            Some(GitFileStatus::Added),
+            false,
         );
+        assert_entry_git_state(tree, "tracked-dir/ancestor-ignored-file2", None, true);
+        assert_entry_git_state(tree, "ignored-dir/ignored-file2", None, true);
+        assert!(tree.entry_for_path(".git").unwrap().is_ignored);
+    });
+}
+
+#[gpui::test]
+async fn test_update_gitignore(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".git": {},
+            ".gitignore": "*.txt\n",
+            "a.xml": "<a></a>",
+            "b.txt": "Some text"
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        "/root".as_ref(),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    tree.read_with(cx, |tree, _| {
+        tree.as_local()
+            .unwrap()
+            .refresh_entries_for_paths(vec![Path::new("").into()])
+    })
+    .recv()
+    .await;
+
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert_entry_git_state(tree, "a.xml", None, false);
+        assert_entry_git_state(tree, "b.txt", None, true);
+    });
+
+    fs.atomic_write("/root/.gitignore".into(), "*.xml".into())
+        .await
+        .unwrap();
+
+    fs.set_status_for_repo_via_working_copy_change(
+        &Path::new("/root/.git"),
+        &[(Path::new("b.txt"), GitFileStatus::Added)],
+    );
+
+    cx.executor().run_until_parked();
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert_entry_git_state(tree, "a.xml", None, true);
+        assert_entry_git_state(tree, "b.txt", Some(GitFileStatus::Added), false);
+    });
+}
+
+#[gpui::test]
+async fn test_update_ancestor_gitignore(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".git": {},
+            ".gitignore": "*.txt\n",
+            "tree": {
+                "a.xml": "<a></a>",
+                "b.txt": "Some text"
+            }
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        "/root/tree".as_ref(),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert!(!tree.entry_for_path("a.xml").unwrap().is_ignored);
+        assert!(tree.entry_for_path("b.txt").unwrap().is_ignored);
+    });
+
+    fs.atomic_write("/root/.gitignore".into(), "*.xml".into())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert!(tree.entry_for_path("a.xml").unwrap().is_ignored);
+        assert!(!tree.entry_for_path("b.txt").unwrap().is_ignored);
+    });
+
+    fs.remove_file("/root/.gitignore".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert!(!tree.entry_for_path("a.xml").unwrap().is_ignored);
+        assert!(!tree.entry_for_path("b.txt").unwrap().is_ignored);
+    });
+}
+
+#[gpui::test]
+async fn test_create_ancestor_gitignore(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".git": {},
+            "tree": {
+                "a.xml": "<a></a>",
+                "b.txt": "Some text"
+            }
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        "/root/tree".as_ref(),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert!(!tree.entry_for_path("a.xml").unwrap().is_ignored);
+        assert!(!tree.entry_for_path("b.txt").unwrap().is_ignored);
+    });
+
+    // An ancestor's `.gitignore` that didn't exist when the worktree was scanned applies too.
+    fs.insert_file("/root/.gitignore", "*.txt\n".into()).await;
+    cx.executor().run_until_parked();
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert!(!tree.entry_for_path("a.xml").unwrap().is_ignored);
+        assert!(tree.entry_for_path("b.txt").unwrap().is_ignored);
+    });
+}
+
+#[gpui::test]
+async fn test_ignores_above_ancestor_repository(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/outer",
+        json!({
+            ".gitignore": "*.txt\n",
+            "repo": {
+                ".git": {},
+                ".gitignore": "*.log\n",
+                "tree": {
+                    "a.log": "",
+                    "b.txt": "",
+                }
+            }
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        "/outer/repo/tree".as_ref(),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
 
-        // Only the newly-expanded directory is scanned.
-        assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 1);
+    // The ignore rules above the repository's root don't apply.
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        let (work_dir, _) = tree.repositories().next().unwrap();
+        assert_eq!(work_dir.as_ref(), Path::new(""));
+        assert!(tree.entry_for_path("a.log").unwrap().is_ignored);
+        assert!(!tree.entry_for_path("b.txt").unwrap().is_ignored);
     });
 
-    // No work happens when files and directories change within an unloaded directory.
-    let prev_fs_call_count = fs.read_dir_call_count() + fs.metadata_call_count();
-    fs.create_dir("/root/one/node_modules/c/lib".as_ref())
-        .await
-        .unwrap();
+    fs.insert_file("/outer/repo/tree/c.txt", Vec::new()).await;
     cx.executor().run_until_parked();
-    assert_eq!(
-        fs.read_dir_call_count() + fs.metadata_call_count() - prev_fs_call_count,
-        0
-    );
+    cx.read(|cx| {
+        assert!(!tree.read(cx).entry_for_path("c.txt").unwrap().is_ignored);
+    });
 }
 
 #[gpui::test]
-async fn test_dirs_no_longer_ignored(cx: &mut TestAppContext) {
+async fn test_revealing_ignored_paths(cx: &mut TestAppContext) {
     init_test(cx);
     let fs = FakeFs::new(cx.background_executor.clone());
     fs.insert_tree(
         "/root",
         json!({
-            ".gitignore": "node_modules\n",
-            "a": {
-                "a.js": "",
-            },
-            "b": {
-                "b.js": "",
-            },
+            ".gitignore": "node_modules\n*.log\n",
+            "a.log": "",
             "node_modules": {
-                "c": {
-                    "c.js": "",
-                },
-                "d": {
-                    "d.js": "",
-                    "e": {
-                        "e1.js": "",
-                        "e2.js": "",
-                    },
-                    "f": {
-                        "f1.js": "",
-                        "f2.js": "",
-                    }
-                },
-            },
+                "pkg": {
+                    "index.js": "",
+                }
+            }
         }),
     )
     .await;
 
     let tree = Worktree::local(
         build_client(cx),
-        Path::new("/root"),
+        "/root".as_ref(),
         true,
         fs.clone(),
         Default::default(),
@@ -600,119 +2460,81 @@ async fn test_dirs_no_longer_ignored(cx: &mut TestAppContext) {
     )
     .await
     .unwrap();
-
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert!(tree.entry_for_path("node_modules").unwrap().is_ignored);
+        assert!(tree.entry_for_path("node_modules/pkg").is_none());
+    });
 
-    // Open a file within the gitignored directory, forcing some of its
-    // subdirectories to be read, but not all.
-    let read_dir_count_1 = fs.read_dir_call_count();
-    tree.read_with(cx, |tree, _| {
-        tree.as_local()
+    // A revealed directory is scanned like the rest of the worktree, and kept up to date.
+    tree.update(cx, |tree, _| {
+        tree.as_local_mut()
             .unwrap()
-            .refresh_entries_for_paths(vec![Path::new("node_modules/d/d.js").into()])
+            .reveal_ignored_path(Path::new("node_modules").into())
     })
     .recv()
     .await;
-
-    // Those subdirectories are now loaded.
-    tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(true)
-                .map(|e| (e.path.as_ref(), e.is_ignored))
-                .collect::<Vec<_>>(),
-            &[
-                (Path::new(""), false),
-                (Path::new(".gitignore"), false),
-                (Path::new("a"), false),
-                (Path::new("a/a.js"), false),
-                (Path::new("b"), false),
-                (Path::new("b/b.js"), false),
-                (Path::new("node_modules"), true),
-                (Path::new("node_modules/c"), true),
-                (Path::new("node_modules/d"), true),
-                (Path::new("node_modules/d/d.js"), true),
-                (Path::new("node_modules/d/e"), true),
-                (Path::new("node_modules/d/f"), true),
-            ]
-        );
+    fs.insert_file("/root/node_modules/pkg/lib.js", Vec::new())
+        .await;
+    cx.executor().run_until_parked();
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert!(tree
+            .as_local()
+            .unwrap()
+            .is_path_revealed(Path::new("node_modules")));
+        assert!(tree.entry_for_path("a.log").unwrap().is_ignored);
+        for path in [
+            "node_modules",
+            "node_modules/pkg/index.js",
+            "node_modules/pkg/lib.js",
+        ] {
+            assert!(!tree.entry_for_path(path).unwrap().is_ignored, "{path}");
+        }
+        assert!(tree
+            .entries(false)
+            .any(|entry| entry.path.as_ref() == Path::new("node_modules/pkg/index.js")));
     });
-    let read_dir_count_2 = fs.read_dir_call_count();
-    assert_eq!(read_dir_count_2 - read_dir_count_1, 2);
 
-    // Update the gitignore so that node_modules is no longer ignored,
-    // but a subdirectory is ignored
-    fs.save("/root/.gitignore".as_ref(), &"e".into(), Default::default())
-        .await
-        .unwrap();
+    tree.update(cx, |tree, _| {
+        tree.as_local_mut()
+            .unwrap()
+            .unreveal_ignored_path(Path::new("node_modules").into())
+    })
+    .recv()
+    .await;
     cx.executor().run_until_parked();
-
-    // All of the directories that are no longer ignored are now loaded.
-    tree.read_with(cx, |tree, _| {
-        assert_eq!(
-            tree.entries(true)
-                .map(|e| (e.path.as_ref(), e.is_ignored))
-                .collect::<Vec<_>>(),
-            &[
-                (Path::new(""), false),
-                (Path::new(".gitignore"), false),
-                (Path::new("a"), false),
-                (Path::new("a/a.js"), false),
-                (Path::new("b"), false),
-                (Path::new("b/b.js"), false),
-                // This directory is no longer ignored
-                (Path::new("node_modules"), false),
-                (Path::new("node_modules/c"), false),
-                (Path::new("node_modules/c/c.js"), false),
-                (Path::new("node_modules/d"), false),
-                (Path::new("node_modules/d/d.js"), false),
-                // This subdirectory is now ignored
-                (Path::new("node_modules/d/e"), true),
-                (Path::new("node_modules/d/f"), false),
-                (Path::new("node_modules/d/f/f1.js"), false),
-                (Path::new("node_modules/d/f/f2.js"), false),
-            ]
-        );
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        assert!(tree.entry_for_path("node_modules").unwrap().is_ignored);
+        assert!(tree
+            .entry_for_path("node_modules/pkg/index.js")
+            .map_or(true, |entry| entry.is_ignored));
     });
-
-    // Each of the newly-loaded directories is scanned only once.
-    let read_dir_count_3 = fs.read_dir_call_count();
-    assert_eq!(read_dir_count_3 - read_dir_count_2, 2);
 }
 
-#[gpui::test(iterations = 10)]
-async fn test_rescan_with_gitignore(cx: &mut TestAppContext) {
+#[gpui::test]
+async fn test_apply_file_operations(cx: &mut TestAppContext) {
     init_test(cx);
-    cx.update(|cx| {
-        cx.update_global::<SettingsStore, _>(|store, cx| {
-            store.update_user_settings::<WorktreeSettings>(cx, |project_settings| {
-                project_settings.file_scan_exclusions = Some(Vec::new());
-            });
-        });
-    });
     let fs = FakeFs::new(cx.background_executor.clone());
     fs.insert_tree(
         "/root",
         json!({
-            ".gitignore": "ancestor-ignored-file1\nancestor-ignored-file2\n",
-            "tree": {
-                ".git": {},
-                ".gitignore": "ignored-dir\n",
-                "tracked-dir": {
-                    "tracked-file1": "",
-                    "ancestor-ignored-file1": "",
-                },
-                "ignored-dir": {
-                    "ignored-file1": ""
-                }
-            }
+            "a": {
+                "one.txt": "one",
+                "two.txt": "two",
+            },
+            "b": {},
+            "c.txt": "c",
         }),
     )
     .await;
 
     let tree = Worktree::local(
         build_client(cx),
-        "/root/tree".as_ref(),
+        "/root".as_ref(),
         true,
         fs.clone(),
         Default::default(),
@@ -723,71 +2545,184 @@ async fn test_rescan_with_gitignore(cx: &mut TestAppContext) {
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
 
-    tree.read_with(cx, |tree, _| {
-        tree.as_local()
-            .unwrap()
-            .refresh_entries_for_paths(vec![Path::new("ignored-dir").into()])
-    })
-    .recv()
-    .await;
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let progress = progress.clone();
+        cx.subscribe(&tree, move |_, _, event, _| {
+            if let Event::FileOperationsProgress(update) = event {
+                progress.lock().push((update.completed, update.total));
+            }
+        })
+        .detach();
+    });
+    let one_id = tree.read_with(cx, |tree, _| tree.entry_for_path("a/one.txt").unwrap().id);
 
-    cx.read(|cx| {
-        let tree = tree.read(cx);
-        assert_entry_git_state(tree, "tracked-dir/tracked-file1", None, false);
-        assert_entry_git_state(tree, "tracked-dir/ancestor-ignored-file1", None, true);
-        assert_entry_git_state(tree, "ignored-dir/ignored-file1", None, true);
+    let path = |path: &str| -> Arc<Path> { Path::new(path).into() };
+    let outcome = tree
+        .update(cx, |tree, cx| {
+            tree.as_local().unwrap().apply_file_operations(
+                vec![
+                    FileOperation::Move {
+                        old_path: path("a/one.txt"),
+                        new_path: path("b/one.txt"),
+                    },
+                    FileOperation::Move {
+                        old_path: path("a/missing.txt"),
+                        new_path: path("b/missing.txt"),
+                    },
+                    FileOperation::Copy {
+                        old_path: path("c.txt"),
+                        new_path: path("b/c.txt"),
+                    },
+                    FileOperation::Delete {
+                        path: path("a/two.txt"),
+                        trash: false,
+                    },
+                ],
+                cx,
+            )
+        })
+        .await;
+
+    // A failed operation doesn't stop the ones after it.
+    assert_eq!(
+        outcome
+            .failed
+            .iter()
+            .map(|(operation, _)| operation.clone())
+            .collect::<Vec<_>>(),
+        vec![FileOperation::Move {
+            old_path: path("a/missing.txt"),
+            new_path: path("b/missing.txt"),
+        }]
+    );
+    assert_eq!(*progress.lock(), vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("a"),
+                Path::new("b"),
+                Path::new("b/c.txt"),
+                Path::new("b/one.txt"),
+                Path::new("c.txt"),
+            ]
+        );
+        assert_eq!(tree.entry_for_path("b/one.txt").unwrap().id, one_id);
     });
 
-    fs.set_status_for_repo_via_working_copy_change(
-        &Path::new("/root/tree/.git"),
-        &[(Path::new("tracked-dir/tracked-file2"), GitFileStatus::Added)],
+    // The deletion can't be undone, but the other applied operations can.
+    assert_eq!(outcome.irreversible_count, 1);
+    assert_eq!(
+        outcome.undo_operations,
+        vec![
+            FileOperation::Delete {
+                path: path("b/c.txt"),
+                trash: true,
+            },
+            FileOperation::Move {
+                old_path: path("b/one.txt"),
+                new_path: path("a/one.txt"),
+            },
+        ]
     );
+    let undo_outcome = tree
+        .update(cx, |tree, cx| {
+            tree.as_local()
+                .unwrap()
+                .apply_file_operations(outcome.undo_operations, cx)
+        })
+        .await;
+    assert!(undo_outcome.failed.is_empty());
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("a"),
+                Path::new("a/one.txt"),
+                Path::new("b"),
+                Path::new("c.txt"),
+            ]
+        );
+    });
+}
 
-    fs.create_file(
-        "/root/tree/tracked-dir/tracked-file2".as_ref(),
-        Default::default(),
-    )
-    .await
-    .unwrap();
-    fs.create_file(
-        "/root/tree/tracked-dir/ancestor-ignored-file2".as_ref(),
-        Default::default(),
+#[gpui::test]
+async fn test_unreadable_directories(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "one.txt": "",
+            },
+            "b": {
+                "two.txt": "",
+            },
+        }),
     )
-    .await
-    .unwrap();
-    fs.create_file(
-        "/root/tree/ignored-dir/ignored-file2".as_ref(),
+    .await;
+    fs.set_dir_readable("/root/a", false);
+
+    let tree = Worktree::local(
+        build_client(cx),
+        "/root".as_ref(),
+        true,
+        fs.clone(),
         Default::default(),
+        &mut cx.to_async(),
     )
     .await
     .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
 
-    cx.executor().run_until_parked();
-    cx.read(|cx| {
-        let tree = tree.read(cx);
-        assert_entry_git_state(
-            tree,
-            "tracked-dir/tracked-file2",
-            Some(GitFileStatus::Added),
-            false,
+    // The unreadable directory is kept without its contents, and the rest of the tree is
+    // scanned.
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("a"),
+                Path::new("b"),
+                Path::new("b/two.txt"),
+            ]
         );
-        assert_entry_git_state(tree, "tracked-dir/ancestor-ignored-file2", None, true);
-        assert_entry_git_state(tree, "ignored-dir/ignored-file2", None, true);
-        assert!(tree.entry_for_path(".git").unwrap().is_ignored);
+        assert_eq!(tree.entry_for_path("a").unwrap().kind, EntryKind::Dir);
+        let errors = tree.as_local().unwrap().scan_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0.as_ref(), Path::new("a"));
+        assert_eq!(errors[0].1.kind, ScanErrorKind::PermissionDenied);
+    });
+
+    // The directory is scanned again once it's readable.
+    fs.set_dir_readable("/root/a", true);
+    cx.executor().run_until_parked();
+    tree.read_with(cx, |tree, _| {
+        assert!(tree.entry_for_path("a/one.txt").is_some());
+        assert!(tree.entry_for_path("a").unwrap().scan_error.is_none());
+        assert!(tree.as_local().unwrap().scan_errors().is_empty());
     });
 }
 
 #[gpui::test]
-async fn test_update_gitignore(cx: &mut TestAppContext) {
+async fn test_replacing_file_by_rename(cx: &mut TestAppContext) {
     init_test(cx);
     let fs = FakeFs::new(cx.background_executor.clone());
     fs.insert_tree(
         "/root",
         json!({
-            ".git": {},
-            ".gitignore": "*.txt\n",
-            "a.xml": "<a></a>",
-            "b.txt": "Some text"
+            "a.txt": "old",
         }),
     )
     .await;
@@ -805,34 +2740,61 @@ async fn test_update_gitignore(cx: &mut TestAppContext) {
     cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
         .await;
 
-    tree.read_with(cx, |tree, _| {
-        tree.as_local()
-            .unwrap()
-            .refresh_entries_for_paths(vec![Path::new("").into()])
-    })
-    .recv()
-    .await;
-
-    cx.read(|cx| {
-        let tree = tree.read(cx);
-        assert_entry_git_state(tree, "a.xml", None, false);
-        assert_entry_git_state(tree, "b.txt", None, true);
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    tree.update(cx, |_, cx| {
+        let changes = changes.clone();
+        cx.subscribe(&tree, move |_, _, event, _| {
+            if let Event::UpdatedEntries(update) = event {
+                changes.lock().extend(
+                    update
+                        .iter()
+                        .map(|(path, _, change)| (path.clone(), *change)),
+                );
+            }
+        })
+        .detach();
+    });
+    let (old_id, old_inode) = tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path("a.txt").unwrap();
+        (entry.id, entry.inode)
     });
 
-    fs.atomic_write("/root/.gitignore".into(), "*.xml".into())
-        .await
-        .unwrap();
+    // Write the new contents to a temporary file that's scanned before it replaces the file.
+    fs.insert_file("/root/a.txt.tmp", b"new".to_vec()).await;
+    cx.executor().run_until_parked();
+    fs.rename(
+        "/root/a.txt.tmp".as_ref(),
+        "/root/a.txt".as_ref(),
+        fs::RenameOptions {
+            overwrite: true,
+            ignore_if_exists: false,
+        },
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
 
-    fs.set_status_for_repo_via_working_copy_change(
-        &Path::new("/root/.git"),
-        &[(Path::new("b.txt"), GitFileStatus::Added)],
-    );
+    // The file keeps its id.
+    tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path("a.txt").unwrap();
+        assert_eq!(entry.id, old_id);
+        assert_ne!(entry.inode, old_inode);
+        assert!(tree.entry_for_path("a.txt.tmp").is_none());
+    });
+    assert!(!changes.lock().iter().any(
+        |(path, change)| path.as_ref() == Path::new("a.txt") && *change == PathChange::Removed
+    ));
 
+    // A new file that reuses the replaced file's inode is a different file.
+    fs.insert_file_with_inode("/root/b.txt", Vec::new(), old_inode)
+        .await;
     cx.executor().run_until_parked();
-    cx.read(|cx| {
-        let tree = tree.read(cx);
-        assert_entry_git_state(tree, "a.xml", None, true);
-        assert_entry_git_state(tree, "b.txt", Some(GitFileStatus::Added), false);
+    tree.read_with(cx, |tree, _| {
+        let a_entry = tree.entry_for_path("a.txt").unwrap();
+        let b_entry = tree.entry_for_path("b.txt").unwrap();
+        assert_eq!(a_entry.id, old_id);
+        assert_eq!(b_entry.inode, old_inode);
+        assert_ne!(b_entry.id, old_id);
     });
 }
 
@@ -866,6 +2828,7 @@ async fn test_write_file(cx: &mut TestAppContext) {
             Path::new("tracked-dir/file.txt"),
             "hello".into(),
             Default::default(),
+            Durability::Buffered,
             cx,
         )
     })
@@ -876,6 +2839,7 @@ async fn test_write_file(cx: &mut TestAppContext) {
             Path::new("ignored-dir/file.txt"),
             "world".into(),
             Default::default(),
+            Durability::Buffered,
             cx,
         )
     })
@@ -987,6 +2951,56 @@ async fn test_file_scan_exclusions(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_extra_file_scan_exclusions(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+    let dir = temp_tree(json!({
+        "target": {
+            "debug": {
+                "app": "",
+            },
+        },
+        "src": {
+            "main.rs": "fn main() {}",
+            "main.o": "",
+        },
+        ".DS_Store": "",
+    }));
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<WorktreeSettings>(cx, |project_settings| {
+                project_settings.extra_file_scan_exclusions =
+                    Some(vec!["**/target".to_string(), "**/*.o".to_string()]);
+            });
+        });
+    });
+
+    let tree = Worktree::local(
+        build_client(cx),
+        dir.path(),
+        true,
+        Arc::new(RealFs::default()),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    tree.flush_fs_events(cx).await;
+
+    // The extra exclusions apply on top of the default ones.
+    tree.read_with(cx, |tree, _| {
+        check_worktree_entries(
+            tree,
+            &["target", "target/debug/app", "src/main.o", ".DS_Store"],
+            &[],
+            &["src/main.rs"],
+        )
+    });
+}
+
 #[gpui::test]
 async fn test_fs_events_in_exclusions(cx: &mut TestAppContext) {
     init_test(cx);
@@ -1227,6 +3241,139 @@ async fn test_create_directory_during_initial_scan(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_create_file(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "existing.txt": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        "/root".as_ref(),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // The new entry is in the snapshot without waiting for the file system's events.
+    fs.pause_events();
+    let file = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .create_file("a".as_ref(), "new.txt", cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(file.path.as_ref(), Path::new("a/new.txt"));
+    tree.read_with(cx, |tree, _| {
+        let entry = tree.entry_for_path("a/new.txt").unwrap();
+        assert!(entry.is_file());
+        assert_eq!(file.entry_id, Some(entry.id));
+    });
+    fs.flush_events(fs.buffered_event_count());
+    assert_eq!(fs.load("/root/a/new.txt".as_ref()).await.unwrap(), "");
+
+    for (parent_dir, name) in [
+        ("a", "existing.txt"),
+        ("a", "b/c.txt"),
+        ("a", ".."),
+        ("a/existing.txt", "d.txt"),
+        ("missing", "d.txt"),
+    ] {
+        let result = tree
+            .update(cx, |tree, cx| {
+                tree.as_local_mut()
+                    .unwrap()
+                    .create_file(parent_dir.as_ref(), name, cx)
+            })
+            .await;
+        assert!(result.is_err(), "created {name:?} in {parent_dir:?}");
+    }
+}
+
+#[gpui::test]
+async fn test_create_dir(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a": {
+                "file.txt": "",
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        "/root".as_ref(),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // The new entries are in the snapshot without waiting for the file system's events.
+    fs.pause_events();
+    let inodes = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .create_dir(Path::new("a/b/c/d"), cx)
+        })
+        .await
+        .unwrap();
+    tree.read_with(cx, |tree, _| {
+        let new_dirs = ["a/b", "a/b/c", "a/b/c/d"].map(|path| {
+            let entry = tree.entry_for_path(path).unwrap();
+            assert!(entry.is_dir());
+            entry.inode
+        });
+        assert_eq!(inodes, new_dirs);
+    });
+    fs.flush_events(fs.buffered_event_count());
+    assert!(fs.is_dir("/root/a/b/c/d".as_ref()).await);
+
+    // Creating a directory that exists already creates nothing.
+    let inodes = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .create_dir(Path::new("a/b"), cx)
+        })
+        .await
+        .unwrap();
+    assert!(inodes.is_empty());
+
+    let result = tree
+        .update(cx, |tree, cx| {
+            tree.as_local_mut()
+                .unwrap()
+                .create_dir(Path::new("a/file.txt/e"), cx)
+        })
+        .await;
+    assert!(result.is_err());
+}
+
 #[gpui::test]
 async fn test_create_dir_all_on_create_entry(cx: &mut TestAppContext) {
     init_test(cx);
@@ -1693,8 +3840,13 @@ fn randomly_mutate_worktree(
                 })
             } else {
                 log::info!("overwriting file {:?} ({})", entry.path, entry.id.0);
-                let task =
-                    worktree.write_file(entry.path.clone(), "".into(), Default::default(), cx);
+                let task = worktree.write_file(
+                    entry.path.clone(),
+                    "".into(),
+                    Default::default(),
+                    Durability::Buffered,
+                    cx,
+                );
                 cx.background_executor().spawn(async move {
                     task.await?;
                     Ok(())
@@ -1785,6 +3937,7 @@ async fn randomly_mutate_fs(
             &ignore_path,
             &ignore_contents.as_str().into(),
             Default::default(),
+            Default::default(),
         )
         .await
         .unwrap();
@@ -1943,6 +4096,68 @@ async fn test_rename_work_directory(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_rename_work_directory_with_fake_fs(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "projects": {
+                "project1": {
+                    ".git": {},
+                    "a": "",
+                    "b": "",
+                }
+            },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        build_client(cx),
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.read(|cx| {
+        let (work_dir, _) = tree.read(cx).repositories().next().unwrap();
+        assert_eq!(work_dir.as_ref(), Path::new("projects/project1"));
+    });
+
+    // The rename races with the removal of one of the directory's files.
+    fs.pause_events();
+    fs.rename(
+        Path::new("/root/projects/project1"),
+        Path::new("/root/projects/project2"),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    fs.remove_file(Path::new("/root/projects/project2/b"), Default::default())
+        .await
+        .unwrap();
+    fs.flush_events(1);
+    cx.executor().run_until_parked();
+    fs.flush_events(fs.buffered_event_count());
+    cx.executor().run_until_parked();
+
+    cx.read(|cx| {
+        let tree = tree.read(cx);
+        let (work_dir, _) = tree.repositories().next().unwrap();
+        assert_eq!(work_dir.as_ref(), Path::new("projects/project2"));
+        assert!(tree.entry_for_path("projects/project1").is_none());
+        assert!(tree.entry_for_path("projects/project2/a").is_some());
+        assert!(tree.entry_for_path("projects/project2/b").is_none());
+    });
+}
+
 #[gpui::test]
 async fn test_git_repository_for_path(cx: &mut TestAppContext) {
     init_test(cx);
@@ -2003,7 +4218,7 @@ async fn test_git_repository_for_path(cx: &mut TestAppContext) {
             Some(Path::new("dir1/deps/dep1").to_owned())
         );
 
-        let entries = tree.files(false, 0);
+        let entries = tree.files(false, true, 0);
 
         let paths_with_repos = tree
             .entries_with_repositories(entries)
@@ -18,7 +18,8 @@ use fs::RealFs;
 use futures::{future, StreamExt};
 use git::GitHostingProviderRegistry;
 use gpui::{
-    App, AppContext, AsyncAppContext, Context, Global, Task, UpdateGlobal as _, VisualContext,
+    App, AppContext, AsyncAppContext, Context, Global, PromptLevel, Task, UpdateGlobal as _,
+    VisualContext,
 };
 use image_viewer;
 use language::LanguageRegistry;
@@ -43,7 +44,7 @@ use theme::{ActiveTheme, SystemAppearance, ThemeRegistry, ThemeSettings};
 use util::{maybe, parse_env_output, paths, with_clone, ResultExt, TryFutureExt};
 use uuid::Uuid;
 use welcome::{show_welcome_view, BaseKeymap, FIRST_OPEN};
-use workspace::{AppState, WorkspaceSettings, WorkspaceStore};
+use workspace::{AppState, LocalPaths, WorkspaceSettings, WorkspaceStore};
 use zed::{
     app_menus, build_window_options, handle_cli_connection, handle_keymap_file_changes,
     initialize_workspace, open_paths_with_positions, OpenListener, OpenRequest,
@@ -135,6 +136,8 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     editor::init(cx);
     image_viewer::init(cx);
     diagnostics::init(cx);
+    diff_view::init(cx);
+    git_commit::init(cx);
 
     audio::init(Assets, cx);
     workspace::init(app_state.clone(), cx);
@@ -154,7 +157,14 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
 
     journal::init(app_state.clone(), cx);
     language_selector::init(cx);
+    mark_selector::init(cx);
+    recent_edit_selector::init(cx);
     theme_selector::init(cx);
+    todos::init(cx);
+    call_hierarchy::init(cx);
+    test_explorer::init(cx);
+    rest_client::init(cx);
+    repl::init(cx);
     language_tools::init(cx);
     call::init(app_state.client.clone(), app_state.user_store.clone(), cx);
     notifications::init(app_state.client.clone(), app_state.user_store.clone(), cx);
@@ -223,6 +233,12 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     load_user_themes_in_background(fs.clone(), cx);
     watch_themes(fs.clone(), cx);
     watch_languages(fs.clone(), app_state.languages.clone(), cx);
+    languages::user_languages::watch(
+        paths::USER_LANGUAGES_DIR.clone(),
+        fs.clone(),
+        app_state.languages.clone(),
+        cx,
+    );
     watch_file_types(fs.clone(), cx);
 
     cx.set_menus(app_menus());
@@ -275,6 +291,12 @@ fn main() {
             return;
         }
     }
+    let last_session_id = app
+        .background_executor()
+        .block(last_session_id(session_id.clone()))
+        .log_err()
+        .flatten();
+    let exited_abnormally = acquire_session_lock(&session_id);
 
     let git_hosting_provider_registry = Arc::new(GitHostingProviderRegistry::new());
     let git_binary_path = if option_env!("ZED_BUNDLE").as_deref() == Some("true") {
@@ -319,7 +341,7 @@ fn main() {
         {
             cx.spawn({
                 let app_state = app_state.clone();
-                |cx| async move { restore_or_create_workspace(app_state, cx).await }
+                |cx| async move { restore_or_create_workspace(app_state, None, false, cx).await }
             })
             .detach();
         }
@@ -331,15 +353,19 @@ fn main() {
             AppCommitSha::set_global(AppCommitSha(build_sha.into()), cx);
         }
 
-        GitHostingProviderRegistry::set_global(git_hosting_provider_registry, cx);
-        git_hosting_providers::init(cx);
-
         OpenListener::set_global(cx, open_listener.clone());
+        cx.on_app_quit(|_| async {
+            std::fs::remove_file(&*paths::SESSION_LOCK).log_err();
+        })
+        .detach();
 
         settings::init(cx);
         handle_settings_file_changes(user_settings_file_rx, cx);
         handle_keymap_file_changes(user_keymap_file_rx, cx);
 
+        GitHostingProviderRegistry::set_global(git_hosting_provider_registry, cx);
+        git_hosting_providers::init(cx);
+
         client::init_settings(cx);
         let client = Client::production(cx);
         cx.update_http_client(client.http_client().clone());
@@ -358,9 +384,11 @@ fn main() {
 
         zed::init(cx);
         project::Project::init(&client, cx);
+        observe_watcher_backend(fs.clone(), cx);
         client::init(&client, cx);
         language::init(cx);
         let telemetry = client.telemetry();
+        workspace::set_session_id(session_id.clone(), cx);
         telemetry.start(installation_id.clone(), session_id, cx);
         telemetry.report_app_event(
             match existing_installation_id_found {
@@ -402,10 +430,21 @@ fn main() {
             .and_then(|urls| OpenRequest::parse(urls, cx).log_err())
         {
             Some(request) => {
-                handle_open_request(request, app_state.clone(), cx);
+                // Backups are only restored along with the session that crashed, so they're
+                // discarded before opened workspaces can restore them.
+                let app_state = app_state.clone();
+                cx.spawn(|cx| async move {
+                    editor::discard_buffer_backups().await.log_err();
+                    cx.update(|cx| handle_open_request(request, app_state, cx))
+                        .log_err();
+                })
+                .detach();
             }
             None => {
                 if let Some(dev_server_token) = args.dev_server_token {
+                    cx.background_executor()
+                        .spawn(async { editor::discard_buffer_backups().await.log_err() })
+                        .detach();
                     let task =
                         init_headless(DevServerToken(dev_server_token), app_state.clone(), cx);
                     cx.spawn(|cx| async move {
@@ -421,7 +460,15 @@ fn main() {
                     init_ui(app_state.clone(), cx).unwrap();
                     cx.spawn({
                         let app_state = app_state.clone();
-                        |cx| async move { restore_or_create_workspace(app_state, cx).await }
+                        |cx| async move {
+                            restore_or_create_workspace(
+                                app_state,
+                                last_session_id,
+                                exited_abnormally,
+                                cx,
+                            )
+                            .await
+                        }
                     })
                     .detach();
                 }
@@ -562,17 +609,80 @@ async fn installation_id() -> Result<(String, bool)> {
     Ok((installation_id, false))
 }
 
-async fn restore_or_create_workspace(app_state: Arc<AppState>, cx: AsyncAppContext) {
+/// Records the id of this run of the app, returning the id of the previous run.
+async fn last_session_id(session_id: String) -> Result<Option<String>> {
+    let key_name = "session_id".to_string();
+    let last_session_id = KEY_VALUE_STORE.read_kvp(&key_name)?;
+    KEY_VALUE_STORE.write_kvp(key_name, session_id).await?;
+    Ok(last_session_id)
+}
+
+/// Creates the lock file that's removed when the app quits, returning whether the previous run
+/// of the app left its lock file behind because it didn't quit normally.
+fn acquire_session_lock(session_id: &str) -> bool {
+    let exited_abnormally = paths::SESSION_LOCK.exists();
+    std::fs::write(&*paths::SESSION_LOCK, session_id).log_err();
+    exited_abnormally
+}
+
+async fn restore_or_create_workspace(
+    app_state: Arc<AppState>,
+    last_session_id: Option<String>,
+    exited_abnormally: bool,
+    mut cx: AsyncAppContext,
+) {
     maybe!(async {
-        let restore_behaviour =
-            cx.update(|cx| WorkspaceSettings::get(None, cx).restore_on_startup)?;
+        let (restore_behaviour, restore_after_crash) = cx.update(|cx| {
+            let settings = WorkspaceSettings::get(None, cx);
+            (settings.restore_on_startup, settings.restore_after_crash)
+        })?;
+        if exited_abnormally && restore_after_crash {
+            let crashed_session_locations = last_session_id
+                .as_deref()
+                .map(workspace::last_session_workspace_paths)
+                .unwrap_or_default();
+            if !crashed_session_locations.is_empty() {
+                return restore_crashed_session(app_state, crashed_session_locations, &mut cx)
+                    .await;
+            }
+        }
+        // Backups are only restored along with the session that crashed.
+        editor::discard_buffer_backups().await.log_err();
+
+        let session_locations = match (restore_behaviour, last_session_id) {
+            (workspace::RestoreOnStartupBehaviour::LastSession, Some(last_session_id)) => {
+                workspace::last_session_workspace_paths(&last_session_id)
+            }
+            _ => Vec::new(),
+        };
+        // Without windows to restore from the last session, the last workspace is restored.
         let location = match restore_behaviour {
-            workspace::RestoreOnStartupBehaviour::LastWorkspace => {
+            workspace::RestoreOnStartupBehaviour::LastWorkspace
+            | workspace::RestoreOnStartupBehaviour::LastSession
+                if session_locations.is_empty() =>
+            {
                 workspace::last_opened_workspace_paths().await
             }
             _ => None,
         };
-        if let Some(location) = location {
+        if !session_locations.is_empty() {
+            // Each window is opened with the bounds it was saved with.
+            for location in session_locations {
+                cx.update(|cx| {
+                    workspace::open_paths(
+                        location.paths().as_ref(),
+                        app_state.clone(),
+                        workspace::OpenOptions {
+                            open_new_workspace: Some(true),
+                            ..Default::default()
+                        },
+                        cx,
+                    )
+                })?
+                .await
+                .log_err();
+            }
+        } else if let Some(location) = location {
             cx.update(|cx| {
                 workspace::open_paths(
                     location.paths().as_ref(),
@@ -599,6 +709,62 @@ async fn restore_or_create_workspace(app_state: Arc<AppState>, cx: AsyncAppConte
     .log_err();
 }
 
+/// Asks whether to restore the windows of a session that didn't quit normally, which reopen with
+/// the layout they were last saved with and the unsaved changes of their editors.
+async fn restore_crashed_session(
+    app_state: Arc<AppState>,
+    locations: Vec<LocalPaths>,
+    cx: &mut AsyncAppContext,
+) -> Result<()> {
+    let (window, _) = cx
+        .update(|cx| {
+            workspace::open_paths(
+                &[],
+                app_state.clone(),
+                workspace::OpenOptions {
+                    open_new_workspace: Some(true),
+                    ..Default::default()
+                },
+                cx,
+            )
+        })?
+        .await?;
+    let answer = window.update(cx, |_, cx| {
+        cx.prompt(
+            PromptLevel::Warning,
+            "Restore previous session?",
+            Some("Zed didn't quit normally. Its windows and unsaved changes can be restored."),
+            &["Restore", "Discard"],
+        )
+    })?;
+    if answer.await? != 0 {
+        editor::discard_buffer_backups().await.log_err();
+        window.update(cx, |workspace, cx| {
+            Editor::new_file(workspace, &Default::default(), cx)
+        })?;
+        return Ok(());
+    }
+
+    // The first window replaces the one that asked.
+    let mut replace_window = Some(window);
+    for location in locations {
+        cx.update(|cx| {
+            workspace::open_paths(
+                location.paths().as_ref(),
+                app_state.clone(),
+                workspace::OpenOptions {
+                    open_new_workspace: Some(true),
+                    replace_window: replace_window.take(),
+                },
+                cx,
+            )
+        })?
+        .await
+        .log_err();
+    }
+    Ok(())
+}
+
 fn init_paths() -> anyhow::Result<()> {
     for path in [
         &*util::paths::CONFIG_DIR,
@@ -877,6 +1043,19 @@ fn load_user_themes_in_background(fs: Arc<dyn fs::Fs>, cx: &mut AppContext) {
     .detach_and_log_err(cx);
 }
 
+/// Keeps the file system's watcher configuration in sync with the `file_watcher` setting.
+fn observe_watcher_backend(fs: Arc<RealFs>, cx: &mut AppContext) {
+    fn apply(fs: &RealFs, cx: &AppContext) {
+        let settings = project::WorktreeSettings::get_global(cx);
+        fs.set_watcher_backend(settings.watcher_backend());
+        fs.set_poll_interval(settings.poll_interval());
+    }
+
+    apply(&fs, cx);
+    cx.observe_global::<SettingsStore>(move |cx| apply(&fs, cx))
+        .detach();
+}
+
 /// Spawns a background task to watch the themes directory for changes.
 fn watch_themes(fs: Arc<dyn fs::Fs>, cx: &mut AppContext) {
     use std::time::Duration;
@@ -67,6 +67,7 @@ actions!(
         OpenLocalTasks,
         OpenTasks,
         OpenTelemetryLog,
+        OpenWorktreeStats,
         ResetBufferFontSize,
         ResetDatabase,
         ShowAll,
@@ -196,6 +197,10 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 workspace_handle.clone(),
                 cx.clone(),
             );
+            let call_hierarchy_panel =
+                call_hierarchy::CallHierarchyPanel::load(workspace_handle.clone(), cx.clone());
+            let test_explorer_panel =
+                test_explorer::TestExplorerPanel::load(workspace_handle.clone(), cx.clone());
 
             let (
                 project_panel,
@@ -204,6 +209,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 channels_panel,
                 chat_panel,
                 notification_panel,
+                call_hierarchy_panel,
+                test_explorer_panel,
             ) = futures::try_join!(
                 project_panel,
                 terminal_panel,
@@ -211,6 +218,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 channels_panel,
                 chat_panel,
                 notification_panel,
+                call_hierarchy_panel,
+                test_explorer_panel,
             )?;
 
             workspace_handle.update(&mut cx, |workspace, cx| {
@@ -238,6 +247,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 workspace.add_panel(channels_panel, cx);
                 workspace.add_panel(chat_panel, cx);
                 workspace.add_panel(notification_panel, cx);
+                workspace.add_panel(call_hierarchy_panel, cx);
+                workspace.add_panel(test_explorer_panel, cx);
                 cx.focus_self();
             })
         })
@@ -334,6 +345,7 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                     open_telemetry_log_file(workspace, cx);
                 },
             )
+            .register_action(open_worktree_stats)
             .register_action(
                 move |_: &mut Workspace, _: &OpenKeymap, cx: &mut ViewContext<Workspace>| {
                     open_settings_file(&paths::KEYMAP, Rope::default, cx);
@@ -843,6 +855,65 @@ fn open_telemetry_log_file(workspace: &mut Workspace, cx: &mut ViewContext<Works
     }).detach();
 }
 
+/// Opens a buffer describing the entries of each of the project's worktrees, which helps to
+/// diagnose slow scans.
+fn open_worktree_stats(
+    workspace: &mut Workspace,
+    _: &OpenWorktreeStats,
+    cx: &mut ViewContext<Workspace>,
+) {
+    const LARGEST_DIR_COUNT: usize = 10;
+
+    let project = workspace.project().clone();
+    let worktree_stats = project
+        .read(cx)
+        .worktrees()
+        .map(|worktree| {
+            let worktree = worktree.read(cx);
+            (worktree.abs_path(), worktree.stats(LARGEST_DIR_COUNT, cx))
+        })
+        .collect::<Vec<_>>();
+    cx.spawn(|workspace, mut cx| async move {
+        let mut text = String::new();
+        for (abs_path, stats) in worktree_stats {
+            let stats = stats.await;
+            text.push_str(&format!("# {}\n\n", abs_path.display()));
+            text.push_str(&format!("Files: {}\n", stats.file_count));
+            text.push_str(&format!(
+                "Directories: {} ({} not scanned yet)\n",
+                stats.dir_count, stats.unloaded_dir_count
+            ));
+            text.push_str(&format!("Ignored entries: {}\n", stats.ignored_count));
+            text.push_str(&format!("Symlinks: {}\n", stats.symlink_count));
+            text.push_str(&format!("External entries: {}\n", stats.external_count));
+            if let Some(duration) = stats.last_scan_duration {
+                text.push_str(&format!("Last scan duration: {duration:?}\n"));
+            }
+            text.push_str("\nLargest directories:\n");
+            for (path, entry_count) in &stats.largest_dirs {
+                text.push_str(&format!("{entry_count:>10}  {}\n", path.display()));
+            }
+            text.push('\n');
+        }
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let buffer = project.update(cx, |project, cx| {
+                project.create_local_buffer(text.trim_end(), None, cx)
+            });
+            let buffer = cx.new_model(|cx| {
+                MultiBuffer::singleton(buffer, cx).with_title("Worktree Stats".into())
+            });
+            let editor = cx.new_view(|cx| {
+                let mut editor = Editor::for_multibuffer(buffer, Some(project), true, cx);
+                editor.set_read_only(true);
+                editor
+            });
+            workspace.add_item_to_active_pane(Box::new(editor), None, cx);
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
 fn open_bundled_file(
     workspace: &mut Workspace,
     text: Cow<'static, str>,
@@ -2794,6 +2865,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2813,6 +2885,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2865,6 +2938,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2890,6 +2964,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2934,6 +3009,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2952,6 +3028,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -2999,6 +3076,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -3024,6 +3102,7 @@ mod tests {
                 "#
                 .into(),
                 Default::default(),
+                Default::default(),
             )
             .await
             .unwrap();
@@ -3106,6 +3185,8 @@ mod tests {
             terminal_view::init(cx);
             assistant::init(app_state.client.clone(), cx);
             tasks_ui::init(cx);
+            call_hierarchy::init(cx);
+            test_explorer::init(cx);
             initialize_workspace(app_state.clone(), cx);
             app_state
         })